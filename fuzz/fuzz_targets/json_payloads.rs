@@ -0,0 +1,37 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Fuzz target for the deserializers of the JSON payloads accepted by the API.
+
+#![no_main]
+
+use lacoctelera::{
+    domain::{Author, Recipe, RecipeContains, SocialProfile, Tag},
+    routes::{
+        batch::BatchDelete,
+        ingredient::FormData,
+        recipe::{RecipeDraft, RecipePatch},
+        token::keys::KeyRequest,
+    },
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_json::from_slice::<Recipe>(data);
+    let _ = serde_json::from_slice::<RecipePatch>(data);
+    let _ = serde_json::from_slice::<RecipeDraft>(data);
+    let _ = serde_json::from_slice::<Author>(data);
+    let _ = serde_json::from_slice::<SocialProfile>(data);
+    let _ = serde_json::from_slice::<FormData>(data);
+    let _ = serde_json::from_slice::<KeyRequest>(data);
+    let _ = serde_json::from_slice::<BatchDelete>(data);
+
+    // Parsers of the values stored in the DB.
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = RecipeContains::parse_amount(text);
+        let _ = Tag::parse_list(text);
+    }
+});
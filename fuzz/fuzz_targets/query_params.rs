@@ -0,0 +1,22 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Fuzz target for the deserializers of the query strings accepted by the API.
+
+#![no_main]
+
+use actix_web::web::Query;
+use lacoctelera::{
+    domain::RecipeQuery,
+    routes::{author::get::AuthorQueryParams, ingredient::QueryData},
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|query: &str| {
+    let _ = Query::<RecipeQuery>::from_query(query);
+    let _ = Query::<AuthorQueryParams>::from_query(query);
+    let _ = Query::<QueryData>::from_query(query);
+});
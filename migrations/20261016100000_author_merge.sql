@@ -0,0 +1,20 @@
+-- ---------------------------------------------
+-- Support for merging duplicated author profiles
+-- ---------------------------------------------
+
+-- Authors merged into another profile are kept in the DB, but hidden from the clients of the API.
+ALTER TABLE `Author` ADD COLUMN `deleted_at` TIMESTAMP NULL DEFAULT NULL;
+
+-- IDs of the author profiles that were merged into another profile.
+DROP TABLE IF EXISTS `AuthorAlias`;
+CREATE TABLE `AuthorAlias` (
+    `alias_id` VARCHAR(40) NOT NULL,
+    `author_id` VARCHAR(40) NOT NULL,
+    `created` TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT `AuthorAlias_PK` PRIMARY KEY (`alias_id`),
+    CONSTRAINT `AuthorAlias_Author_FK` FOREIGN KEY (`author_id`)
+        REFERENCES `Author` (`id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+
+-- Clients of the API allowed to access the administration resources.
+ALTER TABLE `ApiUser` ADD COLUMN `admin` BOOL NOT NULL DEFAULT false;
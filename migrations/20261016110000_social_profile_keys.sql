@@ -0,0 +1,12 @@
+-- ---------------------------------------------
+-- Keys for the social profiles of the authors
+-- ---------------------------------------------
+
+-- Authors are allowed a single profile per social network. Remove duplicated entries before enforcing it.
+DELETE a FROM `AuthorHashSocialProfile` a
+JOIN `AuthorHashSocialProfile` b
+ON a.`author_id` = b.`author_id` AND a.`provider_name` = b.`provider_name` AND a.`id` > b.`id`;
+
+ALTER TABLE `AuthorHashSocialProfile`
+    ADD CONSTRAINT `AuthorHashSocialProfile_PK` PRIMARY KEY (`id`),
+    ADD CONSTRAINT `AuthorHashSocialProfile_UQ` UNIQUE (`author_id`, `provider_name`);
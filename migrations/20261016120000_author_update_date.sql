@@ -0,0 +1,6 @@
+-- ---------------------------------------------
+-- Modification time of the author profiles
+-- ---------------------------------------------
+
+ALTER TABLE `Author`
+    ADD COLUMN `update_date` TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP;
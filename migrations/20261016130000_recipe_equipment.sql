@@ -0,0 +1,13 @@
+-- ---------------------------------------------
+-- Bar equipment needed to prepare a recipe
+-- ---------------------------------------------
+
+CREATE TABLE IF NOT EXISTS `RecipeEquipment` (
+    `cocktail_id` VARCHAR(40) NOT NULL,
+    `equipment` ENUM (
+        'shaker', 'strainer', 'fine_strainer', 'mixing_glass', 'bar_spoon', 'jigger', 'muddler', 'juicer', 'blender',
+        'smoker'
+    ) NOT NULL,
+    PRIMARY KEY (`cocktail_id`, `equipment`),
+    CONSTRAINT `Equipment_Cocktail_FK` FOREIGN KEY (`cocktail_id`) REFERENCES `Cocktail`(`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
@@ -0,0 +1,6 @@
+-- ---------------------------------------------
+-- Estimated preparation time of the recipes
+-- ---------------------------------------------
+
+ALTER TABLE `Cocktail`
+    ADD COLUMN `prep_time_minutes` SMALLINT UNSIGNED DEFAULT NULL;
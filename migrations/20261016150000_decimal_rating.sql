@@ -0,0 +1,18 @@
+-- ---------------------------------------------
+-- Ratings using half-star steps (0.0 to 5.0)
+-- ---------------------------------------------
+
+-- ENUM columns evaluate to the index of the value in a numeric context, thus the existing ratings are copied
+-- using their string value.
+ALTER TABLE `Cocktail`
+    ADD COLUMN `rating_decimal` DECIMAL(2,1) NOT NULL DEFAULT 0.0;
+
+UPDATE `Cocktail` SET `rating_decimal` = CAST(CAST(`rating` AS CHAR) AS DECIMAL(2,1))
+    WHERE `rating` IS NOT NULL;
+
+ALTER TABLE `Cocktail` DROP COLUMN `rating`;
+
+ALTER TABLE `Cocktail` RENAME COLUMN `rating_decimal` TO `rating`;
+
+ALTER TABLE `Cocktail`
+    ADD CONSTRAINT `Cocktail_rating_step` CHECK (`rating` BETWEEN 0.0 AND 5.0 AND MOD(`rating` * 2, 1) = 0);
@@ -0,0 +1,12 @@
+-- ---------------------------------------------
+-- Moderation queue of the flagged recipes
+-- ---------------------------------------------
+
+-- Recipes included in the queue are hidden from the public until an administrator reviews them.
+CREATE TABLE IF NOT EXISTS `ModerationQueue` (
+    `cocktail_id` VARCHAR(40) NOT NULL,
+    `reasons` TEXT NOT NULL,
+    `flagged_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`cocktail_id`),
+    CONSTRAINT `Moderation_Cocktail_FK` FOREIGN KEY (`cocktail_id`) REFERENCES `Cocktail`(`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
@@ -0,0 +1,16 @@
+-- ---------------------------------------------
+-- Correlation of the outbound emails
+-- ---------------------------------------------
+
+-- Emails sent by the backend, along with the ID of the request that triggered them and the ID given by the email
+-- provider, so delivery issues can be traced end to end.
+CREATE TABLE IF NOT EXISTS `EmailMessage` (
+    `id` BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    `request_id` VARCHAR(36) NOT NULL,
+    `client_id` VARCHAR(36) NOT NULL,
+    `provider_message_id` VARCHAR(100) NULL,
+    `sent_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`id`),
+    KEY `EmailMessage_request_IDX` (`request_id`),
+    CONSTRAINT `EmailMessage_ApiUser_FK` FOREIGN KEY (`client_id`) REFERENCES `ApiUser`(`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
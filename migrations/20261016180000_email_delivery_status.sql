@@ -0,0 +1,12 @@
+-- ---------------------------------------------
+-- Delivery status of the outbound emails
+-- ---------------------------------------------
+
+-- Failed attempts are also registered, so the administrators can find them. Recipients are not stored in plain text,
+-- only the SHA-256 hash of their (lower case) address.
+ALTER TABLE `EmailMessage`
+    ADD COLUMN `kind` ENUM('confirmation', 'admin_notification') NOT NULL DEFAULT 'confirmation' AFTER `id`,
+    ADD COLUMN `recipient_hash` CHAR(64) NULL AFTER `client_id`,
+    ADD COLUMN `status` ENUM('sent', 'failed') NOT NULL DEFAULT 'sent' AFTER `recipient_hash`,
+    ADD COLUMN `error` TEXT NULL AFTER `provider_message_id`,
+    ADD KEY `EmailMessage_status_IDX` (`status`, `sent_at`);
@@ -0,0 +1,25 @@
+-- ---------------------------------------------
+-- Lifecycle of the email validation tokens
+-- ---------------------------------------------
+
+-- Tokens sent to validate the email of the clients are kept apart from the API tokens. They expire at `expires_at`,
+-- and they can be used only once (`used_at`). Issuing a new token for a client expires the previous ones.
+CREATE TABLE IF NOT EXISTS `ValidationToken` (
+    `token` VARCHAR(100) NOT NULL,
+    `client_id` VARCHAR(36) NOT NULL,
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    `expires_at` TIMESTAMP NOT NULL,
+    `used_at` TIMESTAMP NULL DEFAULT NULL,
+    PRIMARY KEY (`token`),
+    KEY `ValidationToken_client_IDX` (`client_id`),
+    CONSTRAINT `ValidationToken_ApiUser_FK` FOREIGN KEY (`client_id`) REFERENCES `ApiUser`(`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+
+-- Move the pending validation tokens out of the API tokens.
+INSERT INTO `ValidationToken` (`token`, `client_id`, `created_at`, `expires_at`)
+    SELECT at.api_token, at.client_id, COALESCE(at.created, CURRENT_TIMESTAMP), at.valid_until
+    FROM ApiToken at JOIN ApiUser au ON au.id = at.client_id
+    WHERE COALESCE(au.validated, 0) = 0;
+
+DELETE at FROM ApiToken at JOIN ApiUser au ON au.id = at.client_id
+    WHERE COALESCE(au.validated, 0) = 0;
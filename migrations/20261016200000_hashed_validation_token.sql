@@ -0,0 +1,10 @@
+-- ---------------------------------------------
+-- Hashed email validation tokens
+-- ---------------------------------------------
+
+-- Validation tokens are stored as Argon2 hashes, like the API tokens. Pending tokens stored in plain text can't be
+-- hashed here, so they are removed: the clients shall request a new validation link.
+DELETE FROM `ValidationToken` WHERE `used_at` IS NULL;
+
+ALTER TABLE `ValidationToken`
+    CHANGE COLUMN `token` `token_hash` VARCHAR(255) NOT NULL;
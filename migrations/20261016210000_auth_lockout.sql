@@ -0,0 +1,23 @@
+-- ---------------------------------------------
+-- Auditing of the failed access checks
+-- ---------------------------------------------
+
+-- Consecutive failures due to a wrong token, and the time until which the client is locked.
+ALTER TABLE `ApiUser`
+    ADD COLUMN `failed_attempts` INT UNSIGNED NOT NULL DEFAULT 0,
+    ADD COLUMN `locked_until` TIMESTAMP NULL DEFAULT NULL;
+
+DROP TABLE IF EXISTS `AuthFailure`;
+CREATE TABLE `AuthFailure` (
+    `id` BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    `client_id` VARCHAR(36) NOT NULL,
+    `reason` ENUM('wrong_token', 'account_disabled', 'expired_token', 'locked') NOT NULL,
+    `attempted_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT `AuthFailure_PK` PRIMARY KEY (`id`),
+    KEY `AuthFailure_client_IDX` (`client_id`, `attempted_at`),
+    CONSTRAINT `AuthFailure_ApiUser_FK` FOREIGN KEY (`client_id`) REFERENCES `ApiUser`(`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+
+-- Owners of the locked clients are notified by email.
+ALTER TABLE `EmailMessage`
+    MODIFY COLUMN `kind` ENUM('confirmation', 'admin_notification', 'lockout_notification') NOT NULL DEFAULT 'confirmation';
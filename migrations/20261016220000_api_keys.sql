@@ -0,0 +1,12 @@
+-- ---------------------------------------------
+-- Several API keys per client
+-- ---------------------------------------------
+
+-- Keys get an ID, so clients can revoke them, and a label to tell them apart (i.e. one key per environment).
+ALTER TABLE `ApiToken`
+    DROP PRIMARY KEY,
+    ADD COLUMN `id` BIGINT UNSIGNED NOT NULL AUTO_INCREMENT FIRST,
+    ADD COLUMN `label` VARCHAR(40) NOT NULL DEFAULT 'default' AFTER `id`,
+    ADD CONSTRAINT `ApiToken_PK` PRIMARY KEY (`id`),
+    ADD CONSTRAINT `ApiToken_token_UN` UNIQUE KEY (`api_token`),
+    ADD KEY `ApiToken_client_IDX` (`client_id`, `valid_until`);
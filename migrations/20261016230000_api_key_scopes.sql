@@ -0,0 +1,7 @@
+-- ---------------------------------------------
+-- Scopes of the API keys
+-- ---------------------------------------------
+
+-- Existing keys grant all the scopes. Clients can derive keys with a subset of them.
+ALTER TABLE `ApiToken`
+    ADD COLUMN `scopes` SET('read', 'write', 'keys') NOT NULL DEFAULT 'read,write,keys' AFTER `label`;
@@ -0,0 +1,13 @@
+-- ---------------------------------------------
+-- Latest change of the public collections
+-- ---------------------------------------------
+
+-- Write handlers bump the timestamp of the collections they modify, so searches can be answered with a 304.
+DROP TABLE IF EXISTS `CollectionChange`;
+CREATE TABLE `CollectionChange` (
+    `collection` VARCHAR(40) NOT NULL,
+    `changed_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT `CollectionChange_PK` PRIMARY KEY (`collection`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+
+INSERT INTO `CollectionChange` (`collection`) VALUES ('recipe'), ('ingredient');
@@ -0,0 +1,21 @@
+-- ---------------------------------------------
+-- Email digests of new recipes
+-- ---------------------------------------------
+
+-- Tags are stored as a comma-separated list. Empty filters match every recipe.
+DROP TABLE IF EXISTS `DigestSubscription`;
+CREATE TABLE `DigestSubscription` (
+    `id` VARCHAR(36) NOT NULL,
+    `client_id` VARCHAR(36) NOT NULL,
+    `frequency` ENUM('daily', 'weekly') NOT NULL,
+    `tags` VARCHAR(255) NOT NULL DEFAULT '',
+    `categories` SET('easy', 'medium', 'advanced', 'pro') NOT NULL DEFAULT '',
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    `last_sent_at` TIMESTAMP NULL DEFAULT NULL,
+    CONSTRAINT `DigestSubscription_PK` PRIMARY KEY (`id`),
+    KEY `DigestSubscription_client_IDX` (`client_id`),
+    CONSTRAINT `DigestSubscription_ApiUser_FK` FOREIGN KEY (`client_id`) REFERENCES `ApiUser`(`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+
+ALTER TABLE `EmailMessage`
+    MODIFY COLUMN `kind` ENUM('confirmation', 'admin_notification', 'lockout_notification', 'digest') NOT NULL DEFAULT 'confirmation';
@@ -0,0 +1,21 @@
+-- ---------------------------------------------
+-- Saved searches of the clients
+-- ---------------------------------------------
+
+-- The query is stored as the JSON representation of a RecipeQuery.
+DROP TABLE IF EXISTS `SavedSearch`;
+CREATE TABLE `SavedSearch` (
+    `id` VARCHAR(36) NOT NULL,
+    `client_id` VARCHAR(36) NOT NULL,
+    `name` VARCHAR(40) NOT NULL,
+    `query` TEXT NOT NULL,
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT `SavedSearch_PK` PRIMARY KEY (`id`),
+    CONSTRAINT `SavedSearch_name_UQ` UNIQUE (`client_id`, `name`),
+    CONSTRAINT `SavedSearch_ApiUser_FK` FOREIGN KEY (`client_id`) REFERENCES `ApiUser`(`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+
+-- Digests can list the new recipes among the results of a saved search.
+ALTER TABLE `DigestSubscription`
+    ADD COLUMN `search_id` VARCHAR(36) NULL DEFAULT NULL AFTER `frequency`,
+    ADD CONSTRAINT `DigestSubscription_SavedSearch_FK` FOREIGN KEY (`search_id`) REFERENCES `SavedSearch`(`id`) ON DELETE CASCADE;
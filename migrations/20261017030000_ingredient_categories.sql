@@ -0,0 +1,24 @@
+-- ---------------------------------------------
+-- Ingredient categories as a DB-backed taxonomy
+-- ---------------------------------------------
+
+-- The former values of the ENUM of the column `Ingredient.category` are kept as seeds.
+DROP TABLE IF EXISTS `IngredientCategory`;
+CREATE TABLE `IngredientCategory` (
+    `name` VARCHAR(20) NOT NULL,
+    `description` VARCHAR(255) NULL DEFAULT NULL,
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT `IngredientCategory_PK` PRIMARY KEY (`name`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+
+INSERT INTO `IngredientCategory` (`name`, `description`) VALUES
+    ('spirit', 'Spirit ingredients, such as rum, liquors and so'),
+    ('bitter', 'Bitter ingredients, such as Angostura'),
+    ('soft_drink', 'Soft-drink ingredients, such as soda water, Fanta, Coke, etc.'),
+    ('garnish', 'Garnish ingredients, such a lemon''s peel'),
+    ('other', 'Ingredients whose type does not match any of the other categories');
+
+-- Categories in use can't be deleted.
+ALTER TABLE `Ingredient`
+    MODIFY COLUMN `category` VARCHAR(20) NOT NULL,
+    ADD CONSTRAINT `Ingredient_IngredientCategory_FK` FOREIGN KEY (`category`) REFERENCES `IngredientCategory`(`name`) ON DELETE RESTRICT;
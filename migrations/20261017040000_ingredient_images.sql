@@ -0,0 +1,7 @@
+-- ---------------------------------------------
+-- Images of the ingredients
+-- ---------------------------------------------
+
+-- The image is stored in the media storage of the server, and referenced by the name of its file.
+ALTER TABLE `Ingredient`
+    ADD COLUMN `image_id` VARCHAR(64) NULL DEFAULT NULL AFTER `description`;
@@ -0,0 +1,16 @@
+-- ---------------------------------------------
+-- Ingredients that the clients have at hand
+-- ---------------------------------------------
+
+-- The quantity and its unit are optional, but they are always given together.
+DROP TABLE IF EXISTS `Inventory`;
+CREATE TABLE `Inventory` (
+    `client_id` VARCHAR(36) NOT NULL,
+    `ingredient_id` VARCHAR(36) NOT NULL,
+    `quantity` FLOAT NULL DEFAULT NULL,
+    `unit` VARCHAR(10) NULL DEFAULT NULL,
+    `updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    CONSTRAINT `Inventory_PK` PRIMARY KEY (`client_id`, `ingredient_id`),
+    CONSTRAINT `Inventory_ApiUser_FK` FOREIGN KEY (`client_id`) REFERENCES `ApiUser`(`id`) ON DELETE CASCADE,
+    CONSTRAINT `Inventory_Ingredient_FK` FOREIGN KEY (`ingredient_id`) REFERENCES `Ingredient`(`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
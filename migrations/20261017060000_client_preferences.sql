@@ -0,0 +1,13 @@
+-- ---------------------------------------------
+-- Display preferences of the clients
+-- ---------------------------------------------
+
+DROP TABLE IF EXISTS `ClientPreferences`;
+CREATE TABLE `ClientPreferences` (
+    `client_id` VARCHAR(36) NOT NULL,
+    `unit_system` VARCHAR(10) NULL DEFAULT NULL,
+    `language` VARCHAR(5) NULL DEFAULT NULL,
+    `updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    CONSTRAINT `ClientPreferences_PK` PRIMARY KEY (`client_id`),
+    CONSTRAINT `ClientPreferences_ApiUser_FK` FOREIGN KEY (`client_id`) REFERENCES `ApiUser`(`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
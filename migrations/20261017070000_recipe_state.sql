@@ -0,0 +1,9 @@
+-- ---------------------------------------------
+-- Publishing workflow of the recipes
+-- ---------------------------------------------
+
+-- Recipes follow the workflow draft -> submitted -> published -> archived. Only published recipes are public, so the
+-- recipes registered before the workflow existed are considered published.
+ALTER TABLE `Cocktail`
+    ADD COLUMN `state` VARCHAR(12) NOT NULL DEFAULT 'published' AFTER `owner`,
+    ADD INDEX `Cocktail_State_IDX` (`state`);
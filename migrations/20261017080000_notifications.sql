@@ -0,0 +1,32 @@
+-- ---------------------------------------------
+-- Notifications of the clients
+-- ---------------------------------------------
+
+-- Notifications are produced by system events. The same event is only notified once, i.e. a key about to expire.
+DROP TABLE IF EXISTS `Notification`;
+CREATE TABLE `Notification` (
+    `id` VARCHAR(36) NOT NULL,
+    `client_id` VARCHAR(36) NOT NULL,
+    `kind` VARCHAR(32) NOT NULL,
+    `subject_id` VARCHAR(40) NOT NULL,
+    `message` VARCHAR(400) NOT NULL,
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    `read_at` TIMESTAMP NULL DEFAULT NULL,
+    `emailed_at` TIMESTAMP NULL DEFAULT NULL,
+    CONSTRAINT `Notification_PK` PRIMARY KEY (`id`),
+    CONSTRAINT `Notification_event_UN` UNIQUE KEY (`client_id`, `kind`, `subject_id`),
+    KEY `Notification_client_IDX` (`client_id`, `created_at`),
+    CONSTRAINT `Notification_ApiUser_FK` FOREIGN KEY (`client_id`) REFERENCES `ApiUser`(`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+
+-- Client that registered each recipe, which gets notified when the recipe is approved.
+ALTER TABLE `Cocktail`
+    ADD COLUMN `client_id` VARCHAR(36) NULL DEFAULT NULL AFTER `state`,
+    ADD CONSTRAINT `Cocktail_ApiUser_FK` FOREIGN KEY (`client_id`) REFERENCES `ApiUser`(`id`) ON DELETE SET NULL;
+
+-- Notifications are only mirrored to the email of the clients that ask for it.
+ALTER TABLE `ClientPreferences`
+    ADD COLUMN `email_notifications` BOOL NULL DEFAULT NULL AFTER `language`;
+
+ALTER TABLE `EmailMessage`
+    MODIFY COLUMN `kind` ENUM('confirmation', 'admin_notification', 'lockout_notification', 'digest', 'notification') NOT NULL DEFAULT 'confirmation';
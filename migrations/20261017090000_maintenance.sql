@@ -0,0 +1,17 @@
+-- ---------------------------------------------
+-- Scheduled maintenance windows
+-- ---------------------------------------------
+
+-- Maintenance windows announced by the administrators. Only the latest window that didn't pass is announced to the
+-- clients of the API.
+CREATE TABLE IF NOT EXISTS `MaintenanceWindow` (
+    `id` BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    `starts_at` TIMESTAMP NOT NULL,
+    `ends_at` TIMESTAMP NOT NULL,
+    `message` VARCHAR(400) NULL,
+    `created_by` VARCHAR(36) NULL,
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`id`),
+    KEY `MaintenanceWindow_ends_IDX` (`ends_at`),
+    CONSTRAINT `MaintenanceWindow_ApiUser_FK` FOREIGN KEY (`created_by`) REFERENCES `ApiUser`(`id`) ON DELETE SET NULL
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
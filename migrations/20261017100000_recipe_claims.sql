@@ -0,0 +1,26 @@
+-- ---------------------------------------------
+-- Claims of the ownership of the recipes
+-- ---------------------------------------------
+
+-- Recipes with no owner are claimed by the authors. A claim is granted when the author proves control of the source
+-- URL of the recipe, publishing the token of the claim, or when an administrator approves it.
+CREATE TABLE IF NOT EXISTS `RecipeClaim` (
+    `id` VARCHAR(36) NOT NULL,
+    `recipe_id` VARCHAR(40) NOT NULL,
+    `author_id` VARCHAR(40) NOT NULL,
+    `client_id` VARCHAR(36) NOT NULL,
+    `token` VARCHAR(40) NOT NULL,
+    `state` ENUM('pending', 'approved', 'rejected') NOT NULL DEFAULT 'pending',
+    `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    `resolved_at` TIMESTAMP NULL DEFAULT NULL,
+    PRIMARY KEY (`id`),
+    KEY `RecipeClaim_recipe_IDX` (`recipe_id`, `state`),
+    KEY `RecipeClaim_state_IDX` (`state`, `created_at`),
+    CONSTRAINT `RecipeClaim_Cocktail_FK` FOREIGN KEY (`recipe_id`) REFERENCES `Cocktail`(`id`) ON DELETE CASCADE,
+    CONSTRAINT `RecipeClaim_Author_FK` FOREIGN KEY (`author_id`) REFERENCES `Author`(`id`) ON DELETE CASCADE,
+    CONSTRAINT `RecipeClaim_ApiUser_FK` FOREIGN KEY (`client_id`) REFERENCES `ApiUser`(`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+
+-- Authors get notified by email when they become the owners of a recipe.
+ALTER TABLE `EmailMessage`
+    MODIFY COLUMN `kind` ENUM('confirmation', 'admin_notification', 'lockout_notification', 'digest', 'notification', 'claim_notification') NOT NULL DEFAULT 'confirmation';
@@ -0,0 +1,13 @@
+-- ---------------------------------------------
+-- Registration time of the author profiles
+-- ---------------------------------------------
+
+ALTER TABLE `Author`
+    ADD COLUMN `creation_date` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+-- The latest modification is the best estimation for the existing profiles. The modification time is kept, as it
+-- would be bumped by this update otherwise.
+UPDATE `Author` SET `creation_date` = `update_date`, `update_date` = `update_date`
+    WHERE `update_date` IS NOT NULL;
+
+CREATE INDEX `Author_creation_date_IX` ON `Author` (`creation_date`);
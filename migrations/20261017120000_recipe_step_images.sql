@@ -0,0 +1,13 @@
+-- ---------------------------------------------
+-- Images of the preparation steps of a recipe
+-- ---------------------------------------------
+
+-- Steps are identified by their position in the list of steps of the recipe, starting at 0. The image is stored in the
+-- media storage of the server, and referenced by the name of its file.
+CREATE TABLE IF NOT EXISTS `RecipeStepImage` (
+    `cocktail_id` VARCHAR(40) NOT NULL,
+    `step` SMALLINT UNSIGNED NOT NULL,
+    `image_id` VARCHAR(64) NOT NULL,
+    PRIMARY KEY (`cocktail_id`, `step`),
+    CONSTRAINT `StepImage_Cocktail_FK` FOREIGN KEY (`cocktail_id`) REFERENCES `Cocktail`(`id`) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
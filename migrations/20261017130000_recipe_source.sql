@@ -0,0 +1,9 @@
+-- ---------------------------------------------
+-- Attribution of third-party recipes
+-- ---------------------------------------------
+
+ALTER TABLE `Cocktail`
+    ADD COLUMN `source_book` VARCHAR(120) NULL DEFAULT NULL AFTER `url`,
+    ADD COLUMN `source_page` SMALLINT UNSIGNED NULL DEFAULT NULL AFTER `source_book`,
+    ADD COLUMN `source_url` VARCHAR(255) NULL DEFAULT NULL AFTER `source_page`,
+    ADD COLUMN `source_author` VARCHAR(80) NULL DEFAULT NULL AFTER `source_url`;
@@ -0,0 +1,8 @@
+-- ---------------------------------------------
+-- License of the recipes
+-- ---------------------------------------------
+
+-- Recipes registered before licenses were introduced have no license, so they get the default license of the server.
+ALTER TABLE `Cocktail`
+    ADD COLUMN `license` ENUM ('cc-by', 'cc-by-sa', 'cc-by-nc', 'cc0', 'all-rights-reserved') NULL DEFAULT NULL
+        AFTER `source_author`;
@@ -0,0 +1,15 @@
+-- ---------------------------------------------
+-- Domain events of the recipes
+-- ---------------------------------------------
+
+-- Append-only log of the changes of the recipes, written in the same transaction as the changes. Consumers keep the
+-- ID of the latest event they processed, so events have no foreign keys: they outlive the recipes.
+CREATE TABLE IF NOT EXISTS `DomainEvent` (
+    `id` BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    `kind` VARCHAR(40) NOT NULL,
+    `subject_id` VARCHAR(40) NOT NULL,
+    `payload` JSON NOT NULL,
+    `occurred_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`id`),
+    KEY `DomainEvent_subject_IDX` (`subject_id`, `id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
@@ -0,0 +1,24 @@
+-- ---------------------------------------------
+-- Delivery of the domain events to the sinks
+-- ---------------------------------------------
+
+-- Position of every sink within the DomainEvent log. The attempts and the error refer to the event that follows
+-- `last_event_id`, which is retried until it is delivered or dead-lettered.
+CREATE TABLE IF NOT EXISTS `EventOutbox` (
+    `sink` VARCHAR(64) NOT NULL,
+    `last_event_id` BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    `attempts` INT UNSIGNED NOT NULL DEFAULT 0,
+    `last_error` VARCHAR(512) NULL,
+    `updated_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    PRIMARY KEY (`sink`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+
+-- Events that a sink refused too many times. They are skipped, so they don't block the rest of the events.
+CREATE TABLE IF NOT EXISTS `DeadEvent` (
+    `sink` VARCHAR(64) NOT NULL,
+    `event_id` BIGINT UNSIGNED NOT NULL,
+    `attempts` INT UNSIGNED NOT NULL,
+    `error` VARCHAR(512) NULL,
+    `failed_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`sink`, `event_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
@@ -0,0 +1,17 @@
+-- ---------------------------------------------
+-- Unique emails of the author profiles
+-- ---------------------------------------------
+
+-- Copy of the email of the author, which is only set when the email shall be unique. Deployments that allow
+-- duplicated emails leave it NULL, and so do the profiles merged into another profile.
+ALTER TABLE `Author` ADD COLUMN `email_key` VARCHAR(80) NULL DEFAULT NULL;
+
+-- Emails that are already duplicated are left as they are, they shall be fixed merging the profiles.
+UPDATE `Author` SET `email_key` = `email`
+    WHERE `deleted_at` IS NULL AND `email` IN (
+        SELECT `email` FROM (
+            SELECT `email` FROM `Author` WHERE `deleted_at` IS NULL GROUP BY `email` HAVING COUNT(*) = 1
+        ) AS `unique_emails`
+    );
+
+CREATE UNIQUE INDEX `Author_email_key_UX` ON `Author` (`email_key`);
@@ -0,0 +1,23 @@
+-- ---------------------------------------------
+-- URL-friendly names of the recipes
+-- ---------------------------------------------
+
+ALTER TABLE `Cocktail` ADD COLUMN `slug` VARCHAR(40) NULL DEFAULT NULL;
+
+-- Approximation of the slugs built by the backend for the existing recipes. Accents are kept, which is harmless as
+-- the collation of the table ignores them, i.e. `piña-colada` matches `pina-colada`.
+UPDATE `Cocktail`
+    SET `slug` = LOWER(TRIM(BOTH '-' FROM REGEXP_REPLACE(`name`, '[^[:alnum:]]+', '-')));
+UPDATE `Cocktail` SET `slug` = 'recipe' WHERE `slug` = '';
+
+-- Recipes of the same author whose slugs clash get the end of their ID as suffix, except the oldest one.
+UPDATE `Cocktail` AS `c`
+    SET `slug` = CONCAT(LEFT(`c`.`slug`, 31), '-', RIGHT(`c`.`id`, 8))
+    WHERE EXISTS (
+        SELECT 1 FROM (SELECT `id`, `owner`, `slug`, `creation_date` FROM `Cocktail`) AS `other`
+        WHERE `other`.`owner` = `c`.`owner` AND `other`.`slug` = `c`.`slug`
+            AND (`other`.`creation_date` < `c`.`creation_date`
+                OR (`other`.`creation_date` = `c`.`creation_date` AND `other`.`id` < `c`.`id`))
+    );
+
+CREATE UNIQUE INDEX `Cocktail_owner_slug_UX` ON `Cocktail` (`owner`, `slug`);
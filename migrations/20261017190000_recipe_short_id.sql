@@ -0,0 +1,26 @@
+-- ---------------------------------------------
+-- Short public IDs of the recipes
+-- ---------------------------------------------
+
+-- Short IDs are case sensitive, unlike the default collation of the DB.
+ALTER TABLE `Cocktail`
+    ADD COLUMN `short_id` VARCHAR(11) CHARACTER SET ascii COLLATE ascii_bin NULL DEFAULT NULL;
+
+-- The existing recipes get 11 random symbols of the Base58 alphabet, as the short IDs built by the backend.
+SET @alphabet = '123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz';
+UPDATE `Cocktail`
+    SET `short_id` = CONCAT(
+        SUBSTRING(@alphabet, FLOOR(1 + RAND() * 58), 1),
+        SUBSTRING(@alphabet, FLOOR(1 + RAND() * 58), 1),
+        SUBSTRING(@alphabet, FLOOR(1 + RAND() * 58), 1),
+        SUBSTRING(@alphabet, FLOOR(1 + RAND() * 58), 1),
+        SUBSTRING(@alphabet, FLOOR(1 + RAND() * 58), 1),
+        SUBSTRING(@alphabet, FLOOR(1 + RAND() * 58), 1),
+        SUBSTRING(@alphabet, FLOOR(1 + RAND() * 58), 1),
+        SUBSTRING(@alphabet, FLOOR(1 + RAND() * 58), 1),
+        SUBSTRING(@alphabet, FLOOR(1 + RAND() * 58), 1),
+        SUBSTRING(@alphabet, FLOOR(1 + RAND() * 58), 1),
+        SUBSTRING(@alphabet, FLOOR(1 + RAND() * 58), 1)
+    );
+
+CREATE UNIQUE INDEX `Cocktail_short_id_UX` ON `Cocktail` (`short_id`);
@@ -0,0 +1,20 @@
+-- ---------------------------------------------
+-- Normalized names for the searches
+-- ---------------------------------------------
+
+-- The backend stores the names without accents, in lower case and using the compatibility form of Unicode, so the
+-- searches find "Piña Colada" using "pina colada".
+ALTER TABLE `Cocktail`
+    ADD COLUMN `search_name` VARCHAR(80) NULL DEFAULT NULL AFTER `name`;
+
+ALTER TABLE `Ingredient`
+    ADD COLUMN `search_name` VARCHAR(80) NULL DEFAULT NULL AFTER `name`;
+
+-- The existing names are only converted to lower case, as SQL offers no Unicode decomposition. The accents are still
+-- ignored when comparing them, due to the accent-insensitive collation of the tables, and the backend replaces them
+-- by the normalized names as soon as they are modified.
+UPDATE `Cocktail` SET `search_name` = LOWER(`name`);
+UPDATE `Ingredient` SET `search_name` = LOWER(`name`);
+
+CREATE INDEX `Cocktail_search_name_IX` ON `Cocktail` (`search_name`);
+CREATE INDEX `Ingredient_search_name_IX` ON `Ingredient` (`search_name`);
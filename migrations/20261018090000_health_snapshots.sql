@@ -0,0 +1,18 @@
+-- ---------------------------------------------
+-- History of the health of the server
+-- ---------------------------------------------
+
+-- A periodic job records the health of the server, so a public status page can show the history. Values that could
+-- not be measured, i.e. the latency of the DB while it was down, are stored as NULL.
+DROP TABLE IF EXISTS `HealthSnapshot`;
+CREATE TABLE `HealthSnapshot` (
+    `id` BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    `taken_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    `status` VARCHAR(20) NOT NULL,
+    `db_latency_ms` DOUBLE NULL DEFAULT NULL,
+    `request_rate` DOUBLE NOT NULL DEFAULT 0,
+    `error_rate` DOUBLE NOT NULL DEFAULT 0,
+    `mail_outbox` BIGINT UNSIGNED NULL DEFAULT NULL,
+    CONSTRAINT `HealthSnapshot_PK` PRIMARY KEY (`id`),
+    KEY `HealthSnapshot_taken_at_IDX` (`taken_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
@@ -0,0 +1,22 @@
+-- ---------------------------------------------
+-- Anonymous usage analytics
+-- ---------------------------------------------
+
+-- Daily totals of the requests per endpoint, i.e. `GET /recipe/{id}`.
+DROP TABLE IF EXISTS `EndpointUsage`;
+CREATE TABLE `EndpointUsage` (
+    `day` DATE NOT NULL,
+    `endpoint` VARCHAR(160) NOT NULL,
+    `hits` BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    CONSTRAINT `EndpointUsage_PK` PRIMARY KEY (`day`, `endpoint`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+
+-- Daily totals of the searches per resource and normalized term. No data of the clients is stored.
+DROP TABLE IF EXISTS `SearchUsage`;
+CREATE TABLE `SearchUsage` (
+    `day` DATE NOT NULL,
+    `resource` VARCHAR(20) NOT NULL,
+    `term` VARCHAR(40) NOT NULL,
+    `hits` BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    CONSTRAINT `SearchUsage_PK` PRIMARY KEY (`day`, `resource`, `term`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
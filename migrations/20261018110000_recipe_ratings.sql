@@ -0,0 +1,20 @@
+-- ---------------------------------------------
+-- Votes of the clients for the recipes
+-- ---------------------------------------------
+
+-- Every client of the API has a single vote per recipe. The rating of the recipes is the average of the votes,
+-- rounded to the nearest half star, which is cached in the `Cocktail` table along with the amount of votes.
+DROP TABLE IF EXISTS `RecipeRating`;
+CREATE TABLE `RecipeRating` (
+    `cocktail_id` VARCHAR(40) NOT NULL,
+    `client_id` VARCHAR(36) NOT NULL,
+    `stars` DECIMAL(2,1) NOT NULL,
+    `voted_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    CONSTRAINT `RecipeRating_PK` PRIMARY KEY (`cocktail_id`, `client_id`),
+    CONSTRAINT `RecipeRating_Cocktail_FK` FOREIGN KEY (`cocktail_id`) REFERENCES `Cocktail`(`id`) ON DELETE CASCADE,
+    CONSTRAINT `RecipeRating_ApiUser_FK` FOREIGN KEY (`client_id`) REFERENCES `ApiUser`(`id`) ON DELETE CASCADE,
+    CONSTRAINT `RecipeRating_stars_step` CHECK (`stars` BETWEEN 0.5 AND 5.0 AND MOD(`stars` * 2, 1) = 0)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
+
+ALTER TABLE `Cocktail`
+    ADD COLUMN `rating_votes` INT UNSIGNED NOT NULL DEFAULT 0 AFTER `rating`;
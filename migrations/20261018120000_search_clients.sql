@@ -0,0 +1,14 @@
+-- ---------------------------------------------
+-- Distinct clients of the searches
+-- ---------------------------------------------
+
+-- Clients that searched a term within a day, hashed with a random salt of that day which is never stored. Search
+-- terms are only exposed when enough distinct clients searched them within a day.
+DROP TABLE IF EXISTS `SearchClient`;
+CREATE TABLE `SearchClient` (
+    `day` DATE NOT NULL,
+    `resource` VARCHAR(20) NOT NULL,
+    `term` VARCHAR(40) NOT NULL,
+    `client_hash` CHAR(32) NOT NULL,
+    CONSTRAINT `SearchClient_PK` PRIMARY KEY (`day`, `resource`, `term`, `client_hash`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_uca1400_ai_ci;
@@ -0,0 +1,8 @@
+-- ---------------------------------------------
+-- Preparation steps of any allowed length
+-- ---------------------------------------------
+
+-- The steps are stored joined in a single column. The recipe limits allow 40 steps of 500 characters, which don't fit
+-- in a VARCHAR(500), nor in a TEXT column when the characters take several bytes.
+ALTER TABLE `Cocktail`
+    MODIFY COLUMN `steps` MEDIUMTEXT NOT NULL;
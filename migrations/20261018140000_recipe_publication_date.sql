@@ -0,0 +1,14 @@
+-- ---------------------------------------------
+-- Publication time of the recipes
+-- ---------------------------------------------
+
+-- Moment the recipe became visible to the public: when it reached the state `published`, or when it was approved by
+-- the moderators. Recipes that are not public have none.
+ALTER TABLE `Cocktail`
+    ADD COLUMN `publication_date` TIMESTAMP NULL DEFAULT NULL AFTER `state`,
+    ADD INDEX `Cocktail_PublicationDate_IDX` (`publication_date`);
+
+-- The registration is the best estimation for the public recipes. The modification time is kept, as it would be
+-- bumped by this update otherwise.
+UPDATE `Cocktail` SET `publication_date` = `creation_date`, `update_date` = `update_date`
+    WHERE `state` = 'published' AND `id` NOT IN (SELECT `cocktail_id` FROM `ModerationQueue`);
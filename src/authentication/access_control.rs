@@ -0,0 +1,89 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Shared state of the access checks.
+//!
+//! # Description
+//!
+//! The access checks of the restricted endpoints (see [crate::authentication::check_access]) need the cache of the
+//! granted checks, the [LockoutPolicy], the mail client used to notify the locked clients and whether the application
+//! runs in read-only mode. All of them are bundled in [AccessControl], which is built when the application starts and
+//! shared with the handlers using `web::Data`.
+
+use crate::{
+    authentication::{AuthCache, LockoutPolicy, DEFAULT_AUTH_CACHE_TTL},
+    utils::mailing::EmailSender,
+};
+use actix_web::web::Data;
+use std::time::Duration;
+use tracing::info;
+
+/// Shared state of the access checks, see the [module documentation](self).
+#[derive(Debug)]
+pub struct AccessControl {
+    cache: AuthCache,
+    lockout_policy: LockoutPolicy,
+    mail_client: Option<Data<dyn EmailSender>>,
+    read_only: bool,
+}
+
+impl Default for AccessControl {
+    fn default() -> Self {
+        AccessControl::new(DEFAULT_AUTH_CACHE_TTL, LockoutPolicy::default())
+    }
+}
+
+impl AccessControl {
+    /// Build the state of the access checks.
+    ///
+    /// # Description
+    ///
+    /// Granted checks are cached for `cache_ttl`, a TTL of zero disables the cache. No emails are sent to the owners of
+    /// the locked clients unless a mail client is given ([AccessControl::with_mail_client]).
+    pub fn new(cache_ttl: Duration, lockout_policy: LockoutPolicy) -> Self {
+        info!("Authentication cache TTL: {}s", cache_ttl.as_secs());
+        info!("Lockout policy: {lockout_policy:?}");
+
+        AccessControl {
+            cache: AuthCache::new(cache_ttl),
+            lockout_policy,
+            mail_client: None,
+            read_only: false,
+        }
+    }
+
+    /// Set the mail client used to notify the owners of the locked clients.
+    pub fn with_mail_client(mut self, mail_client: Option<Data<dyn EmailSender>>) -> Self {
+        self.mail_client = mail_client;
+        self
+    }
+
+    /// Set whether the application runs in read-only mode, so the outcome of the checks is not stored in the DB.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        if read_only {
+            info!("The application runs in read-only mode");
+        }
+        self.read_only = read_only;
+        self
+    }
+
+    /// Get the cache of the granted access checks.
+    pub fn cache(&self) -> &AuthCache {
+        &self.cache
+    }
+
+    pub fn lockout_policy(&self) -> LockoutPolicy {
+        self.lockout_policy
+    }
+
+    pub fn mail_client(&self) -> Option<Data<dyn EmailSender>> {
+        self.mail_client.clone()
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! In-memory cache of the granted access checks.
+//!
+//! # Description
+//!
+//! Checking the access of a client requires reading its credentials from the DB and verifying the given token against
+//! the stored Argon2 hash, which takes tens of milliseconds of CPU. Clients usually issue many requests in a row using
+//! the same API key, so the granted checks are kept in memory for a short time. The cache of the application is kept by
+//! [crate::authentication::AccessControl].
+//!
+//! Entries are indexed by the SHA-256 hash of the given API key, so plain tokens are never kept in memory. Entries
+//! also keep the scopes of the key, so the scope required by an endpoint is checked without the DB. Only the
+//! granted checks are cached: denied checks are always performed against the DB, so enabling an account takes effect
+//! immediately. Deleting a token invalidates the entries of its client. Other changes to the accounts (i.e. disabling
+//! it, or the expiry of the token) take effect once the cached entries expire.
+
+use crate::authentication::Scope;
+use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+use tracing::debug;
+
+/// Default time that a granted access check is kept in the cache.
+pub const DEFAULT_AUTH_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Maximum amount of entries kept in the cache.
+const MAX_ENTRIES: usize = 10_000;
+
+/// Granted access check.
+#[derive(Debug, Clone)]
+struct CachedAccess {
+    client_id: String,
+    scopes: Vec<Scope>,
+    expires_at: Instant,
+}
+
+/// Cache of the granted access checks.
+#[derive(Debug)]
+pub struct AuthCache {
+    ttl: Duration,
+    entries: RwLock<HashMap<[u8; 32], CachedAccess>>,
+}
+
+impl AuthCache {
+    /// Build a new cache. A `ttl` of zero disables the cache.
+    pub fn new(ttl: Duration) -> Self {
+        AuthCache {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    fn key(api_key: &SecretString) -> [u8; 32] {
+        Sha256::digest(api_key.expose_secret().as_bytes()).into()
+    }
+
+    /// Check if the access of the given API key was granted recently.
+    pub fn is_granted(&self, api_key: &SecretString) -> bool {
+        self.granted_scopes(api_key).is_some()
+    }
+
+    /// Retrieve the scopes of the given API key, if its access was granted recently.
+    pub fn granted_scopes(&self, api_key: &SecretString) -> Option<Vec<Scope>> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        match self.entries.read() {
+            Ok(entries) => entries
+                .get(&AuthCache::key(api_key))
+                .filter(|entry| entry.expires_at > Instant::now())
+                .map(|entry| entry.scopes.clone()),
+            Err(_) => None,
+        }
+    }
+
+    /// Register a granted access check for the given API key, along with the scopes of the key.
+    pub fn grant(&self, api_key: &SecretString, client_id: &str, scopes: &[Scope]) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        if let Ok(mut entries) = self.entries.write() {
+            if entries.len() >= MAX_ENTRIES {
+                let now = Instant::now();
+                entries.retain(|_, entry| entry.expires_at > now);
+                // All the entries are alive, the oldest ones can't be told apart cheaply, so start from scratch.
+                if entries.len() >= MAX_ENTRIES {
+                    debug!("The authentication cache is full, dropping all the entries");
+                    entries.clear();
+                }
+            }
+
+            entries.insert(
+                AuthCache::key(api_key),
+                CachedAccess {
+                    client_id: client_id.to_owned(),
+                    scopes: scopes.to_vec(),
+                    expires_at: Instant::now() + self.ttl,
+                },
+            );
+        }
+    }
+
+    /// Remove all the entries of a client, i.e. when one of its tokens is revoked.
+    pub fn invalidate_client(&self, client_id: &str) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.retain(|_, entry| entry.client_id != client_id);
+        }
+    }
+
+    /// Amount of entries in the cache, including the expired ones.
+    pub fn len(&self) -> usize {
+        self.entries.read().map(|e| e.len()).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authentication::ALL_SCOPES;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn api_key(value: &str) -> SecretString {
+        SecretString::from(value)
+    }
+
+    #[rstest]
+    fn granted_checks_are_cached() {
+        let cache = AuthCache::new(Duration::from_secs(60));
+        assert!(!cache.is_granted(&api_key("client:token")));
+
+        cache.grant(&api_key("client:token"), "client", &[Scope::Read]);
+        assert_eq!(
+            cache.granted_scopes(&api_key("client:token")),
+            Some(vec![Scope::Read])
+        );
+        assert!(!cache.is_granted(&api_key("client:other")));
+    }
+
+    #[rstest]
+    fn entries_expire() {
+        let cache = AuthCache::new(Duration::from_millis(10));
+        cache.grant(&api_key("client:token"), "client", ALL_SCOPES);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!cache.is_granted(&api_key("client:token")));
+    }
+
+    #[rstest]
+    fn clients_are_invalidated() {
+        let cache = AuthCache::new(Duration::from_secs(60));
+        cache.grant(&api_key("a:token1"), "a", ALL_SCOPES);
+        cache.grant(&api_key("a:token2"), "a", ALL_SCOPES);
+        cache.grant(&api_key("b:token"), "b", ALL_SCOPES);
+
+        cache.invalidate_client("a");
+        assert_eq!(cache.len(), 1);
+        assert!(cache.is_granted(&api_key("b:token")));
+    }
+
+    #[rstest]
+    fn disabled_cache_keeps_nothing() {
+        let cache = AuthCache::new(Duration::ZERO);
+        cache.grant(&api_key("client:token"), "client", ALL_SCOPES);
+        assert!(cache.is_empty());
+        assert!(!cache.is_granted(&api_key("client:token")));
+    }
+}
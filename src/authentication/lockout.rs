@@ -0,0 +1,271 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Auditing of the failed access checks and lockout of the API clients.
+//!
+//! # Description
+//!
+//! Every failed access check of a registered client is stored in the DB along with its reason ([AuthFailureReason]).
+//! Failures due to a wrong token are also counted: a client is locked for a while ([LockoutPolicy::lock_duration])
+//! after [LockoutPolicy::max_failures] consecutive failures, and the owner of the client is notified by email. Locked
+//! clients are denied the access even when they use a valid token. A granted access check resets the count.
+//!
+//! Access checks with an unknown client ID can't be linked to any client, so nothing is stored for them. Still, they
+//! run the same statements as the failures of the registered clients, which change no row, so the registered IDs
+//! can't be found out by timing. Nothing is stored when the application runs in read-only mode (see
+//! [crate::utils::http::ReadOnly]). The policy of the application is kept by [AccessControl].
+
+use crate::{
+    authentication::AccessControl,
+    domain::{ClientId, ServerError},
+    utils::mailing::{register_email_attempt, send_lockout_email, EmailKind, MailCorrelation},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use std::{fmt, str::FromStr, time::Duration};
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Default amount of consecutive failures that lock a client.
+pub const DEFAULT_MAX_AUTH_FAILURES: u32 = 5;
+/// Default time that a client stays locked.
+pub const DEFAULT_LOCK_DURATION: Duration = Duration::from_secs(900);
+
+/// Rules to lock the API clients.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LockoutPolicy {
+    /// Consecutive failures due to a wrong token that lock a client. Use 0 to disable the lockout.
+    pub max_failures: u32,
+    /// Time that a client stays locked.
+    pub lock_duration: Duration,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        LockoutPolicy {
+            max_failures: DEFAULT_MAX_AUTH_FAILURES,
+            lock_duration: DEFAULT_LOCK_DURATION,
+        }
+    }
+}
+
+impl LockoutPolicy {
+    pub fn disabled() -> Self {
+        LockoutPolicy {
+            max_failures: 0,
+            lock_duration: Duration::ZERO,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_failures > 0 && !self.lock_duration.is_zero()
+    }
+}
+
+/// Reasons of a failed access check.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthFailureReason {
+    /// The given token doesn't match any token of the client.
+    WrongToken,
+    /// The account of the client is disabled.
+    AccountDisabled,
+    /// The given token is expired.
+    ExpiredToken,
+    /// The client is locked due to previous failures.
+    Locked,
+}
+
+impl fmt::Display for AuthFailureReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthFailureReason::WrongToken => write!(f, "wrong_token"),
+            AuthFailureReason::AccountDisabled => write!(f, "account_disabled"),
+            AuthFailureReason::ExpiredToken => write!(f, "expired_token"),
+            AuthFailureReason::Locked => write!(f, "locked"),
+        }
+    }
+}
+
+/// Store a failed access check of a client in the DB.
+///
+/// # Description
+///
+/// Failures due to a wrong token are counted, and the client gets locked when it reaches the maximum amount of
+/// consecutive failures of the [LockoutPolicy]. When that happens, the cached access checks of the client are
+/// invalidated and the owner is notified by email in the background.
+///
+/// Unknown client IDs are given to this function as well: the statements are the same, and they change no row.
+#[tracing::instrument(skip(pool, access))]
+pub async fn register_auth_failure(
+    pool: &MySqlPool,
+    access: &AccessControl,
+    client_id: &str,
+    reason: AuthFailureReason,
+) -> Result<(), ServerError> {
+    if access.is_read_only() {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        "INSERT INTO AuthFailure (client_id, reason) SELECT id, ? FROM ApiUser WHERE id = ?",
+        reason.to_string(),
+        client_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let policy = access.lockout_policy();
+    if reason != AuthFailureReason::WrongToken || !policy.is_enabled() {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        "UPDATE ApiUser SET failed_attempts = failed_attempts + 1 WHERE id = ?",
+        client_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    // Only one of the concurrent failures locks the client, so the owner is notified once.
+    let locked = sqlx::query!(
+        r#"
+        UPDATE ApiUser
+        SET locked_until = CURRENT_TIMESTAMP + INTERVAL ? SECOND, failed_attempts = 0
+        WHERE id = ? AND failed_attempts >= ?
+        "#,
+        policy.lock_duration.as_secs(),
+        client_id,
+        policy.max_failures
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?
+    .rows_affected()
+        > 0;
+
+    if locked {
+        warn!(
+            client_id,
+            lock_secs = policy.lock_duration.as_secs(),
+            "The client got locked after {} consecutive failures",
+            policy.max_failures
+        );
+        access.cache().invalidate_client(client_id);
+        notify_lockout(pool, access, client_id).await;
+    }
+
+    Ok(())
+}
+
+/// Reset the count of consecutive failures of a client.
+#[tracing::instrument(skip(pool, access))]
+pub async fn reset_auth_failures(
+    pool: &MySqlPool,
+    access: &AccessControl,
+    client_id: &str,
+) -> Result<(), ServerError> {
+    if access.is_read_only() {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        "UPDATE ApiUser SET failed_attempts = 0 WHERE id = ?",
+        client_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
+
+/// Send an email to the owner of a locked client, without blocking the request.
+async fn notify_lockout(pool: &MySqlPool, access: &AccessControl, client_id: &str) {
+    let Some(mail_client) = access.mail_client() else {
+        info!("No email client configured, the owner of the client won't be notified");
+        return;
+    };
+
+    let record = match sqlx::query!(
+        "SELECT email, locked_until FROM ApiUser WHERE id = ?",
+        client_id
+    )
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(record) => record,
+        Err(e) => {
+            error!("{e}");
+            return;
+        }
+    };
+    let (Some(record), Ok(id)) = (record, ClientId::from_str(client_id)) else {
+        return;
+    };
+    let (email, Some(locked_until)) = (record.email, record.locked_until) else {
+        return;
+    };
+
+    let pool = pool.clone();
+    // The lockout is not tied to a single request, so the email gets a request ID of its own.
+    let correlation = MailCorrelation::new(Uuid::now_v7(), &id);
+    actix_web::rt::spawn(async move {
+        let outcome = send_lockout_email(mail_client, &email, locked_until, &correlation).await;
+        if let Err(e) = register_email_attempt(
+            &pool,
+            &correlation,
+            EmailKind::LockoutNotification,
+            &email,
+            &outcome,
+        )
+        .await
+        {
+            warn!("Failed to register the email attempt: {e}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn policies_can_be_disabled() {
+        assert!(LockoutPolicy::default().is_enabled());
+        assert!(!LockoutPolicy::disabled().is_enabled());
+        assert!(!LockoutPolicy {
+            max_failures: 0,
+            ..Default::default()
+        }
+        .is_enabled());
+    }
+
+    #[rstest]
+    #[case(AuthFailureReason::WrongToken, "\"wrong_token\"")]
+    #[case(AuthFailureReason::Locked, "\"locked\"")]
+    fn reasons_match_the_db(#[case] reason: AuthFailureReason, #[case] expected: &str) {
+        assert_eq!(serde_json::to_string(&reason).unwrap(), expected);
+        assert_eq!(format!("\"{reason}\""), expected);
+    }
+}
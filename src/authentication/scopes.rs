@@ -0,0 +1,108 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Scopes of the API keys.
+//!
+//! # Description
+//!
+//! Every API key grants a set of scopes, and every restricted endpoint requires one of them (see
+//! [crate::authentication::check_access]). The key issued at the end of the token request process grants all the
+//! scopes. Clients can derive keys with a subset of the scopes of their keys, i.e. a read-only key for a public
+//! website, and a write key for a CMS.
+//!
+//! Scopes are stored in the DB as a MariaDB `SET`, that is, a comma separated list of names.
+
+use crate::domain::DataDomainError;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+use utoipa::ToSchema;
+
+/// Scope granted by an API key.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// Retrieve the restricted data, i.e. the private data of the authors.
+    Read,
+    /// Create, modify and delete content.
+    Write,
+    /// Manage the API keys of the client.
+    Keys,
+}
+
+/// All the scopes, granted by the key issued at the end of the token request process.
+pub const ALL_SCOPES: &[Scope] = &[Scope::Read, Scope::Write, Scope::Keys];
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scope::Read => write!(f, "read"),
+            Scope::Write => write!(f, "write"),
+            Scope::Keys => write!(f, "keys"),
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = DataDomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "read" => Ok(Scope::Read),
+            "write" => Ok(Scope::Write),
+            "keys" => Ok(Scope::Keys),
+            _ => Err(DataDomainError::InvalidData),
+        }
+    }
+}
+
+/// Parse the scopes stored in the DB. Unknown names are ignored.
+pub fn parse_scopes(scopes: &str) -> Vec<Scope> {
+    let mut scopes = scopes
+        .split(',')
+        .filter_map(|s| Scope::from_str(s).ok())
+        .collect::<Vec<Scope>>();
+    scopes.sort();
+    scopes.dedup();
+
+    scopes
+}
+
+/// Format a list of scopes to store it in the DB.
+pub fn format_scopes(scopes: &[Scope]) -> String {
+    scopes
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case("read,write,keys", ALL_SCOPES)]
+    #[case("write,read,read", &[Scope::Read, Scope::Write])]
+    #[case("read,admin", &[Scope::Read])]
+    #[case("", &[])]
+    fn scopes_are_parsed(#[case] input: &str, #[case] expected: &[Scope]) {
+        assert_eq!(parse_scopes(input), expected);
+    }
+
+    #[rstest]
+    fn scopes_are_formatted() {
+        assert_eq!(format_scopes(ALL_SCOPES), "read,write,keys");
+        assert_eq!(parse_scopes(&format_scopes(ALL_SCOPES)), ALL_SCOPES);
+        assert_eq!(
+            serde_json::to_string(&[Scope::Read, Scope::Keys]).unwrap(),
+            r#"["read","keys"]"#
+        );
+    }
+}
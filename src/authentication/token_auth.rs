@@ -6,17 +6,60 @@
 
 //! Utilities for managing access tokens of the API.
 
-use crate::domain::{ClientId, DataDomainError, ServerError};
+use crate::{
+    authentication::{
+        format_scopes, parse_scopes, register_auth_failure, reset_auth_failures, AccessControl,
+        AuthCache, AuthFailureReason, Scope,
+    },
+    domain::{ApiError, ClientId, DataDomainError, ServerError},
+    utils::metrics::metrics,
+};
+use actix_web::HttpResponse;
 use argon2::{
     password_hash::SaltString,
     {Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version},
 };
-use chrono::{Local, TimeDelta};
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use once_cell::sync::Lazy;
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
 use sqlx::{Executor, MySql, MySqlPool, Transaction};
-use std::{error::Error, str::FromStr};
+use std::{error::Error, str::FromStr, time::Instant};
 use tracing::{debug, error, info};
+use utoipa::ToSchema;
+
+/// Time that an API key is valid since it is issued.
+pub const API_KEY_EXPIRY: TimeDelta = TimeDelta::days(100);
+
+/// Maximum amount of active API keys that a client can hold.
+pub const MAX_KEYS_PER_CLIENT: u32 = 5;
+
+/// Build the API key given to a client.
+///
+/// # Description
+///
+/// API keys follow the format `<client ID>:<key ID>.<token>`. The ID of the key is not secret, it only points
+/// [check_access] to the stored hash that the token is compared against, so exactly one hash is verified per
+/// request, no matter how many keys the client holds.
+pub fn format_api_key(client_id: &ClientId, key_id: u64, token: &SecretString) -> String {
+    format!("{client_id}:{key_id}.{}", token.expose_secret())
+}
+
+/// Split an API key into the ID of the client, the ID of the key and the token.
+///
+/// # Description
+///
+/// Keys issued before the ID of the key was included (`<client ID>:<token>`) are accepted, and they point to the
+/// first key of the client, which is the only key that clients could hold back then. `None` is returned when the key
+/// has no valid format.
+fn parse_api_key(api_key: &str) -> Option<(&str, Option<u64>, &str)> {
+    let (client_id, token) = api_key.split_once(':')?;
+    match token.split_once('.') {
+        Some((key_id, token)) => Some((client_id, Some(key_id.parse().ok()?), token)),
+        None => Some((client_id, None, token)),
+    }
+}
 
 /// Check if a given token matches the hash stored in the DB.
 ///
@@ -73,14 +116,14 @@ pub fn generate_new_token_hash(plain_token: SecretString) -> Result<SecretString
     Ok(SecretString::from(token_hash))
 }
 
-/// Store a validation token in the DB.
+/// Store a validation token in the DB. The ID of the key is returned (see [format_api_key]).
 #[tracing::instrument(skip(transaction, token))]
 pub async fn store_validation_token(
     transaction: &mut Transaction<'static, MySql>,
     token: &SecretString,
     expiry: TimeDelta,
     client_id: &ClientId,
-) -> Result<(), ServerError> {
+) -> Result<u64, ServerError> {
     let query = sqlx::query!(
         r#"
         INSERT INTO ApiToken
@@ -92,7 +135,77 @@ pub async fn store_validation_token(
         client_id.to_string(),
     );
 
-    transaction.execute(query).await.map_err(|e| {
+    let result = transaction.execute(query).await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(result.last_insert_id())
+}
+
+/// Status of an email validation token.
+#[derive(Debug, Clone)]
+pub enum ValidationTokenStatus {
+    /// The token was valid, and it is now consumed.
+    Valid(ClientId),
+    /// The token expired before being used, or it was replaced by a newer token.
+    Expired,
+    /// The token was already used.
+    Used,
+}
+
+/// Maximum amount of validation tokens of a client that are compared against a given token.
+///
+/// # Description
+///
+/// Validation tokens are stored as hashes, so they can't be searched in the DB. The latest tokens issued to the client
+/// are compared instead, which is enough to tell apart expired or used links from the unknown ones.
+const MAX_VALIDATION_TOKENS_CHECKED: u32 = 5;
+
+/// Store a new email validation token in the DB.
+///
+/// # Description
+///
+/// Only the hash of the token is stored (see [generate_new_token_hash]). Previous tokens of the client that were not
+/// used yet are expired, so only the latest link sent to the client is valid.
+#[tracing::instrument(skip(transaction, token))]
+pub async fn store_email_validation_token(
+    transaction: &mut Transaction<'static, MySql>,
+    token: &SecretString,
+    expiry: TimeDelta,
+    client_id: &ClientId,
+) -> Result<(), ServerError> {
+    let token_hash = generate_new_token_hash(token.clone()).map_err(|e| {
+        error!("Failed to hash the validation token: {e}");
+        ServerError::DbError
+    })?;
+
+    sqlx::query!(
+        r#"
+        UPDATE ValidationToken SET expires_at = CURRENT_TIMESTAMP
+        WHERE client_id = ? AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+        "#,
+        client_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO ValidationToken (token_hash, client_id, expires_at)
+        VALUES (?, ?, CURRENT_TIMESTAMP + INTERVAL ? SECOND)
+        "#,
+        token_hash.expose_secret(),
+        client_id.to_string(),
+        expiry.num_seconds(),
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
         error!("{e}");
         ServerError::DbError
     })?;
@@ -100,41 +213,349 @@ pub async fn store_validation_token(
     Ok(())
 }
 
+/// Consume an email validation token.
+///
+/// # Description
+///
+/// The pair email-token is checked against the DB, comparing the given token with the stored hashes using
+/// [verify_token]. An `Err(InvalidAccessCredentials)` is returned when the pair doesn't match any issued token.
+/// Otherwise, the status of the token is returned. Valid tokens are marked as used, so they can't be used again. The
+/// expiry is checked by the DB server to avoid mixing the time zones of both servers.
+#[tracing::instrument(skip(transaction, token))]
+pub async fn consume_email_validation_token(
+    transaction: &mut Transaction<'static, MySql>,
+    token: &SecretString,
+    email: &str,
+) -> Result<ValidationTokenStatus, Box<dyn Error>> {
+    let records = sqlx::query!(
+        r#"
+        SELECT vt.token_hash, vt.client_id,
+            CAST(vt.expires_at <= CURRENT_TIMESTAMP AS SIGNED) AS expired,
+            CAST(vt.used_at IS NOT NULL AS SIGNED) AS used
+        FROM ApiUser au JOIN ValidationToken vt ON vt.client_id = au.id
+        WHERE au.email = ?
+        ORDER BY vt.created_at DESC, vt.expires_at DESC
+        LIMIT ?
+        FOR UPDATE
+        "#,
+        email,
+        MAX_VALIDATION_TOKENS_CHECKED,
+    )
+    .fetch_all(&mut **transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let record = records.into_iter().find(|record| {
+        verify_token(
+            SecretString::from(record.token_hash.as_str()),
+            token.clone(),
+        )
+        .is_ok()
+    });
+
+    let record = match record {
+        Some(record) => record,
+        None => {
+            info!("The given validation token doesn't match any issued token");
+            return Err(Box::new(DataDomainError::InvalidAccessCredentials));
+        }
+    };
+
+    let client_id = record.client_id;
+
+    if record.used > 0 {
+        info!("The validation token of the client {client_id} was already used");
+        return Ok(ValidationTokenStatus::Used);
+    }
+
+    if record.expired > 0 {
+        info!("The validation token of the client {client_id} is expired");
+        return Ok(ValidationTokenStatus::Expired);
+    }
+
+    sqlx::query!(
+        "UPDATE ValidationToken SET used_at = CURRENT_TIMESTAMP WHERE token_hash = ?",
+        record.token_hash,
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(ValidationTokenStatus::Valid(
+        ClientId::from_str(&client_id).map_err(|_| {
+            error!("Failed to parse ClientId from DB client's ID");
+            ServerError::DbError
+        })?,
+    ))
+}
+
+/// Check whether a client has validated the email of the account.
+#[tracing::instrument(skip(pool))]
+pub async fn is_client_validated(pool: &MySqlPool, id: &ClientId) -> Result<bool, ServerError> {
+    let validated = sqlx::query_scalar!(
+        "SELECT CAST(COALESCE(validated, 0) AS SIGNED) AS validated FROM ApiUser WHERE id = ?",
+        id.to_string(),
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(validated > 0)
+}
+
 /// Delete a token that will be no longer used.
+///
+/// # Description
+///
+/// The cached access checks of the owner of the token are invalidated (see [AuthCache]).
+#[tracing::instrument(skip(pool, cache, token))]
+pub async fn delete_token(
+    pool: &MySqlPool,
+    cache: &AuthCache,
+    token: SecretString,
+) -> Result<(), ServerError> {
+    let client_ids = sqlx::query_scalar!(
+        "DELETE FROM ApiToken WHERE api_token = ? RETURNING client_id",
+        token.expose_secret(),
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    for client_id in client_ids {
+        cache.invalidate_client(&client_id);
+    }
+
+    Ok(())
+}
+
+/// API key of a client, without its token.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct ClientKey {
+    pub id: u64,
+    pub label: String,
+    /// Scopes granted by the key.
+    pub scopes: Vec<Scope>,
+    #[schema(value_type = String, example = "2025-09-11T08:58:56Z")]
+    pub created: DateTime<Utc>,
+    #[schema(value_type = String, example = "2025-12-20T08:58:56Z")]
+    pub valid_until: DateTime<Utc>,
+}
+
+/// Store a new API key of a client, granting the given scopes. The ID of the key is returned.
+///
+/// # Description
+///
+/// `None` is returned when the client already holds [MAX_KEYS_PER_CLIENT] active keys. The row of the client is
+/// locked while the keys are counted, so concurrent requests can't exceed the limit.
 #[tracing::instrument(skip(pool, token))]
-pub async fn delete_token(pool: &MySqlPool, token: SecretString) -> Result<(), ServerError> {
-    let query = sqlx::query!(
-        "DELETE FROM ApiToken WHERE api_token = ?",
-        token.expose_secret()
-    );
+pub async fn store_api_key(
+    pool: &MySqlPool,
+    token: &SecretString,
+    label: &str,
+    scopes: &[Scope],
+    expiry: TimeDelta,
+    client_id: &ClientId,
+) -> Result<Option<u64>, ServerError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
 
-    pool.execute(query).await.map_err(|e| {
+    sqlx::query!(
+        "SELECT id FROM ApiUser WHERE id = ? FOR UPDATE",
+        client_id.to_string()
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .map_err(|e| {
         error!("{e}");
         ServerError::DbError
     })?;
 
-    Ok(())
+    let active_keys = sqlx::query_scalar!(
+        "SELECT COUNT(*) AS count FROM ApiToken \
+        WHERE client_id = ? AND valid_until > CURRENT_TIMESTAMP",
+        client_id.to_string()
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+    if active_keys >= MAX_KEYS_PER_CLIENT as i64 {
+        return Ok(None);
+    }
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO ApiToken (label, scopes, api_token, valid_until, client_id)
+        VALUES (?, ?, ?, CURRENT_TIMESTAMP + INTERVAL ? SECOND, ?)
+        "#,
+        label,
+        format_scopes(scopes),
+        token.expose_secret(),
+        expiry.num_seconds(),
+        client_id.to_string()
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(Some(result.last_insert_id()))
+}
+
+/// Retrieve the API keys of a client, newest first.
+#[tracing::instrument(skip(pool))]
+pub async fn get_api_keys(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+) -> Result<Vec<ClientKey>, ServerError> {
+    let keys = sqlx::query!(
+        r#"
+        SELECT id, label, scopes, created AS "created!", valid_until
+        FROM ApiToken
+        WHERE client_id = ?
+        ORDER BY valid_until DESC, id DESC
+        "#,
+        client_id.to_string()
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(keys
+        .into_iter()
+        .map(|key| ClientKey {
+            id: key.id,
+            label: key.label,
+            scopes: parse_scopes(&key.scopes),
+            created: key.created,
+            valid_until: key.valid_until,
+        })
+        .collect())
+}
+
+/// Delete an API key of a client. `false` is returned when the client has no key identified by `id`.
+///
+/// # Description
+///
+/// The cached access checks of the client are invalidated (see [AuthCache]).
+#[tracing::instrument(skip(pool, cache))]
+pub async fn delete_api_key(
+    pool: &MySqlPool,
+    cache: &AuthCache,
+    client_id: &ClientId,
+    id: u64,
+) -> Result<bool, ServerError> {
+    let result = sqlx::query!(
+        "DELETE FROM ApiToken WHERE id = ? AND client_id = ?",
+        id,
+        client_id.to_string()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+    cache.invalidate_client(&client_id.to_string());
+
+    Ok(result.rows_affected() > 0)
 }
 
+/// Hash compared against the given tokens when the client is unknown.
+///
+/// # Description
+///
+/// Comparing a token against a hash takes most of the time spent by [check_access]. Requests that include an unknown
+/// client ID are compared against this hash, so they can't be told apart from requests with a wrong token by timing.
+static DUMMY_TOKEN_HASH: Lazy<SecretString> = Lazy::new(|| {
+    generate_new_token_hash(SecretString::from(generate_token()))
+        .expect("Failed to generate the dummy token hash")
+});
+
 /// Check if the client hash access to the restricted API's endpoints.
 ///
 /// # Description
 ///
-/// Given a client access token, the stored hash of the token is retrieved from the database and compared. If the
-/// comparison is positive, it is checked if the client is enabled.
-pub async fn check_access(pool: &MySqlPool, token: &SecretString) -> Result<(), Box<dyn Error>> {
+/// Given a client access token, the stored hash of the key that it points to (see [format_api_key]) is retrieved
+/// from the database and compared. If the token matches, it is checked if the client is enabled and the key is not
+/// expired. A single hash is verified per request, whatever the amount of keys held by the client.
+///
+/// All the reasons to deny the access (unknown client, wrong token, disabled account, expired token or locked client)
+/// result in the same `Err(InvalidAccessCredentials)`, and take a similar time, so clients can't find out which IDs
+/// are registered. The detailed reason is only logged, and stored in the DB for registered clients (see
+/// [register_auth_failure]). Errors from the DB are returned as they are.
+///
+/// Every restricted endpoint requires a [Scope]. Once the credentials are checked, an `Err(InsufficientPrivileges)`
+/// is returned when the key doesn't grant the required `scope`. Such checks are not registered as failures. The
+/// scopes granted by the key are returned otherwise.
+///
+/// Granted checks are kept for a short time in the cache of `access` (see [AuthCache]), so the following requests of
+/// the client skip the verification of the token.
+pub async fn check_access(
+    pool: &MySqlPool,
+    access: &AccessControl,
+    token: &SecretString,
+    scope: Scope,
+) -> Result<Vec<Scope>, Box<dyn Error>> {
+    if let Some(scopes) = access.cache().granted_scopes(token) {
+        debug!("Access granted by the authentication cache");
+        return require_scope(scopes, scope);
+    }
+
+    let api_key = token;
     // Let's split the token to get the client's ID and the token itself.
-    let token_split = token.expose_secret().split(':').collect::<Vec<&str>>();
-    let client_id = token_split[0];
-    let token = SecretString::from(token_split[1]);
-    // First, retrieve the credentials for the client using the email.
-    let query = sqlx::query!(
+    let (client_id, key_id, token) = match parse_api_key(token.expose_secret()) {
+        Some((client_id, key_id, token)) => {
+            (client_id.to_owned(), key_id, SecretString::from(token))
+        }
+        None => {
+            info!("Access denied: the given API key has an invalid format");
+            let _ = verify_token(DUMMY_TOKEN_HASH.clone(), token.clone());
+            return Err(Box::new(DataDomainError::InvalidAccessCredentials));
+        }
+    };
+    // First, retrieve the key using the IDs of the client and the key.
+    let started = Instant::now();
+    let record = sqlx::query!(
         r#"
-        SELECT at.api_token, at.valid_until, au.enabled
-        FROM ApiUser au natural join ApiToken at
-        WHERE au.id = ?
+        SELECT at.api_token, at.valid_until, at.scopes, au.enabled,
+            CAST(COALESCE(au.locked_until > CURRENT_TIMESTAMP, 0) AS SIGNED) AS locked,
+            au.failed_attempts
+        FROM ApiUser au JOIN ApiToken at ON at.client_id = au.id
+        WHERE au.id = ? AND (? IS NULL OR at.id = ?)
+        ORDER BY at.id
+        LIMIT 1
         "#,
-        client_id.to_string()
+        client_id,
+        key_id,
+        key_id,
     )
     .fetch_optional(pool)
     .await
@@ -142,42 +563,132 @@ pub async fn check_access(pool: &MySqlPool, token: &SecretString) -> Result<(),
         error!("{e}");
         Box::new(ServerError::DbError)
     })?;
+    metrics().record_db_latency(started.elapsed());
 
-    let (token_saved, valid_until, enabled) = match query {
-        Some(record) => (
-            SecretString::from(record.api_token),
-            record.valid_until,
-            record.enabled,
-        ),
+    let record = match record {
+        Some(record) => record,
         None => {
-            info!("The given client ID ({client_id}) does not exist in the DB");
-            return Err(Box::new(DataDomainError::InvalidId));
+            info!(
+                "Access denied: the given client ID ({client_id}) or key does not exist in the DB"
+            );
+            let _ = verify_token(DUMMY_TOKEN_HASH.clone(), token);
+            // Same statements as for the registered clients, so both take a similar time. The failure is stored
+            // when the client exists, but the key doesn't.
+            register_auth_failure(pool, access, &client_id, AuthFailureReason::WrongToken).await?;
+            return Err(Box::new(DataDomainError::InvalidAccessCredentials));
         }
     };
+    let locked = record.locked > 0;
+    let failed_attempts = record.failed_attempts;
 
-    debug!(
-        "The client exists in the DB. Proceeding to compare the given token with the stored hash"
-    );
+    debug!("The key exists in the DB. Proceeding to compare the given token with the stored hash");
 
-    // First, check if the given pair client-token matches the saved one. This avoid giving information about disabled
-    // accounts or expired tokens to people that has no access to the API.
-    verify_token(token_saved, token).map_err(Box::new)?;
-    debug!("The token is valid and registered to the client");
-
-    // Second, check if the account is actually enabled.
-    if enabled.unwrap_or_default() > 0 {
-        debug!("The client's account is enabled");
-        // Finally, check that the token is not expired.
-        if valid_until.date_naive() - Local::now().date_naive() < TimeDelta::zero() {
-            debug!("The client's token is expired");
-            Err(Box::new(DataDomainError::ExpiredAccess))
+    // The token is verified even for locked clients, so they can't be told apart by timing.
+    let key = verify_token(SecretString::from(record.api_token), token)
+        .ok()
+        .map(|_| (record.valid_until, parse_scopes(&record.scopes)));
+
+    let failure = if locked {
+        Some(AuthFailureReason::Locked)
+    } else if let Some((valid_until, _)) = &key {
+        if record.enabled.unwrap_or_default() == 0 {
+            Some(AuthFailureReason::AccountDisabled)
+        } else if valid_until.date_naive() - Local::now().date_naive() < TimeDelta::zero() {
+            Some(AuthFailureReason::ExpiredToken)
         } else {
-            debug!("The token is valid and not expired");
-            Ok(())
+            None
+        }
+    } else {
+        Some(AuthFailureReason::WrongToken)
+    };
+
+    if let Some(reason) = failure {
+        info!("Access denied for the client ({client_id}): {reason}");
+        register_auth_failure(pool, access, &client_id, reason).await?;
+        return Err(Box::new(DataDomainError::InvalidAccessCredentials));
+    }
+    debug!("The token is valid and not expired, and the client's account is enabled");
+
+    if failed_attempts > 0 {
+        reset_auth_failures(pool, access, &client_id).await?;
+    }
+    let scopes = key.map(|(_, scopes)| scopes).unwrap_or_default();
+    access.cache().grant(api_key, &client_id, &scopes);
+
+    require_scope(scopes, scope)
+}
+
+/// Extract the ID of the client from an API key that was already checked by [check_access].
+pub fn key_client_id(api_key: &SecretString) -> Result<ClientId, Box<dyn Error>> {
+    let id = api_key
+        .expose_secret()
+        .split(':')
+        .next()
+        .unwrap_or_default();
+
+    Ok(ClientId::from_str(id)?)
+}
+
+/// Check that the scopes of a key include the required `scope`.
+fn require_scope(scopes: Vec<Scope>, scope: Scope) -> Result<Vec<Scope>, Box<dyn Error>> {
+    if scopes.contains(&scope) {
+        Ok(scopes)
+    } else {
+        info!("Access denied: the given API key doesn't grant the scope {scope}");
+        Err(Box::new(DataDomainError::InsufficientPrivileges))
+    }
+}
+
+/// Build the response for a client whose access was denied by [check_access] or [check_admin_access].
+///
+/// # Description
+///
+/// Clients with wrong credentials receive a code **401**, and clients lacking the administration privileges, or using a
+/// key that doesn't grant the required scope, a code **403**. No details are included in the response. Other errors are
+/// returned, which results in a code **500**.
+pub fn access_denied_response(e: impl Into<ApiError>) -> Result<HttpResponse, ApiError> {
+    match e.into() {
+        ApiError::Domain(DataDomainError::InvalidAccessCredentials) => {
+            Ok(HttpResponse::Unauthorized().finish())
         }
+        ApiError::Domain(DataDomainError::InsufficientPrivileges) => {
+            Ok(HttpResponse::Forbidden().finish())
+        }
+        e => Err(e),
+    }
+}
+
+/// Check if the client has access to the administration endpoints of the API.
+///
+/// # Description
+///
+/// Administration endpoints are restricted to clients whose account is flagged as `admin` in the DB. The regular
+/// access checks of [check_access] are applied first. An `Err(InsufficientPrivileges)` is returned when the client
+/// has regular access to the API but lacks the administration privileges.
+pub async fn check_admin_access(
+    pool: &MySqlPool,
+    access: &AccessControl,
+    token: &SecretString,
+    scope: Scope,
+) -> Result<(), Box<dyn Error>> {
+    check_access(pool, access, token, scope).await?;
+
+    let client_id = token.expose_secret().split(':').collect::<Vec<&str>>()[0];
+
+    let admin = sqlx::query_scalar!("SELECT admin FROM ApiUser WHERE id = ?", client_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            Box::new(ServerError::DbError)
+        })?;
+
+    if admin != 0 {
+        debug!("The client has administration privileges");
+        Ok(())
     } else {
-        debug!("The account is disabled");
-        Err(Box::new(DataDomainError::AccountDisabled))
+        info!("The client ({client_id}) attempted to access an administration resource");
+        Err(Box::new(DataDomainError::InsufficientPrivileges))
     }
 }
 
@@ -215,7 +726,10 @@ pub async fn check_existing_user(
         })?;
 
     match existing_id {
-        Some(record) => Ok(ClientId::from_str(&record.id).unwrap()),
+        Some(record) => Ok(ClientId::from_str(&record.id).map_err(|e| {
+            error!("Failed to parse a client ID from a value of the DB: {e}");
+            ServerError::DbError
+        })?),
         None => Err(Box::new(DataDomainError::InvalidEmail)),
     }
 }
@@ -246,6 +760,7 @@ pub async fn validate_client_account(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pretty_assertions::assert_eq;
     use rstest::*;
     use secrecy::SecretString;
 
@@ -266,4 +781,28 @@ mod tests {
         let token2_hash = generate_new_token_hash(token).expect("Failed to generate token hash");
         assert!(verify_token(token_hash, token2_hash).is_err())
     }
+
+    #[rstest]
+    fn api_keys_point_to_a_single_key() {
+        let client_id = ClientId::new();
+        let token = SecretString::from(generate_token());
+        let api_key = format_api_key(&client_id, 42, &token);
+        let client_id = client_id.to_string();
+        assert_eq!(
+            parse_api_key(&api_key),
+            Some((client_id.as_str(), Some(42), token.expose_secret()))
+        );
+    }
+
+    #[rstest]
+    #[case("client:token", Some(("client", None, "token")))]
+    #[case("client:7.token", Some(("client", Some(7), "token")))]
+    #[case("client:seven.token", None)]
+    #[case("token", None)]
+    fn legacy_api_keys_are_accepted(
+        #[case] api_key: &str,
+        #[case] expected: Option<(&str, Option<u64>, &str)>,
+    ) {
+        assert_eq!(parse_api_key(api_key), expected);
+    }
 }
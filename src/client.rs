@@ -0,0 +1,292 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Client of the API for Rust consumers, available with the `client` feature.
+//!
+//! # Description
+//!
+//! [ApiClient] wraps the public resources of the API, and the token request, using the same domain types that the
+//! server uses, so Rust consumers (i.e. a frontend or a BFF) don't need to write the HTTP calls nor mirror the types.
+//! The client targets a versioned URL of the API, i.e. `https://lacoctelera.net/api/v0`
+//! (see [crate::startup::api_url]).
+//!
+//! Resources that need an API key use the key given to [ApiClient::with_api_key]. Responses that are not
+//! successful are returned as [ClientError::Status], except for the missing resources, which are returned as `None`
+//! or as an empty list.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), lacoctelera::client::ClientError> {
+//! use lacoctelera::{client::ApiClient, domain::RecipeQuery};
+//!
+//! let client = ApiClient::new("https://lacoctelera.net/api/v0");
+//! let query = RecipeQuery {
+//!     name: Some("margarita".into()),
+//! #   tags: None, rating: None, category: None, equipment_excludes: None, max_prep_time: None, state: None,
+//! #   q: None, sort: None, limit: None, offset: None,
+//!     // ...
+//! };
+//! let page = client.search_recipes(&query).await?;
+//! println!("{} recipes found", page.total);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    domain::{auth::TokenRequestData, screening::ScreeningFlag, Author, Recipe, RecipeQuery},
+    routes::{author::AuthorQueryParams, ingredient::FormData, ingredient::QueryData},
+    utils::http::X_TOTAL_COUNT,
+    Ingredient,
+};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{de::DeserializeOwned, Deserialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Errors of the [ApiClient].
+#[derive(Error, Debug)]
+pub enum ClientError {
+    /// The request could not be sent, or the response could not be parsed.
+    #[error("Request error: {0}")]
+    Request(#[from] reqwest::Error),
+    /// The API answered with an unexpected status. The body of the response is included, as it usually describes the
+    /// issue.
+    #[error("The API answered with status {status}: {body}")]
+    Status { status: StatusCode, body: String },
+    /// The resource needs an API key, and the client has none.
+    #[error("The resource needs an API key")]
+    MissingApiKey,
+}
+
+/// Page of the results of a search.
+#[derive(Clone, Debug)]
+pub struct SearchPage<T> {
+    pub items: Vec<T>,
+    /// Amount of results of the whole search, taken from the `X-Total-Count` header.
+    pub total: usize,
+}
+
+/// Recipe registered by [ApiClient::post_recipe].
+#[derive(Clone, Debug, Deserialize)]
+pub struct CreatedRecipe {
+    pub id: Uuid,
+    /// Reasons why the recipe is waiting for moderation. Empty when the recipe was published.
+    #[serde(default)]
+    pub moderation: Vec<ScreeningFlag>,
+}
+
+/// Client of the API.
+#[derive(Clone, Debug)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<SecretString>,
+}
+
+impl ApiClient {
+    /// Build a client of the API served at the given URL, i.e. `https://lacoctelera.net/api/v0`.
+    pub fn new(base_url: &str) -> Self {
+        ApiClient {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            api_key: None,
+        }
+    }
+
+    /// Use an API key for the requests, needed by the restricted resources.
+    pub fn with_api_key(mut self, api_key: SecretString) -> Self {
+        self.api_key = Some(api_key);
+        self
+    }
+
+    /// Use a custom HTTP client, i.e. to set timeouts or a proxy.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// URL of a resource of the API.
+    pub fn url(&self, resource: &str) -> String {
+        format!("{}/{}", self.base_url, resource.trim_start_matches('/'))
+    }
+
+    /// Search recipes. See `GET /recipe`.
+    pub async fn search_recipes(
+        &self,
+        query: &RecipeQuery,
+    ) -> Result<SearchPage<Recipe>, ClientError> {
+        let request = self.http.get(self.url("recipe")).query(query);
+        search_page(self.authenticated(request, false)?.send().await?).await
+    }
+
+    /// Retrieve a recipe using its ID. See `GET /recipe/{id}`.
+    pub async fn get_recipe(&self, id: &Uuid) -> Result<Option<Recipe>, ClientError> {
+        let request = self.http.get(self.url(&format!("recipe/{id}")));
+        optional_json(self.authenticated(request, false)?.send().await?).await
+    }
+
+    /// Register a new recipe (needs an API key). See `POST /recipe`.
+    pub async fn post_recipe(&self, recipe: &Recipe) -> Result<CreatedRecipe, ClientError> {
+        let request = self.http.post(self.url("recipe")).json(recipe);
+        json(self.authenticated(request, true)?.send().await?).await
+    }
+
+    /// Delete a recipe (needs an API key). `false` is returned when the recipe doesn't exist. See
+    /// `DELETE /recipe/{id}`.
+    pub async fn delete_recipe(&self, id: &Uuid) -> Result<bool, ClientError> {
+        let request = self.http.delete(self.url(&format!("recipe/{id}")));
+        let response = self.authenticated(request, true)?.send().await?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            _ => success(response).await.map(|_| true),
+        }
+    }
+
+    /// Search ingredients by name. See `GET /ingredient`.
+    pub async fn search_ingredients(
+        &self,
+        query: &QueryData,
+    ) -> Result<SearchPage<Ingredient>, ClientError> {
+        let request = self.http.get(self.url("ingredient")).query(query);
+        search_page(request.send().await?).await
+    }
+
+    /// Retrieve an ingredient using its ID. See `GET /ingredient/{id}`.
+    pub async fn get_ingredient(&self, id: &Uuid) -> Result<Option<Ingredient>, ClientError> {
+        let request = self.http.get(self.url(&format!("ingredient/{id}")));
+        optional_json(request.send().await?).await
+    }
+
+    /// Register a new ingredient. See `POST /ingredient`.
+    pub async fn add_ingredient(&self, ingredient: &FormData) -> Result<(), ClientError> {
+        let request = self.http.post(self.url("ingredient")).json(ingredient);
+        success(request.send().await?).await.map(|_| ())
+    }
+
+    /// Search authors. Private data of the profiles is muted unless the client has an API key. See `GET /author`.
+    pub async fn search_authors(
+        &self,
+        query: &AuthorQueryParams,
+    ) -> Result<SearchPage<Author>, ClientError> {
+        let request = self.http.get(self.url("author")).query(query);
+        search_page(self.authenticated(request, false)?.send().await?).await
+    }
+
+    /// Retrieve an author using its ID. See `GET /author/{id}`.
+    pub async fn get_author(&self, id: &Uuid) -> Result<Option<Author>, ClientError> {
+        let request = self.http.get(self.url(&format!("author/{id}")));
+        optional_json(self.authenticated(request, false)?.send().await?).await
+    }
+
+    /// Request an API key. A confirmation email is sent to the given address. See `POST /token/request`.
+    pub async fn request_token(&self, request: &TokenRequestData) -> Result<(), ClientError> {
+        let request = self.http.post(self.url("token/request")).form(request);
+        success(request.send().await?).await.map(|_| ())
+    }
+
+    /// Add the API key to a request. Requests that don't `need` it are sent without it when the client has none.
+    fn authenticated(
+        &self,
+        request: RequestBuilder,
+        need: bool,
+    ) -> Result<RequestBuilder, ClientError> {
+        match (&self.api_key, need) {
+            (Some(api_key), _) => Ok(request.query(&[("api_key", api_key.expose_secret())])),
+            (None, false) => Ok(request),
+            (None, true) => Err(ClientError::MissingApiKey),
+        }
+    }
+}
+
+/// Check that a response is successful.
+async fn success(response: Response) -> Result<Response, ClientError> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(ClientError::Status {
+            status: response.status(),
+            body: response.text().await.unwrap_or_default(),
+        })
+    }
+}
+
+async fn json<T: DeserializeOwned>(response: Response) -> Result<T, ClientError> {
+    Ok(success(response).await?.json().await?)
+}
+
+/// Parse a singleton resource, `None` is returned when it doesn't exist.
+async fn optional_json<T: DeserializeOwned>(response: Response) -> Result<Option<T>, ClientError> {
+    match response.status() {
+        StatusCode::NOT_FOUND => Ok(None),
+        _ => json(response).await.map(Some),
+    }
+}
+
+/// Parse a page of a search. Searches that find nothing are answered by some resources with a code 404, which is
+/// returned as an empty page.
+async fn search_page<T: DeserializeOwned>(
+    response: Response,
+) -> Result<SearchPage<T>, ClientError> {
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(SearchPage {
+            items: Vec::new(),
+            total: 0,
+        });
+    }
+
+    let total = response
+        .headers()
+        .get(X_TOTAL_COUNT)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    let items: Vec<T> = json(response).await?;
+
+    Ok(SearchPage {
+        total: total.unwrap_or(items.len()),
+        items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case(
+        "http://localhost:9090/api/v0",
+        "recipe",
+        "http://localhost:9090/api/v0/recipe"
+    )]
+    #[case(
+        "http://localhost:9090/api/v0/",
+        "/recipe/search",
+        "http://localhost:9090/api/v0/recipe/search"
+    )]
+    fn urls_are_built(#[case] base_url: &str, #[case] resource: &str, #[case] expected: &str) {
+        assert_eq!(ApiClient::new(base_url).url(resource), expected);
+    }
+
+    #[rstest]
+    fn restricted_resources_need_an_api_key() {
+        let client = ApiClient::new("http://localhost:9090/api/v0");
+        let request = client.http.get(client.url("recipe"));
+        assert!(matches!(
+            client.authenticated(request, true),
+            Err(ClientError::MissingApiKey)
+        ));
+
+        let client = client.with_api_key(SecretString::from("client:secret"));
+        let request = client
+            .authenticated(client.http.get(client.url("recipe")), true)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(request.url().query(), Some("api_key=client%3Asecret"));
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Generation of the typed clients of the API.
+//!
+//! # Description
+//!
+//! The frontends consume the API through clients generated from the OpenAPI document, so the clients shall be
+//! regenerated whenever the API changes. The binary writes the OpenAPI document, and runs the client generators over
+//! it, when it is launched with the argument `gen-client`:
+//!
+//! ```bash
+//! $ cargo run -- gen-client
+//! $ cargo run -- gen-client typescript
+//! ```
+//!
+//! The document and the clients are published to the directory set by [ClientGenSettings::output_dir]: the document
+//! as `openapi.json`, and every client in a sub-directory named after its generator. Generators are external commands
+//! (see [ClientGenerator]), by default [openapi-generator](https://openapi-generator.tech) for TypeScript and
+//! [progenitor](https://github.com/oxidecomputer/progenitor) for Rust, which shall be installed beforehand.
+
+use crate::configuration::{ClientGenSettings, ClientGenerator};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tracing::{debug, info};
+use utoipa::openapi::OpenApi;
+
+/// Name of the file of the OpenAPI document within the output directory.
+pub const SPEC_FILE: &str = "openapi.json";
+
+/// Replace the placeholders of the command of a generator.
+///
+/// # Description
+///
+/// `{spec}` is replaced by the path of the OpenAPI document, `{out}` by the directory of the client, and `{version}`
+/// by the version of the API.
+pub fn expand_command(command: &[String], spec: &Path, out: &Path) -> Vec<String> {
+    command
+        .iter()
+        .map(|arg| {
+            arg.replace("{spec}", &spec.to_string_lossy())
+                .replace("{out}", &out.to_string_lossy())
+                .replace("{version}", env!("CARGO_PKG_VERSION"))
+        })
+        .collect()
+}
+
+/// Publish the OpenAPI document, and generate the clients.
+///
+/// # Description
+///
+/// `only` restricts the generators that are run to the given names, all of them are run when it is empty. Generators
+/// run in the order of the settings, and the generation stops at the first generator that fails. The directories of
+/// the generated clients are returned.
+pub fn generate_clients(
+    settings: &ClientGenSettings,
+    doc: &OpenApi,
+    only: &[String],
+) -> Result<Vec<PathBuf>, anyhow::Error> {
+    if let Some(name) = only
+        .iter()
+        .find(|name| !settings.generators.iter().any(|g| &&g.name == name))
+    {
+        anyhow::bail!("Unknown client generator: {name}");
+    }
+    let generators = settings
+        .generators
+        .iter()
+        .filter(|g| only.is_empty() || only.contains(&g.name))
+        .collect::<Vec<&ClientGenerator>>();
+
+    let output_dir = Path::new(&settings.output_dir);
+    fs::create_dir_all(output_dir)?;
+    let spec = output_dir.join(SPEC_FILE);
+    fs::write(&spec, serde_json::to_vec_pretty(doc)?)?;
+    info!("OpenAPI document written to {}", spec.display());
+
+    let mut clients = Vec::new();
+    for generator in generators {
+        let out = output_dir.join(&generator.name);
+        fs::create_dir_all(&out)?;
+        let command = expand_command(&generator.command, &spec, &out);
+        let Some((program, args)) = command.split_first() else {
+            anyhow::bail!("The client generator {} has no command", generator.name);
+        };
+
+        debug!(
+            "Running the client generator {}: {command:?}",
+            generator.name
+        );
+        let status = Command::new(program).args(args).status().map_err(|e| {
+            anyhow::anyhow!("Failed to run the client generator {}: {e}", generator.name)
+        })?;
+        if !status.success() {
+            anyhow::bail!("The client generator {} failed ({status})", generator.name);
+        }
+
+        info!("Client {} generated at {}", generator.name, out.display());
+        clients.push(out);
+    }
+
+    Ok(clients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ApiDoc;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use utoipa::OpenApi;
+    use uuid::Uuid;
+
+    fn settings(dir: &Path, command: &[&str]) -> ClientGenSettings {
+        ClientGenSettings {
+            output_dir: dir.to_string_lossy().into_owned(),
+            generators: vec![ClientGenerator {
+                name: "copy".into(),
+                command: command.iter().map(|arg| arg.to_string()).collect(),
+            }],
+        }
+    }
+
+    #[rstest]
+    fn placeholders_are_replaced() {
+        let command = ["gen", "-i", "{spec}", "-o", "{out}", "-v", "{version}"].map(String::from);
+        assert_eq!(
+            expand_command(
+                &command,
+                Path::new("clients/openapi.json"),
+                Path::new("clients/rust")
+            ),
+            [
+                "gen",
+                "-i",
+                "clients/openapi.json",
+                "-o",
+                "clients/rust",
+                "-v",
+                env!("CARGO_PKG_VERSION")
+            ]
+        );
+    }
+
+    #[rstest]
+    fn clients_are_published() {
+        let dir = std::env::temp_dir().join(format!("clients-{}", Uuid::now_v7()));
+        let settings = settings(&dir, &["cp", "{spec}", "{out}"]);
+
+        let clients =
+            generate_clients(&settings, &ApiDoc::openapi(), &[]).expect("Failed to generate");
+        assert_eq!(clients, [dir.join("copy")]);
+        assert!(dir.join(SPEC_FILE).is_file());
+        assert!(dir.join("copy").join(SPEC_FILE).is_file());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[rstest]
+    fn failures_are_reported() {
+        let dir = std::env::temp_dir().join(format!("clients-{}", Uuid::now_v7()));
+
+        let unknown = generate_clients(
+            &settings(&dir, &["true"]),
+            &ApiDoc::openapi(),
+            &["go".into()],
+        );
+        assert!(unknown.is_err());
+        let failed = generate_clients(&settings(&dir, &["false"]), &ApiDoc::openapi(), &[]);
+        assert!(failed.is_err());
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
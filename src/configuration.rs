@@ -32,9 +32,9 @@
 //! The following environment variables are accepted by the application:
 //!
 //! - `RUN_MODE`: `devel`, `prod`. This variable shall take a value that refers to a
-//!    configuration file in the `config` folder. The settings found there will
-//!    overridden the settings found in `base.toml`. When not set, `prod` is considered
-//!    as run mode.
+//!   configuration file in the `config` folder. The settings found there will
+//!   overridden the settings found in `base.toml`. When not set, `prod` is considered
+//!   as run mode.
 //!
 //! Variables defined within configuration files can be overridden using `LACOCTELERA`
 //! prefix. Variables need to be scoped in the same way as they are found in the configuration
@@ -60,6 +60,20 @@
 //! - [ApplicationSettings] for settings that apply to the main application.
 //! - [DataBaseSettings] for settings that apply to the DB connection.
 
+use crate::{
+    authentication::{
+        LockoutPolicy, DEFAULT_AUTH_CACHE_TTL, DEFAULT_LOCK_DURATION, DEFAULT_MAX_AUTH_FAILURES,
+    },
+    domain::{
+        collation::NameCollations, sanitize::SanitizeLevel, screening::Screener, IdScheme,
+        RecipeLicense, RecipeLimits,
+    },
+    utils::{
+        http::{ForwardingHeader, IpThrottle, DEFAULT_MAX_PAGE_SIZE},
+        metrics::DEFAULT_K_ANONYMITY,
+    },
+};
+use chrono::TimeDelta;
 use config::{Config, ConfigError, Environment, File};
 use core::time;
 use secrecy::{ExposeSecret, SecretString};
@@ -67,6 +81,7 @@ use serde_aux::field_attributes::deserialize_number_from_string;
 use serde_derive::Deserialize;
 use sqlx::mysql::{MySqlConnectOptions, MySqlSslMode};
 use std::env;
+use std::thread;
 use std::time::Duration;
 use tracing::level_filters::LevelFilter;
 
@@ -79,8 +94,8 @@ pub struct Settings {
     pub application: ApplicationSettings,
     /// DB Settings.
     pub database: DataBaseSettings,
-    /// email client settings.
-    pub email_client: EmailClientSettings,
+    /// email client settings. Only required when the application is not read-only.
+    pub email_client: Option<EmailClientSettings>,
 }
 
 /// Application's settings.
@@ -91,12 +106,150 @@ pub struct ApplicationSettings {
     pub port: u16,
     /// Host address for the application.
     pub host: String,
+    /// Path of a Unix socket to listen on, rather than [ApplicationSettings::host] and [ApplicationSettings::port],
+    /// i.e. when a reverse proxy forwards the requests through a local socket.
+    #[serde(default)]
+    pub unix_socket: Option<String>,
+    /// Listen on the socket passed by systemd when the application is started using socket activation. It takes
+    /// precedence over the rest of the listening settings.
+    #[serde(default = "default_true")]
+    pub systemd_socket: bool,
     /// Base URL for accessing the application through the network.
     pub base_url: String,
     /// Log settings.
     pub log_settings: LogSettings,
-    /// Number of maximum workers for the Tokio runtime
+    /// Number of maximum workers for the Tokio runtime. Use 0 to start a worker per CPU core, see
+    /// [ApplicationSettings::workers].
     pub max_workers: u16,
+    /// Tuning of the HTTP server.
+    #[serde(default)]
+    pub server: ServerSettings,
+    /// Directory in which the rendered PDF documents of the recipes are cached.
+    #[serde(default = "default_pdf_cache_dir")]
+    pub pdf_cache_dir: String,
+    /// Directory in which the images uploaded by the clients are stored.
+    #[serde(default = "default_media_dir")]
+    pub media_dir: String,
+    /// Directory in which the snapshots of the DB are stored, see [crate::utils::backup].
+    #[serde(default = "default_backup_dir")]
+    pub backup_dir: String,
+    /// Static assets served under `/static`, see [crate::utils::assets].
+    #[serde(default)]
+    pub static_assets: StaticAssetsSettings,
+    /// Branding of the HTML pages served by the application, see [crate::web].
+    #[serde(default)]
+    pub branding: BrandingSettings,
+    /// Base URL of the frontend. The sitemaps point to the resources using this URL.
+    #[serde(default = "default_frontend_url")]
+    pub frontend_url: String,
+    /// Strictness of the sanitization of the text given by the clients: `strip`, `escape` or `basic`.
+    #[serde(default)]
+    pub sanitize_level: SanitizeLevel,
+    /// Limits of the size of the recipes, see [RecipeLimits].
+    #[serde(default)]
+    pub recipe_limits: RecipeLimits,
+    /// License of the recipes that don't declare one, see [RecipeLicense].
+    #[serde(default)]
+    pub default_recipe_license: RecipeLicense,
+    /// Screening of profanity and spam.
+    #[serde(default)]
+    pub screening: ScreeningSettings,
+    /// Per-IP throttling of the anonymous endpoints.
+    #[serde(default)]
+    pub throttling: ThrottlingSettings,
+    /// Load shedding of the non-essential endpoints when a worker is overloaded.
+    #[serde(default)]
+    pub load_shedding: LoadSheddingSettings,
+    /// Reverse proxies (IPs or networks in CIDR notation) whose forwarding headers are trusted to resolve the IP of
+    /// the clients. Leave it empty when the server is exposed straight to the clients.
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+    /// Forwarding header written by the [ApplicationSettings::trusted_proxies]. The other header is ignored.
+    #[serde(default)]
+    pub forwarding_header: ForwardingHeader,
+    /// Lockout of the API clients after consecutive authentication failures.
+    #[serde(default)]
+    pub lockout: LockoutSettings,
+    /// Time (seconds) that the granted access checks are cached in memory. Use 0 to disable the cache.
+    #[serde(default = "default_auth_cache_ttl_secs")]
+    pub auth_cache_ttl_secs: u64,
+    /// Disable all the endpoints that modify the DB, i.e. for mirrors running against a replica of the DB. See
+    /// [crate::utils::http::ReadOnly].
+    #[serde(default)]
+    pub read_only: bool,
+    /// Allow several author profiles to register the same email. Emails are unique otherwise, see
+    /// [crate::routes::author::post::post_author].
+    #[serde(default)]
+    pub allow_duplicate_author_emails: bool,
+    /// Reject the deletions that don't include an `If-Match` header, see [crate::utils::http::Preconditions].
+    #[serde(default)]
+    pub require_if_match: bool,
+    /// Scheme of the IDs assigned to the new resources: `uuidv7` or `sequential` (only meant for tests). See
+    /// [crate::domain::id_generator].
+    #[serde(default)]
+    pub id_scheme: IdScheme,
+    /// Email digests of new recipes, see [crate::routes::me::digests].
+    #[serde(default)]
+    pub digests: DigestSettings,
+    /// Notifications of the clients, see [crate::routes::me::notifications].
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    /// History of the health of the server, see [crate::routes::status].
+    #[serde(default)]
+    pub health_history: HealthHistorySettings,
+    /// Anonymous usage analytics, see [crate::utils::metrics::UsageAnalytics].
+    #[serde(default)]
+    pub analytics: AnalyticsSettings,
+    /// Delivery of the domain events to external sinks, see [crate::jobs::dispatch_events].
+    #[serde(default)]
+    pub outbox: OutboxSettings,
+    /// Policy of the `Cache-Control` headers of the responses.
+    #[serde(default)]
+    pub cache_control: CacheControlSettings,
+    /// Collations used to sort the names, per language. See [crate::domain::collation].
+    #[serde(default)]
+    pub name_collations: NameCollations,
+    /// Maximum amount of results per page of the paginated searches, see [crate::utils::http::Page].
+    #[serde(default = "default_max_page_size")]
+    pub max_page_size: u32,
+    /// Generation of the typed clients of the API, see [crate::client_gen].
+    #[serde(default)]
+    pub client_gen: ClientGenSettings,
+}
+
+impl ApplicationSettings {
+    /// Amount of workers of the server: [ApplicationSettings::max_workers], or a worker per CPU core when it is set to
+    /// 0.
+    pub fn workers(&self) -> usize {
+        match self.max_workers {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            max_workers => max_workers as usize,
+        }
+    }
+}
+
+fn default_pdf_cache_dir() -> String {
+    "pdf_cache".into()
+}
+
+fn default_max_page_size() -> u32 {
+    DEFAULT_MAX_PAGE_SIZE
+}
+
+fn default_auth_cache_ttl_secs() -> u64 {
+    DEFAULT_AUTH_CACHE_TTL.as_secs()
+}
+
+fn default_media_dir() -> String {
+    "media".into()
+}
+
+fn default_backup_dir() -> String {
+    "backups".into()
+}
+
+fn default_frontend_url() -> String {
+    "http://localhost:8080".into()
 }
 
 /// Data Base connection settings.
@@ -153,7 +306,851 @@ pub struct LogSettings {
     pub console_tracing_level: Option<String>,
 }
 
-/// Settings for the email client [mailjet_client](https://crates.io/crates/mailjet_client)
+/// Settings for the screening of profanity and spam of the submitted content.
+///
+/// # Description
+///
+/// The screening is disabled by default. When it is enabled, content that matches some of the `blocked_words`, or
+/// includes more than `max_links` links, is sent to the moderation queue rather than published straight away. See
+/// [crate::domain::screening] for the details.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScreeningSettings {
+    /// Enable the screening.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Maximum amount of links allowed within a piece of content.
+    #[serde(default = "default_max_links")]
+    pub max_links: usize,
+    /// Wordlist of forbidden words (not case sensitive).
+    #[serde(default)]
+    pub blocked_words: Vec<String>,
+}
+
+impl Default for ScreeningSettings {
+    fn default() -> Self {
+        ScreeningSettings {
+            enabled: false,
+            max_links: default_max_links(),
+            blocked_words: Vec::new(),
+        }
+    }
+}
+
+impl ScreeningSettings {
+    /// Build the [Screener] described by these settings.
+    pub fn screener(&self) -> Screener {
+        if self.enabled {
+            Screener::new(&self.blocked_words, self.max_links)
+        } else {
+            Screener::disabled()
+        }
+    }
+}
+
+fn default_max_links() -> usize {
+    2
+}
+
+/// Settings for the per-IP throttling of the anonymous endpoints.
+///
+/// # Description
+///
+/// Clients that issue more than the allowed amount of requests within `window_secs` are banned for `ban_secs`. The ban
+/// time doubles with every new offence, up to `max_ban_secs`. The token request endpoints have a tighter limit than
+/// the search endpoints. See [crate::utils::http::IpThrottle] for the details.
+///
+/// When the server runs behind a reverse proxy, the proxy shall be listed in
+/// [ApplicationSettings::trusted_proxies], otherwise all the clients would share the IP of the proxy.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ThrottlingSettings {
+    /// Enable the throttling.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Length of the window in which requests are counted (seconds).
+    #[serde(default = "default_throttle_window")]
+    pub window_secs: u64,
+    /// Maximum amount of requests to the search endpoints within a window.
+    #[serde(default = "default_search_max_requests")]
+    pub search_max_requests: u32,
+    /// Maximum amount of requests to the token endpoints within a window.
+    #[serde(default = "default_token_max_requests")]
+    pub token_max_requests: u32,
+    /// Ban time applied to the first offence (seconds).
+    #[serde(default = "default_ban_secs")]
+    pub ban_secs: u64,
+    /// Maximum ban time (seconds).
+    #[serde(default = "default_max_ban_secs")]
+    pub max_ban_secs: u64,
+}
+
+impl Default for ThrottlingSettings {
+    fn default() -> Self {
+        ThrottlingSettings {
+            enabled: true,
+            window_secs: default_throttle_window(),
+            search_max_requests: default_search_max_requests(),
+            token_max_requests: default_token_max_requests(),
+            ban_secs: default_ban_secs(),
+            max_ban_secs: default_max_ban_secs(),
+        }
+    }
+}
+
+impl ThrottlingSettings {
+    /// Build the [IpThrottle] for the search endpoints.
+    pub fn search_throttle(&self) -> IpThrottle {
+        self.throttle(self.search_max_requests)
+    }
+
+    /// Build the [IpThrottle] for the token endpoints.
+    pub fn token_throttle(&self) -> IpThrottle {
+        self.throttle(self.token_max_requests)
+    }
+
+    fn throttle(&self, max_requests: u32) -> IpThrottle {
+        if self.enabled {
+            IpThrottle::new(
+                max_requests,
+                time::Duration::from_secs(self.window_secs),
+                time::Duration::from_secs(self.ban_secs),
+                time::Duration::from_secs(self.max_ban_secs),
+            )
+        } else {
+            IpThrottle::disabled()
+        }
+    }
+}
+
+/// Rule of the cache policy: responses to the paths that match `path` get `cache_control` as `Cache-Control` header.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct CachePolicyRule {
+    /// Pattern of the paths, relative to the URL of the API, i.e. `/recipe/*`.
+    pub path: String,
+    /// Value of the header, i.e. `public, max-age=300`.
+    pub cache_control: String,
+}
+
+/// Settings for the `Cache-Control` headers of the responses of the API.
+///
+/// # Description
+///
+/// Rules are checked in order, and the first rule that matches the path of a request applies. By default, the
+/// public resources can be cached for some minutes. See [crate::utils::http::CachePolicy] for the details.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CacheControlSettings {
+    /// Set the `Cache-Control` headers following the rules.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_cache_rules")]
+    pub rules: Vec<CachePolicyRule>,
+}
+
+impl Default for CacheControlSettings {
+    fn default() -> Self {
+        CacheControlSettings {
+            enabled: true,
+            rules: default_cache_rules(),
+        }
+    }
+}
+
+fn default_cache_rules() -> Vec<CachePolicyRule> {
+    [
+        ("/recipe", "public, max-age=60"),
+        ("/recipe/*", "public, max-age=300"),
+        ("/ingredient", "public, max-age=300"),
+        ("/ingredient/**", "public, max-age=300"),
+        ("/author", "public, max-age=60"),
+        ("/author/*", "public, max-age=300"),
+        ("/me/**", "private, no-store"),
+        ("/admin/**", "private, no-store"),
+    ]
+    .into_iter()
+    .map(|(path, cache_control)| CachePolicyRule {
+        path: path.into(),
+        cache_control: cache_control.into(),
+    })
+    .collect()
+}
+
+/// Settings for the load shedding of the non-essential endpoints.
+///
+/// # Description
+///
+/// When a worker serves more than `max_in_flight` requests at the same time, the requests to retrieve and search the
+/// resources are answered with a code **503**, and a *Retry-After* header set to `retry_after_secs`. The health check
+/// and the requests that modify the content are always served. See [crate::utils::http::LoadShed] for the details.
+///
+/// The limit applies to every worker ([ApplicationSettings::max_workers]), and shall be set considering the size of
+/// the DB pool ([DataBaseSettings::max_connections]).
+#[derive(Clone, Debug, Deserialize)]
+pub struct LoadSheddingSettings {
+    /// Enable the load shedding.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Maximum amount of requests served at the same time by a worker before shedding.
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+    /// Time that the clients are asked to wait before retrying (seconds).
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
+}
+
+impl Default for LoadSheddingSettings {
+    fn default() -> Self {
+        LoadSheddingSettings {
+            enabled: true,
+            max_in_flight: default_max_in_flight(),
+            retry_after_secs: default_retry_after_secs(),
+        }
+    }
+}
+
+impl LoadSheddingSettings {
+    /// Amount of requests served by a worker that triggers the shedding, or `None` when it is disabled.
+    pub fn limit(&self) -> Option<usize> {
+        self.enabled.then_some(self.max_in_flight)
+    }
+
+    pub fn retry_after(&self) -> Duration {
+        Duration::from_secs(self.retry_after_secs)
+    }
+}
+
+fn default_max_in_flight() -> usize {
+    64
+}
+
+fn default_retry_after_secs() -> u64 {
+    5
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_throttle_window() -> u64 {
+    60
+}
+
+fn default_search_max_requests() -> u32 {
+    120
+}
+
+fn default_token_max_requests() -> u32 {
+    5
+}
+
+fn default_ban_secs() -> u64 {
+    60
+}
+
+fn default_max_ban_secs() -> u64 {
+    86400
+}
+
+/// Settings for the lockout of the API clients.
+///
+/// # Description
+///
+/// Clients get locked for `lock_secs` after `max_failures` consecutive access checks with a wrong token. See
+/// [crate::authentication::LockoutPolicy] for the details.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LockoutSettings {
+    /// Enable the lockout.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Consecutive failures that lock a client.
+    #[serde(default = "default_max_auth_failures")]
+    pub max_failures: u32,
+    /// Time that a client stays locked (seconds).
+    #[serde(default = "default_lock_secs")]
+    pub lock_secs: u64,
+}
+
+impl Default for LockoutSettings {
+    fn default() -> Self {
+        LockoutSettings {
+            enabled: true,
+            max_failures: default_max_auth_failures(),
+            lock_secs: default_lock_secs(),
+        }
+    }
+}
+
+impl LockoutSettings {
+    /// Build the [LockoutPolicy] of the application.
+    pub fn policy(&self) -> LockoutPolicy {
+        if self.enabled {
+            LockoutPolicy {
+                max_failures: self.max_failures,
+                lock_duration: time::Duration::from_secs(self.lock_secs),
+            }
+        } else {
+            LockoutPolicy::disabled()
+        }
+    }
+}
+
+fn default_max_auth_failures() -> u32 {
+    DEFAULT_MAX_AUTH_FAILURES
+}
+
+fn default_lock_secs() -> u64 {
+    DEFAULT_LOCK_DURATION.as_secs()
+}
+
+/// Settings for the job that sends the email digests of new recipes.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DigestSettings {
+    /// Enable the job. Digests are not sent when the application has no email client.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Time between runs of the job (seconds). Due digests are sent on the next run.
+    #[serde(default = "default_digest_interval_secs")]
+    pub interval_secs: u64,
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        DigestSettings {
+            enabled: true,
+            interval_secs: default_digest_interval_secs(),
+        }
+    }
+}
+
+impl DigestSettings {
+    /// Time between runs of the job.
+    pub fn interval(&self) -> time::Duration {
+        time::Duration::from_secs(self.interval_secs.max(1))
+    }
+}
+
+fn default_digest_interval_secs() -> u64 {
+    3600
+}
+
+/// Settings for the job that produces the notifications of the clients, and mirrors them to email.
+#[derive(Clone, Debug, Deserialize)]
+pub struct NotificationSettings {
+    /// Enable the job. Notifications are not mirrored to email when the application has no email client.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Time between runs of the job (seconds).
+    #[serde(default = "default_notification_interval_secs")]
+    pub interval_secs: u64,
+    /// Clients are notified of their API keys that expire within this amount of days.
+    #[serde(default = "default_expiry_notice_days")]
+    pub expiry_notice_days: u32,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            enabled: true,
+            interval_secs: default_notification_interval_secs(),
+            expiry_notice_days: default_expiry_notice_days(),
+        }
+    }
+}
+
+impl NotificationSettings {
+    /// Time between runs of the job.
+    pub fn interval(&self) -> time::Duration {
+        time::Duration::from_secs(self.interval_secs.max(1))
+    }
+
+    /// Time before the expiry of an API key when its client gets notified.
+    pub fn expiry_notice(&self) -> TimeDelta {
+        TimeDelta::days(self.expiry_notice_days.into())
+    }
+}
+
+fn default_notification_interval_secs() -> u64 {
+    3600
+}
+
+fn default_expiry_notice_days() -> u32 {
+    7
+}
+
+/// Settings for the job that records the history of the health of the server (see [crate::routes::status]).
+#[derive(Clone, Debug, Deserialize)]
+pub struct HealthHistorySettings {
+    /// Enable the job.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Time between snapshots (seconds).
+    #[serde(default = "default_health_history_interval_secs")]
+    pub interval_secs: u64,
+    /// Snapshots older than this amount of days are deleted.
+    #[serde(default = "default_health_history_retention_days")]
+    pub retention_days: u32,
+}
+
+impl Default for HealthHistorySettings {
+    fn default() -> Self {
+        HealthHistorySettings {
+            enabled: true,
+            interval_secs: default_health_history_interval_secs(),
+            retention_days: default_health_history_retention_days(),
+        }
+    }
+}
+
+impl HealthHistorySettings {
+    /// Time between snapshots.
+    pub fn interval(&self) -> time::Duration {
+        time::Duration::from_secs(self.interval_secs.max(1))
+    }
+
+    /// Time that the snapshots are kept.
+    pub fn retention(&self) -> TimeDelta {
+        TimeDelta::days(self.retention_days.max(1).into())
+    }
+}
+
+fn default_health_history_interval_secs() -> u64 {
+    300
+}
+
+fn default_health_history_retention_days() -> u32 {
+    30
+}
+
+/// Settings of the anonymous usage analytics (see [crate::utils::metrics::UsageAnalytics]).
+#[derive(Clone, Debug, Deserialize)]
+pub struct AnalyticsSettings {
+    /// Enable the analytics. Nothing is recorded when disabled.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Time between the runs of the job that stores the usage counted in memory (seconds).
+    #[serde(default = "default_analytics_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+    /// Daily totals older than this amount of days are deleted.
+    #[serde(default = "default_analytics_retention_days")]
+    pub retention_days: u32,
+    /// Search terms used less than this amount of times are not reported. Values lower than 2 are raised to 2.
+    #[serde(default = "default_k_anonymity")]
+    pub k_anonymity: u32,
+}
+
+impl Default for AnalyticsSettings {
+    fn default() -> Self {
+        AnalyticsSettings {
+            enabled: false,
+            flush_interval_secs: default_analytics_flush_interval_secs(),
+            retention_days: default_analytics_retention_days(),
+            k_anonymity: default_k_anonymity(),
+        }
+    }
+}
+
+impl AnalyticsSettings {
+    /// Time between the runs of the job.
+    pub fn flush_interval(&self) -> time::Duration {
+        time::Duration::from_secs(self.flush_interval_secs.max(1))
+    }
+
+    /// Time that the daily totals are kept.
+    pub fn retention(&self) -> TimeDelta {
+        TimeDelta::days(self.retention_days.max(1).into())
+    }
+}
+
+fn default_analytics_flush_interval_secs() -> u64 {
+    600
+}
+
+fn default_analytics_retention_days() -> u32 {
+    90
+}
+
+fn default_k_anonymity() -> u32 {
+    DEFAULT_K_ANONYMITY
+}
+
+/// Webhook that receives the domain events.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WebhookSettings {
+    /// Name of the webhook. It identifies the position of the webhook within the event log, so it shall not change.
+    pub name: String,
+    /// URL that receives the events via `POST` requests.
+    pub url: String,
+}
+
+/// Settings for the job that publishes the domain events to the sinks (the outbox).
+#[derive(Clone, Debug, Deserialize)]
+pub struct OutboxSettings {
+    /// Enable the job. The job is not scheduled when no sink is configured.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Time between runs of the job (seconds).
+    #[serde(default = "default_outbox_interval_secs")]
+    pub interval_secs: u64,
+    /// Maximum amount of events published to each sink per run.
+    #[serde(default = "default_outbox_batch_size")]
+    pub batch_size: u32,
+    /// Consecutive failures after which an event is dead-lettered and skipped.
+    #[serde(default = "default_outbox_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookSettings>,
+    /// Message bus that receives the events, i.e. for home automation or bar displays.
+    #[serde(default)]
+    pub message_bus: Option<MessageBusSettings>,
+    /// Endpoint that purges the caches of the frontends when the public content changes.
+    #[serde(default)]
+    pub purge: Option<PurgeSettings>,
+}
+
+impl Default for OutboxSettings {
+    fn default() -> Self {
+        OutboxSettings {
+            enabled: false,
+            interval_secs: default_outbox_interval_secs(),
+            batch_size: default_outbox_batch_size(),
+            max_attempts: default_outbox_max_attempts(),
+            webhooks: Vec::new(),
+            message_bus: None,
+            purge: None,
+        }
+    }
+}
+
+impl OutboxSettings {
+    /// Time between runs of the job.
+    pub fn interval(&self) -> time::Duration {
+        time::Duration::from_secs(self.interval_secs.max(1))
+    }
+}
+
+/// Protocols of the message buses. Each of them needs the feature of the same name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageBusProtocol {
+    Mqtt,
+    Nats,
+}
+
+impl MessageBusProtocol {
+    /// Port used by the brokers of the protocol by default.
+    pub fn default_port(&self) -> u16 {
+        match self {
+            MessageBusProtocol::Mqtt => 1883,
+            MessageBusProtocol::Nats => 4222,
+        }
+    }
+}
+
+/// Settings of the endpoint that purges the caches of the frontends (see [crate::utils::events::PurgeSink]).
+#[derive(Clone, Debug, Deserialize)]
+pub struct PurgeSettings {
+    /// URL that receives the purge requests via `POST` requests.
+    pub url: String,
+    /// Name of the header that authenticates the requests.
+    #[serde(default = "default_purge_auth_header")]
+    pub auth_header: String,
+    /// Value of the authentication header, i.e. `Bearer <token>`. Requests are not authenticated when missing.
+    pub auth_value: Option<SecretString>,
+}
+
+fn default_purge_auth_header() -> String {
+    "Authorization".into()
+}
+
+/// Settings of the message bus that receives the domain events (see [crate::utils::events::event_topic]).
+#[derive(Clone, Debug, Deserialize)]
+pub struct MessageBusSettings {
+    pub protocol: MessageBusProtocol,
+    /// Host of the broker.
+    pub host: String,
+    /// Port of the broker. The default port of the protocol is used when missing.
+    pub port: Option<u16>,
+    /// Prefix of the topics (MQTT) or the subjects (NATS) of the events.
+    #[serde(default = "default_topic_prefix")]
+    pub topic_prefix: String,
+    /// ID of the client (MQTT) or name of the connection (NATS).
+    #[serde(default = "default_bus_client_id")]
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<SecretString>,
+    /// Authentication token (NATS only).
+    pub token: Option<SecretString>,
+}
+
+impl MessageBusSettings {
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(self.protocol.default_port())
+    }
+}
+
+fn default_topic_prefix() -> String {
+    "lacoctelera".into()
+}
+
+fn default_bus_client_id() -> String {
+    "lacoctelera-api".into()
+}
+
+fn default_outbox_interval_secs() -> u64 {
+    10
+}
+
+fn default_outbox_batch_size() -> u32 {
+    100
+}
+
+fn default_outbox_max_attempts() -> u32 {
+    5
+}
+
+/// Settings of the generation of the typed clients of the API (`gen-client`).
+#[derive(Clone, Debug, Deserialize)]
+pub struct ClientGenSettings {
+    /// Directory where the OpenAPI document and the clients are published.
+    #[serde(default = "default_client_gen_dir")]
+    pub output_dir: String,
+    /// Generators of the clients, run in order.
+    #[serde(default = "default_client_generators")]
+    pub generators: Vec<ClientGenerator>,
+}
+
+impl Default for ClientGenSettings {
+    fn default() -> Self {
+        ClientGenSettings {
+            output_dir: default_client_gen_dir(),
+            generators: default_client_generators(),
+        }
+    }
+}
+
+/// External command that generates a client from the OpenAPI document.
+///
+/// # Description
+///
+/// The command is given as a list of arguments, the first one being the program. The placeholders `{spec}`, `{out}`
+/// and `{version}` are replaced by the path of the OpenAPI document, the directory of the client, and the version of
+/// the API (see [crate::client_gen::expand_command]).
+#[derive(Clone, Debug, Deserialize)]
+pub struct ClientGenerator {
+    /// Name of the generator, also used as the name of the directory of the client.
+    pub name: String,
+    pub command: Vec<String>,
+}
+
+fn default_client_gen_dir() -> String {
+    "clients".into()
+}
+
+fn default_client_generators() -> Vec<ClientGenerator> {
+    let generator = |name: &str, command: &[&str]| ClientGenerator {
+        name: name.into(),
+        command: command.iter().map(|arg| arg.to_string()).collect(),
+    };
+
+    vec![
+        generator(
+            "typescript",
+            &[
+                "openapi-generator-cli",
+                "generate",
+                "-i",
+                "{spec}",
+                "-g",
+                "typescript-fetch",
+                "-o",
+                "{out}",
+            ],
+        ),
+        generator(
+            "rust",
+            &[
+                "cargo",
+                "progenitor",
+                "-i",
+                "{spec}",
+                "-o",
+                "{out}",
+                "-n",
+                "lacoctelera-client",
+                "-v",
+                "{version}",
+            ],
+        ),
+    ]
+}
+
+/// Settings of the static assets served by the application.
+#[derive(Clone, Debug, Deserialize)]
+pub struct StaticAssetsSettings {
+    /// Directory from which the assets are loaded when the application starts.
+    #[serde(default = "default_static_dir")]
+    pub dir: String,
+    /// Serve a listing of the assets at `/static/`.
+    #[serde(default)]
+    pub listing: bool,
+    /// Extensions of the files that are served. Other files of the directory are ignored.
+    #[serde(default = "default_static_extensions")]
+    pub extensions: Vec<String>,
+}
+
+impl Default for StaticAssetsSettings {
+    fn default() -> Self {
+        StaticAssetsSettings {
+            dir: default_static_dir(),
+            listing: false,
+            extensions: default_static_extensions(),
+        }
+    }
+}
+
+fn default_static_dir() -> String {
+    "static/resources".into()
+}
+
+fn default_static_extensions() -> Vec<String> {
+    ["css", "js", "png", "svg", "ico", "webp", "woff2"]
+        .map(String::from)
+        .to_vec()
+}
+
+/// Branding of the HTML pages of the token request flow.
+///
+/// # Description
+///
+/// Colors are given using the hexadecimal notation of CSS, i.e. `#645cff`. Invalid colors are replaced by the default
+/// ones when the pages are rendered.
+#[derive(Clone, Debug, Deserialize)]
+pub struct BrandingSettings {
+    /// Name of the instance, shown as the title of the pages.
+    #[serde(default = "default_branding_name")]
+    pub name: String,
+    /// Main color of the pages, used by the buttons and the titles.
+    #[serde(default = "default_primary_color")]
+    pub primary_color: String,
+    /// Color of the buttons when the pointer is over them.
+    #[serde(default = "default_accent_color")]
+    pub accent_color: String,
+    /// Background color of the pages.
+    #[serde(default = "default_background_color")]
+    pub background_color: String,
+    /// Email address shown at the bottom of the pages to contact the administrator of the instance.
+    #[serde(default)]
+    pub contact_email: Option<String>,
+}
+
+impl Default for BrandingSettings {
+    fn default() -> Self {
+        BrandingSettings {
+            name: default_branding_name(),
+            primary_color: default_primary_color(),
+            accent_color: default_accent_color(),
+            background_color: default_background_color(),
+            contact_email: None,
+        }
+    }
+}
+
+fn default_branding_name() -> String {
+    "La Coctelera API".into()
+}
+
+fn default_primary_color() -> String {
+    "#645cff".into()
+}
+
+fn default_accent_color() -> String {
+    "#3c3799".into()
+}
+
+fn default_background_color() -> String {
+    "#f8fafc".into()
+}
+
+/// Tuning of the HTTP server.
+///
+/// # Description
+///
+/// Timeouts set to 0 are disabled. The defaults match the defaults of the HTTP server, except for the timeout of the
+/// disconnections, which is disabled by the HTTP server.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ServerSettings {
+    /// Time that idle connections are kept open waiting for new requests (seconds). Use 0 to disable keep-alive.
+    #[serde(default = "default_keep_alive_secs")]
+    pub keep_alive_secs: u64,
+    /// Time given to the clients to send the headers of a request (milliseconds).
+    #[serde(default = "default_client_request_timeout_ms")]
+    pub client_request_timeout_ms: u64,
+    /// Time given to the clients to acknowledge the shutdown of a connection (milliseconds).
+    #[serde(default = "default_client_disconnect_timeout_ms")]
+    pub client_disconnect_timeout_ms: u64,
+    /// Maximum amount of connections served at the same time by a worker.
+    #[serde(default = "default_server_max_connections")]
+    pub max_connections: usize,
+    /// Maximum amount of connections waiting to be accepted by the server.
+    #[serde(default = "default_backlog")]
+    pub backlog: u32,
+    /// Time given to the workers to finish the ongoing requests when the server shuts down (seconds).
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        ServerSettings {
+            keep_alive_secs: default_keep_alive_secs(),
+            client_request_timeout_ms: default_client_request_timeout_ms(),
+            client_disconnect_timeout_ms: default_client_disconnect_timeout_ms(),
+            max_connections: default_server_max_connections(),
+            backlog: default_backlog(),
+            shutdown_timeout_secs: default_shutdown_timeout_secs(),
+        }
+    }
+}
+
+impl ServerSettings {
+    /// Time that idle connections are kept open, or `None` when keep-alive is disabled.
+    pub fn keep_alive(&self) -> Option<time::Duration> {
+        (self.keep_alive_secs > 0).then(|| time::Duration::from_secs(self.keep_alive_secs))
+    }
+
+    pub fn client_request_timeout(&self) -> time::Duration {
+        time::Duration::from_millis(self.client_request_timeout_ms)
+    }
+
+    pub fn client_disconnect_timeout(&self) -> time::Duration {
+        time::Duration::from_millis(self.client_disconnect_timeout_ms)
+    }
+}
+
+fn default_keep_alive_secs() -> u64 {
+    5
+}
+
+fn default_client_request_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_client_disconnect_timeout_ms() -> u64 {
+    1000
+}
+
+fn default_server_max_connections() -> usize {
+    25_000
+}
+
+fn default_backlog() -> u32 {
+    2048
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+/// Settings for the email client of Mailjet, see [crate::utils::mailing::MailjetSender]. Only the version 3.1 of its
+/// API is supported (`target_api`).
 #[derive(Clone, Debug, Deserialize)]
 pub struct EmailClientSettings {
     pub api_user: SecretString,
@@ -1,19 +1,22 @@
 //! Data objects related to the authentication logic.
 
-use crate::{domain::ID_LENGTH, DataDomainError};
+use crate::domain::{DataDomainError, ID_LENGTH};
 use core::fmt;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
-use utoipa::IntoParams;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Validate, IntoParams)]
+#[derive(Serialize, Deserialize, Debug, Clone, Validate, IntoParams, ToSchema)]
 pub struct TokenRequestData {
+    #[schema(example = "Jane")]
     name: Option<String>,
     #[validate(email)]
+    #[schema(example = "jane@mail.com")]
     email: String,
     #[validate(length(min = 20, max = 400))]
+    #[schema(example = "I'd like to add my recipes to the catalog of my bar.")]
     explanation: String,
 }
 
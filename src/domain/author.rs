@@ -6,7 +6,13 @@
 
 //! Data objects related to Authors.
 
-use crate::{domain::DataDomainError, validate_id};
+use crate::{
+    domain::{
+        sanitize::{deserialize_optional_text, sanitize},
+        DataDomainError, EmailAddress, ResourceId, ResourceName, WebsiteUrl,
+    },
+    validate_id,
+};
 use serde::{Deserialize, Serialize};
 use std::cmp::PartialEq;
 use utoipa::{IntoParams, ToSchema};
@@ -24,16 +30,14 @@ use validator::Validate;
 /// endpoint. Mandatory fields are defined in the description of each method of the endpoint.
 ///
 /// Some restrictions over the `struct`'s members:
-/// - [Author::id] must contain a valid [Uuid]. Strings are parsed to [Uuid]. The expected format is a 128-bit value,
-///   formatted as a hex string in five groups. The first 4 groups are randomly generated, and the fifth comes from
-///   a timestamp. However, clients can freely generate this ID using other combinations as long as the length and basic
-///   format rules are honored.
-/// - [Author::name] and [Author::surname] shall have a minimum length of 2 and a maximum of 40 characters. These
-///   fields are allowed to repeat in the DB. Authors are identified in the DB by [Author::id]. Usernames are not
-///   required.
-/// - [Author::email] is validated against the HTML5 regex.
-/// - [Author::description] can't exceed 255 characters length.
-/// - [Author::website] must contain an url format (`http://...` or `https://...`).
+/// - [Author::id] must contain a valid [Uuid]. Strings are parsed to [Uuid] following the rules of [ResourceId]. The
+///   expected format is a 128-bit value, formatted as a hex string in five groups. IDs are always assigned by the
+///   backend when a new author is registered, clients of the API can't choose them.
+/// - [Author::name] and [Author::surname] are a [ResourceName]. These fields are allowed to repeat in the DB. Authors
+///   are identified in the DB by [Author::id]. Usernames are not required.
+/// - [Author::email] is an [EmailAddress].
+/// - [Author::description] can't exceed 255 characters length. It is sanitized (see [crate::domain::sanitize]).
+/// - [Author::website] is a [WebsiteUrl].
 ///
 /// Authors are given the choice to share or keep private their profiles. Activate [Author::shareable] to allow
 /// sharing the author's profile to the main public. Private profiles are protected from non privileged clients of the
@@ -48,18 +52,15 @@ pub struct Author {
     #[validate(custom(function = "validate_id"))]
     #[schema(value_type = String, example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
     id: Option<Uuid>,
-    #[validate(length(min = 2), length(max = 40))]
-    name: Option<String>,
-    #[validate(length(min = 2), length(max = 40))]
-    surname: Option<String>,
-    #[validate(email)]
-    email: Option<String>,
+    name: Option<ResourceName>,
+    surname: Option<ResourceName>,
+    email: Option<EmailAddress>,
     /// Decide whether an author profile can be shared to the public or not.
     pub shareable: Option<bool>,
     #[validate(length(max = 255))]
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
     description: Option<String>,
-    #[validate(url)]
-    website: Option<String>,
+    website: Option<WebsiteUrl>,
     social_profiles: Option<Vec<SocialProfile>>,
 }
 
@@ -146,23 +147,19 @@ impl Author {
         website: Option<String>,
         social_profiles: Option<&[SocialProfile]>,
     ) -> Result<Self, DataDomainError> {
-        let id = if id.is_some() {
-            match Uuid::parse_str(&id.unwrap()) {
-                Ok(id) => Some(id),
-                Err(_) => return Err(DataDomainError::InvalidId),
-            }
-        } else {
-            None
+        let id = match id {
+            Some(id) => Some(ResourceId::try_from(id)?.into()),
+            None => None,
         };
 
         let author = Author {
             id,
-            name,
-            surname,
-            email,
+            name: name.map(ResourceName::try_from).transpose()?,
+            surname: surname.map(ResourceName::try_from).transpose()?,
+            email: email.map(EmailAddress::try_from).transpose()?,
             shareable,
-            description,
-            website,
+            description: description.as_deref().map(sanitize),
+            website: website.map(WebsiteUrl::try_from).transpose()?,
             social_profiles: social_profiles.map(Vec::from),
         };
 
@@ -177,15 +174,15 @@ impl Author {
     }
 
     pub fn name(&self) -> Option<&str> {
-        self.name.as_deref()
+        self.name.as_ref().map(ResourceName::as_str)
     }
 
     pub fn surname(&self) -> Option<&str> {
-        self.surname.as_deref()
+        self.surname.as_ref().map(ResourceName::as_str)
     }
 
     pub fn email(&self) -> Option<&str> {
-        self.email.as_deref()
+        self.email.as_ref().map(EmailAddress::as_str)
     }
 
     pub fn shareable(&self) -> bool {
@@ -197,7 +194,7 @@ impl Author {
     }
 
     pub fn website(&self) -> Option<&str> {
-        self.website.as_deref()
+        self.website.as_ref().map(WebsiteUrl::as_str)
     }
 
     pub fn social_profiles(&self) -> Option<&[SocialProfile]> {
@@ -230,20 +227,20 @@ impl Author {
         if update.id().is_some() {
             self.id = Some(Uuid::parse_str(&update.id().unwrap()).unwrap());
         }
-        if update.name().is_some() {
-            self.name = Some(update.name().unwrap().into());
+        if update.name.is_some() {
+            self.name = update.name.clone();
         }
-        if update.surname().is_some() {
-            self.surname = Some(update.surname().unwrap().into());
+        if update.surname.is_some() {
+            self.surname = update.surname.clone();
         }
-        if update.email().is_some() {
-            self.email = Some(update.email().unwrap().into());
+        if update.email.is_some() {
+            self.email = update.email.clone();
         }
         if update.description().is_some() {
             self.description = Some(update.description().unwrap().into());
         }
-        if update.website().is_some() {
-            self.website = Some(update.website().unwrap().into());
+        if update.website.is_some() {
+            self.website = update.website.clone();
         }
         if update.social_profiles().is_some() {
             self.social_profiles = Some(Vec::from(update.social_profiles().unwrap()));
@@ -325,6 +322,38 @@ impl AuthorBuilder {
     }
 }
 
+/// Policy of the emails of the authors.
+///
+/// # Description
+///
+/// Emails can't be shared by several author profiles unless the settings allow it
+/// (`application.allow_duplicate_author_emails`). The policy is shared with the handlers using `web::Data`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuthorEmailPolicy {
+    unique: bool,
+}
+
+impl Default for AuthorEmailPolicy {
+    fn default() -> Self {
+        AuthorEmailPolicy { unique: true }
+    }
+}
+
+impl AuthorEmailPolicy {
+    pub fn new(unique: bool) -> Self {
+        if !unique {
+            tracing::warn!("Several authors are allowed to register the same email");
+        }
+
+        AuthorEmailPolicy { unique }
+    }
+
+    /// Check whether the email of an author can't be shared by several author profiles.
+    pub fn unique_emails(&self) -> bool {
+        self.unique
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,228 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Heuristics to suggest the difficulty of a recipe.
+//!
+//! # Description
+//!
+//! The category of a recipe ([RecipeCategory]) reflects how hard is to prepare it. Authors choose it, but it is easy
+//! to miss when a recipe needs some uncommon technique. This module suggests a category by scoring a few features of
+//! the recipe:
+//! - The amount of ingredients.
+//! - The techniques detected in the preparation steps, i.e. muddling, layering or fat-washing.
+//! - The equipment needed. Basic bar tools don't add anything to the score, and repeated equipment is counted once.
+//! - The estimated preparation time.
+//!
+//! The score is mapped to a category: up to 2 points is [RecipeCategory::Easy], up to 5 points is
+//! [RecipeCategory::Medium], up to 8 points is [RecipeCategory::Advanced], and [RecipeCategory::Pro] above that.
+
+use crate::domain::{Equipment, RecipeCategory};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use utoipa::ToSchema;
+
+/// Techniques detected in the steps of a recipe, along with the keywords that reveal them and their score.
+const TECHNIQUES: &[(&str, &[&str], u32)] = &[
+    ("muddling", &["muddle"], 1),
+    ("fine straining", &["double strain", "fine strain"], 1),
+    ("blending", &["blend"], 1),
+    ("layering", &["layer", "float"], 2),
+    ("foaming", &["foam", "dry shake"], 2),
+    ("flaming", &["flame", "torch", "ignite"], 2),
+    ("smoking", &["smoke"], 3),
+    ("infusing", &["infuse", "infusion"], 3),
+    ("fat-washing", &["fat-wash", "fat wash"], 3),
+    ("clarifying", &["clarif"], 3),
+    ("sous vide", &["sous vide", "sous-vide"], 3),
+];
+
+/// Features of a recipe that are relevant to guess its difficulty.
+#[derive(Debug, Clone, Default)]
+pub struct RecipeFeatures<'a> {
+    pub ingredients: usize,
+    pub steps: &'a [String],
+    pub equipment: &'a [Equipment],
+    pub prep_time_minutes: Option<u16>,
+}
+
+/// Outcome of the classification of a recipe.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Classification {
+    /// Suggested category for the recipe.
+    pub category: RecipeCategory,
+    /// Score of the recipe. The higher, the harder.
+    pub score: u32,
+    /// Techniques detected in the preparation steps.
+    pub techniques: Vec<String>,
+}
+
+/// Suggest a [RecipeCategory] for a recipe.
+pub fn classify(features: &RecipeFeatures) -> Classification {
+    let mut score: u32 = match features.ingredients {
+        0..=3 => 0,
+        4..=5 => 1,
+        6..=7 => 2,
+        _ => 3,
+    };
+
+    let steps = features.steps.join(" ").to_lowercase();
+    let mut techniques = Vec::new();
+
+    for (technique, keywords, points) in TECHNIQUES {
+        if keywords.iter().any(|k| steps.contains(k)) {
+            techniques.push(technique.to_string());
+            score = score.saturating_add(*points);
+        }
+    }
+
+    let equipment: HashSet<&Equipment> = features.equipment.iter().collect();
+    score = score.saturating_add(
+        equipment
+            .into_iter()
+            .map(|e| match e {
+                Equipment::Shaker
+                | Equipment::Strainer
+                | Equipment::MixingGlass
+                | Equipment::BarSpoon
+                | Equipment::Jigger => 0,
+                Equipment::FineStrainer | Equipment::Muddler | Equipment::Juicer => 1,
+                Equipment::Blender => 1,
+                Equipment::Smoker => 3,
+            })
+            .sum::<u32>(),
+    );
+
+    score = score.saturating_add(match features.prep_time_minutes {
+        None | Some(0..=5) => 0,
+        Some(6..=15) => 1,
+        Some(16..=60) => 2,
+        Some(_) => 3,
+    });
+
+    let category = match score {
+        0..=2 => RecipeCategory::Easy,
+        3..=5 => RecipeCategory::Medium,
+        6..=8 => RecipeCategory::Advanced,
+        _ => RecipeCategory::Pro,
+    };
+
+    Classification {
+        category,
+        score,
+        techniques,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+    use rstest::*;
+
+    const ALL_EQUIPMENT: [Equipment; 10] = [
+        Equipment::Shaker,
+        Equipment::Strainer,
+        Equipment::FineStrainer,
+        Equipment::MixingGlass,
+        Equipment::BarSpoon,
+        Equipment::Jigger,
+        Equipment::Muddler,
+        Equipment::Juicer,
+        Equipment::Blender,
+        Equipment::Smoker,
+    ];
+
+    #[rstest]
+    fn simple_recipes_are_easy() {
+        let steps = ["Pour the ingredients into a highball glass with ice.".to_owned()];
+        let classification = classify(&RecipeFeatures {
+            ingredients: 2,
+            steps: &steps,
+            equipment: &[Equipment::Jigger],
+            prep_time_minutes: Some(2),
+        });
+
+        assert_eq!(classification.category, RecipeCategory::Easy);
+        assert!(classification.techniques.is_empty());
+    }
+
+    #[rstest]
+    fn techniques_are_detected() {
+        let steps = [
+            "Muddle the mint with the sugar.".to_owned(),
+            "Shake and Double Strain into a coupe.".to_owned(),
+        ];
+        let classification = classify(&RecipeFeatures {
+            ingredients: 4,
+            steps: &steps,
+            equipment: &[Equipment::Shaker, Equipment::Muddler],
+            prep_time_minutes: None,
+        });
+
+        assert_eq!(
+            classification.techniques,
+            vec!["muddling".to_owned(), "fine straining".to_owned()]
+        );
+        assert_eq!(classification.score, 4);
+        assert_eq!(classification.category, RecipeCategory::Medium);
+    }
+
+    #[rstest]
+    #[case(Some(10), RecipeCategory::Advanced)]
+    #[case(Some(90), RecipeCategory::Pro)]
+    fn prep_time_raises_the_difficulty(
+        #[case] prep_time_minutes: Option<u16>,
+        #[case] expected: RecipeCategory,
+    ) {
+        let steps = ["Fat-wash the bourbon and smoke the glass.".to_owned()];
+        let classification = classify(&RecipeFeatures {
+            ingredients: 3,
+            steps: &steps,
+            equipment: &[],
+            prep_time_minutes,
+        });
+
+        assert_eq!(classification.category, expected);
+    }
+
+    #[rstest]
+    fn repeated_equipment_counts_once() {
+        let smokers = vec![Equipment::Smoker; 200];
+        let classification = classify(&RecipeFeatures {
+            ingredients: 2,
+            steps: &[],
+            equipment: &smokers,
+            prep_time_minutes: None,
+        });
+
+        assert_eq!(classification.score, 3);
+        assert_eq!(classification.category, RecipeCategory::Medium);
+    }
+
+    proptest! {
+        #[test]
+        fn any_recipe_is_classified(
+            ingredients in any::<usize>(),
+            steps in prop::collection::vec("\\PC{0,60}", 0..40),
+            equipment in prop::collection::vec(prop::sample::select(&ALL_EQUIPMENT[..]), 0..300),
+            prep_time_minutes in any::<Option<u16>>(),
+        ) {
+            let classification = classify(&RecipeFeatures {
+                ingredients,
+                steps: &steps,
+                equipment: &equipment,
+                prep_time_minutes,
+            });
+
+            // Ingredients, techniques, equipment and preparation time, each of them scored once.
+            let techniques = TECHNIQUES.iter().map(|(_, _, points)| points).sum::<u32>();
+            let max_score = 3 + techniques + 7 + 3;
+            prop_assert!(classification.score <= max_score);
+            prop_assert!(classification.techniques.len() <= TECHNIQUES.len());
+        }
+    }
+}
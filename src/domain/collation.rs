@@ -0,0 +1,164 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Locale-aware sorting of the names stored in the DB.
+//!
+//! # Description
+//!
+//! The default collation of the DB sorts the names using the rules of no language in particular, i.e. `Ñoño` is
+//! sorted as if it was written `Nono`, while Spanish speakers expect it after all the names that start by `N`. Lists
+//! sorted by name use the collation of the language of the request instead (see [NameCollations]), which is set per
+//! deployment as the available collations depend on the DB server. The collations are shared with the handlers using
+//! `web::Data`.
+
+use crate::domain::{DataDomainError, Language};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Maximum length of the name of a collation (characters), as defined by MariaDB.
+const MAX_COLLATION_LENGTH: usize = 64;
+
+/// Name of a collation of the DB, i.e. `utf8mb4_uca1400_spanish_ai_ci`.
+///
+/// # Description
+///
+/// Collations are written within the SQL queries, as they can't be bound as parameters. Thus, only names made of
+/// ASCII letters, digits and `_` are accepted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Collation(String);
+
+impl Collation {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for Collation {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if !value.is_empty()
+            && value.len() <= MAX_COLLATION_LENGTH
+            && value
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+        {
+            Ok(Collation(value.to_owned()))
+        } else {
+            Err(DataDomainError::InvalidData)
+        }
+    }
+}
+
+impl TryFrom<String> for Collation {
+    type Error = DataDomainError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.as_str().try_into()
+    }
+}
+
+impl From<Collation> for String {
+    fn from(value: Collation) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for Collation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Collations used to sort the names, per [Language].
+///
+/// # Description
+///
+/// Languages without a collation use the default collation of the DB. The collations shall be compatible with the
+/// character set of the DB (`utf8mb4`), otherwise the queries that sort by name fail.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct NameCollations {
+    #[serde(default)]
+    pub en: Option<Collation>,
+    #[serde(default = "default_spanish_collation")]
+    pub es: Option<Collation>,
+}
+
+impl Default for NameCollations {
+    fn default() -> Self {
+        NameCollations {
+            en: None,
+            es: default_spanish_collation(),
+        }
+    }
+}
+
+impl NameCollations {
+    /// Collation of a language, if any.
+    pub fn get(&self, language: Language) -> Option<&Collation> {
+        match language {
+            Language::En => self.en.as_ref(),
+            Language::Es => self.es.as_ref(),
+        }
+    }
+}
+
+fn default_spanish_collation() -> Option<Collation> {
+    Some(Collation("utf8mb4_uca1400_spanish_ai_ci".to_owned()))
+}
+
+/// Build the `ORDER BY` term that sorts a column of names using a collation, usually the one of the language of the
+/// request (see [NameCollations::get]). The default collation of the DB is used when none is given.
+///
+/// # Description
+///
+/// `column` is written as is within the query, so it shall never come from the clients.
+pub fn order_by_name(column: &str, collation: Option<&Collation>) -> String {
+    match collation {
+        Some(collation) => format!("{column} COLLATE {collation}"),
+        None => column.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case("utf8mb4_uca1400_spanish_ai_ci", true)]
+    #[case("utf8mb4_bin", true)]
+    #[case("", false)]
+    #[case("utf8mb4_bin; DROP TABLE Cocktail", false)]
+    #[case("utf8mb4-bin", false)]
+    fn collations_are_validated(#[case] value: &str, #[case] valid: bool) {
+        assert_eq!(Collation::try_from(value).is_ok(), valid);
+    }
+
+    #[rstest]
+    fn long_collations_are_rejected() {
+        assert!(Collation::try_from("a".repeat(MAX_COLLATION_LENGTH)).is_ok());
+        assert!(Collation::try_from("a".repeat(MAX_COLLATION_LENGTH + 1)).is_err());
+    }
+
+    #[rstest]
+    fn collations_are_chosen_per_language() {
+        let collations: NameCollations =
+            serde_json::from_str(r#"{"en": "utf8mb4_uca1400_ai_ci"}"#).expect("Failed to parse");
+
+        assert_eq!(
+            collations.get(Language::En).map(Collation::as_str),
+            Some("utf8mb4_uca1400_ai_ci")
+        );
+        assert_eq!(
+            collations.get(Language::Es),
+            NameCollations::default().get(Language::Es)
+        );
+        assert!(serde_json::from_str::<NameCollations>(r#"{"es": "spanish ci"}"#).is_err());
+    }
+}
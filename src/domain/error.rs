@@ -6,6 +6,7 @@
 
 use actix_web::{http::StatusCode, HttpResponse, ResponseError};
 use thiserror::Error;
+use tracing::error;
 use validator::ValidationErrors;
 
 /// Custom error type for the operations related to data domains's objects.
@@ -14,7 +15,14 @@ use validator::ValidationErrors;
 ///
 /// - [DataDomainError::InvalidParams] is returned when a data object is built using wrong data for some of its
 ///   members. This is a wrapper and contains the error messages that could have been generated by the internal logic.
-/// - [DataDomainError::InvalidId] is returned when an object is built using an ID that is badly formatted.
+/// - [DataDomainError::LimitExceeded] is returned when a data object exceeds the size limits of the application.
+///   Clients of the API receive a code 422 in such case.
+/// - [DataDomainError::InvalidId] is returned when an object is built using an ID that is badly formatted. Clients
+///   of the API receive a code 400 when a malformed ID is given to a singleton resource.
+/// - [DataDomainError::EmailAlreadyRegistered] is returned when an author takes the email of another author, and
+///   emails are unique. Clients of the API receive a code 409 in such case.
+/// - [DataDomainError::DuplicatedRecipeName] is returned when the name of a recipe has the same slug as another
+///   recipe of its author. Clients of the API receive a code 409 in such case.
 #[derive(Error, Debug)]
 pub enum DataDomainError {
     #[error("Some params contain an invalid format")]
@@ -22,6 +30,8 @@ pub enum DataDomainError {
         #[from]
         source: ValidationErrors,
     },
+    #[error("Some params exceed the limits of the server")]
+    LimitExceeded { source: ValidationErrors },
     #[error("The given Author ID hash an invalid format")]
     InvalidId,
     #[error("The given string is not a valid recipe's category")]
@@ -38,8 +48,20 @@ pub enum DataDomainError {
     InvalidEmail,
     #[error("Account disabled")]
     AccountDisabled,
+    #[error("The client has no privileges to access the resource")]
+    InsufficientPrivileges,
     #[error("Parsing error")]
     InvalidData,
+    #[error("The given string is not a valid email address")]
+    InvalidEmailAddress,
+    #[error("The given string is not a valid URL")]
+    InvalidUrl,
+    #[error("Names shall have from 2 to 40 characters")]
+    InvalidName,
+    #[error("The email is registered by another author")]
+    EmailAlreadyRegistered,
+    #[error("The author has another recipe with the same name")]
+    DuplicatedRecipeName,
 }
 
 #[derive(Error, Debug)]
@@ -59,10 +81,7 @@ impl ResponseError for ServerError {
     }
 
     fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
-        HttpResponse::InternalServerError().body(format!(
-            include_str!("../../static/message_template.html"),
-            "<h3>Detected an error in the server, please, try again later.</h3>"
-        ))
+        HttpResponse::InternalServerError().finish()
     }
 }
 
@@ -70,14 +89,150 @@ impl ResponseError for DataDomainError {
     fn status_code(&self) -> StatusCode {
         match self {
             DataDomainError::InvalidAccessCredentials => StatusCode::FORBIDDEN,
+            DataDomainError::InsufficientPrivileges => StatusCode::FORBIDDEN,
+            DataDomainError::AccountDisabled => StatusCode::FORBIDDEN,
+            DataDomainError::InvalidId
+            | DataDomainError::InvalidParams { .. }
+            | DataDomainError::InvalidRecipeCategory
+            | DataDomainError::InvalidFormData
+            | DataDomainError::InvalidSearch
+            | DataDomainError::InvalidData
+            | DataDomainError::InvalidEmailAddress
+            | DataDomainError::InvalidUrl
+            | DataDomainError::InvalidName => StatusCode::BAD_REQUEST,
+            DataDomainError::LimitExceeded { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            DataDomainError::EmailAlreadyRegistered | DataDomainError::DuplicatedRecipeName => {
+                StatusCode::CONFLICT
+            }
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
     fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
-        HttpResponse::InternalServerError().body(format!(
-            include_str!("../../static/message_template.html"),
-            "<h3>Detected an error in the server, please, try again later.</h3>"
-        ))
+        HttpResponse::build(self.status_code()).finish()
+    }
+}
+
+/// Error type returned by the handlers of the API.
+///
+/// # Description
+///
+/// All the errors raised while serving a request are converted into an [ApiError], so handlers can use the `?`
+/// operator and the status code of the response is decided in a single place:
+/// - [ApiError::Domain] uses the status code of the wrapped [DataDomainError], i.e. a code 400 for invalid data.
+/// - [ApiError::Validation] results in a code 400.
+/// - [ApiError::Db] results in a code 404 when the query found no rows, and a code 500 otherwise.
+/// - [ApiError::Server] and [ApiError::Internal] result in a code 500.
+///
+/// Responses have no body, like the rest of the client errors of the API. Errors with a code 500 are logged, and their
+/// responses get the error page of the server (see [crate::web::server_error_pages]).
+#[derive(Error, Debug)]
+pub enum ApiError {
+    #[error(transparent)]
+    Domain(#[from] DataDomainError),
+    #[error(transparent)]
+    Server(#[from] ServerError),
+    #[error("Error from a DB query: {0}")]
+    Db(#[from] sqlx::Error),
+    #[error("Some params contain an invalid format: {0}")]
+    Validation(#[from] ValidationErrors),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl From<Box<dyn std::error::Error>> for ApiError {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        let e = match e.downcast::<DataDomainError>() {
+            Ok(e) => return ApiError::Domain(*e),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<ServerError>() {
+            Ok(e) => return ApiError::Server(*e),
+            Err(e) => e,
+        };
+        match e.downcast::<sqlx::Error>() {
+            Ok(e) => ApiError::Db(*e),
+            Err(e) => ApiError::Internal(anyhow::anyhow!("{e}")),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        ApiError::Internal(e.into())
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Domain(e) => e.status_code(),
+            ApiError::Validation(_) => StatusCode::BAD_REQUEST,
+            ApiError::Db(sqlx::Error::RowNotFound) => StatusCode::NOT_FOUND,
+            ApiError::Server(_) | ApiError::Db(_) | ApiError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        match self.status_code() {
+            StatusCode::INTERNAL_SERVER_ERROR => {
+                error!("{self}");
+                HttpResponse::InternalServerError().finish()
+            }
+            status => HttpResponse::build(status).finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case(ApiError::from(DataDomainError::InvalidId), StatusCode::BAD_REQUEST)]
+    #[case(
+        ApiError::from(DataDomainError::InvalidSearch),
+        StatusCode::BAD_REQUEST
+    )]
+    #[case(
+        ApiError::from(DataDomainError::InsufficientPrivileges),
+        StatusCode::FORBIDDEN
+    )]
+    #[case(
+        ApiError::from(DataDomainError::EmailAlreadyRegistered),
+        StatusCode::CONFLICT
+    )]
+    #[case(
+        ApiError::from(ServerError::DbError),
+        StatusCode::INTERNAL_SERVER_ERROR
+    )]
+    #[case(ApiError::from(sqlx::Error::RowNotFound), StatusCode::NOT_FOUND)]
+    #[case(
+        ApiError::from(sqlx::Error::PoolTimedOut),
+        StatusCode::INTERNAL_SERVER_ERROR
+    )]
+    #[case(ApiError::from(ValidationErrors::new()), StatusCode::BAD_REQUEST)]
+    fn errors_get_their_status_code(#[case] error: ApiError, #[case] status: StatusCode) {
+        assert_eq!(error.status_code(), status);
+        assert_eq!(error.error_response().status(), status);
+    }
+
+    #[rstest]
+    fn boxed_errors_keep_their_type() {
+        let boxed: Box<dyn std::error::Error> = Box::new(DataDomainError::InvalidId);
+        assert!(matches!(
+            ApiError::from(boxed),
+            ApiError::Domain(DataDomainError::InvalidId)
+        ));
+
+        let boxed: Box<dyn std::error::Error> = Box::new(ServerError::DbError);
+        assert!(matches!(ApiError::from(boxed), ApiError::Server(_)));
+
+        let boxed: Box<dyn std::error::Error> = "unexpected".into();
+        assert!(matches!(ApiError::from(boxed), ApiError::Internal(_)));
     }
 }
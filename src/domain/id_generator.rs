@@ -0,0 +1,114 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Generation of the IDs of the new resources.
+//!
+//! # Description
+//!
+//! The backend assigns an [Uuid] to every new resource of the DB (authors, recipes, ingredients...). Rather than
+//! calling [Uuid::now_v7] inline, handlers get the IDs from the [IdGenerator] of the application, which is injected as
+//! app data (`web::Data<dyn IdGenerator>`). The generator is selected with the setting `application.id_scheme`:
+//! - [IdScheme::UuidV7]: time ordered random IDs, the default scheme.
+//! - [IdScheme::Sequential]: predictable IDs (`00000000-0000-0000-0000-000000000001`, `...002`, ...), meant for
+//!   tests that compare the responses of the API against snapshots.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use uuid::Uuid;
+
+/// Source of the IDs of the new resources.
+pub trait IdGenerator: Send + Sync + Debug {
+    /// Generate a new ID. IDs shall never repeat, and the nil [Uuid] shall never be generated.
+    fn new_id(&self) -> Uuid;
+}
+
+/// Default [IdGenerator], which generates UUIDs version 7.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV7Generator;
+
+impl IdGenerator for UuidV7Generator {
+    fn new_id(&self) -> Uuid {
+        Uuid::now_v7()
+    }
+}
+
+/// [IdGenerator] that generates consecutive IDs, starting from `00000000-0000-0000-0000-000000000001`.
+#[derive(Debug, Default)]
+pub struct SequentialIdGenerator {
+    last: AtomicU64,
+}
+
+impl SequentialIdGenerator {
+    pub fn new() -> Self {
+        SequentialIdGenerator::default()
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn new_id(&self) -> Uuid {
+        let next = self.last.fetch_add(1, Ordering::Relaxed) + 1;
+        Uuid::from_u128(u128::from(next))
+    }
+}
+
+/// Schemes of IDs supported by the application.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdScheme {
+    /// See [UuidV7Generator].
+    #[default]
+    UuidV7,
+    /// See [SequentialIdGenerator].
+    Sequential,
+}
+
+impl IdScheme {
+    /// Build a new [IdGenerator] for the scheme.
+    pub fn generator(&self) -> Arc<dyn IdGenerator> {
+        match self {
+            IdScheme::UuidV7 => Arc::new(UuidV7Generator),
+            IdScheme::Sequential => Arc::new(SequentialIdGenerator::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ResourceId;
+    use pretty_assertions::{assert_eq, assert_ne};
+    use rstest::rstest;
+
+    #[rstest]
+    fn sequential_ids_are_predictable() {
+        let ids = IdScheme::Sequential.generator();
+        assert_eq!(
+            ids.new_id().to_string(),
+            "00000000-0000-0000-0000-000000000001"
+        );
+        assert_eq!(
+            ids.new_id().to_string(),
+            "00000000-0000-0000-0000-000000000002"
+        );
+    }
+
+    #[rstest]
+    #[case(IdScheme::UuidV7)]
+    #[case(IdScheme::Sequential)]
+    fn generated_ids_are_valid_resource_ids(#[case] scheme: IdScheme) {
+        let ids = scheme.generator();
+        let first = ids.new_id();
+        let second = ids.new_id();
+        assert_ne!(first, second);
+        assert!(ResourceId::try_from(first.to_string().as_str()).is_ok());
+    }
+}
@@ -6,6 +6,7 @@
 
 use anyhow::bail;
 use core::fmt;
+use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::convert::{Into, TryFrom};
@@ -14,28 +15,40 @@ use tracing::error;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use super::DataDomainError;
+use super::{
+    sanitize::{deserialize_optional_text, sanitize},
+    DataDomainError, ResourceName,
+};
 
-/// This value is set in the DB's schema definition (VARCHAR(40)).
-const MAX_NAME_LENGTH: usize = 40;
 /// This value is set in the DB's schema definition (VARCHAR(255)).
 const MAX_DESC_LENGTH: usize = 255;
 
-/// Types of ingredients of teh `Cocktail` data base.
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, ToSchema)]
-pub enum IngCategory {
-    /// Spirit ingredients, such as rum, liquors and so.
-    Spirit,
-    /// Bitter ingredients, such as Angostura.
-    Bitter,
-    /// Soft-drink ingredients, such as soda water, Fanta, Coke, etc.
-    #[serde(alias = "soft_drink")]
-    SoftDrink,
-    /// Garnish ingredients, such a lemon's peel.
-    Garnish,
-    /// Category for ingredients whose type does not match any of the other types.
-    Other,
-}
+/// Maximum length of the identifier of an ingredient category. This value is set in the DB's schema definition
+/// (VARCHAR(20)).
+const MAX_ING_CATEGORY_LENGTH: usize = 20;
+
+/// Format of the identifiers of the ingredient categories.
+static RE_ING_CATEGORY: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z][a-z0-9_]+$").unwrap());
+
+/// Category of the ingredients of the `Cocktail` data base.
+///
+/// # Description
+///
+/// Categories are stored in the DB, so new ones (`syrup`, `juice`...) can be added by the administrators of the API
+/// without a new release. The list of the available categories is served by `GET /ingredient/categories`. This type
+/// only checks the format of the identifier of a category: from 2 to 20 lowercase letters, numbers or `_`, starting
+/// with a letter. Whether the category exists is checked against the DB.
+///
+/// Identifiers are not case sensitive, and names written in camel case are converted to snake case, so the legacy
+/// names of the categories (`Spirit`, `SoftDrink`) are still accepted.
+///
+/// **Breaking change:** categories are always served as identifiers (`spirit`, `soft_drink`), while the legacy
+/// releases served the capitalised names (`Spirit`, `SoftDrink`). Clients that compare the categories of the
+/// responses must use the identifiers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+#[schema(value_type = String, example = "spirit")]
+pub struct IngCategory(String);
 
 /// Object that represents an Ingredient of the `Cocktail` data base.
 ///
@@ -48,9 +61,14 @@ pub enum IngCategory {
 #[derive(Clone, Serialize, Deserialize, ToSchema)]
 pub struct Ingredient {
     id: Option<Uuid>,
-    name: String,
+    name: ResourceName,
     category: IngCategory,
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
     description: Option<String>,
+    /// ID of the image of the ingredient in the media storage, see [crate::utils::media].
+    #[serde(default)]
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b.png")]
+    image_id: Option<String>,
 }
 
 impl Ingredient {
@@ -59,7 +77,7 @@ impl Ingredient {
     /// # Description
     ///
     /// The implementation checks that the given _name_ value meets the following requirements:
-    /// - The length of the name doesn't exceeds 40 characters.
+    /// - The name is a valid [ResourceName].
     /// - The name is composed of alphanumeric characters plus the special character `%`.
     /// - The name does not contain the following forbidden characters: `[`, `<`, `>`, `;`,
     ///   `{`, `}`, `]`.
@@ -67,7 +85,7 @@ impl Ingredient {
     /// # Arguments
     ///
     /// - _name_ will be used as [Ingredient::name].
-    /// - _category_ will be used as [Ingredient::category]. Use [IngCategory::Other] when no
+    /// - _category_ will be used as [Ingredient::category]. Use `other` when no category is
     ///   needed.
     /// - _desc_ will be used as [Ingredient::desc]. Pass `None` when no description was provided
     ///   along the Ingredient's name.
@@ -117,17 +135,24 @@ impl Ingredient {
             category,
             description,
             id,
+            image_id: None,
         })
     }
 
+    /// Set the image of the ingredient.
+    pub fn with_image_id(mut self, image_id: Option<String>) -> Self {
+        self.image_id = image_id;
+        self
+    }
+
     /// Get the Ingredient's  name.
     pub fn name(&self) -> &str {
-        &self.name
+        self.name.as_str()
     }
 
-    /// Get the Ingredient's category as a value of the `Enum` [IngCategory].
-    pub fn category(&self) -> IngCategory {
-        self.category
+    /// Get the Ingredient's category.
+    pub fn category(&self) -> &IngCategory {
+        &self.category
     }
 
     /// Get the description of the Ingredient. Wrapped to allow empty descriptions.
@@ -135,6 +160,11 @@ impl Ingredient {
         self.description.as_deref()
     }
 
+    /// Get the ID of the image of the ingredient, if any.
+    pub fn image_id(&self) -> Option<&str> {
+        self.image_id.as_deref()
+    }
+
     /// Get the ingredient's ID in the `Cocktail` data base.
     pub fn id(&self) -> Option<Uuid> {
         self.id
@@ -152,7 +182,7 @@ impl Ingredient {
     /// This internal method performs a series of checks against a given string in order
     /// to detect the violation of some design rule for [Ingredient::name]. The following
     /// is checked:
-    /// - The string does not exceed 40 characters.
+    /// - The string is a valid [ResourceName], which checks its length.
     /// - The string's format is a name that might includes numbers and/or the symbol `%`.
     /// - The string does not contain the following forbidden characters: `[`, `<`, `>`, `;`,
     ///   `{`, `}`, `]`.
@@ -164,13 +194,13 @@ impl Ingredient {
     /// # Return
     ///
     /// A  `Result` enum with:
-    /// - A `String` on success that is an owned copy of the string given as argument.
+    /// - A [ResourceName] on success, built from the string given as argument.
     /// - Otherwise, an error that contains a message that informs what rule was violated.
-    fn check_name(name: &str) -> Result<String, anyhow::Error> {
+    fn check_name(name: &str) -> Result<ResourceName, anyhow::Error> {
         // Avoid processing long strings that exceed the maximum allowed.
-        if name.len() > MAX_NAME_LENGTH {
-            bail!("The length of the given Ingredient's name exceeds {MAX_NAME_LENGTH} characters.")
-        }
+        let Ok(checked) = ResourceName::try_from(name) else {
+            bail!("The given Ingredient's name ({name}) has an invalid length.")
+        };
 
         // Regex for the validation of usual strings composed by words, numbers
         // and the symbol %.
@@ -188,13 +218,14 @@ impl Ingredient {
             bail!("The given Ingredient's name ({name}) has an invalid format.")
         }
 
-        // Finally, look for forbidden characters in the input string.
+        // Finally, look for forbidden characters in the normalized string, as some characters are only
+        // turned into forbidden ones by the normalization (i.e. U+1FEF into a backtick).
         let forbidden_chars = Regex::new(r"[;<>`\{\}]").unwrap();
 
-        if forbidden_chars.is_match(name) {
+        if forbidden_chars.is_match(name) || forbidden_chars.is_match(checked.as_str()) {
             bail!("The given Ingredient's name ({name}) contains invalid characters.")
         } else {
-            Ok(String::from(name))
+            Ok(checked)
         }
     }
 
@@ -202,8 +233,8 @@ impl Ingredient {
     ///
     /// # Description
     ///
-    /// A very basic check is performed: ensure that the length doesn't exceeds the
-    /// maximum allowed (255 characters).
+    /// The description is sanitized (see [crate::domain::sanitize]), and a very basic check is
+    /// performed: ensure that the length doesn't exceeds the maximum allowed (255 characters).
     ///
     /// # Arguments
     ///
@@ -212,7 +243,7 @@ impl Ingredient {
     /// # Return
     ///
     /// A `Result` enum with:
-    /// - A `String` on success that contains an owned version of the string given as
+    /// - A `String` on success that contains the sanitized version of the string given as
     ///   argument.
     /// - Otherwise, an error that contains a message that informs about the violated rule.
     fn check_desc(desc: &str) -> Result<String, anyhow::Error> {
@@ -221,7 +252,7 @@ impl Ingredient {
             bail!("The length of the given string exceeds {MAX_DESC_LENGTH} characters.")
         }
 
-        Ok(String::from(desc))
+        Ok(sanitize(desc))
     }
 }
 
@@ -245,14 +276,19 @@ impl TryFrom<&str> for IngCategory {
     type Error = anyhow::Error;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match value {
-            "Spirit" | "spirit" => Ok(IngCategory::Spirit),
-            "Bitter" | "bitter" => Ok(IngCategory::Bitter),
-            "SofDrink" | "softdrink" | "soft_drink" => Ok(IngCategory::SoftDrink),
-            "Garnish" | "garnish" => Ok(IngCategory::Garnish),
-            "Other" | "other" => Ok(IngCategory::Other),
-            _ => bail!("Invalid ingredient category."),
+        let identifier = IngCategory::normalize(value);
+
+        if identifier.len() > MAX_ING_CATEGORY_LENGTH || !RE_ING_CATEGORY.is_match(&identifier) {
+            bail!("Invalid ingredient category.")
         }
+
+        Ok(IngCategory(identifier))
+    }
+}
+
+impl From<IngCategory> for String {
+    fn from(value: IngCategory) -> Self {
+        value.0
     }
 }
 
@@ -264,12 +300,28 @@ impl fmt::Display for IngCategory {
 
 impl IngCategory {
     pub fn to_str(&self) -> &str {
-        match self {
-            IngCategory::Bitter => "bitter",
-            IngCategory::Garnish => "garnish",
-            IngCategory::SoftDrink => "soft_drink",
-            IngCategory::Spirit => "spirit",
-            IngCategory::Other => "other",
+        &self.0
+    }
+
+    /// Convert a name to the format of the identifiers, i.e. `SoftDrink` to `soft_drink`.
+    fn normalize(value: &str) -> String {
+        let mut identifier = String::new();
+        let mut previous: Option<char> = None;
+
+        for c in value.trim().chars() {
+            if c.is_ascii_uppercase()
+                && previous.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+            {
+                identifier.push('_');
+            }
+            identifier.push(c.to_ascii_lowercase());
+            previous = Some(c);
+        }
+
+        // Legacy aliases of the category soft_drink.
+        match identifier.as_str() {
+            "softdrink" | "sof_drink" => "soft_drink".to_owned(),
+            _ => identifier,
         }
     }
 }
@@ -288,6 +340,8 @@ impl fmt::Debug for Ingredient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::{primitives::MAX_NAME_LENGTH, sanitize::normalize_text};
+    use proptest::prelude::*;
     use rstest::*;
 
     #[rstest]
@@ -303,4 +357,73 @@ mod tests {
     fn convert_names_to_ingredients(#[case] input: &str, #[case] expected: bool) {
         assert_eq!(Ingredient::check_name(input).is_ok(), expected);
     }
+
+    #[rstest]
+    #[case("spirit", Some("spirit"))]
+    #[case("Spirit", Some("spirit"))]
+    #[case("SoftDrink", Some("soft_drink"))]
+    #[case("SofDrink", Some("soft_drink"))]
+    #[case("softdrink", Some("soft_drink"))]
+    #[case("soft_drink", Some("soft_drink"))]
+    #[case("SYRUP", Some("syrup"))]
+    #[case("SPIRIT", Some("spirit"))]
+    #[case("Bitter", Some("bitter"))]
+    #[case(" juice ", Some("juice"))]
+    #[case("x", None)]
+    #[case("2nd", None)]
+    #[case("fresh juice", None)]
+    #[case("a_very_long_category_name", None)]
+    fn parse_ingredient_categories(#[case] input: &str, #[case] expected: Option<&str>) {
+        assert_eq!(
+            IngCategory::try_from(input)
+                .ok()
+                .as_ref()
+                .map(IngCategory::to_str),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn descriptions_are_sanitized() {
+        let ingredient =
+            Ingredient::parse(None, "Gin", "spirit", Some("A <b>dry</b> gin")).unwrap();
+        assert_eq!(ingredient.desc(), Some("A dry gin"));
+
+        let ingredient: Ingredient = serde_json::from_value(serde_json::json!({
+            "name": "Gin",
+            "category": "spirit",
+            "description": "<script>alert(1)</script>Dry",
+        }))
+        .unwrap();
+        assert_eq!(ingredient.desc(), Some("alert(1)Dry"));
+    }
+
+    #[rstest]
+    fn categories_are_serialized_as_identifiers() {
+        let category: IngCategory = serde_json::from_str("\"SoftDrink\"").unwrap();
+        assert_eq!(serde_json::to_string(&category).unwrap(), "\"soft_drink\"");
+        assert!(serde_json::from_str::<IngCategory>("\"<script>\"").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn accepted_names_are_normalized(name in "\\PC{0,50}") {
+            if let Ok(checked) = Ingredient::check_name(&name) {
+                prop_assert_eq!(checked.as_str(), normalize_text(&name));
+                prop_assert!(checked.as_str().chars().count() <= MAX_NAME_LENGTH);
+                let forbidden = [';', '<', '>', '`', '{', '}'];
+                prop_assert!(!checked.as_str().contains(forbidden));
+            }
+        }
+
+        #[test]
+        fn plain_names_are_accepted(name in "[a-zA-Z]{2,20}( [a-z0-9%]{1,9}){0,2}") {
+            prop_assert!(Ingredient::check_name(&name).is_ok());
+        }
+
+        #[test]
+        fn long_names_are_rejected(name in "[a-z ]{41,80}") {
+            prop_assert!(Ingredient::check_name(&name).is_err());
+        }
+    }
 }
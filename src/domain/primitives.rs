@@ -0,0 +1,231 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Validated strings shared by the domain objects.
+//!
+//! # Description
+//!
+//! Emails, URLs and names are checked once, when they are built from the data given by the clients or read from the
+//! DB. Domain objects hold these types rather than plain strings, so an invalid value can't reach the internal logic.
+//! All of them are deserialised from a string, thus JSON payloads with invalid values are rejected by the extractors
+//! of the framework.
+
+use crate::domain::{sanitize::sanitize, DataDomainError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::ToSchema;
+
+/// Minimum length of a [ResourceName] (characters).
+pub const MIN_NAME_LENGTH: usize = 2;
+
+/// Maximum length of a [ResourceName] (characters). This value is set in the DB's schema definition (VARCHAR(40)).
+pub const MAX_NAME_LENGTH: usize = 40;
+
+/// Email address, validated against the HTML5 regex.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+#[schema(value_type = String, example = "jane_doe@mail.com")]
+pub struct EmailAddress(String);
+
+/// URL of a website.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+#[schema(value_type = String, example = "https://janedoe.com")]
+pub struct WebsiteUrl(String);
+
+/// Name of a resource, i.e. a recipe, an ingredient or an author.
+///
+/// # Description
+///
+/// Names are sanitized (see [crate::domain::sanitize]), and they shall have from 2 to 40 characters.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+#[schema(value_type = String, example = "Margarita")]
+pub struct ResourceName(String);
+
+impl EmailAddress {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl WebsiteUrl {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl ResourceName {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for EmailAddress {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim();
+
+        if validator::validate_email(value) {
+            Ok(EmailAddress(value.to_owned()))
+        } else {
+            Err(DataDomainError::InvalidEmailAddress)
+        }
+    }
+}
+
+impl TryFrom<&str> for WebsiteUrl {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim();
+
+        if validator::validate_url(value) {
+            Ok(WebsiteUrl(value.to_owned()))
+        } else {
+            Err(DataDomainError::InvalidUrl)
+        }
+    }
+}
+
+impl TryFrom<&str> for ResourceName {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = sanitize(value);
+        let length = value.chars().count();
+
+        if (MIN_NAME_LENGTH..=MAX_NAME_LENGTH).contains(&length) {
+            Ok(ResourceName(value))
+        } else {
+            Err(DataDomainError::InvalidName)
+        }
+    }
+}
+
+impl TryFrom<String> for EmailAddress {
+    type Error = DataDomainError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.as_str().try_into()
+    }
+}
+
+impl From<EmailAddress> for String {
+    fn from(value: EmailAddress) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for EmailAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<String> for WebsiteUrl {
+    type Error = DataDomainError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.as_str().try_into()
+    }
+}
+
+impl From<WebsiteUrl> for String {
+    fn from(value: WebsiteUrl) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for WebsiteUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<String> for ResourceName {
+    type Error = DataDomainError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.as_str().try_into()
+    }
+}
+
+impl From<ResourceName> for String {
+    fn from(value: ResourceName) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for ResourceName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case("jane_doe@mail.com", true)]
+    #[case(" jane_doe@mail.com ", true)]
+    #[case("janedoe<at>mail.com", false)]
+    #[case("", false)]
+    fn emails_are_validated(#[case] value: &str, #[case] valid: bool) {
+        let email = EmailAddress::try_from(value);
+        assert_eq!(email.is_ok(), valid);
+        if let Ok(email) = email {
+            assert_eq!(email.as_str(), value.trim());
+        }
+    }
+
+    #[rstest]
+    #[case("https://janedoe.com", true)]
+    #[case("http://janedoe.com/recipes?page=2", true)]
+    #[case("janedoe.com", false)]
+    #[case("a web site", false)]
+    fn urls_are_validated(#[case] value: &str, #[case] valid: bool) {
+        assert_eq!(WebsiteUrl::try_from(value).is_ok(), valid);
+    }
+
+    #[rstest]
+    #[case("Margarita", Some("Margarita"))]
+    #[case("Piña colada", Some("Piña colada"))]
+    #[case("Mo\u{200B}jito", Some("Mojito"))]
+    #[case("J", None)]
+    #[case("J\u{200B}", None)]
+    fn names_are_validated(#[case] value: &str, #[case] expected: Option<&str>) {
+        assert_eq!(
+            ResourceName::try_from(value)
+                .ok()
+                .as_ref()
+                .map(ResourceName::as_str),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn long_names_are_rejected() {
+        assert!(ResourceName::try_from("ñ".repeat(MAX_NAME_LENGTH).as_str()).is_ok());
+        assert!(ResourceName::try_from("ñ".repeat(MAX_NAME_LENGTH + 1).as_str()).is_err());
+    }
+
+    #[rstest]
+    fn invalid_values_are_not_deserialised() {
+        assert!(serde_json::from_str::<EmailAddress>(r#""jane_doe@mail.com""#).is_ok());
+        assert!(serde_json::from_str::<EmailAddress>(r#""jane_doe""#).is_err());
+        assert!(serde_json::from_str::<WebsiteUrl>(r#""janedoe.com""#).is_err());
+        assert!(serde_json::from_str::<ResourceName>(r#""J""#).is_err());
+        assert_eq!(
+            serde_json::to_string(&ResourceName::try_from("Margarita").unwrap()).unwrap(),
+            r#""Margarita""#
+        );
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Votes of the clients of the API for the recipes.
+//!
+//! # Description
+//!
+//! The rating of a recipe is the average of the votes of the clients of the API, rounded to the nearest half star
+//! (see [StarRate::from_average]). Every client has a single vote per recipe, which can be changed by voting again.
+//! The aggregated rating is cached along with the recipe, so the recipes and their searches don't compute it.
+
+use crate::domain::{DataDomainError, StarRate};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Vote of a client of the API for a recipe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct RatingVote {
+    /// From 0.5 to 5.0 stars, in steps of 0.5 stars.
+    pub stars: StarRate,
+}
+
+impl RatingVote {
+    /// Check the vote. Votes of 0 stars are rejected, as such rating means that a recipe has no votes.
+    pub fn validate(&self) -> Result<(), DataDomainError> {
+        if self.stars == StarRate::default() {
+            Err(DataDomainError::InvalidData)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Aggregated rating of a recipe.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct RecipeRating {
+    /// Average of the votes, rounded to the nearest half star. It is the rating returned along with the recipe.
+    pub rating: StarRate,
+    /// Amount of clients that voted the recipe.
+    #[schema(example = 12)]
+    pub votes: u32,
+    /// Vote of the client that issued the request.
+    pub vote: StarRate,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("{\"stars\": 4.5}", true)]
+    #[case("{\"stars\": \"3\"}", true)]
+    #[case("{\"stars\": 0}", false)]
+    fn votes_are_validated(#[case] json: &str, #[case] valid: bool) {
+        let vote: RatingVote = serde_json::from_str(json).expect("Failed to parse the vote");
+        assert_eq!(vote.validate().is_ok(), valid);
+    }
+
+    #[rstest]
+    #[case("{\"stars\": 3.7}")]
+    #[case("{\"stars\": 6}")]
+    #[case("{}")]
+    fn invalid_votes_are_rejected(#[case] json: &str) {
+        assert!(serde_json::from_str::<RatingVote>(json).is_err());
+    }
+}
@@ -15,17 +15,25 @@
 //! the aimed member needs to be populated by the client of the API.
 
 use crate::{
-    domain::{DataDomainError, Tag},
+    domain::{
+        classifier::{classify, RecipeFeatures},
+        sanitize::{deserialize_optional_text, deserialize_text_list, sanitize},
+        units::{convert_amount, UnitSystem},
+        DataDomainError, ResourceId, ResourceName, ShortId, Tag, WebsiteUrl,
+    },
     validate_id,
 };
 use chrono::{DateTime, Local};
 use core::fmt;
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::borrow::Cow;
 use tracing::error;
-use utoipa::{IntoParams, ToSchema};
+use utoipa::{
+    openapi::{KnownFormat, ObjectBuilder, SchemaFormat, SchemaType},
+    IntoParams, ToSchema,
+};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 /// Object that represents a Recipe of the `Cocktail` data base.
 ///
@@ -40,37 +48,69 @@ pub struct Recipe {
     #[validate(custom(function = "validate_id"))]
     #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
     id: Option<Uuid>,
+    /// Short public ID of the recipe, accepted anywhere the ID is accepted. Generated by the backend.
+    #[schema(value_type = Option<String>, example = "4Jq7Wd9tXbC")]
+    #[serde(default)]
+    short_id: Option<ShortId>,
     /// Recipe's name. Up to 40 chars.
-    #[validate(length(min = 2), length(max = 40))]
-    name: String,
+    name: ResourceName,
+    /// URL-friendly version of the name, unique among the recipes of the author. Generated by the backend, see
+    /// [crate::domain::slug].
+    #[schema(example = "pina-colada")]
+    #[serde(default)]
+    slug: Option<String>,
     /// Path to an image for the cocktail.
     image_id: Option<String>,
     /// List of tags assigned by the recipe's author.
     author_tags: Option<Vec<Tag>>,
     /// List of tags assigned by the internal logic.
     tags: Option<Vec<Tag>>,
-    /// Recipe's category.
-    category: RecipeCategory,
-    /// Recipe's rating.
+    /// Recipe's category. When it is not given, a category is suggested from the content of the recipe.
+    #[serde(default)]
+    category: Option<RecipeCategory>,
+    /// Recipe's rating: the average of the votes of the clients (see [crate::domain::rating]). It is ignored when
+    /// registering a recipe.
+    #[schema(read_only)]
     rating: Option<StarRate>,
     #[validate(length(min = 2), length(max = 400))]
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
     description: Option<String>,
     /// Linked URL of the recipe. For third-party content.
-    #[validate(url)]
-    url: Option<String>,
-    /// Ingredients of the recipe.
+    url: Option<WebsiteUrl>,
+    /// Attribution of third-party recipes, i.e. the book where the recipe was published.
+    #[validate]
+    source: Option<RecipeSource>,
+    /// Terms under which the recipe can be republished. When it is not given, the default license of the server
+    /// applies.
+    #[serde(default)]
+    license: Option<RecipeLicense>,
+    /// Ingredients of the recipe. Quantities are checked against their units (see [RecipeContains::validate]).
+    #[validate]
     ingredients: Vec<RecipeContains>,
     /// Preparation steps of the cocktail.
+    #[serde(deserialize_with = "deserialize_text_list")]
     steps: Vec<String>,
+    /// Images that illustrate some of the preparation steps. They are uploaded using
+    /// `PUT /recipe/{id}/steps/{step}/image`.
+    step_images: Option<Vec<StepImage>>,
+    /// Bar equipment needed to prepare the cocktail.
+    equipment: Option<Vec<Equipment>>,
+    /// Estimated preparation time (minutes). Up to a day.
+    #[validate(range(min = 1, max = 1440))]
+    prep_time_minutes: Option<u16>,
     /// When the recipe was registered in the DB.
-    #[schema(value_type = String, example = "2025-09-11T08:58:56.121331664+02:00")]
+    #[schema(value_type = Option<String>, example = "2025-09-11T08:58:56.121331664+02:00")]
     creation_date: Option<DateTime<Local>>,
     /// Indicate whether the recipe was updated after creation and when.
-    #[schema(value_type = String, example = "2025-09-11T08:58:56.121331664+02:00")]
+    #[schema(value_type = Option<String>, example = "2025-09-11T08:58:56.121331664+02:00")]
     update_date: Option<DateTime<Local>>,
     /// Recipe's Author ID.
     #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
     author_id: Option<Uuid>,
+    /// State of the recipe in the publishing workflow. New recipes can be registered as `draft`, otherwise they are
+    /// submitted for publication.
+    #[serde(default)]
+    state: Option<RecipeState>,
 }
 
 /// Query object for the `Recipe` entity.
@@ -81,60 +121,171 @@ pub struct Recipe {
 /// a search in the `Cocktail` DB. Recipe queries are allowed using a single member or a combination of many. In that
 /// case, the intersection set of the result sets for each individual query is returned. Notice that set could be
 /// empty if all the result sets are disjoint.
-#[derive(Clone, Debug, Serialize, Deserialize, IntoParams)]
+#[derive(Clone, Debug, Serialize, Deserialize, IntoParams, ToSchema)]
 pub struct RecipeQuery {
     pub name: Option<String>,
-    #[param(example = "tequila,reposado")]
-    pub tags: Option<String>,
+    /// Comma-separated list of tags. Only recipes tagged with all of them are returned.
+    #[param(value_type = Option<String>, example = "tequila,reposado")]
+    #[schema(value_type = Option<String>, example = "tequila,reposado")]
+    #[serde(default, with = "crate::domain::tag::tag_list")]
+    pub tags: Option<Vec<Tag>>,
     pub rating: Option<StarRate>,
     pub category: Option<RecipeCategory>,
+    /// Comma-separated list of equipment. Recipes that need any of it are excluded from the results.
+    #[param(example = "blender,smoker")]
+    #[schema(example = "blender,smoker")]
+    pub equipment_excludes: Option<String>,
+    /// Only recipes whose estimated preparation time (minutes) is lower or equal are returned.
+    #[param(example = 10)]
+    #[schema(example = 10)]
+    pub max_prep_time: Option<u16>,
+    /// State of the recipes in the publishing workflow. Only published recipes are returned by default, other states
+    /// need an API key.
+    pub state: Option<RecipeState>,
+    /// Search expression, i.e. `tag:tiki AND (rum OR cachaça) -egg`. See [crate::domain::search_expression].
+    #[param(example = "tag:tiki AND (rum OR cachaça) -egg")]
+    #[schema(example = "tag:tiki AND (rum OR cachaça) -egg")]
+    pub q: Option<String>,
+    /// Comma-separated list of fields used to sort the recipes, prefixed by `-` for descending order. Accepted fields:
+    /// `name`, `rating`, `category`, `prep_time`, `creation_date` and `update_date`. Sorted by ID by default.
+    #[param(example = "-rating,name")]
+    #[schema(example = "-rating,name")]
+    pub sort: Option<String>,
+    /// Amount of recipes of the page, 20 by default. The maximum depends on the settings of the server (100 by
+    /// default).
+    #[param(example = 20)]
+    #[schema(example = 20)]
+    pub limit: Option<u32>,
+    /// Amount of recipes skipped, 0 by default.
+    #[param(example = 0)]
+    #[schema(example = 0)]
+    pub offset: Option<u32>,
 }
 
-/// Simple `enum` to represent a 5-star rating system.
-#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, PartialEq)]
-pub enum StarRate {
-    #[serde(rename = "0")]
-    Null = 0,
-    #[serde(rename = "1")]
-    One = 1,
-    #[serde(rename = "2")]
-    Two = 2,
-    #[serde(rename = "3")]
-    Three = 3,
-    #[serde(rename = "4")]
-    Four = 4,
-    #[serde(rename = "5")]
-    Five = 5,
+/// Rating of a recipe using a 5-star system with half-star steps.
+///
+/// # Description
+///
+/// Ratings range from 0.0 to 5.0 stars in steps of 0.5 stars, i.e. 3.5 is a valid rating while 3.7 is not. The value
+/// is stored as an amount of half-stars to avoid comparing floats.
+///
+/// Ratings are serialised as a JSON number. For backwards compatibility, the string representation used by the
+/// former integer ratings (`"0"` to `"5"`) is also accepted when deserialising, as well as decimal strings such as
+/// `"4.5"`. The latter is the format received when the rating is given as a query parameter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "RawStarRate", into = "f32")]
+pub struct StarRate(u8);
+
+impl StarRate {
+    /// Maximum rating of a recipe (stars).
+    pub const MAX: f32 = 5.0;
+
+    /// Build a new rating from an amount of stars.
+    ///
+    /// # Description
+    ///
+    /// An error is returned when the value is out of the range [0.0, 5.0] or isn't a multiple of 0.5.
+    pub fn new(stars: f32) -> Result<Self, DataDomainError> {
+        let halves = stars * 2.0;
+
+        if !(0.0..=StarRate::MAX).contains(&stars) || halves.fract() != 0.0 {
+            return Err(DataDomainError::InvalidData);
+        }
+
+        Ok(StarRate(halves as u8))
+    }
+
+    /// Get the amount of stars of the rating.
+    pub fn value(&self) -> f32 {
+        self.0 as f32 / 2.0
+    }
+
+    /// Build the rating nearest to an average of ratings, i.e. 4.3 turns into 4.5 stars. Halves are rounded up.
+    pub fn from_average(stars: f64) -> Self {
+        let halves = (stars * 2.0).round().clamp(0.0, StarRate::MAX as f64 * 2.0);
+
+        StarRate(halves as u8)
+    }
 }
 
-impl std::fmt::Display for StarRate {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = match self {
-            StarRate::One => "1",
-            StarRate::Two => "2",
-            StarRate::Three => "3",
-            StarRate::Four => "4",
-            StarRate::Five => "5",
-            StarRate::Null => "0",
-        };
+/// Accepted representations of a [StarRate] when deserialising.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawStarRate {
+    Number(f32),
+    Text(String),
+}
 
-        write!(f, "{s}")
+impl TryFrom<RawStarRate> for StarRate {
+    type Error = DataDomainError;
+
+    fn try_from(value: RawStarRate) -> Result<Self, Self::Error> {
+        match value {
+            RawStarRate::Number(stars) => StarRate::new(stars),
+            RawStarRate::Text(stars) => StarRate::try_from(stars.as_str()),
+        }
     }
 }
 
-impl From<StarRate> for u8 {
+impl TryFrom<&str> for StarRate {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let stars = value
+            .trim()
+            .parse::<f32>()
+            .map_err(|_| DataDomainError::InvalidData)?;
+
+        StarRate::new(stars)
+    }
+}
+
+impl TryFrom<f32> for StarRate {
+    type Error = DataDomainError;
+
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        StarRate::new(value)
+    }
+}
+
+impl From<StarRate> for f32 {
     fn from(value: StarRate) -> Self {
-        match value {
-            StarRate::One => 1,
-            StarRate::Two => 2,
-            StarRate::Three => 3,
-            StarRate::Four => 4,
-            StarRate::Five => 5,
-            _ => 0,
+        value.value()
+    }
+}
+
+impl std::fmt::Display for StarRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_multiple_of(2) {
+            write!(f, "{}", self.0 / 2)
+        } else {
+            write!(f, "{}.5", self.0 / 2)
         }
     }
 }
 
+impl<'s> ToSchema<'s> for StarRate {
+    fn schema() -> (
+        &'s str,
+        utoipa::openapi::RefOr<utoipa::openapi::schema::Schema>,
+    ) {
+        (
+            "StarRate",
+            ObjectBuilder::new()
+                .schema_type(SchemaType::Number)
+                .format(Some(SchemaFormat::KnownFormat(KnownFormat::Float)))
+                .description(Some(
+                    "Rating of a recipe (0.0 to 5.0 stars in steps of 0.5 stars).",
+                ))
+                .minimum(Some(0.0))
+                .maximum(Some(StarRate::MAX as f64))
+                .multiple_of(Some(0.5))
+                .example(Some(serde_json::json!(4.5)))
+                .into(),
+        )
+    }
+}
+
 /// Categories of recipes.
 ///
 /// # Description
@@ -171,6 +322,74 @@ pub struct RecipeContains {
     pub ingredient_id: Uuid,
 }
 
+impl RecipeContains {
+    /// Amount of the ingredient in the format stored in the DB, i.e. `2.5 cl`.
+    pub fn amount(&self) -> String {
+        format!("{} {}", self.quantity, self.unit)
+    }
+
+    /// Parse an amount stored in the DB (see [RecipeContains::amount]) into a quantity and its unit.
+    pub fn parse_amount(amount: &str) -> Result<(f32, QuantityUnit), DataDomainError> {
+        let (quantity, unit) = amount.split_once(' ').ok_or(DataDomainError::InvalidData)?;
+        let quantity = quantity
+            .parse::<f32>()
+            .map_err(|_| DataDomainError::InvalidData)?;
+
+        if !quantity.is_finite() {
+            return Err(DataDomainError::InvalidData);
+        }
+
+        Ok((quantity, QuantityUnit::try_from(unit)?))
+    }
+}
+
+/// Error of [Recipe::check_limits].
+fn limit_error(code: &'static str, max: usize, value: usize) -> ValidationError {
+    let mut e = ValidationError::new(code);
+    e.add_param(Cow::from("max"), &max);
+    e.add_param(Cow::from("value"), &value);
+    e
+}
+
+impl Validate for RecipeContains {
+    /// Check that the quantity is a sensible amount of the ingredient in its unit.
+    ///
+    /// # Description
+    ///
+    /// Quantities must be positive and up to [QuantityUnit::max_quantity]. Countable units, such as dashes and
+    /// drops, only accept whole numbers. Errors are reported for the field `quantity`.
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let max = self.unit.max_quantity();
+
+        if !(self.quantity > 0.0 && self.quantity <= max) {
+            let mut e = ValidationError::new("range");
+            e.add_param(Cow::from("min"), &0.0);
+            e.add_param(Cow::from("max"), &max);
+            e.add_param(Cow::from("unit"), &self.unit);
+            e.message = Some(Cow::from(format!(
+                "The quantity must be greater than 0 and up to {max} {}",
+                self.unit
+            )));
+            errors.add("quantity", e);
+        } else if self.unit.is_countable() && self.quantity.fract() != 0.0 {
+            let mut e = ValidationError::new("integer");
+            e.add_param(Cow::from("unit"), &self.unit);
+            e.message = Some(Cow::from(format!(
+                "Quantities in {} must be whole numbers",
+                self.unit
+            )));
+            errors.add("quantity", e);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
 /// `Enum` type that defines common types of units in cooking recipes.
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
@@ -191,6 +410,29 @@ pub enum QuantityUnit {
     Cups,
 }
 
+impl QuantityUnit {
+    /// Largest quantity of a single ingredient accepted in this unit. Limits are set around 2 litres, or the usual
+    /// maximum for the units that don't measure volume nor weight.
+    pub fn max_quantity(&self) -> f32 {
+        match self {
+            QuantityUnit::MilliLiter => 2000.0,
+            QuantityUnit::Grams => 1000.0,
+            QuantityUnit::Ounces => 68.0,
+            QuantityUnit::Cups => 8.0,
+            QuantityUnit::TableSpoon => 30.0,
+            QuantityUnit::TeaSpoon => 60.0,
+            QuantityUnit::Dash => 20.0,
+            QuantityUnit::Drops => 50.0,
+            QuantityUnit::Unit => 50.0,
+        }
+    }
+
+    /// Whether the amounts given in this unit are counted, thus they must be whole numbers.
+    pub fn is_countable(&self) -> bool {
+        matches!(self, QuantityUnit::Dash | QuantityUnit::Drops)
+    }
+}
+
 impl fmt::Display for QuantityUnit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -228,6 +470,159 @@ impl TryFrom<&str> for QuantityUnit {
     }
 }
 
+/// Image that illustrates a preparation step of a recipe, i.e. the right technique to shake a cocktail.
+///
+/// # Description
+///
+/// Steps are identified by their position in the list of steps of the recipe, starting at 0. The image is kept by the
+/// media storage, and it is served by `GET /media/{image_id}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct StepImage {
+    /// Position of the step in the list of steps of the recipe.
+    #[schema(example = 1)]
+    pub step: u16,
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b.jpg")]
+    pub image_id: String,
+}
+
+/// Attribution of a third-party recipe.
+///
+/// # Description
+///
+/// Recipes taken from books, magazines or websites shall credit their original source. Unlike the `url` of the
+/// recipe, which links to related content, the source identifies where the recipe was published first. Any
+/// combination of the fields is accepted, but at least one of them must be given.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_source"))]
+pub struct RecipeSource {
+    /// Title of the book or magazine. Up to 120 chars.
+    #[validate(length(min = 1), length(max = 120))]
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    #[schema(example = "The Savoy Cocktail Book")]
+    pub book: Option<String>,
+    /// Page of the book where the recipe is found.
+    #[validate(range(min = 1))]
+    #[schema(example = 42)]
+    pub page: Option<u16>,
+    /// URL of the original publication.
+    pub url: Option<WebsiteUrl>,
+    /// Name of the original author of the recipe. Up to 80 chars.
+    #[validate(length(min = 1), length(max = 80))]
+    #[serde(default, deserialize_with = "deserialize_optional_text")]
+    #[schema(example = "Harry Craddock")]
+    pub author: Option<String>,
+}
+
+impl RecipeSource {
+    /// Text that credits the source, i.e. `Harry Craddock, The Savoy Cocktail Book, p. 42`.
+    pub fn attribution(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(author) = &self.author {
+            parts.push(author.clone());
+        }
+        if let Some(book) = &self.book {
+            parts.push(book.clone());
+        }
+        if let Some(page) = self.page {
+            parts.push(format!("p. {page}"));
+        }
+        if let Some(url) = &self.url {
+            parts.push(url.to_string());
+        }
+
+        parts.join(", ")
+    }
+}
+
+fn validate_source(source: &RecipeSource) -> Result<(), ValidationError> {
+    if source.book.is_none() && source.url.is_none() && source.author.is_none() {
+        let mut e = ValidationError::new("empty_source");
+        e.message = Some(Cow::from(
+            "The source must include a book, a URL or an author",
+        ));
+        return Err(e);
+    }
+    if source.page.is_some() && source.book.is_none() {
+        let mut e = ValidationError::new("page_without_book");
+        e.message = Some(Cow::from("Pages are only accepted for books"));
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Bar equipment needed to prepare a recipe.
+///
+/// # Description
+///
+/// Most of the recipes only need basic equipment (a shaker or a mixing glass, a jigger...), but some of them need
+/// gear that is not that common at home, i.e. a blender or a smoker. Listing the equipment of a recipe allows users
+/// to filter out recipes that they can't prepare.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Equipment {
+    Shaker,
+    Strainer,
+    FineStrainer,
+    MixingGlass,
+    BarSpoon,
+    Jigger,
+    Muddler,
+    Juicer,
+    Blender,
+    Smoker,
+}
+
+impl fmt::Display for Equipment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Equipment::Shaker => "shaker",
+            Equipment::Strainer => "strainer",
+            Equipment::FineStrainer => "fine_strainer",
+            Equipment::MixingGlass => "mixing_glass",
+            Equipment::BarSpoon => "bar_spoon",
+            Equipment::Jigger => "jigger",
+            Equipment::Muddler => "muddler",
+            Equipment::Juicer => "juicer",
+            Equipment::Blender => "blender",
+            Equipment::Smoker => "smoker",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+impl TryFrom<&str> for Equipment {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "shaker" => Ok(Equipment::Shaker),
+            "strainer" => Ok(Equipment::Strainer),
+            "fine_strainer" => Ok(Equipment::FineStrainer),
+            "mixing_glass" => Ok(Equipment::MixingGlass),
+            "bar_spoon" => Ok(Equipment::BarSpoon),
+            "jigger" => Ok(Equipment::Jigger),
+            "muddler" => Ok(Equipment::Muddler),
+            "juicer" => Ok(Equipment::Juicer),
+            "blender" => Ok(Equipment::Blender),
+            "smoker" => Ok(Equipment::Smoker),
+            _ => Err(DataDomainError::InvalidData),
+        }
+    }
+}
+
+impl Equipment {
+    /// Parse a comma-separated list of equipment, i.e. `blender,smoker`.
+    pub fn parse_list(value: &str) -> Result<Vec<Equipment>, DataDomainError> {
+        value
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(Equipment::try_from)
+            .collect()
+    }
+}
+
 impl TryFrom<&str> for RecipeCategory {
     type Error = DataDomainError;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
@@ -267,13 +662,210 @@ impl fmt::Display for RecipeCategory {
     }
 }
 
+/// States of the publishing workflow of the recipes.
+///
+/// # Description
+///
+/// Recipes follow the workflow `draft → submitted → published → archived`. Only published recipes are shown to the
+/// public. The allowed moves between states are described by [RecipeTransition].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RecipeState {
+    /// The recipe is being written by its author.
+    Draft,
+    /// The recipe waits for the review of an administrator.
+    Submitted,
+    /// The recipe is public.
+    #[default]
+    Published,
+    /// The recipe was withdrawn from the public catalogue.
+    Archived,
+}
+
+impl RecipeState {
+    /// Get the state reached by applying a transition, or `None` when the transition is not allowed from this state.
+    pub fn apply(self, transition: RecipeTransition) -> Option<RecipeState> {
+        match (self, transition) {
+            (RecipeState::Draft, RecipeTransition::Submit) => Some(RecipeState::Submitted),
+            (RecipeState::Submitted, RecipeTransition::Withdraw) => Some(RecipeState::Draft),
+            (RecipeState::Submitted, RecipeTransition::Publish) => Some(RecipeState::Published),
+            (RecipeState::Submitted, RecipeTransition::Reject) => Some(RecipeState::Draft),
+            (RecipeState::Published, RecipeTransition::Archive) => Some(RecipeState::Archived),
+            (RecipeState::Archived, RecipeTransition::Restore) => Some(RecipeState::Draft),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<&str> for RecipeState {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "draft" => Ok(RecipeState::Draft),
+            "submitted" => Ok(RecipeState::Submitted),
+            "published" => Ok(RecipeState::Published),
+            "archived" => Ok(RecipeState::Archived),
+            _ => Err(DataDomainError::InvalidData),
+        }
+    }
+}
+
+impl fmt::Display for RecipeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ss = match self {
+            RecipeState::Draft => "draft",
+            RecipeState::Submitted => "submitted",
+            RecipeState::Published => "published",
+            RecipeState::Archived => "archived",
+        };
+
+        write!(f, "{ss}")
+    }
+}
+
+/// Moves between the states of the publishing workflow (see [RecipeState]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RecipeTransition {
+    /// Send a draft to publication. Recipes of authors with published recipes skip the review.
+    Submit,
+    /// Take a submitted recipe back to draft.
+    Withdraw,
+    /// Accept a submitted recipe (administrators only).
+    Publish,
+    /// Send a submitted recipe back to draft (administrators only).
+    Reject,
+    /// Withdraw a published recipe from the public catalogue.
+    Archive,
+    /// Take an archived recipe back to draft.
+    Restore,
+}
+
+impl RecipeTransition {
+    /// Whether the transition is reserved to administrators.
+    pub fn is_review(&self) -> bool {
+        matches!(self, RecipeTransition::Publish | RecipeTransition::Reject)
+    }
+}
+
+/// Licenses of the content of the recipes.
+///
+/// # Description
+///
+/// The license tells the consumers of the API whether they can republish a recipe, and under which terms. Recipes
+/// that don't declare a license get the default license of the server (`application.default_recipe_license`), which
+/// is shared with the handlers using `web::Data`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum RecipeLicense {
+    /// Creative Commons Attribution 4.0.
+    #[serde(rename = "cc-by")]
+    CcBy,
+    /// Creative Commons Attribution-ShareAlike 4.0.
+    #[serde(rename = "cc-by-sa")]
+    CcBySa,
+    /// Creative Commons Attribution-NonCommercial 4.0.
+    #[serde(rename = "cc-by-nc")]
+    CcByNc,
+    /// Creative Commons Zero, i.e. public domain.
+    #[serde(rename = "cc0")]
+    Cc0,
+    /// The recipe can't be republished without the permission of its author.
+    #[default]
+    #[serde(rename = "all-rights-reserved")]
+    AllRightsReserved,
+}
+
+impl RecipeLicense {
+    /// URL of the legal code of the license, when there is one.
+    pub fn url(&self) -> Option<&'static str> {
+        match self {
+            RecipeLicense::CcBy => Some("https://creativecommons.org/licenses/by/4.0/"),
+            RecipeLicense::CcBySa => Some("https://creativecommons.org/licenses/by-sa/4.0/"),
+            RecipeLicense::CcByNc => Some("https://creativecommons.org/licenses/by-nc/4.0/"),
+            RecipeLicense::Cc0 => Some("https://creativecommons.org/publicdomain/zero/1.0/"),
+            RecipeLicense::AllRightsReserved => None,
+        }
+    }
+
+    /// Human readable name of the license.
+    pub fn label(&self) -> &'static str {
+        match self {
+            RecipeLicense::CcBy => "CC BY 4.0",
+            RecipeLicense::CcBySa => "CC BY-SA 4.0",
+            RecipeLicense::CcByNc => "CC BY-NC 4.0",
+            RecipeLicense::Cc0 => "CC0 1.0",
+            RecipeLicense::AllRightsReserved => "All rights reserved",
+        }
+    }
+}
+
+impl TryFrom<&str> for RecipeLicense {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "cc-by" => Ok(RecipeLicense::CcBy),
+            "cc-by-sa" => Ok(RecipeLicense::CcBySa),
+            "cc-by-nc" => Ok(RecipeLicense::CcByNc),
+            "cc0" => Ok(RecipeLicense::Cc0),
+            "all-rights-reserved" => Ok(RecipeLicense::AllRightsReserved),
+            _ => Err(DataDomainError::InvalidData),
+        }
+    }
+}
+
+impl fmt::Display for RecipeLicense {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            RecipeLicense::CcBy => "cc-by",
+            RecipeLicense::CcBySa => "cc-by-sa",
+            RecipeLicense::CcByNc => "cc-by-nc",
+            RecipeLicense::Cc0 => "cc0",
+            RecipeLicense::AllRightsReserved => "all-rights-reserved",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+/// Limits of the size of the recipes.
+///
+/// # Description
+///
+/// Limits protect the DB and the frontends from pathological recipes. The limits of the application are shared with the
+/// handlers using `web::Data`, and they are checked by [Recipe::check_limits].
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RecipeLimits {
+    /// Maximum amount of ingredients of a recipe.
+    pub max_ingredients: usize,
+    /// Maximum amount of preparation steps of a recipe.
+    pub max_steps: usize,
+    /// Maximum length (chars) of a preparation step.
+    pub max_step_length: usize,
+}
+
+impl Default for RecipeLimits {
+    fn default() -> Self {
+        RecipeLimits {
+            max_ingredients: 30,
+            max_steps: 40,
+            max_step_length: 500,
+        }
+    }
+}
+
 impl Recipe {
     /// Constructor of the object [Recipe].
     ///
     /// # Description
     ///
     /// This function creates a new instance of [Recipe] using the given arguments. Arguments are checked to detect
-    /// invalid values.
+    /// invalid values. The name, description and steps are sanitized (see [crate::domain::sanitize]).
+    ///
+    /// Recipes are also built from the content of the DB, so the [RecipeLimits] are not checked here, see
+    /// [Recipe::check_limits].
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: Option<Uuid>,
@@ -286,6 +878,8 @@ impl Recipe {
         url: Option<&str>,
         ingredients: &[RecipeContains],
         steps: &[&str],
+        equipment: Option<&[Equipment]>,
+        prep_time_minutes: Option<u16>,
         author_id: Option<&str>,
     ) -> Result<Self, DataDomainError> {
         let category: RecipeCategory = category.try_into()?;
@@ -294,31 +888,40 @@ impl Recipe {
 
         let recipe = Recipe {
             id,
-            name: name.into(),
+            name: ResourceName::try_from(name)?,
+            short_id: None,
+            slug: None,
             image_id: image_id.map(String::from),
             author_tags: author_tags.map(Vec::from),
             tags: tags.map(Vec::from),
-            category,
-            rating: Some(StarRate::Null),
-            description: description.map(String::from),
-            url: url.map(String::from),
+            category: Some(category),
+            rating: Some(StarRate::default()),
+            description: description.map(sanitize),
+            url: url.map(WebsiteUrl::try_from).transpose()?,
+            source: None,
+            license: None,
             ingredients: Vec::from(ingredients),
-            steps: steps.iter().map(|c| String::from(*c)).collect(),
+            steps: steps.iter().map(|c| sanitize(c)).collect(),
+            step_images: None,
+            equipment: equipment.map(Vec::from),
+            prep_time_minutes,
             author_id: if let Some(id) = author_id {
-                Some(Uuid::from_str(id).map_err(|_| {
-                    error!("Wrong string given as Author ID: {id}");
-                    DataDomainError::InvalidId
-                })?)
+                Some(
+                    ResourceId::try_from(id)
+                        .inspect_err(|_| error!("Wrong string given as Author ID: {id}"))?
+                        .into(),
+                )
             } else {
                 None
             },
             creation_date: Some(Local::now()),
             update_date: None,
+            state: None,
         };
 
         recipe.validate().map_err(|e| {
             error!("{e}");
-            DataDomainError::InvalidFormData
+            DataDomainError::InvalidParams { source: e }
         })?;
 
         Ok(recipe)
@@ -329,7 +932,17 @@ impl Recipe {
     }
 
     pub fn name(&self) -> &str {
-        &self.name
+        self.name.as_str()
+    }
+
+    /// Short public ID of the recipe, as stored in the DB.
+    pub fn short_id(&self) -> Option<&ShortId> {
+        self.short_id.as_ref()
+    }
+
+    /// Slug of the recipe, as stored in the DB.
+    pub fn slug(&self) -> Option<&str> {
+        self.slug.as_deref()
     }
 
     pub fn image_id(&self) -> Option<&str> {
@@ -344,14 +957,92 @@ impl Recipe {
         self.tags.as_deref()
     }
 
+    /// Get the category of the recipe. When the recipe has no category, the one suggested by
+    /// [crate::domain::classifier] is returned.
     pub fn category(&self) -> RecipeCategory {
-        self.category.clone()
+        match &self.category {
+            Some(category) => category.clone(),
+            None => classify(&self.features()).category,
+        }
+    }
+
+    /// Get the features of the recipe used to guess its difficulty.
+    pub fn features(&self) -> RecipeFeatures<'_> {
+        RecipeFeatures {
+            ingredients: self.ingredients.len(),
+            steps: &self.steps,
+            equipment: self.equipment().unwrap_or_default(),
+            prep_time_minutes: self.prep_time_minutes,
+        }
+    }
+
+    /// Set the rating of the recipe. Ratings are computed by the backend, thus they are not accepted by [Recipe::new].
+    pub fn with_rating(mut self, rating: StarRate) -> Self {
+        self.rating = Some(rating);
+        self
+    }
+
+    /// Convert the amounts of the ingredients to the given unit system (see [crate::domain::units]).
+    pub fn with_unit_system(mut self, system: UnitSystem) -> Self {
+        for ingredient in self.ingredients.iter_mut() {
+            (ingredient.quantity, ingredient.unit) =
+                convert_amount(ingredient.quantity, ingredient.unit, system);
+        }
+        self
+    }
+
+    /// Set the license of the recipe.
+    pub fn with_license(mut self, license: Option<RecipeLicense>) -> Self {
+        self.license = license;
+        self
+    }
+
+    /// Set the given license when the recipe declares no license, usually the default license of the server.
+    pub fn with_default_license(mut self, license: RecipeLicense) -> Self {
+        self.license = Some(self.license.unwrap_or(license));
+        self
+    }
+
+    /// Set the attribution of the recipe. The source is checked when the recipe is validated.
+    pub fn with_source(mut self, source: Option<RecipeSource>) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Set the images of the preparation steps. Images of steps that the recipe doesn't have are discarded.
+    pub fn with_step_images(mut self, images: Vec<StepImage>) -> Self {
+        let steps = self.steps.len();
+        self.step_images = Some(
+            images
+                .into_iter()
+                .filter(|image| (image.step as usize) < steps)
+                .collect(),
+        );
+        self
+    }
+
+    /// Set the state of the recipe in the publishing workflow.
+    pub fn with_state(mut self, state: RecipeState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Set the short public ID of the recipe, as stored in the DB.
+    pub fn with_short_id(mut self, short_id: Option<ShortId>) -> Self {
+        self.short_id = short_id;
+        self
+    }
+
+    /// Set the slug of the recipe, as stored in the DB.
+    pub fn with_slug(mut self, slug: Option<String>) -> Self {
+        self.slug = slug;
+        self
     }
 
     pub fn rating(&self) -> StarRate {
         match &self.rating {
-            Some(rating) => rating.clone(),
-            None => StarRate::Null,
+            Some(rating) => *rating,
+            None => StarRate::default(),
         }
     }
 
@@ -360,7 +1051,17 @@ impl Recipe {
     }
 
     pub fn url(&self) -> Option<&str> {
-        self.url.as_deref()
+        self.url.as_ref().map(WebsiteUrl::as_str)
+    }
+
+    pub fn source(&self) -> Option<&RecipeSource> {
+        self.source.as_ref()
+    }
+
+    /// Get the license of the recipe. When the recipe declares no license, [RecipeLicense::default] is returned, so
+    /// handlers set the default license of the server before storing a recipe (see [Recipe::with_default_license]).
+    pub fn license(&self) -> RecipeLicense {
+        self.license.unwrap_or_default()
     }
 
     pub fn ingredients(&self) -> &[RecipeContains] {
@@ -371,6 +1072,55 @@ impl Recipe {
         &self.steps
     }
 
+    pub fn step_images(&self) -> Option<&[StepImage]> {
+        self.step_images.as_deref()
+    }
+
+    /// Check the size of the recipe against the given [RecipeLimits].
+    ///
+    /// # Description
+    ///
+    /// Errors are reported for the fields `ingredients` and `steps`. Steps that are too long are identified by
+    /// their index in the error's params.
+    pub fn check_limits(&self, limits: &RecipeLimits) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if self.ingredients.len() > limits.max_ingredients {
+            errors.add(
+                "ingredients",
+                limit_error("length", limits.max_ingredients, self.ingredients.len()),
+            );
+        }
+        if self.steps.len() > limits.max_steps {
+            errors.add(
+                "steps",
+                limit_error("length", limits.max_steps, self.steps.len()),
+            );
+        }
+        for (index, step) in self.steps.iter().enumerate() {
+            let length = step.chars().count();
+            if length > limits.max_step_length {
+                let mut e = limit_error("step_length", limits.max_step_length, length);
+                e.add_param(Cow::from("index"), &index);
+                errors.add("steps", e);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn equipment(&self) -> Option<&[Equipment]> {
+        self.equipment.as_deref()
+    }
+
+    pub fn prep_time_minutes(&self) -> Option<u16> {
+        self.prep_time_minutes
+    }
+
     pub fn creation_date(&self) -> Option<DateTime<Local>> {
         self.creation_date
     }
@@ -382,32 +1132,57 @@ impl Recipe {
     pub fn owner(&self) -> Option<Uuid> {
         self.author_id
     }
+
+    pub fn state(&self) -> Option<RecipeState> {
+        self.state
+    }
 }
 
 impl std::fmt::Display for RecipeQuery {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut ss = String::new();
 
-        if self.name.is_some() {
-            ss.insert_str(ss.len(), &format!("name={} ", self.name.as_ref().unwrap()));
+        if let Some(name) = self.name.as_ref() {
+            ss.insert_str(ss.len(), &format!("name={name} "));
         }
 
-        if self.tags.is_some() {
-            ss.insert_str(ss.len(), &format!("tag={} ", self.tags.as_ref().unwrap()));
+        if let Some(tags) = self.tags.as_ref() {
+            let tags = tags
+                .iter()
+                .map(|t| t.identifier.as_str())
+                .collect::<Vec<&str>>()
+                .join(",");
+            ss.insert_str(ss.len(), &format!("tag={tags} "));
         }
 
-        if self.rating.is_some() {
-            ss.insert_str(
-                ss.len(),
-                &format!("rating={} ", self.rating.as_ref().unwrap()),
-            );
+        if let Some(rating) = self.rating.as_ref() {
+            ss.insert_str(ss.len(), &format!("rating={rating} "));
         }
 
-        if self.category.is_some() {
-            let category = self.category.as_ref().unwrap();
+        if let Some(category) = self.category.as_ref() {
             ss.insert_str(ss.len(), &format!("category={category} "));
         }
 
+        if let Some(equipment) = self.equipment_excludes.as_ref() {
+            ss.insert_str(ss.len(), &format!("equipment_excludes={equipment} "));
+        }
+
+        if let Some(max_prep_time) = self.max_prep_time.as_ref() {
+            ss.insert_str(ss.len(), &format!("max_prep_time={max_prep_time} "));
+        }
+
+        if let Some(state) = self.state.as_ref() {
+            ss.insert_str(ss.len(), &format!("state={state} "));
+        }
+
+        if let Some(q) = self.q.as_ref() {
+            ss.insert_str(ss.len(), &format!("q={q} "));
+        }
+
+        if let Some(sort) = self.sort.as_ref() {
+            ss.insert_str(ss.len(), &format!("sort={sort} "));
+        }
+
         write!(f, "Search tokens: {}", ss.strip_suffix(" ").unwrap())
     }
 }
@@ -416,6 +1191,7 @@ impl std::fmt::Display for RecipeQuery {
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
     use rstest::*;
     use uuid::Uuid;
 
@@ -430,6 +1206,8 @@ mod tests {
         pub url: Option<String>,
         pub ingredients: Vec<RecipeContains>,
         pub steps: &'a [&'a str],
+        pub equipment: Option<Vec<Equipment>>,
+        pub prep_time_minutes: Option<u16>,
         pub author_id: String,
     }
 
@@ -463,6 +1241,8 @@ mod tests {
                 },
             ]),
             steps: &["Pour all the ingredients in a shaker", "Shake and serve"],
+            equipment: Some(Vec::from([Equipment::Shaker, Equipment::Strainer])),
+            prep_time_minutes: Some(5),
             author_id: Uuid::now_v7().to_string(),
         }
     }
@@ -480,6 +1260,8 @@ mod tests {
             template_recipe.url.as_deref(),
             &template_recipe.ingredients,
             template_recipe.steps,
+            template_recipe.equipment.as_deref(),
+            template_recipe.prep_time_minutes,
             Some(&template_recipe.author_id.to_string()),
         );
 
@@ -488,7 +1270,7 @@ mod tests {
         let recipe = recipe.unwrap();
 
         assert_eq!(recipe.id.unwrap(), template_recipe.id);
-        assert_eq!(recipe.name, template_recipe.name);
+        assert_eq!(recipe.name(), template_recipe.name);
         assert_eq!(
             recipe.image_id.as_deref(),
             template_recipe.image_id.as_deref()
@@ -496,17 +1278,18 @@ mod tests {
         assert_eq!(recipe.author_tags, template_recipe.author_tags);
         assert_eq!(recipe.tags, template_recipe.tags);
         assert_eq!(
-            recipe.category.to_string(),
+            recipe.category().to_string(),
             template_recipe.category.to_string()
         );
-        assert_eq!(recipe.rating.unwrap(), StarRate::Null);
+        assert_eq!(recipe.rating.unwrap(), StarRate::default());
         assert_eq!(
             recipe.description.as_deref(),
             template_recipe.description.as_deref()
         );
-        assert_eq!(recipe.url, template_recipe.url);
+        assert_eq!(recipe.url(), template_recipe.url.as_deref());
         assert_eq!(recipe.ingredients, template_recipe.ingredients);
         assert_eq!(recipe.steps, template_recipe.steps);
+        assert_eq!(recipe.equipment, template_recipe.equipment);
         assert_eq!(recipe.update_date, None);
         assert_eq!(
             recipe.author_id.unwrap().to_string(),
@@ -528,6 +1311,8 @@ mod tests {
             template_recipe.url.as_deref(),
             &template_recipe.ingredients,
             template_recipe.steps,
+            template_recipe.equipment.as_deref(),
+            template_recipe.prep_time_minutes,
             Some(&template_recipe.author_id.to_string()),
         );
 
@@ -545,10 +1330,33 @@ mod tests {
             template_recipe.url.as_deref(),
             &template_recipe.ingredients,
             template_recipe.steps,
+            template_recipe.equipment.as_deref(),
+            template_recipe.prep_time_minutes,
             Some(&template_recipe.author_id.to_string()),
         );
 
         assert!(recipe.is_err());
+
+        // Invalid preparation time test cases
+        for prep_time in [0, 1441] {
+            let recipe = Recipe::new(
+                Some(template_recipe.id),
+                &template_recipe.name,
+                template_recipe.image_id.as_deref(),
+                template_recipe.author_tags.as_deref(),
+                template_recipe.tags.as_deref(),
+                &template_recipe.category,
+                template_recipe.description.as_deref(),
+                template_recipe.url.as_deref(),
+                &template_recipe.ingredients,
+                template_recipe.steps,
+                template_recipe.equipment.as_deref(),
+                Some(prep_time),
+                Some(&template_recipe.author_id.to_string()),
+            );
+
+            assert!(recipe.is_err());
+        }
     }
 
     #[rstest]
@@ -564,6 +1372,8 @@ mod tests {
             template_recipe.url.as_deref(),
             &template_recipe.ingredients,
             template_recipe.steps,
+            template_recipe.equipment.as_deref(),
+            template_recipe.prep_time_minutes,
             Some(&template_recipe.author_id.to_string()),
         );
 
@@ -573,24 +1383,23 @@ mod tests {
 
         assert_eq!(recipe.id().unwrap(), template_recipe.id);
         assert_eq!(recipe.name(), template_recipe.name);
-        assert_eq!(
-            recipe.image_id().as_deref(),
-            template_recipe.image_id.as_deref()
-        );
+        assert_eq!(recipe.image_id(), template_recipe.image_id.as_deref());
         assert_eq!(recipe.author_tags(), template_recipe.author_tags.as_deref());
         assert_eq!(recipe.tags(), template_recipe.tags.as_deref());
         assert_eq!(
             recipe.category().to_string(),
             template_recipe.category.to_string()
         );
-        assert_eq!(recipe.rating(), StarRate::Null);
-        assert_eq!(
-            recipe.description().as_deref(),
-            template_recipe.description.as_deref()
-        );
+        assert_eq!(recipe.rating(), StarRate::default());
+        assert_eq!(recipe.description(), template_recipe.description.as_deref());
         assert_eq!(recipe.url(), template_recipe.url.as_deref());
         assert_eq!(recipe.ingredients(), template_recipe.ingredients);
         assert_eq!(recipe.steps(), template_recipe.steps);
+        assert_eq!(recipe.equipment(), template_recipe.equipment.as_deref());
+        assert_eq!(
+            recipe.prep_time_minutes(),
+            template_recipe.prep_time_minutes
+        );
         assert_eq!(recipe.update_date(), None);
         assert_eq!(
             recipe.owner().unwrap().to_string(),
@@ -632,53 +1441,459 @@ mod tests {
     }
 
     #[rstest]
-    #[case(StarRate::Null, "0")]
-    #[case(StarRate::One, "1")]
-    #[case(StarRate::Two, "2")]
-    #[case(StarRate::Three, "3")]
-    #[case(StarRate::Four, "4")]
-    #[case(StarRate::Five, "5")]
-    fn rating_converts_to_string(#[case] rating: StarRate, #[case] value: &str) {
-        let category: String = format!("{rating}");
-        assert_eq!(&category, value);
+    #[case(0.0, "0")]
+    #[case(1.0, "1")]
+    #[case(2.5, "2.5")]
+    #[case(4.5, "4.5")]
+    #[case(5.0, "5")]
+    fn rating_converts_to_string(#[case] stars: f32, #[case] value: &str) {
+        let rating = StarRate::new(stars).expect("Failed to build a valid rating");
+        assert_eq!(&format!("{rating}"), value);
+        assert_eq!(rating.value(), stars);
+    }
+
+    #[rstest]
+    #[case(-0.5)]
+    #[case(3.7)]
+    #[case(5.5)]
+    #[case(f32::NAN)]
+    fn invalid_ratings_are_rejected(#[case] stars: f32) {
+        assert!(StarRate::new(stars).is_err());
+    }
+
+    #[rstest]
+    #[case("\"0\"", 0.0)]
+    #[case("\"4\"", 4.0)]
+    #[case("\"3.5\"", 3.5)]
+    #[case("3.5", 3.5)]
+    #[case("5", 5.0)]
+    fn rating_deserialises_old_and_new_formats(#[case] json: &str, #[case] stars: f32) {
+        let rating: StarRate = serde_json::from_str(json).expect("Failed to parse a valid rating");
+        assert_eq!(rating.value(), stars);
+        assert_eq!(
+            serde_json::to_string(&rating).unwrap(),
+            format!("{stars:?}")
+        );
+        assert!(serde_json::from_str::<StarRate>("\"3.7\"").is_err());
+        assert!(serde_json::from_str::<StarRate>("\"five\"").is_err());
+    }
+
+    #[rstest]
+    #[case(0.0, 0.0)]
+    #[case(4.3, 4.5)]
+    #[case(4.2, 4.0)]
+    #[case(3.25, 3.5)]
+    #[case(5.0, 5.0)]
+    #[case(7.0, 5.0)]
+    #[case(f64::NAN, 0.0)]
+    fn averages_are_rounded_to_half_stars(#[case] average: f64, #[case] stars: f32) {
+        assert_eq!(StarRate::from_average(average).value(), stars);
+    }
+
+    #[rstest]
+    fn ratings_are_ordered() {
+        assert!(StarRate::new(4.5).unwrap() > StarRate::new(4.0).unwrap());
+        assert!(StarRate::default() < StarRate::new(0.5).unwrap());
     }
 
     #[rstest]
     fn recipe_query_format() {
-        let name = Some("Margarita".to_owned());
-        let tags = None;
-        let rating = None;
-        let category = Some(RecipeCategory::Medium);
+        let name = "Margarita".to_owned();
+        let category = RecipeCategory::Medium;
         let test_string = RecipeQuery {
-            name: name.clone(),
-            tags,
-            rating,
-            category: category.clone(),
+            name: Some(name.clone()),
+            tags: None,
+            rating: None,
+            category: Some(category.clone()),
+            equipment_excludes: None,
+            max_prep_time: Some(10),
+            state: None,
+            q: None,
+            sort: None,
+            limit: None,
+            offset: None,
         };
-        let formatted_string = format!(
-            "Search tokens: name={} category={}",
-            name.unwrap(),
-            category.unwrap()
-        );
+        let formatted_string =
+            format!("Search tokens: name={name} category={category} max_prep_time=10");
         let test_format = format!("{test_string}");
         assert_eq!(test_format, formatted_string);
 
-        let name = None;
-        let tags = Some("mocktail".to_owned());
-        let rating = Some(StarRate::Null);
-        let category = None;
+        let tags = "mocktail,sour".to_owned();
+        let rating = StarRate::new(3.5).unwrap();
         let test_string = RecipeQuery {
-            name,
-            tags: tags.clone(),
-            rating: rating.clone(),
-            category,
+            name: None,
+            tags: Some(Tag::parse_list(&tags).unwrap()),
+            rating: Some(rating),
+            category: None,
+            equipment_excludes: Some("blender".into()),
+            max_prep_time: None,
+            state: Some(RecipeState::Draft),
+            q: Some("rum -egg".into()),
+            sort: Some("-rating".into()),
+            limit: Some(10),
+            offset: None,
         };
         let formatted_string = format!(
-            "Search tokens: tag={} rating={}",
-            tags.unwrap(),
-            rating.unwrap()
+            "Search tokens: tag={tags} rating={rating} equipment_excludes=blender state=draft q=rum -egg sort=-rating"
         );
         let test_format = format!("{test_string}");
         assert_eq!(test_format, formatted_string);
     }
+
+    #[rstest]
+    #[case(
+        RecipeState::Draft,
+        RecipeTransition::Submit,
+        Some(RecipeState::Submitted)
+    )]
+    #[case(
+        RecipeState::Submitted,
+        RecipeTransition::Publish,
+        Some(RecipeState::Published)
+    )]
+    #[case(
+        RecipeState::Submitted,
+        RecipeTransition::Reject,
+        Some(RecipeState::Draft)
+    )]
+    #[case(
+        RecipeState::Published,
+        RecipeTransition::Archive,
+        Some(RecipeState::Archived)
+    )]
+    #[case(
+        RecipeState::Archived,
+        RecipeTransition::Restore,
+        Some(RecipeState::Draft)
+    )]
+    #[case(RecipeState::Draft, RecipeTransition::Publish, None)]
+    #[case(RecipeState::Published, RecipeTransition::Submit, None)]
+    #[case(RecipeState::Archived, RecipeTransition::Archive, None)]
+    fn recipe_state_transitions(
+        #[case] state: RecipeState,
+        #[case] transition: RecipeTransition,
+        #[case] expected: Option<RecipeState>,
+    ) {
+        assert_eq!(state.apply(transition), expected);
+        if let Some(expected) = expected {
+            assert_eq!(
+                RecipeState::try_from(expected.to_string().as_str()).ok(),
+                Some(expected)
+            );
+        }
+    }
+
+    #[rstest]
+    fn recipe_text_is_sanitized() {
+        let recipe: Recipe = serde_json::from_value(serde_json::json!({
+            "name": "Daiquiri<script>",
+            "description": "A <b>classic</b> sour.",
+            "ingredients": [],
+            "steps": ["Shake <img src=x onerror=alert(1)>hard."],
+        }))
+        .expect("Failed to parse a valid recipe");
+        assert_eq!(recipe.name(), "Daiquiri");
+        assert_eq!(recipe.description(), Some("A classic sour."));
+        assert_eq!(recipe.steps(), ["Shake hard.".to_owned()]);
+    }
+
+    #[rstest]
+    fn recipe_query_parses_tags() {
+        let query =
+            actix_web::web::Query::<RecipeQuery>::from_query("tags=Tequila,reposado&rating=4.5")
+                .expect("Failed to parse a valid query");
+        assert_eq!(
+            query.tags,
+            Some(vec![
+                Tag::new("tequila").unwrap(),
+                Tag::new("reposado").unwrap()
+            ])
+        );
+        assert_eq!(query.rating, Some(StarRate::new(4.5).unwrap()));
+
+        assert!(actix_web::web::Query::<RecipeQuery>::from_query("tags=tequila,a(tag)").is_err());
+        assert!(actix_web::web::Query::<RecipeQuery>::from_query("rating=3.7").is_err());
+    }
+
+    #[rstest]
+    #[case("blender", vec![Equipment::Blender])]
+    #[case("Blender, smoker", vec![Equipment::Blender, Equipment::Smoker])]
+    #[case("mixing_glass,", vec![Equipment::MixingGlass])]
+    fn equipment_list_is_parsed(#[case] input: &str, #[case] expected: Vec<Equipment>) {
+        assert_eq!(Equipment::parse_list(input).unwrap(), expected);
+    }
+
+    #[rstest]
+    fn wrong_equipment_fails_to_parse() {
+        assert!(matches!(
+            Equipment::parse_list("blender,spoon"),
+            Err(DataDomainError::InvalidData)
+        ));
+    }
+
+    #[rstest]
+    fn equipment_round_trip() {
+        for equipment in [
+            Equipment::FineStrainer,
+            Equipment::BarSpoon,
+            Equipment::Juicer,
+        ] {
+            let json = serde_json::to_string(&equipment).unwrap();
+            assert_eq!(json, format!("\"{equipment}\""));
+            assert_eq!(
+                Equipment::try_from(equipment.to_string().as_str()).unwrap(),
+                equipment
+            );
+        }
+    }
+
+    #[rstest]
+    #[case(30.0, QuantityUnit::MilliLiter, true)]
+    #[case(2000.0, QuantityUnit::MilliLiter, true)]
+    #[case(2000.5, QuantityUnit::MilliLiter, false)]
+    #[case(0.0, QuantityUnit::MilliLiter, false)]
+    #[case(-15.0, QuantityUnit::Grams, false)]
+    #[case(f32::NAN, QuantityUnit::Ounces, false)]
+    #[case(0.5, QuantityUnit::Unit, true)]
+    #[case(2.0, QuantityUnit::Dash, true)]
+    #[case(1.5, QuantityUnit::Dash, false)]
+    #[case(0.5, QuantityUnit::Drops, false)]
+    fn quantities_are_checked_against_their_units(
+        #[case] quantity: f32,
+        #[case] unit: QuantityUnit,
+        #[case] valid: bool,
+    ) {
+        let contains = RecipeContains {
+            quantity,
+            unit,
+            ingredient_id: Uuid::now_v7(),
+        };
+        assert_eq!(contains.validate().is_ok(), valid);
+    }
+
+    #[rstest]
+    fn invalid_quantities_are_reported_per_field(mut template_recipe: TemplateRecipe) {
+        template_recipe.ingredients[1].quantity = -20.0;
+        let result = Recipe::new(
+            Some(template_recipe.id),
+            &template_recipe.name,
+            template_recipe.image_id.as_deref(),
+            template_recipe.author_tags.as_deref(),
+            template_recipe.tags.as_deref(),
+            &template_recipe.category,
+            template_recipe.description.as_deref(),
+            template_recipe.url.as_deref(),
+            &template_recipe.ingredients,
+            template_recipe.steps,
+            template_recipe.equipment.as_deref(),
+            template_recipe.prep_time_minutes,
+            Some(&template_recipe.author_id.to_string()),
+        );
+
+        let Err(DataDomainError::InvalidParams { source }) = result else {
+            panic!("The recipe was built using a negative quantity");
+        };
+        let errors = serde_json::to_value(source).unwrap();
+        assert_eq!(errors["ingredients"]["1"]["quantity"][0]["code"], "range");
+        assert!(errors["ingredients"].get("0").is_none());
+    }
+
+    #[rstest]
+    fn oversized_recipes_are_rejected(template_recipe: TemplateRecipe) {
+        let limits = RecipeLimits::default();
+        let check = |ingredients: &[RecipeContains], steps: &[&str]| {
+            Recipe::new(
+                Some(template_recipe.id),
+                &template_recipe.name,
+                None,
+                None,
+                None,
+                &template_recipe.category,
+                None,
+                None,
+                ingredients,
+                steps,
+                None,
+                None,
+                None,
+            )
+            .expect("Recipes are built regardless of their size")
+            .check_limits(&limits)
+        };
+        let ingredients = vec![template_recipe.ingredients[1]; limits.max_ingredients];
+        let long_step = "a".repeat(limits.max_step_length + 1);
+
+        assert!(check(&ingredients, template_recipe.steps).is_ok());
+        let Err(errors) = check(
+            &vec![template_recipe.ingredients[1]; limits.max_ingredients + 1],
+            template_recipe.steps,
+        ) else {
+            panic!("The recipe passed the check using too many ingredients");
+        };
+        assert!(errors.field_errors().contains_key("ingredients"));
+
+        let Err(errors) = check(&ingredients, &["Shake", &long_step]) else {
+            panic!("The recipe passed the check using a step that is too long");
+        };
+        let errors = serde_json::to_value(errors).unwrap();
+        assert_eq!(errors["steps"][0]["code"], "step_length");
+        assert_eq!(errors["steps"][0]["params"]["index"], 1);
+
+        assert!(check(&ingredients, &vec!["Shake"; limits.max_steps + 1]).is_err());
+    }
+
+    #[rstest]
+    fn missing_category_is_suggested(template_recipe: TemplateRecipe) {
+        let recipe = Recipe::new(
+            Some(template_recipe.id),
+            &template_recipe.name,
+            template_recipe.image_id.as_deref(),
+            template_recipe.author_tags.as_deref(),
+            template_recipe.tags.as_deref(),
+            "pro",
+            template_recipe.description.as_deref(),
+            template_recipe.url.as_deref(),
+            &template_recipe.ingredients,
+            template_recipe.steps,
+            template_recipe.equipment.as_deref(),
+            template_recipe.prep_time_minutes,
+            Some(&template_recipe.author_id.to_string()),
+        )
+        .unwrap();
+        assert_eq!(recipe.category(), RecipeCategory::Pro);
+
+        let mut json = serde_json::to_value(&recipe).unwrap();
+        json.as_object_mut().unwrap().remove("category");
+        let recipe: Recipe = serde_json::from_value(json).unwrap();
+        assert_eq!(recipe.category(), RecipeCategory::Easy);
+    }
+
+    #[rstest]
+    #[case(Some("The Savoy Cocktail Book"), Some(42), None, None, true)]
+    #[case(
+        None,
+        None,
+        Some("https://example.com/daiquiri"),
+        Some("Harry Craddock"),
+        true
+    )]
+    #[case(None, None, None, None, false)]
+    #[case(None, Some(42), None, Some("Harry Craddock"), false)]
+    #[case(Some("The Savoy Cocktail Book"), Some(0), None, None, false)]
+    #[case(None, None, Some("not a url"), None, false)]
+    fn sources_are_validated(
+        #[case] book: Option<&str>,
+        #[case] page: Option<u16>,
+        #[case] url: Option<&str>,
+        #[case] author: Option<&str>,
+        #[case] valid: bool,
+    ) {
+        // Invalid URLs can't even be built.
+        let Ok(url) = url.map(WebsiteUrl::try_from).transpose() else {
+            assert!(!valid);
+            return;
+        };
+        let source = RecipeSource {
+            book: book.map(String::from),
+            page,
+            url,
+            author: author.map(String::from),
+        };
+        assert_eq!(source.validate().is_ok(), valid);
+    }
+
+    #[rstest]
+    fn licenses_round_trip() {
+        for license in [
+            RecipeLicense::CcBy,
+            RecipeLicense::CcBySa,
+            RecipeLicense::CcByNc,
+            RecipeLicense::Cc0,
+            RecipeLicense::AllRightsReserved,
+        ] {
+            assert_eq!(
+                RecipeLicense::try_from(license.to_string().as_str()).unwrap(),
+                license
+            );
+            assert_eq!(
+                serde_json::to_value(license).unwrap(),
+                serde_json::json!(license.to_string())
+            );
+        }
+        assert!(RecipeLicense::try_from("gpl").is_err());
+    }
+
+    #[rstest]
+    fn step_images_match_the_steps(template_recipe: TemplateRecipe) {
+        let recipe = Recipe::new(
+            Some(template_recipe.id),
+            &template_recipe.name,
+            template_recipe.image_id.as_deref(),
+            template_recipe.author_tags.as_deref(),
+            template_recipe.tags.as_deref(),
+            "easy",
+            template_recipe.description.as_deref(),
+            template_recipe.url.as_deref(),
+            &template_recipe.ingredients,
+            template_recipe.steps,
+            template_recipe.equipment.as_deref(),
+            template_recipe.prep_time_minutes,
+            Some(&template_recipe.author_id.to_string()),
+        )
+        .unwrap();
+        assert!(recipe.step_images().is_none());
+
+        let image = |step: u16| StepImage {
+            step,
+            image_id: format!("0191e13b-5ab7-78f1-bc06-be503a6c111{step}.png"),
+        };
+        let recipe = recipe.with_step_images(vec![image(1), image(2)]);
+        assert_eq!(recipe.step_images().unwrap(), [image(1)]);
+    }
+
+    const ALL_UNITS: [QuantityUnit; 9] = [
+        QuantityUnit::Grams,
+        QuantityUnit::MilliLiter,
+        QuantityUnit::Dash,
+        QuantityUnit::Unit,
+        QuantityUnit::Ounces,
+        QuantityUnit::Drops,
+        QuantityUnit::TableSpoon,
+        QuantityUnit::TeaSpoon,
+        QuantityUnit::Cups,
+    ];
+
+    proptest! {
+        #[test]
+        fn units_round_trip(unit in prop::sample::select(&ALL_UNITS[..])) {
+            prop_assert_eq!(QuantityUnit::try_from(unit.to_string().as_str()).unwrap(), unit);
+        }
+
+        #[test]
+        fn unknown_units_are_rejected(input in "\\PC{0,10}") {
+            match QuantityUnit::try_from(input.as_str()) {
+                Ok(unit) => prop_assert_eq!(unit.to_string(), input),
+                Err(e) => prop_assert!(matches!(e, DataDomainError::InvalidData)),
+            }
+        }
+
+        #[test]
+        fn amounts_round_trip(
+            quantity in prop::num::f32::POSITIVE | prop::num::f32::ZERO,
+            unit in prop::sample::select(&ALL_UNITS[..]),
+        ) {
+            let contains = RecipeContains { quantity, unit, ingredient_id: Uuid::now_v7() };
+            prop_assert_eq!(RecipeContains::parse_amount(&contains.amount()).ok(), Some((quantity, unit)));
+        }
+
+        #[test]
+        fn malformed_amounts_are_rejected(amount in "\\PC{0,20}") {
+            // Only the format written by the backend is accepted, and no input makes the parser panic.
+            if let Ok((quantity, unit)) = RecipeContains::parse_amount(&amount) {
+                prop_assert!(quantity.is_finite());
+                let suffix = format!(" {unit}");
+                prop_assert!(amount.ends_with(&suffix));
+            }
+        }
+    }
 }
@@ -0,0 +1,226 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Shared identifier for the singleton resources of the API.
+
+use crate::domain::DataDomainError;
+use actix_web::{
+    dev::Payload, error::InternalError, web::PathConfig, FromRequest, HttpRequest, HttpResponse,
+};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::{
+    future::{ready, Ready},
+    str::FromStr,
+};
+use tracing::debug;
+use uuid::Uuid;
+
+/// Name of the path segment that holds the ID of a resource, i.e. `/author/{id}`.
+const ID_PATH_SEGMENT: &str = "id";
+
+/// Identifier of a resource of the `Cocktail` DB (authors, recipes, ingredients...).
+///
+/// # Description
+///
+/// All the resources of the DB are identified by an [Uuid]. This type wraps such value and centralises the rules to
+/// accept an ID given by a client of the API:
+/// - Only the hyphenated format is accepted (`0191e13b-5ab7-78f1-bc06-be503a6c111b`). Other formats that are
+///   accepted by [Uuid::parse_str] (simple, braced or URN) are rejected to avoid having many representations of the
+///   same resource in the URLs.
+/// - The nil [Uuid] is rejected, as the backend never generates it.
+/// - IDs are normalised to lower case.
+///
+/// [ResourceId] implements [FromRequest], so handlers of singleton resources can use it straight as an argument
+/// rather than parsing the path by hand. A malformed ID is answered with a code **400** before reaching the handler.
+/// Whether the ID matches an existing entry of the DB is left to the handler, which shall answer with a code **404**
+/// when the resource doesn't exist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ResourceId(Uuid);
+
+impl ResourceId {
+    /// Get the wrapped [Uuid].
+    pub fn as_uuid(&self) -> &Uuid {
+        &self.0
+    }
+}
+
+impl From<Uuid> for ResourceId {
+    fn from(value: Uuid) -> Self {
+        ResourceId(value)
+    }
+}
+
+impl From<ResourceId> for Uuid {
+    fn from(value: ResourceId) -> Self {
+        value.0
+    }
+}
+
+impl From<ResourceId> for String {
+    fn from(value: ResourceId) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<&str> for ResourceId {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        // The hyphenated format is the only one that has a length of 36 chars.
+        if value.len() != uuid::fmt::Hyphenated::LENGTH {
+            return Err(DataDomainError::InvalidId);
+        }
+
+        let id = Uuid::try_parse(value).map_err(|_| DataDomainError::InvalidId)?;
+
+        if id.is_nil() {
+            Err(DataDomainError::InvalidId)
+        } else {
+            Ok(ResourceId(id))
+        }
+    }
+}
+
+impl TryFrom<String> for ResourceId {
+    type Error = DataDomainError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        ResourceId::try_from(value.as_str())
+    }
+}
+
+impl FromStr for ResourceId {
+    type Err = DataDomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ResourceId::try_from(s)
+    }
+}
+
+impl fmt::Display for ResourceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.hyphenated())
+    }
+}
+
+impl FromRequest for ResourceId {
+    type Error = DataDomainError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let id = match req.match_info().get(ID_PATH_SEGMENT) {
+            Some(id) => ResourceId::try_from(id),
+            None => Err(DataDomainError::InvalidId),
+        };
+
+        if id.is_err() {
+            debug!("Malformed ID received in the path: {}", req.path());
+        }
+
+        ready(id)
+    }
+}
+
+/// Configuration of the [Path](actix_web::web::Path) extractor that follows the policy of [ResourceId].
+///
+/// # Description
+///
+/// The framework answers with a code 404 when a path segment can't be parsed, i.e. the ID of an API key that is not a
+/// number. This configuration answers with a code **400** instead, so a malformed ID is never mistaken for an unknown
+/// one, whatever the extractor used by the handler.
+pub fn path_config() -> PathConfig {
+    PathConfig::default().error_handler(|err, req| {
+        debug!("Malformed path segment received: {}", req.path());
+        InternalError::from_response(err, HttpResponse::BadRequest().finish()).into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App, ResponseError,
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case("0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    #[case("0191E13B-5AB7-78F1-BC06-BE503A6C111B")]
+    fn valid_ids_are_accepted(#[case] input: &str) {
+        let id = ResourceId::try_from(input).expect("Failed to parse a valid ID");
+        assert_eq!(id.to_string(), input.to_lowercase());
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("1234")]
+    #[case("Wrong_ID")]
+    #[case("0191e13b5ab778f1bc06be503a6c111b")]
+    #[case("{0191e13b-5ab7-78f1-bc06-be503a6c111b}")]
+    #[case("urn:uuid:0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    #[case("0191e13b-5ab7-78f1-bc06-be503a6c111z")]
+    #[case("00000000-0000-0000-0000-000000000000")]
+    fn invalid_ids_are_rejected(#[case] input: &str) {
+        assert!(matches!(
+            ResourceId::try_from(input),
+            Err(DataDomainError::InvalidId)
+        ));
+    }
+
+    #[rstest]
+    fn serde_round_trip() {
+        let id = ResourceId::from(Uuid::now_v7());
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, format!("\"{id}\""));
+        assert_eq!(serde_json::from_str::<ResourceId>(&json).unwrap(), id);
+        assert!(serde_json::from_str::<ResourceId>("\"1234\"").is_err());
+    }
+
+    #[actix_web::test]
+    async fn extract_from_path() {
+        let id = Uuid::now_v7();
+        let req = TestRequest::default()
+            .param(ID_PATH_SEGMENT, id.to_string())
+            .to_http_request();
+        let extracted = ResourceId::extract(&req).await.unwrap();
+        assert_eq!(extracted.as_uuid(), &id);
+
+        let req = TestRequest::default()
+            .param(ID_PATH_SEGMENT, "1234")
+            .to_http_request();
+        assert!(ResourceId::extract(&req).await.is_err());
+
+        let req = TestRequest::default().to_http_request();
+        assert!(ResourceId::extract(&req).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn malformed_ids_are_bad_requests() {
+        let req = TestRequest::default()
+            .param(ID_PATH_SEGMENT, "Wrong_ID")
+            .to_http_request();
+        let error = ResourceId::extract(&req).await.unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+
+        let app = test::init_service(App::new().app_data(path_config()).route(
+            "/{id}",
+            web::get().to(|id: web::Path<u64>| async move { id.to_string() }),
+        ))
+        .await;
+        for (uri, status) in [
+            ("/12", StatusCode::OK),
+            ("/twelve", StatusCode::BAD_REQUEST),
+        ] {
+            let res = test::call_service(&app, TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(res.status(), status);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Sanitization of the text given by the clients of the API.
+//!
+//! # Description
+//!
+//! Free text (descriptions of the recipes, preparation steps, authors' bios...) is later embedded by other services
+//! in HTML pages or emails. To prevent stored XSS in such consumers, text is sanitized before it gets into the domain
+//! objects. All the levels apply these rules:
+//! - Control characters are removed, except line breaks and tabs. Invisible formatting characters that can be used
+//!   to spoof the text (bidirectional overrides, zero-width chars...) are removed as well.
+//! - Text is normalised to the Unicode NFC form, so the same text is always stored using the same code points.
+//!
+//! On top of that, HTML is handled according to the [SanitizeLevel] configured for the application. All the levels are
+//! idempotent: sanitizing an already sanitized text doesn't modify it. This matters because domain objects are also
+//! built from the content of the DB.
+//!
+//! Domain objects ([crate::domain::Recipe], [crate::domain::Author], [crate::domain::Ingredient] and the
+//! [crate::domain::ResourceName] of all of them) apply the level of the application ([sanitize]) when they are
+//! deserialized or built, so every path that stores their text is covered. The level is set when the application is
+//! built (see [set_sanitize_level]). It is also shared with the handlers using `web::Data`, so free text that doesn't
+//! belong to a domain object (i.e. the labels of the API keys) is sanitized by the handler that stores it.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::sync::RwLock;
+use unicode_normalization::UnicodeNormalization;
+
+/// Regex to match HTML tags and comments. As in HTML, a tag starts with a letter, `/`, `!` or `?`.
+static RE_HTML_TAG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<!--.*?-->|</?[a-zA-Z!?][^<>]*>").unwrap());
+
+/// Level applied by the domain objects, see [set_sanitize_level].
+static LEVEL: RwLock<SanitizeLevel> = RwLock::new(SanitizeLevel::Strip);
+
+/// Strictness of the sanitization of the text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SanitizeLevel {
+    /// HTML tags are removed, keeping the inner text. Stray `<` and `>` are removed too.
+    #[default]
+    Strip,
+    /// `<` and `>` are escaped as HTML entities, so the text is rendered verbatim by a browser.
+    Escape,
+    /// HTML is kept untouched. Only control characters and the Unicode normalisation are handled. Use this level
+    /// only when all the consumers of the API escape the text on their own.
+    Basic,
+}
+
+/// Apply the rules common to all the levels to a text, leaving its HTML untouched.
+pub fn normalize_text(text: &str) -> String {
+    text.nfc().filter(|c| !is_forbidden_char(*c)).collect()
+}
+
+/// Sanitize a text using the given [SanitizeLevel].
+pub fn sanitize_text(text: &str, level: SanitizeLevel) -> String {
+    let text = normalize_text(text);
+
+    match level {
+        SanitizeLevel::Strip => RE_HTML_TAG
+            .replace_all(&text, "")
+            .chars()
+            .filter(|c| *c != '<' && *c != '>')
+            .collect(),
+        SanitizeLevel::Escape => text.replace('<', "&lt;").replace('>', "&gt;"),
+        SanitizeLevel::Basic => text,
+    }
+}
+
+/// Set the [SanitizeLevel] applied by the domain objects. The application sets the level of its settings when it is
+/// built, [SanitizeLevel::Strip] applies until then.
+pub fn set_sanitize_level(level: SanitizeLevel) {
+    *LEVEL.write().unwrap_or_else(|e| e.into_inner()) = level;
+}
+
+/// Get the [SanitizeLevel] applied by the domain objects.
+pub fn sanitize_level() -> SanitizeLevel {
+    *LEVEL.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Sanitize a text using the [SanitizeLevel] applied by the domain objects.
+pub fn sanitize(text: &str) -> String {
+    sanitize_text(text, sanitize_level())
+}
+
+/// Characters that are never accepted within a text.
+fn is_forbidden_char(c: char) -> bool {
+    (c.is_control() && c != '\n' && c != '\t')
+        || matches!(
+            c,
+            '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' | '\u{FEFF}'
+        )
+}
+
+/// Deserialize and sanitize a [String].
+pub fn deserialize_text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(sanitize(&String::deserialize(deserializer)?))
+}
+
+/// Deserialize and sanitize an optional [String].
+pub fn deserialize_optional_text<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.map(|text| sanitize(&text)))
+}
+
+/// Deserialize and sanitize a list of [String].
+pub fn deserialize_text_list<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    Ok(Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|text| sanitize(text))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case("Shake <b>hard</b>", "Shake hard")]
+    #[case("<script>alert('xss')</script>Stir", "alert('xss')Stir")]
+    #[case("<img src=x onerror=alert(1)>Garnish", "Garnish")]
+    #[case("Pour<!-- hidden -->", "Pour")]
+    #[case("1 < 2 > 0", "1  2  0")]
+    #[case("Line\nbreak\u{0007}", "Line\nbreak")]
+    #[case("Invisible\u{200B}\u{202E}text", "Invisibletext")]
+    fn html_is_stripped(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(sanitize_text(input, SanitizeLevel::Strip), expected);
+    }
+
+    #[rstest]
+    #[case("Shake <b>hard</b>", "Shake &lt;b&gt;hard&lt;/b&gt;")]
+    #[case("Gin & Tonic", "Gin & Tonic")]
+    fn html_is_escaped(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(sanitize_text(input, SanitizeLevel::Escape), expected);
+    }
+
+    #[rstest]
+    fn unicode_is_normalised() {
+        // "e" followed by a combining acute accent is normalised to "é".
+        let text = sanitize_text("Cafe\u{0301}", SanitizeLevel::Basic);
+        assert_eq!(text, "Caf\u{00E9}");
+        assert_eq!(
+            normalize_text("<i>Cafe\u{0301}</i>\u{200B}"),
+            "<i>Caf\u{00E9}</i>"
+        );
+    }
+
+    #[rstest]
+    #[case(SanitizeLevel::Strip)]
+    #[case(SanitizeLevel::Escape)]
+    #[case(SanitizeLevel::Basic)]
+    fn sanitization_is_idempotent(#[case] level: SanitizeLevel) {
+        let input = "<p>Muddle & <i>stir</i></p> <<x>> Cafe\u{0301}\u{0000}";
+        let once = sanitize_text(input, level);
+        assert_eq!(sanitize_text(&once, level), once);
+    }
+}
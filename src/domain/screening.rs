@@ -0,0 +1,216 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Screening of the content submitted by the clients of the API.
+//!
+//! # Description
+//!
+//! The [Screener] looks for content that is likely profanity or spam using a wordlist and a few simple heuristics:
+//! - Words of the wordlist, also when written using common character substitutions (`4` for `a`, `0` for `o`...).
+//! - Too many links, which is the usual trait of spam.
+//! - Shouting: long texts written mostly in capital letters.
+//!
+//! Screening doesn't reject the content. Suspect content is flagged and sent to the moderation queue, so an
+//! administrator decides whether it gets published. The screening is optional, see
+//! [crate::configuration::ScreeningSettings].
+
+use crate::domain::Recipe;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::ToSchema;
+
+/// Minimum amount of letters of a text to apply the shouting heuristic.
+const SHOUTING_MIN_LETTERS: usize = 20;
+/// Ratio of capital letters above which a text is considered shouting.
+const SHOUTING_RATIO: f32 = 0.7;
+
+/// Reason why some content was flagged by the [Screener].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreeningFlag {
+    /// A word of the wordlist was found.
+    BlockedWord(String),
+    /// The content includes more links than allowed.
+    ExcessiveLinks(usize),
+    /// Some text is written mostly in capital letters.
+    Shouting,
+}
+
+impl fmt::Display for ScreeningFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreeningFlag::BlockedWord(word) => write!(f, "blocked word: {word}"),
+            ScreeningFlag::ExcessiveLinks(links) => write!(f, "too many links: {links}"),
+            ScreeningFlag::Shouting => write!(f, "shouting"),
+        }
+    }
+}
+
+/// Screening of profanity and spam.
+#[derive(Clone, Debug, Default)]
+pub struct Screener {
+    enabled: bool,
+    blocked_words: Vec<String>,
+    max_links: usize,
+}
+
+impl Screener {
+    /// Build a new [Screener]. The words of the wordlist are not case sensitive.
+    pub fn new(blocked_words: &[String], max_links: usize) -> Self {
+        Screener {
+            enabled: true,
+            blocked_words: blocked_words
+                .iter()
+                .map(|w| w.trim().to_lowercase())
+                .filter(|w| !w.is_empty())
+                .collect(),
+            max_links,
+        }
+    }
+
+    /// Build a [Screener] that never flags any content.
+    pub fn disabled() -> Self {
+        Screener::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Screen a set of texts that belong to the same piece of content.
+    pub fn screen(&self, texts: &[&str]) -> Vec<ScreeningFlag> {
+        let mut flags = Vec::new();
+
+        if !self.enabled {
+            return flags;
+        }
+
+        for text in texts {
+            for word in normalised_words(text) {
+                if self.blocked_words.contains(&word) {
+                    let flag = ScreeningFlag::BlockedWord(word);
+                    if !flags.contains(&flag) {
+                        flags.push(flag);
+                    }
+                }
+            }
+        }
+
+        let links = texts.iter().map(|t| count_links(t)).sum::<usize>();
+        if links > self.max_links {
+            flags.push(ScreeningFlag::ExcessiveLinks(links));
+        }
+
+        if texts.iter().any(|t| is_shouting(t)) {
+            flags.push(ScreeningFlag::Shouting);
+        }
+
+        flags
+    }
+
+    /// Screen the text of a recipe: name, description, steps and the tags given by the author.
+    pub fn screen_recipe(&self, recipe: &Recipe) -> Vec<ScreeningFlag> {
+        let mut texts = vec![recipe.name()];
+        texts.extend(recipe.description());
+        texts.extend(recipe.steps().iter().map(String::as_str));
+        texts.extend(
+            recipe
+                .author_tags()
+                .unwrap_or_default()
+                .iter()
+                .map(|t| t.identifier.as_str()),
+        );
+
+        self.screen(&texts)
+    }
+}
+
+/// Split a text into lower case words, undoing the usual character substitutions.
+fn normalised_words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .collect::<String>()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn count_links(text: &str) -> usize {
+    let text = text.to_lowercase();
+    text.matches("http://").count()
+        + text.matches("https://").count()
+        + text.matches("www.").count()
+        - text.matches("://www.").count()
+}
+
+fn is_shouting(text: &str) -> bool {
+    let letters = text
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect::<Vec<char>>();
+
+    letters.len() >= SHOUTING_MIN_LETTERS
+        && letters.iter().filter(|c| c.is_uppercase()).count() as f32
+            > letters.len() as f32 * SHOUTING_RATIO
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[fixture]
+    fn screener() -> Screener {
+        Screener::new(&["spam".to_owned(), "Scam".to_owned()], 1)
+    }
+
+    #[rstest]
+    #[case("Shake with ice and strain.")]
+    #[case("See https://www.example.com for the original recipe.")]
+    fn clean_text_is_not_flagged(screener: Screener, #[case] text: &str) {
+        assert!(screener.screen(&[text]).is_empty());
+    }
+
+    #[rstest]
+    #[case("Buy my SPAM now", "spam")]
+    #[case("This is not a sc4m", "scam")]
+    #[case("$p@m everywhere", "spam")]
+    fn blocked_words_are_flagged(screener: Screener, #[case] text: &str, #[case] word: &str) {
+        assert_eq!(
+            screener.screen(&[text]),
+            vec![ScreeningFlag::BlockedWord(word.to_owned())]
+        );
+    }
+
+    #[rstest]
+    fn links_are_counted_across_texts(screener: Screener) {
+        let flags = screener.screen(&["Visit http://a.com", "and www.b.com"]);
+        assert_eq!(flags, vec![ScreeningFlag::ExcessiveLinks(2)]);
+    }
+
+    #[rstest]
+    fn shouting_is_flagged(screener: Screener) {
+        let flags = screener.screen(&["THE BEST COCKTAIL OF THE WORLD"]);
+        assert_eq!(flags, vec![ScreeningFlag::Shouting]);
+        assert!(screener.screen(&["IBA"]).is_empty());
+    }
+
+    #[rstest]
+    fn disabled_screener_accepts_everything() {
+        assert!(Screener::disabled().screen(&["spam spam spam"]).is_empty());
+    }
+}
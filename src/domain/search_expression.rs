@@ -0,0 +1,455 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Query language of the recipe searches.
+//!
+//! # Description
+//!
+//! Power users can search recipes using a compact expression rather than the fixed parameters of the search, i.e.
+//! `tag:tiki AND (rum OR cachaça) -egg`. Expressions are made of terms, which are combined using:
+//! - `AND`: both terms shall match. Terms separated by spaces are joined by `AND` as well.
+//! - `OR`: any of the terms shall match.
+//! - `NOT` or a leading `-`: the term shall not match.
+//! - Parentheses to group terms. `NOT` binds tighter than `AND`, which binds tighter than `OR`.
+//!
+//! Terms are a word, or a text between double quotes, optionally prefixed by a field:
+//! - `tag:tiki`: recipes tagged with `tiki`.
+//! - `name:sour`: recipes whose name includes `sour`.
+//! - `ingredient:rum`: recipes that use an ingredient whose name includes `rum`.
+//! - `category:easy`: recipes of the category `easy`.
+//! - `rating:4`: recipes rated with 4 stars or more.
+//!
+//! Terms without a field match the name of the recipes, or the name of their ingredients. Operators are only
+//! recognised in upper case, so `and` is searched as a word.
+
+use crate::domain::{RecipeCategory, StarRate, Tag};
+use anyhow::bail;
+use std::{collections::HashSet, hash::Hash};
+
+/// Maximum length of an expression (characters).
+pub const MAX_EXPRESSION_LENGTH: usize = 256;
+
+/// Maximum amount of terms of an expression. Every term is a condition of the query to the DB.
+pub const MAX_EXPRESSION_TERMS: usize = 16;
+
+/// Single criterion of a [SearchExpression].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchTerm {
+    /// Text searched within the name of the recipes and the name of their ingredients.
+    Text(String),
+    Name(String),
+    Tag(Tag),
+    Ingredient(String),
+    Category(RecipeCategory),
+    Rating(StarRate),
+}
+
+/// Parsed expression of the query language.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchExpression {
+    Term(SearchTerm),
+    And(Vec<SearchExpression>),
+    Or(Vec<SearchExpression>),
+    Not(Box<SearchExpression>),
+}
+
+impl SearchExpression {
+    /// Parse an expression. An error that describes the issue is returned when the expression is not valid.
+    pub fn parse(expression: &str) -> Result<Self, anyhow::Error> {
+        if expression.chars().count() > MAX_EXPRESSION_LENGTH {
+            bail!("The expression is longer than {MAX_EXPRESSION_LENGTH} characters");
+        }
+
+        let mut parser = Parser {
+            tokens: tokenize(expression)?,
+            position: 0,
+        };
+        if parser.tokens.is_empty() {
+            bail!("The expression is empty");
+        }
+
+        let parsed = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            bail!("Unexpected {token} in the expression");
+        }
+        if parsed.terms().len() > MAX_EXPRESSION_TERMS {
+            bail!("The expression has more than {MAX_EXPRESSION_TERMS} terms");
+        }
+
+        Ok(parsed)
+    }
+
+    /// Terms of the expression, without duplicates.
+    pub fn terms(&self) -> Vec<&SearchTerm> {
+        let mut terms = Vec::new();
+        self.collect_terms(&mut terms);
+
+        terms
+    }
+
+    fn collect_terms<'a>(&'a self, terms: &mut Vec<&'a SearchTerm>) {
+        match self {
+            SearchExpression::Term(term) => {
+                if !terms.contains(&term) {
+                    terms.push(term);
+                }
+            }
+            SearchExpression::And(items) | SearchExpression::Or(items) => {
+                items.iter().for_each(|item| item.collect_terms(terms))
+            }
+            SearchExpression::Not(item) => item.collect_terms(terms),
+        }
+    }
+
+    /// Evaluate the expression over a set of items.
+    ///
+    /// # Description
+    ///
+    /// `matches` gives the items that match a term, and `universe` is the set of all the items, which is needed to
+    /// negate a term. The result is a subset of `universe`.
+    pub fn evaluate<T, F>(&self, universe: &HashSet<T>, matches: &F) -> HashSet<T>
+    where
+        T: Clone + Eq + Hash,
+        F: Fn(&SearchTerm) -> HashSet<T>,
+    {
+        match self {
+            SearchExpression::Term(term) => matches(term)
+                .into_iter()
+                .filter(|item| universe.contains(item))
+                .collect(),
+            SearchExpression::And(items) => {
+                let mut result = universe.clone();
+                for item in items {
+                    if result.is_empty() {
+                        break;
+                    }
+                    let matched = item.evaluate(&result, matches);
+                    result.retain(|i| matched.contains(i));
+                }
+                result
+            }
+            SearchExpression::Or(items) => items
+                .iter()
+                .flat_map(|item| item.evaluate(universe, matches))
+                .collect(),
+            SearchExpression::Not(item) => {
+                let matched = item.evaluate(universe, matches);
+                universe
+                    .iter()
+                    .filter(|i| !matched.contains(i))
+                    .cloned()
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Tokens of the query language.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Term(SearchTerm),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+            Token::Term(term) => write!(f, "term {term:?}"),
+        }
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, anyhow::Error> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '-' => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            _ => {
+                let mut word = String::new();
+                let mut field = None;
+                let mut quoted = false;
+
+                while let Some(&c) = chars.peek() {
+                    if c == '"' {
+                        chars.next();
+                        word.push_str(&read_quoted(&mut chars)?);
+                        quoted = true;
+                    } else if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    } else if c == ':' && field.is_none() && !quoted {
+                        chars.next();
+                        field = Some(std::mem::take(&mut word));
+                    } else {
+                        chars.next();
+                        word.push(c);
+                    }
+                }
+
+                tokens.push(match (field, word.as_str()) {
+                    (None, "AND") if !quoted => Token::And,
+                    (None, "OR") if !quoted => Token::Or,
+                    (None, "NOT") if !quoted => Token::Not,
+                    (field, _) => Token::Term(parse_term(field.as_deref(), &word)?),
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn read_quoted(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, anyhow::Error> {
+    let mut text = String::new();
+
+    for c in chars.by_ref() {
+        if c == '"' {
+            return Ok(text);
+        }
+        text.push(c);
+    }
+
+    bail!("Unterminated quoted text in the expression")
+}
+
+fn parse_term(field: Option<&str>, value: &str) -> Result<SearchTerm, anyhow::Error> {
+    let value = value.trim();
+    if value.is_empty() {
+        bail!("Empty term in the expression");
+    }
+
+    Ok(match field.map(str::to_ascii_lowercase).as_deref() {
+        None => SearchTerm::Text(value.to_owned()),
+        Some("name") => SearchTerm::Name(value.to_owned()),
+        Some("ingredient") => SearchTerm::Ingredient(value.to_owned()),
+        Some("tag") => match Tag::new(value) {
+            Ok(tag) => SearchTerm::Tag(tag),
+            Err(_) => bail!("Invalid tag: {value}"),
+        },
+        Some("category") => match RecipeCategory::try_from(value) {
+            Ok(category) => SearchTerm::Category(category),
+            Err(_) => bail!("Invalid category: {value}"),
+        },
+        Some("rating") => match StarRate::try_from(value) {
+            Ok(rating) => SearchTerm::Rating(rating),
+            Err(_) => bail!("Invalid rating: {value}"),
+        },
+        Some(field) => bail!("Unknown field: {field}"),
+    })
+}
+
+/// Recursive descent parser of the query language.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<SearchExpression, anyhow::Error> {
+        let mut items = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            items.push(self.parse_and()?);
+        }
+
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            SearchExpression::Or(items)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<SearchExpression, anyhow::Error> {
+        let mut items = vec![self.parse_not()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                // Adjacent terms are joined by AND.
+                Some(Token::Not | Token::Open | Token::Term(_)) => (),
+                _ => break,
+            }
+            items.push(self.parse_not()?);
+        }
+
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            SearchExpression::And(items)
+        })
+    }
+
+    fn parse_not(&mut self) -> Result<SearchExpression, anyhow::Error> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            return Ok(SearchExpression::Not(Box::new(self.parse_not()?)));
+        }
+
+        match self.next() {
+            Some(Token::Open) => {
+                let expression = self.parse_or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expression),
+                    _ => bail!("Missing ')' in the expression"),
+                }
+            }
+            Some(Token::Term(term)) => Ok(SearchExpression::Term(term)),
+            Some(token) => bail!("Unexpected {token} in the expression"),
+            None => bail!("The expression ends unexpectedly"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn text(value: &str) -> SearchExpression {
+        SearchExpression::Term(SearchTerm::Text(value.into()))
+    }
+
+    fn tag(value: &str) -> SearchExpression {
+        SearchExpression::Term(SearchTerm::Tag(Tag::new(value).unwrap()))
+    }
+
+    #[rstest]
+    fn expressions_are_parsed() {
+        assert_eq!(
+            SearchExpression::parse("tag:tiki AND (rum OR cachaça) -egg").unwrap(),
+            SearchExpression::And(vec![
+                tag("tiki"),
+                SearchExpression::Or(vec![text("rum"), text("cachaça")]),
+                SearchExpression::Not(Box::new(text("egg"))),
+            ])
+        );
+    }
+
+    #[rstest]
+    #[case("rum lime", SearchExpression::And(vec![text("rum"), text("lime")]))]
+    #[case(
+        "rum OR gin lime",
+        SearchExpression::Or(vec![text("rum"), SearchExpression::And(vec![text("gin"), text("lime")])])
+    )]
+    #[case(
+        "NOT NOT rum",
+        SearchExpression::Not(Box::new(SearchExpression::Not(Box::new(text("rum")))))
+    )]
+    #[case("\"old fashioned\"", text("old fashioned"))]
+    #[case(
+        "name:\"old fashioned\" and",
+        SearchExpression::And(vec![
+            SearchExpression::Term(SearchTerm::Name("old fashioned".into())),
+            text("and"),
+        ])
+    )]
+    #[case(
+        "Category:Easy rating:4.5",
+        SearchExpression::And(vec![
+            SearchExpression::Term(SearchTerm::Category(RecipeCategory::Easy)),
+            SearchExpression::Term(SearchTerm::Rating(StarRate::new(4.5).unwrap())),
+        ])
+    )]
+    #[case("((ingredient:rum))", SearchExpression::Term(SearchTerm::Ingredient("rum".into())))]
+    fn operators_have_precedence(#[case] expression: &str, #[case] expected: SearchExpression) {
+        assert_eq!(SearchExpression::parse(expression).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("   ")]
+    #[case("rum AND")]
+    #[case("OR rum")]
+    #[case("(rum OR gin")]
+    #[case("rum)")]
+    #[case("\"old fashioned")]
+    #[case("tag:t")]
+    #[case("category:impossible")]
+    #[case("rating:3.7")]
+    #[case("glass:coupe")]
+    #[case("name:")]
+    fn invalid_expressions_are_rejected(#[case] expression: &str) {
+        assert!(SearchExpression::parse(expression).is_err());
+    }
+
+    #[rstest]
+    fn expressions_are_limited() {
+        let terms = vec!["rum"; MAX_EXPRESSION_TERMS].join(" OR ");
+        assert!(SearchExpression::parse(&terms).is_ok());
+
+        let terms = (0..=MAX_EXPRESSION_TERMS)
+            .map(|i| format!("w{i}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(SearchExpression::parse(&terms).is_err());
+        assert!(SearchExpression::parse(&"a".repeat(MAX_EXPRESSION_LENGTH + 1)).is_err());
+    }
+
+    #[rstest]
+    fn expressions_are_evaluated() {
+        let universe: HashSet<u8> = (1..=6).collect();
+        let matches = |term: &SearchTerm| -> HashSet<u8> {
+            match term {
+                SearchTerm::Tag(_) => [1, 2, 3, 4].into(),
+                SearchTerm::Text(text) if text == "rum" => [1, 2].into(),
+                SearchTerm::Text(text) if text == "cachaça" => [3, 5].into(),
+                SearchTerm::Text(text) if text == "egg" => [2, 9].into(),
+                _ => HashSet::new(),
+            }
+        };
+
+        let expression = SearchExpression::parse("tag:tiki AND (rum OR cachaça) -egg").unwrap();
+        assert_eq!(expression.terms().len(), 4);
+        assert_eq!(expression.evaluate(&universe, &matches), [1, 3].into());
+
+        let expression = SearchExpression::parse("-egg").unwrap();
+        assert_eq!(
+            expression.evaluate(&universe, &matches),
+            [1, 3, 4, 5, 6].into()
+        );
+
+        let expression = SearchExpression::parse("egg OR egg").unwrap();
+        assert_eq!(expression.terms().len(), 1);
+        assert_eq!(expression.evaluate(&universe, &matches), [2].into());
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Normalization of the names used by the searches.
+//!
+//! # Description
+//!
+//! Clients don't always type the accents, nor the exact Unicode form, of the names they look for, i.e. `pina colada`
+//! shall find `Piña Colada`. The names of the recipes and the ingredients are stored along with a normalized copy
+//! (`search_name`), which is kept up to date by the backend when the names are written. The search input is normalized
+//! the same way before being compared to that copy:
+//! - The compatibility decomposition of the text is computed, so ligatures and full-width forms turn into their
+//!   plain counterparts, and the diacritics are removed, i.e. `Piña` turns into `pina`.
+//! - Letters are converted to lower case.
+//! - Runs of whitespace are replaced by a single space, and the leading and trailing whitespace is removed.
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Normalize a text for the searches.
+pub fn normalize_search_text(text: &str) -> String {
+    let folded = text
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .to_lowercase();
+
+    folded.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Build the pattern of a `LIKE` clause that matches the normalized names which contain the given text.
+pub fn search_pattern(text: &str) -> String {
+    format!("%{}%", normalize_search_text(text))
+}
+
+/// Build the pattern of a `LIKE` clause that matches the normalized texts which start with the given text.
+///
+/// # Description
+///
+/// The wildcards of the text are escaped, so they match themselves.
+pub fn prefix_pattern(text: &str) -> String {
+    let mut pattern = String::new();
+    for c in normalize_search_text(text).chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+
+    pattern
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("Piña Colada", "pina colada")]
+    #[case("pina colada", "pina colada")]
+    #[case("  Crème   de Menthe ", "creme de menthe")]
+    #[case("CAIPIRIÑHA", "caipirinha")]
+    #[case("Ｇｉｎ ﬁzz", "gin fizz")]
+    #[case("Dark 'n' Stormy", "dark 'n' stormy")]
+    fn texts_are_normalized(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(normalize_search_text(text), expected);
+    }
+
+    #[rstest]
+    fn patterns_match_substrings() {
+        assert_eq!(search_pattern("Piña"), "%pina%");
+    }
+
+    #[rstest]
+    #[case("Ma", "ma%")]
+    #[case(" Mai  Tai", "mai tai%")]
+    #[case("50%_off", "50\\%\\_off%")]
+    #[case("a\\b", "a\\\\b%")]
+    fn prefix_patterns_escape_wildcards(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(prefix_pattern(text), expected);
+    }
+
+    proptest! {
+        #[test]
+        fn normalization_is_idempotent(text in "\\PC{0,40}") {
+            let normalized = normalize_search_text(&text);
+            prop_assert_eq!(normalize_search_text(&normalized), normalized);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Short public identifiers of the recipes.
+//!
+//! # Description
+//!
+//! UUIDs are long and hard to read in shared links. Recipes get a short identifier as well, i.e. `4Jq7Wd9tXbC`,
+//! which is accepted anywhere the ID of a recipe is accepted. Short IDs are built from 8 random bytes encoded using
+//! the Base58 alphabet (no `0`, `O`, `I` nor `l`), so they are not guessable from the ID of the recipe nor from other
+//! short IDs. The encoding is padded using the first symbol of the alphabet, thus all the short IDs have
+//! [SHORT_ID_LENGTH] characters, which tells them apart from the UUIDs.
+
+use crate::domain::DataDomainError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::ToSchema;
+
+/// Symbols of the Base58 encoding, as used by Bitcoin.
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Length of a short ID (characters). 11 Base58 symbols are enough to encode 8 bytes.
+pub const SHORT_ID_LENGTH: usize = 11;
+
+/// Short public identifier of a recipe.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+#[schema(value_type = String, example = "4Jq7Wd9tXbC")]
+pub struct ShortId(String);
+
+impl ShortId {
+    /// Generate a new random short ID.
+    pub fn generate() -> Self {
+        ShortId::from(rand::random::<u64>())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<u64> for ShortId {
+    fn from(mut value: u64) -> Self {
+        let mut symbols = [ALPHABET[0]; SHORT_ID_LENGTH];
+
+        for symbol in symbols.iter_mut().rev() {
+            *symbol = ALPHABET[(value % 58) as usize];
+            value /= 58;
+        }
+
+        ShortId(symbols.iter().map(|s| *s as char).collect())
+    }
+}
+
+impl TryFrom<&str> for ShortId {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value.len() == SHORT_ID_LENGTH && value.bytes().all(|b| ALPHABET.contains(&b)) {
+            Ok(ShortId(value.to_owned()))
+        } else {
+            Err(DataDomainError::InvalidId)
+        }
+    }
+}
+
+impl TryFrom<String> for ShortId {
+    type Error = DataDomainError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.as_str().try_into()
+    }
+}
+
+impl From<ShortId> for String {
+    fn from(value: ShortId) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for ShortId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(0, "11111111111")]
+    #[case(57, "1111111111z")]
+    #[case(58, "11111111121")]
+    #[case(u64::MAX, "jpXCZedGfVQ")]
+    fn values_are_encoded(#[case] value: u64, #[case] expected: &str) {
+        assert_eq!(ShortId::from(value).as_str(), expected);
+    }
+
+    #[rstest]
+    #[case("4Jq7Wd9tXbC", true)]
+    #[case("4Jq7Wd9tXb", false)]
+    #[case("4Jq7Wd9tXbCC", false)]
+    #[case("4Jq7Wd9tXb0", false)]
+    #[case("4Jq7Wd9tXbl", false)]
+    #[case("0191e13b-5ab7-78f1-bc06-be503a6c111b", false)]
+    fn short_ids_are_validated(#[case] value: &str, #[case] valid: bool) {
+        assert_eq!(ShortId::try_from(value).is_ok(), valid);
+    }
+
+    proptest! {
+        #[test]
+        fn encoded_values_are_valid_short_ids(value in any::<u64>()) {
+            let id = ShortId::from(value);
+            prop_assert_eq!(ShortId::try_from(id.as_str()).ok(), Some(id));
+        }
+    }
+}
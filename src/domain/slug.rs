@@ -0,0 +1,73 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! URL-friendly identifiers built from the names of the resources.
+//!
+//! # Description
+//!
+//! Frontends prefer pretty URLs, i.e. `/recipes/jane/pina-colada`, over the IDs of the resources. A slug is built from
+//! a name using these rules:
+//! - Accents and other diacritics are removed, i.e. `Piña` turns into `pina`.
+//! - Letters are converted to lower case.
+//! - Any run of characters that are not ASCII letters or digits is replaced by a single `-`, and the leading and
+//!   trailing `-` are removed.
+//!
+//! Names with no ASCII letters or digits at all produce the slug [FALLBACK_SLUG].
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Slug of the names that have no ASCII letters or digits.
+pub const FALLBACK_SLUG: &str = "recipe";
+
+/// Build the slug of a name.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+
+    for c in name.nfd().filter(|c| !is_combining_mark(*c)) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        FALLBACK_SLUG.to_owned()
+    } else {
+        slug.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("Margarita", "margarita")]
+    #[case("Piña colada", "pina-colada")]
+    #[case("  Dark 'n' Stormy!  ", "dark-n-stormy")]
+    #[case("Mojito (Cuban style)", "mojito-cuban-style")]
+    #[case("Crème de Menthe", "creme-de-menthe")]
+    #[case("🍹🍹", FALLBACK_SLUG)]
+    fn names_are_slugified(#[case] name: &str, #[case] expected: &str) {
+        assert_eq!(slugify(name), expected);
+    }
+
+    proptest! {
+        #[test]
+        fn slugs_are_url_friendly(name in "\\PC{0,40}") {
+            let slug = slugify(&name);
+            prop_assert!(!slug.is_empty() && slug.len() <= 40);
+            prop_assert!(slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'));
+            prop_assert!(!slug.starts_with('-') && !slug.ends_with('-') && !slug.contains("--"));
+            prop_assert_eq!(slugify(&slug), slug);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Order of the results of the recipe searches.
+//!
+//! # Description
+//!
+//! Clients choose the order of the recipes using a comma-separated list of fields, i.e. `rating,-creation_date`.
+//! Recipes are sorted by the first field, and ties are broken by the following ones. Fields are sorted in ascending
+//! order, unless they are prefixed by `-`. Only the fields of [SortField] are accepted, as the order is written
+//! within the SQL queries; each of them maps to a fixed column of the `Cocktail` table.
+
+use crate::domain::{
+    collation::{order_by_name, Collation},
+    DataDomainError,
+};
+use std::fmt;
+
+/// Fields of the recipes that can be used to sort the results of a search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortField {
+    /// Sorted ignoring the accents and the case, see [crate::domain::search_text], using the collation of the language
+    /// of the request (see [crate::domain::collation]).
+    Name,
+    Rating,
+    /// Sorted following the level of the categories, from `easy` to `pro`.
+    Category,
+    PrepTime,
+    CreationDate,
+    UpdateDate,
+}
+
+impl SortField {
+    /// Column of the `Cocktail` table that holds the field.
+    pub fn column(&self) -> &'static str {
+        match self {
+            SortField::Name => "search_name",
+            SortField::Rating => "rating",
+            SortField::Category => "category",
+            SortField::PrepTime => "prep_time_minutes",
+            SortField::CreationDate => "creation_date",
+            SortField::UpdateDate => "update_date",
+        }
+    }
+}
+
+impl fmt::Display for SortField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            SortField::Name => "name",
+            SortField::Rating => "rating",
+            SortField::Category => "category",
+            SortField::PrepTime => "prep_time",
+            SortField::CreationDate => "creation_date",
+            SortField::UpdateDate => "update_date",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+impl TryFrom<&str> for SortField {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "name" => Ok(SortField::Name),
+            "rating" => Ok(SortField::Rating),
+            "category" => Ok(SortField::Category),
+            "prep_time" => Ok(SortField::PrepTime),
+            "creation_date" => Ok(SortField::CreationDate),
+            "update_date" => Ok(SortField::UpdateDate),
+            _ => Err(DataDomainError::InvalidData),
+        }
+    }
+}
+
+/// Order of the results of a recipe search, i.e. `rating,-creation_date`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecipeSort(Vec<(SortField, bool)>);
+
+impl RecipeSort {
+    /// Parse a comma-separated list of fields, each of them optionally prefixed by `-` to sort it in descending order.
+    ///
+    /// # Description
+    ///
+    /// Unknown fields, fields given twice and empty lists are rejected.
+    pub fn parse(value: &str) -> Result<Self, DataDomainError> {
+        let mut keys: Vec<(SortField, bool)> = Vec::new();
+
+        for key in value.split(',').filter(|s| !s.trim().is_empty()) {
+            let key = key.trim();
+            let (field, descending) = match key.strip_prefix('-') {
+                Some(field) => (SortField::try_from(field)?, true),
+                None => (SortField::try_from(key)?, false),
+            };
+            if keys.iter().any(|(f, _)| *f == field) {
+                return Err(DataDomainError::InvalidData);
+            }
+            keys.push((field, descending));
+        }
+
+        if keys.is_empty() {
+            Err(DataDomainError::InvalidData)
+        } else {
+            Ok(RecipeSort(keys))
+        }
+    }
+
+    /// Build the terms of the `ORDER BY` clause. The ID of the recipes is appended, so the order is stable.
+    ///
+    /// # Description
+    ///
+    /// The names are sorted using the given collation, see [order_by_name].
+    pub fn order_by(&self, collation: Option<&Collation>) -> String {
+        let mut terms = self
+            .0
+            .iter()
+            .map(|(field, descending)| {
+                let column = match field {
+                    SortField::Name => order_by_name(field.column(), collation),
+                    _ => field.column().to_owned(),
+                };
+                format!("{column} {}", if *descending { "DESC" } else { "ASC" })
+            })
+            .collect::<Vec<String>>();
+        terms.push("id".into());
+
+        terms.join(", ")
+    }
+}
+
+impl fmt::Display for RecipeSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys = self
+            .0
+            .iter()
+            .map(|(field, descending)| format!("{}{field}", if *descending { "-" } else { "" }))
+            .collect::<Vec<String>>();
+
+        write!(f, "{}", keys.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{collation::NameCollations, Language};
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case("rating", Language::En, "rating ASC, id")]
+    #[case("-rating,name", Language::En, "rating DESC, search_name ASC, id")]
+    #[case(
+        "-rating,name",
+        Language::Es,
+        "rating DESC, search_name COLLATE utf8mb4_uca1400_spanish_ai_ci ASC, id"
+    )]
+    #[case(
+        " prep_time , -creation_date ",
+        Language::Es,
+        "prep_time_minutes ASC, creation_date DESC, id"
+    )]
+    #[case(
+        "CATEGORY,-update_date",
+        Language::En,
+        "category ASC, update_date DESC, id"
+    )]
+    fn sorts_are_translated(
+        #[case] value: &str,
+        #[case] language: Language,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            RecipeSort::parse(value)
+                .unwrap()
+                .order_by(NameCollations::default().get(language)),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case(",")]
+    #[case("owner")]
+    #[case("rating,-rating")]
+    #[case("--rating")]
+    #[case("rating; DROP TABLE Cocktail")]
+    fn invalid_sorts_are_rejected(#[case] value: &str) {
+        assert!(RecipeSort::parse(value).is_err());
+    }
+
+    #[rstest]
+    fn sorts_are_displayed() {
+        let sort = RecipeSort::parse("-rating, name").unwrap();
+        assert_eq!(sort.to_string(), "-rating,name");
+    }
+}
@@ -12,7 +12,7 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::{Validate, ValidationError};
 
-// Regex to validate an Uuid.
+// Regex to validate the identifier of a tag.
 static RE_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"[a-z_]{2,}$").unwrap());
 
 /// Tag data object.
@@ -44,6 +44,68 @@ impl Tag {
             Err(_) => Err(ValidationError::new("2")),
         }
     }
+
+    /// Parse a comma-separated list of tags, i.e. `tequila,reposado`.
+    ///
+    /// # Description
+    ///
+    /// Every tag of the list is validated, and an error is returned when any of them is invalid. Empty items and
+    /// duplicated tags are ignored.
+    pub fn parse_list(value: &str) -> Result<Vec<Tag>, ValidationError> {
+        let mut tags: Vec<Tag> = Vec::new();
+
+        for tag in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let tag = Tag::new(tag)?;
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+
+        Ok(tags)
+    }
+}
+
+/// Serde helpers to handle an optional list of tags encoded as a comma-separated string.
+///
+/// # Description
+///
+/// Query strings can't hold a list, so lists of tags are sent as a single string: `tags=tequila,reposado`. Use these
+/// helpers with `#[serde(default, with = "crate::domain::tag::tag_list")]` to get a `Option<Vec<Tag>>` with all the
+/// tags validated. An empty list is deserialised as `None`.
+pub mod tag_list {
+    use super::Tag;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        tags: &Option<Vec<Tag>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match tags {
+            Some(tags) => serializer.serialize_str(
+                &tags
+                    .iter()
+                    .map(|t| t.identifier.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(","),
+            ),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<Tag>>, D::Error> {
+        let value = Option::<String>::deserialize(deserializer)?;
+
+        match value {
+            Some(value) => {
+                let tags = Tag::parse_list(&value)
+                    .map_err(|_| D::Error::custom(format!("invalid tag list: {value}")))?;
+                Ok(if tags.is_empty() { None } else { Some(tags) })
+            }
+            None => Ok(None),
+        }
+    }
 }
 
 impl std::fmt::Display for Tag {
@@ -66,6 +128,7 @@ fn validate_identifier(value: &str) -> Result<(), ValidationError> {
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
     use rstest::*;
 
     #[rstest]
@@ -85,6 +148,17 @@ mod tests {
         assert!(Tag::new(input).is_ok())
     }
 
+    #[rstest]
+    fn tag_lists_are_parsed() {
+        let tags =
+            Tag::parse_list("Tequila, reposado,,tequila").expect("Failed to parse a valid list");
+        assert_eq!(
+            tags,
+            vec![Tag::new("tequila").unwrap(), Tag::new("reposado").unwrap()]
+        );
+        assert!(Tag::parse_list("tequila,a(tag)").is_err());
+    }
+
     #[rstest]
     #[case("customTag")]
     #[case("ALLCAPITALLETTERSTAG")]
@@ -94,4 +168,34 @@ mod tests {
             input.to_lowercase()
         )
     }
+
+    proptest! {
+        #[test]
+        fn tags_are_normalised(input in "[a-zA-Z_]{2,20}") {
+            let tag = Tag::new(&input).expect("Failed to build a tag");
+            prop_assert_eq!(&tag.identifier, &input.to_ascii_lowercase());
+            prop_assert_eq!(Tag::new(&tag.identifier).expect("Failed to build a tag"), tag);
+        }
+
+        #[test]
+        fn arbitrary_input_builds_normalised_tags(input in "\\PC{0,25}") {
+            if let Ok(tag) = Tag::new(&input) {
+                prop_assert!((2..=20).contains(&tag.identifier.chars().count()));
+                prop_assert_eq!(&tag.identifier, &input.to_ascii_lowercase());
+                prop_assert_eq!(Tag::new(&tag.identifier).expect("Failed to build a tag"), tag);
+            }
+        }
+
+        #[test]
+        fn tag_lists_keep_the_first_occurrence(identifiers in prop::collection::vec("[a-z_]{2,20}", 1..8)) {
+            let tags = Tag::parse_list(&identifiers.join(",")).expect("Failed to parse a valid list");
+            let mut expected: Vec<&String> = Vec::new();
+            for identifier in &identifiers {
+                if !expected.contains(&identifier) {
+                    expected.push(identifier);
+                }
+            }
+            prop_assert_eq!(tags.iter().map(|t| &t.identifier).collect::<Vec<_>>(), expected);
+        }
+    }
 }
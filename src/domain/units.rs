@@ -0,0 +1,195 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Conversion of the amounts of the ingredients between unit systems.
+//!
+//! # Description
+//!
+//! Recipes are stored using the units chosen by their authors. Clients can ask for the recipes in a given
+//! [UnitSystem], and the amounts given in units of the other system are converted. Units that don't belong to any
+//! system (dashes, drops, spoons...) are kept. Converted amounts are rounded to the precision of the tools used
+//! behind the bar: whole millilitres and grams, and quarters of ounce.
+//!
+//! Ounces are taken as fluid ounces when converted to millilitres. Grams are converted to (weight) ounces in the
+//! imperial system.
+
+use crate::domain::{DataDomainError, QuantityUnit};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::ToSchema;
+
+/// Millilitres in a US fluid ounce.
+const ML_PER_OZ: f32 = 29.5735;
+/// Grams in an ounce.
+const G_PER_OZ: f32 = 28.3495;
+/// Millilitres in a US cup.
+const ML_PER_CUP: f32 = 236.588;
+
+/// Systems of units of the amounts of the ingredients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    /// Millilitres and grams.
+    Metric,
+    /// Ounces and cups.
+    Imperial,
+}
+
+impl fmt::Display for UnitSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnitSystem::Metric => write!(f, "metric"),
+            UnitSystem::Imperial => write!(f, "imperial"),
+        }
+    }
+}
+
+impl TryFrom<&str> for UnitSystem {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "metric" => Ok(UnitSystem::Metric),
+            "imperial" => Ok(UnitSystem::Imperial),
+            _ => Err(DataDomainError::InvalidData),
+        }
+    }
+}
+
+/// Languages of the content generated by the API.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Es,
+}
+
+impl Language {
+    /// Label of a unit, as written within the text of a recipe.
+    pub fn unit_label(&self, unit: QuantityUnit) -> &'static str {
+        match (self, unit) {
+            (Language::Es, QuantityUnit::Dash) => "golpe",
+            (Language::Es, QuantityUnit::Unit) => "unidad",
+            (Language::Es, QuantityUnit::Drops) => "gota",
+            (Language::Es, QuantityUnit::TableSpoon) => "cda",
+            (Language::Es, QuantityUnit::TeaSpoon) => "cdta",
+            (Language::Es, QuantityUnit::Cups) => "taza",
+            (_, QuantityUnit::Grams) => "g",
+            (_, QuantityUnit::MilliLiter) => "ml",
+            (_, QuantityUnit::Ounces) => "oz",
+            (Language::En, QuantityUnit::Dash) => "dash",
+            (Language::En, QuantityUnit::Unit) => "unit",
+            (Language::En, QuantityUnit::Drops) => "drop",
+            (Language::En, QuantityUnit::TableSpoon) => "tbsp",
+            (Language::En, QuantityUnit::TeaSpoon) => "tsp",
+            (Language::En, QuantityUnit::Cups) => "cup",
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Language::En => write!(f, "en"),
+            Language::Es => write!(f, "es"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Language {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "en" => Ok(Language::En),
+            "es" => Ok(Language::Es),
+            _ => Err(DataDomainError::InvalidData),
+        }
+    }
+}
+
+/// Convert an amount to the given unit system. Amounts that need no conversion are returned as they are.
+pub fn convert_amount(
+    quantity: f32,
+    unit: QuantityUnit,
+    system: UnitSystem,
+) -> (f32, QuantityUnit) {
+    match (system, unit) {
+        (UnitSystem::Metric, QuantityUnit::Ounces) => {
+            ((quantity * ML_PER_OZ).round(), QuantityUnit::MilliLiter)
+        }
+        (UnitSystem::Metric, QuantityUnit::Cups) => {
+            ((quantity * ML_PER_CUP).round(), QuantityUnit::MilliLiter)
+        }
+        (UnitSystem::Imperial, QuantityUnit::MilliLiter) => {
+            (round_quarter(quantity / ML_PER_OZ), QuantityUnit::Ounces)
+        }
+        (UnitSystem::Imperial, QuantityUnit::Grams) => {
+            (round_quarter(quantity / G_PER_OZ), QuantityUnit::Ounces)
+        }
+        _ => (quantity, unit),
+    }
+}
+
+/// Round to the nearest quarter. Small amounts are never rounded down to zero.
+fn round_quarter(value: f32) -> f32 {
+    ((value * 4.0).round() / 4.0).max(0.25)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case(2.0, QuantityUnit::Ounces, UnitSystem::Metric, (59.0, QuantityUnit::MilliLiter))]
+    #[case(1.0, QuantityUnit::Cups, UnitSystem::Metric, (237.0, QuantityUnit::MilliLiter))]
+    #[case(60.0, QuantityUnit::MilliLiter, UnitSystem::Imperial, (2.0, QuantityUnit::Ounces))]
+    #[case(45.0, QuantityUnit::MilliLiter, UnitSystem::Imperial, (1.5, QuantityUnit::Ounces))]
+    #[case(2.0, QuantityUnit::MilliLiter, UnitSystem::Imperial, (0.25, QuantityUnit::Ounces))]
+    #[case(100.0, QuantityUnit::Grams, UnitSystem::Imperial, (3.5, QuantityUnit::Ounces))]
+    #[case(60.0, QuantityUnit::MilliLiter, UnitSystem::Metric, (60.0, QuantityUnit::MilliLiter))]
+    #[case(2.0, QuantityUnit::Dash, UnitSystem::Imperial, (2.0, QuantityUnit::Dash))]
+    fn amounts_are_converted(
+        #[case] quantity: f32,
+        #[case] unit: QuantityUnit,
+        #[case] system: UnitSystem,
+        #[case] expected: (f32, QuantityUnit),
+    ) {
+        assert_eq!(convert_amount(quantity, unit, system), expected);
+    }
+
+    #[rstest]
+    #[case(Language::En, QuantityUnit::TableSpoon, "tbsp")]
+    #[case(Language::Es, QuantityUnit::TableSpoon, "cda")]
+    #[case(Language::Es, QuantityUnit::MilliLiter, "ml")]
+    fn units_are_labelled(
+        #[case] language: Language,
+        #[case] unit: QuantityUnit,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(language.unit_label(unit), expected);
+    }
+
+    #[rstest]
+    fn names_round_trip() {
+        for system in [UnitSystem::Metric, UnitSystem::Imperial] {
+            assert_eq!(
+                UnitSystem::try_from(system.to_string().as_str()).ok(),
+                Some(system)
+            );
+        }
+        for language in [Language::En, Language::Es] {
+            assert_eq!(
+                Language::try_from(language.to_string().as_str()).ok(),
+                Some(language)
+            );
+        }
+        assert!(Language::try_from("fr").is_err());
+    }
+}
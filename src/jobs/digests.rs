@@ -0,0 +1,167 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Generation of the digests of new recipes (see [crate::routes::me::digests]).
+
+use crate::{
+    domain::{ClientId, ServerError},
+    routes::{
+        me::utils::{
+            get_digest_recipes_from_db, get_due_digests_from_db, get_search_from_db,
+            mark_digest_sent_in_db, DigestRecipe,
+        },
+        recipe::RecipeSearch,
+    },
+    utils::mailing::{
+        register_email_attempt, send_digest_email, EmailKind, EmailSender, MailCorrelation,
+    },
+};
+use actix_web::web::Data;
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+use std::str::FromStr;
+use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
+
+/// Send the digests whose period elapsed at the given time. The amount of sent emails is returned.
+///
+/// # Description
+///
+/// Every digest lists the recipes published since the previous one (or since the subscription), and links them to
+/// the frontend. Digests with no new recipes are not sent, but their period is restarted anyway. Digests whose email
+/// fails are retried on the next run.
+///
+/// Digests bound to a saved search only list the new recipes that match the search. Saved searches that are no longer
+/// supported by the search engine are skipped, so their digests are retried on the next run.
+#[instrument(skip(pool, mail_client))]
+pub async fn send_due_digests(
+    pool: &MySqlPool,
+    mail_client: Data<dyn EmailSender>,
+    frontend_url: &str,
+    now: DateTime<Utc>,
+) -> Result<usize, ServerError> {
+    let mut sent = 0;
+
+    for due in get_due_digests_from_db(pool, now).await? {
+        let digest = &due.digest;
+        let Ok(client_id) = ClientId::from_str(&due.client_id) else {
+            warn!("Invalid client ID stored in the DB: {}", due.client_id);
+            continue;
+        };
+
+        let matches = match &digest.search_id {
+            Some(search_id) => match search_matches(pool, &client_id, search_id).await {
+                Some(ids) => Some(ids),
+                None => {
+                    warn!("The saved search of the digest {} can't be run", digest.id);
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        let since = digest.last_sent_at.unwrap_or(digest.created_at);
+        let recipes =
+            get_digest_recipes_from_db(pool, digest, since, now, matches.as_deref()).await?;
+
+        if recipes.is_empty() {
+            debug!("No new recipes for the digest {}", digest.id);
+            mark_digest_sent_in_db(pool, &digest.id, now).await?;
+            continue;
+        }
+
+        // Digests are not tied to a request, so every email gets a request ID of its own.
+        let correlation = MailCorrelation::new(Uuid::now_v7(), &client_id);
+        let outcome = send_digest_email(
+            mail_client.clone(),
+            &due.email,
+            &format_digest(&recipes, frontend_url),
+            &correlation,
+        )
+        .await;
+        if let Err(e) =
+            register_email_attempt(pool, &correlation, EmailKind::Digest, &due.email, &outcome)
+                .await
+        {
+            warn!("Failed to register the email attempt: {e}");
+        }
+
+        if outcome.is_ok() {
+            mark_digest_sent_in_db(pool, &digest.id, now).await?;
+            sent += 1;
+        }
+    }
+
+    info!("{sent} digests sent");
+
+    Ok(sent)
+}
+
+/// Run a saved search of a client, and return the IDs of the matching recipes.
+async fn search_matches(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+    search_id: &str,
+) -> Option<Vec<Uuid>> {
+    let search_id = Uuid::parse_str(search_id).ok()?;
+    let saved = get_search_from_db(pool, client_id, &search_id)
+        .await
+        .ok()??;
+    let search = RecipeSearch::new(saved.query).ok()?;
+
+    match search.run_ids(pool).await {
+        Ok(ids) => Some(ids),
+        Err(e) => {
+            warn!("Failed to run the saved search {search_id}: {e}");
+            None
+        }
+    }
+}
+
+/// List the recipes of a digest, one line per recipe.
+fn format_digest(recipes: &[DigestRecipe], frontend_url: &str) -> String {
+    let frontend_url = frontend_url.trim_end_matches('/');
+
+    recipes
+        .iter()
+        .map(|r| {
+            format!(
+                "- {} ({}): {frontend_url}/recipe/{}",
+                r.name, r.category, r.id
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn digests_link_the_frontend() {
+        let recipes = [
+            DigestRecipe {
+                id: "0191e13b-5ab7-78f1-bc06-be503a6c111b".into(),
+                name: "Margarita".into(),
+                category: "easy".into(),
+            },
+            DigestRecipe {
+                id: "0191e13b-5ab7-78f1-bc06-be503a6c111c".into(),
+                name: "Negroni".into(),
+                category: "medium".into(),
+            },
+        ];
+
+        assert_eq!(
+            format_digest(&recipes, "https://lacoctelera.test/"),
+            "- Margarita (easy): https://lacoctelera.test/recipe/0191e13b-5ab7-78f1-bc06-be503a6c111b\n\
+             - Negroni (medium): https://lacoctelera.test/recipe/0191e13b-5ab7-78f1-bc06-be503a6c111c"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Record of the history of the health of the server (see [crate::routes::status]).
+//!
+//! # Description
+//!
+//! Every run takes a snapshot of the health of the server, stores it, and deletes the snapshots older than the
+//! retention period. Snapshots taken while the DB is down can't be stored right away, so they are kept in memory and
+//! stored on the first run that reaches the DB; thus the downtime shows in the history. At most
+//! [MAX_PENDING_SNAPSHOTS] are kept, the oldest ones are dropped first.
+
+use crate::{
+    domain::ServerError,
+    routes::{
+        admin::utils::get_maintenance_window_from_db,
+        me::utils::count_pending_notification_emails_in_db,
+        status::{
+            prune_health_snapshots_in_db, store_health_snapshot_in_db, HealthSnapshot,
+            SnapshotStatus,
+        },
+    },
+    utils::metrics::metrics,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use sqlx::MySqlPool;
+use std::time::Instant;
+use tracing::{error, info, instrument};
+
+/// Maximum amount of snapshots kept in memory while the DB is down.
+pub const MAX_PENDING_SNAPSHOTS: usize = 1000;
+
+/// Take a snapshot of the health of the server.
+///
+/// # Description
+///
+/// The DB is pinged to measure its latency. When the DB answers, the status tells whether a maintenance window is
+/// ongoing, and the emails waiting to be sent are counted. The rates of requests and server errors are taken from the
+/// live metrics (see [crate::utils::metrics]).
+#[instrument(skip(pool))]
+pub async fn take_health_snapshot(pool: &MySqlPool, now: DateTime<Utc>) -> HealthSnapshot {
+    let started = Instant::now();
+    let db_latency = match sqlx::query("SELECT 1").execute(pool).await {
+        Ok(_) => {
+            let latency = started.elapsed();
+            metrics().record_db_latency(latency);
+            Some(latency)
+        }
+        Err(e) => {
+            error!("The DB is not available: {e}");
+            None
+        }
+    };
+
+    let (status, mail_outbox) = match db_latency {
+        Some(_) => {
+            let on_maintenance = get_maintenance_window_from_db(pool, now)
+                .await
+                .ok()
+                .flatten()
+                .is_some_and(|window| window.is_ongoing(now));
+            (
+                if on_maintenance {
+                    SnapshotStatus::OnMaintenance
+                } else {
+                    SnapshotStatus::Ok
+                },
+                count_pending_notification_emails_in_db(pool).await.ok(),
+            )
+        }
+        None => (SnapshotStatus::DbDown, None),
+    };
+    let requests = metrics().snapshot().requests;
+
+    HealthSnapshot {
+        taken_at: now,
+        status,
+        db_latency_ms: db_latency.map(|latency| latency.as_secs_f64() * 1000.0),
+        request_rate: requests.request_rate,
+        error_rate: requests.error_rate,
+        mail_outbox,
+    }
+}
+
+/// Take a snapshot of the health of the server, and store it along with the `pending` ones.
+///
+/// # Description
+///
+/// Snapshots are stored in the order they were taken. Those that can't be stored are left in `pending`, so they are
+/// stored by a later run. Once all of them are stored, the snapshots older than `retention` are deleted. The amount
+/// of stored snapshots is returned.
+#[instrument(skip(pool, pending))]
+pub async fn record_health_history(
+    pool: &MySqlPool,
+    pending: &mut Vec<HealthSnapshot>,
+    retention: TimeDelta,
+    now: DateTime<Utc>,
+) -> Result<usize, ServerError> {
+    pending.push(take_health_snapshot(pool, now).await);
+    if pending.len() > MAX_PENDING_SNAPSHOTS {
+        pending.drain(..pending.len() - MAX_PENDING_SNAPSHOTS);
+    }
+
+    let mut stored = 0;
+    while let Some(snapshot) = pending.first() {
+        store_health_snapshot_in_db(pool, snapshot).await?;
+        pending.remove(0);
+        stored += 1;
+    }
+
+    let pruned = prune_health_snapshots_in_db(pool, now - retention).await?;
+    if pruned > 0 {
+        info!("{pruned} health snapshots deleted");
+    }
+
+    Ok(stored)
+}
@@ -0,0 +1,118 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Production and delivery of the notifications of the clients (see [crate::routes::me::notifications]).
+
+use crate::{
+    domain::{ClientId, IdGenerator, ServerError},
+    routes::me::{
+        notifications::NotificationKind,
+        utils::{
+            get_expiring_keys_from_db, get_pending_notification_emails_from_db,
+            mark_notification_emailed_in_db, store_notification_in_db,
+        },
+    },
+    utils::mailing::{
+        register_email_attempt, send_notification_email, EmailKind, EmailSender, MailCorrelation,
+    },
+};
+use actix_web::web::Data;
+use chrono::{DateTime, TimeDelta, Utc};
+use sqlx::MySqlPool;
+use std::str::FromStr;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+/// Produce the notifications of the periodic events, and mirror the pending notifications to email.
+///
+/// # Description
+///
+/// The clients get notified of their API keys that expire within `expiry_notice`. Every key is notified once.
+///
+/// When a `mail_client` is given, the unread notifications of the clients that enabled `email_notifications` are
+/// sent to their email. Notifications whose email fails are retried on the next run. The amount of new notifications
+/// is returned.
+#[instrument(skip(pool, ids, mail_client))]
+pub async fn run_notifications(
+    pool: &MySqlPool,
+    ids: &dyn IdGenerator,
+    mail_client: Option<Data<dyn EmailSender>>,
+    expiry_notice: TimeDelta,
+    now: DateTime<Utc>,
+) -> Result<usize, ServerError> {
+    let mut notified = 0;
+
+    for key in get_expiring_keys_from_db(pool, now, now + expiry_notice).await? {
+        let message = format!(
+            "Your API key \"{}\" expires on {} (UTC). Request a new key before that date to keep your access.",
+            key.label,
+            key.valid_until.format("%Y-%m-%d %H:%M:%S")
+        );
+        if store_notification_in_db(
+            pool,
+            ids,
+            &key.client_id,
+            NotificationKind::TokenExpiring,
+            &key.id.to_string(),
+            &message,
+        )
+        .await?
+        {
+            notified += 1;
+        }
+    }
+    info!("{notified} new notifications");
+
+    if let Some(mail_client) = mail_client {
+        mirror_notifications(pool, mail_client).await?;
+    }
+
+    Ok(notified)
+}
+
+/// Send the pending notifications to the email of their clients. The amount of sent emails is returned.
+async fn mirror_notifications(
+    pool: &MySqlPool,
+    mail_client: Data<dyn EmailSender>,
+) -> Result<usize, ServerError> {
+    let mut sent = 0;
+
+    for pending in get_pending_notification_emails_from_db(pool).await? {
+        let Ok(client_id) = ClientId::from_str(&pending.client_id) else {
+            warn!("Invalid client ID stored in the DB: {}", pending.client_id);
+            continue;
+        };
+
+        // Notifications are not tied to a request, so every email gets a request ID of its own.
+        let correlation = MailCorrelation::new(Uuid::now_v7(), &client_id);
+        let outcome = send_notification_email(
+            mail_client.clone(),
+            &pending.email,
+            &pending.message,
+            &correlation,
+        )
+        .await;
+        if let Err(e) = register_email_attempt(
+            pool,
+            &correlation,
+            EmailKind::Notification,
+            &pending.email,
+            &outcome,
+        )
+        .await
+        {
+            warn!("Failed to register the email attempt: {e}");
+        }
+
+        if outcome.is_ok() {
+            mark_notification_emailed_in_db(pool, &pending.id).await?;
+            sent += 1;
+        }
+    }
+    info!("{sent} notifications sent by email");
+
+    Ok(sent)
+}
@@ -0,0 +1,251 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Delivery of the domain events to the configured sinks (see [crate::utils::events]).
+//!
+//! # Description
+//!
+//! The event log acts as an outbox: changes and their events are committed together, and this job publishes the
+//! committed events afterwards. Every sink keeps its own position within the log (table `EventOutbox`), so a sink
+//! that is down doesn't hold back the rest.
+//!
+//! Events are published in order, and the position of a sink only moves forward once an event is delivered. Thus
+//! delivery is at-least-once: an event is published again when the job stops between its delivery and the update of
+//! the position. An event that fails [OutboxSettings::max_attempts] times in a row is a poison message: it is moved to
+//! the table `DeadEvent` and skipped, so the rest of the events can be delivered.
+
+use crate::{
+    configuration::OutboxSettings,
+    domain::ServerError,
+    utils::events::{get_events_from_db, EventSink},
+};
+use sqlx::{MySqlPool, Row};
+use std::sync::Arc;
+use tracing::{error, info, instrument, warn};
+
+/// Maximum length of the errors stored in the DB.
+const MAX_ERROR_LENGTH: usize = 512;
+
+/// What happens to an event that a sink failed to publish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FailureOutcome {
+    /// The event is published again on the next run.
+    Retry,
+    /// The event is moved to the dead letters, and skipped.
+    DeadLetter,
+}
+
+impl FailureOutcome {
+    fn new(attempts: u32, max_attempts: u32) -> Self {
+        if attempts >= max_attempts.max(1) {
+            FailureOutcome::DeadLetter
+        } else {
+            FailureOutcome::Retry
+        }
+    }
+}
+
+/// Position of a sink within the event log.
+#[derive(Debug, Default)]
+struct SinkCursor {
+    last_event_id: u64,
+    /// Failed attempts to publish the event that follows `last_event_id`.
+    attempts: u32,
+}
+
+/// Publish the pending events to every sink. The amount of delivered events is returned.
+///
+/// # Description
+///
+/// At most [OutboxSettings::batch_size] events are published to each sink per run. A sink stops at the first event
+/// that fails, so the order of the events is preserved; the event is retried on the next run.
+#[instrument(skip(pool, sinks))]
+pub async fn dispatch_events(
+    pool: &MySqlPool,
+    sinks: &[Arc<dyn EventSink>],
+    settings: &OutboxSettings,
+) -> Result<usize, ServerError> {
+    let mut delivered = 0;
+
+    for sink in sinks.iter() {
+        let mut cursor = get_cursor_from_db(pool, sink.name()).await?;
+
+        for stored in get_events_from_db(pool, cursor.last_event_id, settings.batch_size).await? {
+            match sink.publish(&stored).await {
+                Ok(()) => {
+                    cursor = SinkCursor {
+                        last_event_id: stored.id,
+                        attempts: 0,
+                    };
+                    store_cursor_in_db(pool, sink.name(), &cursor, None).await?;
+                    delivered += 1;
+                }
+                Err(e) => {
+                    let reason = truncate_error(&e.to_string());
+                    cursor.attempts += 1;
+                    warn!(
+                        "Failed to publish the event {} to {} (attempt {}): {reason}",
+                        stored.id,
+                        sink.name(),
+                        cursor.attempts
+                    );
+
+                    match FailureOutcome::new(cursor.attempts, settings.max_attempts) {
+                        FailureOutcome::Retry => {
+                            store_cursor_in_db(pool, sink.name(), &cursor, Some(&reason)).await?;
+                            break;
+                        }
+                        FailureOutcome::DeadLetter => {
+                            error!(
+                                "The event {} is dead-lettered for the sink {}",
+                                stored.id,
+                                sink.name()
+                            );
+                            dead_letter_in_db(pool, sink.name(), stored.id, &cursor, &reason)
+                                .await?;
+                            cursor = SinkCursor {
+                                last_event_id: stored.id,
+                                attempts: 0,
+                            };
+                        }
+                    }
+                }
+            }
+        }
+    }
+    info!("{delivered} events delivered");
+
+    Ok(delivered)
+}
+
+async fn get_cursor_from_db(pool: &MySqlPool, sink: &str) -> Result<SinkCursor, ServerError> {
+    let row = sqlx::query("SELECT last_event_id, attempts FROM EventOutbox WHERE sink = ?")
+        .bind(sink)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    match row {
+        Some(row) => Ok(SinkCursor {
+            last_event_id: row.try_get("last_event_id").map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?,
+            attempts: row.try_get("attempts").map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?,
+        }),
+        None => Ok(SinkCursor::default()),
+    }
+}
+
+async fn store_cursor_in_db(
+    pool: &MySqlPool,
+    sink: &str,
+    cursor: &SinkCursor,
+    error: Option<&str>,
+) -> Result<(), ServerError> {
+    sqlx::query(
+        "INSERT INTO EventOutbox (sink, last_event_id, attempts, last_error) VALUES (?, ?, ?, ?) \
+        ON DUPLICATE KEY UPDATE last_event_id = VALUES(last_event_id), attempts = VALUES(attempts), \
+        last_error = VALUES(last_error)",
+    )
+    .bind(sink)
+    .bind(cursor.last_event_id)
+    .bind(cursor.attempts)
+    .bind(error)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
+
+/// Move an event to the dead letters of a sink, and skip it.
+async fn dead_letter_in_db(
+    pool: &MySqlPool,
+    sink: &str,
+    event_id: u64,
+    cursor: &SinkCursor,
+    error: &str,
+) -> Result<(), ServerError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    sqlx::query(
+        "INSERT IGNORE INTO DeadEvent (sink, event_id, attempts, error) VALUES (?, ?, ?, ?)",
+    )
+    .bind(sink)
+    .bind(event_id)
+    .bind(cursor.attempts)
+    .bind(error)
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    sqlx::query(
+        "INSERT INTO EventOutbox (sink, last_event_id, attempts) VALUES (?, ?, 0) \
+        ON DUPLICATE KEY UPDATE last_event_id = VALUES(last_event_id), attempts = 0, last_error = NULL",
+    )
+    .bind(sink)
+    .bind(event_id)
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })
+}
+
+fn truncate_error(error: &str) -> String {
+    error.chars().take(MAX_ERROR_LENGTH).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case(1, 3, FailureOutcome::Retry)]
+    #[case(2, 3, FailureOutcome::Retry)]
+    #[case(3, 3, FailureOutcome::DeadLetter)]
+    #[case(1, 0, FailureOutcome::DeadLetter)]
+    fn poison_events_are_dead_lettered(
+        #[case] attempts: u32,
+        #[case] max_attempts: u32,
+        #[case] expected: FailureOutcome,
+    ) {
+        assert_eq!(FailureOutcome::new(attempts, max_attempts), expected);
+    }
+
+    #[rstest]
+    fn errors_are_truncated() {
+        assert_eq!(truncate_error("timeout"), "timeout");
+        assert_eq!(
+            truncate_error(&"é".repeat(600)).chars().count(),
+            MAX_ERROR_LENGTH
+        );
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Scheduling of the periodic jobs.
+
+use actix_web::rt::time::{interval_at, Instant};
+use std::{future::Future, time::Duration};
+use tracing::info;
+
+/// Run a job periodically within the runtime of the application.
+///
+/// # Description
+///
+/// The first run happens once `period` elapses, rather than right away, so restarting the application doesn't
+/// trigger all the jobs at once. Runs don't overlap: a run that takes longer than `period` delays the next one.
+pub fn spawn_periodic_job<F, Fut>(name: &'static str, period: Duration, job: F)
+where
+    F: Fn() -> Fut + 'static,
+    Fut: Future<Output = ()> + 'static,
+{
+    info!("Job {name} scheduled every {} seconds", period.as_secs());
+
+    actix_web::rt::spawn(async move {
+        let mut interval = interval_at(Instant::now() + period, period);
+
+        loop {
+            interval.tick().await;
+            info!("Running the job {name}");
+            job().await;
+        }
+    });
+}
@@ -0,0 +1,140 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Storage of the anonymous usage analytics (see [crate::utils::metrics::UsageAnalytics]).
+//!
+//! # Description
+//!
+//! Every run adds the usage counted in memory since the previous run to the totals of the current day (UTC), stores the
+//! hashes of the clients that searched every term, and deletes the totals older than the retention period. Counts that
+//! can't be stored are given back to the registry, so they are stored by the next run.
+
+use crate::{
+    domain::ServerError,
+    utils::metrics::{UsageAnalytics, UsageCounts},
+};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use sqlx::MySqlPool;
+use tracing::{error, info, instrument};
+
+/// Store the usage counted by the registry, and delete the totals older than `retention`.
+///
+/// # Description
+///
+/// The amount of endpoints and search terms whose totals were updated is returned.
+#[instrument(skip(pool, registry))]
+pub async fn flush_usage_analytics(
+    pool: &MySqlPool,
+    registry: &UsageAnalytics,
+    retention: TimeDelta,
+    now: DateTime<Utc>,
+) -> Result<usize, ServerError> {
+    let counts = registry.take();
+    let updated = counts.endpoints.len() + counts.searches.len();
+
+    if !counts.is_empty() {
+        if let Err(e) = store_usage_in_db(pool, &counts, now.date_naive()).await {
+            registry.restore(counts);
+            return Err(e);
+        }
+    }
+
+    let pruned = prune_usage_in_db(pool, (now - retention).date_naive()).await?;
+    if pruned > 0 {
+        info!("{pruned} daily usage totals deleted");
+    }
+
+    Ok(updated)
+}
+
+/// Add the counts to the totals of the given day. All the counts are stored, or none of them.
+async fn store_usage_in_db(
+    pool: &MySqlPool,
+    counts: &UsageCounts,
+    day: NaiveDate,
+) -> Result<(), ServerError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    for (endpoint, hits) in &counts.endpoints {
+        sqlx::query(
+            "INSERT INTO EndpointUsage (day, endpoint, hits) VALUES (?, ?, ?) \
+            ON DUPLICATE KEY UPDATE hits = hits + VALUES(hits)",
+        )
+        .bind(day)
+        .bind(endpoint)
+        .bind(hits)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+    }
+
+    for ((resource, term), hits) in &counts.searches {
+        sqlx::query(
+            "INSERT INTO SearchUsage (day, resource, term, hits) VALUES (?, ?, ?, ?) \
+            ON DUPLICATE KEY UPDATE hits = hits + VALUES(hits)",
+        )
+        .bind(day)
+        .bind(*resource)
+        .bind(term)
+        .bind(hits)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+    }
+
+    for searcher in &counts.searchers {
+        sqlx::query(
+            "INSERT IGNORE INTO SearchClient (day, resource, term, client_hash) VALUES (?, ?, ?, ?)",
+        )
+        .bind(searcher.day)
+        .bind(searcher.resource)
+        .bind(&searcher.term)
+        .bind(&searcher.client_hash)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+    }
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })
+}
+
+/// Delete the totals of the days before the given one. The amount of deleted totals is returned.
+async fn prune_usage_in_db(pool: &MySqlPool, before: NaiveDate) -> Result<u64, ServerError> {
+    let mut pruned = 0;
+
+    for query in [
+        "DELETE FROM EndpointUsage WHERE day < ?",
+        "DELETE FROM SearchUsage WHERE day < ?",
+        "DELETE FROM SearchClient WHERE day < ?",
+    ] {
+        pruned += sqlx::query(query)
+            .bind(before)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?
+            .rows_affected();
+    }
+
+    Ok(pruned)
+}
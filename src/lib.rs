@@ -6,14 +6,8 @@
 
 //! La Coctelera library.
 
-use crate::{
-    authentication::{AuthData, SecurityAddon},
-    domain::DataDomainError,
-};
-use once_cell::sync::Lazy;
-use regex::Regex;
+use crate::authentication::{AuthData, SecurityAddon};
 use routes::{health, ingredient::FormData};
-use serde::{Deserialize, Serialize};
 use utoipa::{
     openapi::{Object, ObjectBuilder},
     OpenApi,
@@ -22,25 +16,120 @@ use uuid::Uuid;
 use validator::ValidationError;
 
 // Re-export of the domain objects.
-pub use domain::{IngCategory, Ingredient};
-
-// Regex to validate an Uuid.
-static RE_UUID_V4: Lazy<Regex> = Lazy::new(|| Regex::new(r"([a-fA-F0-9-]{4,12}){5}$").unwrap());
+pub use domain::{IngCategory, Ingredient, ResourceId};
 
+/// Client of the API for Rust consumers, available with the `client` feature.
+#[cfg(feature = "client")]
+pub mod client;
+pub mod client_gen;
 pub mod configuration;
+
+/// Background jobs that run periodically within the runtime of the application.
+pub mod jobs {
+    mod digests;
+    mod health_history;
+    mod notifications;
+    mod outbox;
+    mod scheduler;
+    mod usage_analytics;
+
+    pub use digests::*;
+    pub use health_history::*;
+    pub use notifications::*;
+    pub use outbox::*;
+    pub use scheduler::*;
+    pub use usage_analytics::*;
+}
+
+pub mod seeding;
 pub mod startup;
 pub mod telemetry;
 
+/// HTML pages served by the application, i.e. the token request flow.
+pub mod web {
+    mod pages;
+    mod theme;
+
+    pub use pages::*;
+    pub use theme::*;
+}
+
 pub mod routes {
     pub mod health;
     pub use health::echo;
 
+    pub mod admin {
+        pub mod analytics;
+        pub mod author;
+        pub mod backups;
+        pub mod claims;
+        pub mod clients;
+        pub mod emails;
+        pub mod events;
+        pub mod ingredient_categories;
+        pub mod maintenance;
+        pub mod migrations;
+        pub mod moderation;
+        pub mod tags;
+        pub(crate) mod utils;
+
+        pub use analytics::get_analytics;
+        pub use author::merge_authors;
+        pub use backups::{get_backups, post_backup};
+        pub use claims::{get_claims, review_claim};
+        pub use clients::get_clients;
+        pub use emails::get_emails;
+        pub use events::get_events;
+        pub use ingredient_categories::{
+            delete_ingredient_category, patch_ingredient_category, post_ingredient_category,
+            reclassify_ingredients,
+        };
+        pub use maintenance::{cancel_maintenance, schedule_maintenance};
+        pub use migrations::get_migrations;
+        pub use moderation::{get_moderation_queue, moderate_recipe};
+        pub use tags::bulk_retag;
+    }
+
+    pub mod activity;
+    pub mod assets;
+    pub mod batch;
+    pub mod docs;
+    pub mod expand;
+    pub mod landing;
+    pub mod media;
+    pub mod sitemap;
+    pub mod status;
+
+    /// Resources owned by the client of the API that issues the request.
+    pub mod me {
+        pub mod digests;
+        pub mod inventory;
+        pub mod notifications;
+        pub mod preferences;
+        pub mod searches;
+        pub(crate) mod utils;
+
+        pub use digests::{delete_digest, get_digests, post_digest};
+        pub use inventory::{
+            delete_inventory_item, get_inventory, put_inventory, put_inventory_item,
+        };
+        pub use notifications::{get_notifications, read_all_notifications, read_notification};
+        pub use preferences::{get_preferences, patch_preferences};
+        pub use searches::{delete_search, get_search_results, get_searches, post_search};
+    }
+
     pub mod ingredient {
+        pub mod categories;
+        pub mod delete;
         pub mod get;
+        pub mod image;
         pub mod post;
-        mod utils;
+        pub(crate) mod utils;
 
+        pub use categories::get_ingredient_categories;
+        pub use delete::batch_delete_ingredients;
         pub use get::{get_ingredient, search_ingredient, QueryData};
+        pub use image::{delete_ingredient_image, put_ingredient_image};
         pub use post::{add_ingredient, FormData};
     }
 
@@ -50,36 +139,68 @@ pub mod routes {
         pub mod head;
         pub mod patch;
         pub mod post;
-        mod utils;
+        pub mod social_profile;
+        pub(crate) mod utils;
 
         pub use delete::delete_author;
-        pub use get::{get_author, search_author};
+        pub use get::{get_author, search_author, AuthorQueryParams};
         pub use head::head_author;
         pub use patch::patch_author;
         pub use post::post_author;
+        pub use social_profile::{
+            delete_social_profile, patch_social_profile, post_social_profile,
+        };
     }
 
     pub mod recipe {
+        pub mod claim;
+        pub mod classify;
+        pub mod delete;
         pub mod get;
         pub mod head;
+        pub mod makeable;
         pub mod patch;
+        pub mod pdf;
         pub mod post;
+        pub mod rating;
+        pub mod recipe_id;
+        pub mod search;
+        pub mod search_suggestions;
+        pub mod step_image;
+        pub mod suggest;
         pub mod utils;
+        pub mod workflow;
 
-        pub use get::get_recipe;
+        pub use claim::claim_recipe;
+        pub use classify::{classify_recipe, RecipeDraft};
+        pub use delete::{batch_delete_recipes, delete_recipe};
         pub use get::search_recipe;
+        pub use get::{get_recipe, get_recipe_by_slug, RecipeFormat, RecipeSearch};
         pub use head::head_recipe;
-        pub use patch::patch_recipe;
+        pub use makeable::get_makeable_recipes;
+        pub use patch::{patch_recipe, RecipePatch};
+        pub use pdf::get_recipe_pdf;
         pub use post::post_recipe;
+        pub use rating::rate_recipe;
+        pub use recipe_id::RecipeId;
+        pub use search::{search_recipe_by_example, RecipeExample};
+        pub use search_suggestions::get_search_suggestions;
+        pub use step_image::{delete_step_image, put_step_image};
+        pub use suggest::suggest_recipes;
         pub use utils::{
-            get_recipe_from_db, register_new_recipe, search_recipe_by_category,
-            search_recipe_by_name, search_recipe_by_rating,
+            assign_default_license_in_db, filter_pending_moderation, flag_recipe_in_db,
+            get_recipe_from_db, is_recipe_pending_moderation, register_new_recipe,
+            search_recipe_by_category, search_recipe_by_name, search_recipe_by_prep_time,
+            search_recipe_by_rating, search_recipe_by_tags, search_recipe_without_equipment,
         };
+        pub use workflow::transition_recipe;
     }
 
     pub mod token {
+        pub mod keys;
         pub mod token_request;
 
+        pub use keys::{delete_key, get_keys, post_key};
         pub use token_request::{req_validation, token_req_get, token_req_post};
     }
 }
@@ -87,17 +208,40 @@ pub mod routes {
 pub mod domain {
     pub mod auth;
     pub mod author;
+    pub mod classifier;
+    pub mod collation;
     mod error;
+    pub mod id_generator;
     mod ingredient;
+    pub mod primitives;
+    pub mod rating;
     pub mod recipe;
+    mod resource_id;
+    pub mod sanitize;
+    pub mod screening;
+    pub mod search_expression;
+    pub mod search_text;
+    mod short_id;
+    pub mod slug;
+    pub mod sort;
     pub mod tag;
+    pub mod units;
 
     pub use auth::ClientId;
-    pub use author::{Author, AuthorBuilder, SocialProfile};
-    pub use error::{DataDomainError, ServerError};
+    pub use author::{Author, AuthorBuilder, AuthorEmailPolicy, SocialProfile};
+    pub use error::{ApiError, DataDomainError, ServerError};
+    pub use id_generator::{IdGenerator, IdScheme};
     pub use ingredient::{IngCategory, Ingredient};
-    pub use recipe::{QuantityUnit, Recipe, RecipeCategory, RecipeContains, RecipeQuery, StarRate};
+    pub use primitives::{EmailAddress, ResourceName, WebsiteUrl};
+    pub use recipe::{
+        Equipment, QuantityUnit, Recipe, RecipeCategory, RecipeContains, RecipeLicense,
+        RecipeLimits, RecipeQuery, RecipeSource, RecipeState, RecipeTransition, StarRate,
+        StepImage,
+    };
+    pub use resource_id::{path_config, ResourceId};
+    pub use short_id::ShortId;
     pub use tag::Tag;
+    pub use units::{Language, UnitSystem};
 
     /// Length of the string that represents a client ID.
     pub static ID_LENGTH: usize = 8;
@@ -105,16 +249,144 @@ pub mod domain {
 
 /// Module with utilities.
 pub mod utils {
+    pub mod changes {
+        mod collection_changes;
+
+        pub use collection_changes::*;
+    }
+
+    pub mod events {
+        mod domain_events;
+        mod event_sink;
+        mod message_bus;
+        mod purge_sink;
+
+        pub use domain_events::*;
+        pub use event_sink::*;
+        pub use message_bus::*;
+        pub use purge_sink::*;
+    }
+
+    pub mod http {
+        mod cache_control;
+        mod client_ip;
+        mod headers;
+        mod load_shed;
+        mod maintenance;
+        mod pagination;
+        mod read_only;
+        mod request_metrics;
+        mod throttle;
+
+        pub use cache_control::*;
+        pub use client_ip::*;
+        pub use headers::*;
+        pub use load_shed::*;
+        pub use maintenance::*;
+        pub use pagination::*;
+        pub use read_only::*;
+        pub use request_metrics::*;
+        pub use throttle::*;
+    }
+
+    pub mod jsonld {
+        mod recipe_ld;
+
+        pub use recipe_ld::*;
+    }
+
+    pub mod spec {
+        mod recipe_spec;
+
+        pub use recipe_spec::*;
+    }
+
     pub mod mailing {
+        mod email_sender;
         mod mailing_utils;
 
+        pub use email_sender::*;
         pub use mailing_utils::*;
     }
+
+    pub mod pdf {
+        mod pdf_cache;
+        mod recipe_sheet;
+
+        pub use pdf_cache::*;
+        pub use recipe_sheet::*;
+    }
+
+    pub mod landing {
+        mod activity_cache;
+        mod landing_cache;
+
+        pub use activity_cache::*;
+        pub use landing_cache::*;
+    }
+
+    pub mod assets {
+        mod asset_store;
+
+        pub use asset_store::*;
+    }
+
+    pub mod backup {
+        mod backup_store;
+
+        pub use backup_store::*;
+    }
+
+    pub mod media {
+        mod media_store;
+
+        pub use media_store::*;
+    }
+
+    pub mod metrics {
+        mod metrics_registry;
+        mod suggestion_cache;
+        mod usage_analytics;
+
+        pub use metrics_registry::*;
+        pub use suggestion_cache::*;
+        pub use usage_analytics::*;
+    }
+
+    pub mod sitemap {
+        mod sitemap_cache;
+
+        pub use sitemap_cache::*;
+    }
+}
+
+/// Utilities to run the application within integration tests, available with the `test-utils` feature.
+#[cfg(feature = "test-utils")]
+pub mod testing {
+    pub mod contract;
+    pub mod database;
+    pub mod email;
+    pub mod fixtures;
+    pub mod helpers;
+
+    pub use contract::*;
+    pub use database::*;
+    pub use email::*;
+    pub use fixtures::*;
+    pub use helpers::*;
 }
 
 pub mod authentication {
+    mod access_control;
+    mod auth_cache;
+    mod lockout;
+    mod scopes;
     mod token_auth;
 
+    pub use access_control::*;
+    pub use auth_cache::*;
+    pub use lockout::*;
+    pub use scopes::*;
     use secrecy::SecretString;
     use serde::Deserialize;
     pub use token_auth::*;
@@ -152,20 +424,6 @@ pub mod authentication {
     }
 }
 
-/// Simple query object that represents an ID for recipes.
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct QueryId(Uuid);
-
-impl TryFrom<&str> for QueryId {
-    type Error = DataDomainError;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let id = Uuid::parse_str(value).map_err(|_| DataDomainError::InvalidId)?;
-
-        Ok(QueryId(id))
-    }
-}
-
 /// Main [OpenApi] `Struct`. See [the official docs](https://docs.rs/utoipa/latest/utoipa/derive.OpenApi.html).
 #[derive(OpenApi)]
 #[openapi(
@@ -173,32 +431,144 @@ impl TryFrom<&str> for QueryId {
         routes::ingredient::get::get_ingredient,
         routes::ingredient::get::search_ingredient,
         routes::ingredient::post::add_ingredient,
+        routes::ingredient::delete::batch_delete_ingredients,
+        routes::ingredient::categories::get_ingredient_categories,
+        routes::ingredient::image::put_ingredient_image,
+        routes::ingredient::image::delete_ingredient_image,
+        routes::media::get_media,
         routes::health::echo,
         routes::health::health_check,
+        routes::status::get_status_history,
         routes::author::get::search_author,
         routes::author::get::get_author,
         routes::author::patch::patch_author,
         routes::author::delete::delete_author,
         routes::author::head::head_author,
         routes::author::post::post_author,
+        routes::author::social_profile::post_social_profile,
+        routes::author::social_profile::patch_social_profile,
+        routes::author::social_profile::delete_social_profile,
         routes::recipe::get::search_recipe,
         routes::recipe::get::get_recipe,
+        routes::recipe::get::get_recipe_by_slug,
+        routes::recipe::search::search_recipe_by_example,
+        routes::recipe::search_suggestions::get_search_suggestions,
         routes::recipe::head::head_recipe,
+        routes::recipe::pdf::get_recipe_pdf,
+        routes::recipe::step_image::put_step_image,
+        routes::recipe::step_image::delete_step_image,
         routes::recipe::post::post_recipe,
         routes::recipe::patch::patch_recipe,
+        routes::sitemap::get_sitemap,
+        routes::sitemap::get_sitemap_page,
+        routes::landing::get_category_recipes,
+        routes::landing::get_tag_featured,
+        routes::activity::get_recent_activity,
+        routes::recipe::delete::delete_recipe,
+        routes::recipe::delete::batch_delete_recipes,
+        routes::recipe::classify::classify_recipe,
+        routes::recipe::suggest::suggest_recipes,
+        routes::recipe::makeable::get_makeable_recipes,
+        routes::recipe::workflow::transition_recipe,
+        routes::recipe::claim::claim_recipe,
+        routes::recipe::rating::rate_recipe,
+        routes::admin::author::merge_authors,
+        routes::admin::moderation::get_moderation_queue,
+        routes::admin::moderation::moderate_recipe,
+        routes::admin::emails::get_emails,
+        routes::admin::events::get_events,
+        routes::admin::analytics::get_analytics,
+        routes::admin::tags::bulk_retag,
+        routes::admin::clients::get_clients,
+        routes::admin::ingredient_categories::post_ingredient_category,
+        routes::admin::ingredient_categories::patch_ingredient_category,
+        routes::admin::ingredient_categories::delete_ingredient_category,
+        routes::admin::ingredient_categories::reclassify_ingredients,
+        routes::admin::maintenance::schedule_maintenance,
+        routes::admin::maintenance::cancel_maintenance,
+        routes::admin::migrations::get_migrations,
+        routes::admin::backups::post_backup,
+        routes::admin::backups::get_backups,
+        routes::admin::claims::get_claims,
+        routes::admin::claims::review_claim,
+        routes::token::token_request::token_req_get,
+        routes::token::token_request::token_req_post,
+        routes::token::token_request::req_validation,
+        routes::token::keys::get_keys,
+        routes::token::keys::post_key,
+        routes::token::keys::delete_key,
+        routes::me::digests::get_digests,
+        routes::me::digests::post_digest,
+        routes::me::digests::delete_digest,
+        routes::me::searches::get_searches,
+        routes::me::searches::post_search,
+        routes::me::searches::delete_search,
+        routes::me::searches::get_search_results,
+        routes::me::inventory::get_inventory,
+        routes::me::inventory::put_inventory,
+        routes::me::inventory::put_inventory_item,
+        routes::me::inventory::delete_inventory_item,
+        routes::me::preferences::get_preferences,
+        routes::me::preferences::patch_preferences,
+        routes::me::notifications::get_notifications,
+        routes::me::notifications::read_all_notifications,
+        routes::me::notifications::read_notification,
     ),
     components(
         schemas(
-            Ingredient, IngCategory, FormData, AuthData, health::HealthResponse, health::ServerStatus, domain::Author,
-            domain::SocialProfile, domain::Tag, domain::Recipe, domain::RecipeCategory, domain::StarRate,
-            domain::RecipeContains, domain::QuantityUnit
+            Ingredient, IngCategory, FormData, AuthData, health::HealthResponse, health::ServerStatus,
+            routes::status::HealthSnapshot, routes::status::SnapshotStatus, routes::status::StatusHistory,
+            utils::metrics::MetricsSnapshot, utils::metrics::RequestStats, domain::Author,
+            domain::SocialProfile, domain::Tag, domain::Recipe, domain::RecipeCategory, domain::StarRate, domain::StepImage, domain::RecipeSource, domain::RecipeLicense,
+            domain::RecipeContains, domain::QuantityUnit, domain::Equipment, routes::recipe::RecipePatch, routes::recipe::RecipeDraft, routes::recipe::RecipeExample, routes::recipe::search::IngredientExample,
+            routes::recipe::RecipeFormat, domain::RecipeState, domain::RecipeTransition,
+            domain::classifier::Classification, routes::author::delete::OwnedRecipe,
+            routes::author::delete::OwnedRecipesSummary, routes::admin::author::AuthorMerge,
+            routes::admin::author::AuthorMergeSummary, routes::batch::BatchDelete, routes::batch::BatchOutcome,
+            routes::batch::BatchItemResult, domain::screening::ScreeningFlag, routes::admin::moderation::ModerationEntry,
+            routes::admin::moderation::ModerationAction, routes::admin::moderation::ModerationDecision,
+            routes::admin::emails::EmailRecord, routes::admin::analytics::UsageReport,
+            routes::admin::analytics::DailyUsage, routes::admin::analytics::EndpointUsage,
+            routes::admin::analytics::SearchUsage, utils::mailing::EmailKind, utils::mailing::EmailStatus,
+            utils::events::DomainEvent, utils::events::StoredEvent, routes::admin::tags::TagAction,
+            routes::admin::tags::BulkTagRequest, routes::admin::tags::BulkTagReport,
+            routes::admin::clients::ClientRecord, authentication::ClientKey, routes::token::keys::KeyRequest, domain::auth::TokenRequestData,
+            routes::token::keys::NewClientKey, authentication::Scope, routes::me::digests::Digest,
+            routes::me::digests::DigestRequest, routes::me::digests::DigestFrequency,
+            routes::me::searches::SearchRequest, routes::me::searches::SavedSearch, domain::RecipeQuery,
+            routes::me::inventory::InventoryItem, routes::me::inventory::InventoryEntry,
+            routes::me::inventory::InventoryAmount, routes::recipe::suggest::Suggestion,
+            routes::me::preferences::Preferences, routes::me::preferences::PreferencesPatch,
+            domain::UnitSystem, domain::Language, routes::me::notifications::Notification,
+            routes::me::notifications::NotificationKind,
+            routes::ingredient::categories::IngredientCategory, routes::admin::ingredient_categories::CategoryRequest,
+            routes::admin::ingredient_categories::CategoryPatch, routes::admin::ingredient_categories::ReclassifyRequest,
+            routes::admin::ingredient_categories::ReclassifyReport,
+            utils::http::MaintenanceWindow, routes::admin::migrations::MigrationsReport,
+            routes::admin::migrations::MigrationStatus, routes::admin::migrations::MigrationState,
+            utils::backup::BackupSnapshot, routes::recipe::claim::RecipeClaim,
+            routes::recipe::claim::ClaimState, routes::recipe::claim::ClaimRequest,
+            domain::rating::RatingVote, domain::rating::RecipeRating,
+            routes::recipe::search_suggestions::SearchSuggestions,
+            routes::recipe::get::FacetedResults, routes::recipe::get::RecipeFacets,
+            routes::expand::ExpandedRecipe, routes::expand::RecipeEmbeds,
+            routes::expand::IngredientDetail, routes::expand::Expansion,
+            routes::expand::ExpandedAuthor, routes::expand::AuthorEmbeds,
+            routes::recipe::get::CategoryFacet, routes::recipe::get::RatingFacet, routes::recipe::get::TagFacet,
+            routes::activity::RecentActivity
         )
     ),
     tags(
         (name = "Ingredient", description = "Resources related to the Ingredient management"),
         (name = "Maintenance", description = "Resources related to server's status"),
         (name = "Author", description = "Resources related to the Author management"),
-        (name = "Recipe", description = "Resources related to the Recipe management")
+        (name = "Recipe", description = "Resources related to the Recipe management"),
+        (name = "Admin", description = "Resources restricted to the administrators of the API"),
+        (name = "Sitemap", description = "Sitemaps of the public content for search engines"),
+        (name = "Landing", description = "Collections of recipes for the landing pages of the frontend"),
+        (name = "Media", description = "Images uploaded by the clients of the API"),
+        (name = "Token", description = "Requests and management of the API keys of the clients"),
+        (name = "Me", description = "Resources owned by the client that issues the request")
     ),
     info(
         title = "La Coctelera API",
@@ -240,11 +610,16 @@ pub fn datetime_object_type() -> Object {
         .build()
 }
 
-/// Custom function to validate a String that should contain an [Uuid].
+/// Custom function to validate an [Uuid] used as ID of a data object.
+///
+/// # Description
+///
+/// The format of the ID is already enforced by [Uuid]. This function applies the same extra rules that
+/// [ResourceId] applies to the IDs received from clients of the API: the nil [Uuid] is not a valid ID.
 fn validate_id(value: &Uuid) -> Result<(), ValidationError> {
-    if RE_UUID_V4.is_match(&value.to_string()) {
-        std::result::Result::Ok(())
-    } else {
+    if value.is_nil() {
         Err(ValidationError::new("1"))
+    } else {
+        std::result::Result::Ok(())
     }
 }
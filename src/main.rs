@@ -4,7 +4,13 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use lacoctelera::{configuration::Settings, startup::Application, telemetry::configure_tracing};
+use lacoctelera::{
+    client_gen::generate_clients,
+    configuration::Settings,
+    seeding::seed_demo_data,
+    startup::{api_document, get_connection_pool, Application},
+    telemetry::configure_tracing,
+};
 use tracing::{debug, info};
 
 #[actix_web::main]
@@ -14,6 +20,26 @@ async fn main() -> Result<(), anyhow::Error> {
     // Set up the tracing sub-system.
     configure_tracing(&configuration.application.log_settings);
 
+    // `seed-dev-data` loads the demo dataset in the DB and exits.
+    if std::env::args().nth(1).as_deref() == Some("seed-dev-data") {
+        let pool = get_connection_pool(&configuration.database).await?;
+        seed_demo_data(
+            &pool,
+            configuration.application.id_scheme.generator().as_ref(),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    // `gen-client` publishes the OpenAPI document and the typed clients, and exits. Following arguments restrict the
+    // generators that are run, i.e. `gen-client typescript`.
+    if std::env::args().nth(1).as_deref() == Some("gen-client") {
+        let only = std::env::args().skip(2).collect::<Vec<String>>();
+        let doc = api_document(&configuration.application.base_url);
+        generate_clients(&configuration.application.client_gen, &doc, &only)?;
+        return Ok(());
+    }
+
     info!(
         "La Coctelera API started @ {}",
         configuration.application.port
@@ -0,0 +1,203 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Recent activity of the site.
+//!
+//! # Description
+//!
+//! Home pages of the frontend show what is new in the site: the latest public recipes, the authors that joined
+//! recently, and the tags that are trending. [get_recent_activity] serves all of it at once, so a home page needs a
+//! single request on load. The activity is cached by the server for [ACTIVITY_CACHE_TTL] (see [ActivityCache]).
+
+use crate::{
+    domain::{ApiError, Author, Recipe, ServerError},
+    routes::{
+        author::utils::get_author_from_db,
+        recipe::{get::TagFacet, get_recipe_from_db},
+    },
+    utils::landing::{ActivityCache, ACTIVITY_CACHE_TTL, LANDING_COLLECTION_SIZE},
+};
+use actix_web::{
+    get,
+    http::header::{CacheControl, CacheDirective, ContentType},
+    web::Data,
+    HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{MySqlPool, Row};
+use tracing::{error, info, instrument};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Amount of authors included in the recent activity.
+pub const RECENT_AUTHORS: u32 = 6;
+
+/// Amount of tags included in the recent activity.
+pub const TRENDING_TAGS: u32 = 10;
+
+/// Amount of days of activity considered to find the trending tags.
+pub const TRENDING_WINDOW_DAYS: u32 = 30;
+
+/// Recent activity of the site.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct RecentActivity {
+    /// Latest public recipes, newest first.
+    pub recipes: Vec<Recipe>,
+    /// Latest registered authors, newest first. Private data of the non-shareable profiles is muted.
+    pub authors: Vec<Author>,
+    /// Tags of the public recipes created or modified recently, sorted by the number of recipes.
+    pub trending_tags: Vec<TagFacet>,
+}
+
+/// Recent activity of the site (Public).
+///
+/// # Description
+///
+/// The response combines the latest public recipes, the latest registered authors, and the trending tags, i.e. the
+/// tags most used by the public recipes created or modified within the last 30 days. The activity is refreshed once
+/// per minute at most.
+#[utoipa::path(
+    get,
+    path = "/activity/recent",
+    tag = "Landing",
+    responses(
+        (
+            status = 200,
+            description = "The recent activity of the site.",
+            body = RecentActivity,
+            headers(
+                ("Cache-Control", description = "The activity can be cached by shared caches for a minute."),
+            )
+        ),
+    )
+)]
+#[instrument(skip(pool, cache))]
+#[get("/activity/recent")]
+pub async fn get_recent_activity(
+    pool: Data<MySqlPool>,
+    cache: Data<ActivityCache>,
+) -> Result<HttpResponse, ApiError> {
+    let body = match cache.get() {
+        Some(body) => body,
+        None => {
+            info!("Building the recent activity");
+            let activity = recent_activity_from_db(&pool).await?;
+            cache.store(serde_json::to_string(&activity)?)
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(ACTIVITY_CACHE_TTL.as_secs() as u32),
+        ]))
+        .body(body.as_str().to_owned()))
+}
+
+async fn recent_activity_from_db(pool: &MySqlPool) -> Result<RecentActivity, ApiError> {
+    let mut recipes = Vec::new();
+    for id in latest_recipes_from_db(pool).await? {
+        if let Some(recipe) = get_recipe_from_db(pool, &id).await? {
+            recipes.push(recipe);
+        }
+    }
+
+    let mut authors = Vec::new();
+    for id in latest_authors_from_db(pool).await? {
+        let Some(mut author) = get_author_from_db(pool, &id).await? else {
+            continue;
+        };
+        if !author.shareable() {
+            author.mute_private_data();
+        }
+        authors.push(author);
+    }
+
+    Ok(RecentActivity {
+        recipes,
+        authors,
+        trending_tags: trending_tags_from_db(pool).await?,
+    })
+}
+
+/// Retrieve the IDs of the latest public recipes. Recipes pending moderation are never listed.
+async fn latest_recipes_from_db(pool: &MySqlPool) -> Result<Vec<Uuid>, ServerError> {
+    let ids: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM Cocktail \
+        WHERE state = 'published' AND id NOT IN (SELECT cocktail_id FROM ModerationQueue) \
+        ORDER BY creation_date DESC, id LIMIT ?",
+    )
+    .bind(LANDING_COLLECTION_SIZE as u32)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    parse_ids(&ids)
+}
+
+/// Retrieve the IDs of the latest registered authors. Authors merged into another profile are never listed.
+async fn latest_authors_from_db(pool: &MySqlPool) -> Result<Vec<Uuid>, ServerError> {
+    let ids: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM Author WHERE deleted_at IS NULL ORDER BY creation_date DESC, id LIMIT ?",
+    )
+    .bind(RECENT_AUTHORS)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    parse_ids(&ids)
+}
+
+/// Retrieve the tags most used by the public recipes created or modified within the trending window.
+async fn trending_tags_from_db(pool: &MySqlPool) -> Result<Vec<TagFacet>, ServerError> {
+    let rows = sqlx::query(
+        "SELECT t.tag, COUNT(DISTINCT c.id) AS count FROM Tagged t \
+        JOIN Cocktail c ON c.id = t.cocktail_id \
+        WHERE c.state = 'published' AND c.id NOT IN (SELECT cocktail_id FROM ModerationQueue) \
+        AND COALESCE(c.update_date, c.creation_date) >= NOW() - INTERVAL ? DAY \
+        GROUP BY t.tag ORDER BY count DESC, t.tag LIMIT ?",
+    )
+    .bind(TRENDING_WINDOW_DAYS)
+    .bind(TRENDING_TAGS)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    rows.iter()
+        .map(|row| {
+            let count: i64 = row.try_get("count")?;
+            Ok(TagFacet {
+                tag: row.try_get("tag")?,
+                count: count as u64,
+            })
+        })
+        .collect::<Result<Vec<TagFacet>, sqlx::Error>>()
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })
+}
+
+fn parse_ids(ids: &[String]) -> Result<Vec<Uuid>, ServerError> {
+    ids.iter()
+        .map(|id| {
+            Uuid::parse_str(id).map_err(|_| {
+                error!("Failed to parse ID from a value of the DB");
+                ServerError::DbError
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,165 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Administration resource for the anonymous usage analytics.
+//!
+//! # Description
+//!
+//! The analytics count the requests per endpoint and the searches per term (see
+//! [crate::utils::metrics::UsageAnalytics]), so the administrators know which features are worth the investment. They
+//! are disabled by default, see [crate::configuration::AnalyticsSettings].
+
+use crate::{
+    authentication::{access_denied_response, check_admin_access, AccessControl, AuthData, Scope},
+    domain::ApiError,
+    routes::admin::utils::{
+        get_daily_usage_from_db, get_endpoint_usage_from_db, get_top_searches_from_db,
+    },
+    utils::metrics::analytics,
+};
+use actix_web::{
+    get,
+    web::{Data, Query},
+    HttpResponse,
+};
+use chrono::{Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+use utoipa::{IntoParams, ToSchema};
+
+/// Days included in the report when the client doesn't give an amount.
+pub const DEFAULT_ANALYTICS_DAYS: u32 = 30;
+
+/// Maximum amount of days included in the report.
+pub const MAX_ANALYTICS_DAYS: u32 = 365;
+
+/// Search terms listed when the client doesn't give an amount.
+pub const DEFAULT_TOP_SEARCHES: u32 = 20;
+
+/// Maximum amount of search terms listed.
+pub const MAX_TOP_SEARCHES: u32 = 100;
+
+/// Period of the report.
+#[derive(Debug, Default, Deserialize, Serialize, IntoParams)]
+pub struct AnalyticsQuery {
+    /// Amount of days included in the report, counted back from today (UTC). 30 by default, and 365 at most.
+    #[param(example = 30)]
+    pub days: Option<u32>,
+    /// Amount of search terms listed. 20 by default, and 100 at most.
+    #[param(example = 20)]
+    pub top: Option<u32>,
+}
+
+/// Requests of a day.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct DailyUsage {
+    #[schema(value_type = String, example = "2025-09-11")]
+    pub day: NaiveDate,
+    pub hits: u64,
+}
+
+/// Requests to an endpoint within the period of the report.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct EndpointUsage {
+    #[schema(example = "GET /recipe/{id}")]
+    pub endpoint: String,
+    pub hits: u64,
+}
+
+/// Searches of a term within the period of the report.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct SearchUsage {
+    /// Resource searched: `recipe` or `ingredient`.
+    #[schema(example = "recipe")]
+    pub resource: String,
+    /// Normalized search term.
+    #[schema(example = "margarita")]
+    pub term: String,
+    pub hits: u64,
+}
+
+/// Report of the usage of the API.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct UsageReport {
+    /// Whether the analytics are being recorded. The totals recorded while they were enabled are reported anyway.
+    pub enabled: bool,
+    #[schema(value_type = String, example = "2025-08-13")]
+    pub from: NaiveDate,
+    #[schema(value_type = String, example = "2025-09-11")]
+    pub to: NaiveDate,
+    /// Minimum amount of distinct clients that searched the terms listed within a day.
+    #[schema(example = 5)]
+    pub k_anonymity: u32,
+    /// Requests per day, oldest first.
+    pub daily: Vec<DailyUsage>,
+    /// Requests per endpoint, most used first.
+    pub endpoints: Vec<EndpointUsage>,
+    /// Most searched terms, most used first.
+    pub top_searches: Vec<SearchUsage>,
+}
+
+/// Report of the anonymous usage analytics.
+///
+/// # Description
+///
+/// The report includes the requests per day and per endpoint, and the most searched terms, within the given amount of
+/// days. Usage is stored every 10 minutes by default, so the latest requests may not be included yet. Only the terms
+/// searched by at least `k_anonymity` distinct clients within a day are listed.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    get,
+    path = "/admin/analytics",
+    tag = "Admin",
+    params(AnalyticsQuery),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The report of the usage of the API.", body = UsageReport),
+        (status = 400, description = "The amount of days or search terms is not valid."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("/analytics")]
+pub async fn get_analytics(
+    req: Query<AnalyticsQuery>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let days = req.days.unwrap_or(DEFAULT_ANALYTICS_DAYS);
+    let top = req.top.unwrap_or(DEFAULT_TOP_SEARCHES);
+    if !(1..=MAX_ANALYTICS_DAYS).contains(&days) || !(1..=MAX_TOP_SEARCHES).contains(&top) {
+        info!("Invalid period of the usage report: {days} days, {top} searches");
+        return Ok(HttpResponse::BadRequest().body(format!(
+            "The report spans 1 to {MAX_ANALYTICS_DAYS} days, and lists 1 to {MAX_TOP_SEARCHES} searches"
+        )));
+    }
+
+    let to = Utc::now().date_naive();
+    let from = to - Days::new((days - 1).into());
+    let k_anonymity = analytics().k_anonymity();
+
+    Ok(HttpResponse::Ok().json(UsageReport {
+        enabled: analytics().is_enabled(),
+        from,
+        to,
+        k_anonymity,
+        daily: get_daily_usage_from_db(&pool, from).await?,
+        endpoints: get_endpoint_usage_from_db(&pool, from).await?,
+        top_searches: get_top_searches_from_db(&pool, from, k_anonymity, top).await?,
+    }))
+}
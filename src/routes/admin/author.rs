@@ -0,0 +1,102 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Administration resources for the management of authors.
+
+use crate::{
+    authentication::{access_denied_response, check_admin_access, AccessControl, AuthData, Scope},
+    domain::{ApiError, ResourceId},
+    routes::admin::utils::{merge_authors_in_db, AuthorMergeOutcome},
+};
+use actix_web::{
+    post,
+    web::{Data, Json, Query},
+    HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+
+/// Request body of the author merge resource.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct AuthorMerge {
+    /// ID of the duplicated author profile. This profile is soft-deleted after the merge.
+    #[schema(value_type = String, example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub source: ResourceId,
+    /// ID of the author profile that receives the data of the duplicated profile.
+    #[schema(value_type = String, example = "0192e8d9-36cf-7ce3-82ef-0a7c9b2deefe")]
+    pub target: ResourceId,
+}
+
+/// Summary of the data moved by a merge of two author profiles.
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AuthorMergeSummary {
+    /// Amount of recipes whose ownership was transferred to the target author.
+    pub recipes_moved: u64,
+    /// Amount of social profiles moved to the target author. Profiles of a social network that the target author
+    /// already had are discarded.
+    pub social_profiles_moved: u64,
+}
+
+/// Merge a duplicated author profile into another profile.
+///
+/// # Description
+///
+/// Duplicated author profiles happen when people are registered twice. This resource moves the data owned by the
+/// **source** author to the **target** author:
+/// - The ownership of the recipes.
+/// - The social profiles, unless the target author has a profile for the same social network.
+///
+/// The ID of the source author is recorded as an alias of the target author, and the source profile is soft-deleted,
+/// i.e. it won't be accessible through the `/author` resources anymore.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    post,
+    path = "/admin/author/merge",
+    tag = "Admin",
+    security(
+        ("api_key" = [])
+    ),
+    request_body(
+        content = AuthorMerge, description = "The IDs of the duplicated and the target author profiles.",
+    ),
+    responses(
+        (status = 200, description = "The author profiles were merged.", body = AuthorMergeSummary),
+        (status = 400, description = "Some of the given IDs has an invalid format, or both IDs are the same."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+        (status = 404, description = "Some of the given IDs didn't match an existing author profile."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[post("/author/merge")]
+pub async fn merge_authors(
+    req: Json<AuthorMerge>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    if req.source == req.target {
+        info!("Attempt to merge an author profile into itself");
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    match merge_authors_in_db(&pool, req.source.as_uuid(), req.target.as_uuid()).await? {
+        AuthorMergeOutcome::Merged(summary) => {
+            info!("Author {} merged into {}", req.source, req.target);
+            Ok(HttpResponse::Ok().json(summary))
+        }
+        AuthorMergeOutcome::AuthorNotFound => Ok(HttpResponse::NotFound().finish()),
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Administration resources for the backups of the DB.
+//!
+//! # Description
+//!
+//! Administrators take snapshots of the DB on demand, which are stored by the server (see
+//! [crate::utils::backup::BackupStore]). Snapshots are SQL scripts, restored using the regular client of the DB.
+
+use crate::{
+    authentication::{access_denied_response, check_admin_access, AccessControl, AuthData, Scope},
+    domain::ApiError,
+    utils::backup::{BackupError, BackupStore},
+};
+use actix_web::{
+    get, post,
+    web::{Data, Query},
+    HttpResponse,
+};
+use anyhow::Context;
+use sqlx::MySqlPool;
+use tracing::{debug, error, info, instrument};
+
+/// Take a snapshot of the DB.
+///
+/// # Description
+///
+/// The snapshot includes the definition and the content of all the tables of the DB. The request completes once the
+/// snapshot is stored, and only one snapshot is taken at a time.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    post,
+    path = "/admin/backup",
+    tag = "Admin",
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 201, description = "The snapshot was stored.", body = BackupSnapshot),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+        (status = 409, description = "Another snapshot is being taken."),
+    )
+)]
+#[instrument(skip(pool, backups, token, access))]
+#[post("/backup")]
+pub async fn post_backup(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    backups: Data<BackupStore>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    match backups.backup(&pool).await {
+        Ok(snapshot) => Ok(HttpResponse::Created().json(snapshot)),
+        Err(BackupError::AlreadyRunning) => {
+            info!("A backup was requested while another backup is running");
+            Ok(HttpResponse::Conflict().finish())
+        }
+        Err(e) => {
+            error!("{e}");
+            Err(ApiError::Internal(e.into()))
+        }
+    }
+}
+
+/// List the snapshots of the DB.
+///
+/// # Description
+///
+/// Snapshots are sorted newest first.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    get,
+    path = "/admin/backups",
+    tag = "Admin",
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The stored snapshots.", body = [BackupSnapshot]),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, backups, token, access))]
+#[get("/backups")]
+pub async fn get_backups(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    backups: Data<BackupStore>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    Ok(HttpResponse::Ok().json(
+        backups
+            .snapshots()
+            .await
+            .context("Failed to list the snapshots")?,
+    ))
+}
@@ -0,0 +1,163 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Administration resources for the claims of the recipes.
+//!
+//! # Description
+//!
+//! Claims whose source URL can't be verified wait for the review of an administrator (see
+//! [crate::routes::recipe::claim]).
+
+use crate::{
+    authentication::{access_denied_response, check_admin_access, AccessControl, AuthData, Scope},
+    domain::{ApiError, ClientId, ResourceId},
+    routes::{
+        admin::moderation::{ModerationAction, ModerationDecision},
+        recipe::{
+            claim::{notify_granted_claim, ClaimState},
+            utils::{
+                get_author_email_from_db, get_claim_from_db, get_pending_claims_from_db,
+                get_recipe_from_db, grant_claim_in_db, reject_claim_in_db,
+            },
+        },
+    },
+    utils::mailing::{EmailSender, MailCorrelation},
+};
+use actix_web::{
+    get, post,
+    web::{Data, Json, Query},
+    HttpResponse,
+};
+use anyhow::Context;
+use sqlx::MySqlPool;
+use std::str::FromStr;
+use tracing::{debug, info, instrument};
+use tracing_actix_web::RequestId;
+use uuid::Uuid;
+
+/// List the pending claims of the recipes.
+///
+/// # Description
+///
+/// Claims are sorted by the time they were issued, oldest first. This resource is restricted to clients of the API
+/// with administration privileges.
+#[utoipa::path(
+    get,
+    path = "/admin/claims",
+    tag = "Admin",
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The pending claims.", body = [RecipeClaim]),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("/claims")]
+pub async fn get_claims(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    Ok(HttpResponse::Ok().json(get_pending_claims_from_db(&pool).await?))
+}
+
+/// Review a pending claim of a recipe.
+///
+/// # Description
+///
+/// Approved claims make their author the owner of the recipe, and the author gets notified via email. The rest of the
+/// pending claims of the recipe are rejected.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    post,
+    path = "/admin/claims/{id}",
+    tag = "Admin",
+    params(("id" = String, Path, description = "ID of the claim.")),
+    security(
+        ("api_key" = [])
+    ),
+    request_body(
+        content = ModerationDecision, description = "The decision over the claim.",
+        example = json!({"action": "approve"})
+    ),
+    responses(
+        (status = 204, description = "The decision was applied."),
+        (status = 400, description = "The given ID has an invalid format, or the decision is not valid."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+        (status = 404, description = "The given ID doesn't match a pending claim."),
+        (status = 409, description = "The recipe has an owner already."),
+    )
+)]
+#[instrument(skip(pool, mail_client, token, request_id, access))]
+#[post("/claims/{id}")]
+pub async fn review_claim(
+    claim_id: ResourceId,
+    req: Json<ModerationDecision>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    mail_client: Option<Data<dyn EmailSender>>,
+    token: Query<AuthData>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let claim = match get_claim_from_db(&pool, claim_id.as_uuid()).await? {
+        Some(claim) if claim.state == ClaimState::Pending => claim,
+        _ => return Ok(HttpResponse::NotFound().finish()),
+    };
+
+    match req.action {
+        ModerationAction::Approve => {
+            if !grant_claim_in_db(&pool, &claim).await? {
+                info!("The recipe {} has an owner already", claim.recipe_id);
+                return Ok(HttpResponse::Conflict().finish());
+            }
+            info!(
+                "Claim {claim_id} of the recipe {} approved",
+                claim.recipe_id
+            );
+
+            let recipe_id =
+                Uuid::parse_str(&claim.recipe_id).context("Invalid recipe ID in the claim")?;
+            let author_id =
+                Uuid::parse_str(&claim.author_id).context("Invalid author ID in the claim")?;
+            if let (Some(recipe), Some(email)) = (
+                get_recipe_from_db(&pool, &recipe_id).await?,
+                get_author_email_from_db(&pool, &author_id).await?,
+            ) {
+                // Emails are correlated with the client that issued the claim.
+                let correlation =
+                    MailCorrelation::new(*request_id, &ClientId::from_str(&claim.client_id)?);
+                notify_granted_claim(&pool, mail_client, &email, recipe.name(), &correlation).await;
+            }
+        }
+        ModerationAction::Reject => {
+            if reject_claim_in_db(&pool, claim_id.as_uuid()).await? {
+                info!(
+                    "Claim {claim_id} of the recipe {} rejected",
+                    claim.recipe_id
+                );
+            }
+        }
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,99 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Administration resources for the clients of the API.
+//!
+//! # Description
+//!
+//! Failed access checks of the clients are audited (see [crate::authentication::register_auth_failure]). These
+//! resources allow the administrators to find the clients that are being targeted, or that got locked.
+
+use crate::{
+    authentication::{access_denied_response, check_admin_access, AccessControl, AuthData, Scope},
+    domain::ApiError,
+    routes::admin::utils::get_clients_from_db,
+};
+use actix_web::{
+    get,
+    web::{Data, Query},
+    HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, instrument};
+use utoipa::{IntoParams, ToSchema};
+
+/// Maximum amount of entries returned by a single query.
+pub const MAX_CLIENT_RECORDS: u32 = 500;
+
+/// Client of the API, along with its failed access checks.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ClientRecord {
+    pub id: String,
+    pub name: Option<String>,
+    pub email: String,
+    pub validated: bool,
+    pub enabled: bool,
+    pub admin: bool,
+    /// Consecutive failed access checks due to a wrong token.
+    pub failed_attempts: u32,
+    /// Failed access checks registered for the client, whatever the reason.
+    pub total_failures: i64,
+    /// Timestamp of the latest failed access check.
+    #[schema(value_type = Option<String>, example = "2025-09-11T08:58:56Z")]
+    pub last_failure_at: Option<DateTime<Utc>>,
+    /// The client is locked until this timestamp.
+    #[schema(value_type = Option<String>, example = "2025-09-11T09:13:56Z")]
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+/// Filters for the clients of the API. All of them are optional.
+#[derive(Debug, Default, Deserialize, Serialize, IntoParams)]
+pub struct ClientQuery {
+    /// Include only the clients that are currently locked (`true`), or not locked (`false`).
+    pub locked: Option<bool>,
+}
+
+/// List the clients of the API.
+///
+/// # Description
+///
+/// Entries are sorted by the amount of failed access checks, highest first. At most [MAX_CLIENT_RECORDS] entries are
+/// returned.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    get,
+    path = "/admin/clients",
+    tag = "Admin",
+    params(ClientQuery),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The matching clients.", body = [ClientRecord]),
+        (status = 400, description = "Some of the filters has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("/clients")]
+pub async fn get_clients(
+    req: Query<ClientQuery>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    Ok(HttpResponse::Ok().json(get_clients_from_db(&pool, &req, MAX_CLIENT_RECORDS).await?))
+}
@@ -0,0 +1,109 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Administration resources for the outbound emails.
+//!
+//! # Description
+//!
+//! Every attempt to send an email is registered in the DB (see [crate::utils::mailing]). These resources allow the
+//! administrators to find the emails that failed, i.e. confirmations of token requests that never reached the client.
+
+use crate::{
+    authentication::{access_denied_response, check_admin_access, AccessControl, AuthData, Scope},
+    domain::ApiError,
+    routes::admin::utils::get_emails_from_db,
+    utils::mailing::{EmailKind, EmailStatus},
+};
+use actix_web::{
+    get,
+    web::{Data, Query},
+    HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, instrument};
+use utoipa::{IntoParams, ToSchema};
+
+/// Maximum amount of entries returned by a single query.
+pub const MAX_EMAIL_RECORDS: u32 = 500;
+
+/// Attempt to send an email.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct EmailRecord {
+    pub id: u64,
+    pub kind: EmailKind,
+    pub status: EmailStatus,
+    /// ID of the request that triggered the email.
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub request_id: String,
+    /// ID of the client of the API that the email concerns.
+    pub client_id: String,
+    /// SHA-256 hash of the address of the recipient.
+    pub recipient_hash: Option<String>,
+    /// ID given to the message by the email provider.
+    pub provider_message_id: Option<String>,
+    /// Description of the error when the email failed.
+    pub error: Option<String>,
+    /// Timestamp of the attempt.
+    #[schema(value_type = String, example = "2025-09-11T08:58:56Z")]
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Filters for the outbound emails. All of them are optional.
+#[derive(Debug, Default, Deserialize, Serialize, IntoParams)]
+pub struct EmailQuery {
+    pub status: Option<EmailStatus>,
+    pub kind: Option<EmailKind>,
+    /// Email address of the recipient.
+    pub recipient: Option<String>,
+    /// Include emails sent since this timestamp (RFC 3339).
+    #[param(value_type = Option<String>, example = "2025-09-11T00:00:00Z")]
+    pub since: Option<DateTime<Utc>>,
+    /// Include emails sent before this timestamp (RFC 3339).
+    #[param(value_type = Option<String>, example = "2025-09-12T00:00:00Z")]
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// List the attempts to send emails.
+///
+/// # Description
+///
+/// Entries are sorted by the time of the attempt, newest first. At most [MAX_EMAIL_RECORDS] entries are returned.
+/// Recipients are filtered by the hash of their address, so the given address is not stored anywhere.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    get,
+    path = "/admin/emails",
+    tag = "Admin",
+    params(EmailQuery),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The matching emails.", body = [EmailRecord]),
+        (status = 400, description = "Some of the filters has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("/emails")]
+pub async fn get_emails(
+    req: Query<EmailQuery>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    Ok(HttpResponse::Ok().json(get_emails_from_db(&pool, &req, MAX_EMAIL_RECORDS).await?))
+}
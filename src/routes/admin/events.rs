@@ -0,0 +1,85 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Audit trail of the recipes.
+//!
+//! # Description
+//!
+//! Every change of the recipes is recorded as a domain event within the transaction that applies it (see
+//! [crate::utils::events]). This resource allows administrators to read the recorded events.
+
+use crate::{
+    authentication::{access_denied_response, check_admin_access, AccessControl, AuthData, Scope},
+    domain::ApiError,
+    utils::events::get_events_from_db,
+};
+use actix_web::{
+    get,
+    web::{Data, Query},
+    HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, instrument};
+use utoipa::IntoParams;
+
+/// Maximum amount of events returned by a single query.
+pub const MAX_EVENT_RECORDS: u32 = 500;
+
+/// Range of the events to retrieve.
+#[derive(Debug, Default, Deserialize, Serialize, IntoParams)]
+pub struct EventQuery {
+    /// Retrieve the events recorded after the event with this sequence number.
+    pub after: Option<u64>,
+    /// Maximum amount of events to retrieve. It is capped to 500.
+    pub limit: Option<u32>,
+}
+
+/// List the domain events of the recipes.
+///
+/// # Description
+///
+/// Events are sorted by their sequence number, oldest first. Clients can follow the trail by passing the sequence
+/// number of the last retrieved event as `after`.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    get,
+    path = "/admin/events",
+    tag = "Admin",
+    params(EventQuery),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The recorded events.", body = [crate::utils::events::StoredEvent]),
+        (status = 400, description = "Some of the parameters has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("/events")]
+pub async fn get_events(
+    req: Query<EventQuery>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let limit = req
+        .limit
+        .unwrap_or(MAX_EVENT_RECORDS)
+        .min(MAX_EVENT_RECORDS);
+
+    Ok(HttpResponse::Ok()
+        .json(get_events_from_db(&pool, req.after.unwrap_or_default(), limit).await?))
+}
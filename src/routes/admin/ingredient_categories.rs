@@ -0,0 +1,426 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Administration resources for the categories of the ingredients.
+//!
+//! # Description
+//!
+//! Ingredient categories are stored in the DB (see [IngCategory]), so administrators can add new categories, modify
+//! their description, or delete the categories that are not used by any ingredient. Categories can't be renamed, as
+//! clients of the API may keep references to them. Instead, ingredients can be moved to another category using
+//! [reclassify_ingredients].
+
+use crate::{
+    authentication::{access_denied_response, check_admin_access, AccessControl, AuthData, Scope},
+    domain::{
+        sanitize::{sanitize_text, SanitizeLevel},
+        ApiError, IngCategory, Ingredient, ResourceId,
+    },
+    routes::ingredient::utils::{
+        delete_ingredient_category_from_db, get_ingredient_categories_from_db,
+        ingredient_category_exists, insert_ingredient_category, reclassify_ingredients_in_db,
+        update_ingredient_category, CategoryDeletion,
+    },
+};
+use actix_web::{
+    delete, patch, post,
+    web::{Data, Json, Path, Query},
+    HttpResponse,
+};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Maximum length of the description of a category. This value is set in the DB's schema definition (VARCHAR(255)).
+const MAX_CATEGORY_DESC_LENGTH: usize = 255;
+
+/// Payload to add a new ingredient category.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct CategoryRequest {
+    #[schema(example = "syrup")]
+    pub name: String,
+    #[schema(example = "Sweeteners, such as simple or agave syrup")]
+    pub description: Option<String>,
+}
+
+/// Payload to modify an ingredient category.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct CategoryPatch {
+    /// New description of the category. A missing value removes the description.
+    #[schema(example = "Sweeteners, such as simple or agave syrup")]
+    pub description: Option<String>,
+}
+
+/// Payload to move ingredients between categories.
+///
+/// # Description
+///
+/// Ingredients of the category `from` are selected by `name`, `ids`, or both. All the ingredients of the category are
+/// selected when no filter is given.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ReclassifyRequest {
+    /// Current category of the ingredients.
+    #[schema(example = "other")]
+    pub from: String,
+    /// New category of the ingredients.
+    #[schema(example = "syrup")]
+    pub to: String,
+    /// Only the ingredients whose name includes this text are selected.
+    #[schema(example = "syrup")]
+    pub name: Option<String>,
+    /// Only the ingredients with these IDs are selected.
+    #[schema(example = json!(["0191e13b-5ab7-78f1-bc06-be503a6c111b"]))]
+    pub ids: Option<Vec<String>>,
+    /// Report the ingredients that would be moved, without moving them.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Report of a reclassification of ingredients.
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+pub struct ReclassifyReport {
+    pub dry_run: bool,
+    /// Amount of ingredients moved, or that would be moved by a dry run.
+    pub matched: usize,
+    /// IDs of the selected ingredients.
+    pub ingredient_ids: Vec<String>,
+}
+
+/// Add a new ingredient category.
+///
+/// # Description
+///
+/// Names follow the format of the categories: from 2 to 20 lowercase letters, numbers or `_`.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    post,
+    path = "/admin/ingredient/categories",
+    tag = "Admin",
+    request_body(
+        content = CategoryRequest,
+        example = json!({"name": "syrup", "description": "Sweeteners, such as simple or agave syrup"})
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 201, description = "The category was added.", body = IngredientCategory),
+        (status = 400, description = "The name or the description of the category are not valid."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+        (status = 409, description = "The category already exists."),
+    )
+)]
+#[instrument(skip(pool, token, access, sanitize_level))]
+#[post("/ingredient/categories")]
+pub async fn post_ingredient_category(
+    req: Json<CategoryRequest>,
+    pool: Data<MySqlPool>,
+    sanitize_level: Data<SanitizeLevel>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let Ok(category) = IngCategory::try_from(req.name.as_str()) else {
+        info!("Invalid name given for an ingredient category");
+        return Ok(HttpResponse::BadRequest().finish());
+    };
+    let Some(description) = check_description(req.description.as_deref(), **sanitize_level) else {
+        return Ok(HttpResponse::BadRequest().finish());
+    };
+
+    if !insert_ingredient_category(&pool, &category, description.as_deref()).await? {
+        info!("The ingredient category {category} already exists");
+        return Ok(HttpResponse::Conflict().finish());
+    }
+    info!("New ingredient category: {category}");
+
+    category_response(&pool, &category, HttpResponse::Created()).await
+}
+
+/// Modify the description of an ingredient category.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    patch,
+    path = "/admin/ingredient/categories/{name}",
+    tag = "Admin",
+    params(("name" = String, Path, description = "Name of the category.")),
+    request_body(
+        content = CategoryPatch,
+        example = json!({"description": "Sweeteners, such as simple or agave syrup"})
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The modified category.", body = IngredientCategory),
+        (status = 400, description = "The name or the description of the category are not valid."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+        (status = 404, description = "The category doesn't exist."),
+    )
+)]
+#[instrument(skip(pool, token, access, sanitize_level))]
+#[patch("/ingredient/categories/{name}")]
+pub async fn patch_ingredient_category(
+    name: Path<String>,
+    req: Json<CategoryPatch>,
+    pool: Data<MySqlPool>,
+    sanitize_level: Data<SanitizeLevel>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let Ok(category) = IngCategory::try_from(name.as_str()) else {
+        return Ok(HttpResponse::BadRequest().finish());
+    };
+    let Some(description) = check_description(req.description.as_deref(), **sanitize_level) else {
+        return Ok(HttpResponse::BadRequest().finish());
+    };
+
+    if !update_ingredient_category(&pool, &category, description.as_deref()).await? {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    info!("Ingredient category {category} modified");
+
+    category_response(&pool, &category, HttpResponse::Ok()).await
+}
+
+/// Delete an ingredient category.
+///
+/// # Description
+///
+/// Categories used by some ingredient can't be deleted, and a code **409** is returned instead.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    delete,
+    path = "/admin/ingredient/categories/{name}",
+    tag = "Admin",
+    params(("name" = String, Path, description = "Name of the category.")),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 204, description = "The category was deleted."),
+        (status = 400, description = "The given name has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+        (status = 404, description = "The category doesn't exist."),
+        (status = 409, description = "Some ingredient belongs to the category."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[delete("/ingredient/categories/{name}")]
+pub async fn delete_ingredient_category(
+    name: Path<String>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let Ok(category) = IngCategory::try_from(name.as_str()) else {
+        return Ok(HttpResponse::BadRequest().finish());
+    };
+
+    match delete_ingredient_category_from_db(&pool, &category).await? {
+        CategoryDeletion::Deleted => {
+            info!("Ingredient category {category} deleted");
+            Ok(HttpResponse::NoContent().finish())
+        }
+        CategoryDeletion::NotFound => Ok(HttpResponse::NotFound().finish()),
+        CategoryDeletion::InUse => {
+            info!("The ingredient category {category} is in use");
+            Ok(HttpResponse::Conflict().finish())
+        }
+    }
+}
+
+/// Move the ingredients of a category to another category.
+///
+/// # Description
+///
+/// Ingredients of the category `from` are selected by a pattern of their name, their IDs, or both, i.e. to split
+/// `other` into `syrup` and `juice`. All the ingredients of the category are moved when no filter is given. Use
+/// `dry_run` to get the ingredients that would be moved first.
+///
+/// Both categories must exist. Every moved ingredient is recorded in the audit trail (`GET /admin/events`).
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    post,
+    path = "/admin/ingredient/reclassify",
+    tag = "Admin",
+    request_body(
+        content = ReclassifyRequest,
+        example = json!({"from": "other", "to": "syrup", "name": "syrup", "dry_run": true})
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The ingredients were moved, or the move was simulated.", body = ReclassifyReport),
+        (status = 400, description = "Some of the categories or the filters is not valid, or both categories are the same."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+        (status = 404, description = "Some of the categories doesn't exist."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[post("/ingredient/reclassify")]
+pub async fn reclassify_ingredients(
+    req: Json<ReclassifyRequest>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let (Ok(from), Ok(to)) = (
+        IngCategory::try_from(req.from.as_str()),
+        IngCategory::try_from(req.to.as_str()),
+    ) else {
+        return Ok(HttpResponse::BadRequest().body("Invalid category"));
+    };
+    if from == to {
+        return Ok(HttpResponse::BadRequest().body("The categories are the same"));
+    }
+    // Names are checked as done by the searches of ingredients.
+    let name = match req.name.as_deref() {
+        Some(name) => match Ingredient::parse(None, name, from.to_str(), None) {
+            Ok(ingredient) => Some(ingredient.name().to_owned()),
+            Err(e) => return Ok(HttpResponse::BadRequest().body(e.to_string())),
+        },
+        None => None,
+    };
+    let ids = match &req.ids {
+        Some(ids) if ids.is_empty() => {
+            return Ok(HttpResponse::BadRequest().body("Empty list of IDs"))
+        }
+        Some(ids) => match ids
+            .iter()
+            .map(|id| ResourceId::try_from(id.as_str()).map(Uuid::from))
+            .collect::<Result<Vec<Uuid>, _>>()
+        {
+            Ok(ids) => Some(ids),
+            Err(_) => return Ok(HttpResponse::BadRequest().body("Invalid ingredient ID")),
+        },
+        None => None,
+    };
+
+    for category in [&from, &to] {
+        if !ingredient_category_exists(&pool, category).await? {
+            info!("The ingredient category {category} doesn't exist");
+            return Ok(HttpResponse::NotFound().finish());
+        }
+    }
+
+    let moved = reclassify_ingredients_in_db(
+        &pool,
+        &from,
+        &to,
+        name.as_deref(),
+        ids.as_deref(),
+        req.dry_run,
+    )
+    .await?;
+    if !req.dry_run {
+        info!(
+            "{} ingredients moved from the category {from} to {to}",
+            moved.len()
+        );
+    }
+
+    Ok(HttpResponse::Ok().json(ReclassifyReport {
+        dry_run: req.dry_run,
+        matched: moved.len(),
+        ingredient_ids: moved.iter().map(Uuid::to_string).collect(),
+    }))
+}
+
+/// Sanitize a description. `None` is returned when the description is too long.
+fn check_description(description: Option<&str>, level: SanitizeLevel) -> Option<Option<String>> {
+    let description = description
+        .map(|d| sanitize_text(d.trim(), level))
+        .filter(|d| !d.is_empty());
+
+    match description {
+        Some(d) if d.chars().count() > MAX_CATEGORY_DESC_LENGTH => {
+            info!("The description of the category is too long");
+            None
+        }
+        description => Some(description),
+    }
+}
+
+async fn category_response(
+    pool: &MySqlPool,
+    category: &IngCategory,
+    mut response: actix_web::HttpResponseBuilder,
+) -> Result<HttpResponse, ApiError> {
+    // A single category is taken, so the categories are not sorted by any collation.
+    let category = get_ingredient_categories_from_db(pool, None)
+        .await?
+        .into_iter()
+        .find(|c| c.name == category.to_str())
+        .context("The ingredient category was not found in the DB")?;
+
+    Ok(response.json(category))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case(None, Some(None))]
+    #[case(Some("  "), Some(None))]
+    #[case(Some(" Sweeteners "), Some(Some("Sweeteners".to_owned())))]
+    fn descriptions_are_checked(
+        #[case] description: Option<&str>,
+        #[case] expected: Option<Option<String>>,
+    ) {
+        assert_eq!(
+            check_description(description, SanitizeLevel::default()),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn long_descriptions_are_rejected() {
+        let description = "a".repeat(MAX_CATEGORY_DESC_LENGTH + 1);
+        assert_eq!(
+            check_description(Some(&description), SanitizeLevel::default()),
+            None
+        );
+    }
+}
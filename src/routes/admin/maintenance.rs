@@ -0,0 +1,247 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Administration resources for the maintenance windows of the server.
+//!
+//! # Description
+//!
+//! Administrators announce the upcoming maintenance windows of the server, so the clients of the API are warned in
+//! advance: `GET /health` reports the status `MaintenanceScheduled` (or `OnMaintenance` during the window), and every
+//! response includes the header *X-Maintenance-Scheduled* until the window passes (see
+//! [crate::utils::http::MaintenanceNotice]).
+//!
+//! Only a single window is announced at a time. Announcing a new window replaces the previous one.
+
+use crate::{
+    authentication::{
+        access_denied_response, check_admin_access, key_client_id, AccessControl, AuthData, Scope,
+    },
+    domain::{
+        sanitize::{sanitize_text, SanitizeLevel},
+        ApiError,
+    },
+    routes::admin::utils::{
+        cancel_maintenance_windows_in_db, get_maintenance_window_from_db,
+        store_maintenance_window_in_db,
+    },
+    utils::http::{MaintenanceSchedule, MaintenanceWindow},
+};
+use actix_web::{
+    delete, post,
+    web::{Data, Json, Query},
+    HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+use tracing::{debug, error, info, instrument};
+
+/// Maximum length of the message of a maintenance window. This value is set in the DB's schema definition
+/// (VARCHAR(400)).
+const MAX_MAINTENANCE_MESSAGE_LENGTH: usize = 400;
+
+/// Announce an upcoming maintenance window.
+///
+/// # Description
+///
+/// The window must start in the future, and it must end after it starts. The announced window replaces any previous
+/// window that didn't pass yet.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    post,
+    path = "/admin/maintenance/schedule",
+    tag = "Admin",
+    request_body(
+        content = MaintenanceWindow,
+        example = json!({
+            "starts_at": "2025-09-11T22:00:00Z",
+            "ends_at": "2025-09-11T23:30:00Z",
+            "message": "Upgrade of the DB server."
+        })
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 201, description = "The maintenance window was announced.", body = MaintenanceWindow),
+        (status = 400, description = "The window already started, it ends before it starts, or the message is too long."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+    )
+)]
+#[instrument(skip(pool, schedule, token, access, sanitize_level))]
+#[post("/maintenance/schedule")]
+pub async fn schedule_maintenance(
+    req: Json<MaintenanceWindow>,
+    pool: Data<MySqlPool>,
+    sanitize_level: Data<SanitizeLevel>,
+    access: Data<AccessControl>,
+    schedule: Data<MaintenanceSchedule>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let Some(window) = check_window(req.into_inner(), Utc::now(), **sanitize_level) else {
+        return Ok(HttpResponse::BadRequest().finish());
+    };
+
+    let client_id = key_client_id(&token.api_key)?;
+    store_maintenance_window_in_db(&pool, &window, &client_id.to_string()).await?;
+    schedule.set(Some(window.clone()));
+    info!(
+        "Maintenance window scheduled from {} to {}",
+        window.starts_at, window.ends_at
+    );
+
+    Ok(HttpResponse::Created().json(window))
+}
+
+/// Cancel the announced maintenance window.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    delete,
+    path = "/admin/maintenance/schedule",
+    tag = "Admin",
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 204, description = "The maintenance window was cancelled."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+        (status = 404, description = "No maintenance window is announced."),
+    )
+)]
+#[instrument(skip(pool, schedule, token, access))]
+#[delete("/maintenance/schedule")]
+pub async fn cancel_maintenance(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    schedule: Data<MaintenanceSchedule>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let cancelled = cancel_maintenance_windows_in_db(&pool, Utc::now()).await?;
+    schedule.set(None);
+
+    if cancelled {
+        info!("Maintenance window cancelled");
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+/// Load the announced maintenance window from the DB.
+///
+/// # Description
+///
+/// This function is meant to be called when the application starts, so the announcements survive the restarts of
+/// the server. Nothing is announced when the DB is not available.
+pub async fn load_maintenance_schedule(pool: &MySqlPool) -> MaintenanceSchedule {
+    match get_maintenance_window_from_db(pool, Utc::now()).await {
+        Ok(window) => MaintenanceSchedule::new(window),
+        Err(e) => {
+            error!("Failed to load the maintenance schedule: {e}");
+            MaintenanceSchedule::default()
+        }
+    }
+}
+
+/// Check the bounds of a window and sanitize its message. `None` is returned when the window is not valid.
+fn check_window(
+    window: MaintenanceWindow,
+    now: DateTime<Utc>,
+    level: SanitizeLevel,
+) -> Option<MaintenanceWindow> {
+    if window.starts_at <= now || window.ends_at <= window.starts_at {
+        info!("Invalid bounds given for a maintenance window");
+        return None;
+    }
+
+    let message = window
+        .message
+        .map(|m| sanitize_text(m.trim(), level))
+        .filter(|m| !m.is_empty());
+    if message
+        .as_ref()
+        .is_some_and(|m| m.chars().count() > MAX_MAINTENANCE_MESSAGE_LENGTH)
+    {
+        info!("The message of the maintenance window is too long");
+        return None;
+    }
+
+    Some(MaintenanceWindow { message, ..window })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case(TimeDelta::hours(1), TimeDelta::hours(2), true)]
+    #[case(TimeDelta::hours(-1), TimeDelta::hours(2), false)]
+    #[case(TimeDelta::hours(2), TimeDelta::hours(1), false)]
+    #[case(TimeDelta::hours(1), TimeDelta::hours(1), false)]
+    fn window_bounds_are_checked(
+        #[case] starts_in: TimeDelta,
+        #[case] ends_in: TimeDelta,
+        #[case] valid: bool,
+    ) {
+        let now = Utc::now();
+        let window = MaintenanceWindow {
+            starts_at: now + starts_in,
+            ends_at: now + ends_in,
+            message: None,
+        };
+        assert_eq!(
+            check_window(window, now, SanitizeLevel::default()).is_some(),
+            valid
+        );
+    }
+
+    #[rstest]
+    fn messages_are_checked() {
+        let now = Utc::now();
+        let window = |message: &str| MaintenanceWindow {
+            starts_at: now + TimeDelta::hours(1),
+            ends_at: now + TimeDelta::hours(2),
+            message: Some(message.to_owned()),
+        };
+
+        assert_eq!(
+            check_window(window("  "), now, SanitizeLevel::default())
+                .unwrap()
+                .message,
+            None
+        );
+        assert_eq!(
+            check_window(window(" Upgrade "), now, SanitizeLevel::default())
+                .unwrap()
+                .message,
+            Some("Upgrade".to_owned())
+        );
+        assert!(check_window(
+            window(&"a".repeat(MAX_MAINTENANCE_MESSAGE_LENGTH + 1)),
+            now,
+            SanitizeLevel::default()
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Administration resource for the status of the migrations of the DB.
+//!
+//! # Description
+//!
+//! The migrations of the DB are embedded in the application when it is built. This resource compares them with the
+//! migrations that were applied to the DB (kept by sqlx in the table `_sqlx_migrations`), so operators can verify
+//! that the DB matches the code after a deployment.
+
+use crate::{
+    authentication::{access_denied_response, check_admin_access, AccessControl, AuthData, Scope},
+    domain::ApiError,
+    routes::admin::utils::get_applied_migrations_from_db,
+};
+use actix_web::{
+    get,
+    web::{Data, Query},
+    HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{
+    migrate::{Migration, Migrator},
+    MySqlPool,
+};
+use std::collections::BTreeMap;
+use tracing::{debug, instrument, warn};
+use utoipa::ToSchema;
+
+/// Migrations embedded in the application.
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Migration as registered in the DB.
+#[derive(Debug, Clone)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub success: bool,
+    pub checksum: Vec<u8>,
+    pub installed_on: DateTime<Utc>,
+}
+
+/// Status of a migration of the application in the DB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationState {
+    /// The migration was applied to the DB.
+    Applied,
+    /// The migration was not applied to the DB yet.
+    Pending,
+    /// The migration was applied, but it failed.
+    Failed,
+    /// The migration applied to the DB differs from the migration of the application.
+    Modified,
+}
+
+/// Migration of the application, along with its status in the DB.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct MigrationStatus {
+    #[schema(example = 20261016100000_i64)]
+    pub version: i64,
+    #[schema(example = "author merge")]
+    pub description: String,
+    pub state: MigrationState,
+    /// When the migration was applied to the DB. Pending migrations have no value.
+    #[schema(value_type = Option<String>, example = "2025-09-11T08:58:56Z")]
+    pub installed_on: Option<DateTime<Utc>>,
+}
+
+/// Comparison between the migrations of the application and the migrations applied to the DB.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct MigrationsReport {
+    /// Whether the DB matches the migrations of the application.
+    pub up_to_date: bool,
+    /// Checksum of the migrations of the application (SHA-256).
+    #[schema(example = "5e0a2c0cd7f2b0d0e9a5a43cb01f6d8bd3b1b9e8c1c5ad2f3d9c43c4e3a5b6d7")]
+    pub schema_checksum: String,
+    /// Checksum of the migrations applied to the DB (SHA-256). It matches `schema_checksum` when the DB is up to date.
+    #[schema(example = "5e0a2c0cd7f2b0d0e9a5a43cb01f6d8bd3b1b9e8c1c5ad2f3d9c43c4e3a5b6d7")]
+    pub db_checksum: String,
+    /// Amount of migrations of the application that were applied successfully to the DB.
+    pub applied: usize,
+    /// Amount of migrations of the application that were not applied successfully to the DB.
+    pub pending: usize,
+    /// Migrations of the application, sorted by version.
+    pub migrations: Vec<MigrationStatus>,
+    /// Versions of the migrations applied to the DB that the application doesn't know about, i.e. applied by a newer
+    /// release of the application.
+    pub unknown: Vec<i64>,
+}
+
+/// Report the status of the migrations of the DB.
+///
+/// # Description
+///
+/// The report includes the state of every migration embedded in the application, and the checksums of the
+/// migrations of the application and the migrations applied to the DB. Both checksums match when the DB is up to
+/// date.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    get,
+    path = "/admin/migrations",
+    tag = "Admin",
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The status of the migrations of the DB.", body = MigrationsReport),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("/migrations")]
+pub async fn get_migrations(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let applied = get_applied_migrations_from_db(&pool).await?;
+    let report = migrations_report(&MIGRATOR.migrations, &applied);
+    if !report.up_to_date {
+        warn!(
+            "The DB doesn't match the migrations of the application: {} pending, {} unknown",
+            report.pending,
+            report.unknown.len()
+        );
+    }
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Compare the migrations of the application with the migrations applied to the DB.
+fn migrations_report(migrations: &[Migration], applied: &[AppliedMigration]) -> MigrationsReport {
+    let migrations = migrations
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .collect::<Vec<&Migration>>();
+    let schema_checksum = checksum(migrations.iter().map(|m| (m.version, m.checksum.as_ref())));
+    let mut applied_by_version = applied
+        .iter()
+        .map(|m| (m.version, m))
+        .collect::<BTreeMap<i64, &AppliedMigration>>();
+    let db_checksum = checksum(
+        applied_by_version
+            .values()
+            .filter(|m| m.success)
+            .map(|m| (m.version, m.checksum.as_slice())),
+    );
+
+    let migrations = migrations
+        .into_iter()
+        .map(|migration| {
+            let applied = applied_by_version.remove(&migration.version);
+            let state = match applied {
+                None => MigrationState::Pending,
+                Some(applied) if !applied.success => MigrationState::Failed,
+                Some(applied) if applied.checksum != *migration.checksum => {
+                    MigrationState::Modified
+                }
+                Some(_) => MigrationState::Applied,
+            };
+
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+                installed_on: applied.map(|m| m.installed_on),
+            }
+        })
+        .collect::<Vec<MigrationStatus>>();
+
+    let applied_count = migrations
+        .iter()
+        .filter(|m| m.state == MigrationState::Applied)
+        .count();
+    let unknown = applied_by_version.into_keys().collect::<Vec<i64>>();
+
+    MigrationsReport {
+        up_to_date: applied_count == migrations.len() && unknown.is_empty(),
+        schema_checksum,
+        db_checksum,
+        applied: applied_count,
+        pending: migrations.len() - applied_count,
+        migrations,
+        unknown,
+    }
+}
+
+/// Checksum of a sequence of migrations, given their versions and the checksums of their scripts.
+fn checksum<'a>(migrations: impl Iterator<Item = (i64, &'a [u8])>) -> String {
+    let mut hasher = Sha256::new();
+    for (version, checksum) in migrations {
+        hasher.update(version.to_le_bytes());
+        hasher.update(checksum);
+    }
+
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use sqlx::migrate::MigrationType;
+
+    fn migration(version: i64, sql: &'static str) -> Migration {
+        Migration::new(
+            version,
+            "test".into(),
+            MigrationType::Simple,
+            sql.into(),
+            false,
+        )
+    }
+
+    fn applied(migration: &Migration, success: bool) -> AppliedMigration {
+        AppliedMigration {
+            version: migration.version,
+            success,
+            checksum: migration.checksum.to_vec(),
+            installed_on: Utc::now(),
+        }
+    }
+
+    #[rstest]
+    fn up_to_date_db() {
+        let migrations = [migration(1, "SELECT 1"), migration(2, "SELECT 2")];
+        let db = migrations
+            .iter()
+            .map(|m| applied(m, true))
+            .collect::<Vec<AppliedMigration>>();
+
+        let report = migrations_report(&migrations, &db);
+        assert!(report.up_to_date);
+        assert_eq!(report.applied, 2);
+        assert_eq!(report.pending, 0);
+        assert_eq!(report.schema_checksum, report.db_checksum);
+    }
+
+    #[rstest]
+    fn outdated_db() {
+        let migrations = [
+            migration(1, "SELECT 1"),
+            migration(2, "SELECT 2"),
+            migration(3, "SELECT 3"),
+            migration(4, "SELECT 4"),
+        ];
+        let db = vec![
+            applied(&migrations[0], true),
+            applied(&migrations[1], false),
+            applied(&migration(3, "SELECT 'modified'"), true),
+            applied(&migration(5, "SELECT 5"), true),
+        ];
+
+        let report = migrations_report(&migrations, &db);
+        assert!(!report.up_to_date);
+        assert_eq!(report.applied, 1);
+        assert_eq!(report.pending, 3);
+        assert_eq!(
+            report
+                .migrations
+                .iter()
+                .map(|m| m.state)
+                .collect::<Vec<MigrationState>>(),
+            vec![
+                MigrationState::Applied,
+                MigrationState::Failed,
+                MigrationState::Modified,
+                MigrationState::Pending
+            ]
+        );
+        assert!(report.migrations[3].installed_on.is_none());
+        assert_eq!(report.unknown, vec![5]);
+        assert_ne!(report.schema_checksum, report.db_checksum);
+    }
+
+    #[rstest]
+    fn embedded_migrations_are_reported() {
+        let report = migrations_report(&MIGRATOR.migrations, &[]);
+        assert!(!report.migrations.is_empty());
+        assert_eq!(report.pending, report.migrations.len());
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Administration resources for the moderation of the content.
+//!
+//! # Description
+//!
+//! Content flagged by the screening of profanity and spam (see [crate::domain::screening]) is kept in a moderation
+//! queue, and it is hidden from the public until an administrator reviews it.
+
+use crate::{
+    authentication::{access_denied_response, check_admin_access, AccessControl, AuthData, Scope},
+    domain::{screening::ScreeningFlag, ApiError, IdGenerator},
+    routes::{
+        admin::utils::{approve_recipe_in_db, get_moderation_queue_from_db},
+        me::notifications::notify_recipe_approved,
+        recipe::{
+            utils::{delete_recipes_from_db, is_recipe_pending_moderation},
+            RecipeId,
+        },
+    },
+};
+use actix_web::{
+    get, post,
+    web::{Data, Json, Query},
+    HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+
+/// Entry of the moderation queue.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ModerationEntry {
+    /// ID of the flagged recipe.
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub recipe_id: String,
+    /// Reasons why the recipe was flagged.
+    pub reasons: Vec<ScreeningFlag>,
+    /// Timestamp of the latest time the recipe was flagged.
+    #[schema(value_type = String, example = "2025-09-11T08:58:56Z")]
+    pub flagged_at: DateTime<Utc>,
+}
+
+/// Decisions of the administrators over the flagged content.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationAction {
+    /// The content is published.
+    Approve,
+    /// The content is deleted.
+    Reject,
+}
+
+/// Request body of the moderation resource.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ModerationDecision {
+    pub action: ModerationAction,
+}
+
+/// List the recipes that are pending moderation.
+///
+/// # Description
+///
+/// Entries are sorted by the time they were flagged, oldest first. This resource is restricted to clients of the API
+/// with administration privileges.
+#[utoipa::path(
+    get,
+    path = "/admin/moderation",
+    tag = "Admin",
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The content of the moderation queue.", body = [ModerationEntry]),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("/moderation")]
+pub async fn get_moderation_queue(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    Ok(HttpResponse::Ok().json(get_moderation_queue_from_db(&pool).await?))
+}
+
+/// Review a recipe of the moderation queue.
+///
+/// # Description
+///
+/// Approved recipes are removed from the queue and published. Rejected recipes are deleted from the DB.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    post,
+    path = "/admin/moderation/{id}",
+    tag = "Admin",
+    params(("id" = String, Path, description = "ID or short ID of the flagged recipe.")),
+    security(
+        ("api_key" = [])
+    ),
+    request_body(
+        content = ModerationDecision, description = "The decision over the flagged recipe.",
+        example = json!({"action": "approve"})
+    ),
+    responses(
+        (status = 204, description = "The decision was applied."),
+        (status = 400, description = "The given ID has an invalid format, or the decision is not valid."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+        (status = 404, description = "The given ID doesn't match a recipe pending moderation."),
+    )
+)]
+#[instrument(skip(pool, ids, token, access))]
+#[post("/moderation/{id}")]
+pub async fn moderate_recipe(
+    recipe_id: RecipeId,
+    req: Json<ModerationDecision>,
+    pool: Data<MySqlPool>,
+    ids: Data<dyn IdGenerator>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    if !is_recipe_pending_moderation(&pool, recipe_id.as_uuid()).await? {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    match req.action {
+        ModerationAction::Approve => {
+            approve_recipe_in_db(&pool, recipe_id.as_uuid()).await?;
+            info!("Recipe {recipe_id} approved");
+            notify_recipe_approved(&pool, &**ids, recipe_id.as_uuid()).await?;
+        }
+        ModerationAction::Reject => {
+            let id = recipe_id.to_string();
+            delete_recipes_from_db(&pool, &[(&id, Some(*recipe_id.as_uuid()))], None).await?;
+            info!("Recipe {recipe_id} rejected and deleted");
+        }
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
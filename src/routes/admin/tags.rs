@@ -0,0 +1,212 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Administration resources for the tags of the recipes.
+//!
+//! # Description
+//!
+//! Curating the tags recipe by recipe doesn't scale, i.e. tagging all the recipes that use mezcal with `smoky`.
+//! [bulk_retag] applies a tag action to all the recipes selected by a set of filters, in batches of
+//! [BULK_TAG_BATCH_SIZE] recipes.
+
+use crate::{
+    authentication::{access_denied_response, check_admin_access, AccessControl, AuthData, Scope},
+    domain::{ApiError, IdGenerator, RecipeQuery, ResourceId, Tag},
+    routes::{
+        admin::utils::{get_recipes_tagged_from_db, retag_recipes_in_db},
+        recipe::{utils::search_recipe_by_ingredients, RecipeSearch},
+    },
+};
+use actix_web::{
+    post,
+    web::{Data, Json, Query},
+    HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Amount of recipes modified within a single transaction.
+pub const BULK_TAG_BATCH_SIZE: usize = 100;
+
+/// Actions over the tags of the recipes.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TagAction {
+    /// The tag is added to the recipes that don't have it yet.
+    Add,
+    /// The tag is removed from the recipes.
+    Remove,
+    /// The tag is replaced by another tag.
+    Replace,
+}
+
+/// Request body of the bulk re-tagging resource.
+///
+/// # Description
+///
+/// Recipes are selected using a recipe search (`filter`), the ingredients they use (`ingredients`), or both. At least
+/// one of them is required, so all the recipes are never modified by mistake.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct BulkTagRequest {
+    pub action: TagAction,
+    /// Tag added, removed or replaced.
+    #[schema(example = "smoky")]
+    pub tag: String,
+    /// New tag of the recipes. It is required by `replace`, and ignored otherwise.
+    #[schema(example = "smoked")]
+    pub replacement: Option<String>,
+    /// Recipe search that selects the recipes.
+    pub filter: Option<RecipeQuery>,
+    /// IDs of ingredients. Only the recipes that use all of them are selected.
+    #[schema(example = json!(["0191e13b-5ab7-78f1-bc06-be503a6c111b"]))]
+    pub ingredients: Option<Vec<String>>,
+    /// Report the recipes that would be modified, without modifying them.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Report of a bulk re-tagging.
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq)]
+pub struct BulkTagReport {
+    pub dry_run: bool,
+    /// Amount of recipes selected by the filters.
+    pub matched: usize,
+    /// Amount of recipes modified, or that would be modified by a dry run. Recipes that already have the expected tags
+    /// are not modified.
+    pub affected: usize,
+    /// IDs of the affected recipes.
+    pub recipe_ids: Vec<String>,
+}
+
+/// Add, remove or replace a tag across a set of recipes.
+///
+/// # Description
+///
+/// The recipes are modified in batches of 100 recipes, each of them within its own transaction. Use `dry_run` to get
+/// the amount of recipes that would be modified first.
+///
+/// Recipe searches follow the rules of the recipe resource, i.e. only published recipes are selected unless the
+/// search asks for another state, and recipes pending moderation are skipped.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    post,
+    path = "/admin/tags/bulk",
+    tag = "Admin",
+    security(
+        ("api_key" = [])
+    ),
+    request_body(
+        content = BulkTagRequest, description = "The tag action and the filters that select the recipes.",
+        example = json!({
+            "action": "add",
+            "tag": "smoky",
+            "ingredients": ["0191e13b-5ab7-78f1-bc06-be503a6c111b"],
+            "dry_run": true
+        })
+    ),
+    responses(
+        (status = 200, description = "The tag action was applied, or simulated.", body = BulkTagReport),
+        (status = 400, description = "Some of the tags, the filters or the IDs is not valid, or no filter was given."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+    )
+)]
+#[instrument(skip(pool, ids, token, access))]
+#[post("/tags/bulk")]
+pub async fn bulk_retag(
+    req: Json<BulkTagRequest>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    ids: Data<dyn IdGenerator>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let Ok(tag) = Tag::new(&req.tag) else {
+        return Ok(HttpResponse::BadRequest().body("Invalid tag"));
+    };
+    let replacement = match (req.action, req.replacement.as_deref()) {
+        (TagAction::Replace, Some(replacement)) => match Tag::new(replacement) {
+            Ok(replacement) if replacement != tag => Some(replacement),
+            _ => return Ok(HttpResponse::BadRequest().body("Invalid replacement")),
+        },
+        (TagAction::Replace, None) => {
+            return Ok(HttpResponse::BadRequest().body("Replacing a tag needs a replacement"))
+        }
+        _ => None,
+    };
+
+    let mut selected: Option<Vec<Uuid>> = None;
+    if let Some(filter) = &req.filter {
+        let search = match RecipeSearch::new(filter.clone()) {
+            Ok(search) => search,
+            Err(e) => return Ok(HttpResponse::BadRequest().body(e)),
+        };
+        selected = Some(search.run_ids(&pool).await?);
+    }
+    if let Some(ingredients) = &req.ingredients {
+        let Ok(ingredients) = ingredients
+            .iter()
+            .map(|id| ResourceId::try_from(id.as_str()).map(Uuid::from))
+            .collect::<Result<Vec<Uuid>, _>>()
+        else {
+            return Ok(HttpResponse::BadRequest().body("Invalid ingredient ID"));
+        };
+        let found = search_recipe_by_ingredients(&pool, &ingredients).await?;
+        selected = Some(match selected {
+            Some(mut selected) => {
+                selected.retain(|id| found.contains(id));
+                selected
+            }
+            None => found,
+        });
+    }
+    let Some(selected) = selected else {
+        return Ok(HttpResponse::BadRequest().body("No filter was given"));
+    };
+
+    // Only the recipes whose tags change are modified.
+    let tagged = get_recipes_tagged_from_db(&pool, &tag).await?;
+    let affected = selected
+        .iter()
+        .filter(|id| tagged.contains(id) != (req.action == TagAction::Add))
+        .copied()
+        .collect::<Vec<Uuid>>();
+
+    if !req.dry_run {
+        for batch in affected.chunks(BULK_TAG_BATCH_SIZE) {
+            retag_recipes_in_db(
+                &pool,
+                ids.get_ref(),
+                req.action,
+                &tag,
+                replacement.as_ref(),
+                batch,
+            )
+            .await?;
+        }
+        info!(
+            "Tag action {:?} ({tag}) applied to {} recipes",
+            req.action,
+            affected.len()
+        );
+    }
+
+    Ok(HttpResponse::Ok().json(BulkTagReport {
+        dry_run: req.dry_run,
+        matched: selected.len(),
+        affected: affected.len(),
+        recipe_ids: affected.iter().map(Uuid::to_string).collect(),
+    }))
+}
@@ -0,0 +1,719 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::{
+    domain::{IdGenerator, ServerError, Tag},
+    routes::{
+        admin::{
+            analytics::{DailyUsage, EndpointUsage, SearchUsage},
+            author::AuthorMergeSummary,
+            clients::{ClientQuery, ClientRecord},
+            emails::{EmailQuery, EmailRecord},
+            migrations::AppliedMigration,
+            moderation::ModerationEntry,
+            tags::TagAction,
+        },
+        recipe::utils::disambiguate_slugs,
+    },
+    utils::{
+        changes::{touch_collection, Collection},
+        events::{record_event, DomainEvent},
+        http::MaintenanceWindow,
+        mailing::recipient_hash,
+    },
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::MySqlPool;
+use std::collections::HashSet;
+use tracing::{debug, error, instrument};
+use uuid::Uuid;
+
+/// Outcome of [merge_authors_in_db].
+#[derive(Debug)]
+pub enum AuthorMergeOutcome {
+    /// The source author was merged into the target author.
+    Merged(AuthorMergeSummary),
+    /// Some of the authors doesn't exist, or was merged previously.
+    AuthorNotFound,
+}
+
+/// Merge the author identified by `source` into the author identified by `target`.
+///
+/// # Description
+///
+/// All the changes are applied within a single transaction. See [crate::routes::admin::merge_authors] for the details
+/// about the data that is moved between both profiles.
+#[instrument(skip(pool))]
+pub async fn merge_authors_in_db(
+    pool: &MySqlPool,
+    source: &Uuid,
+    target: &Uuid,
+) -> Result<AuthorMergeOutcome, ServerError> {
+    let source = source.to_string();
+    let target = target.to_string();
+    let mut summary = AuthorMergeSummary::default();
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let authors = sqlx::query_scalar!(
+        "SELECT id FROM Author WHERE id IN (?, ?) AND deleted_at IS NULL FOR UPDATE",
+        source,
+        target
+    )
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    if authors.len() != 2 {
+        debug!("Some of the authors doesn't exist");
+        return Ok(AuthorMergeOutcome::AuthorNotFound);
+    }
+
+    let moved_recipes =
+        sqlx::query_scalar!("SELECT id FROM Cocktail WHERE owner = ? FOR UPDATE", source)
+            .fetch_all(&mut *transaction)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+
+    disambiguate_slugs(&mut transaction, &moved_recipes, &target).await?;
+    summary.recipes_moved = sqlx::query!(
+        "UPDATE Cocktail SET owner = ? WHERE owner = ?",
+        target,
+        source
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?
+    .rows_affected();
+    debug!("Recipes moved: {}", summary.recipes_moved);
+
+    let owner = Uuid::parse_str(&target).map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+    for recipe_id in moved_recipes.iter() {
+        let recipe_id = Uuid::parse_str(recipe_id).map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+        record_event(
+            &mut *transaction,
+            &DomainEvent::RecipeOwnerChanged { recipe_id, owner },
+        )
+        .await?;
+    }
+
+    // Authors are allowed a single profile per social network, so the target's profiles take precedence.
+    let target_providers = sqlx::query_scalar!(
+        "SELECT provider_name FROM AuthorHashSocialProfile WHERE author_id = ?",
+        target
+    )
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let source_profiles = sqlx::query!(
+        "SELECT id, provider_name FROM AuthorHashSocialProfile WHERE author_id = ?",
+        source
+    )
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    for profile in source_profiles {
+        let result = if target_providers.contains(&profile.provider_name) {
+            debug!("Discarding the profile of {}", profile.provider_name);
+            sqlx::query!(
+                "DELETE FROM AuthorHashSocialProfile WHERE id = ?",
+                profile.id
+            )
+            .execute(&mut *transaction)
+            .await
+        } else {
+            summary.social_profiles_moved += 1;
+            sqlx::query!(
+                "UPDATE AuthorHashSocialProfile SET author_id = ? WHERE id = ?",
+                target,
+                profile.id
+            )
+            .execute(&mut *transaction)
+            .await
+        };
+
+        result.map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+    }
+
+    // Aliases of the source author now point to the target author.
+    sqlx::query!(
+        "UPDATE AuthorAlias SET author_id = ? WHERE author_id = ?",
+        target,
+        source
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    sqlx::query!(
+        "INSERT INTO AuthorAlias (alias_id, author_id) VALUES (?, ?)",
+        source,
+        target
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    // The email of the merged profile is released, so it can be registered again.
+    sqlx::query!(
+        "UPDATE Author SET deleted_at = CURRENT_TIMESTAMP, email_key = NULL WHERE id = ?",
+        source
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    if summary.recipes_moved > 0 {
+        touch_collection(&mut *transaction, Collection::Recipe).await?;
+    }
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(AuthorMergeOutcome::Merged(summary))
+}
+
+/// Retrieve the content of the moderation queue, oldest entries first.
+#[instrument(skip(pool))]
+pub async fn get_moderation_queue_from_db(
+    pool: &MySqlPool,
+) -> Result<Vec<ModerationEntry>, ServerError> {
+    let rows = sqlx::query!(
+        "SELECT cocktail_id, reasons, flagged_at FROM ModerationQueue ORDER BY flagged_at, cocktail_id"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ModerationEntry {
+            recipe_id: row.cocktail_id,
+            reasons: serde_json::from_str(&row.reasons).unwrap_or_default(),
+            flagged_at: row.flagged_at,
+        })
+        .collect())
+}
+
+/// Remove a recipe from the moderation queue, so it gets published.
+///
+/// # Description
+///
+/// Published recipes get their publication date set to the approval, as they were hidden from the public until then.
+#[instrument(skip(pool))]
+pub async fn approve_recipe_in_db(pool: &MySqlPool, id: &Uuid) -> Result<bool, ServerError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let result = sqlx::query!(
+        "DELETE FROM ModerationQueue WHERE cocktail_id = ?",
+        id.to_string()
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        "UPDATE Cocktail SET publication_date = CURRENT_TIMESTAMP \
+        WHERE id = ? AND state = 'published'",
+        id.to_string()
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    touch_collection(&mut *transaction, Collection::Recipe).await?;
+    record_event(
+        &mut *transaction,
+        &DomainEvent::RecipeApproved { recipe_id: *id },
+    )
+    .await?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(true)
+}
+
+/// Retrieve the attempts to send emails that match the given filters, newest first.
+#[instrument(skip(pool))]
+pub async fn get_emails_from_db(
+    pool: &MySqlPool,
+    filters: &EmailQuery,
+    limit: u32,
+) -> Result<Vec<EmailRecord>, ServerError> {
+    let status = filters.status.map(|s| s.to_string());
+    let kind = filters.kind.map(|k| k.to_string());
+    let recipient = filters.recipient.as_deref().map(recipient_hash);
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, kind, request_id, client_id, recipient_hash, status, provider_message_id, error, sent_at
+        FROM EmailMessage
+        WHERE (? IS NULL OR status = ?)
+            AND (? IS NULL OR kind = ?)
+            AND (? IS NULL OR recipient_hash = ?)
+            AND (? IS NULL OR sent_at >= ?)
+            AND (? IS NULL OR sent_at < ?)
+        ORDER BY sent_at DESC, id DESC
+        LIMIT ?
+        "#,
+        status,
+        status,
+        kind,
+        kind,
+        recipient,
+        recipient,
+        filters.since,
+        filters.since,
+        filters.until,
+        filters.until,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(EmailRecord {
+                id: row.id,
+                kind: serde_json::from_value(serde_json::Value::String(row.kind)).map_err(|e| {
+                    error!("{e}");
+                    ServerError::DbError
+                })?,
+                status: serde_json::from_value(serde_json::Value::String(row.status)).map_err(
+                    |e| {
+                        error!("{e}");
+                        ServerError::DbError
+                    },
+                )?,
+                request_id: row.request_id,
+                client_id: row.client_id,
+                recipient_hash: row.recipient_hash,
+                provider_message_id: row.provider_message_id,
+                error: row.error,
+                sent_at: row.sent_at,
+            })
+        })
+        .collect()
+}
+
+/// Retrieve the clients of the API that match the given filters, along with their failed access checks.
+#[instrument(skip(pool))]
+pub async fn get_clients_from_db(
+    pool: &MySqlPool,
+    filters: &ClientQuery,
+    limit: u32,
+) -> Result<Vec<ClientRecord>, ServerError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT au.id, au.name, au.email,
+            CAST(COALESCE(au.validated, 0) AS SIGNED) validated,
+            CAST(COALESCE(au.enabled, 0) AS SIGNED) enabled,
+            CAST(au.admin AS SIGNED) admin,
+            au.failed_attempts, au.locked_until,
+            COUNT(af.id) total_failures, MAX(af.attempted_at) last_failure_at
+        FROM ApiUser au LEFT JOIN AuthFailure af ON af.client_id = au.id
+        WHERE ? IS NULL
+            OR ? = CAST(COALESCE(au.locked_until > CURRENT_TIMESTAMP, 0) AS SIGNED)
+        GROUP BY au.id
+        ORDER BY total_failures DESC, au.id
+        LIMIT ?
+        "#,
+        filters.locked,
+        filters.locked,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ClientRecord {
+            id: row.id,
+            name: row.name,
+            email: row.email,
+            validated: row.validated > 0,
+            enabled: row.enabled > 0,
+            admin: row.admin > 0,
+            failed_attempts: row.failed_attempts,
+            total_failures: row.total_failures,
+            last_failure_at: row.last_failure_at,
+            locked_until: row.locked_until,
+        })
+        .collect())
+}
+
+/// Store a new maintenance window announced by an administrator.
+#[instrument(skip(pool))]
+pub async fn store_maintenance_window_in_db(
+    pool: &MySqlPool,
+    window: &MaintenanceWindow,
+    created_by: &str,
+) -> Result<(), ServerError> {
+    sqlx::query!(
+        "INSERT INTO MaintenanceWindow (starts_at, ends_at, message, created_by) VALUES (?, ?, ?, ?)",
+        window.starts_at,
+        window.ends_at,
+        window.message.as_deref(),
+        created_by
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
+
+/// Retrieve the newest maintenance window that didn't pass at the given time.
+#[instrument(skip(pool))]
+pub async fn get_maintenance_window_from_db(
+    pool: &MySqlPool,
+    now: DateTime<Utc>,
+) -> Result<Option<MaintenanceWindow>, ServerError> {
+    sqlx::query_as!(
+        MaintenanceWindow,
+        r#"
+        SELECT starts_at, ends_at, message
+        FROM MaintenanceWindow
+        WHERE ends_at > ?
+        ORDER BY id DESC
+        LIMIT 1
+        "#,
+        now
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })
+}
+
+/// Cancel the maintenance windows that didn't pass at the given time. Returns whether some window was cancelled.
+#[instrument(skip(pool))]
+pub async fn cancel_maintenance_windows_in_db(
+    pool: &MySqlPool,
+    now: DateTime<Utc>,
+) -> Result<bool, ServerError> {
+    let result = sqlx::query!("DELETE FROM MaintenanceWindow WHERE ends_at > ?", now)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Retrieve the migrations applied to the DB, as registered by sqlx.
+#[instrument(skip(pool))]
+pub async fn get_applied_migrations_from_db(
+    pool: &MySqlPool,
+) -> Result<Vec<AppliedMigration>, ServerError> {
+    let rows = sqlx::query!(
+        "SELECT version, success, checksum, installed_on FROM _sqlx_migrations ORDER BY version"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| AppliedMigration {
+            version: row.version,
+            success: row.success != 0,
+            checksum: row.checksum,
+            installed_on: row.installed_on,
+        })
+        .collect())
+}
+
+/// Retrieve the IDs of the recipes tagged with `tag`.
+#[instrument(skip(pool))]
+pub async fn get_recipes_tagged_from_db(
+    pool: &MySqlPool,
+    tag: &Tag,
+) -> Result<HashSet<Uuid>, ServerError> {
+    let ids = sqlx::query_scalar!(
+        "SELECT DISTINCT cocktail_id FROM Tagged WHERE tag = ?",
+        tag.identifier
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    ids.iter()
+        .map(|id| {
+            Uuid::parse_str(id).map_err(|_| {
+                error!("Failed to parse ID from a value of the DB");
+                ServerError::DbError
+            })
+        })
+        .collect()
+}
+
+/// Apply a tag action to a batch of recipes within a single transaction.
+///
+/// # Description
+///
+/// Tags added by administrators are registered as `backend` tags. Replacing a tag keeps the type of the original tag,
+/// unless the recipe already had the replacement, in which case the original tag is just removed. The recipes are
+/// marked as modified.
+#[instrument(skip(pool, ids, recipes))]
+pub async fn retag_recipes_in_db(
+    pool: &MySqlPool,
+    ids: &dyn IdGenerator,
+    action: TagAction,
+    tag: &Tag,
+    replacement: Option<&Tag>,
+    recipes: &[Uuid],
+) -> Result<(), ServerError> {
+    let db_error = |e: sqlx::Error| {
+        error!("{e}");
+        ServerError::DbError
+    };
+    let mut transaction = pool.begin().await.map_err(db_error)?;
+
+    let new_tag = match action {
+        TagAction::Add => Some(tag),
+        TagAction::Replace => replacement,
+        TagAction::Remove => None,
+    };
+    if let Some(new_tag) = new_tag {
+        sqlx::query!(
+            "INSERT IGNORE INTO Tag SET identifier = ?",
+            new_tag.identifier
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(db_error)?;
+    }
+
+    for recipe_id in recipes {
+        let id = recipe_id.to_string();
+
+        match (action, new_tag) {
+            (TagAction::Add, _) => {
+                sqlx::query!(
+                    "INSERT INTO Tagged (id, cocktail_id, type, tag) SELECT ?, ?, 'backend', ? FROM DUAL \
+                    WHERE NOT EXISTS (SELECT id FROM Tagged WHERE cocktail_id = ? AND tag = ?)",
+                    ids.new_id().to_string(),
+                    id,
+                    tag.identifier,
+                    id,
+                    tag.identifier
+                )
+                .execute(&mut *transaction)
+                .await
+                .map_err(db_error)?;
+            }
+            (TagAction::Replace, Some(new_tag)) => {
+                let tagged = sqlx::query_scalar!(
+                    "SELECT COUNT(*) AS count FROM Tagged WHERE cocktail_id = ? AND tag = ?",
+                    id,
+                    new_tag.identifier
+                )
+                .fetch_one(&mut *transaction)
+                .await
+                .map_err(db_error)?;
+                let result = if tagged > 0 {
+                    sqlx::query!(
+                        "DELETE FROM Tagged WHERE cocktail_id = ? AND tag = ?",
+                        id,
+                        tag.identifier
+                    )
+                    .execute(&mut *transaction)
+                    .await
+                } else {
+                    sqlx::query!(
+                        "UPDATE Tagged SET tag = ? WHERE cocktail_id = ? AND tag = ?",
+                        new_tag.identifier,
+                        id,
+                        tag.identifier
+                    )
+                    .execute(&mut *transaction)
+                    .await
+                };
+                result.map_err(db_error)?;
+            }
+            _ => {
+                sqlx::query!(
+                    "DELETE FROM Tagged WHERE cocktail_id = ? AND tag = ?",
+                    id,
+                    tag.identifier
+                )
+                .execute(&mut *transaction)
+                .await
+                .map_err(db_error)?;
+            }
+        }
+
+        sqlx::query!(
+            "UPDATE Cocktail SET update_date = CURRENT_TIMESTAMP WHERE id = ?",
+            id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(db_error)?;
+        record_event(
+            &mut *transaction,
+            &DomainEvent::RecipeUpdated {
+                recipe_id: *recipe_id,
+            },
+        )
+        .await?;
+    }
+
+    touch_collection(&mut *transaction, Collection::Recipe).await?;
+    transaction.commit().await.map_err(db_error)?;
+    debug!("{} recipes retagged", recipes.len());
+
+    Ok(())
+}
+
+/// Retrieve the amount of requests per day since the given day, oldest first.
+#[instrument(skip(pool))]
+pub async fn get_daily_usage_from_db(
+    pool: &MySqlPool,
+    from: NaiveDate,
+) -> Result<Vec<DailyUsage>, ServerError> {
+    // Sums are reported as nullable, though the groups always hold some row.
+    sqlx::query_as!(
+        DailyUsage,
+        r#"SELECT day, CAST(SUM(hits) AS UNSIGNED) AS "hits!" FROM EndpointUsage
+        WHERE day >= ? GROUP BY day ORDER BY day"#,
+        from
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })
+}
+
+/// Retrieve the amount of requests per endpoint since the given day, most used first.
+#[instrument(skip(pool))]
+pub async fn get_endpoint_usage_from_db(
+    pool: &MySqlPool,
+    from: NaiveDate,
+) -> Result<Vec<EndpointUsage>, ServerError> {
+    sqlx::query_as!(
+        EndpointUsage,
+        r#"SELECT endpoint, CAST(SUM(hits) AS UNSIGNED) AS "hits!" FROM EndpointUsage
+        WHERE day >= ? GROUP BY endpoint ORDER BY SUM(hits) DESC, endpoint"#,
+        from
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })
+}
+
+/// Retrieve the most searched terms since the given day, skipping those that no day got `k_anonymity` distinct clients.
+#[instrument(skip(pool))]
+pub async fn get_top_searches_from_db(
+    pool: &MySqlPool,
+    from: NaiveDate,
+    k_anonymity: u32,
+    limit: u32,
+) -> Result<Vec<SearchUsage>, ServerError> {
+    sqlx::query_as!(
+        SearchUsage,
+        r#"SELECT resource, term, CAST(SUM(hits) AS UNSIGNED) AS "hits!" FROM SearchUsage
+        WHERE day >= ? AND (resource, term) IN (SELECT resource, term FROM SearchClient WHERE day >= ?
+        GROUP BY day, resource, term HAVING COUNT(*) >= ?)
+        GROUP BY resource, term ORDER BY SUM(hits) DESC, resource, term LIMIT ?"#,
+        from,
+        from,
+        k_anonymity,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })
+}
@@ -0,0 +1,201 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Static assets of the application, i.e. the style sheet of the token request flow.
+//!
+//! # Description
+//!
+//! Assets requested by their hashed name are cached forever by the clients, while assets requested by their plain
+//! name are revalidated using their `ETag` (see [crate::utils::assets]). The listing of the assets is disabled unless
+//! the settings enable it.
+
+use crate::utils::{
+    assets::{AssetMatch, AssetStore},
+    http::is_etag_not_modified,
+};
+use actix_files::file_extension_to_mime;
+use actix_web::{
+    get,
+    http::header::{
+        CacheControl, CacheDirective, ContentType, ETag, EntityTag, X_CONTENT_TYPE_OPTIONS,
+    },
+    web::{Data, Path},
+    HttpRequest, HttpResponse,
+};
+use tracing::{debug, instrument};
+
+/// Amount of seconds that an asset requested by its hashed name can be cached (one year).
+const HASHED_ASSET_MAX_AGE: u32 = 31_536_000;
+
+/// Retrieve a static asset.
+#[instrument(skip(req, assets))]
+#[get("/static/{name}")]
+pub async fn get_static_asset(
+    req: HttpRequest,
+    name: Path<String>,
+    assets: Data<AssetStore>,
+) -> HttpResponse {
+    let (asset, cache_control) = match assets.get(&name) {
+        Some(AssetMatch::Plain(asset)) => (asset, vec![CacheDirective::NoCache]),
+        Some(AssetMatch::Hashed(asset)) => (
+            asset,
+            vec![
+                CacheDirective::Public,
+                CacheDirective::MaxAge(HASHED_ASSET_MAX_AGE),
+                CacheDirective::Extension("immutable".into(), None),
+            ],
+        ),
+        None => {
+            debug!("No static asset was found with the name: {name}");
+            return HttpResponse::NotFound().finish();
+        }
+    };
+
+    let etag = EntityTag::new_strong(asset.hash.clone());
+    let not_modified = is_etag_not_modified(&req, &etag);
+
+    let mut response = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+    response
+        .insert_header(ETag(etag))
+        .insert_header(CacheControl(cache_control))
+        .insert_header((X_CONTENT_TYPE_OPTIONS, "nosniff"));
+
+    if not_modified {
+        response.finish()
+    } else {
+        response
+            .content_type(file_extension_to_mime(asset.extension()))
+            .body(asset.content.clone())
+    }
+}
+
+/// List the static assets, when the listing is enabled by the settings.
+#[instrument(skip(assets))]
+#[get("/static/")]
+pub async fn get_static_listing(assets: Data<AssetStore>) -> HttpResponse {
+    if !assets.listing() {
+        return HttpResponse::NotFound().finish();
+    }
+
+    // Names of the assets are checked when they are loaded, so they need no escaping.
+    let entries = assets
+        .iter()
+        .map(|asset| {
+            format!(
+                "<li><a href=\"{hashed}\">{name}</a></li>",
+                hashed = asset.hashed_name(),
+                name = asset.name
+            )
+        })
+        .collect::<String>();
+
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .body(format!(
+            "<!DOCTYPE html><html><head><title>Static assets</title></head><body><ul>{entries}</ul></body></html>"
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        body::to_bytes,
+        http::{header, StatusCode},
+        test::{call_service, init_service, TestRequest},
+        App,
+    };
+    use pretty_assertions::assert_eq;
+
+    fn assets(listing: bool) -> AssetStore {
+        let mut assets = AssetStore::default().with_listing(listing);
+        assets.insert("style.css".into(), b"body {}".to_vec());
+        assets
+    }
+
+    #[actix_web::test]
+    async fn assets_are_cached() {
+        let assets = assets(false);
+        let asset = assets.iter().next().unwrap().clone();
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(assets))
+                .service(get_static_listing)
+                .service(get_static_asset),
+        )
+        .await;
+
+        let response = call_service(
+            &app,
+            TestRequest::get().uri("/static/style.css").to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-cache"
+        );
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/css"
+        );
+        let etag = response.headers().get(header::ETAG).unwrap().clone();
+        assert_eq!(
+            to_bytes(response.into_body()).await.unwrap().as_ref(),
+            b"body {}"
+        );
+
+        let response = call_service(
+            &app,
+            TestRequest::get()
+                .uri("/static/style.css")
+                .insert_header((header::IF_NONE_MATCH, etag))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let response = call_service(
+            &app,
+            TestRequest::get()
+                .uri(&format!("/static/{}", asset.hashed_name()))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            "public, max-age=31536000, immutable"
+        );
+
+        for uri in ["/static/app.js", "/static/", "/static/..%2Fstyle.css"] {
+            let response = call_service(&app, TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{uri}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn assets_are_listed_when_enabled() {
+        let assets = assets(true);
+        let hashed = assets.iter().next().unwrap().hashed_name();
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(assets))
+                .service(get_static_listing),
+        )
+        .await;
+
+        let response = call_service(&app, TestRequest::get().uri("/static/").to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains(&hashed));
+    }
+}
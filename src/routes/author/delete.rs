@@ -7,19 +7,71 @@
 //! Author endpoint DELETE method.
 
 use crate::{
-    authentication::{check_access, AuthData},
-    domain::DataDomainError,
-    routes::author::utils::delete_author_from_db,
+    authentication::{access_denied_response, check_access, AccessControl, AuthData, Scope},
+    domain::{ApiError, ResourceId},
+    routes::author::utils::{
+        delete_author_from_db, get_author_from_db, AuthorDeletion, OwnedRecipesPolicy,
+    },
+    utils::http::{is_precondition_met, resource_etag, Preconditions},
 };
 use actix_web::{
     delete,
-    web::{Data, Path, Query},
-    HttpResponse,
+    web::{Data, Query},
+    HttpRequest, HttpResponse,
 };
+use serde::{Deserialize, Serialize};
 use sqlx::MySqlPool;
-use std::error::Error;
 use tracing::{info, instrument};
-use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Options accepted by the DELETE method of the `/author/{id}` resource.
+///
+/// # Description
+///
+/// These options define what to do with the recipes owned by the author. Only one of them can be given per request.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct DeleteAuthorParams {
+    /// Transfer the ownership of the author's recipes to the author identified by this ID.
+    #[param(value_type = Option<String>, example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub reassign_to: Option<ResourceId>,
+    /// Delete the author's recipes along with the author.
+    pub cascade: Option<bool>,
+}
+
+impl DeleteAuthorParams {
+    /// Translate the params into an [OwnedRecipesPolicy].
+    ///
+    /// # Description
+    ///
+    /// `None` is returned when the params are contradictory: both options given at once, or the recipes are
+    /// transferred to the author that is about to be deleted.
+    fn policy(&self, author_id: &ResourceId) -> Option<OwnedRecipesPolicy> {
+        match (self.reassign_to, self.cascade.unwrap_or_default()) {
+            (Some(_), true) => None,
+            (Some(target), false) if target == *author_id => None,
+            (Some(target), false) => Some(OwnedRecipesPolicy::Reassign(target.into())),
+            (None, true) => Some(OwnedRecipesPolicy::Cascade),
+            (None, false) => Some(OwnedRecipesPolicy::Reject),
+        }
+    }
+}
+
+/// Minimal descriptor of a recipe owned by an author.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OwnedRecipe {
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub id: String,
+    #[schema(example = "Margarita")]
+    pub name: String,
+}
+
+/// Summary of the recipes that prevent the deletion of an author.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OwnedRecipesSummary {
+    /// Amount of recipes owned by the author.
+    pub recipe_count: usize,
+    pub recipes: Vec<OwnedRecipe>,
+}
 
 /// Delete an author from the system.
 ///
@@ -28,6 +80,16 @@ use uuid::Uuid;
 /// This method deletes an **Author** entry from the DB if the given ID matches the ID of a
 /// registered author.
 ///
+/// Authors that own some recipes are not deleted by default. A summary of the owned recipes is returned instead, so
+/// the client can decide what to do with them:
+/// - `reassign_to`: transfer the recipes to another registered author.
+/// - `cascade=true`: delete the recipes along with the author.
+///
+/// Clients can send the `ETag` of the author, as received from `GET /author/{id}`, using `If-Match`. The author is
+/// only deleted when it didn't change since then. Otherwise, the request is answered with a code **412**. The server
+/// can be configured to require such header, in which case the deletions that include none are answered with a code
+/// **428**.
+///
 /// This method requires to provide a valid API token.
 #[utoipa::path(
     delete,
@@ -36,30 +98,131 @@ use uuid::Uuid;
     security(
         ("api_key" = [])
     ),
+    params(
+        ("id" = String, Path, description = "ID of the author."),
+        DeleteAuthorParams,
+        ("If-Match" = Option<String>, Header, description = "Delete the author only when its current `ETag` is included."),
+    ),
     responses(
         (status = 200, description = "The author was deleted from the DB."),
+        (
+            status = 400,
+            description = "The given author's ID has an invalid format, or the options for the owned recipes are \
+            invalid: both options given, the recipes are reassigned to the same author, or the target author doesn't \
+            exist."
+        ),
         (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
         (status = 404, description = "An author identified by the given ID didn't exist in the DB."),
+        (
+            status = 409,
+            description = "The author owns some recipes, and no option to handle them was given.",
+            body = OwnedRecipesSummary,
+        ),
+        (status = 412, description = "The author changed since the `ETag` given in `If-Match` was issued."),
+        (status = 428, description = "The server requires an `If-Match` header to delete the authors."),
     )
 )]
-#[instrument(skip(path, token, pool), fields(author_id = %path.0))]
+#[instrument(skip(token, pool, params, request, preconditions, access), fields(author_id = %author_id))]
 #[delete("{id}")]
 pub async fn delete_author(
-    path: Path<(String,)>,
+    author_id: ResourceId,
+    params: Query<DeleteAuthorParams>,
     token: Query<AuthData>,
+    request: HttpRequest,
     pool: Data<MySqlPool>,
-) -> Result<HttpResponse, Box<dyn Error>> {
+    access: Data<AccessControl>,
+    preconditions: Data<Preconditions>,
+) -> Result<HttpResponse, ApiError> {
     // Access control
-    check_access(&pool, &token.api_key).await?;
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
     info!("Access granted");
 
-    let author_id = match Uuid::parse_str(&path.0) {
-        Ok(id) => id,
-        Err(_) => return Err(Box::new(DataDomainError::InvalidId)),
+    let policy = match params.policy(&author_id) {
+        Some(policy) => policy,
+        None => {
+            info!("Invalid options given for the owned recipes");
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+    };
+
+    let author = match get_author_from_db(&pool, author_id.as_uuid()).await? {
+        Some(author) => author,
+        None => return Ok(HttpResponse::NotFound().finish()),
     };
+    if preconditions.is_missing(&request) {
+        info!("The deletion of the author {author_id} includes no If-Match header");
+        return Ok(HttpResponse::PreconditionRequired().finish());
+    }
+    if !is_precondition_met(&request, &resource_etag(&author)?) {
+        info!("The author {author_id} changed since the given ETag was issued");
+        return Ok(HttpResponse::PreconditionFailed().finish());
+    }
+
+    match delete_author_from_db(&pool, author_id.as_uuid(), policy).await? {
+        AuthorDeletion::Deleted => {
+            info!("Author {author_id} deleted from the DB.");
+            Ok(HttpResponse::Ok().finish())
+        }
+        AuthorDeletion::AuthorNotFound => Ok(HttpResponse::NotFound().finish()),
+        AuthorDeletion::TargetNotFound => Ok(HttpResponse::BadRequest().finish()),
+        AuthorDeletion::OwnsRecipes(recipes) => {
+            info!("Author {author_id} owns {} recipes", recipes.len());
+            Ok(HttpResponse::Conflict().json(OwnedRecipesSummary {
+                recipe_count: recipes.len(),
+                recipes,
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use uuid::Uuid;
+
+    #[rstest]
+    fn owned_recipes_policy() {
+        let author_id = ResourceId::from(Uuid::now_v7());
+        let target_id = ResourceId::from(Uuid::now_v7());
+
+        let params = DeleteAuthorParams::default();
+        assert_eq!(params.policy(&author_id), Some(OwnedRecipesPolicy::Reject));
+
+        let params = DeleteAuthorParams {
+            reassign_to: None,
+            cascade: Some(false),
+        };
+        assert_eq!(params.policy(&author_id), Some(OwnedRecipesPolicy::Reject));
+
+        let params = DeleteAuthorParams {
+            reassign_to: None,
+            cascade: Some(true),
+        };
+        assert_eq!(params.policy(&author_id), Some(OwnedRecipesPolicy::Cascade));
+
+        let params = DeleteAuthorParams {
+            reassign_to: Some(target_id),
+            cascade: None,
+        };
+        assert_eq!(
+            params.policy(&author_id),
+            Some(OwnedRecipesPolicy::Reassign(target_id.into()))
+        );
 
-    delete_author_from_db(&pool, &author_id).await?;
-    info!("Author {} deleted from the DB.", author_id.to_string());
+        let params = DeleteAuthorParams {
+            reassign_to: Some(target_id),
+            cascade: Some(true),
+        };
+        assert_eq!(params.policy(&author_id), None);
 
-    Ok(HttpResponse::Ok().finish())
+        let params = DeleteAuthorParams {
+            reassign_to: Some(author_id),
+            cascade: None,
+        };
+        assert_eq!(params.policy(&author_id), None);
+    }
 }
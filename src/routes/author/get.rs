@@ -5,18 +5,22 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::{
-    authentication::{check_access, AuthData},
-    domain::{AuthorBuilder, DataDomainError},
-    routes::author::utils::{get_author_from_db, search_author_from_db},
+    authentication::{access_denied_response, check_access, AccessControl, AuthData, Scope},
+    domain::{ApiError, AuthorBuilder, DataDomainError, ResourceId},
+    routes::{
+        author::utils::{count_authors_from_db, get_author_from_db, search_author_from_db},
+        expand::{expand_author, ExpandQuery, Expansion},
+    },
+    utils::http::{resource_etag, MaxPageSize, Page},
 };
 use actix_web::{
     get,
-    web::{Data, Path, Query},
-    HttpResponse,
+    http::header::ETag,
+    web::{Data, Query},
+    HttpRequest, HttpResponse,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use sqlx::MySqlPool;
-use std::error::Error;
 use tracing::{debug, info, instrument};
 use utoipa::IntoParams;
 
@@ -28,11 +32,18 @@ use utoipa::IntoParams;
 /// search logic of the `/author` collection resource allows only to use a single token per search. This means that if
 /// multiple tokens are given, the one with the highest priority will be used.
 /// The **email** hash the highest priority, followed by **name** and **surname**.
-#[derive(Debug, Deserialize, IntoParams)]
+#[derive(Debug, Deserialize, Serialize, IntoParams)]
 pub struct AuthorQueryParams {
     pub name: Option<String>,
     pub surname: Option<String>,
     pub email: Option<String>,
+    /// Amount of authors of the page, 20 by default. The maximum depends on the settings of the server (100 by
+    /// default).
+    #[param(example = 20)]
+    pub limit: Option<u32>,
+    /// Amount of authors skipped, 0 by default.
+    #[param(example = 0)]
+    pub offset: Option<u32>,
 }
 
 impl AuthorQueryParams {
@@ -70,6 +81,10 @@ impl AuthorQueryParams {
 /// all the authors that match such criteria. Clients of the API with no API token would retrieve some author entries
 /// with muted data. Authors specify whether their profiles are public or not. If a profile is not public, only
 /// the authorised clients of the API (with a token) will get the whole profile information.
+///
+/// Results are paginated using `limit` and `offset`. The total amount of matching authors is included in the
+/// `X-Total-Count` header, and the links to the other pages in the `Link` header. Authors are sorted by ID, so the
+/// pages are stable.
 #[utoipa::path(
     tag = "Author",
     path = "/author",
@@ -86,6 +101,8 @@ impl AuthorQueryParams {
                 ("Content-Length"),
                 ("Content-Type"),
                 ("Date"),
+                ("X-Total-Count", description = "Amount of authors that match the search."),
+                ("Link", description = "Links to the first, previous, next and last pages."),
                 ("Vary", description = "Origin,Access-Control-Request-Method,Access-Control-Request-Headers")
             ),
             examples(
@@ -104,6 +121,7 @@ impl AuthorQueryParams {
                 ))
             ),
         ),
+        (status = 400, description = "The given page is not valid."),
         (
             status = 404,
             description = "The given author's ID was not found in the DB.",
@@ -113,6 +131,8 @@ impl AuthorQueryParams {
                 ("Vary", description = "Origin,Access-Control-Request-Method,Access-Control-Request-Headers")
             ),
         ),
+        (status = 401, description = "The given API key is not valid. Clients with no API key shall not include it."),
+        (status = 403, description = "The given API key doesn't grant the `read` scope."),
         (
             status = 429, description = "**Too many requests.**",
             headers(
@@ -120,11 +140,18 @@ impl AuthorQueryParams {
                 ("Access-Control-Allow-Origin"),
                 ("Retry-After", description = "Amount of time between requests (seconds).")
             )
-        )
+        ),
+        (
+            status = 503, description = "**The server is overloaded.** Retrieval of resources is temporarily disabled.",
+            headers(
+                ("Cache-Control", description = "Cache control is set to *no-cache*."),
+                ("Retry-After", description = "Amount of time to wait before retrying (seconds).")
+            )
+        ),
     )
 )]
 #[instrument(
-    skip(token, pool, req),
+    skip(token, pool, req, request, max_page_size, access),
     fields(
         author_email = %req.0.email.as_deref().unwrap_or_default(),
         author_name = %req.0.name.as_deref().unwrap_or_default(),
@@ -135,9 +162,21 @@ impl AuthorQueryParams {
 pub async fn search_author(
     req: Query<AuthorQueryParams>,
     token: Option<Query<AuthData>>,
+    request: HttpRequest,
     pool: Data<MySqlPool>,
-) -> Result<HttpResponse, Box<dyn Error>> {
-    let mut authors = search_author_from_db(&pool, req.0).await?;
+    access: Data<AccessControl>,
+    max_page_size: Data<MaxPageSize>,
+) -> Result<HttpResponse, ApiError> {
+    let page = match Page::new(req.limit, req.offset, **max_page_size) {
+        Ok(page) => page,
+        Err(e) => return Ok(HttpResponse::BadRequest().body(e)),
+    };
+    let total = count_authors_from_db(&pool, &req).await?;
+    let mut authors = if page.offset as u64 >= total {
+        Vec::new()
+    } else {
+        search_author_from_db(&pool, &req, page.limit as u32, page.offset as u32).await?
+    };
 
     debug!("Author descriptors found: {:?}", authors);
 
@@ -145,7 +184,9 @@ pub async fn search_author(
     let client_auth = match token {
         Some(token) => {
             debug!("The client included an API token to access the restricted resources.");
-            check_access(&pool, &token.api_key).await?;
+            if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Read).await {
+                return access_denied_response(e);
+            }
             debug!("Access granted");
             true
         }
@@ -157,7 +198,9 @@ pub async fn search_author(
         authors.iter_mut().for_each(|e| e.mute_private_data());
     }
 
-    Ok(HttpResponse::Ok().json(authors))
+    let mut response = HttpResponse::Ok();
+    page.insert_headers(&mut response, &request, total as usize);
+    Ok(response.json(authors))
 }
 
 /// Retrieve an author descriptor using the author's ID.
@@ -170,10 +213,17 @@ pub async fn search_author(
 /// If the author sets the profile as non-public (_non-shareable_), only clients with an API access token will retrieve
 /// the full author's descriptor. Unauthenticated clients will get the author's name, the personal website, and the
 /// social profiles when that data was given to the system. Authors only are required to provide a valid email.
+///
+/// Responses include the version of the author in an `ETag` header, which clients can use to guard the deletion of the
+/// author (see `DELETE /author/{id}`).
+///
+/// Use `expand=recipes` to embed the newest published recipes of the author (see the schema `ExpandedAuthor`). Up to 10
+/// recipes are included.
 #[utoipa::path(
     get,
     context_path = "/author/",
     tag = "Author",
+    params(("id" = String, Path, description = "ID of the author."), ExpandQuery),
     security(
         ("api_key" = [])
     ),
@@ -181,11 +231,12 @@ pub async fn search_author(
         (
             status = 200,
             description = "The Author descriptor was found using the given ID.",
-            body = Author,
+            body = ExpandedAuthor,
             headers(
                 ("Content-Length"),
                 ("Content-Type"),
                 ("Date"),
+                ("ETag", description = "Version of the author."),
                 ("Vary", description = "Origin,Access-Control-Request-Method,Access-Control-Request-Headers")
             ),
             examples(
@@ -204,6 +255,10 @@ pub async fn search_author(
                 ))
             ),
         ),
+        (
+            status = 400,
+            description = "The given author's ID or the expansions have an invalid format.",
+        ),
         (
             status = 404,
             description = "The given author's ID was not found in the DB.",
@@ -213,6 +268,8 @@ pub async fn search_author(
                 ("Vary", description = "Origin,Access-Control-Request-Method,Access-Control-Request-Headers")
             ),
         ),
+        (status = 401, description = "The given API key is not valid. Clients with no API key shall not include it."),
+        (status = 403, description = "The given API key doesn't grant the `read` scope."),
         (
             status = 429, description = "**Too many requests.**",
             headers(
@@ -220,32 +277,49 @@ pub async fn search_author(
                 ("Access-Control-Allow-Origin"),
                 ("Retry-After", description = "Amount of time between requests (seconds).")
             )
-        )
+        ),
+        (
+            status = 503, description = "**The server is overloaded.** Retrieval of resources is temporarily disabled.",
+            headers(
+                ("Cache-Control", description = "Cache control is set to *no-cache*."),
+                ("Retry-After", description = "Amount of time to wait before retrying (seconds).")
+            )
+        ),
     )
 )]
-#[instrument(skip(token, pool, path), fields(author_id = %path.0))]
+#[instrument(skip(token, pool, access), fields(author_id = %author_id))]
 #[get("{id}")]
 pub async fn get_author(
-    path: Path<(String,)>,
+    author_id: ResourceId,
+    expand: Query<ExpandQuery>,
     token: Option<Query<AuthData>>,
     pool: Data<MySqlPool>,
-) -> Result<HttpResponse, Box<dyn Error>> {
+    access: Data<AccessControl>,
+) -> Result<HttpResponse, ApiError> {
+    let expansions = match expand.expansions(&[Expansion::Recipes]) {
+        Ok(expansions) => expansions,
+        Err(e) => {
+            info!("Invalid expansion: {e}");
+            return Ok(HttpResponse::BadRequest().body(e));
+        }
+    };
+
     // First: does the author exists?
-    let author_id = &path.0;
-    let mut author = match get_author_from_db(&pool, author_id).await {
-        Ok(author) => author,
-        Err(e) => match e.downcast_ref() {
-            Some(DataDomainError::InvalidId) => return Ok(HttpResponse::NotFound().finish()),
-            _ => return Err(e),
-        },
+    let mut author = match get_author_from_db(&pool, author_id.as_uuid()).await? {
+        Some(author) => author,
+        None => return Ok(HttpResponse::NotFound().finish()),
     };
 
     debug!("Author descriptor found: {:?}", author);
+    // The tag identifies the stored version of the author, even when private data is muted.
+    let etag = resource_etag(&author)?;
 
     // Check if the client hash privileges to retrieve the full description of the Author.
-    if token.is_some() {
+    if let Some(token) = token {
         debug!("The client included an API token to access the restricted resources.");
-        check_access(&pool, &token.unwrap().api_key).await?;
+        if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Read).await {
+            return access_denied_response(e);
+        }
         debug!("Access granted");
     } else {
         debug!("The client hash no API token to access the restricted resources. Private data will be muted.");
@@ -254,7 +328,9 @@ pub async fn get_author(
         }
     }
 
-    Ok(HttpResponse::Ok().json(author))
+    let author = expand_author(&pool, author, &expansions).await?;
+
+    Ok(HttpResponse::Ok().insert_header(ETag(etag)).json(author))
 }
 
 #[cfg(test)]
@@ -283,6 +359,8 @@ mod tests {
             name: name.map(String::from),
             surname: surname.map(String::from),
             email: email.map(String::from),
+            limit: None,
+            offset: None,
         };
 
         let token = query_params.search_token();
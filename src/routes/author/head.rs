@@ -6,21 +6,28 @@
 
 //! Author endpoint head method.
 
-use crate::{domain::DataDomainError, routes::author::utils::get_author_from_db};
-use actix_web::{
-    head,
-    web::{Data, Path},
-    HttpResponse,
+use crate::{
+    domain::{ApiError, ResourceId},
+    routes::author::utils::{get_author_from_db, get_author_metadata_from_db},
+    utils::http::{last_modified, X_RECIPE_COUNT},
 };
+use actix_web::{head, web::Data, HttpResponse};
 use sqlx::MySqlPool;
-use std::error::Error;
-use tracing::instrument;
+use tracing::{debug, instrument};
 
 /// Metadata request for an author.
+///
+/// # Description
+///
+/// This singleton resource checks whether an author exists, and includes some metadata in the headers, so clients
+/// can cheaply decide whether to fetch the full resource:
+/// - `X-Recipe-Count`: the amount of recipes owned by the author.
+/// - `Last-Modified`: the latest modification of the author's profile or any of the author's recipes.
 #[utoipa::path(
     head,
     context_path = "/author/",
     tag = "Author",
+    params(("id" = String, Path, description = "ID of the author.")),
     responses(
         (
             status = 200,
@@ -28,10 +35,16 @@ use tracing::instrument;
             headers(
                 ("Content-Length"),
                 ("Content-Type"),
+                ("Last-Modified", description = "Latest modification of the author or the author's recipes."),
+                ("X-Recipe-Count" = i64, description = "Amount of recipes owned by the author."),
                 ("Date"),
                 ("Vary", description = "Origin,Access-Control-Request-Method,Access-Control-Request-Headers")
             )
         ),
+        (
+            status = 400,
+            description = "The given author's ID has an invalid format.",
+        ),
         (
             status = 404,
             description = "The given author's ID was not found in the DB.",
@@ -48,24 +61,36 @@ use tracing::instrument;
                 ("Access-Control-Allow-Origin"),
                 ("Retry-After", description = "Amount of time between requests (seconds)."),
             )
-        )
+        ),
+        (
+            status = 503, description = "**The server is overloaded.** Retrieval of resources is temporarily disabled.",
+            headers(
+                ("Cache-Control", description = "Cache control is set to *no-cache*."),
+                ("Retry-After", description = "Amount of time to wait before retrying (seconds).")
+            )
+        ),
     )
 )]
-#[instrument(skip(pool, path), fields(author_id = %path.0))]
+#[instrument(skip(pool), fields(author_id = %author_id))]
 #[head("{id}")]
 pub async fn head_author(
-    path: Path<(String,)>,
+    author_id: ResourceId,
     pool: Data<MySqlPool>,
-) -> Result<HttpResponse, Box<dyn Error>> {
-    let author_id = &path.0;
+) -> Result<HttpResponse, ApiError> {
     // First: does the author exists?
-    let author = match get_author_from_db(&pool, author_id).await {
-        Ok(author) => author,
-        Err(e) => match e.downcast_ref() {
-            Some(DataDomainError::InvalidId) => return Ok(HttpResponse::NotFound().finish()),
-            _ => return Err(e),
-        },
+    let author = match get_author_from_db(&pool, author_id.as_uuid()).await? {
+        Some(author) => author,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+
+    let metadata = match get_author_metadata_from_db(&pool, author_id.as_uuid()).await? {
+        Some(metadata) => metadata,
+        None => return Ok(HttpResponse::NotFound().finish()),
     };
+    debug!("Author metadata: {metadata:?}");
 
-    Ok(HttpResponse::Ok().json(author))
+    Ok(HttpResponse::Ok()
+        .insert_header(last_modified(metadata.last_modified))
+        .insert_header((X_RECIPE_COUNT, metadata.recipe_count.to_string()))
+        .json(author))
 }
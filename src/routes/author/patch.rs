@@ -7,17 +7,16 @@
 //! Author endpoint PATCH method.
 
 use crate::{
-    authentication::{check_access, AuthData},
-    domain::Author,
+    authentication::{access_denied_response, check_access, AccessControl, AuthData, Scope},
+    domain::{ApiError, Author, AuthorEmailPolicy, IdGenerator, ResourceId},
     routes::author::utils::{get_author_from_db, modify_author_from_db},
 };
 use actix_web::{
     patch,
-    web::{Data, Json, Path, Query},
+    web::{Data, Json, Query},
     HttpResponse,
 };
 use sqlx::MySqlPool;
-use std::error::Error;
 use tracing::{debug, info, instrument};
 
 /// Resource that allows to modify some of the attributes of an existing author in the DB.
@@ -28,11 +27,15 @@ use tracing::{debug, info, instrument};
 /// resource accepts a JSON object, that defines (part of) an author entry of the DB. The bare minimum is to include
 /// the author's ID, and an attribute to modify its content.
 ///
+/// When `social_profiles` is given, the list replaces all the social profiles of the author: missing social networks
+/// are removed, and new ones are added. Use the `/author/{id}/social-profiles` sub-resource to modify a single profile.
+///
 /// This resource requires the API client to provide an API token.
 #[utoipa::path(
     patch,
     context_path = "/author/",
     tag = "Author",
+    params(("id" = String, Path, description = "ID of the author.")),
     security(
         ("api_key" = [])
     ),
@@ -42,29 +45,39 @@ use tracing::{debug, info, instrument};
     ),
     responses(
         (status = 200, description = "The author entry was updated in the DB."),
+        (status = 400, description = "The given author's ID has an invalid format."),
         (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
         (status = 404, description = "An author identified by the given ID didn't exist in the DB."),
+        (status = 409, description = "The new email is registered by another author."),
     )
 )]
-#[instrument(skip(pool, token, path), fields(author_id = %path.0))]
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(pool, ids, emails, token, access), fields(author_id = %author_id))]
 #[patch("{id}")]
 pub async fn patch_author(
-    path: Path<(String,)>,
+    author_id: ResourceId,
     req: Json<Author>,
     pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    ids: Data<dyn IdGenerator>,
+    emails: Data<AuthorEmailPolicy>,
     token: Query<AuthData>,
-) -> Result<HttpResponse, Box<dyn Error>> {
+) -> Result<HttpResponse, ApiError> {
     // Access control
-    check_access(&pool, &token.api_key).await?;
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
     debug!("Access granted");
 
-    let author_id = &path.0;
-
     // First, get the current entry for the author identified by its ID.
-    let mut existing_author = get_author_from_db(&pool, author_id).await?;
-    existing_author.update_from(&req);
+    let mut existing_author = match get_author_from_db(&pool, author_id.as_uuid()).await? {
+        Some(author) => author,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+    existing_author.update_from(&req.into_inner());
     debug!("Author modified: {:#?}", existing_author);
-    modify_author_from_db(&pool, &existing_author).await?;
+    modify_author_from_db(&pool, ids.get_ref(), &existing_author, **emails).await?;
     info!("Author entry {author_id} modified");
 
     Ok(HttpResponse::Ok().finish())
@@ -5,9 +5,9 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::{
-    authentication::{check_access, AuthData},
-    domain::Author,
-    routes::author::utils::register_new_author,
+    authentication::{access_denied_response, check_access, AccessControl, AuthData, Scope},
+    domain::{ApiError, Author, AuthorEmailPolicy, IdGenerator},
+    routes::author::utils::{register_new_author, AuthorRegistration},
 };
 use actix_web::{
     post,
@@ -16,7 +16,6 @@ use actix_web::{
 };
 use serde_json::json;
 use sqlx::MySqlPool;
-use std::error::Error;
 use tracing::{debug, info, instrument};
 
 /// Resource that allows the inclusion of a new recipe's author in the DB.
@@ -31,6 +30,12 @@ use tracing::{debug, info, instrument};
 /// When an author registers without providing a name, a *funny name* will be assigned by the backend logic.
 ///
 /// Authors are identified by an unique ID, thus there's no issue when the same names are registered multiple times.
+/// The ID is assigned by the backend and returned in the response. Payloads including an `id` are rejected with a code
+/// **400**, as clients can't choose the ID of a new author.
+///
+/// Emails are unique, unless the deployment allows duplicated emails. A code **409** is returned when the email is
+/// registered by another author. The response includes the ID of that author only when its profile is shareable, so
+/// the ID of a private profile is never disclosed.
 ///
 /// This resource requires clients of the API to provide an API token.
 #[utoipa::path(
@@ -54,14 +59,22 @@ use tracing::{debug, info, instrument};
             ),
         ),
         (
-            status = 404,
-            description = "The given author's ID was not found in the DB.",
+            status = 400,
+            description = "The payload is not a valid Author descriptor, or it includes an ID.",
             headers(
                 ("Content-Length"),
                 ("Date"),
                 ("Vary", description = "Origin,Access-Control-Request-Method,Access-Control-Request-Headers")
             ),
         ),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (
+            status = 409,
+            description = "The email is registered by another author. The ID of that author is included when its profile is shareable.",
+            content_type = "application/json",
+            example = json!({"id": "0192e8d9-36cf-7ce3-82ef-0a7c9b2deefe"}),
+        ),
         (
             status = 429, description = "**Too many requests.**",
             headers(
@@ -72,25 +85,42 @@ use tracing::{debug, info, instrument};
         )
     )
 )]
-#[instrument(skip(pool, token))]
+#[instrument(skip(pool, ids, emails, token, access))]
 #[post("")]
 pub async fn post_author(
     req: Json<Author>,
     pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    ids: Data<dyn IdGenerator>,
+    emails: Data<AuthorEmailPolicy>,
     token: Query<AuthData>,
-) -> Result<HttpResponse, Box<dyn Error>> {
+) -> Result<HttpResponse, ApiError> {
     // Access control
-    check_access(&pool, &token.api_key).await?;
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
     debug!("Access granted");
 
     // Log the received payload
     debug!("Author entry: {:?}", req);
 
-    // Store the received entry in the DB.
-    let id = register_new_author(&pool, &req).await?;
-    info!("New Author entry registered with id: {id}");
+    if req.id().is_some() {
+        info!("The new author entry includes an ID");
+        return Ok(HttpResponse::BadRequest().body("The ID of an author is assigned by the server"));
+    }
 
-    Ok(HttpResponse::Ok().json(json!({
-        "id": id.to_string()
-    })))
+    // Store the received entry in the DB.
+    let author = req.into_inner();
+    match register_new_author(&pool, ids.get_ref(), &author, **emails).await? {
+        AuthorRegistration::Registered(id) => {
+            info!("New Author entry registered with id: {id}");
+            Ok(HttpResponse::Ok().json(json!({
+                "id": id.to_string()
+            })))
+        }
+        AuthorRegistration::EmailInUse(Some(id)) => Ok(HttpResponse::Conflict().json(json!({
+            "id": id.to_string()
+        }))),
+        AuthorRegistration::EmailInUse(None) => Ok(HttpResponse::Conflict().finish()),
+    }
 }
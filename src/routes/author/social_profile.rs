@@ -0,0 +1,210 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Author's social profiles sub-resource.
+
+use crate::{
+    authentication::{access_denied_response, check_access, AccessControl, AuthData, Scope},
+    domain::{ApiError, IdGenerator, ResourceId, SocialProfile},
+    routes::author::utils::{
+        add_social_profile_to_db, delete_social_profile_from_db, modify_social_profile_from_db,
+        SocialProfileOutcome,
+    },
+};
+use actix_web::{
+    delete, patch, post,
+    web::{Data, Json, Path, Query},
+    HttpResponse,
+};
+use serde::Deserialize;
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+use validator::Validate;
+
+/// Path params of the singleton resources of `/author/{id}/social-profiles`.
+#[derive(Debug, Deserialize)]
+pub struct SocialProfilePath {
+    /// Name of the social network.
+    pub provider: String,
+}
+
+impl From<SocialProfileOutcome> for HttpResponse {
+    fn from(value: SocialProfileOutcome) -> Self {
+        match value {
+            SocialProfileOutcome::Done => HttpResponse::Ok().finish(),
+            SocialProfileOutcome::AuthorNotFound | SocialProfileOutcome::ProfileNotFound => {
+                HttpResponse::NotFound().finish()
+            }
+            SocialProfileOutcome::ProfileExists => HttpResponse::Conflict().finish(),
+            SocialProfileOutcome::UnknownProvider => HttpResponse::BadRequest().finish(),
+        }
+    }
+}
+
+/// Add a social profile to an author.
+///
+/// # Description
+///
+/// Authors are allowed a single profile per social network. Use the PATCH method of
+/// `/author/{id}/social-profiles/{provider}` to modify an existing profile.
+///
+/// The `website` attribute accepts both the full URL of the profile, or the user account alone.
+///
+/// This resource requires the API client to provide an API token.
+#[utoipa::path(
+    post,
+    context_path = "/author/",
+    tag = "Author",
+    security(
+        ("api_key" = [])
+    ),
+    params(("id" = String, Path, description = "ID of the author.")),
+    request_body(
+        content = SocialProfile, description = "The new social profile of the author.",
+        example = json!({"provider_name": "Instagram", "website": "https://instagram.com/janedoe"})
+    ),
+    responses(
+        (status = 200, description = "The social profile was added to the author."),
+        (status = 400, description = "The given author's ID has an invalid format, or the social network is not supported."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (status = 404, description = "An author identified by the given ID didn't exist in the DB."),
+        (status = 409, description = "The author has a profile for the given social network already."),
+    )
+)]
+#[instrument(skip(pool, ids, token, access), fields(author_id = %author_id))]
+#[post("{id}/social-profiles")]
+pub async fn post_social_profile(
+    author_id: ResourceId,
+    req: Json<SocialProfile>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    ids: Data<dyn IdGenerator>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    if req.validate().is_err() {
+        info!("The given social profile is invalid");
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    let outcome = add_social_profile_to_db(&pool, ids.get_ref(), author_id.as_uuid(), &req).await?;
+    debug!("Outcome: {outcome:?}");
+
+    Ok(outcome.into())
+}
+
+/// Modify a social profile of an author.
+///
+/// # Description
+///
+/// This singleton resource replaces the user account of the author's profile for the social network given in the
+/// path. The `provider_name` attribute of the request body must match the social network of the path.
+///
+/// This resource requires the API client to provide an API token.
+#[utoipa::path(
+    patch,
+    context_path = "/author/",
+    tag = "Author",
+    security(
+        ("api_key" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "ID of the author."),
+        ("provider" = String, Path, description = "Name of the social network."),
+    ),
+    request_body(
+        content = SocialProfile, description = "The modified social profile of the author.",
+        example = json!({"provider_name": "Instagram", "website": "https://instagram.com/janedoe"})
+    ),
+    responses(
+        (status = 200, description = "The social profile was modified."),
+        (status = 400, description = "The given author's ID has an invalid format, or the request body is invalid."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (status = 404, description = "The author or the author's profile for the social network didn't exist in the DB."),
+    )
+)]
+#[instrument(skip(pool, ids, token, access), fields(author_id = %author_id))]
+#[patch("{id}/social-profiles/{provider}")]
+pub async fn patch_social_profile(
+    author_id: ResourceId,
+    path: Path<SocialProfilePath>,
+    req: Json<SocialProfile>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    ids: Data<dyn IdGenerator>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    if req.validate().is_err() || req.provider_name != path.provider {
+        info!("The given social profile is invalid");
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    let outcome =
+        modify_social_profile_from_db(&pool, ids.get_ref(), author_id.as_uuid(), &req).await?;
+    debug!("Outcome: {outcome:?}");
+
+    Ok(outcome.into())
+}
+
+/// Delete a social profile of an author.
+///
+/// # Description
+///
+/// This singleton resource deletes the author's profile for the social network given in the path.
+///
+/// This resource requires the API client to provide an API token.
+#[utoipa::path(
+    delete,
+    context_path = "/author/",
+    tag = "Author",
+    security(
+        ("api_key" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "ID of the author."),
+        ("provider" = String, Path, description = "Name of the social network."),
+    ),
+    responses(
+        (status = 200, description = "The social profile was deleted."),
+        (status = 400, description = "The given author's ID has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (status = 404, description = "The author or the author's profile for the social network didn't exist in the DB."),
+    )
+)]
+#[instrument(skip(pool, token, access), fields(author_id = %author_id))]
+#[delete("{id}/social-profiles/{provider}")]
+pub async fn delete_social_profile(
+    author_id: ResourceId,
+    path: Path<SocialProfilePath>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let outcome = delete_social_profile_from_db(&pool, author_id.as_uuid(), &path.provider).await?;
+    debug!("Outcome: {outcome:?}");
+
+    Ok(outcome.into())
+}
@@ -5,17 +5,47 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::{
-    domain::{Author, DataDomainError, ServerError, SocialProfile},
-    routes::author::get::AuthorQueryParams,
+    domain::{
+        ApiError, Author, AuthorEmailPolicy, DataDomainError, IdGenerator, ServerError,
+        SocialProfile,
+    },
+    routes::{
+        author::{delete::OwnedRecipe, get::AuthorQueryParams},
+        recipe::utils::disambiguate_slugs,
+    },
+    utils::{
+        changes::{touch_collection, Collection},
+        events::{record_event, DomainEvent},
+    },
 };
 use names::Generator;
-use sqlx::{Executor, MySqlPool, Row};
-use std::error::Error;
-use tracing::{debug, error, instrument};
+use sqlx::{Executor, MySql, MySqlPool, Row, Transaction};
+use tracing::{debug, error, info, instrument};
 use uuid::Uuid;
 
-#[instrument(skip(pool))]
-pub async fn register_new_author(pool: &MySqlPool, author: &Author) -> Result<Uuid, ServerError> {
+/// Result of the registration of a new author.
+#[derive(Debug, PartialEq)]
+pub enum AuthorRegistration {
+    /// The author entry was inserted in the DB using the given ID.
+    Registered(Uuid),
+    /// The email is registered by another author. The ID of that author is only given when its profile is shareable.
+    EmailInUse(Option<Uuid>),
+}
+
+/// Insert a new author entry in the DB.
+///
+/// # Description
+///
+/// The ID of the new author is generated by the backend. Unless the deployment allows duplicated emails (see
+/// [AuthorEmailPolicy]), the email of the author is checked against the unique index of the DB, so two clients
+/// registering the same email at the same time can't both succeed.
+#[instrument(skip(pool, ids))]
+pub async fn register_new_author(
+    pool: &MySqlPool,
+    ids: &dyn IdGenerator,
+    author: &Author,
+    emails: AuthorEmailPolicy,
+) -> Result<AuthorRegistration, ServerError> {
     // Compose a funny name in case the `Author` has no name.
     let funny_name: Vec<String> = Generator::default()
         .next()
@@ -24,11 +54,8 @@ pub async fn register_new_author(pool: &MySqlPool, author: &Author) -> Result<Uu
         .map(String::from)
         .collect();
 
-    // Values for fields that are optional.
-    let id = match author.id() {
-        Some(id) => id,
-        None => Uuid::now_v7().to_string(),
-    };
+    // IDs are never taken from the given author, so an existing ID can't be squatted.
+    let id = ids.new_id().to_string();
 
     let name = match author.name() {
         Some(name) => name,
@@ -46,21 +73,38 @@ pub async fn register_new_author(pool: &MySqlPool, author: &Author) -> Result<Uu
         ServerError::DbError
     })?;
 
-    let query = sqlx::query!(
-        "INSERT INTO Author VALUES (?, ?, ?, ?, ?, ?, ?);",
-        id,
-        name,
-        surname,
-        author.email(),
-        author.shareable(),
-        author.description(),
-        author.website(),
-    );
-
-    transaction.execute(query).await.map_err(|e| {
-        error!("{e}");
-        ServerError::DbError
-    })?;
+    let query = sqlx::query(
+        r#"
+        INSERT INTO Author (id, name, surname, email, email_key, shareable, description, website)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?);
+        "#,
+    )
+    .bind(&id)
+    .bind(name)
+    .bind(surname)
+    .bind(author.email())
+    .bind(author.email().filter(|_| emails.unique_emails()))
+    .bind(author.shareable())
+    .bind(author.description())
+    .bind(author.website());
+
+    match transaction.execute(query).await {
+        Ok(_) => (),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            info!("The email of the new author is already registered");
+            transaction.rollback().await.map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+            return Ok(AuthorRegistration::EmailInUse(
+                shareable_author_by_email(pool, author.email().unwrap_or_default()).await?,
+            ));
+        }
+        Err(e) => {
+            error!("{e}");
+            return Err(ServerError::DbError);
+        }
+    }
 
     // If the author hash any social profile, add the entry in the DB.
     if author.social_profiles().is_some() {
@@ -71,7 +115,7 @@ pub async fn register_new_author(pool: &MySqlPool, author: &Author) -> Result<Uu
             transaction
                 .execute(sqlx::query!(
                     "INSERT INTO AuthorHashSocialProfile (id, provider_name, user_name, author_id) VALUES (?,?,?,?);",
-                    Uuid::now_v7().to_string(),
+                    ids.new_id().to_string(),
                     social_profile.provider_name,
                     user_account,
                     id,
@@ -89,22 +133,51 @@ pub async fn register_new_author(pool: &MySqlPool, author: &Author) -> Result<Uu
         ServerError::DbError
     })?;
 
-    Ok(Uuid::parse_str(&id).unwrap())
+    Ok(AuthorRegistration::Registered(
+        Uuid::parse_str(&id).unwrap(),
+    ))
+}
+
+/// Get the ID of the author that registered the given email, only when its profile is shareable.
+async fn shareable_author_by_email(
+    pool: &MySqlPool,
+    email: &str,
+) -> Result<Option<Uuid>, ServerError> {
+    let id: Option<String> = sqlx::query_scalar(
+        "SELECT id FROM Author WHERE email_key = ? AND deleted_at IS NULL AND COALESCE(shareable, 1) <> 0",
+    )
+    .bind(email)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(id.and_then(|id| Uuid::parse_str(&id).ok()))
 }
 
+/// Retrieve an author from the DB.
+///
+/// # Description
+///
+/// `None` is returned when no author has the given ID, or when the author was merged into another profile. Callers
+/// shall answer with a code 404 in such case, as the ID is well formed but unknown.
 #[instrument(skip(pool))]
 pub async fn get_author_from_db(
     pool: &MySqlPool,
-    author_id: &str,
-) -> Result<Author, Box<dyn Error>> {
-    let record = sqlx::query!(
+    author_id: &Uuid,
+) -> Result<Option<Author>, ApiError> {
+    let author_id = author_id.to_string();
+    // Authors merged into another profile are soft-deleted, and shall not be visible.
+    let record = sqlx::query(
         r#"
-            SELECT id, name, surname, email, shareable, description, website
-            FROM Author
-            WHERE id = ?;
-            "#,
-        author_id
+        SELECT id, name, surname, email, shareable, description, website
+        FROM Author
+        WHERE id = ? AND deleted_at IS NULL;
+        "#,
     )
+    .bind(&author_id)
     .fetch_optional(pool)
     .await
     .map_err(|e| {
@@ -112,44 +185,88 @@ pub async fn get_author_from_db(
         ServerError::DbError
     })?;
 
-    let social_profiles = if record.is_some() {
-        Some(author_social_profiles(pool, author_id).await?)
-    } else {
-        None
+    let Some(author) = record else {
+        info!("No author was found with the ID: {author_id}");
+        return Ok(None);
     };
 
-    let author = if let Some(author) = record {
-        Author::new(
-            Some(author.id),
-            Some(author.name),
-            Some(author.surname),
-            Some(author.email),
-            match author.shareable {
-                Some(0) => Some(false),
-                _ => Some(true),
-            },
-            author.description,
-            author.website,
-            social_profiles.as_deref(),
-        )
-    } else {
-        Err(DataDomainError::InvalidId)
-    };
+    let social_profiles = author_social_profiles(pool, &author_id).await?;
+
+    // Invalid data stored in the DB is an error of the server, not of the client.
+    let author = Author::new(
+        author.try_get("id").ok(),
+        author.try_get("name").ok(),
+        author.try_get("surname").ok(),
+        author.try_get("email").ok(),
+        match author.try_get::<Option<i8>, _>("shareable") {
+            Ok(Some(0)) => Some(false),
+            _ => Some(true),
+        },
+        author.try_get("description").unwrap_or_default(),
+        author.try_get("website").unwrap_or_default(),
+        Some(social_profiles.as_slice()),
+    )
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
 
-    match author {
-        Ok(author) => Ok(author),
-        Err(e) => {
-            error!("{e}");
-            Err(Box::new(e))
-        }
-    }
+    Ok(Some(author))
 }
 
+/// Metadata of an author entry, meant to be returned as headers of a HEAD request.
+#[derive(Debug)]
+pub struct AuthorMetadata {
+    /// Amount of recipes owned by the author.
+    pub recipe_count: i64,
+    /// UNIX timestamp of the latest modification of the author's profile or any of the author's recipes.
+    pub last_modified: i64,
+}
+
+/// Retrieve the [AuthorMetadata] of an author. `None` is returned when the author doesn't exist.
+#[instrument(skip(pool))]
+pub async fn get_author_metadata_from_db(
+    pool: &MySqlPool,
+    author_id: &Uuid,
+) -> Result<Option<AuthorMetadata>, ServerError> {
+    let record = sqlx::query(
+        r#"
+        SELECT
+            COUNT(c.id) AS recipe_count,
+            CAST(UNIX_TIMESTAMP(GREATEST(a.update_date, COALESCE(MAX(c.update_date), a.update_date))) AS SIGNED)
+                AS last_modified
+        FROM Author a LEFT JOIN Cocktail c ON c.owner = a.id
+        WHERE a.id = ? AND a.deleted_at IS NULL
+        GROUP BY a.id, a.update_date
+        "#,
+    )
+    .bind(author_id.to_string())
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(record.map(|row| AuthorMetadata {
+        recipe_count: row.try_get("recipe_count").unwrap_or_default(),
+        last_modified: row.try_get("last_modified").unwrap_or_default(),
+    }))
+}
+
+/// Search the authors that match the token of highest priority of the query (see [AuthorQueryParams::search_token]).
+///
+/// # Description
+///
+/// Authors are sorted by ID, so the pages of the results are stable. Up to `limit` authors are listed after skipping
+/// `offset` of them.
 #[instrument(skip(pool))]
 pub async fn search_author_from_db(
     pool: &MySqlPool,
-    search_string: AuthorQueryParams,
-) -> Result<Vec<Author>, Box<dyn Error>> {
+    search_string: &AuthorQueryParams,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<Author>, ApiError> {
     let mut found_authors = Vec::new();
 
     // Obtain the highest priority token for the search.
@@ -159,13 +276,16 @@ pub async fn search_author_from_db(
         r#"
     SELECT id, name, surname, email, shareable, description, website
     FROM Author
-    WHERE {query} = ?"#
+    WHERE {query} = ? AND deleted_at IS NULL
+    ORDER BY id LIMIT ? OFFSET ?"#
     );
 
     debug!("Searching author using: {value}");
 
     let query_result = sqlx::query(&query)
         .bind(value)
+        .bind(limit)
+        .bind(offset)
         .fetch_all(pool)
         .await
         .map_err(|e| {
@@ -195,7 +315,7 @@ pub async fn search_author_from_db(
             Ok(author) => author,
             Err(e) => {
                 error!("{e}");
-                return Err(Box::new(ServerError::DbError));
+                return Err(ServerError::DbError.into());
             }
         };
 
@@ -205,11 +325,34 @@ pub async fn search_author_from_db(
     Ok(found_authors)
 }
 
+/// Count the authors listed by [search_author_from_db], regardless of the page.
 #[instrument(skip(pool))]
+pub async fn count_authors_from_db(
+    pool: &MySqlPool,
+    search_string: &AuthorQueryParams,
+) -> Result<u64, ApiError> {
+    let (query, value) = search_string.search_token()?;
+    let query = format!("SELECT COUNT(*) FROM Author WHERE {query} = ? AND deleted_at IS NULL");
+
+    let total: i64 = sqlx::query_scalar(&query)
+        .bind(value)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    Ok(total as u64)
+}
+
+#[instrument(skip(pool, ids))]
 pub async fn modify_author_from_db(
     pool: &MySqlPool,
+    ids: &dyn IdGenerator,
     author: &Author,
-) -> Result<(), Box<dyn Error>> {
+    emails: AuthorEmailPolicy,
+) -> Result<(), ApiError> {
     let query = sqlx::query!(
         r#"UPDATE Author
         SET name = ?, surname = ?, email = ?, shareable = ?, description = ?, website = ?
@@ -229,30 +372,71 @@ pub async fn modify_author_from_db(
         ServerError::DbError
     })?;
 
+    // A new email is checked against the unique index, the rest of the emails are left as they are.
+    if emails.unique_emails() {
+        let claim = sqlx::query(
+            "UPDATE Author SET email_key = ? WHERE id = ? AND NOT (email <=> ?) AND deleted_at IS NULL",
+        )
+        .bind(author.email())
+        .bind(author.id())
+        .bind(author.email());
+
+        match transaction.execute(claim).await {
+            Ok(_) => (),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                info!("The new email of the author is already registered");
+                return Err(DataDomainError::EmailAlreadyRegistered.into());
+            }
+            Err(e) => {
+                error!("{e}");
+                return Err(ServerError::DbError.into());
+            }
+        }
+    }
+
     transaction.execute(query).await.map_err(|e| {
         error!("{e}");
         ServerError::DbError
     })?;
 
-    if author.social_profiles().is_some() {
-        for social_profile in author.social_profiles().unwrap() {
-            // Let's try to extract only the user name. If the full URL is given, get the latest breadcrumb.
-            let user_account = extract_profile_account(&social_profile.website);
+    // The given social profiles replace the existing ones.
+    if let Some(social_profiles) = author.social_profiles() {
+        let author_id = author.id().unwrap_or_default();
 
-            transaction
-                .execute(sqlx::query!(
-                    r#"UPDATE AuthorHashSocialProfile
-                SET user_name = ?
-                WHERE provider_name = ? AND author_id = ?"#,
-                    user_account,
-                    social_profile.provider_name,
-                    author.id(),
-                ))
+        let existing_providers =
+            sqlx::query("SELECT provider_name FROM AuthorHashSocialProfile WHERE author_id = ?")
+                .bind(&author_id)
+                .fetch_all(&mut *transaction)
                 .await
                 .map_err(|e| {
                     error!("{e}");
                     ServerError::DbError
-                })?;
+                })?
+                .iter()
+                .map(|row| row.try_get("provider_name").unwrap_or_default())
+                .collect::<Vec<String>>();
+
+        for provider in existing_providers.iter().filter(|provider| {
+            !social_profiles
+                .iter()
+                .any(|profile| &profile.provider_name == *provider)
+        }) {
+            debug!("Removing the profile of {provider}");
+            sqlx::query(
+                "DELETE FROM AuthorHashSocialProfile WHERE author_id = ? AND provider_name = ?",
+            )
+            .bind(&author_id)
+            .bind(provider)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+        }
+
+        for social_profile in social_profiles {
+            upsert_social_profile(&mut transaction, ids, &author_id, social_profile).await?;
         }
     }
 
@@ -264,16 +448,236 @@ pub async fn modify_author_from_db(
     Ok(())
 }
 
-#[instrument(skip(pool, author_id))]
-pub async fn delete_author_from_db(pool: &MySqlPool, author_id: &Uuid) -> Result<(), ServerError> {
-    sqlx::query!(
+/// Policy applied to the recipes owned by an author that is about to be deleted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OwnedRecipesPolicy {
+    /// Abort the deletion when the author owns some recipe.
+    Reject,
+    /// Transfer the ownership of the recipes to the author identified by the given ID.
+    Reassign(Uuid),
+    /// Delete the recipes along with the author.
+    Cascade,
+}
+
+/// Outcome of [delete_author_from_db].
+#[derive(Debug)]
+pub enum AuthorDeletion {
+    /// The author entry was deleted from the DB.
+    Deleted,
+    /// No author entry matched the given ID.
+    AuthorNotFound,
+    /// The author to whom the recipes should be transferred doesn't exist.
+    TargetNotFound,
+    /// The author owns some recipes, and [OwnedRecipesPolicy::Reject] was requested.
+    OwnsRecipes(Vec<OwnedRecipe>),
+}
+
+/// Delete an author entry from the DB.
+///
+/// # Description
+///
+/// The recipes owned by the author are handled following the given [OwnedRecipesPolicy]. All the changes are
+/// applied within a single transaction, so either the author and its recipes are fully processed, or nothing is
+/// modified in the DB.
+#[instrument(skip(pool))]
+pub async fn delete_author_from_db(
+    pool: &MySqlPool,
+    author_id: &Uuid,
+    policy: OwnedRecipesPolicy,
+) -> Result<AuthorDeletion, ServerError> {
+    let author_id = author_id.to_string();
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let author =
+        sqlx::query("SELECT id FROM Author WHERE id = ? AND deleted_at IS NULL FOR UPDATE")
+            .bind(&author_id)
+            .fetch_optional(&mut *transaction)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+
+    if author.is_none() {
+        debug!("No author entry matched the given ID");
+        return Ok(AuthorDeletion::AuthorNotFound);
+    }
+
+    let owned_recipes = sqlx::query("SELECT id, name FROM Cocktail WHERE owner = ? FOR UPDATE")
+        .bind(&author_id)
+        .fetch_all(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?
+        .iter()
+        .map(|row| OwnedRecipe {
+            id: row.try_get("id").unwrap_or_default(),
+            name: row.try_get("name").unwrap_or_default(),
+        })
+        .collect::<Vec<OwnedRecipe>>();
+
+    if !owned_recipes.is_empty() {
+        debug!("The author owns {} recipes", owned_recipes.len());
+
+        match policy {
+            OwnedRecipesPolicy::Reject => return Ok(AuthorDeletion::OwnsRecipes(owned_recipes)),
+            OwnedRecipesPolicy::Reassign(target_id) => {
+                let target_id = target_id.to_string();
+                let target = sqlx::query(
+                    "SELECT id FROM Author WHERE id = ? AND deleted_at IS NULL FOR UPDATE",
+                )
+                .bind(&target_id)
+                .fetch_optional(&mut *transaction)
+                .await
+                .map_err(|e| {
+                    error!("{e}");
+                    ServerError::DbError
+                })?;
+
+                if target.is_none() {
+                    debug!("The target author {target_id} doesn't exist");
+                    return Ok(AuthorDeletion::TargetNotFound);
+                }
+
+                let moved_recipes = owned_recipes
+                    .iter()
+                    .map(|recipe| recipe.id.clone())
+                    .collect::<Vec<String>>();
+                disambiguate_slugs(&mut transaction, &moved_recipes, &target_id).await?;
+                sqlx::query("UPDATE Cocktail SET owner = ? WHERE owner = ?")
+                    .bind(&target_id)
+                    .bind(&author_id)
+                    .execute(&mut *transaction)
+                    .await
+                    .map_err(|e| {
+                        error!("{e}");
+                        ServerError::DbError
+                    })?;
+                debug!("Recipes transferred to the author {target_id}");
+
+                let owner = Uuid::parse_str(&target_id).map_err(|e| {
+                    error!("{e}");
+                    ServerError::DbError
+                })?;
+                for recipe_id in recipe_ids(&owned_recipes)? {
+                    record_event(
+                        &mut *transaction,
+                        &DomainEvent::RecipeOwnerChanged { recipe_id, owner },
+                    )
+                    .await?;
+                }
+            }
+            OwnedRecipesPolicy::Cascade => {
+                // Tagged has no cascade rule, so its entries need to be removed before the recipes.
+                sqlx::query(
+                    "DELETE FROM Tagged WHERE cocktail_id IN (SELECT id FROM Cocktail WHERE owner = ?)",
+                )
+                .bind(&author_id)
+                .execute(&mut *transaction)
+                .await
+                .map_err(|e| {
+                    error!("{e}");
+                    ServerError::DbError
+                })?;
+
+                sqlx::query("DELETE FROM Cocktail WHERE owner = ?")
+                    .bind(&author_id)
+                    .execute(&mut *transaction)
+                    .await
+                    .map_err(|e| {
+                        error!("{e}");
+                        ServerError::DbError
+                    })?;
+                debug!("Recipes owned by the author deleted");
+
+                for recipe_id in recipe_ids(&owned_recipes)? {
+                    record_event(&mut *transaction, &DomainEvent::RecipeDeleted { recipe_id })
+                        .await?;
+                }
+            }
+        }
+
+        touch_collection(&mut *transaction, Collection::Recipe).await?;
+    }
+
+    sqlx::query("DELETE FROM Author WHERE id = ?")
+        .bind(&author_id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(AuthorDeletion::Deleted)
+}
+
+/// Parse the IDs of the recipes owned by an author.
+fn recipe_ids(recipes: &[OwnedRecipe]) -> Result<Vec<Uuid>, ServerError> {
+    recipes
+        .iter()
+        .map(|recipe| {
+            Uuid::parse_str(&recipe.id).map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })
+        })
+        .collect()
+}
+
+/// Outcome of the operations over a single social profile of an author.
+#[derive(Debug, PartialEq)]
+pub enum SocialProfileOutcome {
+    /// The operation was applied to the DB.
+    Done,
+    /// No author entry matched the given ID.
+    AuthorNotFound,
+    /// The author has no profile for the given social network.
+    ProfileNotFound,
+    /// The author has a profile for the given social network already.
+    ProfileExists,
+    /// The given social network is not supported.
+    UnknownProvider,
+}
+
+/// Insert or update the profile of an author for a social network.
+///
+/// # Description
+///
+/// Authors are allowed a single profile per social network. If the author has a profile for the social network of
+/// the given [SocialProfile], the user account is updated. Otherwise, a new entry is added to the DB.
+async fn upsert_social_profile(
+    transaction: &mut Transaction<'_, MySql>,
+    ids: &dyn IdGenerator,
+    author_id: &str,
+    social_profile: &SocialProfile,
+) -> Result<(), ServerError> {
+    // Let's try to extract only the user name. If the full URL is given, get the latest breadcrumb.
+    let user_account = extract_profile_account(&social_profile.website);
+
+    sqlx::query(
         r#"
-        DELETE FROM Author
-        WHERE id = ?
+        INSERT INTO AuthorHashSocialProfile (id, provider_name, user_name, author_id)
+        VALUES (?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE user_name = VALUES(user_name)
         "#,
-        author_id.to_string()
     )
-    .execute(pool)
+    .bind(ids.new_id().to_string())
+    .bind(&social_profile.provider_name)
+    .bind(user_account)
+    .bind(author_id)
+    .execute(&mut **transaction)
     .await
     .map_err(|e| {
         error!("{e}");
@@ -283,6 +687,164 @@ pub async fn delete_author_from_db(pool: &MySqlPool, author_id: &Uuid) -> Result
     Ok(())
 }
 
+/// Check whether an author exists, and whether it has a profile for the given social network.
+///
+/// # Description
+///
+/// Returns `None` when the author doesn't exist, and `Some(exists)` otherwise.
+async fn social_profile_exists(
+    transaction: &mut Transaction<'_, MySql>,
+    author_id: &str,
+    provider_name: &str,
+) -> Result<Option<bool>, ServerError> {
+    let author =
+        sqlx::query("SELECT id FROM Author WHERE id = ? AND deleted_at IS NULL FOR UPDATE")
+            .bind(author_id)
+            .fetch_optional(&mut **transaction)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+
+    if author.is_none() {
+        return Ok(None);
+    }
+
+    let profile = sqlx::query(
+        "SELECT id FROM AuthorHashSocialProfile WHERE author_id = ? AND provider_name = ? FOR UPDATE",
+    )
+    .bind(author_id)
+    .bind(provider_name)
+    .fetch_optional(&mut **transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(Some(profile.is_some()))
+}
+
+/// Add a new social profile to an author.
+#[instrument(skip(pool, ids))]
+pub async fn add_social_profile_to_db(
+    pool: &MySqlPool,
+    ids: &dyn IdGenerator,
+    author_id: &Uuid,
+    social_profile: &SocialProfile,
+) -> Result<SocialProfileOutcome, ServerError> {
+    let author_id = author_id.to_string();
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    match social_profile_exists(&mut transaction, &author_id, &social_profile.provider_name).await?
+    {
+        None => return Ok(SocialProfileOutcome::AuthorNotFound),
+        Some(true) => return Ok(SocialProfileOutcome::ProfileExists),
+        Some(false) => (),
+    }
+
+    let provider = sqlx::query("SELECT provider_name FROM SocialProfile WHERE provider_name = ?")
+        .bind(&social_profile.provider_name)
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    if provider.is_none() {
+        debug!(
+            "Unsupported social network: {}",
+            social_profile.provider_name
+        );
+        return Ok(SocialProfileOutcome::UnknownProvider);
+    }
+
+    upsert_social_profile(&mut transaction, ids, &author_id, social_profile).await?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(SocialProfileOutcome::Done)
+}
+
+/// Modify the user account of an existing social profile of an author.
+#[instrument(skip(pool, ids))]
+pub async fn modify_social_profile_from_db(
+    pool: &MySqlPool,
+    ids: &dyn IdGenerator,
+    author_id: &Uuid,
+    social_profile: &SocialProfile,
+) -> Result<SocialProfileOutcome, ServerError> {
+    let author_id = author_id.to_string();
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    match social_profile_exists(&mut transaction, &author_id, &social_profile.provider_name).await?
+    {
+        None => return Ok(SocialProfileOutcome::AuthorNotFound),
+        Some(false) => return Ok(SocialProfileOutcome::ProfileNotFound),
+        Some(true) => (),
+    }
+
+    upsert_social_profile(&mut transaction, ids, &author_id, social_profile).await?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(SocialProfileOutcome::Done)
+}
+
+/// Delete a social profile of an author.
+#[instrument(skip(pool))]
+pub async fn delete_social_profile_from_db(
+    pool: &MySqlPool,
+    author_id: &Uuid,
+    provider_name: &str,
+) -> Result<SocialProfileOutcome, ServerError> {
+    let author_id = author_id.to_string();
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    match social_profile_exists(&mut transaction, &author_id, provider_name).await? {
+        None => return Ok(SocialProfileOutcome::AuthorNotFound),
+        Some(false) => return Ok(SocialProfileOutcome::ProfileNotFound),
+        Some(true) => (),
+    }
+
+    sqlx::query("DELETE FROM AuthorHashSocialProfile WHERE author_id = ? AND provider_name = ?")
+        .bind(&author_id)
+        .bind(provider_name)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(SocialProfileOutcome::Done)
+}
+
 #[instrument(skip(pool))]
 async fn author_social_profiles(
     pool: &MySqlPool,
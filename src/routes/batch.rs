@@ -0,0 +1,105 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Common objects for the batch operations over the collection resources.
+
+use crate::domain::ResourceId;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Maximum amount of IDs accepted by a single batch request.
+pub const MAX_BATCH_SIZE: usize = 100;
+
+/// Request body of the batch-delete resources.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct BatchDelete {
+    /// IDs of the entries to delete.
+    #[schema(example = json!(["0191e13b-5ab7-78f1-bc06-be503a6c111b"]))]
+    pub ids: Vec<String>,
+    /// When given, only the entries owned by this author are deleted. Only applies to resources that have an owner.
+    #[schema(value_type = Option<String>, example = "0192e8d9-36cf-7ce3-82ef-0a7c9b2deefe")]
+    pub owner: Option<ResourceId>,
+}
+
+impl BatchDelete {
+    /// Check whether the amount of IDs is within the accepted range: `[1, MAX_BATCH_SIZE]`.
+    pub fn is_valid_size(&self) -> bool {
+        !self.ids.is_empty() && self.ids.len() <= MAX_BATCH_SIZE
+    }
+
+    /// Parse the given IDs following the rules of [ResourceId]. `None` is set for malformed IDs.
+    pub fn parsed_ids(&self) -> Vec<(&str, Option<Uuid>)> {
+        self.ids
+            .iter()
+            .map(|id| {
+                (
+                    id.as_str(),
+                    ResourceId::try_from(id.as_str()).ok().map(Uuid::from),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Outcome of a batch operation for a single ID.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchOutcome {
+    /// The entry was deleted.
+    Deleted,
+    /// No entry matched the ID.
+    NotFound,
+    /// The ID has an invalid format.
+    InvalidId,
+    /// The entry is not owned by the given author.
+    NotOwned,
+    /// The entry is referenced by other entries of the DB, thus it can't be deleted.
+    Referenced,
+}
+
+/// Result of a batch operation for a single ID.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema, PartialEq)]
+pub struct BatchItemResult {
+    pub id: String,
+    pub outcome: BatchOutcome,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn batch_delete_validation() {
+        let valid_id = Uuid::now_v7();
+        let batch = BatchDelete {
+            ids: vec![valid_id.to_string(), "1234".into()],
+            owner: None,
+        };
+        assert!(batch.is_valid_size());
+        assert_eq!(
+            batch.parsed_ids(),
+            vec![
+                (valid_id.to_string().as_str(), Some(valid_id)),
+                ("1234", None)
+            ]
+        );
+
+        let batch = BatchDelete {
+            ids: Vec::new(),
+            owner: None,
+        };
+        assert!(!batch.is_valid_size());
+
+        let batch = BatchDelete {
+            ids: vec![valid_id.to_string(); MAX_BATCH_SIZE + 1],
+            owner: None,
+        };
+        assert!(!batch.is_valid_size());
+    }
+}
@@ -0,0 +1,296 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! OpenAPI document of the API.
+//!
+//! # Description
+//!
+//! The document is large, and the tooling of the docs fetches it frequently. Thus it is serialized once when the
+//! application starts, and served with an `ETag` derived from the version of the crate, so clients can revalidate
+//! their copy using `If-None-Match` rather than downloading it again.
+//!
+//! New handlers are easily registered in the application without being listed in the document. The application tracks
+//! the handlers it registers (see [RegisteredHandlers]), audits the document when it starts (see [audit_openapi]),
+//! and logs the gaps.
+
+use crate::utils::http::is_etag_not_modified;
+use actix_web::{
+    dev::HttpServiceFactory,
+    get,
+    http::header::{CacheControl, CacheDirective, ContentType, ETag, EntityTag},
+    web::{Bytes, Data},
+    HttpRequest, HttpResponse,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    any::type_name,
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+};
+use tracing::{instrument, warn};
+use utoipa::openapi::OpenApi;
+
+/// Path of the OpenAPI document, relative to the URL of the API.
+pub const OPENAPI_PATH: &str = "api-docs/openapi.json";
+
+/// Amount of hexadecimal digits of the hash of the document included in its entity tag.
+const HASH_LENGTH: usize = 8;
+
+/// Handlers registered by the application that are intentionally left out of the document.
+const UNDOCUMENTED_HANDLERS: [&str; 5] = [
+    // Preflight requests of CORS.
+    "options_echo",
+    "options_health",
+    // Files of the web pages, rather than resources of the API.
+    "get_static_asset",
+    "get_static_listing",
+    // The document itself.
+    "get_openapi",
+];
+
+/// Differences between the handlers registered by the application and the operations of the OpenAPI document.
+#[derive(Debug, Default, PartialEq)]
+pub struct OpenApiAudit {
+    /// Handlers registered by the application that are missing in the document.
+    pub undocumented: Vec<String>,
+    /// Operations of the document whose handler is not registered by the application.
+    pub unregistered: Vec<String>,
+}
+
+impl OpenApiAudit {
+    pub fn is_clean(&self) -> bool {
+        self.undocumented.is_empty() && self.unregistered.is_empty()
+    }
+}
+
+/// Names of the handlers registered by the application.
+///
+/// # Description
+///
+/// actix-web offers no way to list the routes of an application once it is built, so the application passes its
+/// handlers through [RegisteredHandlers::track] when registering them. The name of a handler is the name of the type
+/// built by the route macros of actix-web, i.e. `get_recipe` for `#[get("/{id}")] async fn get_recipe(...)`.
+///
+/// Handlers are tracked by every worker when it builds its application, the list is shared by all of them.
+#[derive(Debug, Clone, Default)]
+pub struct RegisteredHandlers(Arc<Mutex<BTreeSet<String>>>);
+
+impl RegisteredHandlers {
+    /// Record the name of a handler, and give it back to be registered.
+    pub fn track<T: HttpServiceFactory>(&self, handler: T) -> T {
+        let name = type_name::<T>().rsplit("::").next().unwrap_or_default();
+        self.0.lock().unwrap().insert(name.to_owned());
+
+        handler
+    }
+
+    /// Names of the tracked handlers, except the ones listed in [UNDOCUMENTED_HANDLERS].
+    pub fn names(&self) -> BTreeSet<String> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|handler| !UNDOCUMENTED_HANDLERS.contains(&handler.as_str()))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Compare the handlers registered by the application to the operations of an OpenAPI document.
+///
+/// # Description
+///
+/// Handlers are matched to the operations using their ID, which is the name of the handler unless `operation_id` is
+/// given to `utoipa::path`. The handlers listed in [UNDOCUMENTED_HANDLERS] are ignored.
+pub fn audit_openapi(handlers: &RegisteredHandlers, doc: &OpenApi) -> OpenApiAudit {
+    let handlers = handlers.names();
+    let operations = doc
+        .paths
+        .paths
+        .values()
+        .flat_map(|item| item.operations.values())
+        .filter_map(|operation| operation.operation_id.clone())
+        .collect::<BTreeSet<String>>();
+
+    OpenApiAudit {
+        undocumented: handlers.difference(&operations).cloned().collect(),
+        unregistered: operations.difference(&handlers).cloned().collect(),
+    }
+}
+
+/// Log the gaps between the handlers registered by the application and the OpenAPI document.
+pub fn log_openapi_gaps(handlers: &RegisteredHandlers, doc: &OpenApi) {
+    let audit = audit_openapi(handlers, doc);
+
+    for handler in audit.undocumented.iter() {
+        warn!("The handler {handler} is registered, but it is missing in the OpenAPI document");
+    }
+    for operation in audit.unregistered.iter() {
+        warn!("The operation {operation} is documented, but its handler is not registered");
+    }
+}
+
+/// Serialized OpenAPI document.
+#[derive(Debug, Clone)]
+pub struct OpenApiDocument {
+    content: Bytes,
+    etag: EntityTag,
+}
+
+impl OpenApiDocument {
+    /// Serialize the document.
+    ///
+    /// # Description
+    ///
+    /// The entity tag includes the version of the crate, and a prefix of the hash of the document, as the document
+    /// also depends on the settings, i.e. the base URL of the API.
+    pub fn new(doc: &OpenApi) -> Result<Self, serde_json::Error> {
+        let content = serde_json::to_vec(doc)?;
+        let hash = format!("{:x}", Sha256::digest(&content));
+
+        Ok(OpenApiDocument {
+            etag: EntityTag::new_strong(format!(
+                "{}-{}",
+                env!("CARGO_PKG_VERSION"),
+                &hash[..HASH_LENGTH]
+            )),
+            content: Bytes::from(content),
+        })
+    }
+
+    pub fn etag(&self) -> &EntityTag {
+        &self.etag
+    }
+}
+
+/// Retrieve the OpenAPI document of the API.
+#[instrument(skip(req, doc))]
+#[get("/api-docs/openapi.json")]
+pub async fn get_openapi(req: HttpRequest, doc: Data<OpenApiDocument>) -> HttpResponse {
+    let not_modified = is_etag_not_modified(&req, &doc.etag);
+    let mut response = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+    response
+        .insert_header(ETag(doc.etag.clone()))
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::NoCache,
+        ]));
+
+    if not_modified {
+        response.finish()
+    } else {
+        response
+            .content_type(ContentType::json())
+            .body(doc.content.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{configuration::Settings, startup::Application, ApiDoc};
+    use actix_web::{
+        body::to_bytes,
+        http::{header, StatusCode},
+        test::{call_service, init_service, TestRequest},
+        web, App,
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use sqlx::mysql::MySqlPoolOptions;
+    use std::time::Duration;
+    use utoipa::OpenApi as _;
+
+    #[get("/new")]
+    async fn new_handler() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn all_the_handlers_are_documented() {
+        // The application doesn't write to the DB in read-only mode, so it is built without a DB.
+        let mut settings = Settings::new().unwrap();
+        settings.application.read_only = true;
+        settings.application.port = 0;
+        let pool = MySqlPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy_with(settings.database.build_db_conn_with_db());
+        let application = Application::build_with(settings, pool, None).await.unwrap();
+
+        let audit = application.openapi_audit();
+        assert!(audit.is_clean(), "{audit:#?}");
+    }
+
+    #[rstest]
+    fn gaps_are_found() {
+        let handlers = RegisteredHandlers::default();
+        App::new()
+            .service(handlers.track(crate::routes::echo))
+            .service(handlers.track(crate::routes::health::options_echo))
+            .service(
+                web::scope("/recipe").service(handlers.track(crate::routes::recipe::get_recipe)),
+            )
+            .service(handlers.track(new_handler));
+        assert_eq!(
+            handlers
+                .names()
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<&str>>(),
+            ["echo", "get_recipe", "new_handler"]
+        );
+
+        let audit = audit_openapi(&handlers, &ApiDoc::openapi());
+        assert_eq!(audit.undocumented, ["new_handler"]);
+        assert!(audit.unregistered.contains(&"post_recipe".to_owned()));
+    }
+
+    #[actix_web::test]
+    async fn documents_are_revalidated() {
+        let doc = OpenApiDocument::new(&ApiDoc::openapi()).unwrap();
+        assert!(doc
+            .etag()
+            .tag()
+            .starts_with(&format!("{}-", env!("CARGO_PKG_VERSION"))));
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(doc.clone()))
+                .service(get_openapi),
+        )
+        .await;
+
+        let response = call_service(
+            &app,
+            TestRequest::get()
+                .uri("/api-docs/openapi.json")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::ETAG).unwrap(),
+            &doc.etag().to_string()
+        );
+        let body: serde_json::Value =
+            serde_json::from_slice(&to_bytes(response.into_body()).await.unwrap()).unwrap();
+        assert!(body["paths"].is_object());
+
+        let response = call_service(
+            &app,
+            TestRequest::get()
+                .uri("/api-docs/openapi.json")
+                .insert_header((header::IF_NONE_MATCH, doc.etag().to_string()))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert!(to_bytes(response.into_body()).await.unwrap().is_empty());
+    }
+}
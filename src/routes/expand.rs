@@ -0,0 +1,320 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Expansion of the resources related to the resources of the API.
+//!
+//! # Description
+//!
+//! Resources only include the IDs of the resources they relate to, so clients need extra requests to display them,
+//! i.e. one request per ingredient of a recipe. Using the query parameter `expand`, the response embeds the related
+//! resources within an `embedded` object:
+//! - Recipes accept `expand=ingredients,author`: the name and the category of the ingredients, which are retrieved
+//!   using a single query for all the recipes of the response, and the public profile of the author of the recipe.
+//! - Authors accept `expand=recipes`: the newest published recipes of the author, up to [EMBEDDED_RECIPES_LIMIT].
+
+use crate::{
+    domain::{ApiError, Author, DataDomainError, IngCategory, QuantityUnit, Recipe},
+    routes::{
+        author::utils::get_author_from_db,
+        ingredient::utils::get_ingredients_by_id_from_db,
+        recipe::{get_recipe_from_db, utils::search_recipe_by_owner},
+    },
+};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt,
+};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Maximum amount of recipes embedded in an author (`expand=recipes`).
+pub const EMBEDDED_RECIPES_LIMIT: u32 = 10;
+
+/// Related resources that can be embedded in the responses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Expansion {
+    /// Name and category of the ingredients of a recipe.
+    Ingredients,
+    /// Public profile of the author of a recipe.
+    Author,
+    /// Newest published recipes of an author.
+    Recipes,
+}
+
+impl TryFrom<&str> for Expansion {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "ingredients" => Ok(Expansion::Ingredients),
+            "author" => Ok(Expansion::Author),
+            "recipes" => Ok(Expansion::Recipes),
+            other => Err(format!("Unknown expansion: {other}")),
+        }
+    }
+}
+
+impl fmt::Display for Expansion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expansion = match self {
+            Expansion::Ingredients => "ingredients",
+            Expansion::Author => "author",
+            Expansion::Recipes => "recipes",
+        };
+        write!(f, "{expansion}")
+    }
+}
+
+/// Query parameters that select the related resources embedded in the response.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct ExpandQuery {
+    /// Comma-separated list of related resources to embed, i.e. `expand=author,ingredients`.
+    pub expand: Option<String>,
+}
+
+impl ExpandQuery {
+    /// Parse the requested expansions.
+    ///
+    /// # Description
+    ///
+    /// A description of the issue is returned when some of the expansions is unknown, or it is not included by the
+    /// `supported` expansions of the resource.
+    pub fn expansions(&self, supported: &[Expansion]) -> Result<Vec<Expansion>, String> {
+        let mut expansions = Vec::new();
+
+        for item in self.expand.as_deref().unwrap_or_default().split(',') {
+            if item.trim().is_empty() {
+                continue;
+            }
+            let expansion = Expansion::try_from(item)?;
+            if !supported.contains(&expansion) {
+                return Err(format!("The resource can't expand: {expansion}"));
+            }
+            if !expansions.contains(&expansion) {
+                expansions.push(expansion);
+            }
+        }
+
+        Ok(expansions)
+    }
+}
+
+/// Ingredient of a recipe, including its name and category.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct IngredientDetail {
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub ingredient_id: Uuid,
+    #[schema(example = "Tequila reposado")]
+    pub name: String,
+    pub category: IngCategory,
+    #[schema(example = 4.5)]
+    pub quantity: f32,
+    pub unit: QuantityUnit,
+}
+
+/// Related resources embedded in a recipe.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct RecipeEmbeds {
+    /// Ingredients of the recipe, in the same order as the `ingredients` of the recipe. Ingredients that no longer
+    /// exist are skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ingredients: Option<Vec<IngredientDetail>>,
+    /// Public profile of the author of the recipe. Recipes without an author don't include it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<Author>,
+}
+
+impl RecipeEmbeds {
+    pub fn is_empty(&self) -> bool {
+        self.ingredients.is_none() && self.author.is_none()
+    }
+}
+
+/// A recipe along with the related resources requested by the client.
+///
+/// # Description
+///
+/// The recipe is serialized as usual, and the related resources are added within the `embedded` object. That object
+/// is omitted when no expansion was requested, thus the response is the same as a plain [Recipe].
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct ExpandedRecipe {
+    #[serde(flatten)]
+    pub recipe: Recipe,
+    #[serde(default, skip_serializing_if = "RecipeEmbeds::is_empty")]
+    pub embedded: RecipeEmbeds,
+}
+
+impl From<Recipe> for ExpandedRecipe {
+    fn from(recipe: Recipe) -> Self {
+        ExpandedRecipe {
+            recipe,
+            embedded: RecipeEmbeds::default(),
+        }
+    }
+}
+
+/// Related resources embedded in an author.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AuthorEmbeds {
+    /// Newest published recipes of the author.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipes: Option<Vec<Recipe>>,
+}
+
+impl AuthorEmbeds {
+    pub fn is_empty(&self) -> bool {
+        self.recipes.is_none()
+    }
+}
+
+/// An author along with the related resources requested by the client. See [ExpandedRecipe].
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct ExpandedAuthor {
+    #[serde(flatten)]
+    pub author: Author,
+    #[serde(default, skip_serializing_if = "AuthorEmbeds::is_empty")]
+    pub embedded: AuthorEmbeds,
+}
+
+/// Embed the requested related resources in a list of recipes.
+///
+/// # Description
+///
+/// The ingredients of all the recipes are retrieved using a single query, and every author is retrieved once. Private
+/// data of the authors is muted, as it is for the clients with no API key.
+pub async fn expand_recipes(
+    pool: &MySqlPool,
+    recipes: Vec<Recipe>,
+    expansions: &[Expansion],
+) -> Result<Vec<ExpandedRecipe>, ApiError> {
+    let mut expanded: Vec<ExpandedRecipe> = recipes.into_iter().map(ExpandedRecipe::from).collect();
+
+    if expansions.contains(&Expansion::Ingredients) {
+        let mut ids = expanded
+            .iter()
+            .flat_map(|e| e.recipe.ingredients().iter().map(|i| i.ingredient_id))
+            .collect::<Vec<Uuid>>();
+        ids.sort();
+        ids.dedup();
+
+        let ingredients = get_ingredients_by_id_from_db(pool, &ids)
+            .await?
+            .into_iter()
+            .filter_map(|i| i.id().map(|id| (id, i)))
+            .collect::<HashMap<_, _>>();
+
+        for item in expanded.iter_mut() {
+            item.embedded.ingredients = Some(
+                item.recipe
+                    .ingredients()
+                    .iter()
+                    .filter_map(|contains| {
+                        ingredients.get(&contains.ingredient_id).map(|ingredient| {
+                            IngredientDetail {
+                                ingredient_id: contains.ingredient_id,
+                                name: ingredient.name().to_owned(),
+                                category: ingredient.category().clone(),
+                                quantity: contains.quantity,
+                                unit: contains.unit,
+                            }
+                        })
+                    })
+                    .collect(),
+            );
+        }
+    }
+
+    if expansions.contains(&Expansion::Author) {
+        let mut authors: HashMap<Uuid, Option<Author>> = HashMap::new();
+
+        for item in expanded.iter_mut() {
+            let Some(owner) = item.recipe.owner() else {
+                continue;
+            };
+            if let Entry::Vacant(entry) = authors.entry(owner) {
+                // The author might be deleted, or merged into another profile.
+                let author = get_author_from_db(pool, &owner).await?.map(|mut author| {
+                    author.mute_private_data();
+                    author
+                });
+                entry.insert(author);
+            }
+            item.embedded.author = authors[&owner].clone();
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Embed the requested related resources in an author.
+pub async fn expand_author(
+    pool: &MySqlPool,
+    author: Author,
+    expansions: &[Expansion],
+) -> Result<ExpandedAuthor, ApiError> {
+    let mut embedded = AuthorEmbeds::default();
+
+    if expansions.contains(&Expansion::Recipes) {
+        let mut recipes = Vec::new();
+        if let Some(author_id) = author.id() {
+            let author_id = Uuid::parse_str(&author_id).map_err(|_| DataDomainError::InvalidId)?;
+            for id in search_recipe_by_owner(pool, &author_id, EMBEDDED_RECIPES_LIMIT).await? {
+                if let Some(recipe) = get_recipe_from_db(pool, &id).await? {
+                    recipes.push(recipe);
+                }
+            }
+        }
+        embedded.recipes = Some(recipes);
+    }
+
+    Ok(ExpandedAuthor { author, embedded })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    const RECIPE_EXPANSIONS: [Expansion; 2] = [Expansion::Ingredients, Expansion::Author];
+
+    #[rstest]
+    #[case(None, Ok(vec![]))]
+    #[case(Some("ingredients"), Ok(vec![Expansion::Ingredients]))]
+    #[case(Some("ingredients, ingredients,"), Ok(vec![Expansion::Ingredients]))]
+    #[case(
+        Some("author,ingredients"),
+        Ok(vec![Expansion::Author, Expansion::Ingredients])
+    )]
+    #[case(Some("ingredients,comments"), Err("Unknown expansion: comments".to_owned()))]
+    #[case(Some("recipes"), Err("The resource can't expand: recipes".to_owned()))]
+    fn expansions_are_parsed(
+        #[case] expand: Option<&str>,
+        #[case] expected: Result<Vec<Expansion>, String>,
+    ) {
+        let query = ExpandQuery {
+            expand: expand.map(String::from),
+        };
+        assert_eq!(query.expansions(&RECIPE_EXPANSIONS), expected);
+    }
+
+    #[rstest]
+    fn expansion_names_round_trip() {
+        for expansion in [
+            Expansion::Ingredients,
+            Expansion::Author,
+            Expansion::Recipes,
+        ] {
+            assert_eq!(
+                Expansion::try_from(expansion.to_string().as_str()),
+                Ok(expansion)
+            );
+        }
+    }
+}
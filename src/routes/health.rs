@@ -10,19 +10,36 @@
 //!
 //! Two endpoints are available:
 //! - [echo] for a basic ping support with public access.
-//! - [health_check] for a detailed health report with restricted access.
+//! - [health_check] for a detailed health report with restricted access. The report includes the live metrics of the
+//!   server, see [crate::utils::metrics].
 //!
 //! The number of requests within a time frame to both endpoints are limited by the API to every client. This is
 //! a mechanism to prevent DoS attacks to the server. Every response includes the header *Retry-After* to inform the
 //! client when it is allowed to send a new request to the API.
 
-use crate::{datetime_object_type, AuthData};
-use actix_web::{get, options, web, HttpRequest, HttpResponse, Responder};
-use chrono::{DateTime, Days, Local};
-use secrecy::ExposeSecret;
+use crate::{
+    authentication::{
+        access_denied_response, check_access, get_api_keys, key_client_id, AccessControl, Scope,
+    },
+    datetime_object_type,
+    domain::ApiError,
+    routes::me::utils::count_pending_notification_emails_in_db,
+    utils::{
+        http::MaintenanceSchedule,
+        metrics::{metrics, MetricsSnapshot},
+    },
+    AuthData,
+};
+use actix_web::{
+    get,
+    http::header::{HeaderName, HeaderValue},
+    options, web, HttpRequest, HttpResponse, Responder,
+};
+use chrono::{DateTime, Days, Local, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
-use tracing::instrument;
+use sqlx::MySqlPool;
+use std::{collections::BTreeMap, time::Instant};
+use tracing::{debug, error, instrument};
 use utoipa::{
     openapi::{
         example::ExampleBuilder,
@@ -58,9 +75,13 @@ pub enum ServerStatus {
 pub struct HealthResponse {
     /// Current server status, see [ServerStatus].
     pub server_status: ServerStatus,
-    /// Expire date of the used API token.
+    /// Expire date of the newest API key of the client. No value when the DB is not available.
     #[schema(schema_with = datetime_object_type)]
-    pub api_expire_time: DateTime<Local>,
+    pub api_expire_time: Option<DateTime<Local>>,
+    /// Live metrics of the server.
+    pub metrics: MetricsSnapshot,
+    /// Notifications waiting to be sent by email. No value when the DB is not available.
+    pub mail_outbox: Option<u64>,
 }
 
 impl HealthResponse {
@@ -68,7 +89,13 @@ impl HealthResponse {
     pub fn example_ok() -> HealthResponse {
         HealthResponse {
             server_status: ServerStatus::Ok,
-            api_expire_time: Local::now().checked_add_days(Days::new(1)).unwrap(),
+            api_expire_time: Local::now().checked_add_days(Days::new(1)),
+            metrics: MetricsSnapshot {
+                uptime_secs: 86400,
+                db_latency_p95_ms: Some(4.2),
+                ..Default::default()
+            },
+            mail_outbox: Some(0),
         }
     }
 
@@ -77,7 +104,9 @@ impl HealthResponse {
         let ts = Local::now().checked_add_days(Days::new(1)).unwrap();
         HealthResponse {
             server_status: ServerStatus::MaintenanceScheduled(ts),
-            api_expire_time: ts,
+            api_expire_time: Some(ts),
+            metrics: MetricsSnapshot::default(),
+            mail_outbox: Some(0),
         }
     }
 }
@@ -140,6 +169,13 @@ impl IntoResponses for HealthResponse {
                     .header("Cache-Control", cache_control_header.clone())
                     .header("Retry-After", retry_after_header.clone()),
             )
+            .response(
+                "403",
+                ResponseBuilder::default()
+                    .description("**The given API key doesn't grant the `read` scope.**")
+                    .header("Cache-Control", cache_control_header.clone())
+                    .header("Retry-After", retry_after_header.clone()),
+            )
             .response("401",
                 ResponseBuilder::default()
                 .description("**Unauthorised access to a restricted endpoint.**")
@@ -184,7 +220,7 @@ impl IntoResponses for HealthResponse {
 #[instrument()]
 #[get("/echo")]
 pub async fn echo() -> impl Responder {
-    HttpResponse::NotImplemented()
+    HttpResponse::Ok()
         // Avoid caching this endpoint.
         .append_header(("Cache-Control", "no-cache"))
         .append_header(("Retry-After", "60"))
@@ -209,7 +245,7 @@ pub async fn echo() -> impl Responder {
 )]
 #[options("/echo")]
 pub async fn options_echo() -> impl Responder {
-    HttpResponse::NotImplemented()
+    HttpResponse::NoContent()
         .append_header(("access-control-allow-origin", "*"))
         .append_header(("cache-control", "public, max-age=604800"))
         .append_header(("access-control-allow-methods", "GET, OPTIONS"))
@@ -220,7 +256,12 @@ pub async fn options_echo() -> impl Responder {
 ///
 /// # Description
 ///
-/// This restricted endpoint allows authorized clients to retrieve a health report of the server.
+/// This restricted endpoint allows authorized clients to retrieve a health report of the server. The report includes
+/// the uptime of the server, the rate of requests and server errors during the last 5 minutes (globally and per
+/// resource), the 95th percentile of the latency of the DB, and the amount of emails waiting to be sent. The status
+/// `DbDown` is reported when the DB doesn't answer. When an administrator announced a maintenance window, the status
+/// `MaintenanceScheduled` is reported with the start of the window until it starts, and `OnMaintenance` is reported
+/// with the end of the window while it lasts.
 ///
 /// The number of allowed requests by a single client is limited to 2 per minute. If this value is reached by a client,
 /// the client is banned for an amount of time, which is specified by the header *Retry-After*. The ban time increases
@@ -233,26 +274,78 @@ pub async fn options_echo() -> impl Responder {
         ("api_key" = [])
     ),
 )]
-#[instrument(skip(req))]
+#[instrument(skip(req, pool, maintenance, access))]
 #[get("/health")]
-pub async fn health_check(req: web::Query<AuthData>) -> impl Responder {
-    if !req.api_key.expose_secret().is_empty() {
-        HttpResponse::NotImplemented()
-            .append_header(("Access-Control-Allow-Origin", "*"))
-            .append_header(("access-control-allow-headers", "content-type"))
-            // Avoid caching this endpoint.
-            .append_header(("Cache-Control", "no-cache"))
-            .append_header(("Retry-After", "60"))
-            .finish()
+pub async fn health_check(
+    req: web::Query<AuthData>,
+    pool: web::Data<MySqlPool>,
+    access: web::Data<AccessControl>,
+    maintenance: web::Data<MaintenanceSchedule>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &req.api_key, Scope::Read).await {
+        return access_denied_response(e).map(health_headers);
+    }
+    debug!("Access granted");
+
+    let started = Instant::now();
+    let db_up = match sqlx::query("SELECT 1").execute(pool.get_ref()).await {
+        Ok(_) => {
+            metrics().record_db_latency(started.elapsed());
+            true
+        }
+        Err(e) => {
+            error!("The DB is not available: {e}");
+            false
+        }
+    };
+
+    let (server_status, api_expire_time, mail_outbox) = if db_up {
+        let keys = get_api_keys(&pool, &key_client_id(&req.api_key)?).await?;
+        let now = Utc::now();
+        let server_status = match maintenance.window(now) {
+            Some(window) if window.is_ongoing(now) => {
+                ServerStatus::OnMaintenance(window.ends_at.with_timezone(&Local))
+            }
+            Some(window) => {
+                ServerStatus::MaintenanceScheduled(window.starts_at.with_timezone(&Local))
+            }
+            None => ServerStatus::Ok,
+        };
+        (
+            server_status,
+            keys.first()
+                .map(|key| key.valid_until.with_timezone(&Local)),
+            Some(count_pending_notification_emails_in_db(&pool).await?),
+        )
     } else {
-        HttpResponse::Unauthorized()
-            .append_header(("Access-Control-Allow-Origin", "*"))
-            .append_header(("access-control-allow-headers", "content-type"))
-            // Avoid caching this endpoint.
-            .append_header(("Cache-Control", "no-cache"))
-            .append_header(("Retry-After", "60"))
-            .finish()
+        (ServerStatus::DbDown, None, None)
+    };
+
+    Ok(health_headers(HttpResponse::Ok().json(HealthResponse {
+        server_status,
+        api_expire_time,
+        metrics: metrics().snapshot(),
+        mail_outbox,
+    })))
+}
+
+/// Add the headers of the responses of the /health endpoint.
+fn health_headers(mut response: HttpResponse) -> HttpResponse {
+    for (name, value) in [
+        ("access-control-allow-origin", "*"),
+        ("access-control-allow-headers", "content-type"),
+        // Avoid caching this endpoint.
+        ("cache-control", "no-cache"),
+        ("retry-after", "60"),
+    ] {
+        response.headers_mut().insert(
+            HeaderName::from_static(name),
+            HeaderValue::from_static(value),
+        );
     }
+
+    response
 }
 
 /// Options method for the /health endpoint.
@@ -0,0 +1,66 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Categories of the ingredients.
+//!
+//! # Description
+//!
+//! Categories are stored in the DB, and they are managed by the administrators of the API (see
+//! [crate::routes::admin::ingredient_categories]). This resource lists the categories that can be used when
+//! registering a new ingredient.
+
+use crate::{
+    domain::{collation::NameCollations, ApiError},
+    routes::ingredient::utils::get_ingredient_categories_from_db,
+    utils::http::accepted_language,
+};
+use actix_web::{get, web::Data, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::instrument;
+use utoipa::ToSchema;
+
+/// Category of the ingredients stored in the DB.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct IngredientCategory {
+    /// Identifier of the category, used as the `category` of the ingredients.
+    #[schema(example = "spirit")]
+    pub name: String,
+    #[schema(example = "Spirit ingredients, such as rum, liquors and so")]
+    pub description: Option<String>,
+    /// Amount of ingredients that belong to the category.
+    pub ingredients: i64,
+}
+
+/// List the ingredient categories (Public).
+///
+/// # Description
+///
+/// Categories are sorted by name following the rules of the language given by the `Accept-Language` header, i.e.
+/// `ñ` is sorted after `n` for Spanish speakers.
+#[utoipa::path(
+    get,
+    path = "/ingredient/categories",
+    tag = "Ingredient",
+    params(
+        ("Accept-Language" = Option<String>, Header, description = "Language whose rules sort the names: `en` or `es`."),
+    ),
+    responses(
+        (status = 200, description = "The available categories, sorted by name.", body = [IngredientCategory]),
+    )
+)]
+#[instrument(skip(pool, request, collations))]
+#[get("/categories")]
+pub async fn get_ingredient_categories(
+    pool: Data<MySqlPool>,
+    collations: Data<NameCollations>,
+    request: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let language = accepted_language(&request).unwrap_or_default();
+
+    Ok(HttpResponse::Ok()
+        .json(get_ingredient_categories_from_db(&pool, collations.get(language)).await?))
+}
@@ -0,0 +1,81 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::{
+    authentication::{access_denied_response, check_admin_access, AccessControl, AuthData, Scope},
+    domain::ApiError,
+    routes::{
+        batch::{BatchDelete, MAX_BATCH_SIZE},
+        ingredient::utils::delete_ingredients_from_db,
+    },
+};
+use actix_web::{
+    post,
+    web::{Data, Json, Query},
+    HttpResponse,
+};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+
+/// Batch-delete of ingredients.
+///
+/// # Description
+///
+/// Delete a list of ingredients at once. Each ID receives its own outcome in the response, following the order of
+/// the request:
+/// - `deleted`: the ingredient was removed from the DB.
+/// - `not_found`: no ingredient matched the ID.
+/// - `invalid_id`: the ID has an invalid format.
+/// - `referenced`: the ingredient is used by some recipe, so it was kept.
+///
+/// The `owner` field of the request is ignored, as ingredients have no owner.
+///
+/// All the deletions are applied within a single transaction. The batch is limited to 100 IDs.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    post,
+    path = "/ingredient/batch-delete",
+    tag = "Ingredient",
+    security(
+        ("api_key" = [])
+    ),
+    request_body(
+        content = BatchDelete, description = "The IDs of the ingredients to delete.",
+    ),
+    responses(
+        (status = 200, description = "The batch was processed.", body = [BatchItemResult]),
+        (status = 400, description = "The batch is empty or exceeds the maximum size."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+    )
+)]
+#[instrument(skip(pool, token, req, access))]
+#[post("/batch-delete")]
+pub async fn batch_delete_ingredients(
+    req: Json<BatchDelete>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    if !req.is_valid_size() {
+        info!("Batch of {} IDs rejected", req.ids.len());
+        return Ok(HttpResponse::BadRequest().body(format!(
+            "The batch must contain between 1 and {MAX_BATCH_SIZE} IDs"
+        )));
+    }
+
+    let results = delete_ingredients_from_db(&pool, &req.parsed_ids()).await?;
+    info!("Batch-delete of {} ingredients processed", results.len());
+
+    Ok(HttpResponse::Ok().json(results))
+}
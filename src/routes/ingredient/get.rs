@@ -5,20 +5,25 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::{
-    domain::{DataDomainError, Ingredient},
-    routes::ingredient::utils::{check_ingredient, get_ingredient_from_db},
+    domain::{ApiError, Ingredient, ResourceId},
+    routes::ingredient::utils::{
+        check_ingredient, count_ingredients_from_db, get_ingredient_from_db,
+    },
+    utils::{
+        changes::{get_collection_last_modified, Collection},
+        http::{client_ip, is_not_modified, last_modified, MaxPageSize, Page},
+        metrics::analytics,
+    },
 };
 use actix_web::{
     get,
-    web::{Data, Path, Query},
-    HttpResponse,
+    web::{Data, Query},
+    HttpRequest, HttpResponse,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use sqlx::MySqlPool;
-use std::error::Error;
-use tracing::{debug, error, info, instrument};
+use tracing::{debug, info, instrument};
 use utoipa::IntoParams;
-use uuid::Uuid;
 
 /// `Struct` QueryData models the expected fields for a query string.
 ///
@@ -28,33 +33,75 @@ use uuid::Uuid;
 /// the internal parsing logic of the framework. This way, the endpoint handler would only receive
 /// valid data, since wrong data is rejected and the request is answered with a code 400 by the
 /// framework.
-#[derive(Deserialize, IntoParams)]
+#[derive(Deserialize, Serialize, IntoParams)]
 pub struct QueryData {
     pub name: String,
+    /// Amount of ingredients of the page, 20 by default. The maximum depends on the settings of the server (100 by
+    /// default).
+    #[param(example = 20)]
+    pub limit: Option<u32>,
+    /// Amount of ingredients skipped, 0 by default.
+    #[param(example = 0)]
+    pub offset: Option<u32>,
 }
 
 /// GET for the API's /ingredient endpoint.
+///
+/// # Description
+///
+/// Responses include the time of the latest change of the ingredients in a `Last-Modified` header. Requests that send
+/// it back using `If-Modified-Since` are answered with a code **304** when no ingredient changed since then.
+///
+/// Results are paginated using `limit` and `offset`. The total amount of matching ingredients is included in the
+/// `X-Total-Count` header, and the links to the other pages in the `Link` header. Ingredients are sorted by name.
 #[utoipa::path(
     get,
     path = "/ingredient",
     tag = "Ingredient",
     params(
-        QueryData
+        QueryData,
+        ("If-Modified-Since" = Option<String>, Header, description = "Skip the search when no ingredient changed since the given date."),
     ),
     responses(
         (
             status = 200,
             description = "The query was successfully executed",
-            body = [Ingredient]
+            body = [Ingredient],
+            headers(
+                ("Last-Modified", description = "Time of the latest change of the ingredients."),
+                ("X-Total-Count", description = "Amount of ingredients that match the search."),
+                ("Link", description = "Links to the first, previous, next and last pages."),
+            )
+        ),
+        (
+            status = 304,
+            description = "No ingredient changed since the date given in `If-Modified-Since`.",
+            headers(
+                ("Last-Modified", description = "Time of the latest change of the ingredients."),
+            )
         ),
         (
             status = 400,
-            description = "Error found in the given query",
+            description = "Error found in the given query, or the page is not valid.",
+        ),
+        (
+            status = 429, description = "**Too many requests.**",
+            headers(
+                ("Cache-Control", description = "Cache control is set to *no-cache*."),
+                ("Retry-After", description = "Amount of time between requests (seconds).")
+            )
+        ),
+        (
+            status = 503, description = "**The server is overloaded.** Retrieval of resources is temporarily disabled.",
+            headers(
+                ("Cache-Control", description = "Cache control is set to *no-cache*."),
+                ("Retry-After", description = "Amount of time to wait before retrying (seconds).")
+            )
         ),
     )
 )]
 #[instrument(
-    skip(pool, req),
+    skip(pool, req, request, max_page_size),
     fields(
         ingredient_name = %req.name,
     )
@@ -62,8 +109,14 @@ pub struct QueryData {
 #[get("")]
 pub async fn search_ingredient(
     pool: Data<MySqlPool>,
+    max_page_size: Data<MaxPageSize>,
     req: Query<QueryData>,
-) -> Result<HttpResponse, Box<dyn Error>> {
+    request: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let page = match Page::new(req.limit, req.offset, **max_page_size) {
+        Ok(page) => page,
+        Err(e) => return Ok(HttpResponse::BadRequest().body(e)),
+    };
     // First, validate the given form as a correct name for the instantiation of an Ingredient.
     let query_ingredient = match Ingredient::parse(None, &req.name, "other", None) {
         Ok(ingredient) => {
@@ -75,9 +128,33 @@ pub async fn search_ingredient(
         }
         Err(e) => return Ok(HttpResponse::BadRequest().body(format!("{}", e))),
     };
+    let client = client_ip(&request).map(|ip| ip.to_string());
+    analytics().record_search("ingredient", query_ingredient.name(), client.as_deref());
+
+    let collection_modified = get_collection_last_modified(&pool, Collection::Ingredient).await?;
+    if let Some(timestamp) = collection_modified {
+        if is_not_modified(&request, timestamp) {
+            info!("No ingredient changed since the given date");
+            return Ok(HttpResponse::NotModified()
+                .insert_header(last_modified(timestamp))
+                .finish());
+        }
+    }
 
     // Issue a query to the DB to search for ingredients using the given name.
-    let ingredients = match check_ingredient(&pool, query_ingredient).await {
+    let total = count_ingredients_from_db(&pool, &query_ingredient).await?;
+    let ingredients = if page.offset as u64 >= total {
+        Ok(Vec::new())
+    } else {
+        check_ingredient(
+            &pool,
+            &query_ingredient,
+            page.limit as u32,
+            page.offset as u32,
+        )
+        .await
+    };
+    let ingredients = match ingredients {
         Ok(ingredients) => {
             if !ingredients.is_empty() {
                 let mut ing_list = String::new();
@@ -95,13 +172,19 @@ pub async fn search_ingredient(
         Err(_) => Vec::new(),
     };
 
-    Ok(HttpResponse::Ok().json(ingredients))
+    let mut response = HttpResponse::Ok();
+    if let Some(timestamp) = collection_modified {
+        response.insert_header(last_modified(timestamp));
+    }
+    page.insert_headers(&mut response, &request, total as usize);
+    Ok(response.json(ingredients))
 }
 
 #[utoipa::path(
     get,
     context_path = "/ingredient/",
     tag = "Ingredient",
+    params(("id" = String, Path, description = "ID of the ingredient.")),
     responses(
         (
             status = 200,
@@ -114,6 +197,10 @@ pub async fn search_ingredient(
                 ("Vary", description = "Origin,Access-Control-Request-Method,Access-Control-Request-Headers")
             ),
         ),
+        (
+            status = 400,
+            description = "The given ingredient's ID has an invalid format.",
+        ),
         (
             status = 404,
             description = "The given ingredient's ID was not found in the DB.",
@@ -130,29 +217,28 @@ pub async fn search_ingredient(
                 ("Access-Control-Allow-Origin"),
                 ("Retry-After", description = "Amount of time between requests (seconds).")
             )
-        )
+        ),
+        (
+            status = 503, description = "**The server is overloaded.** Retrieval of resources is temporarily disabled.",
+            headers(
+                ("Cache-Control", description = "Cache control is set to *no-cache*."),
+                ("Retry-After", description = "Amount of time to wait before retrying (seconds).")
+            )
+        ),
     )
 )]
 #[instrument(
-    skip(pool, req),
+    skip(pool),
     fields(
-        ingredient_id = %req.0,
+        ingredient_id = %id,
     )
 )]
 #[get("{id}")]
 pub async fn get_ingredient(
-    req: Path<(String,)>,
+    id: ResourceId,
     pool: Data<MySqlPool>,
-) -> Result<HttpResponse, Box<dyn Error>> {
-    let id = match Uuid::parse_str(&req.0) {
-        Ok(id) => id,
-        Err(e) => {
-            error!("{e}");
-            return Err(Box::new(DataDomainError::InvalidId));
-        }
-    };
-
-    match get_ingredient_from_db(&pool, &id).await? {
+) -> Result<HttpResponse, ApiError> {
+    match get_ingredient_from_db(&pool, id.as_uuid()).await? {
         Some(ingredient) => Ok(HttpResponse::Ok().json(ingredient)),
         None => Ok(HttpResponse::NotFound().finish()),
     }
@@ -0,0 +1,142 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Images of the ingredients.
+//!
+//! # Description
+//!
+//! Each ingredient can be illustrated by a single image, which is kept by the media storage (see [MediaStore]). The
+//! `image_id` of the ingredients points to the image, which is served by `GET /media/{image_id}`. Uploading a new
+//! image replaces the previous one.
+
+use crate::{
+    authentication::{access_denied_response, check_access, AccessControl, AuthData, Scope},
+    domain::{ApiError, ResourceId},
+    routes::ingredient::utils::{get_ingredient_from_db, set_ingredient_image_in_db},
+    utils::media::{MediaError, MediaStore},
+};
+use actix_web::{
+    delete, put,
+    web::{Bytes, Data, Query},
+    HttpResponse,
+};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+
+/// Upload the image of an ingredient.
+///
+/// # Description
+///
+/// The body of the request is the raw content of the image. PNG, JPEG and WebP images up to 2 MiB are accepted, and
+/// their format is detected from their content. Any previous image of the ingredient is replaced.
+#[utoipa::path(
+    put,
+    path = "/ingredient/{id}/image",
+    tag = "Ingredient",
+    params(("id" = String, Path, description = "ID of the ingredient.")),
+    request_body(content = Vec<u8>, description = "Content of the image.", content_type = "image/png"),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The image was stored.", body = Ingredient),
+        (status = 400, description = "The ID has an invalid format, or the image is empty or has an unsupported format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The API key doesn't grant the `write` scope."),
+        (status = 404, description = "The ingredient doesn't exist."),
+        (status = 413, description = "The image exceeds the maximum size."),
+    )
+)]
+#[instrument(skip(content, pool, media, token, access))]
+#[put("/{id}/image")]
+pub async fn put_ingredient_image(
+    id: ResourceId,
+    content: Bytes,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    media: Data<MediaStore>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let image_id = match media.store_image(content).await {
+        Ok(image_id) => image_id,
+        Err(e @ (MediaError::InvalidSize | MediaError::UnsupportedFormat)) => {
+            info!("{e}");
+            return Ok(HttpResponse::BadRequest().body(e.to_string()));
+        }
+        Err(e) => return Err(ApiError::Internal(e.into())),
+    };
+
+    let previous = match set_ingredient_image_in_db(&pool, id.as_uuid(), Some(&image_id)).await {
+        Ok(Some(previous)) => previous,
+        Ok(None) => {
+            media.delete_image(&image_id).await;
+            return Ok(HttpResponse::NotFound().finish());
+        }
+        Err(e) => {
+            media.delete_image(&image_id).await;
+            return Err(e.into());
+        }
+    };
+    if let Some(previous) = previous {
+        media.delete_image(&previous).await;
+    }
+    info!("New image {image_id} for the ingredient {id}");
+
+    match get_ingredient_from_db(&pool, id.as_uuid()).await? {
+        Some(ingredient) => Ok(HttpResponse::Ok().json(ingredient)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Remove the image of an ingredient.
+#[utoipa::path(
+    delete,
+    path = "/ingredient/{id}/image",
+    tag = "Ingredient",
+    params(("id" = String, Path, description = "ID of the ingredient.")),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 204, description = "The ingredient has no image now."),
+        (status = 400, description = "The ID has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The API key doesn't grant the `write` scope."),
+        (status = 404, description = "The ingredient doesn't exist."),
+    )
+)]
+#[instrument(skip(pool, media, token, access))]
+#[delete("/{id}/image")]
+pub async fn delete_ingredient_image(
+    id: ResourceId,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    media: Data<MediaStore>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    match set_ingredient_image_in_db(&pool, id.as_uuid(), None).await? {
+        Some(previous) => {
+            if let Some(previous) = previous {
+                media.delete_image(&previous).await;
+                info!("Image {previous} of the ingredient {id} removed");
+            }
+            Ok(HttpResponse::NoContent().finish())
+        }
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
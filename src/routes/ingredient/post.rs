@@ -4,7 +4,14 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::domain::Ingredient;
+use crate::{
+    domain::{search_text::normalize_search_text, IdGenerator, Ingredient},
+    routes::ingredient::utils::ingredient_category_exists,
+    utils::{
+        changes::{touch_collection, Collection},
+        events::{record_event, DomainEvent},
+    },
+};
 use actix_web::{post, web, HttpResponse};
 use serde::{Deserialize, Serialize};
 use sqlx::MySqlPool;
@@ -20,6 +27,10 @@ pub struct FormData {
 }
 
 /// POST for the API's /ingredient endpoint.
+///
+/// # Description
+///
+/// The category must be one of the categories listed by `GET /ingredient/categories`.
 #[utoipa::path(
     post,
     path = "/ingredient",
@@ -35,7 +46,7 @@ pub struct FormData {
         ),
         (
             status = 400,
-            description = "Format error found in the given JSON",
+            description = "Format error found in the given JSON, or the category doesn't exist",
         ),
         (
             status = 500,
@@ -45,7 +56,7 @@ pub struct FormData {
 )]
 #[instrument(
     target = "lacoctelera::ingredient_post",
-    skip(pool, ingredient),
+    skip(pool, ids, ingredient),
     fields(
         ingredient_name = %ingredient.name,
         ingredient_category = %ingredient.category,
@@ -56,6 +67,7 @@ pub struct FormData {
 pub async fn add_ingredient(
     ingredient: web::Json<FormData>,
     pool: web::Data<MySqlPool>,
+    ids: web::Data<dyn IdGenerator>,
 ) -> HttpResponse {
     let ingredient = match Ingredient::parse(
         None,
@@ -73,7 +85,22 @@ pub async fn add_ingredient(
         }
     };
 
-    match insert_ingredient(&pool, ingredient).await {
+    match ingredient_category_exists(&pool, ingredient.category()).await {
+        Ok(true) => (),
+        Ok(false) => {
+            debug!("The category of the ingredient doesn't exist.");
+            return HttpResponse::BadRequest().body(format!(
+                "Unknown ingredient category: {}",
+                ingredient.category()
+            ));
+        }
+        Err(e) => {
+            error!("The category of the ingredient could not be checked: {e}");
+            return HttpResponse::InternalServerError().body(e.to_string());
+        }
+    }
+
+    match insert_ingredient(&pool, ids.get_ref(), ingredient).await {
         Ok(_) => HttpResponse::Ok().finish(),
         Err(e) => {
             error!("The ingredient could not be inserted in the DB: {e}");
@@ -82,25 +109,37 @@ pub async fn add_ingredient(
     }
 }
 
-#[instrument(skip(pool, ingredient))]
-async fn insert_ingredient(
+#[instrument(skip(pool, ids, ingredient))]
+pub(crate) async fn insert_ingredient(
     pool: &MySqlPool,
+    ids: &dyn IdGenerator,
     ingredient: Ingredient,
 ) -> Result<Uuid, anyhow::Error> {
-    let new_id = Uuid::now_v7();
+    let new_id = ids.new_id();
+    let mut transaction = pool.begin().await?;
 
     sqlx::query!(
         r#"
-        INSERT INTO Ingredient (`id`, `name`, `category`, `description`) VALUES
-        (? , ?, ?, ?)
+        INSERT INTO Ingredient (`id`, `name`, `search_name`, `category`, `description`) VALUES
+        (? , ?, ?, ?, ?)
         "#,
         new_id.to_string(),
         ingredient.name(),
-        ingredient.category().to_str().to_owned(),
+        normalize_search_text(ingredient.name()),
+        ingredient.category().to_str(),
         ingredient.desc(),
     )
-    .execute(pool)
+    .execute(&mut *transaction)
+    .await?;
+    touch_collection(&mut *transaction, Collection::Ingredient).await?;
+    record_event(
+        &mut *transaction,
+        &DomainEvent::IngredientCreated {
+            ingredient_id: new_id,
+        },
+    )
     .await?;
+    transaction.commit().await?;
 
     info!("New ingredient inserted in the DB.");
 
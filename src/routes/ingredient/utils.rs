@@ -4,46 +4,101 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::domain::{Ingredient, ServerError};
-use sqlx::MySqlPool;
-use std::error::Error;
-use tracing::{error, info, instrument};
+use crate::{
+    domain::{
+        collation::{order_by_name, Collation},
+        search_text::search_pattern,
+        ApiError, IngCategory, Ingredient, ServerError,
+    },
+    routes::{
+        batch::{BatchItemResult, BatchOutcome},
+        ingredient::categories::IngredientCategory,
+    },
+    utils::{
+        changes::{touch_collection, Collection},
+        events::{record_event, DomainEvent},
+    },
+};
+use sqlx::{FromRow, MySqlPool};
+use tracing::{debug, error, info, instrument};
 use uuid::Uuid;
 
+/// Columns of the `Ingredient` table: ID, name, category, description and image.
+#[derive(Debug, FromRow)]
+struct StoredIngredient {
+    id: String,
+    name: String,
+    category: String,
+    description: Option<String>,
+    image_id: Option<String>,
+}
+
+fn parse_stored_ingredient(record: StoredIngredient) -> Result<Ingredient, ApiError> {
+    Ok(Ingredient::parse(
+        Some(&record.id),
+        &record.name,
+        &record.category,
+        record.description.as_deref(),
+    )?
+    .with_image_id(record.image_id))
+}
+
+/// Search the ingredients whose name includes the name of the given ingredient.
+///
+/// # Description
+///
+/// Ingredients are sorted by name, so the pages of the results are stable. Up to `limit` ingredients are listed after
+/// skipping `offset` of them.
 #[instrument(skip(pool, ingredient))]
 pub async fn check_ingredient(
     pool: &MySqlPool,
-    ingredient: Ingredient,
-) -> Result<Vec<Ingredient>, Box<dyn Error>> {
-    let rows = sqlx::query!(
-        r#"SELECT `id`, `name`, `category`, `description` FROM Ingredient i WHERE i.name like ?"#,
-        format!("%{}%", ingredient.name()),
+    ingredient: &Ingredient,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<Ingredient>, ApiError> {
+    let rows = sqlx::query_as!(
+        StoredIngredient,
+        r#"SELECT `id`, `name`, `category`, `description`, `image_id` FROM Ingredient i WHERE i.search_name like ?
+        ORDER BY i.name, i.id LIMIT ? OFFSET ?"#,
+        search_pattern(ingredient.name()),
+        limit,
+        offset,
     )
     .fetch_all(pool)
     .await?;
 
-    let mut ingredients = Vec::new();
-    for r in rows {
-        ingredients.push(Ingredient::parse(
-            Some(&r.id),
-            r.name.as_str(),
-            r.category.as_str(),
-            r.description.as_deref(),
-        )?);
-    }
+    rows.into_iter().map(parse_stored_ingredient).collect()
+}
 
-    Ok(ingredients)
+/// Count the ingredients listed by [check_ingredient], regardless of the page.
+#[instrument(skip(pool, ingredient))]
+pub async fn count_ingredients_from_db(
+    pool: &MySqlPool,
+    ingredient: &Ingredient,
+) -> Result<u64, ServerError> {
+    let total: i64 =
+        sqlx::query_scalar(r#"SELECT COUNT(*) FROM Ingredient i WHERE i.search_name like ?"#)
+            .bind(search_pattern(ingredient.name()))
+            .fetch_one(pool)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+
+    Ok(total as u64)
 }
 
 #[instrument(skip(pool, id))]
 pub async fn get_ingredient_from_db(
     pool: &MySqlPool,
     id: &Uuid,
-) -> Result<Option<Ingredient>, Box<dyn Error>> {
-    let row = sqlx::query!(
-        r#"SELECT `id`, `name`, `category`, `description`
+) -> Result<Option<Ingredient>, ApiError> {
+    let row = sqlx::query_as!(
+        StoredIngredient,
+        r#"SELECT `id`, `name`, `category`, `description`, `image_id`
         FROM `Ingredient` WHERE `id`=?"#,
-        id.to_string()
+        id.to_string(),
     )
     .fetch_optional(pool)
     .await
@@ -52,22 +107,425 @@ pub async fn get_ingredient_from_db(
         ServerError::DbError
     })?;
 
-    let raw_ingredient = match row {
-        Some(i) => i,
+    match row {
+        Some(row) => Ok(Some(parse_stored_ingredient(row)?)),
         None => {
-            return {
-                info!("No ingredient was found with the ID: {id}");
-                Ok(None)
-            }
+            info!("No ingredient was found with the ID: {id}");
+            Ok(None)
         }
+    }
+}
+
+/// Retrieve several ingredients using a single query. IDs that don't exist are ignored.
+#[instrument(skip(pool))]
+pub async fn get_ingredients_by_id_from_db(
+    pool: &MySqlPool,
+    ids: &[Uuid],
+) -> Result<Vec<Ingredient>, ApiError> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = vec!["?"; ids.len()].join(",");
+    let query = format!(
+        "SELECT `id`, `name`, `category`, `description`, `image_id` \
+        FROM `Ingredient` WHERE `id` IN ({placeholders})"
+    );
+    let mut query = sqlx::query_as::<_, StoredIngredient>(&query);
+    for id in ids {
+        query = query.bind(id.to_string());
+    }
+    let rows = query.fetch_all(pool).await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+    debug!("{} of {} ingredients found", rows.len(), ids.len());
+
+    rows.into_iter().map(parse_stored_ingredient).collect()
+}
+
+/// Set (or remove) the image of an ingredient.
+///
+/// # Description
+///
+/// `None` is returned when the ingredient doesn't exist. Otherwise, the ID of the replaced image (if any) is returned
+/// so it can be removed from the media storage.
+#[instrument(skip(pool))]
+pub async fn set_ingredient_image_in_db(
+    pool: &MySqlPool,
+    id: &Uuid,
+    image_id: Option<&str>,
+) -> Result<Option<Option<String>>, ServerError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let previous: Option<Option<String>> =
+        sqlx::query_scalar("SELECT image_id FROM Ingredient WHERE id = ? FOR UPDATE")
+            .bind(id.to_string())
+            .fetch_optional(&mut *transaction)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+    if previous.is_none() {
+        return Ok(None);
+    }
+
+    sqlx::query("UPDATE Ingredient SET image_id = ? WHERE id = ?")
+        .bind(image_id)
+        .bind(id.to_string())
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+    touch_collection(&mut *transaction, Collection::Ingredient).await?;
+    record_event(
+        &mut *transaction,
+        &DomainEvent::IngredientUpdated { ingredient_id: *id },
+    )
+    .await?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(previous)
+}
+
+/// Delete a batch of ingredients within a single transaction.
+///
+/// # Description
+///
+/// Each ID is processed independently, and its [BatchOutcome] is reported in the returned list, which follows the
+/// order of the given IDs. Ingredients used by some recipe are kept, as deleting them would leave incomplete
+/// recipes in the DB. Either all the deletions are applied, or none if an error is found while accessing the DB.
+#[instrument(skip(pool, ids))]
+pub async fn delete_ingredients_from_db(
+    pool: &MySqlPool,
+    ids: &[(&str, Option<Uuid>)],
+) -> Result<Vec<BatchItemResult>, ServerError> {
+    let mut results = Vec::new();
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    for (raw_id, id) in ids {
+        let (id, ingredient_id) = match id {
+            Some(id) => (id.to_string(), *id),
+            None => {
+                results.push(BatchItemResult {
+                    id: raw_id.to_string(),
+                    outcome: BatchOutcome::InvalidId,
+                });
+                continue;
+            }
+        };
+
+        let exists = sqlx::query("SELECT id FROM Ingredient WHERE id = ? FOR UPDATE")
+            .bind(&id)
+            .fetch_optional(&mut *transaction)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?
+            .is_some();
+
+        let outcome = if !exists {
+            BatchOutcome::NotFound
+        } else {
+            let references: i64 =
+                sqlx::query_scalar("SELECT COUNT(*) FROM UsedIngredient WHERE ingredient_id = ?")
+                    .bind(&id)
+                    .fetch_one(&mut *transaction)
+                    .await
+                    .map_err(|e| {
+                        error!("{e}");
+                        ServerError::DbError
+                    })?;
+
+            if references > 0 {
+                BatchOutcome::Referenced
+            } else {
+                sqlx::query("DELETE FROM Ingredient WHERE id = ?")
+                    .bind(&id)
+                    .execute(&mut *transaction)
+                    .await
+                    .map_err(|e| {
+                        error!("{e}");
+                        ServerError::DbError
+                    })?;
+                record_event(
+                    &mut *transaction,
+                    &DomainEvent::IngredientDeleted { ingredient_id },
+                )
+                .await?;
+                BatchOutcome::Deleted
+            }
+        };
+
+        debug!("Ingredient {id}: {outcome:?}");
+        results.push(BatchItemResult { id, outcome });
+    }
+
+    if results.iter().any(|r| r.outcome == BatchOutcome::Deleted) {
+        touch_collection(&mut *transaction, Collection::Ingredient).await?;
+    }
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(results)
+}
+
+/// Move the ingredients of a category to another category. The IDs of the selected ingredients are returned.
+///
+/// # Description
+///
+/// Ingredients of the category `from` are selected by a pattern of their name (`name`), their IDs (`ids`), or both.
+/// All the ingredients of the category are selected when no filter is given. Every moved ingredient is recorded as
+/// [DomainEvent::IngredientReclassified], which serves as the audit trail of the reclassification. When `dry_run` is
+/// set, the selected ingredients are returned without modifying them.
+#[instrument(skip(pool, ids))]
+pub async fn reclassify_ingredients_in_db(
+    pool: &MySqlPool,
+    from: &IngCategory,
+    to: &IngCategory,
+    name: Option<&str>,
+    ids: Option<&[Uuid]>,
+    dry_run: bool,
+) -> Result<Vec<Uuid>, ServerError> {
+    let db_error = |e: sqlx::Error| {
+        error!("{e}");
+        ServerError::DbError
     };
+    let mut transaction = pool.begin().await.map_err(db_error)?;
+
+    let mut query = String::from("SELECT id FROM Ingredient WHERE category = ?");
+    if name.is_some() {
+        query.push_str(" AND search_name LIKE ?");
+    }
+    if let Some(ids) = ids {
+        let placeholders = vec!["?"; ids.len()].join(",");
+        query.push_str(&format!(" AND id IN ({placeholders})"));
+    }
+    query.push_str(" ORDER BY id FOR UPDATE");
+
+    let mut query = sqlx::query_scalar::<_, String>(&query).bind(from.to_str());
+    if let Some(name) = name {
+        query = query.bind(search_pattern(name));
+    }
+    if let Some(ids) = ids {
+        for id in ids {
+            query = query.bind(id.to_string());
+        }
+    }
+
+    let selected = query
+        .fetch_all(&mut *transaction)
+        .await
+        .map_err(db_error)?
+        .iter()
+        .map(|id| {
+            Uuid::parse_str(id).map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })
+        })
+        .collect::<Result<Vec<Uuid>, ServerError>>()?;
+
+    if dry_run || selected.is_empty() {
+        return Ok(selected);
+    }
+
+    for ingredient_id in selected.iter() {
+        sqlx::query!(
+            "UPDATE Ingredient SET category = ? WHERE id = ?",
+            to.to_str(),
+            ingredient_id.to_string()
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(db_error)?;
+        record_event(
+            &mut *transaction,
+            &DomainEvent::IngredientReclassified {
+                ingredient_id: *ingredient_id,
+                from: from.clone(),
+                to: to.clone(),
+            },
+        )
+        .await?;
+    }
+    touch_collection(&mut *transaction, Collection::Ingredient).await?;
+
+    transaction.commit().await.map_err(db_error)?;
+
+    Ok(selected)
+}
+
+/// Outcome of [delete_ingredient_category_from_db].
+#[derive(Debug, PartialEq)]
+pub enum CategoryDeletion {
+    Deleted,
+    NotFound,
+    /// Some ingredient belongs to the category.
+    InUse,
+}
+
+/// Retrieve all the ingredient categories, sorted by name using the given collation (see [order_by_name]).
+#[instrument(skip(pool))]
+pub async fn get_ingredient_categories_from_db(
+    pool: &MySqlPool,
+    collation: Option<&Collation>,
+) -> Result<Vec<IngredientCategory>, ServerError> {
+    let query = format!(
+        r#"
+        SELECT c.name, c.description, COUNT(i.id)
+        FROM IngredientCategory c LEFT JOIN Ingredient i ON i.category = c.name
+        GROUP BY c.name, c.description
+        ORDER BY {}
+        "#,
+        order_by_name("c.name", collation)
+    );
+    let rows: Vec<(String, Option<String>, i64)> =
+        sqlx::query_as(&query).fetch_all(pool).await.map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(name, description, ingredients)| IngredientCategory {
+            name,
+            description,
+            ingredients,
+        })
+        .collect())
+}
+
+/// Check whether an ingredient category exists in the DB.
+#[instrument(skip(pool))]
+pub async fn ingredient_category_exists(
+    pool: &MySqlPool,
+    category: &IngCategory,
+) -> Result<bool, ServerError> {
+    let found = sqlx::query("SELECT name FROM IngredientCategory WHERE name = ?")
+        .bind(category.to_str())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    Ok(found.is_some())
+}
+
+/// Add a new ingredient category. `false` is returned when the category already exists.
+#[instrument(skip(pool))]
+pub async fn insert_ingredient_category(
+    pool: &MySqlPool,
+    category: &IngCategory,
+    description: Option<&str>,
+) -> Result<bool, ServerError> {
+    let result =
+        sqlx::query("INSERT IGNORE INTO IngredientCategory (name, description) VALUES (?, ?)")
+            .bind(category.to_str())
+            .bind(description)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Modify the description of an ingredient category. `false` is returned when the category doesn't exist.
+#[instrument(skip(pool))]
+pub async fn update_ingredient_category(
+    pool: &MySqlPool,
+    category: &IngCategory,
+    description: Option<&str>,
+) -> Result<bool, ServerError> {
+    if !ingredient_category_exists(pool, category).await? {
+        return Ok(false);
+    }
+
+    sqlx::query("UPDATE IngredientCategory SET description = ? WHERE name = ?")
+        .bind(description)
+        .bind(category.to_str())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    Ok(true)
+}
+
+/// Delete an ingredient category. Categories in use are kept.
+#[instrument(skip(pool))]
+pub async fn delete_ingredient_category_from_db(
+    pool: &MySqlPool,
+    category: &IngCategory,
+) -> Result<CategoryDeletion, ServerError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let exists = sqlx::query("SELECT name FROM IngredientCategory WHERE name = ? FOR UPDATE")
+        .bind(category.to_str())
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?
+        .is_some();
+    if !exists {
+        return Ok(CategoryDeletion::NotFound);
+    }
+
+    let ingredients: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Ingredient WHERE category = ?")
+        .bind(category.to_str())
+        .fetch_one(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+    if ingredients > 0 {
+        return Ok(CategoryDeletion::InUse);
+    }
+
+    sqlx::query("DELETE FROM IngredientCategory WHERE name = ?")
+        .bind(category.to_str())
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
 
-    let ingredient = Ingredient::parse(
-        Some(&raw_ingredient.id),
-        &raw_ingredient.name,
-        &raw_ingredient.category,
-        raw_ingredient.description.as_deref(),
-    )?;
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
 
-    Ok(Some(ingredient))
+    Ok(CategoryDeletion::Deleted)
 }
@@ -0,0 +1,212 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Collections of recipes for the landing pages of the frontend.
+//!
+//! # Description
+//!
+//! Two endpoints are available:
+//! - [get_category_recipes] serves the top recipes of a category.
+//! - [get_tag_featured] serves the featured recipes of a tag.
+//!
+//! Both return up to [LANDING_COLLECTION_SIZE] public recipes, ranked by their rating (unrated recipes go last), and
+//! then by their latest modification. Recipes pending moderation are never listed. Collections are cached by the
+//! server until any recipe changes (see [LandingCache]), and the responses allow caching by shared caches for
+//! [LANDING_MAX_AGE] seconds.
+
+use crate::{
+    domain::{ApiError, Recipe, RecipeCategory, ServerError, Tag},
+    routes::recipe::get_recipe_from_db,
+    utils::{
+        changes::{get_collection_last_modified, Collection},
+        http::{is_not_modified, last_modified},
+        landing::{LandingCache, LandingCollection, LANDING_COLLECTION_SIZE},
+    },
+};
+use actix_web::{
+    get,
+    http::header::{CacheControl, CacheDirective, ContentType},
+    web::{Data, Path},
+    HttpRequest, HttpResponse,
+};
+use sqlx::MySqlPool;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+
+/// Amount of seconds that shared caches (CDNs, reverse proxies) can serve a landing collection without revalidating.
+pub const LANDING_MAX_AGE: u32 = 60;
+
+/// Top recipes of a category (Public).
+///
+/// # Description
+///
+/// The response includes a `Last-Modified` header, and requests that include an up to date `If-Modified-Since` header
+/// are answered with a code **304**. A category with no public recipes is answered with an empty list.
+#[utoipa::path(
+    get,
+    path = "/category/{category}/recipes",
+    tag = "Landing",
+    params(
+        ("category" = RecipeCategory, Path, description = "Category of the recipes."),
+        ("If-Modified-Since" = Option<String>, Header, description = "HTTP date of the cached collection."),
+    ),
+    responses(
+        (status = 200, description = "The top recipes of the category.", body = [Recipe]),
+        (status = 304, description = "No recipe changed since the given date."),
+        (status = 400, description = "The given category doesn't exist."),
+    )
+)]
+#[instrument(skip(request, pool, cache))]
+#[get("/category/{category}/recipes")]
+pub async fn get_category_recipes(
+    category: Path<String>,
+    request: HttpRequest,
+    pool: Data<MySqlPool>,
+    cache: Data<LandingCache>,
+) -> Result<HttpResponse, ApiError> {
+    let Ok(category) = RecipeCategory::try_from(category.as_str()) else {
+        info!("Unknown recipe category: {category}");
+        return Ok(HttpResponse::BadRequest().finish());
+    };
+
+    landing_response(
+        &request,
+        &pool,
+        &cache,
+        LandingCollection::Category(category.to_string()),
+    )
+    .await
+}
+
+/// Featured recipes of a tag (Public).
+///
+/// # Description
+///
+/// The response includes a `Last-Modified` header, and requests that include an up to date `If-Modified-Since` header
+/// are answered with a code **304**. A tag with no public recipes is answered with an empty list.
+#[utoipa::path(
+    get,
+    path = "/tag/{tag}/featured",
+    tag = "Landing",
+    params(
+        ("tag" = String, Path, description = "Identifier of the tag.", example = "tequila"),
+        ("If-Modified-Since" = Option<String>, Header, description = "HTTP date of the cached collection."),
+    ),
+    responses(
+        (status = 200, description = "The featured recipes of the tag.", body = [Recipe]),
+        (status = 304, description = "No recipe changed since the given date."),
+        (status = 400, description = "The given tag is not valid."),
+    )
+)]
+#[instrument(skip(request, pool, cache))]
+#[get("/tag/{tag}/featured")]
+pub async fn get_tag_featured(
+    tag: Path<String>,
+    request: HttpRequest,
+    pool: Data<MySqlPool>,
+    cache: Data<LandingCache>,
+) -> Result<HttpResponse, ApiError> {
+    let Ok(tag) = Tag::new(tag.trim()) else {
+        info!("Invalid tag: {tag}");
+        return Ok(HttpResponse::BadRequest().finish());
+    };
+
+    landing_response(
+        &request,
+        &pool,
+        &cache,
+        LandingCollection::Tag(tag.identifier),
+    )
+    .await
+}
+
+/// Serve a landing collection from the cache, or build it when the recipes changed.
+async fn landing_response(
+    request: &HttpRequest,
+    pool: &MySqlPool,
+    cache: &LandingCache,
+    collection: LandingCollection,
+) -> Result<HttpResponse, ApiError> {
+    let version = get_collection_last_modified(pool, Collection::Recipe).await?;
+    if let Some(timestamp) = version {
+        if is_not_modified(request, timestamp) {
+            info!("No recipe changed since the given date");
+            return Ok(HttpResponse::NotModified()
+                .insert_header(last_modified(timestamp))
+                .insert_header(cache_control())
+                .finish());
+        }
+    }
+
+    let body = match cache.get(&collection, version) {
+        Some(body) => body,
+        None => {
+            info!("Building the landing collection {collection:?}");
+            let mut recipes: Vec<Recipe> = Vec::new();
+            for id in top_recipes_from_db(pool, &collection).await? {
+                if let Some(recipe) = get_recipe_from_db(pool, &id).await? {
+                    recipes.push(recipe);
+                }
+            }
+            cache.store(collection, version, serde_json::to_string(&recipes)?)
+        }
+    };
+
+    let mut response = HttpResponse::Ok();
+    response
+        .insert_header(ContentType::json())
+        .insert_header(cache_control());
+    if let Some(timestamp) = version {
+        response.insert_header(last_modified(timestamp));
+    }
+
+    Ok(response.body(body.as_str().to_owned()))
+}
+
+fn cache_control() -> CacheControl {
+    CacheControl(vec![
+        CacheDirective::Public,
+        CacheDirective::MaxAge(LANDING_MAX_AGE),
+    ])
+}
+
+/// Retrieve the IDs of the top public recipes of a landing collection.
+async fn top_recipes_from_db(
+    pool: &MySqlPool,
+    collection: &LandingCollection,
+) -> Result<Vec<Uuid>, ServerError> {
+    let (filter, value) = match collection {
+        LandingCollection::Category(category) => ("c.category = ?", category),
+        LandingCollection::Tag(tag) => (
+            "c.id IN (SELECT cocktail_id FROM Tagged WHERE tag = ?)",
+            tag,
+        ),
+    };
+
+    // NULL ratings are sorted last when using a descending order.
+    let ids: Vec<String> = sqlx::query_scalar(&format!(
+        "SELECT c.id FROM Cocktail c \
+        WHERE {filter} AND c.state = 'published' AND c.id NOT IN (SELECT cocktail_id FROM ModerationQueue) \
+        ORDER BY c.rating DESC, c.update_date DESC, c.id LIMIT ?"
+    ))
+    .bind(value)
+    .bind(LANDING_COLLECTION_SIZE as u32)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    ids.iter()
+        .map(|id| {
+            Uuid::parse_str(id).map_err(|_| {
+                error!("Failed to parse ID from a value of the DB");
+                ServerError::DbError
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,318 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Subscriptions of the clients to digests of new recipes.
+//!
+//! # Description
+//!
+//! Clients of the API can subscribe to a daily or weekly email that lists the new public recipes that match some
+//! filters (tags and categories). A client can hold up to [MAX_DIGESTS_PER_CLIENT] subscriptions, and the emails are
+//! sent to the address of the client. The digests are generated by a background job, see [crate::jobs].
+//!
+//! Filters follow the rules of the recipe searches: recipes must include all the given tags, and belong to any of the
+//! given categories. Empty filters match every recipe. A digest can also be bound to a saved search of the client (see
+//! [crate::routes::me::searches]), in which case only the new recipes that match the search are listed.
+
+use crate::{
+    authentication::{
+        access_denied_response, check_access, key_client_id, AccessControl, AuthData, Scope,
+    },
+    domain::{ApiError, IdGenerator, RecipeCategory, ResourceId, Tag},
+    routes::me::utils::{
+        delete_digest_from_db, get_digests_from_db, get_search_from_db, insert_digest_in_db,
+    },
+};
+use actix_web::{
+    delete, get, post,
+    web::{Data, Json, Query},
+    HttpResponse,
+};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use std::fmt;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Maximum amount of digests that a client can subscribe to.
+pub const MAX_DIGESTS_PER_CLIENT: usize = 5;
+
+/// Maximum amount of tags of the filters of a digest.
+pub const MAX_DIGEST_TAGS: usize = 10;
+
+/// How often a digest is sent.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFrequency {
+    Daily,
+    Weekly,
+}
+
+impl fmt::Display for DigestFrequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DigestFrequency::Daily => write!(f, "daily"),
+            DigestFrequency::Weekly => write!(f, "weekly"),
+        }
+    }
+}
+
+impl TryFrom<&str> for DigestFrequency {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "daily" => Ok(DigestFrequency::Daily),
+            "weekly" => Ok(DigestFrequency::Weekly),
+            _ => Err(format!("Unknown digest frequency: {value}")),
+        }
+    }
+}
+
+/// Payload to subscribe to a digest.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct DigestRequest {
+    pub frequency: DigestFrequency,
+    /// ID of a saved search of the client whose results filter the recipes of the digest.
+    #[serde(default)]
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub search_id: Option<String>,
+    /// Recipes must include all these tags.
+    #[serde(default)]
+    #[schema(example = json!(["tequila", "citrus"]))]
+    pub tags: Vec<String>,
+    /// Recipes must belong to any of these categories.
+    #[serde(default)]
+    pub categories: Vec<RecipeCategory>,
+}
+
+/// Digest subscribed by a client.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct Digest {
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub id: String,
+    pub frequency: DigestFrequency,
+    /// ID of the saved search used by the digest, if any.
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub search_id: Option<String>,
+    pub tags: Vec<String>,
+    pub categories: Vec<RecipeCategory>,
+    #[schema(value_type = String, example = "2025-09-11T08:58:56Z")]
+    pub created_at: DateTime<Utc>,
+    /// Time of the latest digest, if any was sent.
+    #[schema(value_type = Option<String>, example = "2025-09-12T08:58:56Z")]
+    pub last_sent_at: Option<DateTime<Utc>>,
+}
+
+/// List the digests subscribed by the client.
+#[utoipa::path(
+    get,
+    path = "/me/digests",
+    tag = "Me",
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The digests of the client, oldest first.", body = [Digest]),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("/digests")]
+pub async fn get_digests(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+
+    Ok(HttpResponse::Ok().json(get_digests_from_db(&pool, &client_id).await?))
+}
+
+/// Subscribe to a digest of new recipes.
+///
+/// # Description
+///
+/// A code **409** is returned when the client already holds [MAX_DIGESTS_PER_CLIENT] digests. The first digest is
+/// sent once the period given by the frequency elapses, and it only includes the recipes published since the
+/// subscription. No email is sent when no new recipe matches the filters.
+#[utoipa::path(
+    post,
+    path = "/me/digests",
+    tag = "Me",
+    request_body(
+        content = DigestRequest,
+        example = json!({"frequency": "weekly", "tags": ["tequila"], "categories": ["easy", "medium"]})
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 201, description = "The new digest.", body = Digest),
+        (status = 400, description = "Some of the given tags is not valid, too many tags were given, or the client has no saved search identified by the given search ID."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (status = 409, description = "The client holds the maximum amount of digests."),
+    )
+)]
+#[instrument(skip(pool, token, ids, req, access))]
+#[post("/digests")]
+pub async fn post_digest(
+    req: Json<DigestRequest>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+    ids: Data<dyn IdGenerator>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let mut tags = Vec::new();
+    for tag in req.tags.iter() {
+        match Tag::new(tag.trim()) {
+            Ok(tag) if !tags.contains(&tag.identifier) => tags.push(tag.identifier),
+            Ok(_) => (),
+            Err(_) => {
+                info!("Invalid tag given in the filters of the digest");
+                return Ok(HttpResponse::BadRequest().body(format!("Invalid tag: {tag}")));
+            }
+        }
+    }
+    if tags.len() > MAX_DIGEST_TAGS {
+        info!("Too many tags given in the filters of the digest");
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    let mut categories = Vec::new();
+    for category in req.categories.iter() {
+        if !categories.contains(category) {
+            categories.push(category.clone());
+        }
+    }
+
+    let client_id = key_client_id(&token.api_key)?;
+
+    let search_id = match req.search_id.as_deref().map(Uuid::parse_str) {
+        Some(Ok(id)) => match get_search_from_db(&pool, &client_id, &id).await? {
+            Some(_) => Some(id),
+            None => {
+                info!("The client ({client_id}) has no saved search ({id})");
+                return Ok(HttpResponse::BadRequest().body(format!("Unknown saved search: {id}")));
+            }
+        },
+        Some(Err(_)) => {
+            info!("Invalid search ID given for the digest");
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+        None => None,
+    };
+
+    if get_digests_from_db(&pool, &client_id).await?.len() >= MAX_DIGESTS_PER_CLIENT {
+        info!("The client ({client_id}) holds the maximum amount of digests");
+        return Ok(HttpResponse::Conflict().finish());
+    }
+
+    let id = ids.new_id();
+    insert_digest_in_db(
+        &pool,
+        &id,
+        &client_id,
+        req.frequency,
+        search_id.as_ref(),
+        &tags,
+        &categories,
+    )
+    .await?;
+    info!("New digest ({id}) subscribed by the client ({client_id})");
+
+    let digest = get_digests_from_db(&pool, &client_id)
+        .await?
+        .into_iter()
+        .find(|d| d.id == id.to_string())
+        .context("The new digest was not found in the DB")?;
+
+    Ok(HttpResponse::Created().json(digest))
+}
+
+/// Unsubscribe from a digest.
+#[utoipa::path(
+    delete,
+    path = "/me/digests/{id}",
+    tag = "Me",
+    params(("id" = String, Path, description = "ID of the digest.")),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 204, description = "The digest was deleted."),
+        (status = 400, description = "The given ID has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (status = 404, description = "The client has no digest identified by the given ID."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[delete("/digests/{id}")]
+pub async fn delete_digest(
+    digest_id: ResourceId,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+
+    if delete_digest_from_db(&pool, &client_id, digest_id.as_uuid()).await? {
+        info!("Digest ({digest_id}) of the client ({client_id}) deleted");
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case(DigestFrequency::Daily, "daily")]
+    #[case(DigestFrequency::Weekly, "weekly")]
+    fn frequencies_match_the_db(#[case] frequency: DigestFrequency, #[case] expected: &str) {
+        assert_eq!(
+            serde_json::to_string(&frequency).unwrap(),
+            format!("\"{expected}\"")
+        );
+        assert_eq!(frequency.to_string(), expected);
+        assert_eq!(DigestFrequency::try_from(expected), Ok(frequency));
+    }
+
+    #[rstest]
+    fn filters_are_optional() {
+        let req: DigestRequest = serde_json::from_str(r#"{"frequency": "daily"}"#).unwrap();
+        assert!(req.search_id.is_none());
+        assert!(req.tags.is_empty());
+        assert!(req.categories.is_empty());
+    }
+}
@@ -0,0 +1,341 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Inventory of the ingredients that the clients have at hand.
+//!
+//! # Description
+//!
+//! Clients of the API can keep the list of the ingredients they have at home, optionally including the amount of
+//! each ingredient. A client can hold up to [MAX_INVENTORY_SIZE] ingredients. The inventory is used by
+//! `GET /recipe/suggest` when no explicit list of ingredients is given.
+
+use crate::{
+    authentication::{
+        access_denied_response, check_access, key_client_id, AccessControl, AuthData, Scope,
+    },
+    domain::{collation::NameCollations, ApiError, ClientId, Language, QuantityUnit, ResourceId},
+    routes::me::utils::{
+        delete_inventory_item_from_db, get_inventory_from_db, get_preferences_from_db,
+        replace_inventory_in_db, set_inventory_item_in_db,
+    },
+    utils::http::accepted_language,
+};
+use actix_web::{
+    delete, get, put,
+    web::{Data, Json, Query},
+    HttpRequest, HttpResponse,
+};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use std::collections::HashSet;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+
+/// Maximum amount of ingredients that a client can hold in the inventory.
+pub const MAX_INVENTORY_SIZE: usize = 200;
+
+/// Ingredient held by a client.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct InventoryItem {
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub ingredient_id: String,
+    /// Name of the ingredient.
+    #[schema(example = "Tequila blanco")]
+    pub name: String,
+    #[schema(example = 700.0)]
+    pub quantity: Option<f32>,
+    pub unit: Option<QuantityUnit>,
+    #[schema(value_type = String, example = "2025-09-11T08:58:56Z")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Amount of an ingredient at hand. Both attributes are given, or none of them.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct InventoryAmount {
+    #[schema(example = 700.0)]
+    pub quantity: Option<f32>,
+    pub unit: Option<QuantityUnit>,
+}
+
+impl InventoryAmount {
+    fn is_valid(&self) -> bool {
+        match (self.quantity, self.unit) {
+            (None, None) => true,
+            (Some(quantity), Some(_)) => quantity.is_finite() && quantity > 0.0,
+            _ => false,
+        }
+    }
+}
+
+/// Ingredient of an inventory given by a client.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct InventoryEntry {
+    #[schema(value_type = String, example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub ingredient_id: ResourceId,
+    #[schema(example = 700.0)]
+    pub quantity: Option<f32>,
+    pub unit: Option<QuantityUnit>,
+}
+
+impl InventoryEntry {
+    fn amount(&self) -> InventoryAmount {
+        InventoryAmount {
+            quantity: self.quantity,
+            unit: self.unit,
+        }
+    }
+}
+
+/// List the ingredients in the inventory of the client.
+///
+/// # Description
+///
+/// Ingredients are sorted by name following the rules of the language preferred by the client (see
+/// `GET /me/preferences`), or the language given by the `Accept-Language` header when the client has no preference.
+#[utoipa::path(
+    get,
+    path = "/me/inventory",
+    tag = "Me",
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The inventory of the client, sorted by the name of the ingredients.", body = [InventoryItem]),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, request, collations, access))]
+#[get("/inventory")]
+pub async fn get_inventory(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    collations: Data<NameCollations>,
+    token: Query<AuthData>,
+    request: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+    let language = sort_language(&pool, &client_id, &request).await?;
+
+    Ok(HttpResponse::Ok()
+        .json(get_inventory_from_db(&pool, &client_id, collations.get(language)).await?))
+}
+
+/// Replace the inventory of the client.
+///
+/// # Description
+///
+/// The given list replaces the whole inventory, so an empty list clears it. Ingredients can't be repeated, and the
+/// list is limited to [MAX_INVENTORY_SIZE] ingredients.
+#[utoipa::path(
+    put,
+    path = "/me/inventory",
+    tag = "Me",
+    request_body(
+        content = [InventoryEntry],
+        example = json!([{"ingredient_id": "0191e13b-5ab7-78f1-bc06-be503a6c111b", "quantity": 700, "unit": "ml"}])
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The new inventory of the client.", body = [InventoryItem]),
+        (status = 400, description = "The list is too long, repeats or includes unknown ingredients, or some amount is not valid."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+    )
+)]
+#[instrument(skip(pool, token, req, request, collations, access))]
+#[put("/inventory")]
+pub async fn put_inventory(
+    req: Json<Vec<InventoryEntry>>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    collations: Data<NameCollations>,
+    token: Query<AuthData>,
+    request: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let entries = req.into_inner();
+    if entries.len() > MAX_INVENTORY_SIZE {
+        info!("The inventory exceeds the maximum size");
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+    let ingredients: HashSet<ResourceId> = entries.iter().map(|e| e.ingredient_id).collect();
+    if ingredients.len() != entries.len() {
+        info!("The inventory includes repeated ingredients");
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+    if !entries.iter().all(|e| e.amount().is_valid()) {
+        info!("The inventory includes invalid amounts");
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    let client_id = key_client_id(&token.api_key)?;
+    if !replace_inventory_in_db(&pool, &client_id, &entries).await? {
+        info!("The inventory includes unknown ingredients");
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+    info!("Inventory of the client ({client_id}) replaced");
+    let language = sort_language(&pool, &client_id, &request).await?;
+
+    Ok(HttpResponse::Ok()
+        .json(get_inventory_from_db(&pool, &client_id, collations.get(language)).await?))
+}
+
+/// Add an ingredient to the inventory of the client, or update its amount.
+#[utoipa::path(
+    put,
+    path = "/me/inventory/{id}",
+    tag = "Me",
+    params(("id" = String, Path, description = "ID of the ingredient.")),
+    request_body(content = InventoryAmount, example = json!({"quantity": 700, "unit": "ml"})),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The ingredient in the inventory.", body = InventoryItem),
+        (status = 400, description = "The given ID has an invalid format, or the amount is not valid."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (status = 404, description = "The ingredient doesn't exist."),
+    )
+)]
+#[instrument(skip(pool, token, req, access))]
+#[put("/inventory/{id}")]
+pub async fn put_inventory_item(
+    ingredient_id: ResourceId,
+    req: Json<InventoryAmount>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    if !req.is_valid() {
+        info!("Invalid amount given for the ingredient");
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    let client_id = key_client_id(&token.api_key)?;
+    if !set_inventory_item_in_db(
+        &pool,
+        &client_id,
+        ingredient_id.as_uuid(),
+        req.quantity,
+        req.unit,
+    )
+    .await?
+    {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    info!("Ingredient ({ingredient_id}) stored in the inventory of the client ({client_id})");
+
+    // A single item is taken, so the inventory is not sorted by any collation.
+    let item = get_inventory_from_db(&pool, &client_id, None)
+        .await?
+        .into_iter()
+        .find(|i| i.ingredient_id == ingredient_id.to_string())
+        .context("The ingredient was not found in the inventory")?;
+
+    Ok(HttpResponse::Ok().json(item))
+}
+
+/// Remove an ingredient from the inventory of the client.
+#[utoipa::path(
+    delete,
+    path = "/me/inventory/{id}",
+    tag = "Me",
+    params(("id" = String, Path, description = "ID of the ingredient.")),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 204, description = "The ingredient was removed from the inventory."),
+        (status = 400, description = "The given ID has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (status = 404, description = "The ingredient is not in the inventory of the client."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[delete("/inventory/{id}")]
+pub async fn delete_inventory_item(
+    ingredient_id: ResourceId,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+
+    if delete_inventory_item_from_db(&pool, &client_id, ingredient_id.as_uuid()).await? {
+        info!(
+            "Ingredient ({ingredient_id}) removed from the inventory of the client ({client_id})"
+        );
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+/// Language whose rules sort the inventory of a client: the language preferred by the client, or the language of the
+/// request.
+async fn sort_language(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+    request: &HttpRequest,
+) -> Result<Language, ApiError> {
+    let preferences = get_preferences_from_db(pool, client_id).await?;
+
+    Ok(preferences
+        .lang
+        .or_else(|| accepted_language(request))
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(None, None, true)]
+    #[case(Some(70.0), Some(QuantityUnit::MilliLiter), true)]
+    #[case(Some(70.0), None, false)]
+    #[case(None, Some(QuantityUnit::Unit), false)]
+    #[case(Some(0.0), Some(QuantityUnit::Unit), false)]
+    #[case(Some(f32::NAN), Some(QuantityUnit::Unit), false)]
+    fn amounts_are_checked(
+        #[case] quantity: Option<f32>,
+        #[case] unit: Option<QuantityUnit>,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(InventoryAmount { quantity, unit }.is_valid(), expected);
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Notifications of the clients.
+//!
+//! # Description
+//!
+//! System events that concern a client of the API are kept as notifications, so frontends have a single inbox to
+//! show to their users:
+//! - An API key of the client is about to expire (see [crate::jobs::run_notifications]).
+//! - A recipe registered by the client was approved by an administrator.
+//!
+//! Every event is notified once. Notifications are mirrored to the email of the clients that enable
+//! `email_notifications` in their preferences (see `PATCH /me/preferences`).
+
+use crate::{
+    authentication::{
+        access_denied_response, check_access, key_client_id, AccessControl, AuthData, Scope,
+    },
+    domain::{ApiError, DataDomainError, IdGenerator, ResourceId, ServerError},
+    routes::me::utils::{
+        get_notifications_from_db, get_recipe_client_from_db, mark_all_notifications_read_in_db,
+        mark_notification_read_in_db, store_notification_in_db,
+    },
+};
+use actix_web::{
+    get, post,
+    web::{Data, Query},
+    HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::MySqlPool;
+use std::fmt;
+use tracing::{debug, info, instrument};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Maximum amount of notifications returned by a single request.
+pub const MAX_NOTIFICATIONS: u32 = 100;
+
+/// Events that produce notifications.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// An API key of the client is about to expire. The subject is the ID of the key.
+    TokenExpiring,
+    /// A recipe registered by the client was approved. The subject is the ID of the recipe.
+    RecipeApproved,
+}
+
+impl fmt::Display for NotificationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotificationKind::TokenExpiring => write!(f, "token_expiring"),
+            NotificationKind::RecipeApproved => write!(f, "recipe_approved"),
+        }
+    }
+}
+
+impl TryFrom<&str> for NotificationKind {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "token_expiring" => Ok(NotificationKind::TokenExpiring),
+            "recipe_approved" => Ok(NotificationKind::RecipeApproved),
+            _ => Err(DataDomainError::InvalidData),
+        }
+    }
+}
+
+/// Notification of a client.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct Notification {
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub id: String,
+    pub kind: NotificationKind,
+    /// ID of the resource that the notification refers to.
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub subject_id: String,
+    #[schema(example = "Your recipe \"Mojito\" was approved and it is public now.")]
+    pub message: String,
+    #[schema(value_type = String, example = "2025-09-11T08:58:56Z")]
+    pub created_at: DateTime<Utc>,
+    /// When the notification was marked as read. Unread notifications have no value.
+    #[schema(value_type = Option<String>, example = "2025-09-11T09:12:03Z")]
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct NotificationQuery {
+    /// Only list the notifications that were not read yet.
+    pub unread: Option<bool>,
+}
+
+/// List the notifications of the client.
+///
+/// # Description
+///
+/// Notifications are sorted newest first, and up to [MAX_NOTIFICATIONS] notifications are returned.
+#[utoipa::path(
+    get,
+    path = "/me/notifications",
+    tag = "Me",
+    params(NotificationQuery),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The notifications of the client.", body = [Notification]),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("/notifications")]
+pub async fn get_notifications(
+    req: Query<NotificationQuery>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+    let notifications = get_notifications_from_db(
+        &pool,
+        &client_id,
+        req.unread.unwrap_or_default(),
+        MAX_NOTIFICATIONS,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(notifications))
+}
+
+/// Mark all the notifications of the client as read.
+#[utoipa::path(
+    post,
+    path = "/me/notifications/read",
+    tag = "Me",
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (
+            status = 200,
+            description = "The amount of notifications marked as read.",
+            content_type = "application/json",
+            example = json!({"read": 3}),
+        ),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[post("/notifications/read")]
+pub async fn read_all_notifications(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+    let read = mark_all_notifications_read_in_db(&pool, &client_id).await?;
+    info!("{read} notifications of the client ({client_id}) marked as read");
+
+    Ok(HttpResponse::Ok().json(json!({"read": read})))
+}
+
+/// Mark a notification of the client as read.
+#[utoipa::path(
+    post,
+    path = "/me/notifications/{id}/read",
+    tag = "Me",
+    params(("id" = String, Path, description = "ID of the notification.")),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 204, description = "The notification was marked as read."),
+        (status = 400, description = "The given ID has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (status = 404, description = "The client has no notification with the given ID."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[post("/notifications/{id}/read")]
+pub async fn read_notification(
+    notification_id: ResourceId,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+
+    if mark_notification_read_in_db(&pool, &client_id, notification_id.as_uuid()).await? {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+/// Notify the client that registered a recipe that the recipe was approved.
+///
+/// # Description
+///
+/// Recipes registered before the clients were tracked, or whose client was removed, produce no notification.
+#[instrument(skip(pool, ids))]
+pub(crate) async fn notify_recipe_approved(
+    pool: &MySqlPool,
+    ids: &dyn IdGenerator,
+    recipe_id: &Uuid,
+) -> Result<(), ServerError> {
+    let Some((client_id, name)) = get_recipe_client_from_db(pool, recipe_id).await? else {
+        debug!("The recipe {recipe_id} has no client to notify");
+        return Ok(());
+    };
+
+    store_notification_in_db(
+        pool,
+        ids,
+        &client_id,
+        NotificationKind::RecipeApproved,
+        &recipe_id.to_string(),
+        &format!("Your recipe \"{name}\" was approved and it is public now."),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case(NotificationKind::TokenExpiring)]
+    #[case(NotificationKind::RecipeApproved)]
+    fn kinds_round_trip(#[case] kind: NotificationKind) {
+        assert_eq!(
+            NotificationKind::try_from(kind.to_string().as_str()).ok(),
+            Some(kind)
+        );
+        assert_eq!(serde_json::to_value(kind).unwrap(), json!(kind.to_string()));
+    }
+}
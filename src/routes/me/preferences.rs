@@ -0,0 +1,210 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Display preferences of the clients.
+//!
+//! # Description
+//!
+//! Clients of the API can store the [UnitSystem] and the [Language] in which they prefer to receive the recipes.
+//! Preferences are applied to the recipes served by `GET /recipe` and `GET /recipe/{id}` when the request includes
+//! the API key of the client. The `units` and `lang` query parameters of those requests override the stored
+//! preferences (see [DisplayQuery]).
+//!
+//! Clients can also ask for their notifications to be mirrored to their email (see
+//! [crate::routes::me::notifications]).
+
+use crate::{
+    authentication::{
+        access_denied_response, check_access, key_client_id, AccessControl, AuthData, Scope,
+    },
+    domain::{ApiError, Language, Recipe, UnitSystem},
+    routes::me::utils::{get_preferences_from_db, store_preferences_in_db},
+};
+use actix_web::{
+    get, patch,
+    web::{Data, Json, Query},
+    HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+use utoipa::{IntoParams, ToSchema};
+
+/// Preferences of a client. Missing values leave the recipes as stored by their authors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct Preferences {
+    pub units: Option<UnitSystem>,
+    pub lang: Option<Language>,
+    /// Mirror the notifications of the client to its email.
+    pub email_notifications: Option<bool>,
+}
+
+impl Preferences {
+    /// Apply the values given in the query of a request over the preferences.
+    pub fn overridden_by(self, query: &DisplayQuery) -> Self {
+        Preferences {
+            units: query.units.or(self.units),
+            lang: query.lang.or(self.lang),
+            email_notifications: self.email_notifications,
+        }
+    }
+
+    /// Apply the unit system of the preferences to a recipe.
+    pub fn apply(&self, recipe: Recipe) -> Recipe {
+        match self.units {
+            Some(units) => recipe.with_unit_system(units),
+            None => recipe,
+        }
+    }
+}
+
+/// Partial definition of the preferences. Only the given attributes are modified.
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct PreferencesPatch {
+    pub units: Option<UnitSystem>,
+    pub lang: Option<Language>,
+    pub email_notifications: Option<bool>,
+}
+
+/// Query parameters that override the preferences of the client for a single request.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct DisplayQuery {
+    /// Unit system of the amounts of the ingredients: `metric` or `imperial`.
+    pub units: Option<UnitSystem>,
+    /// Language of the generated content: `en` or `es`.
+    pub lang: Option<Language>,
+}
+
+/// Retrieve the preferences of the client.
+#[utoipa::path(
+    get,
+    path = "/me/preferences",
+    tag = "Me",
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The preferences of the client.", body = Preferences),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("/preferences")]
+pub async fn get_preferences(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+
+    Ok(HttpResponse::Ok().json(get_preferences_from_db(&pool, &client_id).await?))
+}
+
+/// Modify the preferences of the client.
+#[utoipa::path(
+    patch,
+    path = "/me/preferences",
+    tag = "Me",
+    request_body(content = PreferencesPatch, example = json!({"units": "metric", "lang": "es"})),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The modified preferences of the client.", body = Preferences),
+        (status = 400, description = "The unit system or the language are not supported."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[patch("/preferences")]
+pub async fn patch_preferences(
+    req: Json<PreferencesPatch>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+    let preferences = get_preferences_from_db(&pool, &client_id).await?;
+    let preferences = Preferences {
+        units: req.units.or(preferences.units),
+        lang: req.lang.or(preferences.lang),
+        email_notifications: req.email_notifications.or(preferences.email_notifications),
+    };
+    store_preferences_in_db(&pool, &client_id, &preferences).await?;
+    info!("Preferences of the client ({client_id}) modified");
+
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+/// Resolve the preferences that apply to a request.
+///
+/// # Description
+///
+/// The stored preferences are only looked up when the request includes an API key. The errors of [check_access] are
+/// returned, so handlers can answer them using [access_denied_response].
+pub(crate) async fn request_preferences(
+    pool: &MySqlPool,
+    access: &AccessControl,
+    token: Option<&AuthData>,
+    query: &DisplayQuery,
+) -> Result<Preferences, ApiError> {
+    let preferences = match token {
+        Some(token) => {
+            check_access(pool, access, &token.api_key, Scope::Read).await?;
+            get_preferences_from_db(pool, &key_client_id(&token.api_key)?).await?
+        }
+        None => Preferences::default(),
+    };
+
+    Ok(preferences.overridden_by(query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn queries_override_the_preferences() {
+        let preferences = Preferences {
+            units: Some(UnitSystem::Imperial),
+            lang: Some(Language::Es),
+            email_notifications: Some(true),
+        };
+        let query = DisplayQuery {
+            units: Some(UnitSystem::Metric),
+            lang: None,
+        };
+
+        assert_eq!(
+            preferences.overridden_by(&query),
+            Preferences {
+                units: Some(UnitSystem::Metric),
+                lang: Some(Language::Es),
+                email_notifications: Some(true),
+            }
+        );
+        assert_eq!(
+            Preferences::default().overridden_by(&DisplayQuery::default()),
+            Preferences::default()
+        );
+    }
+}
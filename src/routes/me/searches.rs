@@ -0,0 +1,268 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Saved searches of the clients.
+//!
+//! # Description
+//!
+//! Clients of the API can store the queries of the recipe searches they run often (see [RecipeQuery]), and execute
+//! them later by name. A client can hold up to [MAX_SEARCHES_PER_CLIENT] saved searches, whose names must be unique.
+//! Saved searches use the same engine as `GET /recipe` ([RecipeSearch]), and they can also be used as the filters of
+//! a digest (see [crate::routes::me::digests]).
+
+use crate::{
+    authentication::{
+        access_denied_response, check_access, key_client_id, AccessControl, AuthData, Scope,
+    },
+    domain::{
+        collation::NameCollations,
+        sanitize::{sanitize_text, SanitizeLevel},
+        ApiError, IdGenerator, Language, RecipeQuery, ResourceId,
+    },
+    routes::{
+        me::utils::{
+            delete_search_from_db, get_search_from_db, get_searches_from_db, insert_search_in_db,
+        },
+        recipe::RecipeSearch,
+    },
+};
+use actix_web::{
+    delete, get, post,
+    web::{Data, Json, Query},
+    HttpResponse,
+};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+
+/// Maximum amount of saved searches that a client can hold.
+pub const MAX_SEARCHES_PER_CLIENT: usize = 20;
+
+/// Maximum length of the name of a saved search.
+pub const MAX_SEARCH_NAME_LENGTH: usize = 40;
+
+/// Payload to save a search.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct SearchRequest {
+    /// Name of the search, i.e. `tequila classics`.
+    #[schema(example = "tequila classics")]
+    pub name: String,
+    pub query: RecipeQuery,
+}
+
+/// Search saved by a client.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct SavedSearch {
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub id: String,
+    #[schema(example = "tequila classics")]
+    pub name: String,
+    pub query: RecipeQuery,
+    #[schema(value_type = String, example = "2025-09-11T08:58:56Z")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// List the searches saved by the client.
+#[utoipa::path(
+    get,
+    path = "/me/searches",
+    tag = "Me",
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The saved searches of the client, oldest first.", body = [SavedSearch]),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("/searches")]
+pub async fn get_searches(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+
+    Ok(HttpResponse::Ok().json(get_searches_from_db(&pool, &client_id).await?))
+}
+
+/// Save a search.
+///
+/// # Description
+///
+/// The query follows the rules of the searches of `GET /recipe`. A code **409** is returned when the client already
+/// holds [MAX_SEARCHES_PER_CLIENT] saved searches, or a search with the same name.
+#[utoipa::path(
+    post,
+    path = "/me/searches",
+    tag = "Me",
+    request_body(
+        content = SearchRequest,
+        example = json!({"name": "tequila classics", "query": {"tags": "tequila,classic", "max_prep_time": 10}})
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 201, description = "The new saved search.", body = SavedSearch),
+        (status = 400, description = "The name is empty or too long, or the query is not valid."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (status = 409, description = "The client holds the maximum amount of saved searches, or a search with the same name."),
+    )
+)]
+#[instrument(skip(pool, token, ids, req, access, sanitize_level))]
+#[post("/searches")]
+pub async fn post_search(
+    req: Json<SearchRequest>,
+    pool: Data<MySqlPool>,
+    sanitize_level: Data<SanitizeLevel>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+    ids: Data<dyn IdGenerator>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let req = req.into_inner();
+    let name = sanitize_text(req.name.trim(), **sanitize_level);
+    if name.is_empty() || name.chars().count() > MAX_SEARCH_NAME_LENGTH {
+        info!("The given name is invalid");
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+    if let Err(e) = RecipeSearch::new(req.query.clone()) {
+        info!("Invalid query given for the saved search: {e}");
+        return Ok(HttpResponse::BadRequest().body(e));
+    }
+
+    let client_id = key_client_id(&token.api_key)?;
+    let searches = get_searches_from_db(&pool, &client_id).await?;
+    if searches.len() >= MAX_SEARCHES_PER_CLIENT {
+        info!("The client ({client_id}) holds the maximum amount of saved searches");
+        return Ok(HttpResponse::Conflict().finish());
+    }
+    if searches.iter().any(|s| s.name == name) {
+        info!("The client ({client_id}) already holds a search named {name}");
+        return Ok(HttpResponse::Conflict().finish());
+    }
+
+    let id = ids.new_id();
+    insert_search_in_db(&pool, &id, &client_id, &name, &req.query).await?;
+    info!("New search ({id}) saved by the client ({client_id})");
+
+    let search = get_search_from_db(&pool, &client_id, &id)
+        .await?
+        .context("The new search was not found in the DB")?;
+
+    Ok(HttpResponse::Created().json(search))
+}
+
+/// Delete a saved search.
+///
+/// # Description
+///
+/// Digests that use the search as their filters are deleted as well.
+#[utoipa::path(
+    delete,
+    path = "/me/searches/{id}",
+    tag = "Me",
+    params(("id" = String, Path, description = "ID of the saved search.")),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 204, description = "The saved search was deleted."),
+        (status = 400, description = "The given ID has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (status = 404, description = "The client has no saved search identified by the given ID."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[delete("/searches/{id}")]
+pub async fn delete_search(
+    search_id: ResourceId,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+
+    if delete_search_from_db(&pool, &client_id, search_id.as_uuid()).await? {
+        info!("Saved search ({search_id}) of the client ({client_id}) deleted");
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+/// Execute a saved search.
+///
+/// # Description
+///
+/// Unlike `GET /recipe`, a search that produces no matches is answered with an empty list, as the code **404** is
+/// reserved for unknown saved searches.
+#[utoipa::path(
+    get,
+    path = "/me/searches/{id}/results",
+    tag = "Me",
+    params(("id" = String, Path, description = "ID of the saved search.")),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The recipes that match the saved search.", body = [Recipe]),
+        (status = 400, description = "The given ID has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `read` scope."),
+        (status = 404, description = "The client has no saved search identified by the given ID."),
+    )
+)]
+#[instrument(skip(pool, token, collations, access))]
+#[get("/searches/{id}/results")]
+pub async fn get_search_results(
+    search_id: ResourceId,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    collations: Data<NameCollations>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Read).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+    let Some(saved) = get_search_from_db(&pool, &client_id, search_id.as_uuid()).await? else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    let search = RecipeSearch::new(saved.query)
+        .map_err(anyhow::Error::msg)?
+        .with_collation(collations.get(Language::default()).cloned());
+    Ok(HttpResponse::Ok().json(search.run(&pool).await?))
+}
@@ -0,0 +1,992 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::{
+    domain::{
+        collation::{order_by_name, Collation},
+        ClientId, IdGenerator, Language, QuantityUnit, RecipeCategory, RecipeQuery, ServerError,
+        UnitSystem,
+    },
+    routes::me::{
+        digests::{Digest, DigestFrequency},
+        inventory::{InventoryEntry, InventoryItem},
+        notifications::{Notification, NotificationKind},
+        preferences::Preferences,
+        searches::SavedSearch,
+    },
+};
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+use tracing::{debug, error, instrument};
+use uuid::Uuid;
+
+/// Columns of the `DigestSubscription` table, except the client.
+#[derive(Debug)]
+struct StoredDigest {
+    id: String,
+    frequency: String,
+    search_id: Option<String>,
+    tags: String,
+    categories: String,
+    created_at: DateTime<Utc>,
+    last_sent_at: Option<DateTime<Utc>>,
+}
+
+/// Columns of the `SavedSearch` table, except the client.
+#[derive(Debug)]
+struct StoredSearch {
+    id: String,
+    name: String,
+    query: String,
+    created_at: DateTime<Utc>,
+}
+
+/// Ingredient ID and name, quantity, unit and time of the latest update.
+type StoredInventoryItem = (String, String, Option<f32>, Option<String>, DateTime<Utc>);
+
+/// Client ID and email, followed by the columns of [StoredDigest].
+#[derive(Debug)]
+struct StoredDueDigest {
+    client_id: String,
+    email: String,
+    id: String,
+    frequency: String,
+    search_id: Option<String>,
+    tags: String,
+    categories: String,
+    created_at: DateTime<Utc>,
+    last_sent_at: Option<DateTime<Utc>>,
+}
+
+/// Digest that is due, along with the client that subscribed to it.
+#[derive(Debug)]
+pub(crate) struct DueDigest {
+    pub digest: Digest,
+    pub client_id: String,
+    pub email: String,
+}
+
+/// Recipe listed in a digest.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DigestRecipe {
+    pub id: String,
+    pub name: String,
+    pub category: String,
+}
+
+fn parse_digest(record: StoredDigest) -> Result<Digest, ServerError> {
+    let frequency = DigestFrequency::try_from(record.frequency.as_str()).map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+    let categories = record
+        .categories
+        .split(',')
+        .filter(|c| !c.is_empty())
+        .map(|c| {
+            RecipeCategory::try_from(c).map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })
+        })
+        .collect::<Result<Vec<_>, ServerError>>()?;
+
+    Ok(Digest {
+        id: record.id,
+        frequency,
+        search_id: record.search_id,
+        tags: record
+            .tags
+            .split(',')
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect(),
+        categories,
+        created_at: record.created_at,
+        last_sent_at: record.last_sent_at,
+    })
+}
+
+/// Retrieve the digests subscribed by a client, oldest first.
+#[instrument(skip(pool))]
+pub async fn get_digests_from_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+) -> Result<Vec<Digest>, ServerError> {
+    let digests = sqlx::query_as!(
+        StoredDigest,
+        r#"
+        SELECT id, frequency, search_id, tags, categories, created_at, last_sent_at
+        FROM DigestSubscription
+        WHERE client_id = ?
+        ORDER BY created_at, id
+        "#,
+        client_id.to_string()
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    digests.into_iter().map(parse_digest).collect()
+}
+
+/// Store a new digest of a client.
+#[instrument(skip(pool))]
+pub async fn insert_digest_in_db(
+    pool: &MySqlPool,
+    id: &Uuid,
+    client_id: &ClientId,
+    frequency: DigestFrequency,
+    search_id: Option<&Uuid>,
+    tags: &[String],
+    categories: &[RecipeCategory],
+) -> Result<(), ServerError> {
+    let categories = categories
+        .iter()
+        .map(RecipeCategory::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    sqlx::query!(
+        "INSERT INTO DigestSubscription (id, client_id, frequency, search_id, tags, categories) \
+        VALUES (?, ?, ?, ?, ?, ?)",
+        id.to_string(),
+        client_id.to_string(),
+        frequency.to_string(),
+        search_id.map(Uuid::to_string),
+        tags.join(","),
+        categories
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
+
+/// Delete a digest of a client. `false` is returned when the client has no digest identified by `id`.
+#[instrument(skip(pool))]
+pub async fn delete_digest_from_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+    id: &Uuid,
+) -> Result<bool, ServerError> {
+    let result = sqlx::query!(
+        "DELETE FROM DigestSubscription WHERE id = ? AND client_id = ?",
+        id.to_string(),
+        client_id.to_string()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Retrieve the digests whose period elapsed at the given time. Digests of disabled clients are skipped.
+#[instrument(skip(pool))]
+pub(crate) async fn get_due_digests_from_db(
+    pool: &MySqlPool,
+    now: DateTime<Utc>,
+) -> Result<Vec<DueDigest>, ServerError> {
+    let digests = sqlx::query_as!(
+        StoredDueDigest,
+        r#"
+        SELECT d.client_id, u.email, d.id, d.frequency, d.search_id, d.tags, d.categories, d.created_at, d.last_sent_at
+        FROM DigestSubscription d JOIN ApiUser u ON u.id = d.client_id
+        WHERE u.enabled = true
+            AND COALESCE(d.last_sent_at, d.created_at) <=
+                DATE_SUB(?, INTERVAL IF(d.frequency = 'weekly', 7, 1) DAY)
+        ORDER BY d.created_at, d.id
+        "#,
+        now
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+    debug!("{} digests are due", digests.len());
+
+    digests
+        .into_iter()
+        .map(|record| {
+            Ok(DueDigest {
+                digest: parse_digest(StoredDigest {
+                    id: record.id,
+                    frequency: record.frequency,
+                    search_id: record.search_id,
+                    tags: record.tags,
+                    categories: record.categories,
+                    created_at: record.created_at,
+                    last_sent_at: record.last_sent_at,
+                })?,
+                client_id: record.client_id,
+                email: record.email,
+            })
+        })
+        .collect()
+}
+
+/// Retrieve the public recipes published within the given period that match the filters of a digest, oldest first.
+///
+/// # Description
+///
+/// Recipes are selected by their publication date, so drafts and recipes held for moderation are listed once they
+/// become public rather than when they were registered.
+///
+/// When `restrict_to` is given, only the recipes included in the list are considered, i.e. the results of the saved
+/// search used by the digest.
+#[instrument(skip(pool, digest, restrict_to))]
+pub(crate) async fn get_digest_recipes_from_db(
+    pool: &MySqlPool,
+    digest: &Digest,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    restrict_to: Option<&[Uuid]>,
+) -> Result<Vec<DigestRecipe>, ServerError> {
+    if restrict_to.is_some_and(|ids| ids.is_empty()) {
+        return Ok(Vec::new());
+    }
+
+    // Recipes that are not published, or pending moderation, are hidden from the public.
+    let mut query = String::from(
+        "SELECT id, name, COALESCE(CAST(category AS CHAR), 'easy') FROM Cocktail \
+        WHERE publication_date > ? AND publication_date <= ? AND state = 'published' \
+        AND id NOT IN (SELECT cocktail_id FROM ModerationQueue)",
+    );
+    if !digest.categories.is_empty() {
+        query.push_str(" AND FIND_IN_SET(category, ?) > 0");
+    }
+    if !digest.tags.is_empty() {
+        let placeholders = vec!["?"; digest.tags.len()].join(",");
+        query.push_str(&format!(
+            " AND id IN (SELECT cocktail_id FROM Tagged WHERE tag IN ({placeholders}) \
+            GROUP BY cocktail_id HAVING COUNT(DISTINCT tag) = ?)"
+        ));
+    }
+    if let Some(ids) = restrict_to {
+        let placeholders = vec!["?"; ids.len()].join(",");
+        query.push_str(&format!(" AND id IN ({placeholders})"));
+    }
+    query.push_str(" ORDER BY publication_date, id");
+
+    let mut query = sqlx::query_as::<_, (String, String, String)>(&query)
+        .bind(since)
+        .bind(until);
+    if !digest.categories.is_empty() {
+        query = query.bind(
+            digest
+                .categories
+                .iter()
+                .map(RecipeCategory::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+    }
+    if !digest.tags.is_empty() {
+        for tag in digest.tags.iter() {
+            query = query.bind(tag);
+        }
+        query = query.bind(digest.tags.len() as u32);
+    }
+    if let Some(ids) = restrict_to {
+        for id in ids {
+            query = query.bind(id.to_string());
+        }
+    }
+
+    let recipes = query.fetch_all(pool).await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(recipes
+        .into_iter()
+        .map(|(id, name, category)| DigestRecipe { id, name, category })
+        .collect())
+}
+
+/// Record the time at which a digest was sent.
+#[instrument(skip(pool))]
+pub(crate) async fn mark_digest_sent_in_db(
+    pool: &MySqlPool,
+    id: &str,
+    sent_at: DateTime<Utc>,
+) -> Result<(), ServerError> {
+    sqlx::query!(
+        "UPDATE DigestSubscription SET last_sent_at = ? WHERE id = ?",
+        sent_at,
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
+
+fn parse_search(record: StoredSearch) -> Result<SavedSearch, ServerError> {
+    let query = serde_json::from_str::<RecipeQuery>(&record.query).map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(SavedSearch {
+        id: record.id,
+        name: record.name,
+        query,
+        created_at: record.created_at,
+    })
+}
+
+/// Retrieve the searches saved by a client, oldest first.
+#[instrument(skip(pool))]
+pub async fn get_searches_from_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+) -> Result<Vec<SavedSearch>, ServerError> {
+    let searches = sqlx::query_as!(
+        StoredSearch,
+        "SELECT id, name, query, created_at FROM SavedSearch WHERE client_id = ? ORDER BY created_at, id",
+        client_id.to_string()
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    searches.into_iter().map(parse_search).collect()
+}
+
+/// Retrieve a search saved by a client.
+#[instrument(skip(pool))]
+pub async fn get_search_from_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+    id: &Uuid,
+) -> Result<Option<SavedSearch>, ServerError> {
+    let search = sqlx::query_as!(
+        StoredSearch,
+        "SELECT id, name, query, created_at FROM SavedSearch WHERE id = ? AND client_id = ?",
+        id.to_string(),
+        client_id.to_string()
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    search.map(parse_search).transpose()
+}
+
+/// Store a new saved search of a client.
+#[instrument(skip(pool, query))]
+pub async fn insert_search_in_db(
+    pool: &MySqlPool,
+    id: &Uuid,
+    client_id: &ClientId,
+    name: &str,
+    query: &RecipeQuery,
+) -> Result<(), ServerError> {
+    let query = serde_json::to_string(query).map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    sqlx::query!(
+        "INSERT INTO SavedSearch (id, client_id, name, query) VALUES (?, ?, ?, ?)",
+        id.to_string(),
+        client_id.to_string(),
+        name,
+        query
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
+
+/// Delete a search saved by a client. `false` is returned when the client has no search identified by `id`.
+#[instrument(skip(pool))]
+pub async fn delete_search_from_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+    id: &Uuid,
+) -> Result<bool, ServerError> {
+    let result = sqlx::query!(
+        "DELETE FROM SavedSearch WHERE id = ? AND client_id = ?",
+        id.to_string(),
+        client_id.to_string()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+fn parse_inventory_item(
+    (ingredient_id, name, quantity, unit, updated_at): StoredInventoryItem,
+) -> Result<InventoryItem, ServerError> {
+    let unit = unit
+        .map(|u| QuantityUnit::try_from(u.as_str()))
+        .transpose()
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    Ok(InventoryItem {
+        ingredient_id,
+        name,
+        quantity,
+        unit,
+        updated_at,
+    })
+}
+
+/// Retrieve the inventory of a client, sorted by the name of the ingredients using the given collation (see
+/// [order_by_name]).
+#[instrument(skip(pool))]
+pub async fn get_inventory_from_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+    collation: Option<&Collation>,
+) -> Result<Vec<InventoryItem>, ServerError> {
+    let query = format!(
+        r#"
+        SELECT i.id, i.name, inv.quantity, inv.unit, inv.updated_at
+        FROM Inventory inv
+        INNER JOIN Ingredient i ON i.id = inv.ingredient_id
+        WHERE inv.client_id = ?
+        ORDER BY {}, i.id
+        "#,
+        order_by_name("i.name", collation)
+    );
+    let items: Vec<StoredInventoryItem> = sqlx::query_as(&query)
+        .bind(client_id.to_string())
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    items.into_iter().map(parse_inventory_item).collect()
+}
+
+/// Replace the inventory of a client.
+///
+/// # Description
+///
+/// `false` is returned, and the inventory is kept, when some of the ingredients doesn't exist.
+#[instrument(skip(pool, entries))]
+pub async fn replace_inventory_in_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+    entries: &[InventoryEntry],
+) -> Result<bool, ServerError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    if !entries.is_empty() {
+        let placeholders = vec!["?"; entries.len()].join(",");
+        let query = format!("SELECT COUNT(*) FROM Ingredient WHERE id IN ({placeholders})");
+        let mut query = sqlx::query_scalar::<_, i64>(&query);
+        for entry in entries {
+            query = query.bind(entry.ingredient_id.to_string());
+        }
+        let found = query.fetch_one(&mut *transaction).await.map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+        if found != entries.len() as i64 {
+            debug!("Some of the ingredients of the inventory doesn't exist");
+            return Ok(false);
+        }
+    }
+
+    sqlx::query!(
+        "DELETE FROM Inventory WHERE client_id = ?",
+        client_id.to_string()
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    for entry in entries {
+        sqlx::query!(
+            "INSERT INTO Inventory (client_id, ingredient_id, quantity, unit) VALUES (?, ?, ?, ?)",
+            client_id.to_string(),
+            entry.ingredient_id.to_string(),
+            entry.quantity,
+            entry.unit.map(|u| u.to_string())
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+    }
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(true)
+}
+
+/// Add an ingredient to the inventory of a client, or update its quantity.
+///
+/// # Description
+///
+/// `false` is returned when the ingredient doesn't exist.
+#[instrument(skip(pool))]
+pub async fn set_inventory_item_in_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+    ingredient_id: &Uuid,
+    quantity: Option<f32>,
+    unit: Option<QuantityUnit>,
+) -> Result<bool, ServerError> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO Inventory (client_id, ingredient_id, quantity, unit)
+        SELECT ?, id, ?, ? FROM Ingredient WHERE id = ?
+        ON DUPLICATE KEY UPDATE quantity = VALUES(quantity), unit = VALUES(unit)
+        "#,
+        client_id.to_string(),
+        quantity,
+        unit.map(|u| u.to_string()),
+        ingredient_id.to_string()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    // Depending on the flags of the connection, an unchanged row may be reported as 0 affected rows, so the existence
+    // of the ingredient is checked apart.
+    if result.rows_affected() > 0 {
+        return Ok(true);
+    }
+    let exists = sqlx::query_scalar!(
+        "SELECT id FROM Ingredient WHERE id = ?",
+        ingredient_id.to_string()
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(exists.is_some())
+}
+
+/// Remove an ingredient from the inventory of a client. `false` is returned when the client doesn't hold it.
+#[instrument(skip(pool))]
+pub async fn delete_inventory_item_from_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+    ingredient_id: &Uuid,
+) -> Result<bool, ServerError> {
+    let result = sqlx::query!(
+        "DELETE FROM Inventory WHERE client_id = ? AND ingredient_id = ?",
+        client_id.to_string(),
+        ingredient_id.to_string()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Retrieve the preferences of a client. Clients that never set them get the default preferences.
+#[instrument(skip(pool))]
+pub async fn get_preferences_from_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+) -> Result<Preferences, ServerError> {
+    let stored = sqlx::query!(
+        r#"SELECT unit_system, language, email_notifications AS "email_notifications: bool"
+        FROM ClientPreferences WHERE client_id = ?"#,
+        client_id.to_string()
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let Some(stored) = stored else {
+        return Ok(Preferences::default());
+    };
+
+    Ok(Preferences {
+        units: stored
+            .unit_system
+            .map(|u| UnitSystem::try_from(u.as_str()))
+            .transpose()
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?,
+        lang: stored
+            .language
+            .map(|l| Language::try_from(l.as_str()))
+            .transpose()
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?,
+        email_notifications: stored.email_notifications,
+    })
+}
+
+/// Store the preferences of a client.
+#[instrument(skip(pool))]
+pub async fn store_preferences_in_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+    preferences: &Preferences,
+) -> Result<(), ServerError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO ClientPreferences (client_id, unit_system, language, email_notifications) VALUES (?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE unit_system = VALUES(unit_system), language = VALUES(language),
+            email_notifications = VALUES(email_notifications)
+        "#,
+        client_id.to_string(),
+        preferences.units.map(|u| u.to_string()),
+        preferences.lang.map(|l| l.to_string()),
+        preferences.email_notifications
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
+
+/// Columns of the `Notification` table, except the client and the time it was emailed.
+#[derive(Debug)]
+struct StoredNotification {
+    id: String,
+    kind: String,
+    subject_id: String,
+    message: String,
+    created_at: DateTime<Utc>,
+    read_at: Option<DateTime<Utc>>,
+}
+
+/// Retrieve the notifications of a client, newest first.
+#[instrument(skip(pool))]
+pub async fn get_notifications_from_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+    unread_only: bool,
+    limit: u32,
+) -> Result<Vec<Notification>, ServerError> {
+    let notifications = sqlx::query_as!(
+        StoredNotification,
+        r#"
+        SELECT id, kind, subject_id, message, created_at, read_at FROM Notification
+        WHERE client_id = ? AND (? = FALSE OR read_at IS NULL)
+        ORDER BY created_at DESC, id DESC
+        LIMIT ?
+        "#,
+        client_id.to_string(),
+        unread_only,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    notifications
+        .into_iter()
+        .map(|record| {
+            Ok(Notification {
+                kind: NotificationKind::try_from(record.kind.as_str()).map_err(|e| {
+                    error!("{e}");
+                    ServerError::DbError
+                })?,
+                id: record.id,
+                subject_id: record.subject_id,
+                message: record.message,
+                created_at: record.created_at,
+                read_at: record.read_at,
+            })
+        })
+        .collect()
+}
+
+/// Store a notification for a client. `false` is returned when the same event was already notified.
+#[instrument(skip(pool, ids, message))]
+pub async fn store_notification_in_db(
+    pool: &MySqlPool,
+    ids: &dyn IdGenerator,
+    client_id: &str,
+    kind: NotificationKind,
+    subject_id: &str,
+    message: &str,
+) -> Result<bool, ServerError> {
+    let result = sqlx::query!(
+        "INSERT IGNORE INTO Notification (id, client_id, kind, subject_id, message) VALUES (?, ?, ?, ?, ?)",
+        ids.new_id().to_string(),
+        client_id,
+        kind.to_string(),
+        subject_id,
+        message
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Mark a notification of a client as read. `false` is returned when the client has no such notification.
+#[instrument(skip(pool))]
+pub async fn mark_notification_read_in_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+    id: &Uuid,
+) -> Result<bool, ServerError> {
+    let found = sqlx::query_scalar!(
+        "SELECT id FROM Notification WHERE id = ? AND client_id = ?",
+        id.to_string(),
+        client_id.to_string()
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    if found.is_none() {
+        return Ok(false);
+    }
+
+    // Notifications that were already read keep their original read time.
+    sqlx::query!(
+        "UPDATE Notification SET read_at = CURRENT_TIMESTAMP WHERE id = ? AND read_at IS NULL",
+        id.to_string()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(true)
+}
+
+/// Mark all the notifications of a client as read. The amount of modified notifications is returned.
+#[instrument(skip(pool))]
+pub async fn mark_all_notifications_read_in_db(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+) -> Result<u64, ServerError> {
+    let result = sqlx::query!(
+        "UPDATE Notification SET read_at = CURRENT_TIMESTAMP WHERE client_id = ? AND read_at IS NULL",
+        client_id.to_string()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(result.rows_affected())
+}
+
+/// Retrieve the client that registered a recipe, along with the name of the recipe.
+#[instrument(skip(pool))]
+pub async fn get_recipe_client_from_db(
+    pool: &MySqlPool,
+    recipe_id: &Uuid,
+) -> Result<Option<(String, String)>, ServerError> {
+    let record = sqlx::query!(
+        r#"SELECT client_id AS "client_id!", name FROM Cocktail WHERE id = ? AND client_id IS NOT NULL"#,
+        recipe_id.to_string()
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(record.map(|record| (record.client_id, record.name)))
+}
+
+/// API key that expires soon.
+#[derive(Debug)]
+pub(crate) struct ExpiringKey {
+    pub id: u64,
+    pub client_id: String,
+    pub label: String,
+    pub valid_until: DateTime<Utc>,
+}
+
+/// Retrieve the API keys of the enabled clients that expire between `now` and `until`.
+#[instrument(skip(pool))]
+pub(crate) async fn get_expiring_keys_from_db(
+    pool: &MySqlPool,
+    now: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<ExpiringKey>, ServerError> {
+    sqlx::query_as!(
+        ExpiringKey,
+        r#"
+        SELECT t.id, t.client_id, t.label, t.valid_until
+        FROM ApiToken t JOIN ApiUser u ON u.id = t.client_id
+        WHERE u.enabled = true AND t.valid_until > ? AND t.valid_until <= ?
+        ORDER BY t.valid_until, t.id
+        "#,
+        now,
+        until
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })
+}
+
+/// Notification that shall be mirrored to the email of its client.
+#[derive(Debug)]
+pub(crate) struct PendingNotificationEmail {
+    pub id: String,
+    pub client_id: String,
+    pub email: String,
+    pub message: String,
+}
+
+/// Retrieve the unread notifications that were not emailed yet to the clients that enabled the email notifications.
+#[instrument(skip(pool))]
+pub(crate) async fn get_pending_notification_emails_from_db(
+    pool: &MySqlPool,
+) -> Result<Vec<PendingNotificationEmail>, ServerError> {
+    sqlx::query_as!(
+        PendingNotificationEmail,
+        r#"
+        SELECT n.id, n.client_id, u.email, n.message
+        FROM Notification n
+            JOIN ApiUser u ON u.id = n.client_id
+            JOIN ClientPreferences p ON p.client_id = n.client_id
+        WHERE u.enabled = true AND p.email_notifications = true
+            AND n.read_at IS NULL AND n.emailed_at IS NULL
+        ORDER BY n.created_at, n.id
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })
+}
+
+/// Count the notifications waiting to be mirrored to the email of their clients (see
+/// [get_pending_notification_emails_from_db]).
+#[instrument(skip(pool))]
+pub(crate) async fn count_pending_notification_emails_in_db(
+    pool: &MySqlPool,
+) -> Result<u64, ServerError> {
+    let count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS count
+        FROM Notification n
+            JOIN ApiUser u ON u.id = n.client_id
+            JOIN ClientPreferences p ON p.client_id = n.client_id
+        WHERE u.enabled = true AND p.email_notifications = true
+            AND n.read_at IS NULL AND n.emailed_at IS NULL
+        "#
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(count as u64)
+}
+
+/// Register that a notification was mirrored to the email of its client.
+#[instrument(skip(pool))]
+pub(crate) async fn mark_notification_emailed_in_db(
+    pool: &MySqlPool,
+    id: &str,
+) -> Result<(), ServerError> {
+    sqlx::query!(
+        "UPDATE Notification SET emailed_at = CURRENT_TIMESTAMP WHERE id = ?",
+        id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Media files uploaded by the clients of the API.
+//!
+//! # Description
+//!
+//! Stored images never change: uploading a new image generates a new `image_id`. Thus, responses can be cached
+//! forever by the clients and the shared caches.
+
+use crate::{domain::ApiError, utils::media::MediaStore};
+use actix_web::{
+    get,
+    http::header::{CacheControl, CacheDirective},
+    web::{Data, Path},
+    HttpResponse,
+};
+use tracing::{info, instrument};
+
+/// Amount of seconds that an image can be cached (one year).
+const MEDIA_MAX_AGE: u32 = 31_536_000;
+
+/// Retrieve a stored image (Public).
+#[utoipa::path(
+    get,
+    path = "/media/{image_id}",
+    tag = "Media",
+    params(
+        ("image_id" = String, Path, description = "ID of the image.", example = "0191e13b-5ab7-78f1-bc06-be503a6c111b.png"),
+    ),
+    responses(
+        (status = 200, description = "The content of the image.", content_type = ["image/png", "image/jpeg", "image/webp"]),
+        (status = 404, description = "The image doesn't exist."),
+    )
+)]
+#[instrument(skip(media))]
+#[get("/media/{image_id}")]
+pub async fn get_media(
+    image_id: Path<String>,
+    media: Data<MediaStore>,
+) -> Result<HttpResponse, ApiError> {
+    let Some((content, format)) = media.get_image(&image_id).await else {
+        info!("No image was found with the ID: {image_id}");
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(MEDIA_MAX_AGE),
+            CacheDirective::Extension("immutable".into(), None),
+        ]))
+        .body(content))
+}
@@ -0,0 +1,586 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Claims of the ownership of the recipes that have no owner.
+//!
+//! # Description
+//!
+//! Recipes imported from other sources, or registered anonymously, have no owner. Authors claim them using
+//! `POST /recipe/{id}/claim`, which registers a pending claim that includes a verification token. Clients of the API
+//! can only claim recipes for the authors that registered the same email as the client. The claim is granted when
+//! either:
+//! - The author proves control of the source URL of the recipe: the token is published within the page served at that
+//!   URL, and the claim is requested again.
+//! - An administrator approves the claim (see [crate::routes::admin::claims]).
+//!
+//! Once a claim is granted, the author becomes the owner of the recipe, the rest of the pending claims of the recipe
+//! are rejected, and the author is notified via email.
+//!
+//! Only public pages are fetched to verify the source URLs: URLs that point to local or private addresses are never
+//! verified, and redirections are not followed. Host names are resolved by [PublicResolver], which drops the private
+//! addresses, and the page is fetched from the very addresses that were checked, so a host name that resolves to a
+//! private address, or that changes its address between the check and the request, is never fetched.
+
+use crate::{
+    authentication::{
+        access_denied_response, check_access, key_client_id, AccessControl, AuthData, Scope,
+    },
+    domain::{ApiError, DataDomainError, IdGenerator, ResourceId},
+    routes::recipe::{
+        utils::{
+            get_author_email_from_db, get_client_email_from_db, get_pending_claim_from_db,
+            get_recipe_from_db, grant_claim_in_db, store_claim_in_db,
+        },
+        RecipeId,
+    },
+    utils::mailing::{
+        register_email_attempt, send_claim_email, EmailKind, EmailSender, MailCorrelation,
+    },
+};
+use actix_web::{
+    post,
+    web::{self, Data, Json, Query},
+    HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect::Policy,
+    Client, Url,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{debug, info, instrument, warn};
+use tracing_actix_web::RequestId;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Length of the verification tokens of the claims.
+pub const CLAIM_TOKEN_LENGTH: usize = 32;
+
+/// Maximum size of the pages fetched to verify the source URLs (bytes).
+const MAX_SOURCE_SIZE: usize = 1024 * 1024;
+
+/// Client used to fetch the source URLs of the recipes.
+static SOURCE_CLIENT: Lazy<Client> = Lazy::new(|| {
+    Client::builder()
+        .dns_resolver(Arc::new(PublicResolver))
+        // Proxies would resolve the host names by themselves.
+        .no_proxy()
+        .redirect(Policy::none())
+        .timeout(Duration::from_secs(10))
+        .user_agent(concat!("lacoctelera/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .expect("Failed to build the HTTP client")
+});
+
+/// DNS resolver that only yields public addresses.
+///
+/// # Description
+///
+/// Host names that resolve to no public address fail to resolve, so the client never connects to a private address.
+#[derive(Debug, Clone, Copy)]
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_owned();
+        Box::pin(async move {
+            let addrs = web::block(move || (host.as_str(), 0).to_socket_addrs())
+                .await??
+                .collect::<Vec<SocketAddr>>();
+            let public = public_addrs(addrs);
+            if public.is_empty() {
+                return Err("The host has no public address".into());
+            }
+
+            Ok(Box::new(public.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Keep the public addresses of a list.
+fn public_addrs(addrs: impl IntoIterator<Item = SocketAddr>) -> Vec<SocketAddr> {
+    addrs
+        .into_iter()
+        .filter(|addr| is_public_ip(addr.ip()))
+        .collect()
+}
+
+/// States of a claim.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimState {
+    /// The claim waits for the verification of the source URL, or the review of an administrator.
+    Pending,
+    /// The author of the claim owns the recipe.
+    Approved,
+    /// The claim was rejected by an administrator, or the recipe was granted to another claim.
+    Rejected,
+}
+
+impl fmt::Display for ClaimState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClaimState::Pending => write!(f, "pending"),
+            ClaimState::Approved => write!(f, "approved"),
+            ClaimState::Rejected => write!(f, "rejected"),
+        }
+    }
+}
+
+impl TryFrom<&str> for ClaimState {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "pending" => Ok(ClaimState::Pending),
+            "approved" => Ok(ClaimState::Approved),
+            "rejected" => Ok(ClaimState::Rejected),
+            _ => Err(DataDomainError::InvalidData),
+        }
+    }
+}
+
+/// Claim of the ownership of a recipe.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct RecipeClaim {
+    #[schema(example = "0192e8d9-36cf-7ce3-82ef-0a7c9b2deefe")]
+    pub id: String,
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub recipe_id: String,
+    /// Author that becomes the owner of the recipe when the claim is granted.
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111c")]
+    pub author_id: String,
+    /// Client of the API that issued the claim.
+    #[schema(example = "0191e13b-5ab7-78f1-bc06-be503a6c111d")]
+    pub client_id: String,
+    pub state: ClaimState,
+    /// Token to publish within the page served at the source URL of the recipe.
+    #[schema(example = "Zq4Wv0cJ7pXr2LkT9mNb3HsY8dFa6GeU")]
+    pub token: String,
+    /// Source URL of the recipe. Claims of recipes with no source URL need the review of an administrator.
+    #[schema(example = "https://mybar.com/recipes/mojito")]
+    pub source_url: Option<String>,
+    #[schema(value_type = String, example = "2025-09-11T08:58:56Z")]
+    pub created_at: DateTime<Utc>,
+    /// When the claim was approved or rejected.
+    #[schema(value_type = Option<String>, example = "2025-09-11T09:12:03Z")]
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+impl RecipeClaim {
+    /// Build a new pending claim, identified by `id`, with a random token.
+    pub fn new(
+        id: &Uuid,
+        recipe_id: &Uuid,
+        author_id: &Uuid,
+        client_id: &str,
+        source_url: Option<&str>,
+    ) -> Self {
+        RecipeClaim {
+            id: id.to_string(),
+            recipe_id: recipe_id.to_string(),
+            author_id: author_id.to_string(),
+            client_id: client_id.to_owned(),
+            state: ClaimState::Pending,
+            token: thread_rng()
+                .sample_iter(Alphanumeric)
+                .take(CLAIM_TOKEN_LENGTH)
+                .map(char::from)
+                .collect(),
+            source_url: source_url.map(String::from),
+            created_at: Utc::now(),
+            resolved_at: None,
+        }
+    }
+}
+
+/// Request body of the claim resource.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ClaimRequest {
+    /// ID of the author that claims the recipe.
+    #[schema(value_type = String, example = "0191e13b-5ab7-78f1-bc06-be503a6c111c")]
+    pub author: ResourceId,
+}
+
+/// Claim the ownership of a recipe that has no owner (Restricted).
+///
+/// # Description
+///
+/// The first request registers a pending claim, answered with a code **202**. The response includes a token that
+/// the author publishes within the page served at the source URL of the recipe, i.e. using a
+/// `<meta name="lacoctelera-claim" content="{token}">` tag. Then, the author repeats the request: when the page
+/// includes the token, the claim is granted and answered with a code **200**. Otherwise, the claim stays pending
+/// until the source URL is verified, or an administrator reviews it.
+///
+/// Clients can only claim recipes for the authors whose email matches the email of the client.
+///
+/// The author gets notified via email when the claim is granted.
+#[utoipa::path(
+    post,
+    path = "/recipe/{id}/claim",
+    tag = "Recipe",
+    params(("id" = String, Path, description = "ID or short ID of the recipe.")),
+    security(
+        ("api_key" = [])
+    ),
+    request_body(
+        content = ClaimRequest, description = "The author that claims the recipe.",
+        example = json!({"author": "0191e13b-5ab7-78f1-bc06-be503a6c111c"})
+    ),
+    responses(
+        (status = 200, description = "The claim was granted, the author owns the recipe.", body = RecipeClaim),
+        (status = 202, description = "The claim is pending.", body = RecipeClaim),
+        (status = 400, description = "The given IDs have an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The API key doesn't grant the `write` scope, or the author doesn't share the email of the client."),
+        (status = 404, description = "The recipe or the author don't exist."),
+        (status = 409, description = "The recipe has an owner already."),
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(pool, ids, mail_client, token, request_id, access))]
+#[post("/{id}/claim")]
+pub async fn claim_recipe(
+    recipe_id: RecipeId,
+    req: Json<ClaimRequest>,
+    pool: Data<MySqlPool>,
+    ids: Data<dyn IdGenerator>,
+    access: Data<AccessControl>,
+    mail_client: Option<Data<dyn EmailSender>>,
+    token: Query<AuthData>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+    let client_id = key_client_id(&token.api_key)?;
+
+    let Some(recipe) = get_recipe_from_db(&pool, recipe_id.as_uuid()).await? else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    if recipe.owner().is_some() {
+        info!("The recipe {recipe_id} has an owner already");
+        return Ok(HttpResponse::Conflict().finish());
+    }
+    let author_id = req.author.as_uuid();
+    let Some(author_email) = get_author_email_from_db(&pool, author_id).await? else {
+        info!("The author {author_id} doesn't exist");
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    let client_email = get_client_email_from_db(&pool, &client_id.to_string()).await?;
+    if !client_email.is_some_and(|email| email.eq_ignore_ascii_case(&author_email)) {
+        info!("The author {author_id} doesn't share the email of the client");
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+
+    let claim = match get_pending_claim_from_db(
+        &pool,
+        recipe_id.as_uuid(),
+        author_id,
+        &client_id.to_string(),
+    )
+    .await?
+    {
+        Some(claim) => claim,
+        None => {
+            let claim = RecipeClaim::new(
+                &ids.new_id(),
+                recipe_id.as_uuid(),
+                author_id,
+                &client_id.to_string(),
+                recipe.url(),
+            );
+            store_claim_in_db(&pool, &claim).await?;
+            info!("New claim ({}) of the recipe {recipe_id}", claim.id);
+            return Ok(HttpResponse::Accepted().json(claim));
+        }
+    };
+
+    let verified = match recipe.url() {
+        Some(url) => source_includes_token(url, &claim.token).await,
+        None => false,
+    };
+    if !verified {
+        debug!("The source of the recipe {recipe_id} doesn't include the token of the claim");
+        return Ok(HttpResponse::Accepted().json(claim));
+    }
+
+    if !grant_claim_in_db(&pool, &claim).await? {
+        info!("The recipe {recipe_id} got an owner concurrently");
+        return Ok(HttpResponse::Conflict().finish());
+    }
+    info!("Claim {} of the recipe {recipe_id} granted", claim.id);
+
+    let correlation = MailCorrelation::new(*request_id, &client_id);
+    notify_granted_claim(
+        &pool,
+        mail_client,
+        &author_email,
+        recipe.name(),
+        &correlation,
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(RecipeClaim {
+        state: ClaimState::Approved,
+        resolved_at: Some(Utc::now()),
+        ..claim
+    }))
+}
+
+/// Notify the author of a granted claim via email.
+///
+/// # Description
+///
+/// The ownership is already transferred, so failures to send or store the email are only logged.
+pub(crate) async fn notify_granted_claim(
+    pool: &MySqlPool,
+    mail_client: Option<Data<dyn EmailSender>>,
+    recipient: &str,
+    recipe_name: &str,
+    correlation: &MailCorrelation,
+) {
+    let Some(mail_client) = mail_client else {
+        warn!("No email client available to notify the granted claim");
+        return;
+    };
+
+    let outcome = send_claim_email(mail_client, recipient, recipe_name, correlation).await;
+    if let Err(e) = register_email_attempt(
+        pool,
+        correlation,
+        EmailKind::ClaimNotification,
+        recipient,
+        &outcome,
+    )
+    .await
+    {
+        warn!("Failed to store the claim email: {e}");
+    }
+    if let Err(e) = outcome {
+        warn!("Failed to notify the granted claim: {e}");
+    }
+}
+
+/// Check whether the page served at `url` includes the token of a claim.
+async fn source_includes_token(url: &str, token: &str) -> bool {
+    let Some(url) = public_url(url) else {
+        info!("The source URL {url} is not public, so it can't be verified");
+        return false;
+    };
+
+    let mut response = match SOURCE_CLIENT.get(url).send().await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            debug!("The source URL answered with {}", response.status());
+            return false;
+        }
+        Err(e) => {
+            debug!("Failed to fetch the source URL: {e}");
+            return false;
+        }
+    };
+
+    let mut page = Vec::new();
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) if page.len() + chunk.len() <= MAX_SOURCE_SIZE => {
+                page.extend_from_slice(&chunk)
+            }
+            Ok(Some(_)) => {
+                debug!("The source page exceeds {MAX_SOURCE_SIZE} bytes");
+                break;
+            }
+            Ok(None) => break,
+            Err(e) => {
+                debug!("Failed to read the source page: {e}");
+                return false;
+            }
+        }
+    }
+
+    includes_token(&page, token)
+}
+
+fn includes_token(page: &[u8], token: &str) -> bool {
+    !token.is_empty()
+        && page
+            .windows(token.len())
+            .any(|window| window == token.as_bytes())
+}
+
+/// Parse a URL, and check that it points to a public HTTP server.
+///
+/// # Description
+///
+/// Host names are not resolved here, see [PublicResolver].
+fn public_url(url: &str) -> Option<Url> {
+    let url = Url::parse(url).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+
+    let host = url
+        .host_str()?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let public = match host.parse::<IpAddr>() {
+        Ok(ip) => is_public_ip(ip),
+        Err(_) => {
+            let domain = host.trim_end_matches('.').to_ascii_lowercase();
+            domain != "localhost" && !domain.ends_with(".localhost") && domain.contains('.')
+        }
+    };
+
+    public.then_some(url)
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_documentation()
+                // This network (0.0.0.0/8).
+                || octets[0] == 0
+                // Shared address space (100.64.0.0/10).
+                || (octets[0] == 100 && (octets[1] & 0xc0) == 64)
+                // Benchmarking (198.18.0.0/15).
+                || (octets[0] == 198 && (octets[1] & 0xfe) == 18)
+                // Reserved (240.0.0.0/4), including the broadcast address.
+                || octets[0] >= 240)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                let segments = ip.segments();
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    // Unique local (fc00::/7) and link local (fe80::/10) addresses.
+                    || (segments[0] & 0xfe00) == 0xfc00
+                    || (segments[0] & 0xffc0) == 0xfe80
+                    // NAT64 (64:ff9b::/96) and 6to4 (2002::/16) addresses embed IPv4 addresses.
+                    || segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0]
+                    || segments[0] == 0x2002)
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case("https://mybar.com/recipes/mojito", true)]
+    #[case("http://93.184.215.14/mojito", true)]
+    #[case("ftp://mybar.com/mojito", false)]
+    #[case("http://localhost:8080/mojito", false)]
+    #[case("http://intranet/mojito", false)]
+    #[case("http://127.0.0.1/mojito", false)]
+    #[case("http://10.0.0.4/mojito", false)]
+    #[case("http://192.168.1.1/mojito", false)]
+    #[case("http://169.254.169.254/latest/meta-data", false)]
+    #[case("http://[::1]/mojito", false)]
+    #[case("http://[::ffff:10.0.0.1]/mojito", false)]
+    #[case("http://[fd00::1]/mojito", false)]
+    #[case("http://0.1.2.3/mojito", false)]
+    #[case("http://198.19.0.1/mojito", false)]
+    #[case("http://198.20.0.1/mojito", true)]
+    #[case("http://250.1.2.3/mojito", false)]
+    #[case("http://255.255.255.255/mojito", false)]
+    #[case("http://[64:ff9b::a00:1]/mojito", false)]
+    #[case("http://[2002:a00:1::1]/mojito", false)]
+    #[case("http://[2001:4860:4860::8888]/mojito", true)]
+    #[case("not a url", false)]
+    fn only_public_urls_are_verified(#[case] url: &str, #[case] public: bool) {
+        assert_eq!(public_url(url).is_some(), public, "{url}");
+    }
+
+    #[rstest]
+    fn private_addresses_are_not_resolved() {
+        let addrs = [
+            "127.0.0.1:0".parse().unwrap(),
+            "[::1]:0".parse().unwrap(),
+            "10.1.2.3:0".parse().unwrap(),
+            "93.184.215.14:0".parse().unwrap(),
+        ];
+        assert_eq!(public_addrs(addrs), ["93.184.215.14:0".parse().unwrap()]);
+    }
+
+    #[actix_web::test]
+    async fn host_names_of_loopback_are_not_resolved() {
+        // `localhost` resolves to the loopback addresses with no network access.
+        let name: Name = "localhost".parse().unwrap();
+        assert!(PublicResolver.resolve(name).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn host_names_of_loopback_are_not_fetched() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let url = Url::parse(&format!("http://localhost:{port}/mojito")).unwrap();
+        assert!(SOURCE_CLIENT.get(url).send().await.is_err());
+        assert!(
+            listener.accept().is_err(),
+            "The loopback server was reached"
+        );
+    }
+
+    #[rstest]
+    fn tokens_are_found() {
+        let claim = RecipeClaim::new(
+            &Uuid::now_v7(),
+            &Uuid::now_v7(),
+            &Uuid::now_v7(),
+            "client",
+            None,
+        );
+        assert_eq!(claim.token.len(), CLAIM_TOKEN_LENGTH);
+        assert_eq!(claim.state, ClaimState::Pending);
+
+        let page = format!(
+            "<html><head><meta name=\"lacoctelera-claim\" content=\"{}\"></head></html>",
+            claim.token
+        );
+        assert!(includes_token(page.as_bytes(), &claim.token));
+        assert!(!includes_token(
+            page.as_bytes(),
+            &claim.token[1..].to_lowercase()
+        ));
+        assert!(!includes_token(b"", &claim.token));
+        assert!(!includes_token(page.as_bytes(), ""));
+    }
+
+    #[rstest]
+    #[case(ClaimState::Pending)]
+    #[case(ClaimState::Approved)]
+    #[case(ClaimState::Rejected)]
+    fn states_round_trip(#[case] state: ClaimState) {
+        assert_eq!(
+            ClaimState::try_from(state.to_string().as_str()).ok(),
+            Some(state)
+        );
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Dry-run of the classification of recipes.
+
+use crate::domain::{
+    classifier::{classify, RecipeFeatures},
+    Equipment, RecipeContains,
+};
+use actix_web::{post, web::Json, HttpResponse};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, instrument};
+use utoipa::ToSchema;
+
+/// Draft of a recipe to classify.
+///
+/// # Description
+///
+/// Only the attributes of a recipe that are relevant to guess its difficulty are included. Other attributes are
+/// ignored, so the same body that would be sent to `POST /recipe` is accepted.
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct RecipeDraft {
+    #[serde(default)]
+    pub ingredients: Vec<RecipeContains>,
+    #[serde(default)]
+    pub steps: Vec<String>,
+    pub equipment: Option<Vec<Equipment>>,
+    pub prep_time_minutes: Option<u16>,
+}
+
+/// Suggest a category for a recipe (Public).
+///
+/// # Description
+///
+/// This resource runs the heuristics that the backend applies when a recipe is registered without a category, and
+/// returns the suggested category along with the score of the recipe and the techniques detected in the steps.
+/// Nothing is stored in the DB.
+#[utoipa::path(
+    post,
+    path = "/recipe/classify",
+    tag = "Recipe",
+    request_body(
+        content = RecipeDraft, description = "The draft of a recipe.",
+        example = json!({"steps": ["Muddle the mint.", "Shake and double strain."], "equipment": ["muddler"]})
+    ),
+    responses(
+        (status = 200, description = "The suggested category for the recipe.", body = Classification),
+        (status = 400, description = "The body has an invalid format."),
+    )
+)]
+#[instrument(skip(req))]
+#[post("/classify")]
+pub async fn classify_recipe(req: Json<RecipeDraft>) -> HttpResponse {
+    let classification = classify(&RecipeFeatures {
+        ingredients: req.ingredients.len(),
+        steps: &req.steps,
+        equipment: req.equipment.as_deref().unwrap_or_default(),
+        prep_time_minutes: req.prep_time_minutes,
+    });
+    debug!("Classification: {classification:?}");
+
+    HttpResponse::Ok().json(classification)
+}
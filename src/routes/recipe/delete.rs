@@ -0,0 +1,185 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Recipe endpoint DELETE method.
+
+use crate::{
+    authentication::{
+        access_denied_response, check_access, check_admin_access, AccessControl, AuthData, Scope,
+    },
+    domain::{ApiError, ShortId},
+    routes::{
+        batch::{BatchDelete, BatchOutcome, MAX_BATCH_SIZE},
+        recipe::{
+            utils::{
+                delete_recipe_from_db, delete_recipes_from_db, get_recipe_from_db,
+                get_recipe_ids_by_short_id,
+            },
+            RecipeId,
+        },
+    },
+    utils::http::{is_precondition_met, resource_etag, Preconditions},
+};
+use actix_web::{
+    delete, post,
+    web::{Data, Json, Query},
+    HttpRequest, HttpResponse,
+};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+
+/// DELETE method for the Recipe endpoint (Restricted).
+///
+/// # Description
+///
+/// This method deletes a `Recipe` entry from the DB if the given `id` matches the ID of a registered recipe. The
+/// ingredients and tags of the recipe are removed along with it.
+///
+/// Clients can make sure that the recipe didn't change since they retrieved it using an `If-Match` header with the
+/// `ETag` of the recipe. The server can be configured to require such header, in which case the deletions that include
+/// none are answered with a code **428**.
+///
+/// This method requires to authenticate the client using a valid [crate::AuthData::api_key].
+#[utoipa::path(
+    delete,
+    path = "/recipe/{id}",
+    tag = "Recipe",
+    params(
+        ("id" = String, Path, description = "ID or short ID of the recipe."),
+        ("If-Match" = Option<String>, Header, description = "Delete the recipe only when its current `ETag` is included."),
+    ),
+    responses(
+        (status = 204, description = "The recipe was deleted from the DB."),
+        (status = 400, description = "The given recipe's ID has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (status = 404, description = "A recipe identified by the given ID was not existing in the DB."),
+        (status = 412, description = "The recipe changed since the `ETag` given in `If-Match` was issued."),
+        (status = 428, description = "The server requires an `If-Match` header to delete the recipes."),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[instrument(skip(pool, token, request, preconditions, access), fields(recipe_id = %recipe_id))]
+#[delete("{id}")]
+pub async fn delete_recipe(
+    recipe_id: RecipeId,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+    request: HttpRequest,
+    preconditions: Data<Preconditions>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    if preconditions.is_missing(&request) {
+        info!("The deletion of the recipe {recipe_id} includes no If-Match header");
+        return Ok(HttpResponse::PreconditionRequired().finish());
+    }
+    let Some(recipe) = get_recipe_from_db(&pool, recipe_id.as_uuid()).await? else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    if !is_precondition_met(&request, &resource_etag(&recipe)?) {
+        info!("The recipe {recipe_id} changed since the given ETag was issued");
+        return Ok(HttpResponse::PreconditionFailed().finish());
+    }
+
+    if delete_recipe_from_db(&pool, recipe_id.as_uuid()).await? {
+        info!("Recipe {recipe_id} deleted from the DB");
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
+
+/// Batch-delete of recipes.
+///
+/// # Description
+///
+/// Delete a list of recipes at once. Each ID receives its own outcome in the response, following the order of the
+/// request:
+/// - `deleted`: the recipe was removed from the DB.
+/// - `not_found`: no recipe matched the ID.
+/// - `invalid_id`: the ID has an invalid format.
+/// - `not_owned`: the recipe is not owned by the author given in `owner`.
+///
+/// Short IDs of the recipes are accepted as well, and they are reported as given in the request.
+///
+/// All the deletions are applied within a single transaction. The batch is limited to 100 IDs.
+///
+/// This resource is restricted to clients of the API with administration privileges.
+#[utoipa::path(
+    post,
+    path = "/recipe/batch-delete",
+    tag = "Recipe",
+    security(
+        ("api_key" = [])
+    ),
+    request_body(
+        content = BatchDelete, description = "The IDs of the recipes to delete.",
+    ),
+    responses(
+        (status = 200, description = "The batch was processed.", body = [BatchItemResult]),
+        (status = 400, description = "The batch is empty or exceeds the maximum size."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The client has no administration privileges, or the API key doesn't grant the `write` scope."),
+    )
+)]
+#[instrument(skip(pool, token, req, access))]
+#[post("/batch-delete")]
+pub async fn batch_delete_recipes(
+    req: Json<BatchDelete>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_admin_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    if !req.is_valid_size() {
+        info!("Batch of {} IDs rejected", req.ids.len());
+        return Ok(HttpResponse::BadRequest().body(format!(
+            "The batch must contain between 1 and {MAX_BATCH_SIZE} IDs"
+        )));
+    }
+
+    let mut ids = req.parsed_ids();
+    let short_ids = ids
+        .iter()
+        .filter(|(_, id)| id.is_none())
+        .filter_map(|(raw_id, _)| ShortId::try_from(*raw_id).ok())
+        .collect::<Vec<ShortId>>();
+    let resolved =
+        get_recipe_ids_by_short_id(&pool, &short_ids.iter().collect::<Vec<&ShortId>>()).await?;
+    for (raw_id, id) in ids.iter_mut().filter(|(_, id)| id.is_none()) {
+        *id = resolved
+            .iter()
+            .find(|(short_id, _)| short_id.as_str() == *raw_id)
+            .map(|(_, id)| *id);
+    }
+
+    let mut results =
+        delete_recipes_from_db(&pool, &ids, req.owner.as_ref().map(|id| id.as_uuid())).await?;
+    // Well-formed short IDs that weren't resolved match no recipe.
+    for result in results.iter_mut() {
+        if result.outcome == BatchOutcome::InvalidId
+            && short_ids.iter().any(|s| s.as_str() == result.id)
+        {
+            result.outcome = BatchOutcome::NotFound;
+        }
+    }
+    info!("Batch-delete of {} recipes processed", results.len());
+
+    Ok(HttpResponse::Ok().json(results))
+}
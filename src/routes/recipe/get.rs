@@ -7,61 +7,204 @@
 //! Example
 
 use crate::{
-    domain::{DataDomainError, RecipeQuery},
+    authentication::{access_denied_response, key_client_id, AccessControl, AuthData},
+    domain::{
+        collation::{Collation, NameCollations},
+        search_expression::SearchExpression,
+        sort::RecipeSort,
+        ApiError, Equipment, Recipe, RecipeCategory, RecipeQuery, RecipeState, ResourceId,
+    },
+    routes::expand::{expand_recipes, ExpandQuery, ExpandedRecipe, Expansion},
+    routes::me::preferences::{request_preferences, DisplayQuery},
     routes::recipe::{
-        get_recipe_from_db, search_recipe_by_category, search_recipe_by_name,
-        search_recipe_by_rating,
+        get_recipe_from_db,
+        utils::{
+            count_recipes_by_criteria, get_author_public_name_from_db, get_named_ingredients,
+            get_rating_votes_from_db, get_recipe_facets_from_db, get_recipe_id_by_slug,
+            get_recipes_from_db, is_recipe_pending_moderation, search_recipe_by_criteria,
+            RecipeCriteria,
+        },
+        RecipeId,
+    },
+    utils::{
+        changes::{get_collection_last_modified, Collection},
+        http::{
+            accepted_language, client_ip, is_not_modified, last_modified, resource_etag,
+            MaxPageSize, Page,
+        },
+        jsonld::{recipe_to_jsonld, JSONLD_CONTENT_TYPE},
+        metrics::analytics,
+        spec::{CocktailSpec, SPEC_CONTENT_TYPE},
     },
 };
 use actix_web::{
     get,
+    http::header::{ETag, CONTENT_LANGUAGE},
     web::{Data, Path, Query},
-    HttpResponse,
+    HttpRequest, HttpResponse,
 };
+use serde::{Deserialize, Serialize};
 use sqlx::MySqlPool;
 use std::convert::TryFrom;
-use std::error::Error;
 use std::fmt::Display;
 use tracing::{info, instrument};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+/// Related resources that can be embedded in the recipes.
+pub const RECIPE_EXPANSIONS: [Expansion; 2] = [Expansion::Ingredients, Expansion::Author];
+
+/// Maximum number of tags included by the facets of a search.
+pub const MAX_FACET_TAGS: u32 = 10;
+
+/// Query parameters that select the content of the search results.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FacetsQuery {
+    /// Include the facets of the results, i.e. `facets=true`.
+    pub facets: Option<bool>,
+}
+
+/// Number of recipes within a category.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct CategoryFacet {
+    pub category: RecipeCategory,
+    #[schema(example = 12)]
+    pub count: u64,
+}
+
+/// Number of recipes rated within a bucket of whole stars.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct RatingFacet {
+    /// Recipes rated from `stars` up to, but excluding, `stars + 1`. The 5 stars bucket only includes the top rating.
+    #[schema(example = 4)]
+    pub stars: u8,
+    #[schema(example = 7)]
+    pub count: u64,
+}
+
+/// Number of recipes tagged with a tag.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct TagFacet {
+    #[schema(example = "tequila")]
+    pub tag: String,
+    #[schema(example = 5)]
+    pub count: u64,
+}
+
+/// Facets of the results of a recipe search.
+///
+/// # Description
+///
+/// Facets count the recipes of the results per category, per rating and per tag, so frontends can render the filters
+/// of a search without issuing extra requests. Only the most used tags are included (see [MAX_FACET_TAGS]).
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct RecipeFacets {
+    pub categories: Vec<CategoryFacet>,
+    pub ratings: Vec<RatingFacet>,
+    pub tags: Vec<TagFacet>,
+}
+
+/// Results of a recipe search that include the facets.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct FacetedResults {
+    pub recipes: Vec<ExpandedRecipe>,
+    pub facets: RecipeFacets,
+}
+
 /// GET method for the /recipe endpoint (Public).
 ///
 /// # Description
 ///
 /// The GET method allows *searching* a recipe in the DB. It expects multiple attributes to filter the recipes in the
 /// DB that shall be encoded in the url. The following keys can be used to perform a search:
-/// - `name`: Use a string that can match the name of a recipe (or part of it).
-/// - `tags`: Only recipes that contain all the included tags in the query will be returned by the API.
+/// - `name`: Use a string that can match the name of a recipe (or part of it). Accents and letter case are ignored,
+///   i.e. `pina colada` matches `Piña Colada`.
+/// - `tags`: Comma-separated list of tags. Only recipes that contain all the included tags in the query will be
+///   returned by the API. An invalid tag is answered with a code **400**.
 /// - `rating`: Recipes that are scored with a rating greater or equal to the given rating will be returned by the API.
-///   See the schema `RecipeRating` for more details.
+///   Half-star ratings are accepted, i.e. `rating=3.5`. See the schema `StarRate` for more details.
 /// - `category`: Filter recipes using one of the available categories. See the schema `RecipeCategory` for more
 ///    details.
+/// - `equipment_excludes`: Comma-separated list of equipment. Recipes that need any of it are filtered out, so users
+///   without some gear can find recipes they can prepare. See the schema `Equipment` for more details.
+/// - `max_prep_time`: Only recipes that can be prepared in the given amount of minutes (or less) will be returned by
+///   the API. Recipes with no estimated preparation time are excluded.
+/// - `state`: State of the recipes in the publishing workflow. Only published recipes are returned by default.
+///   Searching recipes in other states (`draft`, `submitted` or `archived`) needs an API key.
+/// - `q`: Search expression that combines terms using `AND`, `OR`, `NOT` (or `-`) and parentheses, i.e.
+///   `tag:tiki AND (rum OR cachaça) -egg`. Terms can be prefixed by a field: `tag:`, `name:`, `ingredient:`,
+///   `category:` or `rating:`. Terms without a field match the name of the recipes or the name of their ingredients.
+///   An invalid expression is answered with a code **400**.
 ///
 /// A query can be composed by many attributes. For example, consider this query:
 ///
 /// ```bash
-/// http://localhost:9090/recipe?name=margarita&tags=tequila&tags=reposado&rating=2
+/// http://localhost:9090/recipe?name=margarita&tags=tequila,reposado&rating=4
 /// ```
 ///
 /// Would return recipes that contain the string *margarita* in their name attribute; whose tags include *tequila* and
 /// *reposado*; and, whose rating is greater or equal to 4 stars.
+///
+/// Use `facets=true` to get the results along with their facets, i.e. the number of matching recipes per category, per
+/// rating and per tag. In that case, the response is an object that includes the recipes and the facets (see the
+/// schema `FacetedResults`), rather than a list of recipes.
+///
+/// Responses include the time of the latest change of the recipes in a `Last-Modified` header. Clients that poll a
+/// search can send it back using `If-Modified-Since`, and the request is answered with a code **304** when no recipe
+/// changed since then.
+///
+/// The amounts of the ingredients are converted to the unit system given by `units`. Requests that include an API key
+/// use the preferences of the client (see `GET /me/preferences`) when `units` is not given.
+///
+/// Use `expand=ingredients` to embed the name and the category of the ingredients within each recipe, and
+/// `expand=author` to embed the public profile of their authors (see the schema `ExpandedRecipe`).
+///
+/// Results are paginated using `limit` and `offset`. The total amount of matching recipes is included in the
+/// `X-Total-Count` header, and the links to the other pages in the `Link` header. The facets describe all the matching
+/// recipes, not only the recipes of the page.
+///
+/// Use `sort` to choose the order of the results, i.e. `sort=-rating,name` returns the best rated recipes first, and
+/// the recipes with the same rating sorted by name. Names are sorted following the rules of the language preferred by
+/// the client, or the language given by `lang` or the `Accept-Language` header. Recipes are sorted by ID by default.
 #[utoipa::path(
     get,
     path = "/recipe",
     tag = "Recipe",
-    params(RecipeQuery),
+    params(
+        RecipeQuery,
+        DisplayQuery,
+        FacetsQuery,
+        ExpandQuery,
+        ("If-Modified-Since" = Option<String>, Header, description = "Skip the search when no recipe changed since the given date."),
+    ),
     responses(
         (
             status = 200,
-            description = "The query was executed successfully and produced some matches.",
-            body = [Recipe],
+            description = "The query was executed successfully and produced some matches. A `FacetedResults` object is returned when `facets=true`.",
+            body = [ExpandedRecipe],
             headers(
                 ("Access-Control-Allow-Origin"),
                 ("Content-Type"),
-                ("Cache-Control"),
+                ("Last-Modified", description = "Time of the latest change of the recipes."),
+                ("X-Total-Count", description = "Amount of recipes that match the search."),
+                ("Link", description = "Links to the first, previous, next and last pages."),
+            )
+        ),
+        (
+            status = 304,
+            description = "No recipe changed since the date given in `If-Modified-Since`.",
+            headers(
+                ("Last-Modified", description = "Time of the latest change of the recipes."),
             )
         ),
+        (
+            status = 400,
+            description = "The query includes no search criteria, or some of the given tags, equipment, expansions, the search expression, the sort or the page is not valid.",
+        ),
+        (
+            status = 401,
+            description = "Recipes that are not published were requested without a valid API key.",
+        ),
         (
             status = 404,
             description = "The query was executed successfully but didn't produce any match.",
@@ -79,74 +222,196 @@ use uuid::Uuid;
                 ("Retry-After"),
             )
         ),
+        (
+            status = 503, description = "**The server is overloaded.** Retrieval of resources is temporarily disabled.",
+            headers(
+                ("Cache-Control", description = "Cache control is set to *no-cache*."),
+                ("Retry-After", description = "Amount of time to wait before retrying (seconds).")
+            )
+        ),
 
     )
 )]
+#[allow(clippy::too_many_arguments)]
 #[get("")]
 pub async fn search_recipe(
     req: Query<RecipeQuery>,
+    overrides: Query<DisplayQuery>,
+    facets: Query<FacetsQuery>,
+    expand: Query<ExpandQuery>,
+    token: Option<Query<AuthData>>,
+    request: HttpRequest,
     pool: Data<MySqlPool>,
-) -> Result<HttpResponse, Box<dyn Error>> {
-    let search_type: SearchType = (&req.0).try_into().expect("Wrong query");
-
-    info!("Recipe search ({search_type}) using: {{{}}}", req.0);
-
-    let recipe_ids = match search_type {
-        SearchType::ByName => {
-            let search_token = match req.0.name {
-                Some(name) => name,
-                None => return Err(Box::new(DataDomainError::InvalidSearch)),
-            };
-            search_recipe_by_name(&pool, &search_token).await?
-        }
-        SearchType::ByCategory => {
-            let search_token = match req.0.category {
-                Some(category) => category,
-                None => return Err(Box::new(DataDomainError::InvalidSearch)),
-            };
-            search_recipe_by_category(&pool, search_token).await?
+    access: Data<AccessControl>,
+    collations: Data<NameCollations>,
+    max_page_size: Data<MaxPageSize>,
+) -> Result<HttpResponse, ApiError> {
+    let page = match Page::new(req.limit, req.offset, **max_page_size) {
+        Ok(page) => page,
+        Err(e) => return Ok(HttpResponse::BadRequest().body(e)),
+    };
+    let search = match RecipeSearch::new(req.into_inner()) {
+        Ok(search) => search,
+        Err(e) => {
+            info!("Invalid recipe search: {e}");
+            return Ok(HttpResponse::BadRequest().body(e));
         }
-        SearchType::ByRating => {
-            let search_token = match req.0.rating {
-                Some(rating) => rating,
-                None => return Err(Box::new(DataDomainError::InvalidSearch)),
-            };
-            search_recipe_by_rating(&pool, search_token).await?
+    };
+    let expansions = match expand.expansions(&RECIPE_EXPANSIONS) {
+        Ok(expansions) => expansions,
+        Err(e) => {
+            info!("Invalid expansion: {e}");
+            return Ok(HttpResponse::BadRequest().body(e));
         }
-        SearchType::ByTags => return Ok(HttpResponse::NotImplemented().finish()),
-        SearchType::Intersection => return Ok(HttpResponse::NotImplemented().finish()),
     };
+    let preferences = match request_preferences(&pool, &access, token.as_deref(), &overrides).await
+    {
+        Ok(preferences) => preferences,
+        Err(e) => return access_denied_response(e),
+    };
+    let language = preferences
+        .lang
+        .or_else(|| accepted_language(&request))
+        .unwrap_or_default();
+    let search = search.with_collation(collations.get(language).cloned());
+    // The access of requests that include an API key was checked when resolving the preferences.
+    if search
+        .query()
+        .state
+        .is_some_and(|s| s != RecipeState::Published)
+        && token.is_none()
+    {
+        info!("Recipes that are not published were requested without an API key");
+        return Ok(HttpResponse::Unauthorized().finish());
+    }
+    if let Some(term) = search
+        .query()
+        .name
+        .as_deref()
+        .or(search.query().q.as_deref())
+    {
+        // Clients are told apart by their ID when they give an API key, as several clients might share an IP.
+        let client = token
+            .as_deref()
+            .and_then(|token| key_client_id(&token.api_key).ok())
+            .map(|id| id.to_string())
+            .or_else(|| client_ip(&request).map(|ip| ip.to_string()));
+        analytics().record_search("recipe", term, client.as_deref());
+    }
 
-    let mut recipes = Vec::new();
-
-    for id in recipe_ids.iter() {
-        recipes.push(get_recipe_from_db(&pool, id).await?)
+    let collection_modified = get_collection_last_modified(&pool, Collection::Recipe).await?;
+    if let Some(timestamp) = collection_modified {
+        if is_not_modified(&request, timestamp) {
+            info!("No recipe changed since the given date");
+            return Ok(HttpResponse::NotModified()
+                .insert_header(last_modified(timestamp))
+                .finish());
+        }
     }
 
-    if recipes.is_empty() {
-        Ok(HttpResponse::Ok().json(recipes))
-    } else {
+    let (recipes, total) = search.run_page(&pool, &page).await?;
+
+    if total == 0 {
         Ok(HttpResponse::NotFound().finish())
+    } else {
+        let mut response = HttpResponse::Ok();
+        if let Some(timestamp) = collection_modified {
+            response.insert_header(last_modified(timestamp));
+        }
+        page.insert_headers(&mut response, &request, total as usize);
+
+        let recipes = recipes
+            .into_iter()
+            .map(|recipe| preferences.apply(recipe))
+            .collect();
+        let recipes = expand_recipes(&pool, recipes, &expansions).await?;
+
+        if facets.facets.unwrap_or(false) {
+            let facets = search.facets(&pool).await?;
+            Ok(response.json(FacetedResults { recipes, facets }))
+        } else {
+            Ok(response.json(recipes))
+        }
     }
 }
 
+/// Representations available for a recipe.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, ToSchema)]
+pub enum RecipeFormat {
+    /// The [Recipe] object of this API.
+    #[default]
+    #[serde(rename = "json")]
+    Json,
+    /// Structured data using the schema.org `Recipe` vocabulary (JSON-LD).
+    #[serde(rename = "jsonld")]
+    JsonLd,
+    /// Plain text specification in the style of the IBA (see [crate::utils::spec]).
+    #[serde(rename = "spec")]
+    Spec,
+}
+
+/// Query parameters of the singleton recipe resource.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RecipeFormatQuery {
+    /// Representation of the recipe: `json` (default), `jsonld` or `spec`.
+    pub format: Option<RecipeFormat>,
+}
+
 /// Retrieve a recipe from the DB using its unique ID.
+///
+/// # Description
+///
+/// Use `format=jsonld` to get the recipe as schema.org structured data, which frontends can embed in their pages so
+/// crawlers get rich results. Such document includes the names of the ingredients, and the name of the author when
+/// the author shares the profile. Use `format=spec` to get a plain text specification in the style of the IBA, with the
+/// volumes given in centilitres.
+///
+/// Recipes that are not published (see `RecipeState`) are only shown to requests that include an API key.
+///
+/// The short ID of the recipe (`short_id`) is accepted instead of its ID, i.e. `/recipe/4Jq7Wd9tXbC`, here and in the
+/// rest of the recipe resources. Short IDs are meant for shared links.
+///
+/// The amounts of the ingredients are converted to the unit system given by `units`, and the JSON-LD document is
+/// written in the language given by `lang`. Requests that include an API key use the preferences of the client (see
+/// `GET /me/preferences`) when those parameters are not given.
+///
+/// Responses include the version of the recipe in an `ETag` header, which is shared by all the representations of
+/// the recipe, so clients can use it to guard later changes of the recipe.
+///
+/// Use `expand=ingredients` to embed the name and the category of the ingredients within the recipe, and
+/// `expand=author` to embed the public profile of its author (see the schema `ExpandedRecipe`). Expansions only apply
+/// to the `json` format.
 #[utoipa::path(
     get,
     context_path = "/recipe/",
     tag = "Recipe",
+    params(
+        ("id" = String, Path, description = "ID or short ID of the recipe."),
+        RecipeFormatQuery,
+        DisplayQuery,
+        ExpandQuery,
+    ),
     responses(
         (
             status = 200,
             description = "The recipe identified by the given ID was found in the DB",
-            body = Recipe,
+            content(
+                ("application/json" = ExpandedRecipe),
+                ("application/ld+json" = Object),
+                ("text/plain" = String),
+            ),
             headers(
                 ("Content-Length"),
                 ("Content-Type"),
                 ("Date"),
+                ("ETag", description = "Version of the recipe."),
                 ("Vary", description = "Origin,Access-Control-Request-Method,Access-Control-Request-Headers")
             ),
         ),
+        (
+            status = 400,
+            description = "The given recipe's ID, the requested format or the expansions are not valid.",
+        ),
         (
             status = 404,
             description = "The given recipe's ID was not found in the DB.",
@@ -164,23 +429,359 @@ pub async fn search_recipe(
                 ("Retry-After"),
             )
         ),
+        (
+            status = 503, description = "**The server is overloaded.** Retrieval of resources is temporarily disabled.",
+            headers(
+                ("Cache-Control", description = "Cache control is set to *no-cache*."),
+                ("Retry-After", description = "Amount of time to wait before retrying (seconds).")
+            )
+        ),
 
     )
 
 )]
-#[instrument(skip(pool))]
+#[instrument(skip(pool, token, access))]
 #[get("{id}")]
 pub async fn get_recipe(
     pool: Data<MySqlPool>,
-    path: Path<(String,)>,
-) -> Result<HttpResponse, Box<dyn Error>> {
-    let recipe_id = Uuid::parse_str(&path.0).map_err(|_| DataDomainError::InvalidId)?;
+    access: Data<AccessControl>,
+    recipe_id: RecipeId,
+    query: Query<RecipeFormatQuery>,
+    overrides: Query<DisplayQuery>,
+    expand: Query<ExpandQuery>,
+    token: Option<Query<AuthData>>,
+) -> Result<HttpResponse, ApiError> {
+    recipe_response(
+        &pool,
+        &access,
+        recipe_id.as_uuid(),
+        &query,
+        &overrides,
+        &expand,
+        token.as_deref(),
+    )
+    .await
+}
+
+/// Path of the recipe resource that uses slugs.
+#[derive(Debug, Deserialize)]
+pub struct RecipeSlugPath {
+    /// ID of the author of the recipe.
+    pub author: ResourceId,
+    /// Slug of the recipe.
+    pub slug: String,
+}
+
+/// Retrieve a recipe from the DB using its slug (Public).
+///
+/// # Description
+///
+/// Slugs are URL-friendly versions of the names of the recipes, i.e. `pina-colada`, so frontends can build pretty
+/// URLs. A slug identifies a recipe among the recipes of its author, thus the ID of the author is needed as well. The
+/// slug of a recipe is included in the `slug` attribute of the recipe.
+///
+/// This resource behaves like `GET /recipe/{id}`: it supports the same formats, preferences and expansions.
+#[utoipa::path(
+    get,
+    path = "/recipe/by-slug/{author}/{slug}",
+    tag = "Recipe",
+    params(
+        ("author" = String, Path, description = "ID of the author of the recipe."),
+        ("slug" = String, Path, description = "Slug of the recipe."),
+        RecipeFormatQuery,
+        DisplayQuery,
+        ExpandQuery,
+    ),
+    responses(
+        (
+            status = 200,
+            description = "The recipe was found in the DB",
+            content(
+                ("application/json" = ExpandedRecipe),
+                ("application/ld+json" = Object),
+                ("text/plain" = String),
+            ),
+            headers(
+                ("ETag", description = "Version of the recipe."),
+            ),
+        ),
+        (
+            status = 400,
+            description = "The given author's ID, the requested format or the expansions are not valid.",
+        ),
+        (status = 404, description = "The author has no recipe with the given slug."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("by-slug/{author}/{slug}")]
+pub async fn get_recipe_by_slug(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    path: Path<RecipeSlugPath>,
+    query: Query<RecipeFormatQuery>,
+    overrides: Query<DisplayQuery>,
+    expand: Query<ExpandQuery>,
+    token: Option<Query<AuthData>>,
+) -> Result<HttpResponse, ApiError> {
+    let Some(recipe_id) = get_recipe_id_by_slug(&pool, path.author.as_uuid(), &path.slug).await?
+    else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    recipe_response(
+        &pool,
+        &access,
+        &recipe_id,
+        &query,
+        &overrides,
+        &expand,
+        token.as_deref(),
+    )
+    .await
+}
+
+/// Build the response of the singleton recipe resources.
+async fn recipe_response(
+    pool: &MySqlPool,
+    access: &AccessControl,
+    recipe_id: &Uuid,
+    query: &RecipeFormatQuery,
+    overrides: &DisplayQuery,
+    expand: &ExpandQuery,
+    token: Option<&AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    let expansions = match expand.expansions(&RECIPE_EXPANSIONS) {
+        Ok(expansions) => expansions,
+        Err(e) => {
+            info!("Invalid expansion: {e}");
+            return Ok(HttpResponse::BadRequest().body(e));
+        }
+    };
+    let preferences = match request_preferences(pool, access, token, overrides).await {
+        Ok(preferences) => preferences,
+        Err(e) => return access_denied_response(e),
+    };
+
+    // Recipes pending moderation are hidden from the public.
+    if is_recipe_pending_moderation(pool, recipe_id).await? {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let (recipe, etag) = match get_recipe_from_db(pool, recipe_id).await? {
+        // The tag identifies the stored version of the recipe, before applying the preferences of the client.
+        Some(recipe) => {
+            let etag = resource_etag(&recipe)?;
+            (preferences.apply(recipe), etag)
+        }
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+    // Recipes that are not published are only shown to the clients of the API.
+    if recipe.state() != Some(RecipeState::Published) && token.is_none() {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    match query.format.unwrap_or_default() {
+        RecipeFormat::Json => {
+            let recipe = expand_recipes(pool, vec![recipe], &expansions)
+                .await?
+                .pop()
+                .expect("A recipe was given");
+            Ok(HttpResponse::Ok().insert_header(ETag(etag)).json(recipe))
+        }
+        RecipeFormat::JsonLd => {
+            let ingredients = get_named_ingredients(pool, &recipe).await?;
+            let language = preferences.lang.unwrap_or_default();
+            let author = match recipe.owner() {
+                Some(owner) => get_author_public_name_from_db(pool, &owner).await?,
+                None => None,
+            };
+            let votes = match recipe.id() {
+                Some(id) => get_rating_votes_from_db(pool, &id).await?,
+                None => 0,
+            };
+
+            Ok(HttpResponse::Ok()
+                .insert_header(ETag(etag))
+                .content_type(JSONLD_CONTENT_TYPE)
+                .insert_header((CONTENT_LANGUAGE, language.to_string()))
+                .body(
+                    recipe_to_jsonld(&recipe, &ingredients, author.as_deref(), language, votes)
+                        .to_string(),
+                ))
+        }
+        RecipeFormat::Spec => {
+            let ingredients = get_named_ingredients(pool, &recipe).await?;
+
+            Ok(HttpResponse::Ok()
+                .insert_header(ETag(etag))
+                .content_type(SPEC_CONTENT_TYPE)
+                .body(CocktailSpec::new(&recipe, &ingredients).to_string()))
+        }
+    }
+}
+
+/// Recipe search, validated and ready to run against the DB.
+///
+/// # Description
+///
+/// This is the search engine behind [search_recipe], which is shared with the saved searches of the clients (see
+/// [crate::routes::me::searches]). All the criteria are combined within a single query (see [RecipeCriteria]).
+/// Recipes pending moderation are skipped, and only published recipes are returned unless the query asks for another
+/// state.
+#[derive(Debug, Clone)]
+pub struct RecipeSearch {
+    query: RecipeQuery,
+    search_type: SearchType,
+    excluded_equipment: Option<Vec<Equipment>>,
+    expression: Option<SearchExpression>,
+    sort: Option<RecipeSort>,
+    collation: Option<Collation>,
+    ingredients: Option<Vec<Uuid>>,
+    author: Option<Uuid>,
+}
+
+impl RecipeSearch {
+    /// Validate a query. A description of the issue is returned when the query is not valid.
+    pub fn new(query: RecipeQuery) -> Result<Self, String> {
+        let search_type = SearchType::try_from(&query)
+            .map_err(|_| "The query includes no search criteria".to_string())?;
+        let excluded_equipment = match query.equipment_excludes.as_deref() {
+            Some(equipment) => Some(
+                Equipment::parse_list(equipment).map_err(|e| format!("Invalid equipment: {e}"))?,
+            ),
+            None => None,
+        };
+        let expression = match query.q.as_deref() {
+            Some(expression) => Some(
+                SearchExpression::parse(expression)
+                    .map_err(|e| format!("Invalid search expression: {e}"))?,
+            ),
+            None => None,
+        };
+        let sort = match query.sort.as_deref() {
+            Some(sort) => Some(RecipeSort::parse(sort).map_err(|e| format!("Invalid sort: {e}"))?),
+            None => None,
+        };
+
+        Ok(RecipeSearch {
+            query,
+            search_type,
+            excluded_equipment,
+            expression,
+            sort,
+            collation: None,
+            ingredients: None,
+            author: None,
+        })
+    }
+
+    /// Sort the names using the given collation, usually the one of the language of the request. The default
+    /// collation of the DB is used otherwise.
+    pub fn with_collation(mut self, collation: Option<Collation>) -> Self {
+        self.collation = collation;
+        self
+    }
 
-    let recipe = get_recipe_from_db(&pool, &recipe_id).await?;
+    /// Only match the recipes that use all the given ingredients.
+    pub fn with_ingredients(mut self, ingredients: Vec<Uuid>) -> Self {
+        self.ingredients = Some(ingredients);
+        self
+    }
+
+    /// Only match the recipes of the given author, whatever the client that registered them.
+    pub fn with_author(mut self, author: Uuid) -> Self {
+        self.author = Some(author);
+        self
+    }
+
+    /// Query of the search.
+    pub fn query(&self) -> &RecipeQuery {
+        &self.query
+    }
+
+    fn criteria(&self) -> RecipeCriteria<'_> {
+        RecipeCriteria {
+            name: self.query.name.as_deref(),
+            tags: self.query.tags.as_deref(),
+            rating: self.query.rating,
+            category: self.query.category.as_ref(),
+            expression: self.expression.as_ref(),
+            excluded_equipment: self.excluded_equipment.as_deref(),
+            max_prep_time: self.query.max_prep_time,
+            ingredients: self.ingredients.as_deref(),
+            author: self.author.as_ref(),
+            state: self.query.state.unwrap_or_default(),
+        }
+    }
+
+    /// Retrieve the IDs of the matching recipes.
+    ///
+    /// # Description
+    ///
+    /// Searches that combine several criteria return the recipes that match all of them. The IDs are sorted following
+    /// the `sort` of the query, or by ID when it is not given, so the pages of the results are stable.
+    pub async fn run_ids(&self, pool: &MySqlPool) -> Result<Vec<Uuid>, ApiError> {
+        info!(
+            "Recipe search ({}) using: {{{}}}",
+            self.search_type, self.query
+        );
+
+        Ok(search_recipe_by_criteria(
+            pool,
+            &self.criteria(),
+            self.sort.as_ref(),
+            self.collation.as_ref(),
+            None,
+            0,
+        )
+        .await?)
+    }
+
+    /// Retrieve the matching recipes. See [RecipeSearch::run_ids].
+    pub async fn run(&self, pool: &MySqlPool) -> Result<Vec<Recipe>, ApiError> {
+        let recipe_ids = self.run_ids(pool).await?;
+
+        get_recipes_from_db(pool, &recipe_ids).await
+    }
+
+    /// Retrieve a page of the matching recipes, along with the amount of matching recipes. See
+    /// [RecipeSearch::run_ids].
+    ///
+    /// # Description
+    ///
+    /// Only the recipes of the page are read from the DB. A page beyond the end of the results is empty.
+    pub async fn run_page(
+        &self,
+        pool: &MySqlPool,
+        page: &Page,
+    ) -> Result<(Vec<Recipe>, u64), ApiError> {
+        info!(
+            "Recipe search ({}) using: {{{}}}",
+            self.search_type, self.query
+        );
+        let criteria = self.criteria();
+
+        let total = count_recipes_by_criteria(pool, &criteria).await?;
+        if page.offset as u64 >= total {
+            return Ok((Vec::new(), total));
+        }
+
+        let recipe_ids = search_recipe_by_criteria(
+            pool,
+            &criteria,
+            self.sort.as_ref(),
+            self.collation.as_ref(),
+            Some(page.limit as u32),
+            page.offset as u32,
+        )
+        .await?;
+
+        Ok((get_recipes_from_db(pool, &recipe_ids).await?, total))
+    }
 
-    match recipe {
-        Some(recipe) => Ok(HttpResponse::Ok().json(recipe)),
-        None => Ok(HttpResponse::NotFound().finish()),
+    /// Count the matching recipes per category, per rating and per tag. See [get_recipe_facets_from_db].
+    pub async fn facets(&self, pool: &MySqlPool) -> Result<RecipeFacets, ApiError> {
+        Ok(get_recipe_facets_from_db(pool, &self.criteria(), MAX_FACET_TAGS).await?)
     }
 }
 
@@ -190,6 +791,8 @@ enum SearchType {
     ByTags,
     ByRating,
     ByCategory,
+    ByFilters,
+    ByExpression,
     Intersection,
 }
 
@@ -200,6 +803,8 @@ impl Display for SearchType {
             SearchType::ByTags => "ByTags",
             SearchType::ByRating => "ByRating",
             SearchType::ByCategory => "ByCategory",
+            SearchType::ByFilters => "ByFilters",
+            SearchType::ByExpression => "ByExpression",
             SearchType::Intersection => "Intersection",
         };
 
@@ -208,8 +813,13 @@ impl Display for SearchType {
 }
 
 fn multiple_choices(query: &RecipeQuery) -> bool {
-    if (query.name.is_some()
-        && (query.tags.is_some() || query.rating.is_some() || query.category.is_some()))
+    if (query.q.is_some()
+        && (query.name.is_some()
+            || query.tags.is_some()
+            || query.rating.is_some()
+            || query.category.is_some()))
+        || (query.name.is_some()
+            && (query.tags.is_some() || query.rating.is_some() || query.category.is_some()))
         || (query.tags.is_some() && (query.rating.is_some() || query.category.is_some()))
         || (query.rating.is_some() && query.category.is_some())
     {
@@ -225,6 +835,8 @@ impl TryFrom<&RecipeQuery> for SearchType {
     fn try_from(query: &RecipeQuery) -> std::result::Result<Self, Self::Error> {
         if multiple_choices(query) {
             Ok(SearchType::Intersection)
+        } else if query.q.is_some() {
+            Ok(SearchType::ByExpression)
         } else if query.name.is_some() {
             Ok(SearchType::ByName)
         } else if query.tags.is_some() {
@@ -233,6 +845,11 @@ impl TryFrom<&RecipeQuery> for SearchType {
             Ok(SearchType::ByRating)
         } else if query.category.is_some() {
             Ok(SearchType::ByCategory)
+        } else if query.equipment_excludes.is_some()
+            || query.max_prep_time.is_some()
+            || query.state.is_some()
+        {
+            Ok(SearchType::ByFilters)
         } else {
             Err("Invalid conversion".to_string())
         }
@@ -6,29 +6,49 @@
 
 //! Recipe endpoint head method.
 
-use crate::domain::RecipeQuery;
-use actix_web::{head, web, HttpResponse, Responder};
+use crate::{
+    domain::ApiError,
+    routes::recipe::{
+        utils::{get_recipe_metadata_from_db, is_recipe_hidden},
+        RecipeId,
+    },
+    utils::http::{last_modified, X_RECIPE_RATING},
+};
+use actix_web::{head, web::Data, HttpResponse};
+use sqlx::MySqlPool;
+use tracing::{debug, instrument};
 
-/// HEAD method for the Recipe endpoint (Public).
+/// Metadata request for a recipe (Public).
 ///
 /// # Description
 ///
-/// This method checks the headers that a GET method to the endpoint `/recipe` would respond. This is useful to
-/// check the header `Content-Length` and others without doing the full request.
+/// This singleton resource checks whether a recipe exists without retrieving the full recipe, and includes some
+/// metadata in the headers, so clients can cheaply decide whether to fetch the full resource:
+/// - `Last-Modified`: the latest modification of the recipe, or its creation when it was never modified.
+/// - `X-Recipe-Rating`: the rating of the recipe (stars).
 #[utoipa::path(
     head,
-    path = "/recipe",
+    context_path = "/recipe/",
     tag = "Recipe",
+    params(("id" = String, Path, description = "ID or short ID of the recipe.")),
     responses(
         (
             status = 200,
-            description = "The search query was successfully executed.",
+            description = "The given ID matches an existing recipe in the DB.",
             headers(
-                ("Cache-Control"),
+                ("Last-Modified", description = "Latest modification of the recipe."),
+                ("X-Recipe-Rating" = f32, description = "Rating of the recipe (0 to 5 stars)."),
                 ("Access-Control-Allow-Origin"),
-                ("Content-Type")
             )
         ),
+        (
+            status = 400,
+            description = "The given recipe's ID has an invalid format.",
+        ),
+        (
+            status = 404,
+            description = "The given recipe's ID was not found in the DB.",
+        ),
         (
             status = 429, description = "**Too many requests.**",
             headers(
@@ -36,10 +56,35 @@ use actix_web::{head, web, HttpResponse, Responder};
                 ("Access-Control-Allow-Origin"),
                 ("Retry-After", description = "Amount of time between requests (seconds)."),
             )
-        )
+        ),
+        (
+            status = 503, description = "**The server is overloaded.** Retrieval of resources is temporarily disabled.",
+            headers(
+                ("Cache-Control", description = "Cache control is set to *no-cache*."),
+                ("Retry-After", description = "Amount of time to wait before retrying (seconds).")
+            )
+        ),
     )
 )]
-#[head("")]
-pub async fn head_recipe(_req: web::Query<RecipeQuery>) -> impl Responder {
-    HttpResponse::NotImplemented().finish()
+#[instrument(skip(pool), fields(recipe_id = %recipe_id))]
+#[head("{id}")]
+pub async fn head_recipe(
+    recipe_id: RecipeId,
+    pool: Data<MySqlPool>,
+) -> Result<HttpResponse, ApiError> {
+    // Recipes that are not published, or pending moderation, are hidden from the public.
+    if is_recipe_hidden(&pool, recipe_id.as_uuid()).await? {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let metadata = match get_recipe_metadata_from_db(&pool, recipe_id.as_uuid()).await? {
+        Some(metadata) => metadata,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+    debug!("Recipe metadata: {metadata:?}");
+
+    Ok(HttpResponse::Ok()
+        .insert_header(last_modified(metadata.last_modified))
+        .insert_header((X_RECIPE_RATING, metadata.rating.value().to_string()))
+        .finish())
 }
@@ -0,0 +1,146 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Recipes that can be prepared with a list of ingredients (a pantry).
+//!
+//! # Description
+//!
+//! Unlike the suggestions (see [crate::routes::recipe::suggest]), which rank the recipes that nearly match the
+//! ingredients at hand, this resource answers a yes/no question: the recipes returned can be prepared with the given
+//! ingredients, i.e. the ingredients of the recipe are a subset of the pantry. The near-miss mode relaxes it to the
+//! recipes that miss a single ingredient.
+
+use crate::{
+    domain::ApiError,
+    routes::recipe::{
+        suggest::{parse_ingredient_list, Suggestion},
+        utils::{count_suggested_recipes_from_db, get_recipes_from_db, suggest_recipes_from_db},
+    },
+    utils::http::{MaxPageSize, Page},
+};
+use actix_web::{
+    get,
+    web::{Data, Query},
+    HttpRequest, HttpResponse,
+};
+use serde::Deserialize;
+use sqlx::MySqlPool;
+use std::collections::HashSet;
+use tracing::{debug, info, instrument};
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+/// Maximum amount of missing ingredients of the near-miss mode.
+pub const MAX_NEAR_MISS: u32 = 1;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct MakeableQuery {
+    /// Comma-separated list of the IDs of the ingredients of the pantry.
+    #[param(example = "0191e13b-5ab7-78f1-bc06-be503a6c111b,0191e13b-5ab7-78f1-bc06-be503a6c111c")]
+    pub ingredients: String,
+    /// Amount of ingredients that the recipes may miss: 0 (default) returns only the recipes that can be prepared,
+    /// and 1 also returns the near misses.
+    #[param(example = 1)]
+    pub missing: Option<u32>,
+    /// Amount of recipes of the page, 20 by default. The maximum depends on the settings of the server (100 by
+    /// default).
+    #[param(example = 20)]
+    pub limit: Option<u32>,
+    /// Amount of recipes skipped, 0 by default.
+    #[param(example = 0)]
+    pub offset: Option<u32>,
+}
+
+/// Recipes that can be prepared with the given ingredients (Public).
+///
+/// # Description
+///
+/// Recipes whose ingredients are all included in the given list are returned. Use `missing=1` to also return the
+/// recipes that miss a single ingredient; the missing ingredients are listed along with each recipe. Recipes that can
+/// be prepared go first, then the results are sorted by rating. Only published recipes are considered.
+///
+/// Results are paginated using `limit` and `offset`. The total amount of recipes is included in the `X-Total-Count`
+/// header, and the links to the other pages in the `Link` header.
+#[utoipa::path(
+    get,
+    path = "/recipe/makeable",
+    tag = "Recipe",
+    params(MakeableQuery),
+    responses(
+        (
+            status = 200,
+            description = "The recipes that can be prepared, or nearly.",
+            body = [Suggestion],
+            headers(
+                ("X-Total-Count", description = "Amount of recipes that can be prepared, or nearly."),
+                ("Link", description = "Links to the first, previous, next and last pages."),
+            )
+        ),
+        (
+            status = 400,
+            description = "The list of ingredients is empty, too long, or includes invalid IDs, or the amount of missing ingredients or the page is not valid.",
+        ),
+    )
+)]
+#[instrument(skip(pool, request, max_page_size))]
+#[get("/makeable")]
+pub async fn get_makeable_recipes(
+    req: Query<MakeableQuery>,
+    request: HttpRequest,
+    pool: Data<MySqlPool>,
+    max_page_size: Data<MaxPageSize>,
+) -> Result<HttpResponse, ApiError> {
+    let page = match Page::new(req.limit, req.offset, **max_page_size) {
+        Ok(page) => page,
+        Err(e) => return Ok(HttpResponse::BadRequest().body(e)),
+    };
+    let max_missing = req.missing.unwrap_or_default();
+    if max_missing > MAX_NEAR_MISS {
+        info!("Too many missing ingredients requested: {max_missing}");
+        return Ok(HttpResponse::BadRequest().body(format!(
+            "The recipes can miss {MAX_NEAR_MISS} ingredient at most"
+        )));
+    }
+    let Some(ingredients) = parse_ingredient_list(&req.ingredients) else {
+        info!("Invalid list of ingredients: {}", req.ingredients);
+        return Ok(HttpResponse::BadRequest().finish());
+    };
+
+    let total = count_suggested_recipes_from_db(&pool, &ingredients, max_missing).await?;
+    debug!("Makeable recipes: {total}");
+
+    let recipes = if page.offset as u64 >= total {
+        Vec::new()
+    } else {
+        let ids = suggest_recipes_from_db(
+            &pool,
+            &ingredients,
+            max_missing,
+            Some(page.limit as u32),
+            page.offset as u32,
+        )
+        .await?;
+        get_recipes_from_db(&pool, &ids).await?
+    };
+
+    let at_hand: HashSet<Uuid> = ingredients.iter().copied().collect();
+    let recipes = recipes
+        .into_iter()
+        .map(|recipe| {
+            let missing = recipe
+                .ingredients()
+                .iter()
+                .map(|i| i.ingredient_id)
+                .filter(|i| !at_hand.contains(i))
+                .collect();
+            Suggestion { recipe, missing }
+        })
+        .collect::<Vec<Suggestion>>();
+
+    let mut response = HttpResponse::Ok();
+    page.insert_headers(&mut response, &request, total as usize);
+    Ok(response.json(recipes))
+}
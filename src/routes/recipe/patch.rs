@@ -4,36 +4,185 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-//! Author endpoint PATCH method.
+//! Recipe endpoint PATCH method.
 
-use actix_web::{patch, web, HttpResponse, Responder};
+use crate::{
+    authentication::{access_denied_response, check_access, AccessControl, AuthData, Scope},
+    domain::{
+        recipe::RecipeLimits, screening::Screener, ApiError, DataDomainError, Equipment, Recipe,
+        RecipeCategory, RecipeContains, RecipeLicense, RecipeSource,
+    },
+    routes::recipe::{
+        utils::{flag_recipe_in_db, get_recipe_from_db, update_recipe_in_db},
+        RecipeId,
+    },
+};
+use actix_web::{
+    patch,
+    web::{Data, Json, Query},
+    HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Partial definition of a recipe. Only the given attributes are modified.
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct RecipePatch {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<RecipeCategory>,
+    pub url: Option<String>,
+    /// Replaces the attribution of the recipe.
+    pub source: Option<RecipeSource>,
+    /// Replaces the license of the recipe.
+    pub license: Option<RecipeLicense>,
+    /// Replaces all the ingredients of the recipe.
+    pub ingredients: Option<Vec<RecipeContains>>,
+    /// Replaces all the steps of the recipe.
+    pub steps: Option<Vec<String>>,
+    /// Replaces all the equipment of the recipe.
+    pub equipment: Option<Vec<Equipment>>,
+    /// Estimated preparation time (minutes).
+    pub prep_time_minutes: Option<u16>,
+}
+
+impl RecipePatch {
+    /// Build a new [Recipe] applying the modifications to an existing recipe.
+    ///
+    /// # Description
+    ///
+    /// The new recipe is built using [Recipe::new], so the same rules that apply to new recipes are checked for the
+    /// modified ones, including its source and the sanitization of its text. Its size is checked against `limits`.
+    pub fn apply(&self, recipe: &Recipe, limits: &RecipeLimits) -> Result<Recipe, ApiError> {
+        let steps = self
+            .steps
+            .as_deref()
+            .unwrap_or(recipe.steps())
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+
+        let modified = Recipe::new(
+            recipe.id(),
+            self.name.as_deref().unwrap_or(recipe.name()),
+            recipe.image_id(),
+            recipe.author_tags(),
+            recipe.tags(),
+            &self
+                .category
+                .as_ref()
+                .map(|c| c.to_string())
+                .unwrap_or(recipe.category().to_string()),
+            self.description.as_deref().or(recipe.description()),
+            self.url.as_deref().or(recipe.url()),
+            self.ingredients.as_deref().unwrap_or(recipe.ingredients()),
+            &steps,
+            self.equipment.as_deref().or(recipe.equipment()),
+            self.prep_time_minutes.or(recipe.prep_time_minutes()),
+            recipe.owner().map(|id| id.to_string()).as_deref(),
+        )?
+        .with_source(self.source.clone().or(recipe.source().cloned()))
+        .with_license(Some(self.license.unwrap_or(recipe.license())));
+        modified
+            .check_limits(limits)
+            .map_err(|e| DataDomainError::LimitExceeded { source: e })?;
+        modified
+            .validate()
+            .map_err(|e| DataDomainError::InvalidParams { source: e })?;
+
+        Ok(modified)
+    }
+}
 
 /// PATCH method for the Recipe endpoint (Restricted).
 ///
 /// # Description
 ///
 /// This method updates an `Recipe` entry in the DB if the given `id` matches the ID of a
-/// registered recipe.
+/// registered recipe. Only the attributes included in the request body are modified. Lists (ingredients, steps and
+/// equipment) are replaced as a whole.
+///
+/// Modified recipes are checked using the same rules as new recipes. Invalid fields are listed in the response, see
+/// `POST /recipe`. A new name updates the slug of the recipe, and a code **409** is returned when the author has
+/// another recipe with the same slug.
+///
+/// Modified recipes are screened for profanity and spam when the screening is enabled in the server. Suspect recipes
+/// are sent to the moderation queue, and they are hidden from the public until an administrator reviews them.
 ///
 /// This method requires to authenticate the client using a valid [crate::AuthData::api_key].
 #[utoipa::path(
     patch,
-    path = "/recipe",
+    path = "/recipe/{id}",
     tag = "Recipe",
+    params(("id" = String, Path, description = "ID or short ID of the recipe.")),
     request_body(
-        content = Recipe, description = "A partial definition of an Recipe entry.",
-        example = json!({"name": "The most delicious cocktail"})
+        content = RecipePatch, description = "A partial definition of an Recipe entry.",
+        example = json!({"name": "The most delicious cocktail", "equipment": ["shaker", "fine_strainer"]})
     ),
     responses(
         (status = 204, description = "The recipe entry was updated in the DB."),
+        (status = 400, description = "The given recipe's ID has an invalid format, or the modified recipe is invalid."),
         (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
         (status = 404, description = "A recipe identified by the given ID was not existing in the DB."),
+        (status = 409, description = "The author has another recipe with the same name."),
+        (status = 422, description = "The modified recipe exceeds the size limits of the server."),
     ),
     security(
         ("api_key" = [])
     )
 )]
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(pool, token, screener, access, limits), fields(recipe_id = %recipe_id))]
 #[patch("{id}")]
-pub async fn patch_recipe(_path: web::Path<(String,)>) -> impl Responder {
-    HttpResponse::NotImplemented().finish()
+pub async fn patch_recipe(
+    recipe_id: RecipeId,
+    req: Json<RecipePatch>,
+    pool: Data<MySqlPool>,
+    limits: Data<RecipeLimits>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+    screener: Data<Screener>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let existing_recipe = match get_recipe_from_db(&pool, recipe_id.as_uuid()).await? {
+        Some(recipe) => recipe,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+
+    let recipe = match req.apply(&existing_recipe, &limits) {
+        Ok(recipe) => recipe,
+        Err(e) => {
+            info!("The modified recipe is invalid: {e}");
+            return Ok(match &e {
+                ApiError::Domain(DataDomainError::InvalidParams { source })
+                | ApiError::Validation(source) => HttpResponse::BadRequest().json(source),
+                ApiError::Domain(DataDomainError::LimitExceeded { source }) => {
+                    HttpResponse::UnprocessableEntity().json(source)
+                }
+                _ => HttpResponse::BadRequest().body(e.to_string()),
+            });
+        }
+    };
+    debug!("Recipe modified: {:#?}", recipe);
+
+    if update_recipe_in_db(&pool, recipe_id.as_uuid(), &recipe).await? {
+        info!("Recipe entry {recipe_id} modified");
+        let flags = screener.screen_recipe(&recipe);
+        if !flags.is_empty() {
+            info!("The recipe {recipe_id} was flagged for moderation: {flags:?}");
+            flag_recipe_in_db(&pool, recipe_id.as_uuid(), &flags).await?;
+        }
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
 }
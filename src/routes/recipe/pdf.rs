@@ -0,0 +1,141 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Print-ready version of the recipes.
+
+use crate::{
+    domain::ApiError,
+    routes::recipe::{
+        utils::{
+            get_named_ingredients, get_recipe_from_db, get_recipe_last_modified_from_db,
+            is_recipe_hidden,
+        },
+        RecipeId,
+    },
+    utils::pdf::{render_recipe_sheet, PdfCache, MAX_SERVINGS},
+};
+use actix_web::{
+    get,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web::{self, Data, Query},
+    HttpResponse,
+};
+use anyhow::Context;
+use serde::Deserialize;
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument, warn};
+use utoipa::IntoParams;
+
+/// Query parameters of the PDF resource.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PdfQuery {
+    /// Amount of servings (1 to 50). The quantities of the ingredients are scaled accordingly.
+    pub servings: Option<u8>,
+}
+
+/// Print-ready PDF of a recipe (Public).
+///
+/// # Description
+///
+/// This singleton resource renders a recipe into a PDF document, so it can be printed straight away, i.e. to keep
+/// spec sheets behind the bar. Recipes are registered for a single serving, use `servings` to scale the quantities
+/// of the ingredients.
+///
+/// Rendered documents are cached by the server until the recipe gets modified.
+#[utoipa::path(
+    get,
+    path = "/recipe/{id}/pdf",
+    tag = "Recipe",
+    params(("id" = String, Path, description = "ID or short ID of the recipe."), PdfQuery),
+    responses(
+        (
+            status = 200,
+            description = "The recipe rendered as a PDF document.",
+            content_type = "application/pdf",
+            headers(
+                ("Content-Disposition"),
+                ("Access-Control-Allow-Origin"),
+            )
+        ),
+        (
+            status = 400,
+            description = "The given recipe's ID has an invalid format, or the amount of servings is out of range.",
+        ),
+        (
+            status = 404,
+            description = "The given recipe's ID was not found in the DB.",
+        ),
+    )
+)]
+#[instrument(skip(pool, cache))]
+#[get("{id}/pdf")]
+pub async fn get_recipe_pdf(
+    recipe_id: RecipeId,
+    query: Query<PdfQuery>,
+    pool: Data<MySqlPool>,
+    cache: Data<PdfCache>,
+) -> Result<HttpResponse, ApiError> {
+    let servings = query.servings.unwrap_or(1);
+    if servings == 0 || servings > MAX_SERVINGS {
+        info!("Invalid amount of servings: {servings}");
+        return Ok(HttpResponse::BadRequest()
+            .body(format!("The servings must be between 1 and {MAX_SERVINGS}")));
+    }
+
+    let id = *recipe_id.as_uuid();
+    // Recipes that are not published, or pending moderation, are hidden from the public.
+    if is_recipe_hidden(&pool, &id).await? {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let last_modified = match get_recipe_last_modified_from_db(&pool, &id).await? {
+        Some(timestamp) => timestamp,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+
+    let cached = {
+        let cache = cache.clone();
+        web::block(move || cache.get(&id, servings, last_modified))
+            .await
+            .context("Failed to read the cache of PDF documents")?
+    };
+
+    let document = match cached {
+        Some(document) => {
+            debug!("PDF document served from the cache");
+            document
+        }
+        None => {
+            let recipe = match get_recipe_from_db(&pool, &id).await? {
+                Some(recipe) => recipe,
+                None => return Ok(HttpResponse::NotFound().finish()),
+            };
+
+            let ingredients = get_named_ingredients(&pool, &recipe).await?;
+            let document = render_recipe_sheet(&recipe, &ingredients, servings);
+
+            // A failure of the cache shall not prevent serving the document.
+            let cache = cache.clone();
+            let stored = document.clone();
+            if let Err(e) = web::block(move || cache.store(&id, servings, last_modified, &stored))
+                .await
+                .context("Failed to write the cache of PDF documents")?
+            {
+                warn!("Failed to cache the PDF document: {e}");
+            }
+
+            document
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/pdf")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![DispositionParam::Filename(format!("{recipe_id}.pdf"))],
+        })
+        .body(document))
+}
@@ -5,9 +5,17 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::{
-    authentication::{check_access, AuthData},
-    domain::Recipe,
-    routes::recipe::utils::register_new_recipe,
+    authentication::{
+        access_denied_response, check_access, key_client_id, AccessControl, AuthData, Scope,
+    },
+    domain::RecipeState,
+    domain::{
+        recipe::RecipeLimits, screening::Screener, ApiError, IdGenerator, Recipe, RecipeLicense,
+    },
+    routes::recipe::{
+        utils::{flag_recipe_in_db, register_new_recipe},
+        workflow::review_state,
+    },
 };
 use actix_web::{
     post,
@@ -16,8 +24,8 @@ use actix_web::{
 };
 use serde_json::json;
 use sqlx::MySqlPool;
-use std::error::Error;
 use tracing::{debug, info, instrument};
+use validator::Validate;
 
 /// POST method for the /recipe endpoint (Restricted)
 ///
@@ -25,7 +33,11 @@ use tracing::{debug, info, instrument};
 ///
 /// This method creates new recipes in the DB using the data provided by authors. Recipes are identified by an unique
 /// ID that is generated by the backend sw before inserting a recipe into the DB. This means that the same recipe
-/// can be pushed several times either by the same author or by another one.
+/// can be pushed several times by different authors.
+///
+/// Recipes also get a URL-friendly `slug` built from their name, i.e. `pina-colada` for *Piña colada*, which
+/// identifies the recipe among the recipes of its author (see `GET /recipe/by-slug/{author}/{slug}`). An author can't
+/// have two recipes whose names produce the same slug, so such recipes are answered with a code **409**.
 ///
 /// A previous search in the DB is advised to avoid having many similar recipes. However, every author is free to
 /// add a recipe that is quite similar to another one if he/she likes to.
@@ -36,6 +48,26 @@ use tracing::{debug, info, instrument};
 /// - *author_tags*: Tags that can be freely assigned by the author.
 /// - *description*: A free text input in which the author can describe in detail the recipe.
 /// - *url*: Useful to link the recipe entry to another web resource.
+/// - *category*: When it is omitted, the backend suggests a category from the amount of ingredients, the techniques
+///   detected in the steps, the equipment and the preparation time. Use `POST /recipe/classify` to preview it.
+///
+/// Recipes are submitted for publication straight away, and they go through the same review as the submitted drafts:
+/// they are published when the client already published some recipe, and they wait for the review of an
+/// administrator otherwise. The state reached is included in the response. Authors that prefer to keep working on a
+/// recipe before publishing it can register it with `"state": "draft"`, and submit it later using
+/// `POST /recipe/{id}/submit`.
+///
+/// Quantities of the ingredients are checked against their units: they must be positive and lower than a sensible
+/// maximum for the unit, and dashes and drops only accept whole numbers. Invalid recipes are answered with a code
+/// **400** that lists the errors per field, i.e. `{"ingredients": {"0": {"quantity": [{"code": "range", ...}]}}}`.
+///
+/// The size of the recipes is limited by the server: amount of ingredients, amount of steps and length of each step
+/// (see [crate::domain::RecipeLimits]). Recipes that exceed the limits are answered with a code **422** that lists
+/// the errors per field as well.
+///
+/// When the screening of profanity and spam is enabled in the server, suspect recipes are registered but kept hidden
+/// from the public until an administrator reviews them. Such recipes are answered with a code **202** that includes
+/// the reasons why the recipe was flagged.
 #[utoipa::path(
     post,
     path = "/recipe",
@@ -48,7 +80,7 @@ use tracing::{debug, info, instrument};
             status = 200,
             description = "The Recipe was inserted in the DB.",
             content_type = "application/json",
-            example = json!({"id": "0192e8d9-36cf-7ce3-82ef-0a7c9b2deefe"}),
+            example = json!({"id": "0192e8d9-36cf-7ce3-82ef-0a7c9b2deefe", "state": "published"}),
             headers(
                 ("Content-Length"),
                 ("Content-Type"),
@@ -56,10 +88,24 @@ use tracing::{debug, info, instrument};
                 ("Vary", description = "Origin,Access-Control-Request-Method,Access-Control-Request-Headers")
             ),
         ),
+        (
+            status = 202,
+            description = "The Recipe was inserted in the DB, but it was flagged and it is pending moderation.",
+            content_type = "application/json",
+            example = json!({
+                "id": "0192e8d9-36cf-7ce3-82ef-0a7c9b2deefe",
+                "state": "published",
+                "moderation": [{"excessive_links": 3}]
+            }),
+        ),
         (
             status = 400,
-            description = "Missing API key. This endpoint is restricted to public access.",
+            description = "Missing API key, or the recipe is invalid. Invalid fields are listed in the response.",
         ),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `write` scope."),
+        (status = 409, description = "The author has another recipe with the same name."),
+        (status = 422, description = "The recipe exceeds the size limits of the server."),
         (
             status = 429, description = "**Too many requests.**",
             headers(
@@ -70,20 +116,54 @@ use tracing::{debug, info, instrument};
         )
     )
 )]
-#[instrument(skip(pool, token))]
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(pool, ids, token, screener, access, limits, default_license))]
 #[post("")]
 pub async fn post_recipe(
     req: Json<Recipe>,
     pool: Data<MySqlPool>,
+    limits: Data<RecipeLimits>,
+    access: Data<AccessControl>,
+    ids: Data<dyn IdGenerator>,
     token: Query<AuthData>,
-) -> Result<HttpResponse, Box<dyn Error>> {
+    screener: Data<Screener>,
+    default_license: Data<RecipeLicense>,
+) -> Result<HttpResponse, ApiError> {
     info!("Post new recipe: {:#?}", req.0);
 
     // Access control
-    check_access(&pool, &token.api_key).await?;
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
     debug!("Access granted");
 
-    let id = register_new_recipe(&pool, &req.0).await?;
+    let req = req.into_inner();
+    if let Err(e) = req.check_limits(&limits) {
+        info!("The recipe exceeds the limits: {e}");
+        return Ok(HttpResponse::UnprocessableEntity().json(e));
+    }
+    if let Err(e) = req.validate() {
+        info!("The recipe is invalid: {e}");
+        return Ok(HttpResponse::BadRequest().json(e));
+    }
+
+    let client_id = key_client_id(&token.api_key)?;
+    let state = match req.state() {
+        Some(RecipeState::Draft) => RecipeState::Draft,
+        _ => review_state(&pool, &client_id).await?,
+    };
+    let recipe = req
+        .with_state(state)
+        .with_default_license(**default_license);
+    let id = register_new_recipe(&pool, ids.get_ref(), &recipe, Some(&client_id)).await?;
 
-    Ok(HttpResponse::Ok().json(json!({"id": id.to_string()})))
+    let flags = screener.screen_recipe(&recipe);
+    if flags.is_empty() {
+        Ok(HttpResponse::Ok().json(json!({"id": id.to_string(), "state": state})))
+    } else {
+        info!("The recipe {id} was flagged for moderation: {flags:?}");
+        flag_recipe_in_db(&pool, &id, &flags).await?;
+        Ok(HttpResponse::Accepted()
+            .json(json!({"id": id.to_string(), "state": state, "moderation": flags})))
+    }
 }
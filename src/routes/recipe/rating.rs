@@ -0,0 +1,88 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Votes of the clients of the API for the recipes (see [crate::domain::rating]).
+
+use crate::{
+    authentication::{
+        access_denied_response, check_access, key_client_id, AccessControl, AuthData, Scope,
+    },
+    domain::{rating::RatingVote, ApiError},
+    routes::recipe::{utils::store_rating_vote_in_db, RecipeId},
+};
+use actix_web::{
+    post,
+    web::{Data, Json, Query},
+    HttpResponse,
+};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+
+/// Vote a recipe (Restricted).
+///
+/// # Description
+///
+/// Every client of the API has a single vote per recipe: voting again replaces the former vote. The rating of the
+/// recipe is the average of the votes, rounded to the nearest half star. Only published recipes can be voted.
+#[utoipa::path(
+    post,
+    path = "/recipe/{id}/rating",
+    tag = "Recipe",
+    params(("id" = String, Path, description = "ID or short ID of the recipe.")),
+    security(
+        ("api_key" = [])
+    ),
+    request_body(
+        content = RatingVote, description = "The vote of the client, from 0.5 to 5.0 stars.",
+        example = json!({"stars": 4.5})
+    ),
+    responses(
+        (status = 200, description = "The vote was stored.", body = RecipeRating),
+        (status = 400, description = "The vote is not valid."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The API key doesn't grant the `write` scope."),
+        (status = 404, description = "The recipe doesn't exist, or it is not public."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[post("/{id}/rating")]
+pub async fn rate_recipe(
+    recipe_id: RecipeId,
+    req: Json<RatingVote>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+    let client_id = key_client_id(&token.api_key)?;
+
+    if req.validate().is_err() {
+        info!("Invalid vote for the recipe {recipe_id}");
+        return Ok(HttpResponse::BadRequest().body("Votes range from 0.5 to 5.0 stars"));
+    }
+
+    match store_rating_vote_in_db(
+        &pool,
+        recipe_id.as_uuid(),
+        &client_id.to_string(),
+        req.stars,
+    )
+    .await?
+    {
+        Some(rating) => {
+            info!(
+                "The recipe {recipe_id} is rated {} ({} votes)",
+                rating.rating, rating.votes
+            );
+            Ok(HttpResponse::Ok().json(rating))
+        }
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
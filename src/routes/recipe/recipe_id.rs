@@ -0,0 +1,91 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Identifier of the singleton recipe resources.
+
+use crate::{
+    domain::{DataDomainError, ResourceId, ServerError, ShortId},
+    routes::recipe::utils::get_recipe_ids_by_short_id,
+};
+use actix_web::{
+    dev::Payload, error::InternalError, web::Data, FromRequest, HttpRequest, HttpResponse,
+};
+use core::fmt;
+use sqlx::MySqlPool;
+use std::{future::Future, pin::Pin};
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+/// Name of the path segment that holds the ID of a recipe, i.e. `/recipe/{id}`.
+const ID_PATH_SEGMENT: &str = "id";
+
+/// Identifier of a recipe given in the path of a request.
+///
+/// # Description
+///
+/// Recipes are identified by their ID, following the rules of [ResourceId], or by their short ID (see [ShortId]).
+/// Short IDs are resolved into the ID of the recipe before reaching the handler, thus handlers only deal with the
+/// [Uuid] of the recipe. A malformed ID is answered with a code **400**, and a short ID that matches no recipe is
+/// answered with a code **404**. As for [ResourceId], whether a well-formed ID matches an existing recipe is left to
+/// the handler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecipeId(Uuid);
+
+impl RecipeId {
+    /// Get the ID of the recipe.
+    pub fn as_uuid(&self) -> &Uuid {
+        &self.0
+    }
+}
+
+impl From<RecipeId> for Uuid {
+    fn from(value: RecipeId) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for RecipeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.hyphenated())
+    }
+}
+
+impl FromRequest for RecipeId {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let segment = req
+            .match_info()
+            .get(ID_PATH_SEGMENT)
+            .unwrap_or_default()
+            .to_owned();
+        let pool = req.app_data::<Data<MySqlPool>>().cloned();
+        let path = req.path().to_owned();
+
+        Box::pin(async move {
+            if let Ok(id) = ResourceId::try_from(segment.as_str()) {
+                return Ok(RecipeId(id.into()));
+            }
+            let Ok(short_id) = ShortId::try_from(segment.as_str()) else {
+                debug!("Malformed ID received in the path: {path}");
+                return Err(DataDomainError::InvalidId.into());
+            };
+
+            let Some(pool) = pool else {
+                error!("The DB pool is not available to resolve short IDs");
+                return Err(ServerError::DbError.into());
+            };
+            match get_recipe_ids_by_short_id(&pool, &[&short_id]).await?.pop() {
+                Some((_, id)) => Ok(RecipeId(id)),
+                None => {
+                    info!("The short ID {short_id} doesn't match any recipe");
+                    Err(InternalError::from_response("", HttpResponse::NotFound().finish()).into())
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Search of recipes using an example recipe.
+//!
+//! # Description
+//!
+//! Some searches don't fit in a URL, i.e. a long list of ingredients. [search_recipe_by_example] receives the
+//! criteria of the search as a partial recipe in the body of the request, and runs it using the same planner as
+//! `GET /recipe` (see [RecipeSearch]).
+
+use crate::{
+    authentication::{access_denied_response, AccessControl, AuthData},
+    domain::{ApiError, RecipeCategory, RecipeQuery, RecipeState, ResourceId, StarRate, Tag},
+    routes::{
+        expand::{expand_recipes, ExpandQuery},
+        me::preferences::{request_preferences, DisplayQuery},
+        recipe::{get::RECIPE_EXPANSIONS, RecipeSearch},
+    },
+    utils::http::{MaxPageSize, PageQuery},
+};
+use actix_web::{
+    post,
+    web::{Data, Json, Query},
+    HttpRequest, HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{info, instrument};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Ingredient of an example recipe. Only the ID of the ingredient is considered, the quantities are ignored.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct IngredientExample {
+    #[schema(value_type = String, example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub ingredient_id: ResourceId,
+}
+
+/// Partial recipe used as the criteria of a search.
+///
+/// # Description
+///
+/// Members follow the names of the `Recipe` schema, and all of them are optional. Recipes match the example when they
+/// match all the given members:
+/// - `name`: the name of the recipe includes the given string.
+/// - `category`: the recipe belongs to the given category.
+/// - `tags` and `author_tags`: the recipe is tagged with all the given tags.
+/// - `rating`: the recipe is rated with the given rating or better.
+/// - `prep_time_minutes`: the recipe can be prepared in the given amount of minutes, or less.
+/// - `ingredients`: the recipe uses all the given ingredients.
+/// - `author_id`: the recipe belongs to the given author.
+/// - `state`: the recipe is in the given state of the publishing workflow. Published recipes by default.
+///
+/// Other members of the recipes can't be used to search, so they are rejected.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RecipeExample {
+    #[schema(example = "margarita")]
+    pub name: Option<String>,
+    pub category: Option<RecipeCategory>,
+    pub tags: Option<Vec<Tag>>,
+    pub author_tags: Option<Vec<Tag>>,
+    pub rating: Option<StarRate>,
+    #[schema(example = 10)]
+    pub prep_time_minutes: Option<u16>,
+    pub ingredients: Option<Vec<IngredientExample>>,
+    #[schema(value_type = Option<String>, example = "0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    pub author_id: Option<ResourceId>,
+    pub state: Option<RecipeState>,
+}
+
+impl RecipeExample {
+    /// Build the query of the recipe search that matches the example.
+    ///
+    /// # Description
+    ///
+    /// Tags are validated, and a description of the issue is returned when some of them is not valid. The state of
+    /// the recipes is always set, so an example that only includes ingredients or an author is a valid search.
+    pub fn to_query(&self) -> Result<RecipeQuery, String> {
+        let mut tags: Vec<Tag> = Vec::new();
+        for tag in self.tags.iter().chain(self.author_tags.iter()).flatten() {
+            let tag = Tag::new(&tag.identifier)
+                .map_err(|_| format!("Invalid tag: {}", tag.identifier))?;
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+
+        Ok(RecipeQuery {
+            name: self.name.clone(),
+            tags: (!tags.is_empty()).then_some(tags),
+            rating: self.rating,
+            category: self.category.clone(),
+            equipment_excludes: None,
+            max_prep_time: self.prep_time_minutes,
+            state: Some(self.state.unwrap_or_default()),
+            q: None,
+            sort: None,
+            limit: None,
+            offset: None,
+        })
+    }
+}
+
+/// Search recipes using an example recipe (Public).
+///
+/// # Description
+///
+/// The criteria of the search are given as a partial recipe in the body of the request (see the schema
+/// `RecipeExample`), which suits searches that don't fit in a URL. The search follows the rules of `GET /recipe`:
+/// searching recipes that are not published needs an API key, recipes pending moderation are skipped, and only the
+/// recipes that match all the criteria are returned.
+///
+/// Results are paginated using `limit` and `offset`, and sorted by the ID of the recipes, so the pages are stable. The
+/// total amount of matching recipes is included in the `X-Total-Count` header, and the links to the other pages in the
+/// `Link` header. A page beyond the end of the results is an empty list.
+///
+/// The amounts of the ingredients are converted to the unit system given by `units`, and `expand` embeds related
+/// resources within each recipe, as in `GET /recipe`.
+#[utoipa::path(
+    post,
+    path = "/recipe/search",
+    tag = "Recipe",
+    params(
+        PageQuery,
+        DisplayQuery,
+        ExpandQuery,
+    ),
+    request_body(
+        content = RecipeExample, description = "The partial recipe that the results shall match.",
+        example = json!({
+            "tags": [{"identifier": "tequila"}],
+            "ingredients": [{"ingredient_id": "0191e13b-5ab7-78f1-bc06-be503a6c111b"}]
+        })
+    ),
+    responses(
+        (
+            status = 200,
+            description = "A page of the matching recipes.",
+            body = [crate::routes::expand::ExpandedRecipe],
+            headers(
+                ("X-Total-Count", description = "Amount of recipes that match the example."),
+                ("Link", description = "Links to the first, previous, next and last pages."),
+            )
+        ),
+        (
+            status = 400,
+            description = "The example includes members that can't be used to search or invalid values, or the page or the expansions are not valid.",
+        ),
+        (
+            status = 401,
+            description = "Recipes that are not published were requested without a valid API key.",
+        ),
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(pool, token, request, max_page_size, access))]
+#[post("/search")]
+pub async fn search_recipe_by_example(
+    req: Json<RecipeExample>,
+    page: Query<PageQuery>,
+    overrides: Query<DisplayQuery>,
+    expand: Query<ExpandQuery>,
+    token: Option<Query<AuthData>>,
+    request: HttpRequest,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    max_page_size: Data<MaxPageSize>,
+) -> Result<HttpResponse, ApiError> {
+    let page = match page.page(**max_page_size) {
+        Ok(page) => page,
+        Err(e) => return Ok(HttpResponse::BadRequest().body(e)),
+    };
+    let mut search = match req.to_query().and_then(RecipeSearch::new) {
+        Ok(search) => search,
+        Err(e) => {
+            info!("Invalid recipe example: {e}");
+            return Ok(HttpResponse::BadRequest().body(e));
+        }
+    };
+    let expansions = match expand.expansions(&RECIPE_EXPANSIONS) {
+        Ok(expansions) => expansions,
+        Err(e) => {
+            info!("Invalid expansion: {e}");
+            return Ok(HttpResponse::BadRequest().body(e));
+        }
+    };
+    let preferences = match request_preferences(&pool, &access, token.as_deref(), &overrides).await
+    {
+        Ok(preferences) => preferences,
+        Err(e) => return access_denied_response(e),
+    };
+    // The access of requests that include an API key was checked when resolving the preferences.
+    if search.query().state != Some(RecipeState::Published) && token.is_none() {
+        info!("Recipes that are not published were requested without an API key");
+        return Ok(HttpResponse::Unauthorized().finish());
+    }
+
+    // Criteria of the example that have no counterpart in the query of `GET /recipe` are added to the search.
+    if let Some(ingredients) = req.ingredients.as_deref().filter(|i| !i.is_empty()) {
+        search = search.with_ingredients(
+            ingredients
+                .iter()
+                .map(|i| Uuid::from(i.ingredient_id))
+                .collect(),
+        );
+    }
+    if let Some(author_id) = &req.author_id {
+        search = search.with_author(*author_id.as_uuid());
+    }
+
+    let (recipes, total) = search.run_page(&pool, &page).await?;
+    let recipes = recipes
+        .into_iter()
+        .map(|recipe| preferences.apply(recipe))
+        .collect();
+    let recipes = expand_recipes(&pool, recipes, &expansions).await?;
+
+    let mut response = HttpResponse::Ok();
+    page.insert_headers(&mut response, &request, total as usize);
+    Ok(response.json(recipes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn examples_are_converted_into_queries() {
+        let example: RecipeExample = serde_json::from_str(
+            r#"{
+                "name": "margarita",
+                "tags": [{"identifier": "Tequila"}],
+                "author_tags": [{"identifier": "tequila"}, {"identifier": "classic"}],
+                "prep_time_minutes": 5
+            }"#,
+        )
+        .expect("Failed to parse the example");
+        let query = example.to_query().expect("Failed to build the query");
+
+        assert_eq!(query.name.as_deref(), Some("margarita"));
+        assert_eq!(
+            query.tags,
+            Some(vec![
+                Tag::new("tequila").unwrap(),
+                Tag::new("classic").unwrap()
+            ])
+        );
+        assert_eq!(query.max_prep_time, Some(5));
+        assert_eq!(query.state, Some(RecipeState::Published));
+    }
+
+    #[rstest]
+    #[case(r#"{"tags": [{"identifier": "a(tag)"}]}"#)]
+    #[case(r#"{"author_tags": [{"identifier": "anemoji❌"}]}"#)]
+    fn invalid_tags_are_rejected(#[case] example: &str) {
+        let example: RecipeExample = serde_json::from_str(example).unwrap();
+        assert!(example.to_query().is_err());
+    }
+
+    #[rstest]
+    #[case(r#"{"steps": ["Shake"]}"#)]
+    #[case(r#"{"ingredients": [{"ingredient_id": "1234"}]}"#)]
+    fn invalid_examples_are_not_deserialised(#[case] example: &str) {
+        assert!(serde_json::from_str::<RecipeExample>(example).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Suggestions of search terms for the recipes.
+//!
+//! # Description
+//!
+//! Suggestions are the popular search terms of the recipes, taken from the anonymous usage analytics (see
+//! [crate::utils::metrics::UsageAnalytics]). The same threshold as the reports of the analytics applies: terms that no
+//! day got `k_anonymity` distinct clients are never suggested, so rare searches, that could single out a person, are
+//! not exposed even when a single client repeats them. Suggestions are built from the searches stored while the
+//! analytics were enabled, thus none are returned when they were never enabled.
+
+use crate::{
+    domain::{search_text::normalize_search_text, ApiError},
+    routes::recipe::utils::get_search_suggestions_from_db,
+    utils::metrics::{analytics, SuggestionCache, MAX_SEARCH_TERM_LENGTH, SUGGESTION_CACHE_TTL},
+};
+use actix_web::{
+    get,
+    http::header::{CacheControl, CacheDirective},
+    web::{Data, Query},
+    HttpResponse,
+};
+use chrono::{Days, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+use utoipa::{IntoParams, ToSchema};
+
+/// Minimum length of the prefixes (characters).
+pub const MIN_PREFIX_LENGTH: usize = 2;
+
+/// Maximum amount of suggestions of a prefix.
+pub const MAX_SEARCH_SUGGESTIONS: u32 = 10;
+
+/// Days of searches counted to rank the suggestions.
+pub const SUGGESTION_WINDOW_DAYS: u64 = 30;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SuggestionQuery {
+    /// Beginning of the search term. It is normalized as the searches are.
+    #[param(example = "ma")]
+    pub prefix: String,
+    /// Amount of suggestions. 10 at most, which is the default.
+    #[param(example = 5)]
+    pub limit: Option<u32>,
+}
+
+/// Suggested search terms of a prefix.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct SearchSuggestions {
+    /// Normalized prefix.
+    #[schema(example = "ma")]
+    pub prefix: String,
+    /// Search terms that start with the prefix, most searched first.
+    #[schema(example = json!(["margarita", "mai tai"]))]
+    pub suggestions: Vec<String>,
+}
+
+/// Suggest search terms for the recipes.
+///
+/// # Description
+///
+/// The suggestions are the terms that start with the given prefix among the most searched ones during the last 30 days,
+/// most searched first. Only the terms searched by several clients within a day are suggested. Suggestions are
+/// refreshed every 5 minutes at most.
+#[utoipa::path(
+    get,
+    path = "/recipe/search-suggestions",
+    tag = "Recipe",
+    params(SuggestionQuery),
+    responses(
+        (
+            status = 200,
+            description = "The suggested search terms.",
+            body = SearchSuggestions,
+            headers(
+                ("Cache-Control", description = "The suggestions can be cached by shared caches for 5 minutes."),
+            )
+        ),
+        (status = 400, description = "The prefix is too short or too long, or the amount of suggestions is not valid."),
+    )
+)]
+#[instrument(skip(pool, cache))]
+#[get("/search-suggestions")]
+pub async fn get_search_suggestions(
+    req: Query<SuggestionQuery>,
+    pool: Data<MySqlPool>,
+    cache: Data<SuggestionCache>,
+) -> Result<HttpResponse, ApiError> {
+    let prefix = normalize_search_text(&req.prefix);
+    let length = prefix.chars().count();
+    let limit = req.limit.unwrap_or(MAX_SEARCH_SUGGESTIONS);
+    if !(MIN_PREFIX_LENGTH..=MAX_SEARCH_TERM_LENGTH).contains(&length)
+        || !(1..=MAX_SEARCH_SUGGESTIONS).contains(&limit)
+    {
+        info!("Invalid request of suggestions: {length} characters, {limit} suggestions");
+        return Ok(HttpResponse::BadRequest().body(format!(
+            "Prefixes have {MIN_PREFIX_LENGTH} to {MAX_SEARCH_TERM_LENGTH} characters, \
+            and up to {MAX_SEARCH_SUGGESTIONS} suggestions are given"
+        )));
+    }
+
+    let suggestions = match cache.get(&prefix) {
+        Some(suggestions) => suggestions,
+        None => {
+            debug!("Building the suggestions of {prefix}");
+            let from = Utc::now().date_naive() - Days::new(SUGGESTION_WINDOW_DAYS - 1);
+            let suggestions = get_search_suggestions_from_db(
+                &pool,
+                &prefix,
+                from,
+                analytics().k_anonymity(),
+                MAX_SEARCH_SUGGESTIONS,
+            )
+            .await?;
+            cache.store(&prefix, suggestions)
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(SUGGESTION_CACHE_TTL.as_secs() as u32),
+        ]))
+        .json(SearchSuggestions {
+            prefix,
+            suggestions: suggestions.iter().take(limit as usize).cloned().collect(),
+        }))
+}
@@ -0,0 +1,169 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Images of the preparation steps of the recipes.
+//!
+//! # Description
+//!
+//! Each preparation step of a recipe can be illustrated by a single image, i.e. to show the right technique to shake a
+//! cocktail. Images are kept by the media storage (see [MediaStore]), and they are listed by the `step_images` of the
+//! recipe. Steps are identified by their position in the list of steps, starting at 0. Uploading a new image for a
+//! step replaces the previous one.
+
+use crate::{
+    authentication::{access_denied_response, check_access, AccessControl, AuthData, Scope},
+    domain::ApiError,
+    routes::recipe::{
+        utils::{get_recipe_from_db, set_step_image_in_db},
+        RecipeId,
+    },
+    utils::media::{MediaError, MediaStore},
+};
+use actix_web::{
+    delete, put,
+    web::{Bytes, Data, Path, Query},
+    HttpResponse,
+};
+use serde::Deserialize;
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+
+/// Path params of the resources of `/recipe/{id}/steps`.
+#[derive(Debug, Deserialize)]
+pub struct RecipeStepPath {
+    /// Position of the step in the list of steps of the recipe.
+    pub step: u16,
+}
+
+/// Upload the image of a preparation step of a recipe.
+///
+/// # Description
+///
+/// The body of the request is the raw content of the image. PNG, JPEG and WebP images up to 2 MiB are accepted, and
+/// their format is detected from their content. Any previous image of the step is replaced.
+#[utoipa::path(
+    put,
+    path = "/recipe/{id}/steps/{step}/image",
+    tag = "Recipe",
+    params(
+        ("id" = String, Path, description = "ID or short ID of the recipe."),
+        ("step" = u16, Path, description = "Position of the step, starting at 0."),
+    ),
+    request_body(content = Vec<u8>, description = "Content of the image.", content_type = "image/png"),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The image was stored.", body = Recipe),
+        (status = 400, description = "The ID has an invalid format, or the image is empty or has an unsupported format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The API key doesn't grant the `write` scope."),
+        (status = 404, description = "The recipe doesn't exist, or it has no such step."),
+        (status = 413, description = "The image exceeds the maximum size."),
+    )
+)]
+#[instrument(skip(content, pool, media, token, access))]
+#[put("/{id}/steps/{step}/image")]
+pub async fn put_step_image(
+    id: RecipeId,
+    path: Path<RecipeStepPath>,
+    content: Bytes,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    media: Data<MediaStore>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let image_id = match media.store_image(content).await {
+        Ok(image_id) => image_id,
+        Err(e @ (MediaError::InvalidSize | MediaError::UnsupportedFormat)) => {
+            info!("{e}");
+            return Ok(HttpResponse::BadRequest().body(e.to_string()));
+        }
+        Err(e) => return Err(ApiError::Internal(e.into())),
+    };
+
+    let previous = match set_step_image_in_db(&pool, id.as_uuid(), path.step, Some(&image_id)).await
+    {
+        Ok(Some(previous)) => previous,
+        Ok(None) => {
+            media.delete_image(&image_id).await;
+            return Ok(HttpResponse::NotFound().finish());
+        }
+        Err(e) => {
+            media.delete_image(&image_id).await;
+            return Err(e.into());
+        }
+    };
+    if let Some(previous) = previous {
+        media.delete_image(&previous).await;
+    }
+    info!(
+        "New image {image_id} for the step {} of the recipe {id}",
+        path.step
+    );
+
+    match get_recipe_from_db(&pool, id.as_uuid()).await? {
+        Some(recipe) => Ok(HttpResponse::Ok().json(recipe)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Remove the image of a preparation step of a recipe.
+#[utoipa::path(
+    delete,
+    path = "/recipe/{id}/steps/{step}/image",
+    tag = "Recipe",
+    params(
+        ("id" = String, Path, description = "ID or short ID of the recipe."),
+        ("step" = u16, Path, description = "Position of the step, starting at 0."),
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 204, description = "The step has no image now."),
+        (status = 400, description = "The ID has an invalid format."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The API key doesn't grant the `write` scope."),
+        (status = 404, description = "The recipe doesn't exist, or it has no such step."),
+    )
+)]
+#[instrument(skip(pool, media, token, access))]
+#[delete("/{id}/steps/{step}/image")]
+pub async fn delete_step_image(
+    id: RecipeId,
+    path: Path<RecipeStepPath>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    media: Data<MediaStore>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Write).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    match set_step_image_in_db(&pool, id.as_uuid(), path.step, None).await? {
+        Some(previous) => {
+            if let Some(previous) = previous {
+                media.delete_image(&previous).await;
+                info!(
+                    "Image {previous} of the step {} of the recipe {id} removed",
+                    path.step
+                );
+            }
+            Ok(HttpResponse::NoContent().finish())
+        }
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Suggestions of recipes from the ingredients at hand.
+
+use crate::{
+    authentication::{
+        access_denied_response, check_access, key_client_id, AccessControl, AuthData, Scope,
+    },
+    domain::{ApiError, Recipe, ResourceId},
+    routes::{
+        me::{inventory::MAX_INVENTORY_SIZE, utils::get_inventory_from_db},
+        recipe::utils::{get_recipes_from_db, suggest_recipes_from_db},
+    },
+};
+use actix_web::{
+    get,
+    web::{Data, Query},
+    HttpResponse,
+};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use std::collections::HashSet;
+use tracing::{debug, info, instrument};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Maximum amount of missing ingredients of a suggested recipe.
+pub const MAX_MISSING_INGREDIENTS: u32 = 2;
+
+/// Maximum amount of suggested recipes.
+pub const MAX_SUGGESTIONS: u32 = 20;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SuggestQuery {
+    /// Comma-separated list of the IDs of the ingredients at hand. When omitted, the inventory of the client is used.
+    pub ingredients: Option<String>,
+}
+
+/// Recipe suggested from a list of ingredients.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Suggestion {
+    pub recipe: Recipe,
+    /// IDs of the ingredients of the recipe that are not at hand.
+    #[schema(example = json!(["0191e13b-5ab7-78f1-bc06-be503a6c111b"]))]
+    pub missing: Vec<Uuid>,
+}
+
+/// Suggest recipes from the ingredients at hand.
+///
+/// # Description
+///
+/// Recipes that can be prepared with the given ingredients go first, followed by the recipes that miss up to
+/// [MAX_MISSING_INGREDIENTS] ingredients. Up to [MAX_SUGGESTIONS] recipes are returned.
+///
+/// The list of ingredients can be omitted by authenticated clients, and the ingredients of their inventory (see
+/// `GET /me/inventory`) are used instead.
+#[utoipa::path(
+    get,
+    path = "/recipe/suggest",
+    tag = "Recipe",
+    params(SuggestQuery),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The suggested recipes, best matches first.", body = [Suggestion]),
+        (status = 400, description = "The list of ingredients is empty, too long, or includes invalid IDs."),
+        (status = 401, description = "No list of ingredients was given, and the client has no access to the inventory."),
+        (status = 403, description = "The given API key doesn't grant the `read` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("/suggest")]
+pub async fn suggest_recipes(
+    req: Query<SuggestQuery>,
+    token: Option<Query<AuthData>>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+) -> Result<HttpResponse, ApiError> {
+    let ingredients = match (req.ingredients.as_deref(), token) {
+        (Some(list), _) => match parse_ingredient_list(list) {
+            Some(ingredients) => ingredients,
+            None => {
+                info!("Invalid list of ingredients: {list}");
+                return Ok(HttpResponse::BadRequest().finish());
+            }
+        },
+        (None, Some(token)) => {
+            // Access control
+            if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Read).await {
+                return access_denied_response(e);
+            }
+            debug!("Access granted");
+
+            let client_id = key_client_id(&token.api_key)?;
+            get_inventory_from_db(&pool, &client_id, None)
+                .await?
+                .iter()
+                .map(|item| Uuid::parse_str(&item.ingredient_id))
+                .collect::<Result<Vec<_>, _>>()
+                .context("Invalid ingredient ID in the inventory")?
+        }
+        (None, None) => {
+            info!("No list of ingredients nor API key were given");
+            return Ok(HttpResponse::Unauthorized().finish());
+        }
+    };
+
+    let at_hand: HashSet<Uuid> = ingredients.iter().copied().collect();
+    let ids = suggest_recipes_from_db(
+        &pool,
+        &ingredients,
+        MAX_MISSING_INGREDIENTS,
+        Some(MAX_SUGGESTIONS),
+        0,
+    )
+    .await?;
+    let suggestions = get_recipes_from_db(&pool, &ids)
+        .await?
+        .into_iter()
+        .map(|recipe| {
+            let missing = recipe
+                .ingredients()
+                .iter()
+                .map(|i| i.ingredient_id)
+                .filter(|i| !at_hand.contains(i))
+                .collect();
+            Suggestion { recipe, missing }
+        })
+        .collect::<Vec<Suggestion>>();
+    debug!("Suggested recipes: {}", suggestions.len());
+
+    Ok(HttpResponse::Ok().json(suggestions))
+}
+
+/// Parse a comma-separated list of ingredient IDs. Repeated IDs are ignored.
+pub(crate) fn parse_ingredient_list(list: &str) -> Option<Vec<Uuid>> {
+    let mut ingredients = Vec::new();
+    for id in list.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        let id = *ResourceId::try_from(id).ok()?.as_uuid();
+        if !ingredients.contains(&id) {
+            ingredients.push(id);
+        }
+    }
+
+    if ingredients.is_empty() || ingredients.len() > MAX_INVENTORY_SIZE {
+        None
+    } else {
+        Some(ingredients)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case("0191e13b-5ab7-78f1-bc06-be503a6c111b", Some(1))]
+    #[case(
+        "0191e13b-5ab7-78f1-bc06-be503a6c111b, 0191e13b-5ab7-78f1-bc06-be503a6c111c,",
+        Some(2)
+    )]
+    #[case(
+        "0191e13b-5ab7-78f1-bc06-be503a6c111b,0191e13b-5ab7-78f1-bc06-be503a6c111b",
+        Some(1)
+    )]
+    #[case("", None)]
+    #[case(" , ", None)]
+    #[case("0191e13b-5ab7-78f1-bc06-be503a6c111b,rum", None)]
+    fn ingredient_lists_are_parsed(#[case] list: &str, #[case] expected: Option<usize>) {
+        assert_eq!(parse_ingredient_list(list).map(|l| l.len()), expected);
+    }
+}
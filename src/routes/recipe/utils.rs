@@ -4,19 +4,46 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::domain::{
-    QuantityUnit, Recipe, RecipeCategory, RecipeContains, ServerError, StarRate, Tag,
+use crate::{
+    domain::{
+        collation::Collation,
+        rating::RecipeRating,
+        screening::ScreeningFlag,
+        search_expression::{SearchExpression, SearchTerm},
+        search_text::{normalize_search_text, prefix_pattern, search_pattern},
+        slug::slugify,
+        sort::RecipeSort,
+        ApiError, ClientId, DataDomainError, Equipment, IdGenerator, Recipe, RecipeCategory,
+        RecipeContains, RecipeLicense, RecipeSource, RecipeState, ServerError, ShortId, StarRate,
+        StepImage, Tag, WebsiteUrl,
+    },
+    routes::{
+        batch::{BatchItemResult, BatchOutcome},
+        recipe::{
+            claim::{ClaimState, RecipeClaim},
+            get::{CategoryFacet, RatingFacet, RecipeFacets, TagFacet},
+        },
+    },
+    utils::{
+        changes::{touch_collection, Collection},
+        events::{record_event, DomainEvent},
+        pdf::SheetIngredient,
+    },
 };
-use sqlx::{Executor, MySqlPool};
-use std::error::Error;
+use anyhow::Context;
+use chrono::NaiveDate;
+use sqlx::{mysql::MySqlRow, Executor, FromRow, MySql, MySqlPool, QueryBuilder, Row, Transaction};
+use std::collections::HashMap;
 use tracing::{debug, error, info, instrument};
 use uuid::Uuid;
 
-#[instrument(skip(pool))]
+#[instrument(skip(pool, ids))]
 pub async fn register_new_recipe(
     pool: &MySqlPool,
+    ids: &dyn IdGenerator,
     recipe: &Recipe,
-) -> Result<Uuid, Box<dyn Error>> {
+    client_id: Option<&ClientId>,
+) -> Result<Uuid, ApiError> {
     // First, let's handle tags. If tags are already defined in the system, add a new entry in the `Tagged` table.
     // Otherwise, register the new tag, and add the entry in `Tagged`.
 
@@ -50,31 +77,52 @@ pub async fn register_new_recipe(
         }
     }
 
-    let new_id = Uuid::now_v7();
+    let new_id = ids.new_id();
 
     let mut transaction = pool.begin().await.map_err(|e| {
         error!("{e}");
         ServerError::DbError
     })?;
 
+    // The clients' recipes get their state from the publishing workflow, the seeded ones are published straight away.
+    let state = recipe.state().unwrap_or(RecipeState::Published).to_string();
     let query = sqlx::query!(
-        r#"INSERT INTO `Cocktail` (`id`, `name`, `description`, `category`, `image_id`, `url`, `rating`, `owner`, `steps`)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+        r#"INSERT INTO `Cocktail` (`id`, `short_id`, `name`, `search_name`, `slug`, `description`, `category`, `image_id`,
+        `url`, `source_book`, `source_page`, `source_url`, `source_author`, `license`, `rating`, `owner`, `steps`,
+        `prep_time_minutes`, `state`, `publication_date`, `client_id`)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, IF(? = 'published', CURRENT_TIMESTAMP, NULL), ?)"#,
         new_id.to_string(),
+        // Clashes of random 64-bit values are negligible, thus they are not retried.
+        ShortId::generate().to_string(),
         recipe.name(),
+        normalize_search_text(recipe.name()),
+        slugify(recipe.name()),
         recipe.description(),
         recipe.category().to_string(),
         recipe.image_id(),
         recipe.url(),
-        recipe.rating().to_string(),
+        recipe.source().and_then(|s| s.book.as_deref()),
+        recipe.source().and_then(|s| s.page),
+        recipe
+            .source()
+            .and_then(|s| s.url.as_ref().map(WebsiteUrl::as_str)),
+        recipe.source().and_then(|s| s.author.as_deref()),
+        // The license is fixed when the recipe is registered, so changes of the default license don't relicense it.
+        recipe.license().to_string(),
+        // Ratings are computed from the votes of the clients, so new recipes have none regardless of the request.
+        StarRate::default().value(),
         recipe.owner().map(|s| s.to_string()),
         recipe.steps().join("/n"),
+        recipe.prep_time_minutes(),
+        state.as_str(),
+        state.as_str(),
+        client_id.map(|id| id.to_string()),
     );
 
-    transaction.execute(query).await.map_err(|e| {
-        error!("{e}");
-        ServerError::DbError
-    })?;
+    transaction
+        .execute(query)
+        .await
+        .map_err(recipe_name_error)?;
 
     for ingredient in recipe.ingredients() {
         transaction
@@ -82,7 +130,7 @@ pub async fn register_new_recipe(
                 "INSERT INTO `UsedIngredient` (`cocktail_id`, `ingredient_id`, `amount`) VALUES (?, ?, ?)",
                 new_id.to_string(),
                 ingredient.ingredient_id.to_string(),
-                &format!("{} {}", ingredient.quantity, ingredient.unit.to_string()),
+                ingredient.amount(),
             ))
             .await
             .map_err(|e| {
@@ -91,12 +139,19 @@ pub async fn register_new_recipe(
             })?;
     }
 
+    insert_equipment(
+        &mut transaction,
+        &new_id,
+        recipe.equipment().unwrap_or_default(),
+    )
+    .await?;
+
     if let Some(tags) = recipe.author_tags() {
         for tag in tags {
             transaction
                 .execute(sqlx::query!(
                     "INSERT INTO `Tagged` (`id`, `cocktail_id`, `type`, `tag`) VALUES (?, ?, ?, ?)",
-                    Uuid::now_v7().to_string(),
+                    ids.new_id().to_string(),
                     new_id.to_string(),
                     "author",
                     tag.identifier,
@@ -114,7 +169,7 @@ pub async fn register_new_recipe(
             transaction
                 .execute(sqlx::query!(
                     "INSERT INTO `Tagged` (`id`, `cocktail_id`, `type`, `tag`) VALUES (?, ?, ?, ?)",
-                    Uuid::now_v7().to_string(),
+                    ids.new_id().to_string(),
                     new_id.to_string(),
                     "backend",
                     tag.identifier,
@@ -127,6 +182,16 @@ pub async fn register_new_recipe(
         }
     }
 
+    touch_collection(&mut *transaction, Collection::Recipe).await?;
+    record_event(
+        &mut *transaction,
+        &DomainEvent::RecipeCreated {
+            recipe_id: new_id,
+            state: recipe.state().unwrap_or(RecipeState::Published),
+        },
+    )
+    .await?;
+
     transaction.commit().await.map_err(|e| {
         error!("{e}");
         ServerError::DbError
@@ -135,12 +200,176 @@ pub async fn register_new_recipe(
     Ok(new_id)
 }
 
+/// Delete a recipe from the DB.
+///
+/// # Description
+///
+/// The recipe is removed along with its ingredients and tags within a single transaction. `false` is returned when
+/// no recipe matched the given ID.
+#[instrument(skip(pool))]
+pub async fn delete_recipe_from_db(pool: &MySqlPool, id: &Uuid) -> Result<bool, ServerError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let mut deleted = false;
+    for query in [
+        "DELETE FROM UsedIngredient WHERE cocktail_id = ?",
+        "DELETE FROM Tagged WHERE cocktail_id = ?",
+        "DELETE FROM Cocktail WHERE id = ?",
+    ] {
+        deleted = sqlx::query(query)
+            .bind(id.to_string())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?
+            .rows_affected()
+            > 0;
+    }
+
+    // Dropping the transaction rolls back the (empty) deletions of an unknown recipe.
+    if !deleted {
+        return Ok(false);
+    }
+
+    record_event(
+        &mut *transaction,
+        &DomainEvent::RecipeDeleted { recipe_id: *id },
+    )
+    .await?;
+    touch_collection(&mut *transaction, Collection::Recipe).await?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(true)
+}
+
+/// Delete a batch of recipes within a single transaction.
+///
+/// # Description
+///
+/// Each ID is processed independently, and its [BatchOutcome] is reported in the returned list, which follows the
+/// order of the given IDs. When `owner` is given, recipes owned by other authors are kept. Either all the
+/// deletions are applied, or none if an error is found while accessing the DB.
+#[instrument(skip(pool, ids))]
+pub async fn delete_recipes_from_db(
+    pool: &MySqlPool,
+    ids: &[(&str, Option<Uuid>)],
+    owner: Option<&Uuid>,
+) -> Result<Vec<BatchItemResult>, ServerError> {
+    let mut results = Vec::new();
+    let owner = owner.map(|id| id.to_string());
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    for (raw_id, id) in ids {
+        let (recipe_id, id) = match id {
+            Some(id) => (*id, id.to_string()),
+            None => {
+                results.push(BatchItemResult {
+                    id: raw_id.to_string(),
+                    outcome: BatchOutcome::InvalidId,
+                });
+                continue;
+            }
+        };
+
+        let row = sqlx::query("SELECT owner FROM Cocktail WHERE id = ? FOR UPDATE")
+            .bind(&id)
+            .fetch_optional(&mut *transaction)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+
+        let outcome = match row {
+            None => BatchOutcome::NotFound,
+            Some(row)
+                if owner.is_some()
+                    && row
+                        .try_get::<Option<String>, _>("owner")
+                        .unwrap_or_default()
+                        != owner =>
+            {
+                BatchOutcome::NotOwned
+            }
+            Some(_) => {
+                // Tagged has no cascade rule, so its entries need to be removed before the recipe.
+                for query in [
+                    "DELETE FROM Tagged WHERE cocktail_id = ?",
+                    "DELETE FROM Cocktail WHERE id = ?",
+                ] {
+                    sqlx::query(query)
+                        .bind(&id)
+                        .execute(&mut *transaction)
+                        .await
+                        .map_err(|e| {
+                            error!("{e}");
+                            ServerError::DbError
+                        })?;
+                }
+                record_event(&mut *transaction, &DomainEvent::RecipeDeleted { recipe_id }).await?;
+                BatchOutcome::Deleted
+            }
+        };
+
+        debug!("Recipe {id}: {outcome:?}");
+        results.push(BatchItemResult { id, outcome });
+    }
+
+    if results.iter().any(|r| r.outcome == BatchOutcome::Deleted) {
+        touch_collection(&mut *transaction, Collection::Recipe).await?;
+    }
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(results)
+}
+
+/// Retrieve the UNIX timestamp of the latest modification of a recipe. `None` is returned when the recipe doesn't
+/// exist.
 #[instrument(skip(pool))]
-pub async fn get_recipe_from_db(
+pub async fn get_recipe_last_modified_from_db(
     pool: &MySqlPool,
     id: &Uuid,
-) -> Result<Option<Recipe>, Box<dyn Error>> {
-    let row = sqlx::query!("SELECT * FROM `Cocktail` WHERE id=?", id.to_string(),)
+) -> Result<Option<i64>, ServerError> {
+    let row = sqlx::query(
+        "SELECT CAST(UNIX_TIMESTAMP(update_date) AS SIGNED) AS last_modified FROM Cocktail WHERE id = ?",
+    )
+    .bind(id.to_string())
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(row.map(|row| {
+        row.try_get::<Option<i64>, _>("last_modified")
+            .unwrap_or_default()
+            .unwrap_or_default()
+    }))
+}
+
+/// Retrieve the amount of clients that rated a recipe. Recipes that don't exist have no votes.
+#[instrument(skip(pool))]
+pub async fn get_rating_votes_from_db(pool: &MySqlPool, id: &Uuid) -> Result<u32, ServerError> {
+    let votes: Option<u32> = sqlx::query_scalar("SELECT rating_votes FROM Cocktail WHERE id = ?")
+        .bind(id.to_string())
         .fetch_optional(pool)
         .await
         .map_err(|e| {
@@ -148,16 +377,378 @@ pub async fn get_recipe_from_db(
             ServerError::DbError
         })?;
 
-    if row.is_none() {
-        info!("The given ID was not found in the recipes DB.");
-        return Ok(None);
+    Ok(votes.unwrap_or_default())
+}
+
+/// Metadata of a recipe, included in the headers of the responses to `HEAD` requests.
+#[derive(Debug, Clone, Copy)]
+pub struct RecipeMetadata {
+    /// UNIX timestamp of the latest modification of the recipe (or its creation, if it was never modified).
+    pub last_modified: i64,
+    pub rating: StarRate,
+}
+
+/// Retrieve the [RecipeMetadata] of a recipe. `None` is returned when the recipe doesn't exist.
+#[instrument(skip(pool))]
+pub async fn get_recipe_metadata_from_db(
+    pool: &MySqlPool,
+    id: &Uuid,
+) -> Result<Option<RecipeMetadata>, ServerError> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            CAST(UNIX_TIMESTAMP(COALESCE(update_date, creation_date)) AS SIGNED) AS last_modified,
+            CAST(rating AS DOUBLE) AS rating
+        FROM Cocktail WHERE id = ?
+        "#,
+    )
+    .bind(id.to_string())
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(row.map(|row| RecipeMetadata {
+        last_modified: row
+            .try_get::<Option<i64>, _>("last_modified")
+            .unwrap_or_default()
+            .unwrap_or_default(),
+        rating: row
+            .try_get::<Option<f64>, _>("rating")
+            .unwrap_or_default()
+            .and_then(|rating| StarRate::new(rating as f32).ok())
+            .unwrap_or_default(),
+    }))
+}
+
+/// Retrieve the name of an ingredient. `None` is returned when the ingredient doesn't exist.
+#[instrument(skip(pool))]
+pub async fn get_ingredient_name_from_db(
+    pool: &MySqlPool,
+    id: &Uuid,
+) -> Result<Option<String>, ServerError> {
+    sqlx::query_scalar("SELECT name FROM Ingredient WHERE id = ?")
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })
+}
+
+/// Retrieve the public name of an author (name and surname).
+///
+/// # Description
+///
+/// `None` is returned when the author doesn't exist, was merged into another profile, or doesn't share the profile.
+#[instrument(skip(pool))]
+pub async fn get_author_public_name_from_db(
+    pool: &MySqlPool,
+    id: &Uuid,
+) -> Result<Option<String>, ServerError> {
+    let row = sqlx::query(
+        "SELECT name, surname FROM Author \
+        WHERE id = ? AND deleted_at IS NULL AND COALESCE(shareable, 1) = 1",
+    )
+    .bind(id.to_string())
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(row
+        .map(|row| {
+            [
+                row.try_get::<Option<String>, _>("name").unwrap_or_default(),
+                row.try_get::<Option<String>, _>("surname")
+                    .unwrap_or_default(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<String>>()
+            .join(" ")
+        })
+        .filter(|name| !name.is_empty()))
+}
+
+/// Resolve the names of the ingredients of a recipe. Missing ingredients get an empty name.
+#[instrument(skip(pool, recipe))]
+pub async fn get_named_ingredients(
+    pool: &MySqlPool,
+    recipe: &Recipe,
+) -> Result<Vec<SheetIngredient>, ServerError> {
+    let mut ingredients = Vec::new();
+
+    for ingredient in recipe.ingredients() {
+        ingredients.push(SheetIngredient {
+            name: get_ingredient_name_from_db(pool, &ingredient.ingredient_id)
+                .await?
+                .unwrap_or_default(),
+            quantity: ingredient.quantity,
+            unit: ingredient.unit,
+        });
     }
 
-    let record = row.unwrap();
+    Ok(ingredients)
+}
+
+/// Set a license to the recipes that have no license, i.e. the recipes registered before licenses were introduced.
+///
+/// # Description
+///
+/// The license of a recipe is fixed when the recipe is registered, so such recipes get the default license of the
+/// server once, usually when the application starts. The amount of modified recipes is returned.
+#[instrument(skip(pool))]
+pub async fn assign_default_license_in_db(
+    pool: &MySqlPool,
+    license: RecipeLicense,
+) -> Result<u64, ServerError> {
+    let result = sqlx::query("UPDATE Cocktail SET license = ? WHERE license IS NULL")
+        .bind(license.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    Ok(result.rows_affected())
+}
+
+/// Columns of the `Cocktail` table read into a [StoredRecipe]. [get_recipe_from_db] lists the same columns within its
+/// query, as the query macros only take literals.
+const RECIPE_COLUMNS: &str =
+    "id, short_id, name, slug, image_id, category, description, url, source_book, source_page, source_url, \
+    source_author, license, steps, owner, prep_time_minutes, state, CAST(rating AS DOUBLE) AS rating";
+
+/// Row of the `Cocktail` table, see [RECIPE_COLUMNS].
+#[derive(Debug, FromRow)]
+struct StoredRecipe {
+    id: String,
+    short_id: Option<String>,
+    name: String,
+    slug: Option<String>,
+    image_id: Option<String>,
+    category: Option<String>,
+    description: Option<String>,
+    url: Option<String>,
+    source_book: Option<String>,
+    source_page: Option<u16>,
+    source_url: Option<String>,
+    source_author: Option<String>,
+    license: Option<String>,
+    steps: String,
+    owner: Option<String>,
+    prep_time_minutes: Option<u16>,
+    state: String,
+    rating: Option<f64>,
+}
+
+/// Lists of a recipe that are stored in their own tables.
+#[derive(Debug, Default)]
+struct RecipeLists {
+    author_tags: Vec<Tag>,
+    tags: Vec<Tag>,
+    ingredients: Vec<RecipeContains>,
+    equipment: Vec<Equipment>,
+    step_images: Vec<StepImage>,
+}
+
+#[instrument(skip(pool))]
+pub async fn get_recipe_from_db(pool: &MySqlPool, id: &Uuid) -> Result<Option<Recipe>, ApiError> {
+    // The collation of `short_id` is binary, so its type is given to read it as text.
+    let row = sqlx::query_as!(
+        StoredRecipe,
+        r#"SELECT id, short_id AS "short_id: String", name, slug, image_id, category, description, url, source_book,
+        source_page, source_url, source_author, license, steps, owner, prep_time_minutes, state,
+        CAST(rating AS DOUBLE) AS rating
+        FROM Cocktail WHERE id = ?"#,
+        id.to_string(),
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let record = match row {
+        Some(record) => record,
+        None => {
+            info!("The given ID was not found in the recipes DB.");
+            return Ok(None);
+        }
+    };
 
     let (author_tags, tags) = get_tags_for_recipe(pool, id.to_string().as_ref()).await?;
-    let ingredients = get_ingredients_for_recipe(pool, id.to_string().as_ref()).await?;
+    let lists = RecipeLists {
+        author_tags,
+        tags,
+        ingredients: get_ingredients_for_recipe(pool, id.to_string().as_ref()).await?,
+        equipment: get_equipment_for_recipe(pool, id).await?,
+        step_images: get_step_images_for_recipe(pool, id).await?,
+    };
+
+    Ok(Some(recipe_from_row(record, lists)?))
+}
+
+/// Retrieve several recipes, in the order of the given IDs. IDs that are not found are skipped.
+///
+/// # Description
+///
+/// Unlike [get_recipe_from_db], every table is read once for all the recipes, so the cost of retrieving a page of
+/// results doesn't grow with its size.
+#[instrument(skip(pool))]
+pub async fn get_recipes_from_db(pool: &MySqlPool, ids: &[Uuid]) -> Result<Vec<Recipe>, ApiError> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = vec!["?"; ids.len()].join(",");
+    let mut records = fetch_rows_of_recipes(
+        pool,
+        &format!("SELECT {RECIPE_COLUMNS} FROM Cocktail WHERE id IN ({placeholders})"),
+        ids,
+    )
+    .await?
+    .into_iter()
+    .map(|row| {
+        let record = StoredRecipe::from_row(&row)?;
+        Ok((record.id.clone(), record))
+    })
+    .collect::<Result<HashMap<String, StoredRecipe>, sqlx::Error>>()?;
+
+    let mut lists: HashMap<String, RecipeLists> = HashMap::new();
+
+    for row in fetch_rows_of_recipes(
+        pool,
+        &format!(
+            "SELECT cocktail_id, tag, type FROM Tagged WHERE cocktail_id IN ({placeholders}) ORDER BY id"
+        ),
+        ids,
+    )
+    .await?
+    {
+        let entry = lists.entry(row.try_get("cocktail_id")?).or_default();
+        let tag = Tag {
+            identifier: row.try_get("tag")?,
+        };
+        if row.try_get::<String, _>("type")? == "author" {
+            entry.author_tags.push(tag);
+        } else {
+            entry.tags.push(tag);
+        }
+    }
+
+    for row in fetch_rows_of_recipes(
+        pool,
+        &format!(
+            "SELECT cocktail_id, ingredient_id, amount FROM UsedIngredient WHERE cocktail_id IN ({placeholders}) \
+            ORDER BY cocktail_id, ingredient_id"
+        ),
+        ids,
+    )
+    .await?
+    {
+        let amount: String = row.try_get("amount")?;
+        let (quantity, unit) = RecipeContains::parse_amount(&amount).map_err(|e| {
+            error!("Malformed amount ({amount}): {e}");
+            ServerError::DbError
+        })?;
+        let ingredient_id: String = row.try_get("ingredient_id")?;
+        lists
+            .entry(row.try_get("cocktail_id")?)
+            .or_default()
+            .ingredients
+            .push(RecipeContains {
+                quantity,
+                unit,
+                ingredient_id: Uuid::parse_str(&ingredient_id).map_err(|e| {
+                    error!("{e}");
+                    ServerError::DbError
+                })?,
+            });
+    }
+
+    for row in fetch_rows_of_recipes(
+        pool,
+        &format!(
+            "SELECT cocktail_id, equipment FROM RecipeEquipment WHERE cocktail_id IN ({placeholders}) \
+            ORDER BY cocktail_id, equipment"
+        ),
+        ids,
+    )
+    .await?
+    {
+        let equipment: String = row.try_get("equipment")?;
+        lists
+            .entry(row.try_get("cocktail_id")?)
+            .or_default()
+            .equipment
+            .push(Equipment::try_from(equipment.as_str()).map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?);
+    }
+
+    for row in fetch_rows_of_recipes(
+        pool,
+        &format!(
+            "SELECT cocktail_id, step, image_id FROM RecipeStepImage WHERE cocktail_id IN ({placeholders}) \
+            ORDER BY cocktail_id, step"
+        ),
+        ids,
+    )
+    .await?
+    {
+        lists
+            .entry(row.try_get("cocktail_id")?)
+            .or_default()
+            .step_images
+            .push(StepImage {
+                step: row.try_get("step")?,
+                image_id: row.try_get("image_id")?,
+            });
+    }
+
+    let mut recipes = Vec::new();
+    for id in ids.iter().map(Uuid::to_string) {
+        if let Some(record) = records.remove(&id) {
+            recipes.push(recipe_from_row(
+                record,
+                lists.remove(&id).unwrap_or_default(),
+            )?);
+        }
+    }
+
+    Ok(recipes)
+}
+
+/// Run a query that takes the IDs of some recipes as its only parameters.
+async fn fetch_rows_of_recipes(
+    pool: &MySqlPool,
+    query: &str,
+    ids: &[Uuid],
+) -> Result<Vec<MySqlRow>, ServerError> {
+    let mut query = sqlx::query(query);
+    for id in ids {
+        query = query.bind(id.to_string());
+    }
+
+    query.fetch_all(pool).await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })
+}
 
+/// Build a recipe from its row of the `Cocktail` table (see [RECIPE_COLUMNS]) and its lists.
+fn recipe_from_row(record: StoredRecipe, lists: RecipeLists) -> Result<Recipe, ApiError> {
+    let source = source_from_row(&record)?;
     let recipe = Recipe::new(
         Some(Uuid::parse_str(&record.id).map_err(|e| {
             error!("{e}");
@@ -165,33 +756,175 @@ pub async fn get_recipe_from_db(
         })?),
         &record.name,
         record.image_id.as_deref(),
-        Some(&author_tags),
-        Some(&tags),
+        Some(&lists.author_tags),
+        Some(&lists.tags),
         match record.category.as_deref() {
             Some(category) => category,
             None => {
                 error!("The recipe has no associated category");
-                return Err(Box::new(ServerError::DbError));
+                return Err(ServerError::DbError.into());
             }
         },
         record.description.as_deref(),
         record.url.as_deref(),
-        &ingredients,
+        &lists.ingredients,
         &stepize(&record.steps),
+        Some(&lists.equipment),
+        record.prep_time_minutes,
         record.owner.as_deref(),
     )?;
 
-    Ok(Some(recipe))
+    let recipe = match record.rating {
+        Some(rating) => recipe.with_rating(StarRate::new(rating as f32)?),
+        None => recipe,
+    };
+    let recipe = recipe
+        .with_state(RecipeState::try_from(record.state.as_str())?)
+        .with_source(source)
+        // Recipes without a license get the default license of the server when the application starts (see
+        // [assign_default_license_in_db]).
+        .with_license(Some(match record.license {
+            Some(license) => RecipeLicense::try_from(license.as_str())?,
+            None => RecipeLicense::default(),
+        }))
+        .with_step_images(lists.step_images)
+        .with_slug(record.slug)
+        .with_short_id(record.short_id.map(ShortId::try_from).transpose()?);
+
+    Ok(recipe)
 }
 
+/// Retrieve the ID of a recipe using its slug and the ID of its author.
 #[instrument(skip(pool))]
-pub async fn search_recipe_by_name(
+pub async fn get_recipe_id_by_slug(
     pool: &MySqlPool,
-    name: &str,
-) -> Result<Vec<Uuid>, Box<dyn Error>> {
-    let recipes = sqlx::query!(
-        r#"SELECT `id` FROM `Cocktail` WHERE name like ?"#,
-        &format!("%{name}%"),
+    author_id: &Uuid,
+    slug: &str,
+) -> Result<Option<Uuid>, ServerError> {
+    let id: Option<String> =
+        sqlx::query_scalar("SELECT id FROM Cocktail WHERE owner = ? AND slug = ?")
+            .bind(author_id.to_string())
+            .bind(slug)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+
+    id.map(|id| {
+        Uuid::parse_str(&id).map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })
+    })
+    .transpose()
+}
+
+/// Retrieve the IDs of some recipes using their short IDs.
+///
+/// # Description
+///
+/// The returned pairs follow no particular order. Short IDs that match no recipe are skipped.
+#[instrument(skip(pool))]
+pub async fn get_recipe_ids_by_short_id(
+    pool: &MySqlPool,
+    short_ids: &[&ShortId],
+) -> Result<Vec<(ShortId, Uuid)>, ServerError> {
+    if short_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = format!(
+        "SELECT short_id, id FROM Cocktail WHERE short_id IN ({})",
+        vec!["?"; short_ids.len()].join(",")
+    );
+    let mut query = sqlx::query_as::<_, (String, String)>(&query);
+    for short_id in short_ids {
+        query = query.bind(short_id.as_str());
+    }
+
+    query
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?
+        .into_iter()
+        .map(
+            |(short_id, id)| match (ShortId::try_from(short_id), Uuid::parse_str(&id)) {
+                (Ok(short_id), Ok(id)) => Ok((short_id, id)),
+                _ => {
+                    error!("Invalid short ID or ID stored for the recipe {id}");
+                    Err(ServerError::DbError)
+                }
+            },
+        )
+        .collect()
+}
+
+/// Rename the slugs of some recipes that clash with the slugs of the recipes of their new owner.
+///
+/// # Description
+///
+/// Slugs are unique per author, so a recipe that moves to another author (a merge, a transfer or a claim) can't keep
+/// a slug that the new owner already uses. The end of the ID of the recipe is appended to its slug in such case. Call
+/// this function before changing the owner of the recipes.
+pub async fn disambiguate_slugs(
+    transaction: &mut Transaction<'_, MySql>,
+    recipe_ids: &[String],
+    new_owner: &str,
+) -> Result<(), ServerError> {
+    if recipe_ids.is_empty() {
+        return Ok(());
+    }
+
+    let query = format!(
+        "UPDATE Cocktail SET slug = CONCAT(LEFT(slug, 31), '-', RIGHT(id, 8)) \
+        WHERE id IN ({}) AND slug IN (SELECT slug FROM (SELECT slug FROM Cocktail WHERE owner = ?) AS owned)",
+        vec!["?"; recipe_ids.len()].join(",")
+    );
+    let mut query = sqlx::query(&query);
+    for id in recipe_ids {
+        query = query.bind(id);
+    }
+
+    let renamed = query
+        .bind(new_owner)
+        .execute(&mut **transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?
+        .rows_affected();
+    if renamed > 0 {
+        info!("{renamed} recipes got a new slug to avoid clashes with the recipes of {new_owner}");
+    }
+
+    Ok(())
+}
+
+/// Map the errors of the queries that set the name of a recipe, which might clash with another recipe of the author.
+fn recipe_name_error(e: sqlx::Error) -> ApiError {
+    match e {
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            info!("The author has another recipe with the same slug");
+            DataDomainError::DuplicatedRecipeName.into()
+        }
+        e => {
+            error!("{e}");
+            ServerError::DbError.into()
+        }
+    }
+}
+
+#[instrument(skip(pool))]
+pub async fn search_recipe_by_name(pool: &MySqlPool, name: &str) -> Result<Vec<Uuid>, ApiError> {
+    let recipes = sqlx::query_scalar!(
+        r#"SELECT `id` FROM `Cocktail` WHERE search_name like ?"#,
+        search_pattern(name),
     )
     .fetch_all(pool)
     .await
@@ -204,7 +937,7 @@ pub async fn search_recipe_by_name(
 
     if let Ok(ids) = recipes {
         for id in ids.iter() {
-            found_recipes.push(Uuid::parse_str(&id.id).map_err(|_| {
+            found_recipes.push(Uuid::parse_str(id).map_err(|_| {
                 error!("Failed to parse ID from a value of the DB");
                 ServerError::DbError
             })?);
@@ -226,7 +959,7 @@ pub async fn search_recipe_by_name(
 pub async fn search_recipe_by_category(
     pool: &MySqlPool,
     category: RecipeCategory,
-) -> Result<Vec<Uuid>, Box<dyn Error>> {
+) -> Result<Vec<Uuid>, ApiError> {
     let recipes = sqlx::query!(
         r#"SELECT `id` FROM `Cocktail` WHERE `category`=?"#,
         &category.to_string(),
@@ -264,10 +997,11 @@ pub async fn search_recipe_by_category(
 pub async fn search_recipe_by_rating(
     pool: &MySqlPool,
     rating: StarRate,
-) -> Result<Vec<Uuid>, Box<dyn Error>> {
+) -> Result<Vec<Uuid>, ApiError> {
+    // Ratings are stored as DECIMAL, thus the comparison is numeric.
     let recipes = sqlx::query!(
         r#"SELECT `id` FROM `Cocktail` WHERE `rating`>=?"#,
-        &rating.to_string(),
+        rating.value(),
     )
     .fetch_all(pool)
     .await
@@ -299,10 +1033,7 @@ pub async fn search_recipe_by_rating(
 }
 
 #[instrument(skip(pool))]
-async fn get_tags_for_recipe(
-    pool: &MySqlPool,
-    id: &str,
-) -> Result<(Vec<Tag>, Vec<Tag>), Box<dyn Error>> {
+async fn get_tags_for_recipe(pool: &MySqlPool, id: &str) -> Result<(Vec<Tag>, Vec<Tag>), ApiError> {
     let records = sqlx::query!(
         "SELECT `tag`, `type` from `Tagged` WHERE `cocktail_id` = ?",
         id,
@@ -336,7 +1067,7 @@ async fn get_tags_for_recipe(
 async fn get_ingredients_for_recipe(
     pool: &MySqlPool,
     id: &str,
-) -> Result<Vec<RecipeContains>, Box<dyn Error>> {
+) -> Result<Vec<RecipeContains>, ApiError> {
     let records = sqlx::query!(
         "SELECT `ingredient_id`, `amount` FROM `UsedIngredient` WHERE `cocktail_id`=?",
         id,
@@ -349,14 +1080,8 @@ async fn get_ingredients_for_recipe(
     let mut ingredients = Vec::new();
 
     for row in records {
-        let split: Vec<&str> = row.amount.split(" ").collect();
-        let quantity = split[0].parse::<f32>().map_err(|e| {
-            error!("{e}");
-            ServerError::DbError
-        })?;
-
-        let unit: QuantityUnit = split[1].try_into().map_err(|e| {
-            error!("{e}");
+        let (quantity, unit) = RecipeContains::parse_amount(&row.amount).map_err(|e| {
+            error!("Malformed amount ({}): {e}", row.amount);
             ServerError::DbError
         })?;
 
@@ -373,12 +1098,1560 @@ async fn get_ingredients_for_recipe(
     Ok(ingredients)
 }
 
-fn stepize(steps: &str) -> Vec<&str> {
-    let mut step_list = Vec::new();
-
-    for line in steps.split("/n") {
-        step_list.push(line);
-    }
-
-    step_list
+/// Retrieve the equipment needed to prepare a recipe.
+#[instrument(skip(pool))]
+async fn get_equipment_for_recipe(
+    pool: &MySqlPool,
+    id: &Uuid,
+) -> Result<Vec<Equipment>, ServerError> {
+    let records: Vec<String> = sqlx::query_scalar(
+        "SELECT equipment FROM RecipeEquipment WHERE cocktail_id = ? ORDER BY equipment",
+    )
+    .bind(id.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    records
+        .iter()
+        .map(|e| {
+            Equipment::try_from(e.as_str()).map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })
+        })
+        .collect()
+}
+
+/// Build the attribution of a recipe from its columns. Recipes without any of them have no source.
+fn source_from_row(record: &StoredRecipe) -> Result<Option<RecipeSource>, sqlx::Error> {
+    let source = RecipeSource {
+        book: record.source_book.clone(),
+        page: record.source_page,
+        url: record
+            .source_url
+            .clone()
+            .map(WebsiteUrl::try_from)
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        author: record.source_author.clone(),
+    };
+
+    if source.book.is_none() && source.url.is_none() && source.author.is_none() {
+        Ok(None)
+    } else {
+        Ok(Some(source))
+    }
+}
+
+async fn get_step_images_for_recipe(
+    pool: &MySqlPool,
+    id: &Uuid,
+) -> Result<Vec<StepImage>, ServerError> {
+    let rows = sqlx::query(
+        "SELECT step, image_id FROM RecipeStepImage WHERE cocktail_id = ? ORDER BY step",
+    )
+    .bind(id.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(StepImage {
+                step: row.try_get("step")?,
+                image_id: row.try_get("image_id")?,
+            })
+        })
+        .collect::<Result<Vec<StepImage>, sqlx::Error>>()
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })
+}
+
+/// Set or remove the image of a preparation step of a recipe.
+///
+/// # Description
+///
+/// The ID of the previous image of the step, if any, is returned so the caller can remove it from the media storage.
+/// `None` is returned when the recipe doesn't exist or it has no such step.
+#[instrument(skip(pool))]
+pub async fn set_step_image_in_db(
+    pool: &MySqlPool,
+    id: &Uuid,
+    step: u16,
+    image_id: Option<&str>,
+) -> Result<Option<Option<String>>, ServerError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let steps = sqlx::query_scalar!(
+        "SELECT steps FROM Cocktail WHERE id = ? FOR UPDATE",
+        id.to_string()
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+    match steps {
+        Some(steps) if (step as usize) < stepize(&steps).len() => (),
+        _ => return Ok(None),
+    }
+
+    let previous = sqlx::query_scalar!(
+        "SELECT image_id FROM RecipeStepImage WHERE cocktail_id = ? AND step = ?",
+        id.to_string(),
+        step
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let query = match image_id {
+        Some(image_id) => {
+            sqlx::query!(
+                "INSERT INTO RecipeStepImage (cocktail_id, step, image_id) VALUES (?, ?, ?) \
+            ON DUPLICATE KEY UPDATE image_id = VALUES(image_id)",
+                id.to_string(),
+                step,
+                image_id
+            )
+            .execute(&mut *transaction)
+            .await
+        }
+        None => {
+            sqlx::query!(
+                "DELETE FROM RecipeStepImage WHERE cocktail_id = ? AND step = ?",
+                id.to_string(),
+                step
+            )
+            .execute(&mut *transaction)
+            .await
+        }
+    };
+    query.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+    // The images are part of the recipe, hence clients that cache it must fetch it again.
+    sqlx::query!(
+        "UPDATE Cocktail SET update_date = CURRENT_TIMESTAMP WHERE id = ?",
+        id.to_string()
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+    touch_collection(&mut *transaction, Collection::Recipe).await?;
+    record_event(
+        &mut *transaction,
+        &DomainEvent::RecipeUpdated { recipe_id: *id },
+    )
+    .await?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(Some(previous))
+}
+
+/// Register the equipment of a recipe. Duplicated entries are ignored.
+async fn insert_equipment(
+    transaction: &mut Transaction<'_, MySql>,
+    id: &Uuid,
+    equipment: &[Equipment],
+) -> Result<(), ServerError> {
+    for item in equipment {
+        sqlx::query("INSERT IGNORE INTO RecipeEquipment (cocktail_id, equipment) VALUES (?, ?)")
+            .bind(id.to_string())
+            .bind(item.to_string())
+            .execute(&mut **transaction)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+    }
+
+    Ok(())
+}
+
+/// Search recipes that don't need any of the given equipment.
+#[instrument(skip(pool))]
+pub async fn search_recipe_without_equipment(
+    pool: &MySqlPool,
+    excluded: &[Equipment],
+) -> Result<Vec<Uuid>, ServerError> {
+    let placeholders = vec!["?"; excluded.len()].join(",");
+    let query = if excluded.is_empty() {
+        "SELECT id FROM Cocktail".to_owned()
+    } else {
+        format!(
+            "SELECT id FROM Cocktail WHERE id NOT IN \
+            (SELECT cocktail_id FROM RecipeEquipment WHERE equipment IN ({placeholders}))"
+        )
+    };
+
+    let mut query = sqlx::query_scalar::<_, String>(&query);
+    for item in excluded {
+        query = query.bind(item.to_string());
+    }
+
+    let ids = query.fetch_all(pool).await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let found_recipes = ids
+        .iter()
+        .map(|id| {
+            Uuid::parse_str(id).map_err(|_| {
+                error!("Failed to parse ID from a value of the DB");
+                ServerError::DbError
+            })
+        })
+        .collect::<Result<Vec<Uuid>, ServerError>>()?;
+
+    info!(
+        "{} recipes found that don't need any of: {excluded:?}",
+        found_recipes.len()
+    );
+
+    Ok(found_recipes)
+}
+
+/// Search recipes that are tagged with all the given tags.
+///
+/// # Description
+///
+/// Both the tags given by the author of the recipe and the tags added by the backend are considered. Repeated tags
+/// are counted once.
+#[instrument(skip(pool))]
+pub async fn search_recipe_by_tags(
+    pool: &MySqlPool,
+    tags: &[Tag],
+) -> Result<Vec<Uuid>, ServerError> {
+    let mut identifiers = tags
+        .iter()
+        .map(|tag| tag.identifier.as_str())
+        .collect::<Vec<&str>>();
+    identifiers.sort_unstable();
+    identifiers.dedup();
+    if identifiers.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = vec!["?"; identifiers.len()].join(",");
+    let query = format!(
+        "SELECT cocktail_id FROM Tagged WHERE tag IN ({placeholders}) \
+        GROUP BY cocktail_id HAVING COUNT(DISTINCT tag) = ?"
+    );
+
+    let mut query = sqlx::query_scalar::<_, String>(&query);
+    for identifier in &identifiers {
+        query = query.bind(*identifier);
+    }
+    query = query.bind(identifiers.len() as u32);
+
+    let ids = query.fetch_all(pool).await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let found_recipes = ids
+        .iter()
+        .map(|id| {
+            Uuid::parse_str(id).map_err(|_| {
+                error!("Failed to parse ID from a value of the DB");
+                ServerError::DbError
+            })
+        })
+        .collect::<Result<Vec<Uuid>, ServerError>>()?;
+
+    info!(
+        "{} recipes found tagged with all of: {tags:?}",
+        found_recipes.len()
+    );
+
+    Ok(found_recipes)
+}
+
+/// Criteria of a recipe search that are combined within a single query.
+#[derive(Debug, Default)]
+pub struct RecipeCriteria<'a> {
+    pub name: Option<&'a str>,
+    pub tags: Option<&'a [Tag]>,
+    pub rating: Option<StarRate>,
+    pub category: Option<&'a RecipeCategory>,
+    pub expression: Option<&'a SearchExpression>,
+    pub excluded_equipment: Option<&'a [Equipment]>,
+    pub max_prep_time: Option<u16>,
+    pub ingredients: Option<&'a [Uuid]>,
+    pub author: Option<&'a Uuid>,
+    pub state: RecipeState,
+}
+
+/// Recipes that use some ingredient whose name is like the bound pattern.
+const INGREDIENT_NAME_SUBQUERY: &str = "SELECT u.cocktail_id FROM UsedIngredient u \
+    JOIN Ingredient i ON i.id = u.ingredient_id WHERE i.search_name LIKE ";
+
+/// Append the `WHERE` clause that selects the recipes that match the criteria to a query over the `Cocktail` table.
+///
+/// # Description
+///
+/// The criteria follow the rules of the individual searches ([search_recipe_by_name], [search_recipe_by_tags],
+/// [search_recipe_by_rating], [search_recipe_by_category], [search_recipe_without_equipment],
+/// [search_recipe_by_prep_time] and [search_recipe_by_ingredients]), but they are combined within a single clause,
+/// rather than intersecting the results of one query per criterion. Criteria that are not given don't restrict the
+/// results. Recipes pending moderation never match.
+fn push_criteria(query: &mut QueryBuilder<'_, MySql>, criteria: &RecipeCriteria<'_>) {
+    query
+        .push(" WHERE state = ")
+        .push_bind(criteria.state.to_string())
+        .push(" AND id NOT IN (SELECT cocktail_id FROM ModerationQueue)");
+    if let Some(name) = criteria.name {
+        query
+            .push(" AND search_name LIKE ")
+            .push_bind(search_pattern(name));
+    }
+    if let Some(category) = criteria.category {
+        query
+            .push(" AND category = ")
+            .push_bind(category.to_string());
+    }
+    // Ratings are stored as DECIMAL, thus the comparison is numeric.
+    if let Some(rating) = criteria.rating {
+        query.push(" AND rating >= ").push_bind(rating.value());
+    }
+    if let Some(tags) = criteria.tags {
+        let tags = tags
+            .iter()
+            .map(|tag| tag.identifier.clone())
+            .collect::<Vec<String>>();
+        push_all_of(query, "Tagged", "tag", tags);
+    }
+    if let Some(excluded) = criteria.excluded_equipment.filter(|e| !e.is_empty()) {
+        query.push(" AND id NOT IN (SELECT cocktail_id FROM RecipeEquipment WHERE equipment IN (");
+        let mut items = query.separated(", ");
+        for item in excluded {
+            items.push_bind(item.to_string());
+        }
+        query.push("))");
+    }
+    if let Some(max_prep_time) = criteria.max_prep_time {
+        query
+            .push(" AND prep_time_minutes <= ")
+            .push_bind(max_prep_time);
+    }
+    if let Some(ingredients) = criteria.ingredients {
+        let ingredients = ingredients.iter().map(Uuid::to_string).collect();
+        push_all_of(query, "UsedIngredient", "ingredient_id", ingredients);
+    }
+    if let Some(author) = criteria.author {
+        query.push(" AND owner = ").push_bind(author.to_string());
+    }
+    if let Some(expression) = criteria.expression {
+        query.push(" AND ");
+        push_expression(query, expression);
+    }
+}
+
+/// Append a condition that matches the recipes linked to all the given values by a table, i.e. the recipes tagged
+/// with all the given tags. Repeated values are counted once, and no recipe matches an empty list.
+fn push_all_of(
+    query: &mut QueryBuilder<'_, MySql>,
+    table: &str,
+    column: &str,
+    mut values: Vec<String>,
+) {
+    values.sort_unstable();
+    values.dedup();
+    if values.is_empty() {
+        query.push(" AND FALSE");
+        return;
+    }
+
+    let count = values.len() as u32;
+    query.push(format!(
+        " AND id IN (SELECT cocktail_id FROM {table} WHERE {column} IN ("
+    ));
+    let mut items = query.separated(", ");
+    for value in values {
+        items.push_bind(value);
+    }
+    query
+        .push(format!(
+            ") GROUP BY cocktail_id HAVING COUNT(DISTINCT {column}) = "
+        ))
+        .push_bind(count)
+        .push(")");
+}
+
+/// Append the condition that matches the recipes that match a search expression.
+///
+/// # Description
+///
+/// Terms that compare a column which might be `NULL`, i.e. the rating of unrated recipes, are unknown rather than
+/// false, so negated expressions are coalesced to keep such recipes.
+fn push_expression(query: &mut QueryBuilder<'_, MySql>, expression: &SearchExpression) {
+    match expression {
+        SearchExpression::Term(term) => push_term(query, term),
+        SearchExpression::And(items) | SearchExpression::Or(items) => {
+            let (operator, empty) = match expression {
+                SearchExpression::And(_) => (" AND ", "TRUE"),
+                _ => (" OR ", "FALSE"),
+            };
+            if items.is_empty() {
+                query.push(empty);
+                return;
+            }
+            query.push("(");
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    query.push(operator);
+                }
+                push_expression(query, item);
+            }
+            query.push(")");
+        }
+        SearchExpression::Not(item) => {
+            query.push("NOT COALESCE(");
+            push_expression(query, item);
+            query.push(", FALSE)");
+        }
+    }
+}
+
+fn push_term(query: &mut QueryBuilder<'_, MySql>, term: &SearchTerm) {
+    match term {
+        SearchTerm::Text(text) => {
+            query
+                .push("(search_name LIKE ")
+                .push_bind(search_pattern(text))
+                .push(format!(" OR id IN ({INGREDIENT_NAME_SUBQUERY}"))
+                .push_bind(search_pattern(text))
+                .push("))");
+        }
+        SearchTerm::Name(name) => {
+            query
+                .push("search_name LIKE ")
+                .push_bind(search_pattern(name));
+        }
+        SearchTerm::Ingredient(name) => {
+            query
+                .push(format!("id IN ({INGREDIENT_NAME_SUBQUERY}"))
+                .push_bind(search_pattern(name))
+                .push(")");
+        }
+        SearchTerm::Tag(tag) => {
+            query
+                .push("id IN (SELECT cocktail_id FROM Tagged WHERE tag = ")
+                .push_bind(tag.identifier.clone())
+                .push(")");
+        }
+        SearchTerm::Category(category) => {
+            query.push("category = ").push_bind(category.to_string());
+        }
+        SearchTerm::Rating(rating) => {
+            query.push("rating >= ").push_bind(rating.value());
+        }
+    }
+}
+
+/// Search recipes that match all the given criteria (see [RecipeCriteria]).
+///
+/// # Description
+///
+/// The order of the recipes is translated into the `ORDER BY` clause of the query (see [RecipeSort::order_by]).
+/// Recipes are sorted by ID when no order is given, so the pages of the results are stable. Up to `limit` recipes are
+/// listed after skipping `offset` of them, all of them when `limit` is not given.
+#[instrument(skip(pool))]
+pub async fn search_recipe_by_criteria(
+    pool: &MySqlPool,
+    criteria: &RecipeCriteria<'_>,
+    sort: Option<&RecipeSort>,
+    collation: Option<&Collation>,
+    limit: Option<u32>,
+    offset: u32,
+) -> Result<Vec<Uuid>, ServerError> {
+    let mut query = QueryBuilder::new("SELECT id FROM Cocktail");
+    push_criteria(&mut query, criteria);
+    query.push(" ORDER BY ").push(match sort {
+        Some(sort) => sort.order_by(collation),
+        None => "id".to_owned(),
+    });
+    if let Some(limit) = limit {
+        query
+            .push(" LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+    }
+
+    let ids = query
+        .build_query_scalar::<String>()
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    let found_recipes = ids
+        .iter()
+        .map(|id| {
+            Uuid::parse_str(id).map_err(|_| {
+                error!("Failed to parse ID from a value of the DB");
+                ServerError::DbError
+            })
+        })
+        .collect::<Result<Vec<Uuid>, ServerError>>()?;
+
+    info!(
+        "{} recipes found matching all of: {criteria:?}",
+        found_recipes.len()
+    );
+
+    Ok(found_recipes)
+}
+
+/// Count the recipes listed by [search_recipe_by_criteria], regardless of the page.
+#[instrument(skip(pool))]
+pub async fn count_recipes_by_criteria(
+    pool: &MySqlPool,
+    criteria: &RecipeCriteria<'_>,
+) -> Result<u64, ServerError> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM Cocktail");
+    push_criteria(&mut query, criteria);
+
+    let total = query
+        .build_query_scalar::<i64>()
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    Ok(total as u64)
+}
+
+/// Search recipes that use all the given ingredients.
+#[instrument(skip(pool))]
+pub async fn search_recipe_by_ingredients(
+    pool: &MySqlPool,
+    ingredients: &[Uuid],
+) -> Result<Vec<Uuid>, ServerError> {
+    if ingredients.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = vec!["?"; ingredients.len()].join(",");
+    let query = format!(
+        "SELECT cocktail_id FROM UsedIngredient WHERE ingredient_id IN ({placeholders}) \
+        GROUP BY cocktail_id HAVING COUNT(DISTINCT ingredient_id) = ?"
+    );
+
+    let mut query = sqlx::query_scalar::<_, String>(&query);
+    for ingredient in ingredients {
+        query = query.bind(ingredient.to_string());
+    }
+    query = query.bind(ingredients.len() as u32);
+
+    let ids = query.fetch_all(pool).await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let found_recipes = ids
+        .iter()
+        .map(|id| {
+            Uuid::parse_str(id).map_err(|_| {
+                error!("Failed to parse ID from a value of the DB");
+                ServerError::DbError
+            })
+        })
+        .collect::<Result<Vec<Uuid>, ServerError>>()?;
+
+    info!(
+        "{} recipes found using all of: {ingredients:?}",
+        found_recipes.len()
+    );
+
+    Ok(found_recipes)
+}
+
+/// Search recipes whose estimated preparation time is lower or equal than `max_minutes`.
+///
+/// # Description
+///
+/// Recipes with no estimated preparation time are not included, as there is no way to tell whether they match.
+#[instrument(skip(pool))]
+pub async fn search_recipe_by_prep_time(
+    pool: &MySqlPool,
+    max_minutes: u16,
+) -> Result<Vec<Uuid>, ServerError> {
+    let ids: Vec<String> =
+        sqlx::query_scalar("SELECT id FROM Cocktail WHERE prep_time_minutes <= ?")
+            .bind(max_minutes)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+
+    let found_recipes = ids
+        .iter()
+        .map(|id| {
+            Uuid::parse_str(id).map_err(|_| {
+                error!("Failed to parse ID from a value of the DB");
+                ServerError::DbError
+            })
+        })
+        .collect::<Result<Vec<Uuid>, ServerError>>()?;
+
+    info!(
+        "{} recipes found that take {max_minutes} minutes or less.",
+        found_recipes.len()
+    );
+
+    Ok(found_recipes)
+}
+
+/// Update an existing recipe.
+///
+/// # Description
+///
+/// The given [Recipe] replaces the content of the entry identified by `id`: the ingredients and the equipment are
+/// replaced as a whole. Tags are kept untouched. `false` is returned when no recipe matches the given ID.
+///
+/// The update date of the recipe is always set, as it would be kept when only the ingredients or the equipment
+/// change: they live in their own tables.
+#[instrument(skip(pool, recipe))]
+pub async fn update_recipe_in_db(
+    pool: &MySqlPool,
+    id: &Uuid,
+    recipe: &Recipe,
+) -> Result<bool, ApiError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let exists = sqlx::query("SELECT id FROM Cocktail WHERE id = ? FOR UPDATE")
+        .bind(id.to_string())
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?
+        .is_some();
+
+    if !exists {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        "UPDATE Cocktail SET name = ?, search_name = ?, slug = ?, description = ?, category = ?, url = ?, source_book = ?, \
+        source_page = ?, source_url = ?, source_author = ?, license = ?, steps = ?, prep_time_minutes = ?, \
+        update_date = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(recipe.name())
+    .bind(normalize_search_text(recipe.name()))
+    .bind(slugify(recipe.name()))
+    .bind(recipe.description())
+    .bind(recipe.category().to_string())
+    .bind(recipe.url())
+    .bind(recipe.source().and_then(|s| s.book.as_deref()))
+    .bind(recipe.source().and_then(|s| s.page))
+    .bind(recipe.source().and_then(|s| s.url.as_ref().map(WebsiteUrl::as_str)))
+    .bind(recipe.source().and_then(|s| s.author.as_deref()))
+    .bind(recipe.license().to_string())
+    .bind(recipe.steps().join("/n"))
+    .bind(recipe.prep_time_minutes())
+    .bind(id.to_string())
+    .execute(&mut *transaction)
+    .await
+    .map_err(recipe_name_error)?;
+
+    for query in [
+        "DELETE FROM UsedIngredient WHERE cocktail_id = ?",
+        "DELETE FROM RecipeEquipment WHERE cocktail_id = ?",
+    ] {
+        sqlx::query(query)
+            .bind(id.to_string())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+    }
+
+    for ingredient in recipe.ingredients() {
+        sqlx::query(
+            "INSERT INTO UsedIngredient (cocktail_id, ingredient_id, amount) VALUES (?, ?, ?)",
+        )
+        .bind(id.to_string())
+        .bind(ingredient.ingredient_id.to_string())
+        .bind(ingredient.amount())
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+    }
+
+    insert_equipment(&mut transaction, id, recipe.equipment().unwrap_or_default()).await?;
+
+    touch_collection(&mut *transaction, Collection::Recipe).await?;
+    record_event(
+        &mut *transaction,
+        &DomainEvent::RecipeUpdated { recipe_id: *id },
+    )
+    .await?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(true)
+}
+
+fn stepize(steps: &str) -> Vec<&str> {
+    let mut step_list = Vec::new();
+
+    for line in steps.split("/n") {
+        step_list.push(line);
+    }
+
+    step_list
+}
+
+/// Send a recipe to the moderation queue. Recipes already in the queue get their reasons updated.
+#[instrument(skip(pool))]
+pub async fn flag_recipe_in_db(
+    pool: &MySqlPool,
+    id: &Uuid,
+    reasons: &[ScreeningFlag],
+) -> Result<(), ServerError> {
+    let serialized_reasons = serde_json::to_string(reasons).map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    sqlx::query(
+        "INSERT INTO ModerationQueue (cocktail_id, reasons) VALUES (?, ?) \
+        ON DUPLICATE KEY UPDATE reasons = VALUES(reasons), flagged_at = CURRENT_TIMESTAMP",
+    )
+    .bind(id.to_string())
+    .bind(serialized_reasons)
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    // Flagged recipes are hidden from the searches.
+    touch_collection(&mut *transaction, Collection::Recipe).await?;
+    record_event(
+        &mut *transaction,
+        &DomainEvent::RecipeFlagged {
+            recipe_id: *id,
+            reasons: reasons.to_vec(),
+        },
+    )
+    .await?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
+
+/// Check whether a recipe is waiting in the moderation queue.
+#[instrument(skip(pool))]
+pub async fn is_recipe_pending_moderation(
+    pool: &MySqlPool,
+    id: &Uuid,
+) -> Result<bool, ServerError> {
+    let pending: Option<String> =
+        sqlx::query_scalar("SELECT cocktail_id FROM ModerationQueue WHERE cocktail_id = ?")
+            .bind(id.to_string())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?;
+
+    Ok(pending.is_some())
+}
+
+/// Remove the recipes that are waiting in the moderation queue from a list of IDs.
+#[instrument(skip(pool, ids))]
+pub async fn filter_pending_moderation(
+    pool: &MySqlPool,
+    mut ids: Vec<Uuid>,
+) -> Result<Vec<Uuid>, ServerError> {
+    let pending: Vec<String> = sqlx::query_scalar("SELECT cocktail_id FROM ModerationQueue")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    ids.retain(|id| !pending.contains(&id.to_string()));
+
+    Ok(ids)
+}
+
+/// Store the vote of a client for a recipe, and update the cached rating of the recipe.
+///
+/// # Description
+///
+/// The vote replaces the former vote of the client, if any. Only the public recipes can be voted: `None` is returned
+/// when the recipe doesn't exist, or it is hidden (see [is_recipe_hidden]). Votes of a recipe are serialized using a
+/// lock on the recipe, so concurrent votes don't compute the rating using stale data.
+#[instrument(skip(pool))]
+pub async fn store_rating_vote_in_db(
+    pool: &MySqlPool,
+    recipe_id: &Uuid,
+    client_id: &str,
+    stars: StarRate,
+) -> Result<Option<RecipeRating>, ServerError> {
+    let db_error = |e: sqlx::Error| {
+        error!("{e}");
+        ServerError::DbError
+    };
+    let mut transaction = pool.begin().await.map_err(db_error)?;
+
+    let current = sqlx::query_scalar!(
+        "SELECT CAST(rating AS DOUBLE) AS rating FROM Cocktail WHERE id = ? AND state = 'published' \
+        AND id NOT IN (SELECT cocktail_id FROM ModerationQueue) FOR UPDATE",
+        recipe_id.to_string()
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .map_err(db_error)?;
+    let Some(current) = current else {
+        return Ok(None);
+    };
+
+    sqlx::query!(
+        "INSERT INTO RecipeRating (cocktail_id, client_id, stars) VALUES (?, ?, ?) \
+        ON DUPLICATE KEY UPDATE stars = VALUES(stars)",
+        recipe_id.to_string(),
+        client_id,
+        stars.value()
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(db_error)?;
+
+    let row = sqlx::query!(
+        "SELECT COUNT(*) AS votes, CAST(AVG(stars) AS DOUBLE) AS average FROM RecipeRating \
+        WHERE cocktail_id = ?",
+        recipe_id.to_string()
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(db_error)?;
+    let votes = row.votes;
+    let rating = StarRate::from_average(row.average.unwrap_or_default());
+
+    // The votes don't modify the content of the recipe, so its update date is kept.
+    sqlx::query!(
+        "UPDATE Cocktail SET rating = ?, rating_votes = ?, update_date = update_date WHERE id = ?",
+        rating.value(),
+        votes,
+        recipe_id.to_string()
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(db_error)?;
+    // Searches filter and sort by the rating, so they change when the rating does.
+    if StarRate::from_average(current) != rating {
+        touch_collection(&mut *transaction, Collection::Recipe).await?;
+    }
+    record_event(
+        &mut *transaction,
+        &DomainEvent::RatingSubmitted {
+            recipe_id: *recipe_id,
+            rating,
+            votes: votes as u32,
+        },
+    )
+    .await?;
+    transaction.commit().await.map_err(db_error)?;
+
+    Ok(Some(RecipeRating {
+        rating,
+        votes: votes as u32,
+        vote: stars,
+    }))
+}
+
+/// Check whether a recipe is hidden from the public, i.e. it is not published or it is pending moderation.
+///
+/// # Description
+///
+/// `false` is returned when the recipe doesn't exist, so callers answer such case as they see fit.
+#[instrument(skip(pool))]
+pub async fn is_recipe_hidden(pool: &MySqlPool, id: &Uuid) -> Result<bool, ServerError> {
+    let hidden = sqlx::query_scalar!(
+        "SELECT CAST(state <> 'published' OR id IN (SELECT cocktail_id FROM ModerationQueue) AS SIGNED) \
+        AS hidden FROM Cocktail WHERE id = ?",
+        id.to_string()
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(hidden.unwrap_or_default() != 0)
+}
+
+/// Retrieve the state of a recipe in the publishing workflow. `None` is returned when the recipe doesn't exist.
+#[instrument(skip(pool))]
+pub async fn get_recipe_state_from_db(
+    pool: &MySqlPool,
+    id: &Uuid,
+) -> Result<Option<RecipeState>, ServerError> {
+    let state: Option<String> = sqlx::query_scalar("SELECT state FROM Cocktail WHERE id = ?")
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    state
+        .map(|state| {
+            RecipeState::try_from(state.as_str()).map_err(|_| {
+                error!("Unknown state of a recipe: {state}");
+                ServerError::DbError
+            })
+        })
+        .transpose()
+}
+
+/// Move a recipe between two states of the publishing workflow.
+///
+/// # Description
+///
+/// The recipe is only modified when it is still in the state `from`, so concurrent transitions don't overwrite each
+/// other. `false` is returned when the recipe was not modified. Recipes that reach the state `published` get their
+/// publication date set, so they are listed by the next digests regardless of when they were registered.
+#[instrument(skip(pool))]
+pub async fn set_recipe_state_in_db(
+    pool: &MySqlPool,
+    id: &Uuid,
+    from: RecipeState,
+    to: RecipeState,
+) -> Result<bool, ServerError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let result = sqlx::query(
+        "UPDATE Cocktail SET state = ?, \
+        publication_date = IF(? = 'published', CURRENT_TIMESTAMP, NULL) \
+        WHERE id = ? AND state = ?",
+    )
+    .bind(to.to_string())
+    .bind(to.to_string())
+    .bind(id.to_string())
+    .bind(from.to_string())
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    touch_collection(&mut *transaction, Collection::Recipe).await?;
+    record_event(
+        &mut *transaction,
+        &DomainEvent::RecipeStateChanged {
+            recipe_id: *id,
+            from,
+            to,
+        },
+    )
+    .await?;
+
+    transaction.commit().await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(true)
+}
+
+/// Retrieve the newest published recipes of an author, up to `limit` recipes. Recipes pending moderation are skipped.
+#[instrument(skip(pool))]
+pub async fn search_recipe_by_owner(
+    pool: &MySqlPool,
+    author_id: &Uuid,
+    limit: u32,
+) -> Result<Vec<Uuid>, ServerError> {
+    let ids: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM Cocktail WHERE owner = ? AND state = 'published' \
+        AND id NOT IN (SELECT cocktail_id FROM ModerationQueue) \
+        ORDER BY creation_date DESC, id LIMIT ?",
+    )
+    .bind(author_id.to_string())
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    ids.iter()
+        .map(|id| {
+            Uuid::parse_str(id).map_err(|_| {
+                error!("Failed to parse ID from a value of the DB");
+                ServerError::DbError
+            })
+        })
+        .collect()
+}
+
+/// Check whether a client of the API has published recipes, which makes the review of its new recipes unnecessary.
+///
+/// # Description
+///
+/// The recipes are matched by the client that registered them, not by their author, as any client can register
+/// recipes on behalf of any author.
+#[instrument(skip(pool))]
+pub async fn has_published_recipes(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+) -> Result<bool, ServerError> {
+    let published: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM Cocktail WHERE client_id = ? AND state = 'published' \
+        AND id NOT IN (SELECT cocktail_id FROM ModerationQueue)",
+    )
+    .bind(client_id.to_string())
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(published > 0)
+}
+
+/// Select the public recipes that use some of the given ingredients, and miss `max_missing` ingredients at most.
+///
+/// # Description
+///
+/// The query binds the IDs of the ingredients, then `max_missing`. Its rows have the ID of the recipe, the amount of
+/// ingredients that it misses, and its rating.
+fn suggestion_query(ingredients: usize) -> String {
+    let placeholders = vec!["?"; ingredients].join(",");
+    format!(
+        r#"
+        SELECT id, total - available AS missing, rating FROM (
+            SELECT c.id, c.rating, COUNT(*) AS total,
+                COUNT(CASE WHEN u.ingredient_id IN ({placeholders}) THEN 1 END) AS available
+            FROM Cocktail c
+            INNER JOIN UsedIngredient u ON u.cocktail_id = c.id
+            WHERE c.state = 'published' AND c.id NOT IN (SELECT cocktail_id FROM ModerationQueue)
+            GROUP BY c.id, c.rating
+        ) s
+        WHERE available > 0 AND total - available <= ?
+        "#
+    )
+}
+
+/// Retrieve the IDs of the public recipes that can be prepared, or nearly, using the given ingredients.
+///
+/// # Description
+///
+/// Only the recipes that use some of the given ingredients, and miss `max_missing` ingredients at most, are listed.
+/// Recipes are ranked by the amount of missing ingredients, then by their rating (unrated recipes go last). Up to
+/// `limit` recipes are listed after skipping `offset` of them, all of them when `limit` is not given.
+#[instrument(skip(pool, ingredients))]
+pub async fn suggest_recipes_from_db(
+    pool: &MySqlPool,
+    ingredients: &[Uuid],
+    max_missing: u32,
+    limit: Option<u32>,
+    offset: u32,
+) -> Result<Vec<Uuid>, ServerError> {
+    if ingredients.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut query = format!(
+        "SELECT id FROM ({}) r ORDER BY missing, rating DESC, id",
+        suggestion_query(ingredients.len())
+    );
+    if limit.is_some() {
+        query.push_str(" LIMIT ? OFFSET ?");
+    }
+    let mut query = sqlx::query_scalar::<_, String>(&query);
+    for id in ingredients {
+        query = query.bind(id.to_string());
+    }
+    query = query.bind(max_missing);
+    if let Some(limit) = limit {
+        query = query.bind(limit).bind(offset);
+    }
+    let ids = query.fetch_all(pool).await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    ids.iter()
+        .map(|id| {
+            Uuid::parse_str(id).map_err(|_| {
+                error!("Failed to parse ID from a value of the DB");
+                ServerError::DbError
+            })
+        })
+        .collect()
+}
+
+/// Count the recipes listed by [suggest_recipes_from_db], regardless of the page.
+#[instrument(skip(pool, ingredients))]
+pub async fn count_suggested_recipes_from_db(
+    pool: &MySqlPool,
+    ingredients: &[Uuid],
+    max_missing: u32,
+) -> Result<u64, ServerError> {
+    if ingredients.is_empty() {
+        return Ok(0);
+    }
+
+    let query = format!(
+        "SELECT COUNT(*) FROM ({}) r",
+        suggestion_query(ingredients.len())
+    );
+    let mut query = sqlx::query_scalar::<_, i64>(&query);
+    for id in ingredients {
+        query = query.bind(id.to_string());
+    }
+    let total = query.bind(max_missing).fetch_one(pool).await.map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(total as u64)
+}
+
+/// Retrieve the email of an author. `None` is returned when the author doesn't exist, or was merged into another
+/// profile.
+#[instrument(skip(pool))]
+pub async fn get_author_email_from_db(
+    pool: &MySqlPool,
+    author_id: &Uuid,
+) -> Result<Option<String>, ServerError> {
+    sqlx::query_scalar("SELECT email FROM Author WHERE id = ? AND deleted_at IS NULL")
+        .bind(author_id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })
+}
+
+/// Retrieve the email of a client of the API.
+#[instrument(skip(pool))]
+pub async fn get_client_email_from_db(
+    pool: &MySqlPool,
+    client_id: &str,
+) -> Result<Option<String>, ServerError> {
+    sqlx::query_scalar("SELECT email FROM ApiUser WHERE id = ?")
+        .bind(client_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })
+}
+
+/// Columns of the claims, including the source URL of the claimed recipe.
+const CLAIM_COLUMNS: &str =
+    "c.id, c.recipe_id, c.author_id, c.client_id, c.state, c.token, r.url, c.created_at, \
+    c.resolved_at FROM RecipeClaim c INNER JOIN Cocktail r ON r.id = c.recipe_id";
+
+fn claim_from_row(row: &MySqlRow) -> Result<RecipeClaim, ServerError> {
+    let claim = || -> Result<RecipeClaim, ApiError> {
+        let state: String = row.try_get("state")?;
+        Ok(RecipeClaim {
+            id: row.try_get("id")?,
+            recipe_id: row.try_get("recipe_id")?,
+            author_id: row.try_get("author_id")?,
+            client_id: row.try_get("client_id")?,
+            state: ClaimState::try_from(state.as_str())?,
+            token: row.try_get("token")?,
+            source_url: row.try_get("url")?,
+            created_at: row.try_get("created_at")?,
+            resolved_at: row.try_get("resolved_at")?,
+        })
+    };
+
+    claim().map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })
+}
+
+/// Store a new claim of a recipe.
+#[instrument(skip(pool))]
+pub async fn store_claim_in_db(pool: &MySqlPool, claim: &RecipeClaim) -> Result<(), ServerError> {
+    sqlx::query(
+        "INSERT INTO RecipeClaim (id, recipe_id, author_id, client_id, state, token, created_at) \
+        VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&claim.id)
+    .bind(&claim.recipe_id)
+    .bind(&claim.author_id)
+    .bind(&claim.client_id)
+    .bind(claim.state.to_string())
+    .bind(&claim.token)
+    .bind(claim.created_at)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
+
+/// Retrieve the pending claim of a recipe issued by a client in the name of an author.
+#[instrument(skip(pool))]
+pub async fn get_pending_claim_from_db(
+    pool: &MySqlPool,
+    recipe_id: &Uuid,
+    author_id: &Uuid,
+    client_id: &str,
+) -> Result<Option<RecipeClaim>, ServerError> {
+    let row = sqlx::query(&format!(
+        "SELECT {CLAIM_COLUMNS} WHERE c.recipe_id = ? AND c.author_id = ? AND c.client_id = ? \
+        AND c.state = 'pending' ORDER BY c.created_at DESC LIMIT 1"
+    ))
+    .bind(recipe_id.to_string())
+    .bind(author_id.to_string())
+    .bind(client_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    row.as_ref().map(claim_from_row).transpose()
+}
+
+/// Retrieve a claim using its ID.
+#[instrument(skip(pool))]
+pub async fn get_claim_from_db(
+    pool: &MySqlPool,
+    id: &Uuid,
+) -> Result<Option<RecipeClaim>, ServerError> {
+    let row = sqlx::query(&format!("SELECT {CLAIM_COLUMNS} WHERE c.id = ?"))
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    row.as_ref().map(claim_from_row).transpose()
+}
+
+/// Retrieve the pending claims, oldest first.
+#[instrument(skip(pool))]
+pub async fn get_pending_claims_from_db(pool: &MySqlPool) -> Result<Vec<RecipeClaim>, ServerError> {
+    let rows = sqlx::query(&format!(
+        "SELECT {CLAIM_COLUMNS} WHERE c.state = 'pending' ORDER BY c.created_at"
+    ))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    rows.iter().map(claim_from_row).collect()
+}
+
+/// Grant a claim: the author of the claim becomes the owner of the recipe.
+///
+/// # Description
+///
+/// The recipe is only modified when it still has no owner. The rest of the pending claims of the recipe are rejected.
+/// `false` is returned when the recipe got an owner in the meantime.
+#[instrument(skip(pool))]
+pub async fn grant_claim_in_db(pool: &MySqlPool, claim: &RecipeClaim) -> Result<bool, ServerError> {
+    let db_error = |e: sqlx::Error| {
+        error!("{e}");
+        ServerError::DbError
+    };
+    let mut transaction = pool.begin().await.map_err(db_error)?;
+
+    disambiguate_slugs(
+        &mut transaction,
+        std::slice::from_ref(&claim.recipe_id),
+        &claim.author_id,
+    )
+    .await?;
+    let owned = sqlx::query("UPDATE Cocktail SET owner = ? WHERE id = ? AND owner IS NULL")
+        .bind(&claim.author_id)
+        .bind(&claim.recipe_id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(db_error)?;
+    if owned.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        "UPDATE RecipeClaim SET state = IF(id = ?, 'approved', 'rejected'), resolved_at = CURRENT_TIMESTAMP \
+        WHERE recipe_id = ? AND state = 'pending'",
+    )
+    .bind(&claim.id)
+    .bind(&claim.recipe_id)
+    .execute(&mut *transaction)
+    .await
+    .map_err(db_error)?;
+
+    touch_collection(&mut *transaction, Collection::Recipe).await?;
+    let parse_id = |id: &str| {
+        Uuid::parse_str(id).map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })
+    };
+    record_event(
+        &mut *transaction,
+        &DomainEvent::RecipeOwnerChanged {
+            recipe_id: parse_id(&claim.recipe_id)?,
+            owner: parse_id(&claim.author_id)?,
+        },
+    )
+    .await?;
+    transaction.commit().await.map_err(db_error)?;
+
+    Ok(true)
+}
+
+/// Reject a pending claim. `false` is returned when the claim is not pending.
+#[instrument(skip(pool))]
+pub async fn reject_claim_in_db(pool: &MySqlPool, id: &Uuid) -> Result<bool, ServerError> {
+    let result = sqlx::query(
+        "UPDATE RecipeClaim SET state = 'rejected', resolved_at = CURRENT_TIMESTAMP \
+        WHERE id = ? AND state = 'pending'",
+    )
+    .bind(id.to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Count the recipes that match the given criteria per category, per rating and per tag.
+///
+/// # Description
+///
+/// Ratings are grouped by whole stars, i.e. recipes rated with 3.5 stars are counted within the 3 stars bucket. Only
+/// the `max_tags` most used tags are counted, sorted by the number of recipes.
+#[instrument(skip(pool))]
+pub async fn get_recipe_facets_from_db(
+    pool: &MySqlPool,
+    criteria: &RecipeCriteria<'_>,
+    max_tags: u32,
+) -> Result<RecipeFacets, ServerError> {
+    let map_db_error = |e: sqlx::Error| {
+        error!("{e}");
+        ServerError::DbError
+    };
+
+    let mut query = QueryBuilder::new("SELECT `category`, COUNT(*) AS `count` FROM `Cocktail`");
+    push_criteria(&mut query, criteria);
+    query.push(" AND `category` IS NOT NULL GROUP BY `category` ORDER BY `category`");
+    let categories = query
+        .build()
+        .fetch_all(pool)
+        .await
+        .map_err(map_db_error)?
+        .iter()
+        .map(|row| -> Result<CategoryFacet, ApiError> {
+            let category: String = row.try_get("category")?;
+            let count: i64 = row.try_get("count")?;
+            Ok(CategoryFacet {
+                category: RecipeCategory::try_from(category.as_str())?,
+                count: count as u64,
+            })
+        })
+        .collect::<Result<Vec<CategoryFacet>, ApiError>>();
+
+    let mut query = QueryBuilder::new(
+        "SELECT CAST(FLOOR(`rating`) AS UNSIGNED) AS `stars`, COUNT(*) AS `count` FROM `Cocktail`",
+    );
+    push_criteria(&mut query, criteria);
+    query.push(" GROUP BY `stars` ORDER BY `stars` DESC");
+    let ratings = query
+        .build()
+        .fetch_all(pool)
+        .await
+        .map_err(map_db_error)?
+        .iter()
+        .map(|row| -> Result<RatingFacet, ApiError> {
+            let stars: u64 = row.try_get("stars")?;
+            let count: i64 = row.try_get("count")?;
+            Ok(RatingFacet {
+                stars: u8::try_from(stars).context("Invalid rating")?,
+                count: count as u64,
+            })
+        })
+        .collect::<Result<Vec<RatingFacet>, ApiError>>();
+
+    let mut query = QueryBuilder::new(
+        "SELECT `tag`, COUNT(DISTINCT `cocktail_id`) AS `count` FROM `Tagged` \
+        WHERE `cocktail_id` IN (SELECT `id` FROM `Cocktail`",
+    );
+    push_criteria(&mut query, criteria);
+    query
+        .push(") GROUP BY `tag` ORDER BY `count` DESC, `tag` LIMIT ")
+        .push_bind(max_tags);
+    let tags = query
+        .build()
+        .fetch_all(pool)
+        .await
+        .map_err(map_db_error)?
+        .iter()
+        .map(|row| -> Result<TagFacet, ApiError> {
+            let count: i64 = row.try_get("count")?;
+            Ok(TagFacet {
+                tag: row.try_get("tag")?,
+                count: count as u64,
+            })
+        })
+        .collect::<Result<Vec<TagFacet>, ApiError>>();
+
+    match (categories, ratings, tags) {
+        (Ok(categories), Ok(ratings), Ok(tags)) => Ok(RecipeFacets {
+            categories,
+            ratings,
+            tags,
+        }),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+            error!("{e}");
+            Err(ServerError::DbError)
+        }
+    }
+}
+
+/// Retrieve the most searched recipe terms that start with a prefix, most searched first.
+///
+/// # Description
+///
+/// Only the searches stored since `from` are counted, and only the terms searched by at least `k_anonymity` distinct
+/// clients within a day are returned (see [crate::utils::metrics::UsageAnalytics]).
+#[instrument(skip(pool))]
+pub async fn get_search_suggestions_from_db(
+    pool: &MySqlPool,
+    prefix: &str,
+    from: NaiveDate,
+    k_anonymity: u32,
+    limit: u32,
+) -> Result<Vec<String>, ServerError> {
+    let pattern = prefix_pattern(prefix);
+
+    sqlx::query_scalar!(
+        "SELECT term FROM SearchUsage WHERE resource = 'recipe' AND day >= ? AND term LIKE ? \
+        AND term IN (SELECT term FROM SearchClient WHERE resource = 'recipe' AND day >= ? AND term LIKE ? \
+        GROUP BY day, term HAVING COUNT(*) >= ?) \
+        GROUP BY term ORDER BY SUM(hits) DESC, term LIMIT ?",
+        from,
+        pattern,
+        from,
+        pattern,
+        k_anonymity,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case("tag:tiki", "id IN (SELECT cocktail_id FROM Tagged WHERE tag = ?)")]
+    #[case("name:sour OR rating:4", "(search_name LIKE ? OR rating >= ?)")]
+    #[case(
+        "category:easy -rating:4",
+        "(category = ? AND NOT COALESCE(rating >= ?, FALSE))"
+    )]
+    fn expressions_are_translated_into_conditions(#[case] expression: &str, #[case] sql: &str) {
+        let expression = SearchExpression::parse(expression).expect("Failed to parse");
+        let mut query = QueryBuilder::<MySql>::new("");
+        push_expression(&mut query, &expression);
+
+        assert_eq!(query.sql(), sql);
+    }
 }
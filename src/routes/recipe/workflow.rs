@@ -0,0 +1,159 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Publishing workflow of the recipes.
+//!
+//! # Description
+//!
+//! Recipes follow the workflow `draft → submitted → published → archived` (see [RecipeState]). Authors submit their
+//! drafts for publication, and the recipes of first-time clients, i.e. clients of the API with no published recipes,
+//! wait for the review of an administrator. Recipes of clients that already published some recipe skip the review.
+//! The same review applies to the recipes registered without `"state": "draft"`, which are submitted straight away
+//! (see [review_state]).
+
+use crate::{
+    authentication::{
+        access_denied_response, check_access, check_admin_access, key_client_id, AccessControl,
+        AuthData, Scope,
+    },
+    domain::{ApiError, ClientId, IdGenerator, RecipeState, RecipeTransition, ServerError},
+    routes::{
+        me::notifications::notify_recipe_approved,
+        recipe::{
+            utils::{get_recipe_state_from_db, has_published_recipes, set_recipe_state_in_db},
+            RecipeId,
+        },
+    },
+};
+use actix_web::{
+    post,
+    web::{Data, Path, Query},
+    HttpResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+
+/// State reached by the recipes that a client submits for publication.
+///
+/// # Description
+///
+/// Recipes of clients that already published some recipe are published straight away. Otherwise, they wait for the
+/// review of an administrator in the state `submitted`. The client is the one authenticated by the request, as the
+/// author of the recipe is given by the client itself.
+pub async fn review_state(
+    pool: &MySqlPool,
+    client_id: &ClientId,
+) -> Result<RecipeState, ServerError> {
+    if has_published_recipes(pool, client_id).await? {
+        Ok(RecipeState::Published)
+    } else {
+        Ok(RecipeState::Submitted)
+    }
+}
+
+/// Path of the transition resources, i.e. `/recipe/{id}/submit`.
+#[derive(Debug, Deserialize)]
+pub struct TransitionPath {
+    pub transition: RecipeTransition,
+}
+
+/// Move a recipe to another state of the publishing workflow (Restricted).
+///
+/// # Description
+///
+/// The following transitions are available:
+/// - `submit`: Send a draft to publication. The recipe is published straight away when the client has published
+///   recipes, otherwise it waits for the review of an administrator in the state `submitted`.
+/// - `withdraw`: Take a submitted recipe back to draft.
+/// - `publish` and `reject`: Review a submitted recipe, which is published or sent back to draft. These transitions
+///   are restricted to clients of the API with administration privileges.
+/// - `archive`: Withdraw a published recipe from the public catalogue.
+/// - `restore`: Take an archived recipe back to draft.
+///
+/// Administrators find the recipes waiting for review using `GET /recipe?state=submitted`.
+#[utoipa::path(
+    post,
+    path = "/recipe/{id}/{transition}",
+    tag = "Recipe",
+    params(
+        ("id" = String, Path, description = "ID or short ID of the recipe."),
+        ("transition" = RecipeTransition, Path, description = "Transition to apply."),
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (
+            status = 200,
+            description = "The recipe moved to a new state.",
+            content_type = "application/json",
+            example = json!({"id": "0192e8d9-36cf-7ce3-82ef-0a7c9b2deefe", "state": "submitted"}),
+        ),
+        (status = 400, description = "The given ID has an invalid format, or the transition doesn't exist."),
+        (status = 401, description = "The client has no access to this resource."),
+        (
+            status = 403,
+            description = "The API key doesn't grant the `write` scope, or the review needs administration privileges.",
+        ),
+        (status = 404, description = "The recipe doesn't exist."),
+        (status = 409, description = "The transition is not allowed from the current state of the recipe."),
+    )
+)]
+#[instrument(skip(pool, ids, token, access))]
+#[post("/{id}/{transition}")]
+pub async fn transition_recipe(
+    recipe_id: RecipeId,
+    path: Path<TransitionPath>,
+    pool: Data<MySqlPool>,
+    ids: Data<dyn IdGenerator>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    let transition = path.transition;
+
+    // Access control
+    let access = if transition.is_review() {
+        check_admin_access(&pool, &access, &token.api_key, Scope::Write).await
+    } else {
+        check_access(&pool, &access, &token.api_key, Scope::Write)
+            .await
+            .map(|_| ())
+    };
+    if let Err(e) = access {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let id = recipe_id.as_uuid();
+    let Some(current) = get_recipe_state_from_db(&pool, id).await? else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    let Some(next) = current.apply(transition) else {
+        info!("The recipe {recipe_id} can't {transition:?} from the state {current}");
+        return Ok(HttpResponse::Conflict().finish());
+    };
+
+    // Only the recipes of first-time clients need a review.
+    let next = if next == RecipeState::Submitted {
+        review_state(&pool, &key_client_id(&token.api_key)?).await?
+    } else {
+        next
+    };
+
+    if !set_recipe_state_in_db(&pool, id, current, next).await? {
+        info!("The state of the recipe {recipe_id} changed concurrently");
+        return Ok(HttpResponse::Conflict().finish());
+    }
+    info!("Recipe {recipe_id} moved from {current} to {next}");
+
+    if transition == RecipeTransition::Publish {
+        notify_recipe_approved(&pool, &**ids, id).await?;
+    }
+
+    Ok(HttpResponse::Ok().json(json!({"id": recipe_id.to_string(), "state": next})))
+}
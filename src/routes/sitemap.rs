@@ -0,0 +1,190 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Sitemaps of the public content of the DB.
+//!
+//! # Description
+//!
+//! Two endpoints are available:
+//! - [get_sitemap] serves the sitemap of the public recipes and authors. When the content doesn't fit in a single
+//!   sitemap, a sitemap index is served instead.
+//! - [get_sitemap_page] serves each page of a paginated sitemap.
+//!
+//! Only authors that share their profile, and recipes that are not pending moderation, are listed. Sitemaps are cached
+//! by the server, and rebuilt when a change of the public content is detected.
+
+use crate::{
+    domain::{ApiError, ServerError},
+    utils::sitemap::{render_sitemap_index, SitemapCache, SitemapEntry, SITEMAP_CONTENT_TYPE},
+};
+use actix_web::{
+    get,
+    web::{Data, Path},
+    HttpRequest, HttpResponse,
+};
+use sqlx::{MySqlPool, Row};
+use std::sync::Arc;
+use tracing::{error, info, instrument};
+
+/// Sitemap of the public content (Public).
+///
+/// # Description
+///
+/// Recipes and shareable authors are listed using the URLs of the frontend. When the content exceeds the limit of a
+/// single sitemap (50.000 URLs), a sitemap index is returned that links to each page (`/sitemap/{page}.xml`).
+#[utoipa::path(
+    get,
+    path = "/sitemap.xml",
+    tag = "Sitemap",
+    responses(
+        (
+            status = 200,
+            description = "The sitemap (or the sitemap index) of the public content.",
+            content_type = "application/xml",
+        ),
+    )
+)]
+#[instrument(skip(req, pool, cache))]
+#[get("/sitemap.xml")]
+pub async fn get_sitemap(
+    req: HttpRequest,
+    pool: Data<MySqlPool>,
+    cache: Data<SitemapCache>,
+) -> Result<HttpResponse, ApiError> {
+    let pages = sitemap_pages(&pool, &cache).await?;
+
+    let body = if pages.len() == 1 {
+        pages[0].clone()
+    } else {
+        let info = req.connection_info();
+        let base = format!(
+            "{}://{}{}",
+            info.scheme(),
+            info.host(),
+            req.path().trim_end_matches("/sitemap.xml")
+        );
+        render_sitemap_index(pages.len(), |page| format!("{base}/sitemap/{page}.xml"))
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(SITEMAP_CONTENT_TYPE)
+        .body(body))
+}
+
+/// Page of a paginated sitemap (Public).
+#[utoipa::path(
+    get,
+    path = "/sitemap/{page}.xml",
+    tag = "Sitemap",
+    params(("page" = usize, Path, description = "Number of the page (starting at 1).")),
+    responses(
+        (
+            status = 200,
+            description = "The requested page of the sitemap.",
+            content_type = "application/xml",
+        ),
+        (
+            status = 400,
+            description = "The number of the page has an invalid format.",
+        ),
+        (
+            status = 404,
+            description = "The requested page doesn't exist.",
+        ),
+    )
+)]
+#[instrument(skip(pool, cache))]
+#[get("/sitemap/{page}.xml")]
+pub async fn get_sitemap_page(
+    page: Path<usize>,
+    pool: Data<MySqlPool>,
+    cache: Data<SitemapCache>,
+) -> Result<HttpResponse, ApiError> {
+    let pages = sitemap_pages(&pool, &cache).await?;
+
+    match page.into_inner().checked_sub(1).and_then(|i| pages.get(i)) {
+        Some(page) => Ok(HttpResponse::Ok()
+            .content_type(SITEMAP_CONTENT_TYPE)
+            .body(page.clone())),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Retrieve the sitemap pages from the cache, or rebuild them when the public content changed.
+async fn sitemap_pages(
+    pool: &MySqlPool,
+    cache: &SitemapCache,
+) -> Result<Arc<Vec<String>>, ServerError> {
+    let fingerprint = content_fingerprint(pool).await?;
+
+    match cache.get(&fingerprint) {
+        Some(pages) => Ok(pages),
+        None => {
+            info!("The public content changed, rebuilding the sitemap");
+            let entries = public_entries(pool).await?;
+            Ok(cache.build(&fingerprint, &entries))
+        }
+    }
+}
+
+/// Build a fingerprint of the public content using the amount of entries and their latest modification.
+async fn content_fingerprint(pool: &MySqlPool) -> Result<String, ServerError> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM Cocktail WHERE state = 'published') AS recipes,
+            (SELECT COUNT(*) FROM ModerationQueue) AS pending_recipes,
+            (SELECT CAST(COALESCE(UNIX_TIMESTAMP(MAX(update_date)), 0) AS SIGNED) FROM Cocktail) AS recipes_modified,
+            (SELECT COUNT(*) FROM Author WHERE deleted_at IS NULL AND COALESCE(shareable, 1) = 1) AS authors,
+            (SELECT CAST(COALESCE(UNIX_TIMESTAMP(MAX(update_date)), 0) AS SIGNED) FROM Author) AS authors_modified
+        "#,
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    let value = |column: &str| row.try_get::<i64, _>(column).unwrap_or_default();
+
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        value("recipes"),
+        value("pending_recipes"),
+        value("recipes_modified"),
+        value("authors"),
+        value("authors_modified")
+    ))
+}
+
+/// Retrieve the entries of all the public recipes and authors.
+async fn public_entries(pool: &MySqlPool) -> Result<Vec<SitemapEntry>, ServerError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT CONCAT('recipe/', id) AS path, CAST(UNIX_TIMESTAMP(update_date) AS SIGNED) AS last_modified
+        FROM Cocktail WHERE state = 'published' AND id NOT IN (SELECT cocktail_id FROM ModerationQueue)
+        UNION ALL
+        SELECT CONCAT('author/', id) AS path, CAST(UNIX_TIMESTAMP(update_date) AS SIGNED) AS last_modified
+        FROM Author WHERE deleted_at IS NULL AND COALESCE(shareable, 1) = 1
+        ORDER BY path
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(rows
+        .iter()
+        .map(|row| SitemapEntry {
+            path: row.try_get("path").unwrap_or_default(),
+            last_modified: row.try_get("last_modified").unwrap_or_default(),
+        })
+        .collect())
+}
@@ -0,0 +1,355 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! History of the health of the server.
+//!
+//! # Description
+//!
+//! A periodic job records the health of the server (see [crate::jobs::record_health_history]): the status, the
+//! latency of the DB, the rate of requests and server errors, and the emails waiting to be sent. [get_status_history]
+//! serves the recorded snapshots publicly, so a status page can be built directly from the API, without external
+//! monitoring.
+
+use crate::domain::{ApiError, DataDomainError, ServerError};
+use actix_web::{
+    get,
+    http::header::{CacheControl, CacheDirective},
+    web::{Data, Query},
+    HttpResponse,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{MySqlPool, Row};
+use std::fmt;
+use tracing::{error, info, instrument};
+use utoipa::{IntoParams, ToSchema};
+
+/// Window of the history served when the client doesn't give one.
+pub const DEFAULT_HISTORY_WINDOW: &str = "24h";
+
+/// Maximum window of the history (days).
+pub const MAX_HISTORY_WINDOW_DAYS: i64 = 30;
+
+/// Time that the history can be cached by the clients (seconds).
+const HISTORY_MAX_AGE: u32 = 60;
+
+/// Status of the server when a snapshot was taken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotStatus {
+    /// The server was running smoothly.
+    Ok,
+    /// The server was within an announced maintenance window.
+    OnMaintenance,
+    /// The DB didn't answer.
+    DbDown,
+}
+
+impl fmt::Display for SnapshotStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotStatus::Ok => write!(f, "ok"),
+            SnapshotStatus::OnMaintenance => write!(f, "on_maintenance"),
+            SnapshotStatus::DbDown => write!(f, "db_down"),
+        }
+    }
+}
+
+impl TryFrom<&str> for SnapshotStatus {
+    type Error = DataDomainError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "ok" => Ok(SnapshotStatus::Ok),
+            "on_maintenance" => Ok(SnapshotStatus::OnMaintenance),
+            "db_down" => Ok(SnapshotStatus::DbDown),
+            _ => Err(DataDomainError::InvalidData),
+        }
+    }
+}
+
+/// Health of the server at a point in time.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct HealthSnapshot {
+    #[schema(value_type = String, example = "2025-09-11T08:55:00Z")]
+    pub taken_at: DateTime<Utc>,
+    pub status: SnapshotStatus,
+    /// Time taken by the DB to answer a ping (milliseconds). No value when the DB didn't answer.
+    #[schema(example = 1.8)]
+    pub db_latency_ms: Option<f64>,
+    /// Average amount of requests per second during the last 5 minutes.
+    #[schema(example = 0.4)]
+    pub request_rate: f64,
+    /// Ratio of the requests answered with a server error during the last 5 minutes, from 0 to 1.
+    #[schema(example = 0.0)]
+    pub error_rate: f64,
+    /// Notifications waiting to be sent by email. No value when the DB didn't answer.
+    #[schema(example = 0)]
+    pub mail_outbox: Option<u64>,
+}
+
+/// History of the health of the server within a window of time.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct StatusHistory {
+    #[schema(value_type = String, example = "2025-09-10T09:00:00Z")]
+    pub from: DateTime<Utc>,
+    #[schema(value_type = String, example = "2025-09-11T09:00:00Z")]
+    pub to: DateTime<Utc>,
+    /// Ratio of the snapshots of the window whose status is not `db_down`, from 0 to 1. No value when the window has
+    /// no snapshots.
+    #[schema(example = 0.998)]
+    pub availability: Option<f64>,
+    /// Snapshots of the window, oldest first.
+    pub snapshots: Vec<HealthSnapshot>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct HistoryQuery {
+    /// Window of the history, counted back from now. Given in hours (`24h`) or days (`7d`), 30 days at most, and
+    /// `24h` by default.
+    #[param(example = "24h")]
+    pub window: Option<String>,
+}
+
+/// Parse a window of the history, i.e. `24h` or `7d`.
+///
+/// # Description
+///
+/// `None` is returned when the format is not valid, the window is empty, or it exceeds [MAX_HISTORY_WINDOW_DAYS].
+pub fn parse_window(value: &str) -> Option<TimeDelta> {
+    let value = value.trim();
+    let unit = value.chars().last()?;
+    let amount = value[..value.len() - unit.len_utf8()].parse::<i64>().ok()?;
+    let window = match unit.to_ascii_lowercase() {
+        'h' => TimeDelta::try_hours(amount)?,
+        'd' => TimeDelta::try_days(amount)?,
+        _ => return None,
+    };
+
+    if window <= TimeDelta::zero() || window > TimeDelta::days(MAX_HISTORY_WINDOW_DAYS) {
+        None
+    } else {
+        Some(window)
+    }
+}
+
+/// Ratio of the snapshots whose status is not [SnapshotStatus::DbDown].
+pub fn availability(snapshots: &[HealthSnapshot]) -> Option<f64> {
+    if snapshots.is_empty() {
+        return None;
+    }
+
+    let up = snapshots
+        .iter()
+        .filter(|s| s.status != SnapshotStatus::DbDown)
+        .count();
+    Some(up as f64 / snapshots.len() as f64)
+}
+
+/// History of the health of the server (Public).
+///
+/// # Description
+///
+/// The snapshots of the health of the server taken within the given window are returned, oldest first, along with
+/// the availability of the server within the window. Snapshots are taken periodically (every 5 minutes by default),
+/// and kept for 30 days by default. The history can be cached for a minute.
+#[utoipa::path(
+    get,
+    path = "/status/history",
+    tag = "Maintenance",
+    params(HistoryQuery),
+    responses(
+        (
+            status = 200,
+            description = "The history of the health of the server.",
+            body = StatusHistory,
+            headers(
+                ("Cache-Control", description = "The history can be cached for a minute."),
+            )
+        ),
+        (status = 400, description = "The window is not valid."),
+    )
+)]
+#[instrument(skip(pool))]
+#[get("/status/history")]
+pub async fn get_status_history(
+    req: Query<HistoryQuery>,
+    pool: Data<MySqlPool>,
+) -> Result<HttpResponse, ApiError> {
+    let window = req.window.as_deref().unwrap_or(DEFAULT_HISTORY_WINDOW);
+    let Some(window) = parse_window(window) else {
+        info!("Invalid window of the history: {window}");
+        return Ok(HttpResponse::BadRequest().body(format!(
+            "The window shall be given in hours (24h) or days (7d), and span {MAX_HISTORY_WINDOW_DAYS} days at most"
+        )));
+    };
+
+    let to = Utc::now();
+    let from = to - window;
+    let snapshots = get_health_snapshots_from_db(&pool, from).await?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(HISTORY_MAX_AGE),
+        ]))
+        .json(StatusHistory {
+            from,
+            to,
+            availability: availability(&snapshots),
+            snapshots,
+        }))
+}
+
+/// Store a snapshot of the health of the server.
+#[instrument(skip(pool))]
+pub(crate) async fn store_health_snapshot_in_db(
+    pool: &MySqlPool,
+    snapshot: &HealthSnapshot,
+) -> Result<(), ServerError> {
+    sqlx::query(
+        "INSERT INTO HealthSnapshot \
+        (taken_at, status, db_latency_ms, request_rate, error_rate, mail_outbox) \
+        VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(snapshot.taken_at)
+    .bind(snapshot.status.to_string())
+    .bind(snapshot.db_latency_ms)
+    .bind(snapshot.request_rate)
+    .bind(snapshot.error_rate)
+    .bind(snapshot.mail_outbox)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
+
+/// Retrieve the snapshots taken since the given time, oldest first.
+#[instrument(skip(pool))]
+async fn get_health_snapshots_from_db(
+    pool: &MySqlPool,
+    from: DateTime<Utc>,
+) -> Result<Vec<HealthSnapshot>, ServerError> {
+    let rows = sqlx::query(
+        "SELECT taken_at, status, db_latency_ms, request_rate, error_rate, mail_outbox \
+        FROM HealthSnapshot WHERE taken_at >= ? ORDER BY taken_at, id",
+    )
+    .bind(from)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    rows.iter()
+        .map(|row| {
+            let status: String = row.try_get("status")?;
+            Ok(HealthSnapshot {
+                taken_at: row.try_get("taken_at")?,
+                status: SnapshotStatus::try_from(status.as_str())
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+                db_latency_ms: row.try_get("db_latency_ms")?,
+                request_rate: row.try_get("request_rate")?,
+                error_rate: row.try_get("error_rate")?,
+                mail_outbox: row.try_get("mail_outbox")?,
+            })
+        })
+        .collect::<Result<Vec<HealthSnapshot>, sqlx::Error>>()
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })
+}
+
+/// Delete the snapshots taken before the given time. The amount of deleted snapshots is returned.
+#[instrument(skip(pool))]
+pub(crate) async fn prune_health_snapshots_in_db(
+    pool: &MySqlPool,
+    before: DateTime<Utc>,
+) -> Result<u64, ServerError> {
+    let result = sqlx::query("DELETE FROM HealthSnapshot WHERE taken_at < ?")
+        .bind(before)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn snapshot(status: SnapshotStatus) -> HealthSnapshot {
+        HealthSnapshot {
+            taken_at: Utc::now(),
+            status,
+            db_latency_ms: None,
+            request_rate: 0.0,
+            error_rate: 0.0,
+            mail_outbox: None,
+        }
+    }
+
+    #[rstest]
+    #[case("24h", TimeDelta::hours(24))]
+    #[case("1h", TimeDelta::hours(1))]
+    #[case(" 7d ", TimeDelta::days(7))]
+    #[case("30D", TimeDelta::days(30))]
+    #[case("720h", TimeDelta::days(30))]
+    fn windows_are_parsed(#[case] value: &str, #[case] expected: TimeDelta) {
+        assert_eq!(parse_window(value), Some(expected));
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("h")]
+    #[case("24")]
+    #[case("0h")]
+    #[case("-1d")]
+    #[case("31d")]
+    #[case("2w")]
+    #[case("1.5h")]
+    #[case("9999999999999999d")]
+    fn invalid_windows_are_rejected(#[case] value: &str) {
+        assert_eq!(parse_window(value), None);
+    }
+
+    #[rstest]
+    fn availability_counts_the_db_downtime() {
+        assert_eq!(availability(&[]), None);
+        assert_eq!(
+            availability(&[
+                snapshot(SnapshotStatus::Ok),
+                snapshot(SnapshotStatus::OnMaintenance),
+                snapshot(SnapshotStatus::DbDown),
+                snapshot(SnapshotStatus::Ok),
+            ]),
+            Some(0.75)
+        );
+    }
+
+    #[rstest]
+    #[case(SnapshotStatus::Ok)]
+    #[case(SnapshotStatus::OnMaintenance)]
+    #[case(SnapshotStatus::DbDown)]
+    fn statuses_are_stored(#[case] status: SnapshotStatus) {
+        assert_eq!(
+            SnapshotStatus::try_from(status.to_string().as_str()).ok(),
+            Some(status)
+        );
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Management of the API keys of a client.
+//!
+//! # Description
+//!
+//! Clients can hold several API keys at the same time (up to [crate::authentication::MAX_KEYS_PER_CLIENT]), i.e.
+//! one per environment. Keys are told apart using a label given by the client. The key issued at the end of the token
+//! request process is labelled as `default`.
+//!
+//! Keys can also grant a reduced set of [Scope]s, i.e. a read-only key for a public website, and a write key for a
+//! CMS. New keys can only grant scopes that are granted by the key used to issue them. The scopes are enforced by
+//! [check_access], which is called by every restricted endpoint.
+//!
+//! These endpoints require to authenticate using an active key of the client that grants the `keys` scope. New keys
+//! are shown only once, when they are issued, and only their hash is stored in the DB.
+
+use crate::{
+    authentication::{
+        access_denied_response, check_access, delete_api_key, format_api_key,
+        generate_new_token_hash, generate_token, get_api_keys, key_client_id, store_api_key,
+        AccessControl, AuthData, ClientKey, Scope, API_KEY_EXPIRY,
+    },
+    domain::{
+        sanitize::{sanitize_text, SanitizeLevel},
+        ApiError,
+    },
+};
+use actix_web::{
+    delete, get, post,
+    web::{Data, Json, Path, Query},
+    HttpResponse,
+};
+use anyhow::Context;
+use chrono::Utc;
+use secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+
+/// Maximum length of the label of a key.
+pub const MAX_KEY_LABEL_LENGTH: usize = 40;
+
+/// Payload to issue a new API key.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct KeyRequest {
+    /// Label of the key, i.e. `staging`.
+    #[schema(example = "staging")]
+    pub label: String,
+    /// Scopes granted by the key. When omitted, the key grants the same scopes as the key used to issue it.
+    #[schema(example = json!(["read"]))]
+    pub scopes: Option<Vec<Scope>>,
+}
+
+/// Issued API key.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct NewClientKey {
+    #[serde(flatten)]
+    pub key: ClientKey,
+    /// API key to access the restricted endpoints. It is shown only once.
+    #[schema(example = "0191e13b5ab778f1:3.B1Ue1ZsT0wsBNmp7GaCs7Ujw8")]
+    pub api_key: String,
+}
+
+/// List the API keys of the client.
+///
+/// # Description
+///
+/// The tokens of the keys are not included, only their ID, label and validity.
+#[utoipa::path(
+    get,
+    path = "/token/keys",
+    tag = "Token",
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 200, description = "The keys of the client, newest first.", body = [ClientKey]),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `keys` scope."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[get("")]
+pub async fn get_keys(
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Keys).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let client_id = key_client_id(&token.api_key)?;
+
+    Ok(HttpResponse::Ok().json(get_api_keys(&pool, &client_id).await?))
+}
+
+/// Issue a new API key for the client.
+///
+/// # Description
+///
+/// The new key is valid for the same time as the key issued at the end of the token request process. A code **409**
+/// is returned when the client already holds [crate::authentication::MAX_KEYS_PER_CLIENT] active keys. Concurrent
+/// requests of the same client are serialized, so the limit can't be exceeded.
+///
+/// The scopes of the new key must be a subset of the scopes of the key used to issue it, otherwise a code **403** is
+/// returned.
+#[utoipa::path(
+    post,
+    path = "/token/keys",
+    tag = "Token",
+    request_body(content = KeyRequest, example = json!({"label": "website", "scopes": ["read"]})),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 201, description = "The new key. The API key is shown only once.", body = NewClientKey),
+        (status = 400, description = "The label is empty or too long, or the list of scopes is empty."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `keys` scope, or some of the requested scopes."),
+        (status = 409, description = "The client holds the maximum amount of active keys."),
+    )
+)]
+#[instrument(skip(pool, token, req, access, sanitize_level))]
+#[post("")]
+pub async fn post_key(
+    req: Json<KeyRequest>,
+    pool: Data<MySqlPool>,
+    sanitize_level: Data<SanitizeLevel>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    let granted_scopes = match check_access(&pool, &access, &token.api_key, Scope::Keys).await {
+        Ok(scopes) => scopes,
+        Err(e) => return access_denied_response(e),
+    };
+    debug!("Access granted");
+
+    let label = sanitize_text(req.label.trim(), **sanitize_level);
+    if label.is_empty() || label.chars().count() > MAX_KEY_LABEL_LENGTH {
+        info!("The given label is invalid");
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+
+    let mut scopes = req.scopes.clone().unwrap_or_else(|| granted_scopes.clone());
+    scopes.sort();
+    scopes.dedup();
+    if scopes.is_empty() {
+        info!("The new key must grant at least one scope");
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+    if scopes.iter().any(|s| !granted_scopes.contains(s)) {
+        info!("The requested scopes exceed the scopes of the given API key");
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+
+    let client_id = key_client_id(&token.api_key)?;
+    let token = SecretString::from(generate_token());
+    let token_hashed = generate_new_token_hash(token.clone())?;
+    let Some(id) = store_api_key(
+        &pool,
+        &token_hashed,
+        &label,
+        &scopes,
+        API_KEY_EXPIRY,
+        &client_id,
+    )
+    .await?
+    else {
+        info!("The client ({client_id}) holds the maximum amount of keys");
+        return Ok(HttpResponse::Conflict().finish());
+    };
+    info!("New API key ({id}) issued to the client ({client_id})");
+
+    let key = get_api_keys(&pool, &client_id)
+        .await?
+        .into_iter()
+        .find(|k| k.id == id)
+        .context("The new key was not found in the DB")?;
+
+    Ok(HttpResponse::Created().json(NewClientKey {
+        key,
+        api_key: format_api_key(&client_id, id, &token),
+    }))
+}
+
+/// Revoke an API key of the client.
+///
+/// # Description
+///
+/// The key used to authenticate the request can be revoked as well. However, the last active key of the client can't
+/// be revoked, as the client would lose the access to the API. A code **409** is returned in such case.
+#[utoipa::path(
+    delete,
+    path = "/token/keys/{id}",
+    tag = "Token",
+    params(("id" = u64, Path, description = "ID of the key.")),
+    security(
+        ("api_key" = [])
+    ),
+    responses(
+        (status = 204, description = "The key was revoked."),
+        (status = 400, description = "The given ID is not a number."),
+        (status = 401, description = "The client has no access to this resource."),
+        (status = 403, description = "The given API key doesn't grant the `keys` scope."),
+        (status = 404, description = "The client has no key identified by the given ID."),
+        (status = 409, description = "The key is the last active key of the client."),
+    )
+)]
+#[instrument(skip(pool, token, access))]
+#[delete("/{id}")]
+pub async fn delete_key(
+    path: Path<u64>,
+    pool: Data<MySqlPool>,
+    access: Data<AccessControl>,
+    token: Query<AuthData>,
+) -> Result<HttpResponse, ApiError> {
+    // Access control
+    if let Err(e) = check_access(&pool, &access, &token.api_key, Scope::Keys).await {
+        return access_denied_response(e);
+    }
+    debug!("Access granted");
+
+    let id = path.into_inner();
+    let client_id = key_client_id(&token.api_key)?;
+    let keys = get_api_keys(&pool, &client_id).await?;
+
+    let Some(key) = keys.iter().find(|k| k.id == id) else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    let now = Utc::now();
+    if key.valid_until > now && keys.iter().filter(|k| k.valid_until > now).count() == 1 {
+        info!("Attempt to revoke the last active key of the client ({client_id})");
+        return Ok(HttpResponse::Conflict().finish());
+    }
+
+    if delete_api_key(&pool, access.cache(), &client_id, id).await? {
+        info!("API key ({id}) of the client ({client_id}) revoked");
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Ok(HttpResponse::NotFound().finish())
+    }
+}
@@ -18,35 +18,51 @@
 //! Anyone interested on using the restricted endpoints needs to request an API token. To ease such process, a
 //! specific endpoint is enabled in the backend that serves some simple HTML pages: `/token/request`. That endpoint
 //! is accessible via a web browser, and includes a simple form that a client must fill before issuing a token request.
+//! The pages are rendered using the branding of the instance (see [crate::web]).
 //!
 //! The request gets registered in the system, but partially, until the client verifies the used email account. The
-//! backend sends an email after registering a new request with a validation link that will be available for a day.
-//! The client needs to visit such URL in order to complete the request process because during the validation process,
-//! the real API token gets generated. It is shown only once to the client, and the hash gets stored into the DB. If
-//! the client fails to complete the validation process, or looses the token, the process needs to be restarted.
+//! backend sends an email after registering a new request with a validation link that will be available for a day
+//! ([VALIDATION_LINK_EXPIRY]). The client needs to visit such URL in order to complete the request process because
+//! during the validation process, the real API token gets generated. It is shown only once to the client, and the hash
+//! gets stored into the DB. If the client fails to complete the validation process, or looses the token, the process
+//! needs to be restarted.
+//!
+//! As the API tokens, validation tokens are only stored as hashes. Validation links can be used only once. Expired or
+//! already used links are answered with a code **410**. Clients that didn't validate their email yet can request a
+//! new link filling the form again with the same email. Issuing a new link expires the previous ones.
+//!
+//! Once the access is granted, clients can issue more keys for the same account (see [crate::routes::token::keys]).
 //!
 //! Once the email gets validated, the request is fully registered and sent to evaluation. The evaluation process is
 //! manual and involves the system administrator. The result of the evaluation is notified via email to the client. If
 //! the request gets approved, the client is ready to start using the restricted endpoints using the token that was
 //! given at the end of the validation process.
+//!
+//! These endpoints are public, so the amount of requests per IP is limited (see
+//! [crate::configuration::ThrottlingSettings]). Clients that exceed the limit receive a code **429**.
 
 use crate::{
     authentication::*,
-    domain::{auth::TokenRequestData, ClientId, DataDomainError, ServerError},
-    utils::mailing::{notify_pending_req, send_confirmation_email},
+    domain::{auth::TokenRequestData, ApiError, ClientId, DataDomainError, ServerError},
+    utils::mailing::{
+        notify_pending_req, register_email_attempt, send_confirmation_email, EmailKind,
+        EmailSender, MailCorrelation,
+    },
+    web::{html_response, message_page, secret_token_page, token_request_page, Theme},
 };
 use actix_web::{
-    get, http::header::ContentType, post, web, web::Data, web::Form, HttpRequest, HttpResponse,
-    Responder,
+    get, http::StatusCode, post, web, web::Data, web::Form, HttpRequest, HttpResponse, Responder,
 };
 use anyhow::Context;
-use chrono::{DateTime, Local, TimeDelta};
-use mailjet_client::MailjetClient;
+use chrono::TimeDelta;
 use secrecy::{ExposeSecret, SecretString};
 use serde::Deserialize;
 use sqlx::{Executor, MySql, MySqlPool, Transaction};
-use std::{error::Error, str::FromStr};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+use tracing_actix_web::RequestId;
+
+/// Time that a validation link is available since it is sent to the client.
+pub const VALIDATION_LINK_EXPIRY: TimeDelta = TimeDelta::days(1);
 
 /// Payload of the token validation POST.
 #[derive(Deserialize, Debug)]
@@ -61,11 +77,17 @@ struct TokenValidationData {
 ///
 /// This endpoint offers a simple HTML form that allows clients interested in accessing the restricted endpoints to
 /// request an API token.
+#[utoipa::path(
+    get,
+    path = "/token/request",
+    tag = "Token",
+    responses(
+        (status = 200, description = "HTML form to request an API token.", content_type = "text/html"),
+    )
+)]
 #[get("/request")]
-pub async fn token_req_get() -> impl Responder {
-    HttpResponse::Ok()
-        .content_type(ContentType::html())
-        .body(include_str!("../../../static/token_request.html"))
+pub async fn token_req_get(theme: Data<Theme>) -> impl Responder {
+    html_response(StatusCode::OK, token_request_page(&theme))
 }
 
 /// POST for the API's /token/request endpoint.
@@ -74,52 +96,71 @@ pub async fn token_req_get() -> impl Responder {
 ///
 /// Once a client fills the requested data, a confirmation email is sent to the given email address. If the email gets
 /// confirmed, the request gets actually registered in the system, and waits until the sysadmin approves or rejects it.
-#[tracing::instrument(skip(req, form, pool, mail_client))]
+#[utoipa::path(
+    post,
+    path = "/token/request",
+    tag = "Token",
+    request_body(content = TokenRequestData, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 202, description = "A confirmation email was sent to the given address.", content_type = "text/html"),
+        (status = 400, description = "The form is not valid."),
+        (status = 406, description = "The email is already registered.", content_type = "text/html"),
+    )
+)]
+#[tracing::instrument(skip(req, form, pool, mail_client, theme, request_id))]
 #[post("/request")]
 pub async fn token_req_post(
     req: HttpRequest,
     form: Form<TokenRequestData>,
     pool: Data<MySqlPool>,
-    mail_client: Data<MailjetClient>,
-) -> Result<HttpResponse, Box<dyn Error>> {
+    mail_client: Data<dyn EmailSender>,
+    theme: Data<Theme>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
     info!("An API token was requested by {}", form.email());
 
     // Check if the client is already registered in the DB.
-    match check_existing_user(&pool, form.email()).await {
+    let client_id = match check_existing_user(&pool, form.email()).await {
         Ok(id) => {
-            info!("A client ({id}) is already registered with the given email");
-            return Ok(HttpResponse::NotAcceptable().body(format!(
-                include_str!("../../../static/message_template.html"),
-                "The email is already registered in the system. Please, contact the sysadmin if you have any problem."
-            )));
+            if is_client_validated(&pool, &id).await? {
+                info!("A client ({id}) is already registered with the given email");
+                return Ok(html_response(
+                    StatusCode::NOT_ACCEPTABLE,
+                    message_page(&theme, "The email is already registered in the system. Please, contact the sysadmin if you have any problem."),
+                ));
+            }
+            info!(
+                "The client ({id}) didn't validate the email yet, a new validation link is issued"
+            );
+            Some(id)
         }
-        Err(e) => match e.downcast_ref() {
-            Some(DataDomainError::InvalidEmail) => {
-                debug!("The given email was not registered in the DB")
+        Err(e) => match ApiError::from(e) {
+            ApiError::Domain(DataDomainError::InvalidEmail) => {
+                debug!("The given email was not registered in the DB");
+                None
             }
-            _ => return Err(e),
+            e => return Err(e),
         },
-    }
+    };
 
-    // It's a new client, let's register the new request.
     let mut transaction = pool.begin().await.map_err(|e| {
         error!("{e}");
         ServerError::DbError
     })?;
-    let client_id = register_new_request(&mut transaction, &form)
-        .await
-        .map_err(|e| {
-            error!("{e}");
-            ServerError::DbError
-        })?;
+    // It's a new client, let's register the new request.
+    let client_id = match client_id {
+        Some(id) => id,
+        None => register_new_request(&mut transaction, &form)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?,
+    };
     let token = SecretString::from(generate_token());
-    // Store the temporal validation token with an expiry of 1 day.
-    store_validation_token(&mut transaction, &token, TimeDelta::days(1), &client_id)
-        .await
-        .map_err(|e| {
-            error!("{e}");
-            ServerError::DbError
-        })?;
+    // Store the temporal validation token. Previous links sent to the client are expired.
+    store_email_validation_token(&mut transaction, &token, VALIDATION_LINK_EXPIRY, &client_id)
+        .await?;
     transaction.commit().await.map_err(|e| {
         error!("{e}");
         ServerError::DbError
@@ -134,66 +175,148 @@ pub async fn token_req_post(
     );
 
     // Finally, send the confirmation email to the recipient.
-    send_confirmation_email(mail_client, &link, form.email()).await?;
+    let correlation = MailCorrelation::new(*request_id, &client_id);
+    let outcome = send_confirmation_email(mail_client, &link, form.email(), &correlation).await;
+    store_email_attempt(
+        &pool,
+        &correlation,
+        EmailKind::Confirmation,
+        form.email(),
+        &outcome,
+    )
+    .await;
+    outcome?;
 
-    Ok(HttpResponse::Accepted().body(format!(
-        include_str!("../../../static/message_template.html"),
-        "<h3>Please, check your email's inbox and confirm your request.</h3>"
-    )))
+    Ok(html_response(
+        StatusCode::ACCEPTED,
+        message_page(
+            &theme,
+            "Please, check your email's inbox and confirm your request.",
+        ),
+    ))
 }
 
 /// Endpoint to validate a token request sent to an email account.
 ///
 /// # Description
 ///
-/// This endpoint receives the token that was sent when a client registered a new request using `/token/request`, and
-/// if the token matches the stored in the DB, the client receives a new token that is shown only once and stored in
+/// This endpoint receives the token that was sent when a client registered a new request using `/token/request`, and if
+/// the token matches the hash stored in the DB, the client receives a new token that is shown only once and stored in
 /// the DB (replacing the previous one). This way, only the client knows the token.
-#[tracing::instrument(skip(req, pool, mail_client))]
+///
+/// Validation tokens are consumed when used. A code **410** is returned when the link expired or it was already used,
+/// and a code **404** when the link doesn't match any issued token.
+#[utoipa::path(
+    get,
+    path = "/token/request/validate",
+    tag = "Token",
+    params(
+        ("email" = String, Query, description = "Email used to request the token."),
+        ("token" = String, Query, description = "Validation token sent to the email."),
+    ),
+    responses(
+        (status = 202, description = "The request was validated. The page shows the API token once.", content_type = "text/html"),
+        (status = 404, description = "The link doesn't match any issued token.", content_type = "text/html"),
+        (status = 410, description = "The link expired or it was already used.", content_type = "text/html"),
+    )
+)]
+#[tracing::instrument(skip(req, pool, mail_client, theme, request_id))]
 #[get("/request/validate")]
 pub async fn req_validation(
     req: web::Query<TokenValidationData>,
     pool: Data<MySqlPool>,
-    mail_client: Data<MailjetClient>,
-) -> Result<HttpResponse, Box<dyn Error>> {
-    // First, check if the token is valid and received in time.
-    let client_id = check_email_validation(&pool, &req.token, &req.email).await?;
-
-    // Once here, we are about to complete the validation process and deliver a real token to the client, let's
-    // delete the temporal token first.
-    delete_token(&pool, req.token.clone()).await?;
-
+    mail_client: Data<dyn EmailSender>,
+    theme: Data<Theme>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
     let mut transaction = pool
         .begin()
         .await
         .context("Failed to acquire a connection from the pool")?;
 
+    // First, check if the token is valid and received in time. The token is consumed within the same transaction that
+    // stores the new API token, so a link can't be used twice.
+    let client_id =
+        match consume_email_validation_token(&mut transaction, &req.token, &req.email).await {
+            Ok(ValidationTokenStatus::Valid(client_id)) => client_id,
+            Ok(ValidationTokenStatus::Expired) => {
+                return Ok(html_response(
+                    StatusCode::GONE,
+                    message_page(&theme, "The validation link expired. Please, fill the request form again using the same email to receive a new link."),
+                ))
+            }
+            Ok(ValidationTokenStatus::Used) => {
+                return Ok(html_response(
+                    StatusCode::GONE,
+                    message_page(&theme, "The validation link was already used."),
+                ))
+            }
+            Err(e) => {
+                return match ApiError::from(e) {
+                    ApiError::Domain(DataDomainError::InvalidAccessCredentials) => {
+                        Ok(html_response(
+                            StatusCode::NOT_FOUND,
+                            message_page(&theme, "The validation link is not valid."),
+                        ))
+                    }
+                    e => Err(e),
+                }
+            }
+        };
+
     // Generate a new token using the client's ID and a new random token.
     let token = SecretString::from(generate_token());
-    // Show this to the client. It will be gone for ever.
-    let token_string = format!("{}:{}", client_id, token.expose_secret());
     // Hash the token part, as that is what we'll store in the DB.
-    let token_hashed = generate_new_token_hash(token)?;
+    let token_hashed = generate_new_token_hash(token.clone())?;
     // Store the new token.
-    store_validation_token(
-        &mut transaction,
-        &token_hashed,
-        TimeDelta::days(100),
-        &client_id,
-    )
-    .await?;
+    let key_id =
+        store_validation_token(&mut transaction, &token_hashed, API_KEY_EXPIRY, &client_id).await?;
+    // Show this to the client. It will be gone for ever.
+    let token_string = format_api_key(&client_id, key_id, &token);
     validate_client_account(&mut transaction, &client_id).await?;
     transaction
         .commit()
         .await
         .context("Failed to commit SQL transaction to store a new client's access token")?;
 
-    notify_pending_req(mail_client, &client_id).await?;
+    let correlation = MailCorrelation::new(*request_id, &client_id);
+    let admin_address = mail_client.sender_address().unwrap_or_default().to_owned();
+    let outcome = notify_pending_req(mail_client, &client_id, &correlation).await;
+    store_email_attempt(
+        &pool,
+        &correlation,
+        EmailKind::AdminNotification,
+        &admin_address,
+        &outcome,
+    )
+    .await;
+    outcome?;
+
+    Ok(html_response(
+        StatusCode::ACCEPTED,
+        secret_token_page(&theme, &token_string),
+    ))
+}
 
-    Ok(HttpResponse::Accepted().body(format!(
-        include_str!("../../../static/secret_token.html"),
-        token_string
-    )))
+/// Store an attempt to send an email.
+///
+/// # Description
+///
+/// The outcome of the attempt is already known, so a failure to store it is only logged rather than failing the
+/// request.
+async fn store_email_attempt(
+    pool: &MySqlPool,
+    correlation: &MailCorrelation,
+    kind: EmailKind,
+    recipient: &str,
+    outcome: &Result<Option<String>, ServerError>,
+) {
+    if let Err(e) = register_email_attempt(pool, correlation, kind, recipient, outcome).await {
+        warn!(
+            "Failed to store the {kind} email sent to {}: {e}",
+            correlation.client_id
+        );
+    }
 }
 
 /// Register a new request in the DB.
@@ -221,63 +344,3 @@ async fn register_new_request(
 
     Ok(id)
 }
-
-// Validate a pair email-token
-#[tracing::instrument(
-    name = "Validate a token request"
-    skip(pool, token, client_email)
-)]
-async fn check_email_validation(
-    pool: &MySqlPool,
-    token: &SecretString,
-    client_email: &str,
-) -> Result<ClientId, Box<dyn Error>> {
-    // First, retrieve the credentials for the client using the email.
-    let query = sqlx::query!(
-        r#"
-        SELECT at.client_id, at.valid_until, at.api_token
-        FROM ApiUser au natural join ApiToken at
-        WHERE au.email = ?
-        "#,
-        client_email
-    )
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| {
-        error!("{e}");
-        ServerError::DbError
-    })?;
-
-    let record = match query {
-        Some(record) => record,
-        None => {
-            info!("The given email is not registered in the DB");
-            return Err(Box::new(DataDomainError::InvalidEmail));
-        }
-    };
-
-    // Given token does not match the saved one, reject the validation process.
-    if record.api_token != token.expose_secret() {
-        info!(
-            "The given access token for the client {} is not valid",
-            &record.client_id
-        );
-        Err(Box::new(DataDomainError::InvalidAccessCredentials))
-    } else {
-        // Ensure the validation took place within the valid time frame.
-        let valid_until = match record.valid_until.to_string().parse::<DateTime<Local>>() {
-            Ok(d) => d,
-            Err(e) => {
-                error!("Failed to read valid_until date from the DB: {e}");
-                return Err(Box::new(ServerError::DbError));
-            }
-        };
-        if (valid_until - Local::now()) < TimeDelta::days(1) {
-            info!("Validation received in time");
-            Ok(ClientId::from_str(&record.client_id)
-                .expect("Failed to parse ClientId from DB client's ID"))
-        } else {
-            Err(Box::new(ServerError::DbError))
-        }
-    }
-}
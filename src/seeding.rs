@@ -0,0 +1,290 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Seeding of the demo dataset.
+//!
+//! # Description
+//!
+//! A fresh DB contains no data at all, which is not very helpful when developing a client of the API or when running a
+//! demo deployment. This module loads a curated dataset of classic cocktails, their ingredients and a couple of demo
+//! authors. The dataset is described using a YAML file that is embedded in the crate.
+//!
+//! The binary seeds the dataset when it is launched with the argument `seed-dev-data`:
+//!
+//! ```bash
+//! $ cargo run -- seed-dev-data
+//! ```
+//!
+//! The seeding is idempotent: ingredients are matched by name, authors by email and recipes by name and owner, so
+//! entries that were seeded by a previous run are not duplicated.
+
+use crate::{
+    domain::{
+        Author, AuthorEmailPolicy, Equipment, IdGenerator, Recipe, RecipeContains, ServerError, Tag,
+    },
+    routes::recipe::register_new_recipe,
+    routes::{
+        author::utils::{register_new_author, AuthorRegistration},
+        ingredient::post::insert_ingredient,
+    },
+    Ingredient,
+};
+use serde::Deserialize;
+use sqlx::{MySqlPool, Row};
+use std::collections::HashMap;
+use tracing::{debug, error, info, instrument};
+use uuid::Uuid;
+
+const DEMO_DATA: &str = include_str!("seeding/demo_data.yml");
+
+/// Content of the demo dataset.
+#[derive(Debug, Deserialize)]
+pub struct DemoDataset {
+    pub authors: Vec<Author>,
+    pub ingredients: Vec<Ingredient>,
+    pub recipes: Vec<DemoRecipe>,
+}
+
+/// Recipe of the demo dataset.
+///
+/// # Description
+///
+/// Ingredients are referenced by name and the owner by the email of an author of the dataset, as the IDs are not
+/// known until the entries are seeded.
+#[derive(Debug, Deserialize)]
+pub struct DemoRecipe {
+    pub name: String,
+    pub owner: String,
+    pub description: String,
+    pub category: String,
+    pub author_tags: Vec<String>,
+    pub tags: Vec<String>,
+    pub ingredients: Vec<DemoIngredient>,
+    pub steps: Vec<String>,
+    pub equipment: Vec<Equipment>,
+    pub prep_time_minutes: u16,
+}
+
+/// Ingredient of a [DemoRecipe], i.e. `{ ingredient: "Gin", amount: "30 ml" }`.
+#[derive(Debug, Deserialize)]
+pub struct DemoIngredient {
+    pub ingredient: String,
+    pub amount: String,
+}
+
+/// Amount of entries added to the DB by [seed_demo_data].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SeedSummary {
+    pub authors: usize,
+    pub ingredients: usize,
+    pub recipes: usize,
+}
+
+impl DemoDataset {
+    /// Load the embedded demo dataset.
+    pub fn load() -> Result<Self, anyhow::Error> {
+        Ok(serde_yml::from_str(DEMO_DATA)?)
+    }
+
+    /// Build a [Recipe] out of a [DemoRecipe].
+    ///
+    /// # Description
+    ///
+    /// `ingredients` maps the names of the ingredients to their IDs, and `owner` is the ID of the author of the
+    /// recipe.
+    pub fn build_recipe(
+        recipe: &DemoRecipe,
+        ingredients: &HashMap<String, Uuid>,
+        owner: &str,
+    ) -> Result<Recipe, anyhow::Error> {
+        let mut contents = Vec::new();
+
+        for item in recipe.ingredients.iter() {
+            let ingredient_id = *ingredients.get(&item.ingredient).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown ingredient {} in the recipe {}",
+                    item.ingredient,
+                    recipe.name
+                )
+            })?;
+            let (quantity, unit) = RecipeContains::parse_amount(&item.amount)?;
+            contents.push(RecipeContains {
+                quantity,
+                unit,
+                ingredient_id,
+            });
+        }
+
+        let author_tags = parse_tags(&recipe.author_tags)?;
+        let tags = parse_tags(&recipe.tags)?;
+        let steps = recipe.steps.iter().map(String::as_str).collect::<Vec<_>>();
+
+        Ok(Recipe::new(
+            None,
+            &recipe.name,
+            None,
+            Some(&author_tags),
+            Some(&tags),
+            &recipe.category,
+            Some(&recipe.description),
+            None,
+            &contents,
+            &steps,
+            Some(&recipe.equipment),
+            Some(recipe.prep_time_minutes),
+            Some(owner),
+        )?)
+    }
+}
+
+fn parse_tags(tags: &[String]) -> Result<Vec<Tag>, anyhow::Error> {
+    tags.iter()
+        .map(|t| Tag::new(t).map_err(|_| anyhow::anyhow!("Invalid tag {t} in the demo dataset")))
+        .collect()
+}
+
+/// Seed the demo dataset in the DB.
+///
+/// # Description
+///
+/// Entries that already exist in the DB are reused rather than inserted again, see the module's documentation.
+#[instrument(skip(pool, ids))]
+pub async fn seed_demo_data(
+    pool: &MySqlPool,
+    ids: &dyn IdGenerator,
+) -> Result<SeedSummary, anyhow::Error> {
+    let dataset = DemoDataset::load()?;
+    let mut summary = SeedSummary::default();
+
+    let mut authors = HashMap::new();
+    for author in dataset.authors.iter() {
+        let email = author.email().unwrap_or_default().to_owned();
+        let id = match find_id(pool, "SELECT `id` FROM `Author` WHERE `email` = ?", &email).await? {
+            Some(id) => id,
+            None => {
+                summary.authors += 1;
+                // Every demo author has its own email.
+                match register_new_author(pool, ids, author, AuthorEmailPolicy::default()).await? {
+                    AuthorRegistration::Registered(id) => id.to_string(),
+                    AuthorRegistration::EmailInUse(_) => {
+                        anyhow::bail!("The email {email} is registered by another author")
+                    }
+                }
+            }
+        };
+        debug!("Demo author {email} -> {id}");
+        authors.insert(email, id);
+    }
+
+    let mut ingredients = HashMap::new();
+    for ingredient in dataset.ingredients.iter() {
+        let name = ingredient.name().to_owned();
+        let id = match find_id(
+            pool,
+            "SELECT `id` FROM `Ingredient` WHERE `name` = ?",
+            &name,
+        )
+        .await?
+        {
+            Some(id) => Uuid::parse_str(&id)?,
+            None => {
+                summary.ingredients += 1;
+                insert_ingredient(pool, ids, ingredient.clone()).await?
+            }
+        };
+        ingredients.insert(name, id);
+    }
+
+    for recipe in dataset.recipes.iter() {
+        let owner = authors.get(&recipe.owner).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown owner {} of the recipe {}",
+                recipe.owner,
+                recipe.name
+            )
+        })?;
+
+        let exists = sqlx::query("SELECT `id` FROM `Cocktail` WHERE `name` = ? AND `owner` = ?")
+            .bind(&recipe.name)
+            .bind(owner)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                error!("{e}");
+                ServerError::DbError
+            })?
+            .is_some();
+
+        if exists {
+            debug!("The recipe {} was already seeded", recipe.name);
+            continue;
+        }
+
+        let recipe = DemoDataset::build_recipe(recipe, &ingredients, owner)?;
+        register_new_recipe(pool, ids, &recipe, None)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to seed the recipe {}: {e}", recipe.name()))?;
+        summary.recipes += 1;
+    }
+
+    info!(
+        "Demo dataset seeded: {} authors, {} ingredients and {} recipes added",
+        summary.authors, summary.ingredients, summary.recipes
+    );
+
+    Ok(summary)
+}
+
+async fn find_id(
+    pool: &MySqlPool,
+    query: &str,
+    value: &str,
+) -> Result<Option<String>, ServerError> {
+    let row = sqlx::query(query)
+        .bind(value)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    Ok(row.map(|r| r.get::<String, _>("id")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn demo_dataset_is_consistent() {
+        let dataset = DemoDataset::load().expect("Failed to parse the demo dataset");
+        assert!(!dataset.recipes.is_empty());
+
+        let ingredients = dataset
+            .ingredients
+            .iter()
+            .map(|i| (i.name().to_owned(), Uuid::now_v7()))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(ingredients.len(), dataset.ingredients.len());
+        let owner = Uuid::now_v7().to_string();
+
+        for recipe in dataset.recipes.iter() {
+            assert!(
+                dataset
+                    .authors
+                    .iter()
+                    .any(|a| a.email() == Some(recipe.owner.as_str())),
+                "Unknown owner of {}",
+                recipe.name
+            );
+            DemoDataset::build_recipe(recipe, &ingredients, &owner)
+                .unwrap_or_else(|e| panic!("Invalid recipe {}: {e}", recipe.name));
+        }
+    }
+}
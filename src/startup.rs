@@ -7,24 +7,89 @@
 //! Module that includes helper functions to start the **La Coctelera** application.
 
 use crate::{
-    configuration::{DataBaseSettings, Settings},
-    routes::{self, health},
+    authentication::AccessControl,
+    configuration::{
+        ApplicationSettings, DataBaseSettings, EmailClientSettings, LoadSheddingSettings,
+        OutboxSettings, ServerSettings, Settings, ThrottlingSettings,
+    },
+    domain::{
+        collation::NameCollations,
+        path_config,
+        recipe::RecipeLimits,
+        sanitize::{set_sanitize_level, SanitizeLevel},
+        screening::Screener,
+        AuthorEmailPolicy, IdGenerator, RecipeLicense,
+    },
+    jobs::{
+        dispatch_events, flush_usage_analytics, record_health_history, run_notifications,
+        send_due_digests, spawn_periodic_job,
+    },
+    routes::{
+        self,
+        docs::{audit_openapi, OpenApiAudit, OpenApiDocument, RegisteredHandlers, OPENAPI_PATH},
+        health,
+        recipe::assign_default_license_in_db,
+    },
+    utils::{
+        assets::AssetStore,
+        backup::BackupStore,
+        events::{build_event_sinks, EventSink},
+        http::{
+            CachePolicy, ClientIpRootSpan, InFlight, LoadShed, MaintenanceNotice, MaxPageSize,
+            Preconditions, ReadOnly, RequestMetrics, Throttle, TrustedProxies,
+        },
+        landing::{ActivityCache, LandingCache},
+        mailing::{EmailSender, MailjetSender},
+        media::{MediaStore, MAX_IMAGE_SIZE},
+        metrics::{analytics, metrics, SuggestionCache},
+        pdf::PdfCache,
+        sitemap::SitemapCache,
+    },
+    web::{error_pages, server_error_pages, Theme},
     ApiDoc,
 };
 use actix_cors::Cors;
-use actix_files as fs;
-use actix_web::{dev::Server, http, web, App, HttpServer};
-use mailjet_client::{MailjetClient, MailjetClientBuilder};
+use actix_web::{
+    dev::Server,
+    http::{self, KeepAlive},
+    web, App, HttpServer,
+};
+use anyhow::anyhow;
+use chrono::{TimeDelta, Utc};
 use secrecy::ExposeSecret;
+use socket2::{Domain, Socket, Type};
 use sqlx::{mysql::MySqlPoolOptions, MySqlPool};
-use std::net::TcpListener;
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::{
+    cell::RefCell,
+    io,
+    net::{TcpListener, ToSocketAddrs},
+    path::Path,
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{error, info, warn};
 use tracing_actix_web::TracingLogger;
 use utoipa::{openapi, OpenApi};
-use utoipa_swagger_ui::SwaggerUi;
+use utoipa_swagger_ui::{Config, SwaggerUi};
+
+/// Endpoints of the API that only read data despite using `POST`, so they are served in read-only mode.
+const READ_ONLY_POSTS: &[&str] = &["/recipe/search", "/recipe/classify"];
+
+/// Search endpoints of the scopes `/ingredient` and `/author`, which are throttled.
+const SEARCHES: &[(http::Method, &str)] = &[(http::Method::GET, "")];
+
+/// Search endpoints of the scope `/recipe`, which are throttled.
+const RECIPE_SEARCHES: &[(http::Method, &str)] =
+    &[(http::Method::GET, ""), (http::Method::POST, "/search")];
 
 pub struct Application {
     port: u16,
     server: Server,
+    access_control: web::Data<AccessControl>,
+    handlers: RegisteredHandlers,
 }
 
 impl Application {
@@ -34,38 +99,169 @@ impl Application {
             .await
             .expect("Failed to connect to MariaDB.");
 
-        let address = format!(
-            "{}:{}",
-            configuration.application.host, configuration.application.port
+        // No emails are sent in read-only mode, so the mail client is not required.
+        let mail_client = if configuration.application.read_only {
+            None
+        } else {
+            Some(build_mail_client(configuration.email_client.as_ref())?)
+        };
+
+        Application::build_with(configuration, connection_pool, mail_client).await
+    }
+
+    /// Build the application using an existing connection pool and email sender, rather than the ones described by
+    /// the settings.
+    pub async fn build_with(
+        configuration: Settings,
+        connection_pool: MySqlPool,
+        mail_client: Option<Arc<dyn EmailSender>>,
+    ) -> Result<Self, anyhow::Error> {
+        let listener = Listener::from_settings(&configuration.application)?;
+        let port = listener.port();
+        let workers = configuration.application.workers();
+        let read_only = configuration.application.read_only;
+        let access_control = web::Data::new(
+            AccessControl::new(
+                Duration::from_secs(configuration.application.auth_cache_ttl_secs),
+                configuration.application.lockout.policy(),
+            )
+            .with_mail_client(mail_client.clone().map(web::Data::from))
+            .with_read_only(read_only),
         );
-        let listener = TcpListener::bind(address)?;
-        let port = listener.local_addr().unwrap().port();
-        let max_workers = configuration.application.max_workers;
 
-        let mut mail_client = MailjetClientBuilder::new(
-            configuration.email_client.api_user,
-            configuration.email_client.api_key,
-        )
-        .with_api_version(&configuration.email_client.target_api)
-        .with_email_name("La Coctelera")
-        .with_email_address(configuration.email_client.admin_address.expose_secret())
-        .with_https_enforcing(true)
-        .build()?;
+        if let (true, false, Some(mail_client)) = (
+            configuration.application.digests.enabled,
+            read_only,
+            mail_client.clone(),
+        ) {
+            spawn_digest_job(
+                connection_pool.clone(),
+                web::Data::from(mail_client),
+                configuration.application.frontend_url.clone(),
+                configuration.application.digests.interval(),
+            );
+        }
 
-        if configuration.email_client.sandbox_mode.unwrap_or_default() {
-            mail_client.enable_sandbox_mode();
+        let id_generator = configuration.application.id_scheme.generator();
+        if configuration.application.notifications.enabled && !read_only {
+            spawn_notification_job(
+                connection_pool.clone(),
+                id_generator.clone(),
+                mail_client.clone().map(web::Data::from),
+                configuration.application.notifications.expiry_notice(),
+                configuration.application.notifications.interval(),
+            );
         }
 
+        if configuration.application.health_history.enabled && !read_only {
+            spawn_health_history_job(
+                connection_pool.clone(),
+                configuration.application.health_history.retention(),
+                configuration.application.health_history.interval(),
+            );
+        }
+
+        // Nothing is recorded in read-only mode, as the usage could not be stored.
+        analytics().configure(
+            configuration.application.analytics.enabled && !read_only,
+            configuration.application.analytics.k_anonymity,
+        );
+        if configuration.application.analytics.enabled && !read_only {
+            spawn_analytics_job(
+                connection_pool.clone(),
+                configuration.application.analytics.retention(),
+                configuration.application.analytics.flush_interval(),
+            );
+        }
+
+        let pdf_cache = PdfCache::new(Path::new(&configuration.application.pdf_cache_dir));
+        if configuration.application.outbox.enabled && !read_only {
+            let mut sinks = build_event_sinks(&configuration.application.outbox).await?;
+            if sinks.is_empty() {
+                warn!("The outbox is enabled, but no event sink is configured");
+            }
+            // The cached recipe sheets are removed as soon as their recipe changes.
+            sinks.push(Arc::new(pdf_cache.clone()));
+            spawn_outbox_job(
+                connection_pool.clone(),
+                sinks,
+                configuration.application.outbox.clone(),
+            );
+        }
+
+        let asset_store = AssetStore::load(
+            Path::new(&configuration.application.static_assets.dir),
+            &configuration.application.static_assets.extensions,
+        )?
+        .with_listing(configuration.application.static_assets.listing);
+        let theme = Theme::new(
+            &configuration.application.branding,
+            &format!("{}/static", api_url(&configuration.application.base_url)),
+            &asset_store,
+        );
+        info!("Theme of the HTML pages: {}", theme.name());
+
+        let default_license = configuration.application.default_recipe_license;
+        info!("Default license of the recipes: {default_license}");
+        if !read_only {
+            let relicensed =
+                assign_default_license_in_db(&connection_pool, default_license).await?;
+            if relicensed > 0 {
+                info!("{relicensed} recipes with no license got the default license");
+            }
+        }
+
+        // The domain objects sanitize the text given by the clients using the level of the application.
+        set_sanitize_level(configuration.application.sanitize_level);
+
+        let state = AppState {
+            base_url: configuration.application.base_url,
+            mail_client,
+            pdf_cache,
+            sitemap_cache: SitemapCache::new(&configuration.application.frontend_url),
+            media_store: MediaStore::new(Path::new(&configuration.application.media_dir)),
+            asset_store,
+            backup_store: BackupStore::new(Path::new(&configuration.application.backup_dir)),
+            screener: configuration.application.screening.screener(),
+            throttling: configuration.application.throttling,
+            load_shedding: configuration.application.load_shedding,
+            trusted_proxies: TrustedProxies::new(
+                &configuration.application.trusted_proxies,
+                configuration.application.forwarding_header,
+            )?,
+            id_generator,
+            cache_policy: CachePolicy::new(&configuration.application.cache_control)?,
+            preconditions: Preconditions::new(configuration.application.require_if_match),
+            read_only,
+            access_control: access_control.clone(),
+            sanitize_level: configuration.application.sanitize_level,
+            recipe_limits: configuration.application.recipe_limits,
+            name_collations: configuration.application.name_collations,
+            max_page_size: MaxPageSize::new(configuration.application.max_page_size),
+            theme,
+            default_license,
+            author_emails: AuthorEmailPolicy::new(
+                !configuration.application.allow_duplicate_author_emails,
+            ),
+        };
+
+        let handlers = RegisteredHandlers::default();
         let server = run(
             listener,
             connection_pool,
-            configuration.application.base_url,
-            max_workers,
-            mail_client,
+            workers,
+            configuration.application.server,
+            state,
+            &handlers,
         )
         .await?;
 
-        Ok(Self { port, server })
+        Ok(Self {
+            port,
+            server,
+            access_control,
+            handlers,
+        })
     }
 
     pub async fn run_until_stopped(self) -> Result<(), std::io::Error> {
@@ -75,22 +271,143 @@ impl Application {
     pub fn port(&self) -> u16 {
         self.port
     }
+
+    /// Get the state of the access checks, shared by all the workers.
+    pub fn access_control(&self) -> web::Data<AccessControl> {
+        self.access_control.clone()
+    }
+
+    /// Compare the handlers registered by the application to the operations of the OpenAPI document.
+    pub fn openapi_audit(&self) -> OpenApiAudit {
+        audit_openapi(&self.handlers, &ApiDoc::openapi())
+    }
+}
+
+/// URL under which the resources of the API are served: the base URL followed by the major version of the API.
+pub fn api_url(base_url: &str) -> String {
+    format!(
+        "{base_url}/v{}",
+        env!("CARGO_PKG_VERSION").split(".").collect::<Vec<&str>>()[0]
+    )
+}
+
+/// Build the OpenAPI document of the API, as served at [OPENAPI_PATH].
+pub fn api_document(base_url: &str) -> openapi::OpenApi {
+    let mut api_doc = ApiDoc::openapi();
+    api_doc.servers = Some(Vec::from([openapi::Server::new(api_url(base_url))]));
+    let mut external_docs =
+        openapi::ExternalDocs::new("https://felipet.github.io/lacoctelera_backend/lacoctelera/");
+    external_docs.description = Some(String::from("Code documentation of the API (Rust docs)"));
+    api_doc.external_docs = Some(external_docs);
+
+    api_doc
+}
+
+/// State of the application derived from the settings.
+///
+/// # Description
+///
+/// [run] shares the state with the handlers of all the workers, registering each member as `web::Data`.
+pub struct AppState {
+    /// Base URL of the API, see [api_url].
+    pub base_url: String,
+    /// Email sender, none in read-only mode.
+    pub mail_client: Option<Arc<dyn EmailSender>>,
+    pub pdf_cache: PdfCache,
+    pub sitemap_cache: SitemapCache,
+    pub media_store: MediaStore,
+    pub asset_store: AssetStore,
+    pub backup_store: BackupStore,
+    pub screener: Screener,
+    pub throttling: ThrottlingSettings,
+    pub load_shedding: LoadSheddingSettings,
+    pub trusted_proxies: TrustedProxies,
+    pub id_generator: Arc<dyn IdGenerator>,
+    pub cache_policy: CachePolicy,
+    pub preconditions: Preconditions,
+    /// Reject the requests that would modify the DB, see [ReadOnly].
+    pub read_only: bool,
+    pub access_control: web::Data<AccessControl>,
+    pub sanitize_level: SanitizeLevel,
+    pub recipe_limits: RecipeLimits,
+    pub name_collations: NameCollations,
+    pub max_page_size: MaxPageSize,
+    pub theme: Theme,
+    pub default_license: RecipeLicense,
+    pub author_emails: AuthorEmailPolicy,
 }
 
 pub async fn run(
-    listener: TcpListener,
+    listener: Listener,
     db_pool: MySqlPool,
-    base_url: String,
-    max_workers: u16,
-    mail_client: MailjetClient,
+    workers: usize,
+    server_settings: ServerSettings,
+    state: AppState,
+    handlers: &RegisteredHandlers,
 ) -> Result<Server, anyhow::Error> {
+    let AppState {
+        base_url,
+        mail_client,
+        pdf_cache,
+        sitemap_cache,
+        media_store,
+        asset_store,
+        backup_store,
+        screener,
+        throttling,
+        load_shedding,
+        trusted_proxies,
+        id_generator,
+        cache_policy,
+        preconditions,
+        read_only,
+        access_control,
+        sanitize_level,
+        recipe_limits,
+        name_collations,
+        max_page_size,
+        theme,
+        default_license,
+        author_emails,
+    } = state;
     let db_pool = web::Data::new(db_pool);
-    let mail_client = web::Data::new(mail_client);
+    let mail_client: Option<web::Data<dyn EmailSender>> = mail_client.map(web::Data::from);
+    let pdf_cache = web::Data::new(pdf_cache);
+    let sitemap_cache = web::Data::new(sitemap_cache);
+    let landing_cache = web::Data::new(LandingCache::new());
+    let activity_cache = web::Data::new(ActivityCache::default());
+    let suggestion_cache = web::Data::new(SuggestionCache::default());
+    let maintenance =
+        web::Data::new(routes::admin::maintenance::load_maintenance_schedule(&db_pool).await);
+    let media_store = web::Data::new(media_store);
+    let asset_store = web::Data::new(asset_store);
+    let backup_store = web::Data::new(backup_store);
+    let screener = web::Data::new(screener);
+    // Throttles are shared by all the workers.
+    let search_throttle = Arc::new(throttling.search_throttle());
+    let token_throttle = Arc::new(throttling.token_throttle());
+    metrics().register_throttle(&search_throttle);
+    metrics().register_throttle(&token_throttle);
+    let trusted_proxies = web::Data::new(trusted_proxies);
+    let preconditions = web::Data::new(preconditions);
+    let id_generator: web::Data<dyn IdGenerator> = web::Data::from(id_generator);
+    let sanitize_level = web::Data::new(sanitize_level);
+    let recipe_limits = web::Data::new(recipe_limits);
+    let name_collations = web::Data::new(name_collations);
+    let max_page_size = web::Data::new(max_page_size);
+    let theme = web::Data::new(theme);
+    let default_license = web::Data::new(default_license);
+    let author_emails = web::Data::new(author_emails);
+    // The OpenAPI document is serialized once, and shared by all the workers.
+    let openapi = api_document(&base_url);
+    let api_doc = web::Data::new(OpenApiDocument::new(&openapi)?);
+    let tracked_handlers = handlers.clone();
 
-    let server = HttpServer::new(move || {
+    let app = move || {
+        let handlers = &tracked_handlers;
         let cors_ingredient = Cors::default()
             .allow_any_origin()
-            .allowed_methods(vec!["GET", "POST"])
+            .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
             .allowed_header(http::header::CONTENT_TYPE)
             .max_age(3600);
 
@@ -106,70 +423,467 @@ pub async fn run(
             .allowed_header(http::header::CONTENT_TYPE)
             .max_age(3600);
 
-        let relative_url = &format!(
-            "{base_url}/v{}",
-            env!("CARGO_PKG_VERSION").split(".").collect::<Vec<&str>>()[0]
-        );
-        let mut api_doc = ApiDoc::openapi();
-        api_doc.servers = Some(Vec::from([openapi::Server::new(relative_url)]));
-        let mut external_docs = openapi::ExternalDocs::new(
-            "https://felipet.github.io/lacoctelera_backend/lacoctelera/",
+        let search_throttle = Throttle::new(search_throttle.clone());
+        let token_throttle = Throttle::new(token_throttle.clone());
+        // The factory runs once per worker, so every worker counts its own requests.
+        let in_flight = InFlight::new();
+        let load_shed = LoadShed::shed(
+            in_flight.clone(),
+            load_shedding.limit(),
+            load_shedding.retry_after(),
         );
-        external_docs.description = Some(String::from("Code documentation of the API (Rust docs)"));
-        api_doc.external_docs = Some(external_docs);
 
-        App::new()
-            .wrap(TracingLogger::default())
+        let relative_url = &api_url(&base_url);
+
+        let app = App::new()
+            .wrap(server_error_pages())
+            .wrap(TracingLogger::<ClientIpRootSpan>::new())
             .service(
                 web::scope(relative_url)
-                    .service(routes::echo)
-                    .service(health::options_echo)
-                    .service(health::health_check)
-                    .service(health::options_health)
+                    .wrap(cache_policy.clone().with_prefix(relative_url))
+                    .wrap(ReadOnly::new(read_only).except(READ_ONLY_POSTS))
+                    .wrap(LoadShed::track(in_flight))
+                    .wrap(RequestMetrics::new(relative_url, metrics()).with_analytics(analytics()))
+                    .wrap(MaintenanceNotice::new(maintenance.clone()))
+                    .service(handlers.track(routes::echo))
+                    .service(handlers.track(health::options_echo))
+                    .service(handlers.track(health::health_check))
+                    .service(handlers.track(health::options_health))
+                    .service(handlers.track(routes::status::get_status_history))
+                    .service(handlers.track(routes::sitemap::get_sitemap))
+                    .service(handlers.track(routes::sitemap::get_sitemap_page))
+                    .service(handlers.track(routes::landing::get_category_recipes))
+                    .service(handlers.track(routes::landing::get_tag_featured))
+                    .service(handlers.track(routes::activity::get_recent_activity))
+                    .service(handlers.track(routes::media::get_media))
                     .service(
                         web::scope("/ingredient")
+                            .wrap(load_shed.clone())
+                            .wrap(search_throttle.clone().only(SEARCHES))
                             .wrap(cors_ingredient)
-                            .service(routes::ingredient::search_ingredient)
-                            .service(routes::ingredient::get_ingredient)
-                            .service(routes::ingredient::add_ingredient),
+                            .app_data(web::PayloadConfig::new(MAX_IMAGE_SIZE))
+                            .service(handlers.track(routes::ingredient::search_ingredient))
+                            // Registered before the ingredients, as `categories` would match their ID.
+                            .service(handlers.track(routes::ingredient::get_ingredient_categories))
+                            .service(handlers.track(routes::ingredient::get_ingredient))
+                            .service(handlers.track(routes::ingredient::put_ingredient_image))
+                            .service(handlers.track(routes::ingredient::delete_ingredient_image))
+                            .service(handlers.track(routes::ingredient::add_ingredient))
+                            .service(handlers.track(routes::ingredient::batch_delete_ingredients)),
                     )
                     .service(
                         web::scope("/author")
+                            .wrap(load_shed.clone())
+                            .wrap(search_throttle.clone().only(SEARCHES))
                             .wrap(cors_author)
-                            .service(routes::author::search_author)
-                            .service(routes::author::patch_author)
-                            .service(routes::author::head_author)
-                            .service(routes::author::post_author)
-                            .service(routes::author::get_author)
-                            .service(routes::author::delete_author),
+                            .service(handlers.track(routes::author::search_author))
+                            .service(handlers.track(routes::author::patch_author))
+                            .service(handlers.track(routes::author::head_author))
+                            .service(handlers.track(routes::author::post_author))
+                            .service(handlers.track(routes::author::get_author))
+                            .service(handlers.track(routes::author::delete_author))
+                            .service(handlers.track(routes::author::post_social_profile))
+                            .service(handlers.track(routes::author::patch_social_profile))
+                            .service(handlers.track(routes::author::delete_social_profile)),
                     )
                     .service(
                         web::scope("/recipe")
+                            .wrap(load_shed)
+                            .wrap(search_throttle.only(RECIPE_SEARCHES))
                             .wrap(cors_recipe)
-                            .service(routes::recipe::get_recipe)
-                            .service(routes::recipe::search_recipe)
-                            .service(routes::recipe::head_recipe)
-                            .service(routes::recipe::post_recipe),
+                            .app_data(web::PayloadConfig::new(MAX_IMAGE_SIZE))
+                            // Registered before the recipes, as `suggest`, `makeable` and `search-suggestions`
+                            // would match their ID.
+                            .service(handlers.track(routes::recipe::suggest_recipes))
+                            .service(handlers.track(routes::recipe::get_makeable_recipes))
+                            .service(handlers.track(routes::recipe::get_search_suggestions))
+                            .service(handlers.track(routes::recipe::get_recipe_by_slug))
+                            .service(handlers.track(routes::recipe::search_recipe_by_example))
+                            .service(handlers.track(routes::recipe::get_recipe))
+                            .service(handlers.track(routes::recipe::search_recipe))
+                            .service(handlers.track(routes::recipe::head_recipe))
+                            .service(handlers.track(routes::recipe::patch_recipe))
+                            .service(handlers.track(routes::recipe::delete_recipe))
+                            .service(handlers.track(routes::recipe::get_recipe_pdf))
+                            .service(handlers.track(routes::recipe::put_step_image))
+                            .service(handlers.track(routes::recipe::delete_step_image))
+                            .service(handlers.track(routes::recipe::post_recipe))
+                            .service(handlers.track(routes::recipe::batch_delete_recipes))
+                            .service(handlers.track(routes::recipe::classify_recipe))
+                            // Registered before the transitions, as `claim` and `rating` would match a transition.
+                            .service(handlers.track(routes::recipe::claim_recipe))
+                            .service(handlers.track(routes::recipe::rate_recipe))
+                            .service(handlers.track(routes::recipe::transition_recipe)),
+                    )
+                    .service(
+                        web::scope("/me")
+                            .service(handlers.track(routes::me::get_digests))
+                            .service(handlers.track(routes::me::post_digest))
+                            .service(handlers.track(routes::me::delete_digest))
+                            .service(handlers.track(routes::me::get_searches))
+                            .service(handlers.track(routes::me::post_search))
+                            .service(handlers.track(routes::me::delete_search))
+                            .service(handlers.track(routes::me::get_search_results))
+                            .service(handlers.track(routes::me::get_inventory))
+                            .service(handlers.track(routes::me::put_inventory))
+                            .service(handlers.track(routes::me::put_inventory_item))
+                            .service(handlers.track(routes::me::delete_inventory_item))
+                            .service(handlers.track(routes::me::get_preferences))
+                            .service(handlers.track(routes::me::patch_preferences))
+                            .service(handlers.track(routes::me::get_notifications))
+                            .service(handlers.track(routes::me::read_all_notifications))
+                            .service(handlers.track(routes::me::read_notification)),
+                    )
+                    .service(
+                        web::scope("/admin")
+                            .service(handlers.track(routes::admin::merge_authors))
+                            .service(handlers.track(routes::admin::get_moderation_queue))
+                            .service(handlers.track(routes::admin::moderate_recipe))
+                            .service(handlers.track(routes::admin::bulk_retag))
+                            .service(handlers.track(routes::admin::get_emails))
+                            .service(handlers.track(routes::admin::get_events))
+                            .service(handlers.track(routes::admin::get_analytics))
+                            .service(handlers.track(routes::admin::get_clients))
+                            .service(handlers.track(routes::admin::post_ingredient_category))
+                            .service(handlers.track(routes::admin::patch_ingredient_category))
+                            .service(handlers.track(routes::admin::delete_ingredient_category))
+                            .service(handlers.track(routes::admin::reclassify_ingredients))
+                            .service(handlers.track(routes::admin::schedule_maintenance))
+                            .service(handlers.track(routes::admin::cancel_maintenance))
+                            .service(handlers.track(routes::admin::get_migrations))
+                            .service(handlers.track(routes::admin::post_backup))
+                            .service(handlers.track(routes::admin::get_backups))
+                            .service(handlers.track(routes::admin::get_claims))
+                            .service(handlers.track(routes::admin::review_claim)),
+                    )
+                    .service(handlers.track(routes::assets::get_static_listing))
+                    .service(handlers.track(routes::assets::get_static_asset))
+                    // Keys are managed by authenticated clients, so they are not throttled as the token requests.
+                    .service(
+                        web::scope("/token/keys")
+                            .service(handlers.track(routes::token::get_keys))
+                            .service(handlers.track(routes::token::post_key))
+                            .service(handlers.track(routes::token::delete_key)),
                     )
-                    .service(fs::Files::new("/static", "./static/resources").show_files_listing())
                     .service(
                         web::scope("/token")
-                            .service(routes::token::token_req_get)
-                            .service(routes::token::token_req_post)
-                            .service(routes::token::req_validation),
+                            .wrap(error_pages())
+                            // The validation of the token requests modifies the DB despite being a GET request.
+                            .wrap(ReadOnly::all(read_only))
+                            .wrap(token_throttle)
+                            .service(handlers.track(routes::token::token_req_get))
+                            .service(handlers.track(routes::token::token_req_post))
+                            .service(handlers.track(routes::token::req_validation)),
                     )
-                    .service(SwaggerUi::new("/{_:.*}").url("api-docs/openapi.json", api_doc)),
+                    // Registered before the Swagger UI, which would match the path of the document.
+                    .service(handlers.track(routes::docs::get_openapi))
+                    .service(SwaggerUi::new("/{_:.*}").config(Config::new([OPENAPI_PATH]))),
             )
             .app_data(db_pool.clone())
-            .app_data(mail_client.clone())
-    })
-    .workers(max_workers as usize)
-    .listen(listener)?
+            .app_data(pdf_cache.clone())
+            .app_data(sitemap_cache.clone())
+            .app_data(landing_cache.clone())
+            .app_data(activity_cache.clone())
+            .app_data(suggestion_cache.clone())
+            .app_data(maintenance.clone())
+            .app_data(media_store.clone())
+            .app_data(asset_store.clone())
+            .app_data(backup_store.clone())
+            .app_data(screener.clone())
+            .app_data(trusted_proxies.clone())
+            .app_data(preconditions.clone())
+            .app_data(id_generator.clone())
+            .app_data(access_control.clone())
+            .app_data(sanitize_level.clone())
+            .app_data(recipe_limits.clone())
+            .app_data(name_collations.clone())
+            .app_data(max_page_size.clone())
+            .app_data(theme.clone())
+            .app_data(default_license.clone())
+            .app_data(author_emails.clone())
+            .app_data(api_doc.clone())
+            .app_data(path_config());
+
+        match &mail_client {
+            Some(mail_client) => app.app_data(mail_client.clone()),
+            None => app,
+        }
+    };
+    // Building an application tracks its handlers, so the document is audited before the workers start.
+    app();
+    routes::docs::log_openapi_gaps(handlers, &openapi);
+
+    let server = HttpServer::new(app)
+        .workers(workers)
+        .keep_alive(match server_settings.keep_alive() {
+            Some(timeout) => KeepAlive::Timeout(timeout),
+            None => KeepAlive::Disabled,
+        })
+        .client_request_timeout(server_settings.client_request_timeout())
+        .client_disconnect_timeout(server_settings.client_disconnect_timeout())
+        .max_connections(server_settings.max_connections)
+        .shutdown_timeout(server_settings.shutdown_timeout_secs);
+
+    let server = match listener {
+        Listener::Tcp(listener) => server.listen(listener)?,
+        #[cfg(unix)]
+        Listener::Unix(listener) => server.listen_uds(listener)?,
+    }
     .run();
 
     Ok(server)
 }
 
+/// Schedule the job that sends the email digests of new recipes.
+fn spawn_digest_job(
+    pool: MySqlPool,
+    mail_client: web::Data<dyn EmailSender>,
+    frontend_url: String,
+    interval: Duration,
+) {
+    spawn_periodic_job("digests", interval, move || {
+        let pool = pool.clone();
+        let mail_client = mail_client.clone();
+        let frontend_url = frontend_url.clone();
+
+        async move {
+            if let Err(e) = send_due_digests(&pool, mail_client, &frontend_url, Utc::now()).await {
+                error!("Failed to send the digests: {e}");
+            }
+        }
+    });
+}
+
+/// Schedule the job that produces the notifications of the clients, and mirrors them to email.
+fn spawn_notification_job(
+    pool: MySqlPool,
+    ids: Arc<dyn IdGenerator>,
+    mail_client: Option<web::Data<dyn EmailSender>>,
+    expiry_notice: TimeDelta,
+    interval: Duration,
+) {
+    spawn_periodic_job("notifications", interval, move || {
+        let pool = pool.clone();
+        let ids = ids.clone();
+        let mail_client = mail_client.clone();
+
+        async move {
+            if let Err(e) =
+                run_notifications(&pool, &*ids, mail_client, expiry_notice, Utc::now()).await
+            {
+                error!("Failed to run the notifications: {e}");
+            }
+        }
+    });
+}
+
+/// Schedule the job that records the history of the health of the server.
+fn spawn_health_history_job(pool: MySqlPool, retention: TimeDelta, interval: Duration) {
+    // Snapshots that could not be stored yet. Runs don't overlap, so they never share it.
+    let pending = Rc::new(RefCell::new(Vec::new()));
+
+    spawn_periodic_job("health_history", interval, move || {
+        let pool = pool.clone();
+        let pending = pending.clone();
+
+        async move {
+            let mut snapshots = pending.take();
+            if let Err(e) =
+                record_health_history(&pool, &mut snapshots, retention, Utc::now()).await
+            {
+                error!("Failed to record the health history: {e}");
+            }
+            pending.replace(snapshots);
+        }
+    });
+}
+
+/// Schedule the job that stores the anonymous usage analytics.
+fn spawn_analytics_job(pool: MySqlPool, retention: TimeDelta, interval: Duration) {
+    spawn_periodic_job("analytics", interval, move || {
+        let pool = pool.clone();
+
+        async move {
+            if let Err(e) = flush_usage_analytics(&pool, analytics(), retention, Utc::now()).await {
+                error!("Failed to store the usage analytics: {e}");
+            }
+        }
+    });
+}
+
+/// Schedule the job that publishes the domain events to the sinks.
+fn spawn_outbox_job(pool: MySqlPool, sinks: Vec<Arc<dyn EventSink>>, settings: OutboxSettings) {
+    spawn_periodic_job("outbox", settings.interval(), move || {
+        let pool = pool.clone();
+        let sinks = sinks.clone();
+        let settings = settings.clone();
+
+        async move {
+            if let Err(e) = dispatch_events(&pool, &sinks, &settings).await {
+                error!("Failed to dispatch the events: {e}");
+            }
+        }
+    });
+}
+
+/// Build the client of Mailjet, which is used to send the emails of the application.
+pub fn build_mail_client(
+    settings: Option<&EmailClientSettings>,
+) -> Result<Arc<dyn EmailSender>, anyhow::Error> {
+    let settings = settings.ok_or_else(|| anyhow!("The email client settings are missing"))?;
+    if settings.target_api != "v3.1" {
+        return Err(anyhow!(
+            "Unsupported version of the API of Mailjet: {}",
+            settings.target_api
+        ));
+    }
+    let mail_client = MailjetSender::new(
+        settings.api_user.clone(),
+        settings.api_key.clone(),
+        settings.admin_address.expose_secret(),
+        &settings.user_agent,
+    )?
+    .with_sandbox_mode(settings.sandbox_mode.unwrap_or_default());
+
+    Ok(Arc::new(mail_client))
+}
+
+/// Socket on which the server listens for new connections.
+#[derive(Debug)]
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Listener {
+    /// Build the listener described by the settings.
+    ///
+    /// # Description
+    ///
+    /// The socket passed by systemd is used when the application was started using socket activation (see
+    /// [ApplicationSettings::systemd_socket]). Otherwise, the listener is bound to
+    /// [ApplicationSettings::unix_socket] when it is set, or to `host:port`.
+    pub fn from_settings(settings: &ApplicationSettings) -> io::Result<Self> {
+        #[cfg(unix)]
+        if settings.systemd_socket {
+            if let Some(listener) = Listener::from_systemd()? {
+                return Ok(listener);
+            }
+        }
+
+        match settings.unix_socket.as_deref() {
+            #[cfg(unix)]
+            Some(path) => Listener::bind_unix(Path::new(path), settings.server.backlog),
+            #[cfg(not(unix))]
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Unix sockets are not supported by this platform",
+            )),
+            None => bind_listener(
+                &format!("{}:{}", settings.host, settings.port),
+                settings.server.backlog,
+            )
+            .map(Listener::Tcp),
+        }
+    }
+
+    /// Port of the listener. Unix sockets have no port, so 0 is returned for them.
+    pub fn port(&self) -> u16 {
+        match self {
+            Listener::Tcp(listener) => listener.local_addr().map_or(0, |a| a.port()),
+            #[cfg(unix)]
+            Listener::Unix(_) => 0,
+        }
+    }
+
+    /// Bind a Unix socket to the given path. A stale socket left by a previous run of the application is replaced.
+    #[cfg(unix)]
+    pub fn bind_unix(path: &Path, backlog: u32) -> io::Result<Self> {
+        use socket2::SockAddr;
+        use std::{fs, os::unix::fs::FileTypeExt};
+
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path)?,
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} exists and it is not a socket", path.display()),
+                ))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e),
+        }
+
+        let socket = Socket::new(Domain::UNIX, Type::STREAM, None)?;
+        socket.bind(&SockAddr::unix(path)?)?;
+        socket.listen(backlog.min(i32::MAX as u32) as i32)?;
+
+        Ok(Listener::Unix(socket.into()))
+    }
+
+    /// Take the first socket passed by systemd, following the protocol of `sd_listen_fds`.
+    ///
+    /// # Description
+    ///
+    /// `None` is returned when the application was not started using socket activation. The environment variables
+    /// of the protocol are removed, so the socket is only taken once.
+    #[cfg(unix)]
+    fn from_systemd() -> io::Result<Option<Self>> {
+        use std::{env, os::fd::FromRawFd};
+
+        /// First file descriptor passed by systemd.
+        const SD_LISTEN_FDS_START: i32 = 3;
+
+        let for_this_process = env::var("LISTEN_PID")
+            .ok()
+            .and_then(|pid| pid.parse::<u32>().ok())
+            .is_some_and(|pid| pid == std::process::id());
+        let fds = env::var("LISTEN_FDS")
+            .ok()
+            .and_then(|fds| fds.parse::<u32>().ok())
+            .unwrap_or_default();
+        if !for_this_process || fds == 0 {
+            return Ok(None);
+        }
+        env::remove_var("LISTEN_PID");
+        env::remove_var("LISTEN_FDS");
+        env::remove_var("LISTEN_FDNAMES");
+
+        // SAFETY: systemd passes the ownership of the descriptor to this process, and the variables of the protocol
+        // were removed, so the descriptor is not owned twice.
+        let socket = unsafe { Socket::from_raw_fd(SD_LISTEN_FDS_START) };
+        if socket.local_addr()?.is_unix() {
+            Ok(Some(Listener::Unix(socket.into())))
+        } else {
+            Ok(Some(Listener::Tcp(socket.into())))
+        }
+    }
+}
+
+/// Bind the listener of the server to the given address (`host:port`).
+///
+/// # Description
+///
+/// The listener is built like [TcpListener::bind], but the size of the queue of pending connections is set to
+/// `backlog`.
+pub fn bind_listener(address: &str, backlog: u32) -> io::Result<TcpListener> {
+    let address = address.to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("The address {address} can't be resolved"),
+        )
+    })?;
+
+    let socket = Socket::new(Domain::for_address(address), Type::STREAM, None)?;
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.bind(&address.into())?;
+    socket.listen(backlog.min(i32::MAX as u32) as i32)?;
+
+    Ok(socket.into())
+}
+
 pub async fn get_connection_pool(
     configuration: &DataBaseSettings,
 ) -> Result<MySqlPool, sqlx::Error> {
@@ -179,3 +893,32 @@ pub async fn get_connection_pool(
         .connect_with(configuration.build_db_conn_with_db())
         .await
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{fs, os::unix::net::UnixStream};
+    use uuid::Uuid;
+
+    #[test]
+    fn stale_unix_sockets_are_replaced() {
+        let path = std::env::temp_dir().join(format!("lacoctelera-{}.sock", Uuid::now_v7()));
+
+        let listener = Listener::bind_unix(&path, 16).unwrap();
+        assert_eq!(listener.port(), 0);
+        drop(listener);
+        // The socket file outlives the listener.
+        assert!(path.exists());
+
+        let Listener::Unix(listener) = Listener::bind_unix(&path, 16).unwrap() else {
+            panic!("A Unix socket was expected");
+        };
+        UnixStream::connect(&path).unwrap();
+        assert!(listener.accept().is_ok());
+        fs::remove_file(&path).unwrap();
+
+        fs::write(&path, "").unwrap();
+        assert!(Listener::bind_unix(&path, 16).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
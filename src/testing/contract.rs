@@ -0,0 +1,477 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Contract tests of the API.
+//!
+//! # Description
+//!
+//! The OpenAPI spec of the API ([ApiDoc]) is the contract with the clients. [ContractChecker] checks the responses
+//! of the API against such spec, and reports every mismatch:
+//! - The status code of the response is not documented for the endpoint.
+//! - Some header documented for the status code is missing in the response.
+//! - The `Content-Type` of the response is not documented, or its body doesn't match the documented schema.
+//!
+//! Some headers (i.e. `Access-Control-Allow-Origin`) are only sent to cross-origin requests, so
+//! [TestApp::contract_test] includes an `Origin` header in all the requests.
+
+use crate::{testing::helpers::TestApp, ApiDoc};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use utoipa::OpenApi;
+
+/// Origin used by the requests of the contract tests.
+pub const CONTRACT_TEST_ORIGIN: &str = "http://contract.test";
+
+/// Response of the API under test.
+#[derive(Debug, Clone, Default)]
+pub struct ObservedResponse {
+    pub status: u16,
+    /// Headers of the response. Names are kept in lower case.
+    pub headers: BTreeMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl ObservedResponse {
+    pub fn new<'a>(
+        status: u16,
+        headers: impl IntoIterator<Item = (&'a str, &'a str)>,
+        body: &[u8],
+    ) -> Self {
+        ObservedResponse {
+            status,
+            headers: headers
+                .into_iter()
+                .map(|(name, value)| (name.to_lowercase(), value.to_owned()))
+                .collect(),
+            body: body.to_vec(),
+        }
+    }
+
+    /// Consume a response of the HTTP client.
+    pub async fn from_response(response: reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str().to_lowercase(),
+                    value.to_str().unwrap_or_default().to_owned(),
+                )
+            })
+            .collect();
+        let body = response
+            .bytes()
+            .await
+            .expect("Failed to read the body of the response")
+            .to_vec();
+
+        ObservedResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    /// Media type of the response, without parameters.
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .get("content-type")
+            .map(|value| value.split(';').next().unwrap_or_default().trim())
+    }
+
+    /// Parse the body as JSON.
+    pub fn json(&self) -> Result<Value, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+/// Checker of the responses of the API against its OpenAPI spec.
+#[derive(Debug, Clone)]
+pub struct ContractChecker {
+    spec: Value,
+    paths: Vec<(String, Regex)>,
+}
+
+impl Default for ContractChecker {
+    /// Build a checker for the spec of this API.
+    fn default() -> Self {
+        ContractChecker::new(
+            serde_json::to_value(ApiDoc::openapi()).expect("Failed to serialise the OpenAPI spec"),
+        )
+    }
+}
+
+impl ContractChecker {
+    /// Build a checker for the given OpenAPI spec, in JSON format.
+    pub fn new(spec: Value) -> Self {
+        let mut paths = spec["paths"]
+            .as_object()
+            .map(|paths| {
+                paths
+                    .keys()
+                    .map(|path| (path.clone(), ContractChecker::path_regex(path)))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        // Paths with fewer parameters are more specific, i.e. `/recipe/classify` goes before `/recipe/{id}`.
+        paths.sort_by_key(|(path, _)| path.matches('{').count());
+
+        ContractChecker { spec, paths }
+    }
+
+    fn path_regex(template: &str) -> Regex {
+        let params = Regex::new(r"\\\{[^/]+?\\\}").unwrap();
+        let escaped = regex::escape(template);
+        let pattern = params.replace_all(&escaped, "[^/]+");
+        Regex::new(&format!("^{pattern}$")).unwrap()
+    }
+
+    /// Find the documented operation for a request.
+    ///
+    /// # Description
+    ///
+    /// `path` is relative to the base URL of the API, i.e. `/recipe/{id}`. The query string is ignored.
+    fn operation(&self, method: &str, path: &str) -> Option<(&str, &Value)> {
+        let path = path.split('?').next().unwrap_or_default();
+        let method = method.to_lowercase();
+
+        self.paths
+            .iter()
+            .filter(|(_, regex)| regex.is_match(path))
+            .find_map(|(template, _)| {
+                self.spec["paths"][template]
+                    .get(&method)
+                    .map(|operation| (template.as_str(), operation))
+            })
+    }
+
+    /// Check a response of the API.
+    ///
+    /// # Description
+    ///
+    /// All the mismatches between the response and the spec are returned, described for humans.
+    pub fn check(
+        &self,
+        method: &str,
+        path: &str,
+        response: &ObservedResponse,
+    ) -> Result<(), Vec<String>> {
+        let request = format!("{} {path}", method.to_uppercase());
+        let Some((template, operation)) = self.operation(method, path) else {
+            return Err(vec![format!("{request}: the endpoint is not documented")]);
+        };
+        let request = format!("{request} ({template})");
+
+        let status = response.status.to_string();
+        let Some(documented) = operation["responses"].get(&status) else {
+            return Err(vec![format!(
+                "{request}: the status code {status} is not documented"
+            )]);
+        };
+        let documented = self.resolve(documented);
+
+        let mut violations = Vec::new();
+
+        if let Some(headers) = documented["headers"].as_object() {
+            for name in headers.keys() {
+                if !response.headers.contains_key(&name.to_lowercase()) {
+                    violations.push(format!(
+                        "{request}: the header {name} is missing in the response {status}"
+                    ));
+                }
+            }
+        }
+
+        // Responses to HEAD requests have no body.
+        let content = documented["content"]
+            .as_object()
+            .filter(|_| !method.eq_ignore_ascii_case("head") && !response.body.is_empty());
+
+        if let Some(content) = content {
+            let content_type = response.content_type().unwrap_or_default();
+            match content.get(content_type) {
+                None => violations.push(format!(
+                    "{request}: the content type '{content_type}' is not documented for the response {status}"
+                )),
+                Some(media) if content_type.ends_with("json") => match response.json() {
+                    Ok(body) => {
+                        if let Some(schema) = media.get("schema") {
+                            let at = format!("{request}: body");
+                            self.validate(schema, &body, &at, &mut violations);
+                        }
+                    }
+                    Err(e) => violations.push(format!("{request}: malformed JSON body: {e}")),
+                },
+                Some(_) => (),
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Follow a reference to the components of the spec.
+    fn resolve<'a>(&'a self, object: &'a Value) -> &'a Value {
+        match object["$ref"].as_str() {
+            Some(reference) => {
+                let pointer = reference.trim_start_matches('#');
+                self.spec.pointer(pointer).unwrap_or(&Value::Null)
+            }
+            None => object,
+        }
+    }
+
+    /// Validate a JSON value against a schema of the spec.
+    ///
+    /// # Description
+    ///
+    /// Only the keywords used by the spec of this API are supported: `$ref`, `allOf`, `oneOf`, `anyOf`, `type`,
+    /// `nullable`, `enum`, `required`, `properties` and `items`.
+    fn validate(&self, schema: &Value, value: &Value, at: &str, violations: &mut Vec<String>) {
+        let schema = self.resolve(schema);
+
+        if value.is_null() && schema["nullable"].as_bool() == Some(true) {
+            return;
+        }
+        if value.is_null() && schema.get("type").is_some() {
+            violations.push(format!("{at}: null is not allowed"));
+            return;
+        }
+
+        if let Some(schemas) = schema["allOf"].as_array() {
+            for schema in schemas {
+                self.validate(schema, value, at, violations);
+            }
+        }
+
+        for keyword in ["oneOf", "anyOf"] {
+            if let Some(schemas) = schema[keyword].as_array() {
+                let matches = schemas.iter().any(|schema| {
+                    let mut errors = Vec::new();
+                    self.validate(schema, value, at, &mut errors);
+                    errors.is_empty()
+                });
+                if !matches {
+                    violations.push(format!("{at}: {value} doesn't match any of the schemas"));
+                }
+            }
+        }
+
+        if value.is_null() {
+            return;
+        }
+
+        if let Some(values) = schema["enum"].as_array() {
+            if !values.contains(value) {
+                violations.push(format!("{at}: {value} is not a documented value"));
+            }
+        }
+
+        let valid_type = match schema["type"].as_str() {
+            Some("object") => value.is_object(),
+            Some("array") => value.is_array(),
+            Some("string") => value.is_string(),
+            Some("integer") => value.is_i64() || value.is_u64(),
+            Some("number") => value.is_number(),
+            Some("boolean") => value.is_boolean(),
+            _ => true,
+        };
+        if !valid_type {
+            violations.push(format!(
+                "{at}: expected {}, found {value}",
+                schema["type"].as_str().unwrap_or_default()
+            ));
+            return;
+        }
+
+        if let Some(object) = value.as_object() {
+            for name in schema["required"].as_array().into_iter().flatten() {
+                let name = name.as_str().unwrap_or_default();
+                if !object.contains_key(name) {
+                    violations.push(format!("{at}: the required property '{name}' is missing"));
+                }
+            }
+            if let Some(properties) = schema["properties"].as_object() {
+                for (name, property) in properties {
+                    if let Some(value) = object.get(name) {
+                        self.validate(property, value, &format!("{at}.{name}"), violations);
+                    }
+                }
+            }
+        }
+
+        if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+            for (i, item) in array.iter().enumerate() {
+                self.validate(items, item, &format!("{at}[{i}]"), violations);
+            }
+        }
+    }
+}
+
+impl TestApp {
+    /// Send a request to the API and check its response against the OpenAPI spec.
+    ///
+    /// # Description
+    ///
+    /// `path` is relative to the base URL of the API, and it may include a query string. The response is returned
+    /// when it fulfills the contract, so tests can check its content.
+    pub async fn contract_test(
+        &self,
+        checker: &ContractChecker,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<ObservedResponse, Vec<String>> {
+        let mut request = self
+            .api_client
+            .request(method.clone(), format!("{}{path}", self.address))
+            .header("Origin", CONTRACT_TEST_ORIGIN);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = ObservedResponse::from_response(
+            request
+                .send()
+                .await
+                .unwrap_or_else(|_| panic!("Failed to execute {method} {path}")),
+        )
+        .await;
+
+        checker
+            .check(method.as_str(), path, &response)
+            .map(|_| response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes;
+    use actix_web::{
+        test::{call_service, init_service, read_body, TestRequest},
+        App,
+    };
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn spec() -> Value {
+        json!({
+            "paths": {
+                "/item/{id}": {
+                    "get": {
+                        "responses": {
+                            "200": {
+                                "headers": {"Last-Modified": {}},
+                                "content": {"application/json": {"schema": {"$ref": "#/components/schemas/Item"}}}
+                            },
+                            "404": {}
+                        }
+                    }
+                },
+                "/item/latest": {"get": {"responses": {"204": {}}}}
+            },
+            "components": {
+                "schemas": {
+                    "Item": {
+                        "type": "object",
+                        "required": ["name", "tags"],
+                        "properties": {
+                            "name": {"type": "string"},
+                            "rating": {"type": "integer", "nullable": true},
+                            "category": {"allOf": [{"$ref": "#/components/schemas/Category"}], "nullable": true},
+                            "tags": {"type": "array", "items": {"type": "string", "enum": ["a", "b"]}}
+                        }
+                    },
+                    "Category": {"type": "string", "enum": ["easy", "pro"]}
+                }
+            }
+        })
+    }
+
+    fn json_response(status: u16, body: Value) -> ObservedResponse {
+        ObservedResponse::new(
+            status,
+            [
+                ("Content-Type", "application/json"),
+                ("Last-Modified", "Wed, 21 Oct 2015 07:28:00 GMT"),
+            ],
+            body.to_string().as_bytes(),
+        )
+    }
+
+    #[test]
+    fn documented_responses_pass() {
+        let checker = ContractChecker::new(spec());
+        let response = json_response(
+            200,
+            json!({"name": "x", "rating": null, "category": null, "tags": ["a"]}),
+        );
+        assert_eq!(checker.check("GET", "/item/1?full=true", &response), Ok(()));
+        let response = ObservedResponse::new(204, [], b"");
+        assert_eq!(checker.check("GET", "/item/latest", &response), Ok(()));
+    }
+
+    #[test]
+    fn drifts_are_reported() {
+        let checker = ContractChecker::new(spec());
+
+        let response = ObservedResponse::new(500, [], b"");
+        assert!(checker.check("GET", "/item/1", &response).is_err());
+        assert!(checker.check("POST", "/item/1", &response).is_err());
+        assert!(checker.check("GET", "/other", &response).is_err());
+
+        let mut response =
+            json_response(200, json!({"name": 1, "category": "hard", "tags": ["c"]}));
+        response.headers.remove("last-modified");
+        let violations = checker.check("GET", "/item/1", &response).unwrap_err();
+        assert_eq!(violations.len(), 4, "{violations:#?}");
+    }
+
+    #[actix_web::test]
+    async fn public_handlers_fulfill_the_contract() {
+        let checker = ContractChecker::default();
+        let app = init_service(
+            App::new()
+                .service(routes::echo)
+                .service(routes::recipe::classify_recipe),
+        )
+        .await;
+
+        let cases = [
+            (TestRequest::get().uri("/echo"), "GET", "/echo"),
+            (
+                TestRequest::post()
+                    .uri("/classify")
+                    .set_json(json!({"steps": ["Shake."], "equipment": ["shaker"]})),
+                "POST",
+                "/recipe/classify",
+            ),
+        ];
+
+        for (request, method, path) in cases {
+            let response = call_service(&app, request.to_request()).await;
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.as_str().to_owned(), value.to_str().unwrap().to_owned()))
+                .collect::<Vec<_>>();
+            let body = read_body(response).await;
+            let response = ObservedResponse::new(
+                status,
+                headers.iter().map(|(n, v)| (n.as_str(), v.as_str())),
+                &body,
+            );
+            assert_eq!(checker.check(method, path, &response), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,490 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Lifecycle of the DBs used by the integration tests.
+//!
+//! # Description
+//!
+//! Every [crate::testing::TestApp] runs against its own DB, named `lacoctelera_test_<uuid>`. The DB is owned by a
+//! [TestDatabase], which drops it from the server when the test finishes (even when the test panics). Set the
+//! environment variable `TEST_KEEP_DB` to keep the DBs around, i.e. to inspect them after a failing test.
+//!
+//! Running all the migrations for every test is slow. When the environment variable `TEST_DB_TEMPLATE` is set, the
+//! migrations are applied only once to a template DB (`lacoctelera_template_<hash of the migrations>`), and the DB of
+//! every test is cloned from it.
+//!
+//! DBs whose teardown didn't run (i.e. a test run that was killed) are dropped by [cleanup_test_databases], which is
+//! run once per process by [crate::testing::spawn_app_with_settings].
+//!
+//! An even faster alternative is the transaction-per-test mode ([IsolationMode::Transaction]), selected by setting
+//! the environment variable `TEST_DB_ISOLATION=transaction`. All the tests share a single migrated DB
+//! (`lacoctelera_shared_<hash of the migrations>`), and every application gets a pool with a single connection to it,
+//! which is wrapped in a transaction that is never committed. The transactions of the application are turned into
+//! savepoints of such transaction, and everything is rolled back when the connection is closed at the end of the
+//! test. Some caveats apply:
+//! - The test and the application share the pool ([crate::testing::TestApp::db_pool]), so queries are served one at a
+//!   time. Code that runs a query using the pool while holding a transaction would wait for the connection forever, so
+//!   acquiring a connection times out after [TX_ACQUIRE_TIMEOUT].
+//! - Tests that insert rows with the same keys (i.e. the same tags) wait for each other, as the locks of the rows are
+//!   held until the end of the test. This includes the rows of `CollectionChange`, which are updated by every write of
+//!   recipes or ingredients, so tests that modify the same collection run one after the other.
+
+use crate::configuration::DataBaseSettings;
+use actix_web::rt::Runtime;
+use once_cell::sync::OnceCell;
+use sha2::{Digest, Sha256};
+use sqlx::{
+    migrate::Migrator,
+    mysql::{MySqlPoolOptions, MySqlTransactionManager},
+    Connection, Executor, MySqlConnection, MySqlPool, Row, TransactionManager,
+};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Prefix of the names of the DBs created by the tests.
+pub const TEST_DB_PREFIX: &str = "lacoctelera_test_";
+
+/// Prefix of the names of the template DBs.
+pub const TEMPLATE_DB_PREFIX: &str = "lacoctelera_template_";
+
+/// Prefix of the names of the DBs shared by the tests run in [IsolationMode::Transaction].
+pub const SHARED_DB_PREFIX: &str = "lacoctelera_shared_";
+
+/// Time to wait for the connection of the DB in [IsolationMode::Transaction].
+pub const TX_ACQUIRE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Age of the orphaned DBs dropped by [cleanup_test_databases] when spawning the first application of a process.
+pub const ORPHAN_DB_AGE: Duration = Duration::from_secs(3600);
+
+/// Name of the template DB of every process, once it is ready.
+static TEMPLATE: OnceCell<String> = OnceCell::new();
+
+/// Name of the shared DB of every process, once it is ready.
+static SHARED: OnceCell<String> = OnceCell::new();
+
+/// Cleanup pass of the process.
+static CLEANUP: OnceCell<()> = OnceCell::new();
+
+/// Ways of isolating the data of the integration tests.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum IsolationMode {
+    /// Every test gets its own DB.
+    #[default]
+    Database,
+    /// Every test runs within a transaction of a shared DB that is rolled back at the end.
+    Transaction,
+}
+
+impl IsolationMode {
+    /// Read the mode from the environment variable `TEST_DB_ISOLATION` (`database` or `transaction`).
+    pub fn from_env() -> Self {
+        match std::env::var("TEST_DB_ISOLATION").as_deref() {
+            Ok("transaction") | Ok("tx") => IsolationMode::Transaction,
+            _ => IsolationMode::Database,
+        }
+    }
+}
+
+/// DB owned by a test, which is dropped along with the [TestDatabase].
+///
+/// # Description
+///
+/// In [IsolationMode::Transaction] the DB is shared, so nothing is dropped: the changes of the test are rolled back
+/// when the connection of the pool is closed.
+#[derive(Debug)]
+pub struct TestDatabase {
+    settings: DataBaseSettings,
+    isolation: IsolationMode,
+    keep: bool,
+}
+
+impl TestDatabase {
+    /// Build a random name for a new test DB.
+    ///
+    /// # Description
+    ///
+    /// The name includes an UUID v7, so the time in which the DB was created can be retrieved from its name (see
+    /// [test_db_created_at]).
+    pub fn random_name() -> String {
+        format!("{TEST_DB_PREFIX}{}", Uuid::now_v7().simple())
+    }
+
+    /// Create a brand new DB, migrated to the latest version of the schema.
+    ///
+    /// # Description
+    ///
+    /// The DB is named after [DataBaseSettings::db_name]. When the variable `TEST_DB_TEMPLATE` is set, the DB is
+    /// cloned from the template DB rather than migrated from scratch.
+    ///
+    /// In [IsolationMode::Transaction] (see [IsolationMode::from_env]), no DB is created, and the returned pool
+    /// connects to the shared DB within a transaction.
+    pub async fn create(settings: &DataBaseSettings) -> (TestDatabase, MySqlPool) {
+        let isolation = IsolationMode::from_env();
+
+        if isolation == IsolationMode::Transaction {
+            let mut settings = settings.clone();
+            settings.db_name = shared_database(&settings).to_owned();
+            let pool = transaction_pool(&settings)
+                .await
+                .expect("Failed to connect to MariaDB.");
+            let database = TestDatabase {
+                settings,
+                isolation,
+                keep: true,
+            };
+
+            return (database, pool);
+        }
+
+        let pool = if std::env::var("TEST_DB_TEMPLATE").is_ok() {
+            let template = template_database(settings);
+            clone_database(settings, template)
+                .await
+                .expect("Failed to clone the template DB.");
+            MySqlPool::connect_with(settings.build_db_conn_with_db())
+                .await
+                .expect("Failed to connect to MariaDB.")
+        } else {
+            crate::testing::configure_database(settings).await
+        };
+
+        let database = TestDatabase {
+            settings: settings.clone(),
+            isolation,
+            keep: std::env::var("TEST_KEEP_DB").is_ok(),
+        };
+
+        (database, pool)
+    }
+
+    /// Name of the DB.
+    pub fn name(&self) -> &str {
+        &self.settings.db_name
+    }
+
+    /// Isolation of the data of the test.
+    pub fn isolation(&self) -> IsolationMode {
+        self.isolation
+    }
+
+    /// Drop the DB from the server.
+    pub async fn drop_database(&self) -> Result<(), sqlx::Error> {
+        drop_database(&self.settings, self.name()).await
+    }
+}
+
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        if self.keep {
+            if self.isolation == IsolationMode::Database {
+                info!("Keeping the test DB {}", self.name());
+            }
+            return;
+        }
+
+        // Drop runs within the runtime of the test, which can't be blocked waiting for a future. The DB is dropped
+        // from a new runtime in a different thread.
+        let settings = self.settings.clone();
+        let result = std::thread::spawn(move || {
+            Runtime::new()
+                .map_err(|e| e.to_string())?
+                .block_on(drop_database(&settings, &settings.db_name))
+                .map_err(|e| e.to_string())
+        })
+        .join();
+
+        match result {
+            Ok(Ok(())) => debug!("Test DB {} dropped", self.name()),
+            Ok(Err(e)) => warn!("Failed to drop the test DB {}: {e}", self.name()),
+            Err(_) => warn!("Failed to drop the test DB {}", self.name()),
+        }
+    }
+}
+
+/// Retrieve the time in which a test DB was created from its name.
+///
+/// # Description
+///
+/// Returns `None` when the name doesn't belong to a DB created by [TestDatabase::random_name].
+pub fn test_db_created_at(name: &str) -> Option<SystemTime> {
+    let id = Uuid::try_parse(name.strip_prefix(TEST_DB_PREFIX)?).ok()?;
+    let (secs, nanos) = id.get_timestamp()?.to_unix();
+
+    Some(SystemTime::UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+/// Drop the test DBs created more than `older_than` ago.
+///
+/// # Description
+///
+/// Only the DBs named by [TestDatabase::random_name] are considered. Returns the names of the dropped DBs.
+pub async fn cleanup_test_databases(
+    settings: &DataBaseSettings,
+    older_than: Duration,
+) -> Result<Vec<String>, sqlx::Error> {
+    let mut conn = MySqlConnection::connect_with(&settings.build_db_conn_without_db()).await?;
+    let names = sqlx::query("SHOW DATABASES LIKE ?")
+        .bind(format!("{}%", TEST_DB_PREFIX.replace('_', "\\_")))
+        .fetch_all(&mut conn)
+        .await?
+        .iter()
+        .map(|row| row.get::<String, _>(0))
+        .collect::<Vec<_>>();
+    let now = SystemTime::now();
+
+    let mut dropped = Vec::new();
+    for name in names {
+        let is_orphan = test_db_created_at(&name)
+            .is_some_and(|t| now.duration_since(t).unwrap_or_default() > older_than);
+        if is_orphan {
+            conn.execute(format!("DROP DATABASE IF EXISTS `{name}`").as_str())
+                .await?;
+            dropped.push(name);
+        }
+    }
+
+    if !dropped.is_empty() {
+        info!("Dropped {} orphaned test DBs", dropped.len());
+    }
+
+    Ok(dropped)
+}
+
+/// Run [cleanup_test_databases] once per process.
+pub(crate) fn cleanup_once(settings: &DataBaseSettings) {
+    CLEANUP.get_or_init(|| {
+        let settings = settings.clone();
+        let result = std::thread::spawn(move || {
+            Runtime::new()
+                .map_err(|e| e.to_string())?
+                .block_on(cleanup_test_databases(&settings, ORPHAN_DB_AGE))
+                .map_err(|e| e.to_string())
+        })
+        .join();
+
+        if let Ok(Err(e)) = result {
+            warn!("Failed to clean the orphaned test DBs: {e}");
+        }
+    });
+}
+
+/// Name of the template DB for the current set of migrations.
+pub fn template_name() -> String {
+    format!("{TEMPLATE_DB_PREFIX}{}", migrations_hash())
+}
+
+/// Name of the DB shared by the tests run in [IsolationMode::Transaction], for the current set of migrations.
+pub fn shared_name() -> String {
+    format!("{SHARED_DB_PREFIX}{}", migrations_hash())
+}
+
+fn migrations_hash() -> String {
+    let mut hasher = Sha256::new();
+    for migration in MIGRATOR.migrations.iter() {
+        hasher.update(migration.version.to_le_bytes());
+        hasher.update(&migration.checksum);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>()
+}
+
+/// Get the name of the template DB, creating it when it doesn't exist yet.
+fn template_database(settings: &DataBaseSettings) -> &'static str {
+    TEMPLATE.get_or_init(|| prepare_once(settings, template_name()))
+}
+
+/// Get the name of the shared DB, creating it when it doesn't exist yet.
+fn shared_database(settings: &DataBaseSettings) -> &'static str {
+    SHARED.get_or_init(|| prepare_once(settings, shared_name()))
+}
+
+fn prepare_once(settings: &DataBaseSettings, name: String) -> String {
+    let settings = settings.clone();
+    std::thread::spawn(move || {
+        Runtime::new()
+            .expect("Failed to build a runtime")
+            .block_on(prepare_migrated_database(&settings, name))
+    })
+    .join()
+    .expect("Failed to prepare a migrated DB.")
+    .unwrap_or_else(|e| panic!("Failed to prepare a migrated DB: {e}"))
+}
+
+/// Create and migrate a DB that is shared by many tests, unless it is already there.
+///
+/// # Description
+///
+/// Many test processes might run at the same time, so the DB is prepared holding a lock of the server.
+async fn prepare_migrated_database(
+    settings: &DataBaseSettings,
+    name: String,
+) -> Result<String, sqlx::Error> {
+    let mut conn = MySqlConnection::connect_with(&settings.build_db_conn_without_db()).await?;
+
+    sqlx::query("SELECT GET_LOCK(?, 60)")
+        .bind(&name)
+        .execute(&mut conn)
+        .await?;
+
+    let applied = sqlx::query(
+        "SELECT COUNT(*) FROM `information_schema`.`tables` WHERE `table_schema` = ? AND `table_name` = '_sqlx_migrations'",
+    )
+    .bind(&name)
+    .fetch_one(&mut conn)
+    .await?
+    .get::<i64, _>(0);
+
+    let result = if applied == 0 {
+        info!("Preparing the DB {name}");
+        let mut shared = settings.clone();
+        shared.db_name = name.clone();
+        migrate_from_scratch(&mut conn, &shared).await
+    } else {
+        Ok(())
+    };
+
+    sqlx::query("SELECT RELEASE_LOCK(?)")
+        .bind(&name)
+        .execute(&mut conn)
+        .await?;
+
+    result.map(|_| name)
+}
+
+async fn migrate_from_scratch(
+    conn: &mut MySqlConnection,
+    settings: &DataBaseSettings,
+) -> Result<(), sqlx::Error> {
+    conn.execute(format!("DROP DATABASE IF EXISTS `{}`", settings.db_name).as_str())
+        .await?;
+    conn.execute(format!("CREATE DATABASE `{}`", settings.db_name).as_str())
+        .await?;
+
+    let pool = MySqlPool::connect_with(settings.build_db_conn_with_db()).await?;
+    let result = MIGRATOR.run(&pool).await;
+    pool.close().await;
+
+    if let Err(e) = result {
+        error!("{e}");
+        // Don't leave a half migrated DB around.
+        conn.execute(format!("DROP DATABASE IF EXISTS `{}`", settings.db_name).as_str())
+            .await?;
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+/// Create the DB of `settings` as a copy of `template` (schema and content).
+async fn clone_database(settings: &DataBaseSettings, template: &str) -> Result<(), sqlx::Error> {
+    let name = &settings.db_name;
+    let mut conn = MySqlConnection::connect_with(&settings.build_db_conn_without_db()).await?;
+
+    conn.execute(format!("CREATE DATABASE `{name}`").as_str())
+        .await?;
+    conn.execute(format!("USE `{name}`").as_str()).await?;
+    // Tables are created in alphabetical order, regardless of their foreign keys.
+    conn.execute("SET FOREIGN_KEY_CHECKS = 0").await?;
+
+    let tables = sqlx::query(
+        "SELECT `table_name` FROM `information_schema`.`tables` WHERE `table_schema` = ? AND `table_type` = 'BASE TABLE' ORDER BY `table_name`",
+    )
+    .bind(template)
+    .fetch_all(&mut conn)
+    .await?
+    .iter()
+    .map(|row| row.get::<String, _>(0))
+    .collect::<Vec<_>>();
+
+    for table in tables {
+        // References to other tables of the template are not qualified, so they point to the new DB.
+        let create = sqlx::query(&format!("SHOW CREATE TABLE `{template}`.`{table}`"))
+            .fetch_one(&mut conn)
+            .await?
+            .get::<String, _>(1);
+        conn.execute(create.as_str()).await?;
+        conn.execute(
+            format!("INSERT INTO `{name}`.`{table}` SELECT * FROM `{template}`.`{table}`").as_str(),
+        )
+        .await?;
+    }
+
+    conn.execute("SET FOREIGN_KEY_CHECKS = 1").await?;
+    debug!("Test DB {name} cloned from {template}");
+
+    Ok(())
+}
+
+/// Build a pool with a single connection, which runs within a transaction that is never committed.
+async fn transaction_pool(settings: &DataBaseSettings) -> Result<MySqlPool, sqlx::Error> {
+    MySqlPoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(TX_ACQUIRE_TIMEOUT)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .after_connect(|conn, _| {
+            // The transaction is opened without a guard, so the transactions started later on (i.e. `pool.begin()`)
+            // become savepoints, and no guard rolls it back when the connection returns to the pool.
+            Box::pin(async move { MySqlTransactionManager::begin(conn).await })
+        })
+        .connect_with(settings.build_db_conn_with_db())
+        .await
+}
+
+async fn drop_database(settings: &DataBaseSettings, name: &str) -> Result<(), sqlx::Error> {
+    let mut conn = MySqlConnection::connect_with(&settings.build_db_conn_without_db()).await?;
+    conn.execute(format!("DROP DATABASE IF EXISTS `{name}`").as_str())
+        .await?;
+    conn.close().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn random_names_carry_the_creation_time() {
+        let before = SystemTime::now() - Duration::from_secs(1);
+        let name = TestDatabase::random_name();
+        assert!(name.starts_with(TEST_DB_PREFIX));
+        // MariaDB limits the length of the names of the DBs to 64 characters.
+        assert!(name.len() <= 64);
+
+        let created_at = test_db_created_at(&name).expect("Failed to parse the name");
+        assert!(created_at >= before);
+    }
+
+    #[rstest]
+    #[case("test_cocktail")]
+    #[case("lacoctelera_test_")]
+    #[case("lacoctelera_test_not-an-uuid")]
+    #[case("lacoctelera_template_0123456789abcdef")]
+    // UUID v4 carry no timestamp.
+    #[case("lacoctelera_test_5f9e2c4e8a2b4d0c9c1e6a8f3b7d2e10")]
+    fn foreign_dbs_are_ignored(#[case] name: &str) {
+        assert_eq!(test_db_created_at(name), None);
+    }
+
+    #[rstest]
+    fn template_depends_on_the_migrations() {
+        let name = template_name();
+        assert!(name.starts_with(TEMPLATE_DB_PREFIX));
+        assert_eq!(name, template_name());
+        assert_eq!(
+            name.strip_prefix(TEMPLATE_DB_PREFIX),
+            shared_name().strip_prefix(SHARED_DB_PREFIX)
+        );
+    }
+}
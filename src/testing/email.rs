@@ -0,0 +1,151 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Capture of the emails sent by the application.
+//!
+//! # Description
+//!
+//! Applications spawned by the integration tests don't send emails to Mailjet. They use a [CapturingEmailSender]
+//! instead, which is found at [crate::testing::TestApp::emails], so tests can check the emails that were sent:
+//!
+//! ```ignore
+//! let email = test_app.emails.last_to("jane@mail.com").expect("No email was sent");
+//! let link = email_links(&email.text_body)[0].clone();
+//! ```
+
+use crate::{
+    domain::ServerError,
+    utils::mailing::{Email, EmailSender, SendFuture},
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+/// Address used as sender by the [CapturingEmailSender]s.
+pub const TEST_SENDER_ADDRESS: &str = "backend@lacoctelera.test";
+
+static LINK_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"https?://\S+").unwrap());
+
+/// [EmailSender] that keeps the emails rather than sending them.
+#[derive(Debug, Default)]
+pub struct CapturingEmailSender {
+    emails: Mutex<Vec<Email>>,
+    failing: AtomicBool,
+}
+
+impl CapturingEmailSender {
+    pub fn new() -> Self {
+        CapturingEmailSender::default()
+    }
+
+    /// Simulate a provider that rejects every email. Rejected emails are not captured.
+    pub fn set_failing(&self, failing: bool) {
+        self.failing.store(failing, Ordering::Relaxed);
+    }
+
+    /// Emails sent so far.
+    pub fn sent(&self) -> Vec<Email> {
+        self.emails.lock().unwrap().clone()
+    }
+
+    /// Emails sent to `recipient` so far.
+    pub fn sent_to(&self, recipient: &str) -> Vec<Email> {
+        self.sent()
+            .into_iter()
+            .filter(|e| e.to == recipient)
+            .collect()
+    }
+
+    /// Latest email sent to `recipient`.
+    pub fn last_to(&self, recipient: &str) -> Option<Email> {
+        self.sent_to(recipient).pop()
+    }
+}
+
+impl EmailSender for CapturingEmailSender {
+    fn sender_address(&self) -> Option<&str> {
+        Some(TEST_SENDER_ADDRESS)
+    }
+
+    fn sender_name(&self) -> Option<&str> {
+        Some("La Coctelera")
+    }
+
+    fn send<'a>(&'a self, email: &'a Email) -> SendFuture<'a> {
+        Box::pin(async move {
+            if self.failing.load(Ordering::Relaxed) {
+                return Err(ServerError::EmailClientError);
+            }
+
+            let mut emails = self.emails.lock().unwrap();
+            emails.push(email.clone());
+
+            Ok(Some(format!("captured-{}", emails.len())))
+        })
+    }
+}
+
+/// Extract the links found in the body of an email.
+pub fn email_links(body: &str) -> Vec<String> {
+    LINK_REGEX
+        .find_iter(body)
+        .map(|m| m.as_str().to_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{domain::ClientId, utils::mailing::MailCorrelation};
+    use pretty_assertions::assert_eq;
+    use uuid::Uuid;
+
+    fn email(to: &str) -> Email {
+        Email {
+            to: to.to_owned(),
+            to_name: None,
+            subject: "Verify your email".to_owned(),
+            text_body:
+                "Visit http://localhost:9090/api/v0/token/request/validate?email=a@b.com&token=abc"
+                    .to_owned(),
+            correlation: MailCorrelation::new(Uuid::now_v7(), &ClientId::new()),
+        }
+    }
+
+    #[actix_web::test]
+    async fn emails_are_captured() {
+        let sender = CapturingEmailSender::new();
+        assert_eq!(
+            sender
+                .send(&email("jane@mail.com"))
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("captured-1")
+        );
+        sender.send(&email("john@mail.com")).await.unwrap();
+
+        assert_eq!(sender.sent().len(), 2);
+        assert_eq!(sender.sent_to("jane@mail.com").len(), 1);
+        assert!(sender.last_to("nobody@mail.com").is_none());
+
+        sender.set_failing(true);
+        assert!(sender.send(&email("jane@mail.com")).await.is_err());
+        assert_eq!(sender.sent().len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn links_are_extracted() {
+        assert_eq!(
+            email_links(&email("jane@mail.com").text_body),
+            vec!["http://localhost:9090/api/v0/token/request/validate?email=a@b.com&token=abc"]
+        );
+        assert!(email_links("No links here").is_empty());
+    }
+}
@@ -4,19 +4,32 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use lacoctelera::{
+//! Fixtures of the resources of the API.
+//!
+//! # Description
+//!
+//! The fixtures are described using YAML files, which are embedded in the crate. [FixtureSeeder] loads the requested
+//! fixtures, and optionally, seeds them in the DB of a [crate::testing::TestApp]. Seeded fixtures get the IDs
+//! assigned by the DB.
+
+use crate::{
     domain::{
-        Author, AuthorBuilder, QuantityUnit, Recipe, RecipeCategory, RecipeContains, SocialProfile,
-        StarRate, Tag,
+        search_text::normalize_search_text, Author, AuthorBuilder, Equipment, QuantityUnit, Recipe,
+        RecipeCategory, RecipeContains, SocialProfile, StarRate, Tag,
     },
     Ingredient,
 };
 use serde::Deserialize;
 use sqlx::{Executor, MySqlPool};
-use std::{fs, iter::zip};
+use std::iter::zip;
 use tracing::{debug, error};
 use uuid::Uuid;
 
+const AUTHORS: &str = include_str!("fixtures/authors.yml");
+const SOCIAL_PROFILES: &str = include_str!("fixtures/social_profiles.yml");
+const INGREDIENTS: &str = include_str!("fixtures/ingredients.yml");
+const RECIPES: &str = include_str!("fixtures/recipes.yml");
+
 pub struct FixtureSeeder<'a> {
     db_pool: &'a MySqlPool,
     seed_authors: Option<bool>,
@@ -42,7 +55,6 @@ impl<'a> FixtureSeeder<'a> {
         self
     }
 
-    #[allow(dead_code)]
     pub fn with_social_profiles(mut self, seed: bool) -> FixtureSeeder<'a> {
         self.seed_social_profiles = Some(seed);
 
@@ -120,9 +132,7 @@ pub struct AuthorFixture {
 
 impl AuthorFixture {
     pub fn load(&mut self) -> Result<(), String> {
-        let file =
-            fs::read_to_string("tests/api/fixtures/authors.yml").map_err(|e| e.to_string())?;
-        self.valid_fixtures = serde_yml::from_str(&file).map_err(|e| e.to_string())?;
+        self.valid_fixtures = serde_yml::from_str(AUTHORS).map_err(|e| e.to_string())?;
 
         Ok(())
     }
@@ -143,17 +153,8 @@ impl AuthorFixture {
             })?;
 
             transaction.execute(
-            sqlx::query!(
-                r#"INSERT INTO `Author`(`id`, `name`, `surname`, `email`, `shareable`, `description`, `website`)
-                VALUES (?,?,?,?,?,?,?)"#,
-                id.to_string(),
-                author.name(),
-                author.surname(),
-                author.email(),
-                author.shareable(),
-                author.description(),
-                author.website()
-            )).await.map_err(|e| {error!("{e}"); e.to_string()})?;
+            sqlx::query(r#"INSERT INTO `Author`(`id`, `name`, `surname`, `email`, `shareable`, `description`, `website`)
+                VALUES (?,?,?,?,?,?,?)"#).bind(id.to_string()).bind(author.name()).bind(author.surname()).bind(author.email()).bind(author.shareable()).bind(author.description()).bind(author.website())).await.map_err(|e| {error!("{e}"); e.to_string()})?;
 
             ids.push(id);
 
@@ -161,14 +162,8 @@ impl AuthorFixture {
                 if let Some(profiles) = author.social_profiles() {
                     for profile in profiles {
                         transaction.execute(
-                        sqlx::query!(
-                            r#"INSERT INTO `AuthorHashSocialProfile`(`id`, `provider_name`, `user_name`, `author_id`)
-                            VALUES (?,?,?,?)"#,
-                            Uuid::now_v7().to_string(),
-                            profile.provider_name,
-                            profile.website,
-                            id.to_string(),
-                        )).await.map_err(|e| {error!("{e}"); e.to_string()})?;
+                        sqlx::query(r#"INSERT INTO `AuthorHashSocialProfile`(`id`, `provider_name`, `user_name`, `author_id`)
+                            VALUES (?,?,?,?)"#).bind(Uuid::now_v7().to_string()).bind(&profile.provider_name).bind(&profile.website).bind(id.to_string())).await.map_err(|e| {error!("{e}"); e.to_string()})?;
                     }
                 }
             }
@@ -179,12 +174,12 @@ impl AuthorFixture {
             })?;
         }
 
-        for it in 0..ids.len() {
+        for (fixture, id) in zip(self.valid_fixtures.iter_mut(), ids) {
             let author = AuthorBuilder::default()
-                .set_id(&ids[it].to_string())
+                .set_id(&id.to_string())
                 .build()
                 .expect("Wrong ID");
-            self.valid_fixtures[it].update_from(&author);
+            fixture.update_from(&author);
         }
 
         Ok(())
@@ -198,23 +193,19 @@ pub struct SocialProfileFixture {
 
 impl SocialProfileFixture {
     pub fn load(&mut self) -> Result<(), String> {
-        let file = fs::read_to_string("tests/api/fixtures/social_profiles.yml")
-            .map_err(|e| e.to_string())?;
-        self.valid_fixtures = serde_yml::from_str(&file).map_err(|e| e.to_string())?;
+        self.valid_fixtures = serde_yml::from_str(SOCIAL_PROFILES).map_err(|e| e.to_string())?;
 
         Ok(())
     }
 
     pub async fn seed(&self, pool: &MySqlPool) -> Result<(), String> {
         for profile in self.valid_fixtures.iter() {
-            sqlx::query!(
-                "INSERT INTO `SocialProfile` VALUES (?, ?)",
-                profile.provider_name,
-                profile.website
-            )
-            .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            sqlx::query("INSERT INTO `SocialProfile` VALUES (?, ?)")
+                .bind(&profile.provider_name)
+                .bind(&profile.website)
+                .execute(pool)
+                .await
+                .map_err(|e| e.to_string())?;
         }
 
         Ok(())
@@ -228,9 +219,7 @@ pub struct IngredientFixture {
 
 impl IngredientFixture {
     pub fn load(&mut self) -> Result<(), String> {
-        let file =
-            fs::read_to_string("tests/api/fixtures/ingredients.yml").map_err(|e| e.to_string())?;
-        self.valid_fixtures = serde_yml::from_str(&file).map_err(|e| e.to_string())?;
+        self.valid_fixtures = serde_yml::from_str(INGREDIENTS).map_err(|e| e.to_string())?;
 
         Ok(())
     }
@@ -239,13 +228,15 @@ impl IngredientFixture {
         for ingredient in self.valid_fixtures.iter_mut() {
             ingredient.set_id(Uuid::now_v7());
 
-            sqlx::query!(
-                "INSERT INTO `Ingredient` VALUES (?,?,?,?)",
-                ingredient.id().unwrap().to_string(),
-                ingredient.name(),
-                ingredient.category().to_str().to_owned(),
-                ingredient.desc(),
+            sqlx::query(
+                "INSERT INTO `Ingredient`(`id`, `name`, `search_name`, `category`, `description`) \
+                VALUES (?,?,?,?,?)",
             )
+            .bind(ingredient.id().unwrap().to_string())
+            .bind(ingredient.name())
+            .bind(normalize_search_text(ingredient.name()))
+            .bind(ingredient.category().to_str().to_owned())
+            .bind(ingredient.desc())
             .execute(pool)
             .await
             .map_err(|e| e.to_string())?;
@@ -272,13 +263,14 @@ struct SimpleRecipe {
     pub category: RecipeCategory,
     pub rating: StarRate,
     pub steps: Vec<String>,
+    #[serde(default)]
+    pub equipment: Vec<Equipment>,
+    pub prep_time_minutes: Option<u16>,
 }
 
 impl RecipeFixture {
     pub fn load(&mut self) -> Result<(), String> {
-        let file =
-            fs::read_to_string("tests/api/fixtures/recipes.yml").map_err(|e| e.to_string())?;
-        self.simple_recipe = serde_yml::from_str(&file).map_err(|e| e.to_string())?;
+        self.simple_recipe = serde_yml::from_str(RECIPES).map_err(|e| e.to_string())?;
 
         Ok(())
     }
@@ -319,31 +311,52 @@ impl RecipeFixture {
 
         let mut transaction = pool.begin().await.expect("Failed to acquire DB");
 
-        transaction.execute(sqlx::query!(
-            r#"INSERT INTO `Cocktail`(`id`,`name`,`description`,`category`,`steps`,`image_id`,`url`,`rating`,`owner`)
-            VALUES (?,?,?,?,?,?,?,?,?)"#,
-            recipe_id.to_string(),
-            template_recipe.name,
-            template_recipe.description,
-            template_recipe.category.to_string(),
-            template_recipe.steps.join("/n"),
-            template_recipe.image_id,
-            template_recipe.url,
-            template_recipe.rating.to_string(),
-            authors[0].id().expect("Failed to extract author's ID"),
-        ))
-        .await
-        .map_err(|e| e.to_string())?;
+        transaction
+            .execute(
+                sqlx::query(
+                    r#"INSERT INTO `Cocktail`(`id`,`name`,`search_name`,`description`,`category`,`steps`,`image_id`,
+                    `url`,`rating`,`owner`,`prep_time_minutes`)
+                    VALUES (?,?,?,?,?,?,?,?,?,?,?)"#,
+                )
+                .bind(recipe_id.to_string())
+                .bind(&template_recipe.name)
+                .bind(normalize_search_text(&template_recipe.name))
+                .bind(&template_recipe.description)
+                .bind(template_recipe.category.to_string())
+                .bind(template_recipe.steps.join("/n"))
+                .bind(&template_recipe.image_id)
+                .bind(&template_recipe.url)
+                .bind(template_recipe.rating.value())
+                .bind(authors[0].id().expect("Failed to extract author's ID"))
+                .bind(template_recipe.prep_time_minutes),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
 
         for ingredient in included_ingredients {
             transaction
-                .execute(sqlx::query!(
-                    r#"INSERT INTO `UsedIngredient`(`cocktail_id`, `ingredient_id`, `amount`)
+                .execute(
+                    sqlx::query(
+                        r#"INSERT INTO `UsedIngredient`(`cocktail_id`, `ingredient_id`, `amount`)
                     VALUES (?,?,?)"#,
-                    recipe_id.to_string(),
-                    ingredient.ingredient_id.to_string(),
-                    &format!("{} {}", ingredient.quantity, ingredient.unit),
-                ))
+                    )
+                    .bind(recipe_id.to_string())
+                    .bind(ingredient.ingredient_id.to_string())
+                    .bind(format!("{} {}", ingredient.quantity, ingredient.unit)),
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        for equipment in template_recipe.equipment.iter() {
+            transaction
+                .execute(
+                    sqlx::query(
+                        "INSERT INTO `RecipeEquipment`(`cocktail_id`, `equipment`) VALUES (?,?)",
+                    )
+                    .bind(recipe_id.to_string())
+                    .bind(equipment.to_string()),
+                )
                 .await
                 .map_err(|e| e.to_string())?;
         }
@@ -353,39 +366,43 @@ impl RecipeFixture {
             template_recipe.author_tags.iter(),
         ) {
             transaction
-                .execute(sqlx::query!(
-                    "INSERT IGNORE INTO `Tag` VALUES (?), (?)",
-                    tag.0,
-                    tag.1,
-                ))
+                .execute(
+                    sqlx::query("INSERT IGNORE INTO `Tag` VALUES (?), (?)")
+                        .bind(tag.0)
+                        .bind(tag.1),
+                )
                 .await
                 .map_err(|e| e.to_string())?;
         }
 
         for tag in template_recipe.tags.iter() {
             transaction
-                .execute(sqlx::query!(
-                    r#"INSERT INTO `Tagged`(`id`, `cocktail_id`, `type`, `tag`)
+                .execute(
+                    sqlx::query(
+                        r#"INSERT INTO `Tagged`(`id`, `cocktail_id`, `type`, `tag`)
                 VALUES (?,?,?,?)"#,
-                    Uuid::now_v7().to_string(),
-                    recipe_id.to_string(),
-                    "backend",
-                    tag,
-                ))
+                    )
+                    .bind(Uuid::now_v7().to_string())
+                    .bind(recipe_id.to_string())
+                    .bind("backend")
+                    .bind(tag),
+                )
                 .await
                 .map_err(|e| e.to_string())?;
         }
 
         for tag in template_recipe.author_tags.iter() {
             transaction
-                .execute(sqlx::query!(
-                    r#"INSERT INTO `Tagged`(`id`, `cocktail_id`, `type`, `tag`)
+                .execute(
+                    sqlx::query(
+                        r#"INSERT INTO `Tagged`(`id`, `cocktail_id`, `type`, `tag`)
                 VALUES (?,?,?,?)"#,
-                    Uuid::now_v7().to_string(),
-                    recipe_id.to_string(),
-                    "author",
-                    tag,
-                ))
+                    )
+                    .bind(Uuid::now_v7().to_string())
+                    .bind(recipe_id.to_string())
+                    .bind("author")
+                    .bind(tag),
+                )
                 .await
                 .map_err(|e| e.to_string())?;
         }
@@ -419,6 +436,8 @@ impl RecipeFixture {
                 .map(AsRef::as_ref)
                 .collect::<Vec<&str>>()
                 .as_slice(),
+            Some(&template_recipe.equipment),
+            template_recipe.prep_time_minutes,
             authors[0].id().as_deref(),
         )
         .map_err(|e| e.to_string())?;
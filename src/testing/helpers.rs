@@ -5,21 +5,35 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 //! Common stuff for running integration tests.
-
-use actix_web::rt::spawn;
-use lacoctelera::{
-    authentication::{generate_new_token_hash, generate_token, store_validation_token, AuthData},
+//!
+//! # Description
+//!
+//! [spawn_app] runs the application in the background, using a brand new DB that is migrated from scratch. The
+//! returned [TestApp] includes a client for the API, and a connection pool to the DB, so tests can inspect the
+//! stored data. The DB is dropped when the [TestApp] goes out of scope, see [crate::testing::database].
+
+use crate::{
+    authentication::{
+        format_api_key, generate_new_token_hash, generate_token, store_validation_token,
+        AccessControl, AuthData,
+    },
     configuration::{DataBaseSettings, LogSettings, Settings},
     domain::ClientId,
     startup::Application,
     telemetry::configure_tracing,
+    testing::{
+        database::{cleanup_once, TestDatabase},
+        email::CapturingEmailSender,
+    },
+    utils::mailing::EmailSender,
 };
+use actix_web::{rt::spawn, web::Data};
 use once_cell::sync::Lazy;
 use reqwest::Response;
 use secrecy::{ExposeSecret, SecretString};
 use sqlx::{Connection, Executor, MySqlConnection, MySqlPool};
+use std::sync::Arc;
 use tracing::debug;
-use uuid::Uuid;
 
 static TRACING: Lazy<()> = Lazy::new(|| {
     let mut settings = LogSettings {
@@ -50,6 +64,12 @@ pub struct TestApp {
     pub db_pool: MySqlPool,
     pub api_client: reqwest::Client,
     pub api_token: AuthData,
+    /// Emails sent by the application.
+    pub emails: Arc<CapturingEmailSender>,
+    /// State of the access checks of the application.
+    pub access_control: Data<AccessControl>,
+    /// DB of the application, which is dropped along with the [TestApp].
+    pub database: TestDatabase,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -75,6 +95,11 @@ pub enum Resource {
     Author,
     TokenRequest,
     TokenValidate,
+    TokenKeys,
+    Digests,
+    Searches,
+    Inventory,
+    Notifications,
 }
 
 impl From<&str> for Resource {
@@ -85,6 +110,11 @@ impl From<&str> for Resource {
             "recipe" => Resource::Recipe,
             "token/request" => Resource::TokenRequest,
             "token/request/validate" => Resource::TokenValidate,
+            "token/keys" => Resource::TokenKeys,
+            "me/digests" => Resource::Digests,
+            "me/searches" => Resource::Searches,
+            "me/inventory" => Resource::Inventory,
+            "me/notifications" => Resource::Notifications,
             _ => panic!("Wrong string given to make a Resource"),
         }
     }
@@ -98,12 +128,18 @@ impl std::fmt::Display for Resource {
             Resource::Recipe => "recipe",
             Resource::TokenRequest => "token/request",
             Resource::TokenValidate => "token/request/validate",
+            Resource::TokenKeys => "token/keys",
+            Resource::Digests => "me/digests",
+            Resource::Searches => "me/searches",
+            Resource::Inventory => "me/inventory",
+            Resource::Notifications => "me/notifications",
         };
 
         write!(f, "{}", ss)
     }
 }
 
+#[allow(async_fn_in_trait)]
 pub trait TestObject {
     async fn get(&self, query: &str) -> Response;
     async fn head(&self, id: &str) -> Response;
@@ -115,6 +151,7 @@ pub trait TestObject {
     fn db_pool(&self) -> &MySqlPool;
 }
 
+#[allow(async_fn_in_trait)]
 pub trait ApiTesterBuilder {
     type ApiTester;
 
@@ -161,9 +198,11 @@ impl TestApp {
 
         debug!("GET for /author using: {url}");
 
-        self.api_client.get(url).send().await.expect(&format!(
-            "Failed to execute GET for the resource {target_resource}."
-        ))
+        self.api_client
+            .get(url)
+            .send()
+            .await
+            .unwrap_or_else(|_| panic!("Failed to execute GET for the resource {target_resource}."))
     }
 
     pub async fn get_test(
@@ -176,9 +215,11 @@ impl TestApp {
 
         let url = &format!("{}/{target_resource}{query}{credentials}", &self.address);
 
-        self.api_client.get(url).send().await.expect(&format!(
-            "Failed to execute GET for the resource {target_resource}."
-        ))
+        self.api_client
+            .get(url)
+            .send()
+            .await
+            .unwrap_or_else(|_| panic!("Failed to execute GET for the resource {target_resource}."))
     }
 
     pub async fn post_test<Body>(
@@ -195,33 +236,33 @@ impl TestApp {
         match target_resource {
             Resource::TokenRequest => self
                 .api_client
-                .post(&format!("{}/{target_resource}{credentials}", &self.address))
+                .post(format!("{}/{target_resource}{credentials}", &self.address))
                 .form(body)
                 .header("Content-type", "application/json")
                 .send()
                 .await
-                .expect(&format!(
-                    "Failed to execute POST for the resource {target_resource}."
-                )),
+                .unwrap_or_else(|_| {
+                    panic!("Failed to execute POST for the resource {target_resource}.")
+                }),
             _ => self
                 .api_client
-                .post(&format!("{}/{target_resource}{credentials}", &self.address))
+                .post(format!("{}/{target_resource}{credentials}", &self.address))
                 .json(body)
                 .header("Content-type", "application/json")
                 .send()
                 .await
-                .expect(&format!(
-                    "Failed to execute POST for the resource {target_resource}."
-                )),
+                .unwrap_or_else(|_| {
+                    panic!("Failed to execute POST for the resource {target_resource}.")
+                }),
         }
     }
 
     pub async fn head_test(&self, target_resource: Resource, id: &str) -> Response {
         let url = format!("{}/{target_resource}/{id}", &self.address);
 
-        self.api_client.head(url).send().await.expect(&format!(
-            "Failed to execute HEAD for the resource {target_resource}."
-        ))
+        self.api_client.head(url).send().await.unwrap_or_else(|_| {
+            panic!("Failed to execute HEAD for the resource {target_resource}.")
+        })
     }
 
     pub async fn delete_test(
@@ -234,9 +275,38 @@ impl TestApp {
 
         let url = format!("{}/{target_resource}/{id}{credentials}", &self.address);
 
-        self.api_client.delete(url).send().await.expect(&format!(
-            "Failed to execute HEAD for the resource {target_resource}."
-        ))
+        self.api_client
+            .delete(url)
+            .send()
+            .await
+            .unwrap_or_else(|_| {
+                panic!("Failed to execute HEAD for the resource {target_resource}.")
+            })
+    }
+
+    /// PUT a resource. An empty `id` targets the collection.
+    pub async fn put_test<Body>(
+        &self,
+        target_resource: Resource,
+        credentials: Credentials,
+        id: &str,
+        body: &Body,
+    ) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        let credentials = self.credentials_to_url(credentials);
+        let url = match id {
+            "" => format!("{}/{target_resource}{credentials}", &self.address),
+            id => format!("{}/{target_resource}/{id}{credentials}", &self.address),
+        };
+
+        self.api_client
+            .put(url)
+            .json(body)
+            .send()
+            .await
+            .unwrap_or_else(|_| panic!("Failed to execute PUT for the resource {target_resource}."))
     }
 
     pub async fn patch_test<Body>(
@@ -260,6 +330,27 @@ impl TestApp {
             .expect("Failed to execute PATCH for the resource {target_resource}.")
     }
 
+    pub async fn batch_delete_test<Body>(&self, target_resource: Resource, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        let credentials = self.credentials_to_url(Credentials::WithCredentials);
+
+        let url = format!(
+            "{}/{target_resource}/batch-delete{credentials}",
+            &self.address
+        );
+
+        self.api_client
+            .post(url)
+            .json(body)
+            .send()
+            .await
+            .unwrap_or_else(|_| {
+                panic!("Failed to execute POST for the resource {target_resource}/batch-delete.")
+            })
+    }
+
     pub async fn options_test(&self, target_resource: Resource) -> Response {
         let url = format!("{}/{target_resource}", &self.address);
 
@@ -268,9 +359,9 @@ impl TestApp {
             .header("Access-Control-Request-Method", "GET")
             .send()
             .await
-            .expect(&format!(
-                "Failed to execute HEAD for the resource {target_resource}."
-            ))
+            .unwrap_or_else(|_| {
+                panic!("Failed to execute HEAD for the resource {target_resource}.")
+            })
     }
 
     pub async fn post_token_request<Body>(&self, body: &Body) -> Response
@@ -286,37 +377,82 @@ impl TestApp {
             .await
             .expect("Failed to generate an API token for testing");
     }
+
+    /// Flag the client that owns the API token as an administrator of the API.
+    pub async fn grant_admin_access(&self) {
+        let client_id = self
+            .api_token
+            .api_key
+            .expose_secret()
+            .split(':')
+            .next()
+            .expect("Failed to extract the client ID from the API token")
+            .to_owned();
+
+        sqlx::query("UPDATE ApiUser SET admin = TRUE WHERE id = ?")
+            .bind(client_id)
+            .execute(&self.db_pool)
+            .await
+            .expect("Failed to grant admin access to the test client");
+    }
 }
 
+/// Spawn an application using the settings found in the working directory (see [Settings::new]).
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| {}).await
+}
+
+/// Spawn an application whose settings are modified by `configure`.
+pub async fn spawn_app_with(configure: impl FnOnce(&mut Settings)) -> TestApp {
+    let mut configuration = Settings::new().expect("Failed to read configuration");
+    configure(&mut configuration);
+
+    spawn_app_with_settings(configuration).await
+}
+
+/// Spawn an application using the given settings.
+///
+/// # Description
+///
+/// The name of the DB and the listening port of the given settings are overridden, every application gets a brand
+/// new DB and a random port.
+pub async fn spawn_app_with_settings(settings: Settings) -> TestApp {
     Lazy::force(&TRACING);
 
     // Overwrite the DB name to provide a random name for every test runner. This way, we ensure that each test
     // is run in a pristine DB environment.
     let configuration = {
-        let mut c = Settings::new().expect("Failed to read configuration");
-        c.database.db_name = Uuid::new_v4().to_string();
+        let mut c = settings;
+        c.database.db_name = TestDatabase::random_name();
         // When using 0, a random port will be used.
         c.application.port = 0;
         c
     };
 
-    // Connect to the DB backend.
-    let db_pool = configure_database(&configuration.database).await;
+    // Drop the DBs of previous runs whose teardown didn't run, then create the DB of this application.
+    cleanup_once(&configuration.database);
+    let (database, db_pool) = TestDatabase::create(&configuration.database).await;
 
     // Instantitate the backend application of La Coctelera.
-    let application = Application::build(configuration.clone())
+    // Emails are captured rather than sent, unless the application has no email client.
+    let emails = Arc::new(CapturingEmailSender::new());
+    let mail_client = configuration
+        .email_client
+        .as_ref()
+        .map(|_| emails.clone() as Arc<dyn EmailSender>);
+    let application = Application::build_with(configuration.clone(), db_pool.clone(), mail_client)
         .await
         .expect("Failed to build La Coctelera application.");
 
     let port = application.port();
+    let access_control = application.access_control();
     let address = format!(
         "http://{}:{port}{}/v{}",
         configuration.application.host,
         configuration.application.base_url,
         env!("CARGO_PKG_VERSION").split(".").collect::<Vec<&str>>()[0]
     );
-    let _ = spawn(application.run_until_stopped());
+    spawn(application.run_until_stopped());
 
     // Instantiate an HTTP client to run the tests against the app's backend.
     let api_client = reqwest::Client::builder()
@@ -334,6 +470,9 @@ pub async fn spawn_app() -> TestApp {
         db_pool,
         api_client,
         api_token,
+        emails,
+        access_control,
+        database,
     }
 }
 
@@ -364,15 +503,15 @@ pub async fn configure_database(config: &DataBaseSettings) -> MySqlPool {
 async fn generate_access_token(pool: &MySqlPool) -> Result<AuthData, anyhow::Error> {
     // Add a new entry in the ApiUser DB table.
     let client_id = ClientId::new();
-    let query = sqlx::query!(
+    let query = sqlx::query(
         r#"
         INSERT INTO ApiUser (id, email, validated,enabled,explanation) VALUES
         (?, ?, 1, 1, ?);
         "#,
-        client_id.to_string(),
-        "jane_doe@mail.com",
-        "Because I'm testing this thing",
-    );
+    )
+    .bind(client_id.to_string())
+    .bind("jane_doe@mail.com")
+    .bind("Because I'm testing this thing");
 
     let token = SecretString::from(generate_token());
     let token_hashed = generate_new_token_hash(token.clone())?;
@@ -386,7 +525,7 @@ async fn generate_access_token(pool: &MySqlPool) -> Result<AuthData, anyhow::Err
         .execute(query)
         .await
         .expect("Failed to create a dummy user for testing");
-    store_validation_token(
+    let key_id = store_validation_token(
         &mut transaction,
         &token_hashed,
         chrono::TimeDelta::days(1),
@@ -399,6 +538,6 @@ async fn generate_access_token(pool: &MySqlPool) -> Result<AuthData, anyhow::Err
         .expect("Failed to commit DB transaction");
 
     Ok(AuthData {
-        api_key: SecretString::from(format!("{client_id}:{}", token.expose_secret())),
+        api_key: SecretString::from(format_api_key(&client_id, key_id, &token)),
     })
 }
@@ -0,0 +1,237 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Static assets served by the application, i.e. the style sheet of the token request flow.
+//!
+//! # Description
+//!
+//! Assets are loaded in memory when the application starts (see [AssetStore]), so the server never serves files
+//! added to the disk afterwards. Only the files placed at the top of the directory of the assets, whose name is made
+//! of ASCII letters, digits, `.`, `_` or `-`, and whose extension is allowed by the settings, are loaded.
+//!
+//! Every asset is reachable using two names:
+//! - Its plain name, i.e. `style.css`, which clients shall revalidate using the `ETag` of the asset.
+//! - Its hashed name, i.e. `style.3f2a9c1b.css`, which includes a prefix of the SHA-256 hash of its content. The
+//!   content behind a hashed name never changes, so it can be cached forever.
+
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, fs, io, path::Path};
+use tracing::{debug, info};
+
+/// Amount of hexadecimal digits of the hash included in the hashed names of the assets.
+const HASH_LENGTH: usize = 8;
+
+/// Static asset loaded in memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asset {
+    /// Plain name of the asset.
+    pub name: String,
+    /// Hexadecimal SHA-256 hash of the content of the asset.
+    pub hash: String,
+    pub content: Vec<u8>,
+}
+
+impl Asset {
+    fn new(name: String, content: Vec<u8>) -> Self {
+        Asset {
+            hash: format!("{:x}", Sha256::digest(&content)),
+            name,
+            content,
+        }
+    }
+
+    /// Name of the asset including a prefix of the hash of its content, i.e. `style.3f2a9c1b.css`.
+    pub fn hashed_name(&self) -> String {
+        let hash = &self.hash[..HASH_LENGTH];
+        match self.name.rsplit_once('.') {
+            Some((stem, extension)) => format!("{stem}.{hash}.{extension}"),
+            None => format!("{}.{hash}", self.name),
+        }
+    }
+
+    /// Extension of the name of the asset.
+    pub fn extension(&self) -> &str {
+        self.name.rsplit_once('.').map_or("", |(_, ext)| ext)
+    }
+}
+
+/// Resolution of a requested name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssetMatch<'a> {
+    /// The plain name of an asset was requested.
+    Plain(&'a Asset),
+    /// The hashed name of an asset was requested.
+    Hashed(&'a Asset),
+}
+
+/// Static assets of the application.
+#[derive(Debug, Clone, Default)]
+pub struct AssetStore {
+    assets: BTreeMap<String, Asset>,
+    listing: bool,
+}
+
+impl AssetStore {
+    /// Allow the clients to list the assets.
+    pub fn with_listing(mut self, listing: bool) -> Self {
+        self.listing = listing;
+        self
+    }
+
+    pub fn listing(&self) -> bool {
+        self.listing
+    }
+
+    /// Load the assets placed at `dir` with any of the given `extensions`.
+    ///
+    /// # Description
+    ///
+    /// A missing directory results in an empty store.
+    pub fn load(dir: &Path, extensions: &[String]) -> io::Result<Self> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                info!("No static assets found at {}", dir.display());
+                return Ok(AssetStore::default());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut store = AssetStore::default();
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !entry.file_type()?.is_file() || !AssetStore::is_allowed(&name, extensions) {
+                debug!("Skipping the static file {name}");
+                continue;
+            }
+            store.insert(name, fs::read(entry.path())?);
+        }
+        info!("{} static assets loaded", store.assets.len());
+
+        Ok(store)
+    }
+
+    /// Add an asset to the store.
+    pub fn insert(&mut self, name: String, content: Vec<u8>) {
+        self.assets.insert(name.clone(), Asset::new(name, content));
+    }
+
+    /// Find the asset with the given plain or hashed name.
+    pub fn get(&self, name: &str) -> Option<AssetMatch<'_>> {
+        if let Some(asset) = self.assets.get(name) {
+            return Some(AssetMatch::Plain(asset));
+        }
+
+        // Hashed names: `{stem}.{hash}.{extension}`, or `{name}.{hash}` for names with no extension.
+        let (rest, last) = name.rsplit_once('.')?;
+        let candidates = [
+            rest.rsplit_once('.')
+                .map(|(stem, hash)| (format!("{stem}.{last}"), hash)),
+            Some((rest.to_owned(), last)),
+        ];
+
+        candidates.into_iter().flatten().find_map(|(plain, hash)| {
+            self.assets
+                .get(&plain)
+                .filter(|asset| hash.len() == HASH_LENGTH && asset.hash.starts_with(hash))
+                .map(AssetMatch::Hashed)
+        })
+    }
+
+    /// Iterate over the assets, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = &Asset> {
+        self.assets.values()
+    }
+
+    fn is_allowed(name: &str, extensions: &[String]) -> bool {
+        let valid_name = !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase());
+
+        valid_name
+            && extension.is_some_and(|extension| {
+                extensions
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(&extension))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use uuid::Uuid;
+
+    fn store() -> AssetStore {
+        let mut store = AssetStore::default();
+        store.insert("style.css".into(), b"body {}".to_vec());
+        store.insert("LICENSE".into(), b"MPL".to_vec());
+        store
+    }
+
+    #[rstest]
+    fn assets_are_found_by_name() {
+        let store = store();
+        let asset = store.assets["style.css"].clone();
+        let hashed = asset.hashed_name();
+        assert!(hashed.starts_with("style."));
+        assert!(hashed.ends_with(".css"));
+        assert_eq!(hashed.len(), "style..css".len() + HASH_LENGTH);
+
+        assert_eq!(store.get("style.css"), Some(AssetMatch::Plain(&asset)));
+        assert_eq!(store.get(&hashed), Some(AssetMatch::Hashed(&asset)));
+        assert_eq!(store.get("style.00000000.css"), None);
+        assert_eq!(store.get("script.js"), None);
+
+        let license = store.assets["LICENSE"].clone();
+        assert_eq!(
+            store.get(&license.hashed_name()),
+            Some(AssetMatch::Hashed(&license))
+        );
+    }
+
+    #[rstest]
+    #[case("style.css", true)]
+    #[case("app.JS", true)]
+    #[case("notes.txt", false)]
+    #[case(".hidden.css", false)]
+    #[case("with space.css", false)]
+    #[case("css", false)]
+    fn allowed_files(#[case] name: &str, #[case] allowed: bool) {
+        let extensions = vec!["css".to_owned(), "js".to_owned()];
+        assert_eq!(AssetStore::is_allowed(name, &extensions), allowed);
+    }
+
+    #[rstest]
+    fn assets_are_loaded_from_disk() {
+        let dir = std::env::temp_dir().join(format!("assets-{}", Uuid::now_v7()));
+        fs::create_dir_all(dir.join("nested.css")).unwrap();
+        fs::write(dir.join("style.css"), "body {}").unwrap();
+        fs::write(dir.join("secret.env"), "KEY=1").unwrap();
+
+        let store = AssetStore::load(&dir, &["css".to_owned()]).unwrap();
+        assert_eq!(
+            store.iter().map(|a| a.name.as_str()).collect::<Vec<&str>>(),
+            vec!["style.css"]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            AssetStore::load(&dir, &["css".to_owned()])
+                .unwrap()
+                .iter()
+                .count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,398 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Logical backups of the DB.
+//!
+//! # Description
+//!
+//! Backups are SQL scripts that recreate all the tables of the DB along with their content, so they are restored
+//! using the regular client of the DB, i.e. `mariadb lacoctelera < lacoctelera-20250911T085856123Z.sql`. The export is
+//! done using plain queries to the DB within a single transaction, so the snapshot is consistent and the server needs
+//! no external tools such as `mysqldump`.
+//!
+//! Snapshots are stored in a directory of the server (see [BackupStore]). Only one backup runs at a time. The files are
+//! accessed from the thread pool for blocking operations of the server, so the workers that serve the requests are
+//! never blocked by the disk.
+
+use actix_web::web;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{MySql, MySqlPool, Row, Transaction};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use thiserror::Error;
+use tracing::{debug, error, info, instrument};
+use utoipa::ToSchema;
+
+/// Prefix of the names of the snapshots.
+const SNAPSHOT_PREFIX: &str = "lacoctelera-";
+
+/// Extension of the snapshots.
+const SNAPSHOT_EXTENSION: &str = ".sql";
+
+/// Amount of rows exported by every `INSERT` statement of a snapshot.
+const ROWS_PER_INSERT: usize = 100;
+
+/// Amount of bytes of a snapshot buffered in memory before they are written to the file.
+const SNAPSHOT_BUFFER_SIZE: usize = 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("Another backup is running")]
+    AlreadyRunning,
+    #[error("Failed to export the DB")]
+    Db,
+    #[error("Failed to access the backup storage: {0}")]
+    Storage(#[from] io::Error),
+}
+
+/// Snapshot of the DB.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct BackupSnapshot {
+    #[schema(example = "lacoctelera-20250911T085856123Z.sql")]
+    pub name: String,
+    #[schema(example = 48213)]
+    pub size_bytes: u64,
+    #[schema(value_type = String, example = "2025-09-11T08:58:56Z")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Disk storage for the snapshots of the DB.
+#[derive(Debug, Clone)]
+pub struct BackupStore {
+    dir: PathBuf,
+    running: Arc<AtomicBool>,
+}
+
+impl BackupStore {
+    pub fn new(dir: &Path) -> Self {
+        BackupStore {
+            dir: dir.to_path_buf(),
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Export the DB to a new snapshot.
+    ///
+    /// # Description
+    ///
+    /// The snapshot is written to a temporary file, which is renamed once the export completes, so incomplete
+    /// snapshots are never listed. [BackupError::AlreadyRunning] is returned when another backup is running.
+    #[instrument(skip(self, pool))]
+    pub async fn backup(&self, pool: &MySqlPool) -> Result<BackupSnapshot, BackupError> {
+        if self.running.swap(true, Ordering::AcqRel) {
+            return Err(BackupError::AlreadyRunning);
+        }
+        // The flag is released even when the request is cancelled.
+        let _running = RunningGuard(&self.running);
+
+        self.export(pool).await
+    }
+
+    /// List the stored snapshots, newest first.
+    pub async fn snapshots(&self) -> Result<Vec<BackupSnapshot>, BackupError> {
+        let dir = self.dir.clone();
+        web::block(move || BackupStore::list_snapshots(&dir))
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    fn list_snapshots(dir: &Path) -> Result<Vec<BackupSnapshot>, BackupError> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut snapshots = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !BackupStore::is_snapshot(&name) {
+                continue;
+            }
+            snapshots.push(BackupStore::snapshot_info(&entry.path(), name)?);
+        }
+        // Names include the timestamp of the snapshot.
+        snapshots.sort_by(|a, b| b.name.cmp(&a.name));
+
+        Ok(snapshots)
+    }
+
+    async fn export(&self, pool: &MySqlPool) -> Result<BackupSnapshot, BackupError> {
+        let name = format!(
+            "{SNAPSHOT_PREFIX}{}{SNAPSHOT_EXTENSION}",
+            Utc::now().format("%Y%m%dT%H%M%S%3fZ")
+        );
+        let path = self.dir.join(&name);
+        let partial = self.dir.join(format!("{name}.partial"));
+        debug!("Exporting the DB to {}", partial.display());
+
+        let mut output = SnapshotWriter::create(&self.dir, &partial).await?;
+        let result = match dump_database(pool, &mut output).await {
+            Ok(()) => output.flush().await,
+            Err(e) => Err(e),
+        };
+        drop(output);
+        if let Err(e) = result {
+            let _ = web::block(move || fs::remove_file(partial)).await;
+            return Err(e);
+        }
+
+        let snapshot = web::block(move || {
+            fs::rename(&partial, &path)?;
+            BackupStore::snapshot_info(&path, name)
+        })
+        .await
+        .map_err(io::Error::other)??;
+        info!(
+            "New snapshot of the DB: {} ({} bytes)",
+            snapshot.name, snapshot.size_bytes
+        );
+
+        Ok(snapshot)
+    }
+
+    fn is_snapshot(name: &str) -> bool {
+        name.starts_with(SNAPSHOT_PREFIX) && name.ends_with(SNAPSHOT_EXTENSION)
+    }
+
+    fn snapshot_info(path: &Path, name: String) -> Result<BackupSnapshot, BackupError> {
+        let metadata = fs::metadata(path)?;
+
+        Ok(BackupSnapshot {
+            name,
+            size_bytes: metadata.len(),
+            created_at: metadata.modified()?.into(),
+        })
+    }
+}
+
+/// Release the running flag of a [BackupStore] when dropped.
+struct RunningGuard<'a>(&'a AtomicBool);
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// Output of a snapshot.
+///
+/// # Description
+///
+/// The statements are buffered in memory, and the buffer is appended to the file from the thread pool for blocking
+/// operations once it gets [SNAPSHOT_BUFFER_SIZE] bytes.
+struct SnapshotWriter {
+    file: Option<fs::File>,
+    buffer: Vec<u8>,
+}
+
+impl SnapshotWriter {
+    async fn create(dir: &Path, path: &Path) -> Result<Self, BackupError> {
+        let (dir, path) = (dir.to_path_buf(), path.to_path_buf());
+        let file = web::block(move || {
+            fs::create_dir_all(dir)?;
+            fs::File::create(path)
+        })
+        .await
+        .map_err(io::Error::other)??;
+
+        Ok(SnapshotWriter {
+            file: Some(file),
+            buffer: Vec::with_capacity(SNAPSHOT_BUFFER_SIZE),
+        })
+    }
+
+    /// Append the buffer to the file once it is full.
+    async fn flush_if_full(&mut self) -> Result<(), BackupError> {
+        if self.buffer.len() >= SNAPSHOT_BUFFER_SIZE {
+            self.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Append the buffer to the file.
+    async fn flush(&mut self) -> Result<(), BackupError> {
+        let mut file = self
+            .file
+            .take()
+            .ok_or_else(|| io::Error::other("A previous write of the snapshot failed"))?;
+        let mut buffer = std::mem::take(&mut self.buffer);
+
+        let (file, buffer) = web::block(move || {
+            file.write_all(&buffer)?;
+            buffer.clear();
+            Ok::<_, io::Error>((file, buffer))
+        })
+        .await
+        .map_err(io::Error::other)??;
+        self.file = Some(file);
+        self.buffer = buffer;
+
+        Ok(())
+    }
+}
+
+impl Write for SnapshotWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Write an SQL script that recreates all the tables of the DB along with their content.
+async fn dump_database(pool: &MySqlPool, output: &mut SnapshotWriter) -> Result<(), BackupError> {
+    let mut transaction = pool.begin().await.map_err(db_error)?;
+
+    let tables = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT CAST(TABLE_NAME AS CHAR) FROM information_schema.TABLES
+        WHERE TABLE_SCHEMA = DATABASE() AND TABLE_TYPE = 'BASE TABLE'
+        ORDER BY TABLE_NAME
+        "#,
+    )
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(db_error)?;
+
+    writeln!(output, "-- Snapshot of the DB taken on {}", Utc::now())?;
+    writeln!(output, "SET FOREIGN_KEY_CHECKS = 0;")?;
+    for table in tables {
+        dump_table(&mut transaction, &table, output).await?;
+    }
+    writeln!(output, "SET FOREIGN_KEY_CHECKS = 1;")?;
+
+    transaction.commit().await.map_err(db_error)?;
+
+    Ok(())
+}
+
+async fn dump_table(
+    transaction: &mut Transaction<'_, MySql>,
+    table: &str,
+    output: &mut SnapshotWriter,
+) -> Result<(), BackupError> {
+    let quoted_table = quote_identifier(table);
+
+    let definition = sqlx::query(&format!("SHOW CREATE TABLE {quoted_table}"))
+        .fetch_one(&mut **transaction)
+        .await
+        .map_err(db_error)?;
+    let definition: String = definition.try_get(1).map_err(db_error)?;
+    writeln!(output, "\nDROP TABLE IF EXISTS {quoted_table};")?;
+    writeln!(output, "{definition};")?;
+
+    // Generated columns can't be inserted.
+    let columns = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT CAST(COLUMN_NAME AS CHAR) FROM information_schema.COLUMNS
+        WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND EXTRA NOT LIKE '%GENERATED%'
+        ORDER BY ORDINAL_POSITION
+        "#,
+    )
+    .bind(table)
+    .fetch_all(&mut **transaction)
+    .await
+    .map_err(db_error)?
+    .iter()
+    .map(|column| quote_identifier(column))
+    .collect::<Vec<String>>();
+    if columns.is_empty() {
+        return Ok(());
+    }
+
+    // The DB formats the values as SQL literals, so no type conversion is needed.
+    let values = columns
+        .iter()
+        .map(|column| format!("QUOTE({column})"))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let rows = sqlx::query_scalar::<_, Vec<u8>>(&format!(
+        "SELECT CAST(CONCAT('(', CONCAT_WS(', ', {values}), ')') AS BINARY) FROM {quoted_table}"
+    ))
+    .fetch_all(&mut **transaction)
+    .await
+    .map_err(db_error)?;
+
+    let insert = format!(
+        "INSERT INTO {quoted_table} ({}) VALUES\n",
+        columns.join(", ")
+    );
+    for chunk in rows.chunks(ROWS_PER_INSERT) {
+        output.write_all(insert.as_bytes())?;
+        for (i, row) in chunk.iter().enumerate() {
+            output.write_all(row)?;
+            output.write_all(if i + 1 < chunk.len() { b",\n" } else { b";\n" })?;
+        }
+        output.flush_if_full().await?;
+    }
+    debug!("{} rows exported from {table}", rows.len());
+
+    Ok(())
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("`{}`", identifier.replace('`', "``"))
+}
+
+fn db_error(e: sqlx::Error) -> BackupError {
+    error!("{e}");
+    BackupError::Db
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use uuid::Uuid;
+
+    #[rstest]
+    #[case("Cocktail", "`Cocktail`")]
+    #[case("we`ird", "`we``ird`")]
+    fn identifiers_are_quoted(#[case] identifier: &str, #[case] expected: &str) {
+        assert_eq!(quote_identifier(identifier), expected);
+    }
+
+    #[actix_web::test]
+    async fn snapshots_are_listed() {
+        let dir = std::env::temp_dir().join(format!("backups-{}", Uuid::now_v7()));
+        let store = BackupStore::new(&dir);
+        assert!(store.snapshots().await.unwrap().is_empty());
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lacoctelera-20250911T085856123Z.sql"), "SELECT 1;").unwrap();
+        fs::write(dir.join("lacoctelera-20250912T085856123Z.sql"), "").unwrap();
+        fs::write(dir.join("lacoctelera-20250913T085856123Z.sql.partial"), "").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let snapshots = store.snapshots().await.unwrap();
+        assert_eq!(
+            snapshots
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec![
+                "lacoctelera-20250912T085856123Z.sql",
+                "lacoctelera-20250911T085856123Z.sql"
+            ]
+        );
+        assert_eq!(snapshots[1].size_bytes, 9);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tracking of the latest change of the public collections.
+//!
+//! # Description
+//!
+//! Clients that poll the searches of the API would run the same (expensive) queries over and over again, even when
+//! nothing changed in the DB. To avoid that, the DB keeps the time of the latest change of every [Collection], which
+//! is bumped by the write handlers using [touch_collection]. Searches include it in a `Last-Modified` header, and
+//! answer requests that include an up to date `If-Modified-Since` header with a code **304**.
+//!
+//! HTTP dates have a resolution of one second, so a change that happens within the same second as a search, but after
+//! it, would be missed by a client that revalidates using the `Last-Modified` of that search. Hence, the reported time
+//! is moved one second back while the second of the latest change hasn't elapsed yet.
+
+use crate::domain::ServerError;
+use sqlx::{Executor, MySql, MySqlPool, Row};
+use std::fmt::Display;
+use tracing::error;
+
+/// Collections of the DB whose changes are tracked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Collection {
+    Recipe,
+    Ingredient,
+}
+
+impl Collection {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Collection::Recipe => "recipe",
+            Collection::Ingredient => "ingredient",
+        }
+    }
+}
+
+impl Display for Collection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Record that the given collection changed.
+///
+/// # Description
+///
+/// The executor can be a transaction, so the change is only recorded when the write is committed.
+pub async fn touch_collection<'c, E>(executor: E, collection: Collection) -> Result<(), ServerError>
+where
+    E: Executor<'c, Database = MySql>,
+{
+    sqlx::query(
+        "INSERT INTO `CollectionChange` (`collection`) VALUES (?) \
+         ON DUPLICATE KEY UPDATE `changed_at` = CURRENT_TIMESTAMP",
+    )
+    .bind(collection.as_str())
+    .execute(executor)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
+
+/// Retrieve the time (UNIX timestamp) to report as the latest change of the given collection.
+///
+/// # Description
+///
+/// See the module's documentation for the details about the returned value. `None` is returned when no change of
+/// the collection has been recorded.
+pub async fn get_collection_last_modified(
+    pool: &MySqlPool,
+    collection: Collection,
+) -> Result<Option<i64>, ServerError> {
+    let row = sqlx::query(
+        "SELECT CAST(UNIX_TIMESTAMP(`changed_at`) AS SIGNED) AS `changed_at`, \
+         CAST(UNIX_TIMESTAMP() AS SIGNED) AS `now` \
+         FROM `CollectionChange` WHERE `collection` = ?",
+    )
+    .bind(collection.as_str())
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(row.map(|r| settled_timestamp(r.get("changed_at"), r.get("now"))))
+}
+
+/// Move the timestamp of a change one second back while its second hasn't elapsed yet.
+fn settled_timestamp(changed_at: i64, now: i64) -> i64 {
+    if changed_at < now {
+        changed_at
+    } else {
+        changed_at - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case(100, 101, 100)]
+    #[case(100, 200, 100)]
+    #[case(100, 100, 99)]
+    fn settled_timestamps(#[case] changed_at: i64, #[case] now: i64, #[case] expected: i64) {
+        assert_eq!(settled_timestamp(changed_at, now), expected);
+    }
+}
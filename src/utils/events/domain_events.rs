@@ -0,0 +1,273 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Domain events of the recipes and the ingredients.
+//!
+//! # Description
+//!
+//! Every change of a recipe or an ingredient is recorded as a [DomainEvent] in the append-only table `DomainEvent`.
+//! Events are written using [record_event] within the same transaction as the change, so an event exists if, and only
+//! if, its change was committed. Features that react to the changes of the recipes (webhooks, streams, digests, the
+//! audit trail...) shall read the events using [get_events_from_db] rather than hooking into the write handlers.
+//!
+//! Events are identified by an increasing sequence number, so consumers only need to keep the ID of the latest event
+//! they processed.
+
+use crate::domain::{screening::ScreeningFlag, IngCategory, RecipeState, ServerError, StarRate};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, MySql, MySqlPool};
+use tracing::error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Change of a recipe or an ingredient.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    /// A new recipe was registered.
+    RecipeCreated { recipe_id: Uuid, state: RecipeState },
+    /// The content of a recipe was modified.
+    RecipeUpdated { recipe_id: Uuid },
+    /// A recipe was removed from the DB.
+    RecipeDeleted { recipe_id: Uuid },
+    /// A recipe moved within the publishing workflow.
+    RecipeStateChanged {
+        recipe_id: Uuid,
+        from: RecipeState,
+        to: RecipeState,
+    },
+    /// A recipe was sent to the moderation queue.
+    RecipeFlagged {
+        recipe_id: Uuid,
+        reasons: Vec<ScreeningFlag>,
+    },
+    /// A recipe left the moderation queue, so it is public again.
+    RecipeApproved { recipe_id: Uuid },
+    /// A recipe got a new owner.
+    RecipeOwnerChanged { recipe_id: Uuid, owner: Uuid },
+    /// A client voted a recipe. The voters are not included.
+    RatingSubmitted {
+        recipe_id: Uuid,
+        rating: StarRate,
+        votes: u32,
+    },
+    /// A new ingredient was registered.
+    IngredientCreated { ingredient_id: Uuid },
+    /// The content of an ingredient was modified.
+    IngredientUpdated { ingredient_id: Uuid },
+    /// An ingredient was removed from the DB.
+    IngredientDeleted { ingredient_id: Uuid },
+    /// An ingredient was moved to another category.
+    IngredientReclassified {
+        ingredient_id: Uuid,
+        from: IngCategory,
+        to: IngCategory,
+    },
+}
+
+impl DomainEvent {
+    /// Name of the kind of event, as given by the `type` of its JSON representation.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DomainEvent::RecipeCreated { .. } => "recipe_created",
+            DomainEvent::RecipeUpdated { .. } => "recipe_updated",
+            DomainEvent::RecipeDeleted { .. } => "recipe_deleted",
+            DomainEvent::RecipeStateChanged { .. } => "recipe_state_changed",
+            DomainEvent::RecipeFlagged { .. } => "recipe_flagged",
+            DomainEvent::RecipeApproved { .. } => "recipe_approved",
+            DomainEvent::RecipeOwnerChanged { .. } => "recipe_owner_changed",
+            DomainEvent::RatingSubmitted { .. } => "rating_submitted",
+            DomainEvent::IngredientCreated { .. } => "ingredient_created",
+            DomainEvent::IngredientUpdated { .. } => "ingredient_updated",
+            DomainEvent::IngredientDeleted { .. } => "ingredient_deleted",
+            DomainEvent::IngredientReclassified { .. } => "ingredient_reclassified",
+        }
+    }
+
+    /// Kind of the resource that changed: `recipe` or `ingredient`.
+    pub fn resource(&self) -> &'static str {
+        match self {
+            DomainEvent::IngredientCreated { .. }
+            | DomainEvent::IngredientUpdated { .. }
+            | DomainEvent::IngredientDeleted { .. }
+            | DomainEvent::IngredientReclassified { .. } => "ingredient",
+            _ => "recipe",
+        }
+    }
+
+    /// ID of the resource that changed.
+    pub fn subject_id(&self) -> &Uuid {
+        match self {
+            DomainEvent::RecipeCreated { recipe_id, .. }
+            | DomainEvent::RecipeUpdated { recipe_id }
+            | DomainEvent::RecipeDeleted { recipe_id }
+            | DomainEvent::RecipeStateChanged { recipe_id, .. }
+            | DomainEvent::RecipeFlagged { recipe_id, .. }
+            | DomainEvent::RecipeApproved { recipe_id }
+            | DomainEvent::RecipeOwnerChanged { recipe_id, .. }
+            | DomainEvent::RatingSubmitted { recipe_id, .. } => recipe_id,
+            DomainEvent::IngredientCreated { ingredient_id }
+            | DomainEvent::IngredientUpdated { ingredient_id }
+            | DomainEvent::IngredientDeleted { ingredient_id }
+            | DomainEvent::IngredientReclassified { ingredient_id, .. } => ingredient_id,
+        }
+    }
+}
+
+/// Event as stored in the DB.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StoredEvent {
+    /// Sequence number of the event.
+    #[schema(example = 1024)]
+    pub id: u64,
+    #[schema(value_type = String, example = "2025-09-11T08:58:56Z")]
+    pub occurred_at: DateTime<Utc>,
+    pub event: DomainEvent,
+}
+
+/// Record an event.
+///
+/// # Description
+///
+/// The executor shall be the transaction that applies the change, so the event is only recorded when the change is
+/// committed.
+pub async fn record_event<'c, E>(executor: E, event: &DomainEvent) -> Result<(), ServerError>
+where
+    E: Executor<'c, Database = MySql>,
+{
+    let payload = serde_json::to_string(event).map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    sqlx::query("INSERT INTO DomainEvent (kind, subject_id, payload) VALUES (?, ?, ?)")
+        .bind(event.kind())
+        .bind(event.subject_id().to_string())
+        .bind(payload)
+        .execute(executor)
+        .await
+        .map_err(|e| {
+            error!("{e}");
+            ServerError::DbError
+        })?;
+
+    Ok(())
+}
+
+/// Retrieve the events recorded after the event `after`, oldest first.
+pub async fn get_events_from_db(
+    pool: &MySqlPool,
+    after: u64,
+    limit: u32,
+) -> Result<Vec<StoredEvent>, ServerError> {
+    let events: Vec<(u64, String, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT id, payload, occurred_at FROM DomainEvent WHERE id > ? ORDER BY id LIMIT ?",
+    )
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    events
+        .into_iter()
+        .map(|(id, payload, occurred_at)| {
+            Ok(StoredEvent {
+                id,
+                occurred_at,
+                event: serde_json::from_str(&payload).map_err(|e| {
+                    error!("Malformed event {id}: {e}");
+                    ServerError::DbError
+                })?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use serde_json::json;
+
+    #[rstest]
+    fn events_are_tagged_with_their_kind() {
+        let recipe_id = Uuid::now_v7();
+        let events = [
+            DomainEvent::RecipeCreated {
+                recipe_id,
+                state: RecipeState::Draft,
+            },
+            DomainEvent::RecipeUpdated { recipe_id },
+            DomainEvent::RecipeDeleted { recipe_id },
+            DomainEvent::RecipeStateChanged {
+                recipe_id,
+                from: RecipeState::Draft,
+                to: RecipeState::Submitted,
+            },
+            DomainEvent::RecipeFlagged {
+                recipe_id,
+                reasons: vec![ScreeningFlag::Shouting],
+            },
+            DomainEvent::RecipeApproved { recipe_id },
+            DomainEvent::RecipeOwnerChanged {
+                recipe_id,
+                owner: Uuid::now_v7(),
+            },
+            DomainEvent::RatingSubmitted {
+                recipe_id,
+                rating: StarRate::new(4.5).unwrap(),
+                votes: 3,
+            },
+        ];
+
+        for event in events {
+            let payload = serde_json::to_value(&event).unwrap();
+            assert_eq!(payload["type"], event.kind());
+            assert_eq!(payload["recipe_id"], json!(recipe_id));
+            assert_eq!(event.subject_id(), &recipe_id);
+            assert_eq!(event.resource(), "recipe");
+            assert_eq!(
+                serde_json::from_value::<DomainEvent>(payload).unwrap(),
+                event
+            );
+        }
+    }
+
+    #[rstest]
+    fn ingredient_events_name_their_resource() {
+        let ingredient_id = Uuid::now_v7();
+        let event = DomainEvent::IngredientDeleted { ingredient_id };
+
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            json!({"type": "ingredient_deleted", "ingredient_id": ingredient_id})
+        );
+        assert_eq!(event.resource(), "ingredient");
+        assert_eq!(event.subject_id(), &ingredient_id);
+
+        let event = DomainEvent::IngredientReclassified {
+            ingredient_id,
+            from: IngCategory::try_from("other").unwrap(),
+            to: IngCategory::try_from("syrup").unwrap(),
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            json!({
+                "type": "ingredient_reclassified",
+                "ingredient_id": ingredient_id,
+                "from": "other",
+                "to": "syrup"
+            })
+        );
+        assert_eq!(event.resource(), "ingredient");
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Destinations of the domain events.
+//!
+//! # Description
+//!
+//! The outbox job (see [crate::jobs::dispatch_events]) publishes the recorded events to every configured [EventSink].
+//! Besides the webhooks, events can be published to a message bus (see [crate::utils::events::build_message_bus_sink]),
+//! and the changes of the public content can purge the caches of the frontends (see [PurgeSink]).
+//! Delivery is at-least-once: an event is published again when the job can't record that it was delivered, so sinks
+//! shall be idempotent, i.e. using the sequence number of the events.
+
+use crate::{
+    configuration::OutboxSettings,
+    utils::events::{build_message_bus_sink, PurgeSink, StoredEvent},
+};
+use anyhow::bail;
+use std::{fmt::Debug, future::Future, pin::Pin, sync::Arc, time::Duration};
+use tracing::debug;
+
+/// Future returned by [EventSink::publish].
+pub type PublishFuture<'a> = Pin<Box<dyn Future<Output = Result<(), anyhow::Error>> + 'a>>;
+
+/// Name of the header that includes the sequence number of the event published by a [WebhookSink].
+pub const EVENT_ID_HEADER: &str = "X-Event-Id";
+
+/// Name of the header that includes the kind of the event published by a [WebhookSink].
+pub const EVENT_KIND_HEADER: &str = "X-Event-Kind";
+
+/// Time given to the receivers of the webhooks to answer.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Destination of the domain events.
+pub trait EventSink: Send + Sync + Debug {
+    /// Name of the sink, which identifies its position within the event log. Renaming a sink makes it start over.
+    fn name(&self) -> &str;
+
+    /// Publish an event. Errors make the event be published again on the next run of the outbox.
+    fn publish<'a>(&'a self, event: &'a StoredEvent) -> PublishFuture<'a>;
+}
+
+/// Sink that sends the events to a URL using `POST` requests.
+///
+/// # Description
+///
+/// The body of the requests is the JSON representation of [StoredEvent]. The sequence number and the kind of the
+/// event are also given by the headers [EVENT_ID_HEADER] and [EVENT_KIND_HEADER]. Any response with a status code
+/// other than 2xx is taken as a failed delivery.
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+    name: String,
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    pub fn new(name: &str, url: &str) -> Result<Self, anyhow::Error> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            bail!("Invalid URL for the webhook {name}: {url}");
+        }
+
+        Ok(WebhookSink {
+            name: format!("webhook:{name}"),
+            url: url.to_owned(),
+            client: reqwest::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()?,
+        })
+    }
+}
+
+impl EventSink for WebhookSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn publish<'a>(&'a self, event: &'a StoredEvent) -> PublishFuture<'a> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .post(&self.url)
+                .header(EVENT_ID_HEADER, event.id)
+                .header(EVENT_KIND_HEADER, event.event.kind())
+                .json(event)
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                bail!("The webhook answered {}", response.status());
+            }
+            debug!("Event {} delivered to {}", event.id, self.name);
+
+            Ok(())
+        })
+    }
+}
+
+/// Build the sinks given by the settings.
+///
+/// # Description
+///
+/// An error is returned when some sink is misconfigured, or when several sinks share the same name.
+pub async fn build_event_sinks(
+    settings: &OutboxSettings,
+) -> Result<Vec<Arc<dyn EventSink>>, anyhow::Error> {
+    let mut sinks: Vec<Arc<dyn EventSink>> = Vec::with_capacity(settings.webhooks.len() + 2);
+
+    for webhook in settings.webhooks.iter() {
+        let sink = WebhookSink::new(&webhook.name, &webhook.url)?;
+        if sinks.iter().any(|s| s.name() == sink.name()) {
+            bail!("Duplicated event sink: {}", sink.name());
+        }
+        sinks.push(Arc::new(sink));
+    }
+
+    if let Some(message_bus) = &settings.message_bus {
+        sinks.push(build_message_bus_sink(message_bus).await?);
+    }
+
+    if let Some(purge) = &settings.purge {
+        sinks.push(Arc::new(PurgeSink::new(purge)?));
+    }
+
+    Ok(sinks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::{PurgeSettings, WebhookSettings};
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case("https://hooks.example.com/events", true)]
+    #[case("http://localhost:8080", true)]
+    #[case("ftp://hooks.example.com", false)]
+    #[case("hooks.example.com", false)]
+    fn webhooks_need_http_urls(#[case] url: &str, #[case] valid: bool) {
+        let sink = WebhookSink::new("site", url);
+        assert_eq!(sink.is_ok(), valid);
+        if let Ok(sink) = sink {
+            assert_eq!(sink.name(), "webhook:site");
+        }
+    }
+
+    #[actix_web::test]
+    async fn sinks_have_unique_names() {
+        let webhook = |name: &str| WebhookSettings {
+            name: name.into(),
+            url: "https://hooks.example.com".into(),
+        };
+        let mut settings = OutboxSettings {
+            webhooks: vec![webhook("site"), webhook("search")],
+            ..Default::default()
+        };
+        assert_eq!(build_event_sinks(&settings).await.unwrap().len(), 2);
+
+        settings.purge = Some(PurgeSettings {
+            url: "https://www.example.com/purge".into(),
+            auth_header: "Authorization".into(),
+            auth_value: None,
+        });
+        assert_eq!(build_event_sinks(&settings).await.unwrap().len(), 3);
+
+        settings.webhooks.push(webhook("site"));
+        assert!(build_event_sinks(&settings).await.is_err());
+    }
+}
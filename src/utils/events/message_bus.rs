@@ -0,0 +1,236 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Publishers of the domain events for message buses.
+//!
+//! # Description
+//!
+//! Some deployments feed home automation or bar displays with the changes of the recipes. Such systems usually listen
+//! to a message bus, so the outbox can publish the events to an MQTT broker or a NATS server. The clients of both
+//! protocols are optional dependencies, enabled by the features `mqtt` and `nats`.
+//!
+//! Events are published to a topic per kind of event (see [event_topic]), i.e. `lacoctelera/recipe/recipe_updated`
+//! (MQTT) or `lacoctelera.recipe.recipe_updated` (NATS). The payload is the JSON representation of [StoredEvent].
+
+use crate::{
+    configuration::MessageBusSettings,
+    utils::events::{DomainEvent, EventSink},
+};
+use anyhow::bail;
+use std::sync::Arc;
+#[cfg(any(feature = "mqtt", feature = "nats"))]
+use {
+    crate::{
+        configuration::MessageBusProtocol,
+        utils::events::{PublishFuture, StoredEvent},
+    },
+    secrecy::ExposeSecret,
+    std::time::Duration,
+    tracing::debug,
+};
+
+/// Time given to the NATS server to flush an event.
+#[cfg(feature = "nats")]
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Topic of an event: the prefix, the kind of the resource and the kind of the event, joined by `separator`.
+pub fn event_topic(prefix: &str, separator: char, event: &DomainEvent) -> String {
+    let prefix = prefix.trim_end_matches(separator);
+    let topic = format!("{separator}{}{separator}{}", event.resource(), event.kind());
+
+    if prefix.is_empty() {
+        topic[1..].to_owned()
+    } else {
+        format!("{prefix}{topic}")
+    }
+}
+
+/// Build the sink of the message bus given by the settings.
+///
+/// # Description
+///
+/// An error is returned when the application was built without the feature of the protocol. Brokers that are not
+/// reachable are not an error: connections are established in the background, and the events are kept in the outbox
+/// until the broker is back.
+pub async fn build_message_bus_sink(
+    settings: &MessageBusSettings,
+) -> Result<Arc<dyn EventSink>, anyhow::Error> {
+    match settings.protocol {
+        #[cfg(feature = "mqtt")]
+        MessageBusProtocol::Mqtt => Ok(Arc::new(MqttSink::new(settings))),
+        #[cfg(feature = "nats")]
+        MessageBusProtocol::Nats => Ok(Arc::new(NatsSink::connect(settings).await?)),
+        #[allow(unreachable_patterns)]
+        protocol => bail!("The application was built without support for {protocol:?}"),
+    }
+}
+
+/// Sink that publishes the events to an MQTT broker.
+///
+/// # Description
+///
+/// Events are published using QoS 1 (at least once). The connection is handled by a task of the runtime, which
+/// reconnects to the broker when needed.
+#[cfg(feature = "mqtt")]
+#[derive(Debug)]
+pub struct MqttSink {
+    name: String,
+    prefix: String,
+    client: rumqttc::AsyncClient,
+}
+
+#[cfg(feature = "mqtt")]
+impl MqttSink {
+    /// Amount of events that can wait for the connection.
+    const CAPACITY: usize = 64;
+
+    pub fn new(settings: &MessageBusSettings) -> Self {
+        let mut options =
+            rumqttc::MqttOptions::new(&settings.client_id, &settings.host, settings.port());
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some(username) = &settings.username {
+            options.set_credentials(
+                username,
+                settings
+                    .password
+                    .as_ref()
+                    .map(|p| p.expose_secret().to_owned())
+                    .unwrap_or_default(),
+            );
+        }
+
+        let (client, mut eventloop) = rumqttc::AsyncClient::new(options, Self::CAPACITY);
+        actix_web::rt::spawn(async move {
+            loop {
+                if let Err(e) = eventloop.poll().await {
+                    tracing::warn!("Connection to the MQTT broker failed: {e}");
+                    actix_web::rt::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        });
+
+        MqttSink {
+            name: format!("mqtt:{}", settings.topic_prefix),
+            prefix: settings.topic_prefix.clone(),
+            client,
+        }
+    }
+}
+
+#[cfg(feature = "mqtt")]
+impl EventSink for MqttSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn publish<'a>(&'a self, event: &'a StoredEvent) -> PublishFuture<'a> {
+        Box::pin(async move {
+            let topic = event_topic(&self.prefix, '/', &event.event);
+            // Queued events are retried by the client when the connection is lost, so a full queue means the broker
+            // is down: the event stays in the outbox.
+            self.client.try_publish(
+                &topic,
+                rumqttc::QoS::AtLeastOnce,
+                false,
+                serde_json::to_vec(event)?,
+            )?;
+            debug!("Event {} published to {topic}", event.id);
+
+            Ok(())
+        })
+    }
+}
+
+/// Sink that publishes the events to a NATS server.
+///
+/// # Description
+///
+/// Every event is flushed to the server before it is taken as delivered.
+#[cfg(feature = "nats")]
+#[derive(Debug)]
+pub struct NatsSink {
+    name: String,
+    prefix: String,
+    client: async_nats::Client,
+}
+
+#[cfg(feature = "nats")]
+impl NatsSink {
+    pub async fn connect(settings: &MessageBusSettings) -> Result<Self, anyhow::Error> {
+        let mut options = async_nats::ConnectOptions::new()
+            .name(&settings.client_id)
+            .retry_on_initial_connect();
+        if let Some(token) = &settings.token {
+            options = options.token(token.expose_secret().to_owned());
+        } else if let Some(username) = &settings.username {
+            options = options.user_and_password(
+                username.clone(),
+                settings
+                    .password
+                    .as_ref()
+                    .map(|p| p.expose_secret().to_owned())
+                    .unwrap_or_default(),
+            );
+        }
+
+        let client = options
+            .connect(format!("{}:{}", settings.host, settings.port()))
+            .await?;
+
+        Ok(NatsSink {
+            name: format!("nats:{}", settings.topic_prefix),
+            prefix: settings.topic_prefix.clone(),
+            client,
+        })
+    }
+}
+
+#[cfg(feature = "nats")]
+impl EventSink for NatsSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn publish<'a>(&'a self, event: &'a StoredEvent) -> PublishFuture<'a> {
+        Box::pin(async move {
+            let subject = event_topic(&self.prefix, '.', &event.event);
+            self.client
+                .publish(
+                    subject.clone(),
+                    actix_web::web::Bytes::from(serde_json::to_vec(event)?),
+                )
+                .await?;
+            actix_web::rt::time::timeout(PUBLISH_TIMEOUT, self.client.flush()).await??;
+            debug!("Event {} published to {subject}", event.id);
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use uuid::Uuid;
+
+    #[rstest]
+    #[case("lacoctelera", '/', "lacoctelera/recipe/recipe_deleted")]
+    #[case("home/bar/", '/', "home/bar/recipe/recipe_deleted")]
+    #[case("lacoctelera", '.', "lacoctelera.recipe.recipe_deleted")]
+    #[case("", '.', "recipe.recipe_deleted")]
+    fn topics_name_the_event(
+        #[case] prefix: &str,
+        #[case] separator: char,
+        #[case] expected: &str,
+    ) {
+        let event = DomainEvent::RecipeDeleted {
+            recipe_id: Uuid::now_v7(),
+        };
+        assert_eq!(event_topic(prefix, separator, &event), expected);
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Purge of the caches of the frontends when the public content changes.
+//!
+//! # Description
+//!
+//! Statically generated frontends and CDNs keep copies of the pages built from the recipes and the ingredients. The
+//! outbox notifies the changes of the public content to a purge endpoint (see [PurgeSink]), so such copies are purged
+//! or rebuilt as soon as the content changes. The request lists the paths of the API whose content changed (see
+//! [purge_paths]), and the receiver maps them to its own pages.
+
+use crate::{
+    configuration::PurgeSettings,
+    domain::RecipeState,
+    utils::events::{DomainEvent, EventSink, PublishFuture, StoredEvent},
+};
+use anyhow::bail;
+use reqwest::header::HeaderName;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Serialize;
+use std::time::Duration;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Time given to the purge endpoint to answer.
+const PURGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Body of the requests sent to the purge endpoint.
+#[derive(Debug, Clone, Serialize)]
+pub struct PurgeRequest<'a> {
+    /// Sequence number of the event that triggered the purge.
+    pub event_id: u64,
+    /// Kind of the event, i.e. `recipe_updated`.
+    pub event: &'static str,
+    /// Kind of the resource that changed: `recipe` or `ingredient`.
+    pub resource: &'static str,
+    pub id: &'a Uuid,
+    /// Paths of the API whose content changed, relative to the URL of the API.
+    pub paths: Vec<String>,
+}
+
+/// Paths of the API whose public content changed due to an event.
+///
+/// # Description
+///
+/// Both the resource and the collection that lists it are included. Recipes registered as drafts are not public, so
+/// their creation purges nothing.
+pub fn purge_paths(event: &DomainEvent) -> Vec<String> {
+    if let DomainEvent::RecipeCreated { state, .. } = event {
+        if *state != RecipeState::Published {
+            return Vec::new();
+        }
+    }
+
+    let resource = event.resource();
+    vec![
+        format!("/{resource}/{}", event.subject_id()),
+        format!("/{resource}"),
+    ]
+}
+
+/// Sink that calls the purge endpoint of the frontends using `POST` requests.
+///
+/// # Description
+///
+/// The body of the requests is a [PurgeRequest]. Requests include the authentication header given by the settings.
+/// Events that purge nothing don't produce a request. Any response with a status code other than 2xx is taken as a
+/// failed delivery, so the purge is retried by the outbox.
+#[derive(Debug, Clone)]
+pub struct PurgeSink {
+    url: String,
+    auth: Option<(HeaderName, SecretString)>,
+    client: reqwest::Client,
+}
+
+impl PurgeSink {
+    pub fn new(settings: &PurgeSettings) -> Result<Self, anyhow::Error> {
+        if !settings.url.starts_with("http://") && !settings.url.starts_with("https://") {
+            bail!("Invalid URL for the purge endpoint: {}", settings.url);
+        }
+        let auth = match &settings.auth_value {
+            Some(value) => Some((HeaderName::try_from(&settings.auth_header)?, value.clone())),
+            None => None,
+        };
+
+        Ok(PurgeSink {
+            url: settings.url.clone(),
+            auth,
+            client: reqwest::Client::builder().timeout(PURGE_TIMEOUT).build()?,
+        })
+    }
+}
+
+impl EventSink for PurgeSink {
+    fn name(&self) -> &str {
+        "purge"
+    }
+
+    fn publish<'a>(&'a self, event: &'a StoredEvent) -> PublishFuture<'a> {
+        Box::pin(async move {
+            let paths = purge_paths(&event.event);
+            if paths.is_empty() {
+                return Ok(());
+            }
+
+            let body = PurgeRequest {
+                event_id: event.id,
+                event: event.event.kind(),
+                resource: event.event.resource(),
+                id: event.event.subject_id(),
+                paths,
+            };
+            let mut request = self.client.post(&self.url).json(&body);
+            if let Some((name, value)) = &self.auth {
+                request = request.header(name, value.expose_secret());
+            }
+
+            let response = request.send().await?;
+            if !response.status().is_success() {
+                bail!("The purge endpoint answered {}", response.status());
+            }
+            debug!("Purge of {:?} requested", body.paths);
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn public_changes_are_purged() {
+        let recipe_id = Uuid::now_v7();
+        assert_eq!(
+            purge_paths(&DomainEvent::RecipeUpdated { recipe_id }),
+            [format!("/recipe/{recipe_id}"), "/recipe".into()]
+        );
+        assert_eq!(
+            purge_paths(&DomainEvent::RecipeCreated {
+                recipe_id,
+                state: RecipeState::Published
+            }),
+            [format!("/recipe/{recipe_id}"), "/recipe".into()]
+        );
+
+        let ingredient_id = Uuid::now_v7();
+        assert_eq!(
+            purge_paths(&DomainEvent::IngredientDeleted { ingredient_id }),
+            [format!("/ingredient/{ingredient_id}"), "/ingredient".into()]
+        );
+    }
+
+    #[rstest]
+    fn drafts_are_not_purged() {
+        let event = DomainEvent::RecipeCreated {
+            recipe_id: Uuid::now_v7(),
+            state: RecipeState::Draft,
+        };
+        assert!(purge_paths(&event).is_empty());
+    }
+
+    #[rstest]
+    #[case("https://www.example.com/purge", "Authorization", true)]
+    #[case("www.example.com/purge", "Authorization", false)]
+    #[case("https://www.example.com/purge", "Bad Header", false)]
+    fn purge_settings_are_validated(
+        #[case] url: &str,
+        #[case] auth_header: &str,
+        #[case] valid: bool,
+    ) {
+        let settings = PurgeSettings {
+            url: url.into(),
+            auth_header: auth_header.into(),
+            auth_value: Some(SecretString::from("Bearer secret")),
+        };
+        assert_eq!(PurgeSink::new(&settings).is_ok(), valid);
+    }
+}
@@ -0,0 +1,349 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Cache policy of the responses of the API.
+//!
+//! # Description
+//!
+//! The [CachePolicy] middleware sets the `Cache-Control` header of the responses using a list of rules given in the
+//! settings (`application.cache_control`). Each rule maps a pattern of paths to the value of the header, so the
+//! public resources can be cached by CDNs and browsers. Patterns are relative to the URL of the API, and they are
+//! split in segments:
+//! - `*` matches any single segment, i.e. `/recipe/*` matches `/recipe/{id}` but not `/recipe/{id}/pdf`.
+//! - `**` matches the rest of the path, including no segments at all. It can only be used as the last segment.
+//! - Any other segment matches itself.
+//!
+//! Rules are checked in order, and the first rule that matches the path of the request applies. Only the successful
+//! responses to `GET` and `HEAD` requests get the header, and the responses whose handler sets its own policy are
+//! left untouched. Responses to requests that include an API key may include restricted data, so rules that allow
+//! shared caches (`public`) are replaced by `private, no-cache` for such requests.
+
+use crate::configuration::CacheControlSettings;
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{HeaderValue, CACHE_CONTROL},
+        Method, StatusCode,
+    },
+    Error,
+};
+use anyhow::bail;
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+    sync::Arc,
+};
+use tracing::{debug, info};
+
+/// Policy of the responses to requests that include an API key.
+const PRIVATE_POLICY: &str = "private, no-cache";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Exact(String),
+    Any,
+    Rest,
+}
+
+#[derive(Debug, Clone)]
+struct CacheRule {
+    pattern: Vec<Segment>,
+    value: HeaderValue,
+}
+
+impl CacheRule {
+    fn matches(&self, path: &str) -> bool {
+        let mut segments = path.split('/').filter(|s| !s.is_empty());
+
+        for segment in self.pattern.iter() {
+            match (segment, segments.next()) {
+                (Segment::Rest, _) => return true,
+                (Segment::Any, Some(_)) => (),
+                (Segment::Exact(expected), Some(segment)) if expected == segment => (),
+                _ => return false,
+            }
+        }
+
+        segments.next().is_none()
+    }
+}
+
+/// Middleware that sets the `Cache-Control` header of the responses following the rules of the settings.
+///
+/// # Description
+///
+/// Paths are matched after removing the prefix given by [CachePolicy::with_prefix], i.e. the URL of the API. See the
+/// documentation of the module for the syntax of the rules.
+#[derive(Debug, Clone, Default)]
+pub struct CachePolicy {
+    rules: Arc<Vec<CacheRule>>,
+    prefix: String,
+}
+
+impl CachePolicy {
+    /// Build the policy from the settings.
+    ///
+    /// # Description
+    ///
+    /// An error is returned when a rule includes a malformed pattern or an invalid header value. A disabled policy has
+    /// no rules, so it leaves all the responses untouched.
+    pub fn new(settings: &CacheControlSettings) -> Result<Self, anyhow::Error> {
+        if !settings.enabled {
+            return Ok(CachePolicy::default());
+        }
+
+        let mut rules = Vec::with_capacity(settings.rules.len());
+        for rule in settings.rules.iter() {
+            let pattern = rule
+                .path
+                .split('/')
+                .filter(|s| !s.is_empty())
+                .map(|segment| match segment {
+                    "*" => Segment::Any,
+                    "**" => Segment::Rest,
+                    segment => Segment::Exact(segment.to_owned()),
+                })
+                .collect::<Vec<Segment>>();
+            if pattern
+                .iter()
+                .rev()
+                .skip(1)
+                .any(|segment| *segment == Segment::Rest)
+            {
+                bail!(
+                    "Invalid cache policy path: {} (`**` must be the last segment)",
+                    rule.path
+                );
+            }
+            let Ok(value) = HeaderValue::from_str(rule.cache_control.trim()) else {
+                bail!(
+                    "Invalid cache policy for {}: {}",
+                    rule.path,
+                    rule.cache_control
+                );
+            };
+
+            rules.push(CacheRule { pattern, value });
+        }
+        info!("Cache policy with {} rules", rules.len());
+
+        Ok(CachePolicy {
+            rules: Arc::new(rules),
+            prefix: String::new(),
+        })
+    }
+
+    /// Match the paths after removing `prefix`.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Value of the `Cache-Control` header for a path, when a rule matches it.
+    pub fn policy(&self, path: &str) -> Option<&HeaderValue> {
+        let path = path
+            .strip_prefix(self.prefix.as_str())
+            .filter(|path| path.is_empty() || path.starts_with('/'))?;
+
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(path))
+            .map(|rule| &rule.value)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for CachePolicy
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = CachePolicyMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CachePolicyMiddleware {
+            service: Rc::new(service),
+            policy: self.clone(),
+        }))
+    }
+}
+
+/// Service built by [CachePolicy].
+pub struct CachePolicyMiddleware<S> {
+    service: Rc<S>,
+    policy: CachePolicy,
+}
+
+impl<S, B> Service<ServiceRequest> for CachePolicyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let value = if matches!(*req.method(), Method::GET | Method::HEAD) {
+            self.policy.policy(req.path()).cloned()
+        } else {
+            None
+        };
+        let private = req
+            .query_string()
+            .split('&')
+            .any(|param| param.starts_with("api_key="));
+
+        let service = self.service.clone();
+        Box::pin(async move {
+            let mut res = service.call(req).await?;
+
+            let cacheable = res.status().is_success() || res.status() == StatusCode::NOT_MODIFIED;
+            if let (Some(value), true) = (value, cacheable) {
+                if !res.headers().contains_key(CACHE_CONTROL) {
+                    let public = value.to_str().is_ok_and(|v| v.contains("public"));
+                    let value = if private && public {
+                        debug!("The request includes an API key, the response is private");
+                        HeaderValue::from_static(PRIVATE_POLICY)
+                    } else {
+                        value
+                    };
+                    res.headers_mut().insert(CACHE_CONTROL, value);
+                }
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::CachePolicyRule;
+    use actix_web::{
+        test::{call_service, init_service, TestRequest},
+        web, App, HttpResponse,
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn settings(rules: &[(&str, &str)]) -> CacheControlSettings {
+        CacheControlSettings {
+            enabled: true,
+            rules: rules
+                .iter()
+                .map(|(path, cache_control)| CachePolicyRule {
+                    path: path.to_string(),
+                    cache_control: cache_control.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[rstest]
+    #[case("/api/v0/recipe", Some("public, max-age=60"))]
+    #[case("/api/v0/recipe/", Some("public, max-age=60"))]
+    #[case("/api/v0/recipe/0191e13b", Some("public, max-age=300"))]
+    #[case("/api/v0/recipe/0191e13b/pdf", None)]
+    #[case("/api/v0/me", Some("no-store"))]
+    #[case("/api/v0/me/inventory/0191e13b", Some("no-store"))]
+    #[case("/api/v0/author", None)]
+    #[case("/recipe", None)]
+    #[case("/api/v0recipe", None)]
+    fn rules_match_the_paths(#[case] path: &str, #[case] expected: Option<&str>) {
+        let policy = CachePolicy::new(&settings(&[
+            ("/recipe", "public, max-age=60"),
+            ("/recipe/*", "public, max-age=300"),
+            ("/me/**", "no-store"),
+        ]))
+        .unwrap()
+        .with_prefix("/api/v0");
+
+        assert_eq!(
+            policy.policy(path).map(|value| value.to_str().unwrap()),
+            expected,
+            "{path}"
+        );
+    }
+
+    #[rstest]
+    #[case("/recipe/**/pdf", "no-store")]
+    #[case("/recipe", "public,\nmax-age=60")]
+    fn invalid_rules_are_rejected(#[case] path: &str, #[case] cache_control: &str) {
+        assert!(CachePolicy::new(&settings(&[(path, cache_control)])).is_err());
+    }
+
+    #[rstest]
+    fn disabled_policy_has_no_rules() {
+        let mut settings = settings(&[("/**", "no-store")]);
+        settings.enabled = false;
+        assert!(CachePolicy::new(&settings).unwrap().policy("/").is_none());
+    }
+
+    #[actix_web::test]
+    async fn policies_are_applied() {
+        let policy = CachePolicy::new(&settings(&[
+            ("/me", "private, no-store"),
+            ("/**", "public, max-age=60"),
+        ]))
+        .unwrap();
+        let app = init_service(
+            App::new()
+                .wrap(policy)
+                .service(
+                    web::resource("/recipe")
+                        .route(web::get().to(HttpResponse::Ok))
+                        .route(web::post().to(HttpResponse::Created)),
+                )
+                .route("/missing", web::get().to(HttpResponse::NotFound))
+                .route("/me", web::get().to(HttpResponse::Ok))
+                .route(
+                    "/media",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .insert_header((CACHE_CONTROL, "no-cache"))
+                            .finish()
+                    }),
+                ),
+        )
+        .await;
+
+        for (req, expected) in [
+            (
+                TestRequest::get().uri("/recipe"),
+                Some("public, max-age=60"),
+            ),
+            (
+                TestRequest::get().uri("/recipe?name=margarita&api_key=secret"),
+                Some(PRIVATE_POLICY),
+            ),
+            (
+                TestRequest::get().uri("/me?api_key=secret"),
+                Some("private, no-store"),
+            ),
+            (TestRequest::post().uri("/recipe"), None),
+            (TestRequest::get().uri("/missing"), None),
+            (TestRequest::get().uri("/media"), Some("no-cache")),
+        ] {
+            let response = call_service(&app, req.to_request()).await;
+            assert_eq!(
+                response
+                    .headers()
+                    .get(CACHE_CONTROL)
+                    .map(|value| value.to_str().unwrap()),
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Resolution of the real IP of the clients of the API.
+//!
+//! # Description
+//!
+//! When the server runs behind a reverse proxy (nginx, Traefik...), the peer of every connection is the proxy. Proxies
+//! report the IP of the client using the `Forwarded` ([RFC 7239](https://www.rfc-editor.org/rfc/rfc7239)) or the
+//! `X-Forwarded-For` headers. Such headers can be forged by any client, so they are only considered when the peer is
+//! a trusted proxy.
+//!
+//! Each proxy appends the address of its own peer to the list, hence the list is walked from right to left, skipping
+//! the trusted proxies. The first address that doesn't belong to a trusted proxy is the IP of the client.
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderMap, FORWARDED, X_FORWARDED_FOR},
+    web::Data,
+    Error, HttpRequest,
+};
+use anyhow::bail;
+use ipnet::IpNet;
+use serde::Deserialize;
+use std::net::{IpAddr, SocketAddr};
+use tracing::Span;
+use tracing_actix_web::{root_span, DefaultRootSpanBuilder, RootSpanBuilder};
+
+/// Forwarding header written by the trusted proxies.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ForwardingHeader {
+    /// `X-Forwarded-For`, used by most of the proxies (nginx, Traefik, HAProxy...).
+    #[default]
+    XForwardedFor,
+    /// `Forwarded`, defined by [RFC 7239](https://www.rfc-editor.org/rfc/rfc7239).
+    Forwarded,
+}
+
+/// Set of reverse proxies whose forwarding headers are trusted.
+///
+/// # Description
+///
+/// Proxies are given either as single IPs (`10.0.0.1`) or as networks in CIDR notation (`10.0.0.0/8`). An empty set
+/// means that the server is exposed straight to the clients, and the forwarding headers are always ignored.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    networks: Vec<IpNet>,
+    header: ForwardingHeader,
+}
+
+impl TrustedProxies {
+    pub fn new(proxies: &[String], header: ForwardingHeader) -> Result<Self, anyhow::Error> {
+        let mut networks = Vec::with_capacity(proxies.len());
+
+        for proxy in proxies {
+            let proxy = proxy.trim();
+            match proxy
+                .parse::<IpNet>()
+                .or_else(|_| proxy.parse::<IpAddr>().map(IpNet::from))
+            {
+                Ok(network) => networks.push(network),
+                Err(_) => bail!("Invalid trusted proxy: {proxy}"),
+            }
+        }
+
+        Ok(TrustedProxies { networks, header })
+    }
+
+    pub fn is_trusted(&self, ip: &IpAddr) -> bool {
+        self.networks.iter().any(|n| n.contains(ip))
+    }
+
+    /// Resolve the IP of the client given the IP of the peer and the headers of the request.
+    ///
+    /// # Description
+    ///
+    /// Only the [ForwardingHeader] of the proxies is read. When the chain of addresses includes some malformed or
+    /// obfuscated entry, i.e. `unknown`, the last valid address is taken as the client.
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(&peer) {
+            return peer;
+        }
+
+        let chain = forwarded_chain(headers, self.header);
+        let mut client = peer;
+
+        for hop in chain.iter().rev() {
+            match parse_ip(hop) {
+                Some(ip) => {
+                    client = ip;
+                    if !self.is_trusted(&ip) {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        client
+    }
+
+    /// Resolve the IP of the client that issued a request. `None` is returned when the peer is unknown.
+    pub fn client_ip(&self, req: &HttpRequest) -> Option<IpAddr> {
+        req.peer_addr()
+            .map(|peer| self.resolve(peer.ip(), req.headers()))
+    }
+}
+
+/// Retrieve the IP of the client that issued a request using the [TrustedProxies] registered in the application.
+///
+/// # Description
+///
+/// The forwarding headers are ignored when the application has no [TrustedProxies].
+pub fn client_ip(req: &HttpRequest) -> Option<IpAddr> {
+    match req.app_data::<Data<TrustedProxies>>() {
+        Some(proxies) => proxies.client_ip(req),
+        None => req.peer_addr().map(|peer| peer.ip()),
+    }
+}
+
+/// Collect the addresses listed by a forwarding header, from the farthest to the closest hop.
+fn forwarded_chain(headers: &HeaderMap, header: ForwardingHeader) -> Vec<String> {
+    match header {
+        ForwardingHeader::Forwarded => headers
+            .get_all(FORWARDED)
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .filter_map(|element| {
+                element.split(';').find_map(|pair| {
+                    let (key, value) = pair.split_once('=')?;
+                    key.trim()
+                        .eq_ignore_ascii_case("for")
+                        .then(|| value.trim().trim_matches('"').to_owned())
+                })
+            })
+            .collect(),
+        ForwardingHeader::XForwardedFor => headers
+            .get_all(X_FORWARDED_FOR)
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(|v| v.trim().to_owned())
+            .filter(|v| !v.is_empty())
+            .collect(),
+    }
+}
+
+/// Parse an IP that might include a port, i.e. `10.0.0.1:8080`, `[::1]:8080` or `[::1]`.
+pub(crate) fn parse_ip(value: &str) -> Option<IpAddr> {
+    let value = value.trim();
+    value
+        .parse::<IpAddr>()
+        .or_else(|_| value.parse::<SocketAddr>().map(|a| a.ip()))
+        .or_else(|_| {
+            value
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+        })
+        .ok()
+}
+
+/// Root span of the requests that includes the resolved IP of the client.
+///
+/// # Description
+///
+/// The default root span of [tracing_actix_web] includes the field `client_ip`, which is taken from the forwarding
+/// headers without checking the peer. This builder adds the field `client.real_ip`, which is resolved using the
+/// [TrustedProxies] of the application, so the logs can be audited.
+pub struct ClientIpRootSpan;
+
+impl RootSpanBuilder for ClientIpRootSpan {
+    fn on_request_start(request: &ServiceRequest) -> Span {
+        let real_ip = client_ip(request.request())
+            .map(|ip| ip.to_string())
+            .unwrap_or_default();
+        root_span!(request, client.real_ip = %real_ip)
+    }
+
+    fn on_request_end<B: MessageBody>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
+        DefaultRootSpanBuilder::on_request_end(span, outcome);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::{HeaderName, HeaderValue};
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn headers(entries: &[(HeaderName, &'static str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in entries {
+            map.append(name.clone(), HeaderValue::from_static(value));
+        }
+        map
+    }
+
+    fn trusted(header: ForwardingHeader) -> TrustedProxies {
+        TrustedProxies::new(&["10.0.0.0/8".to_owned(), "192.168.1.1".to_owned()], header).unwrap()
+    }
+
+    #[fixture]
+    fn proxies() -> TrustedProxies {
+        trusted(ForwardingHeader::XForwardedFor)
+    }
+
+    #[rstest]
+    fn invalid_proxies_are_rejected() {
+        let header = ForwardingHeader::default();
+        assert!(TrustedProxies::new(&["not an ip".to_owned()], header).is_err());
+        assert!(TrustedProxies::new(&["10.0.0.0/33".to_owned()], header).is_err());
+    }
+
+    #[rstest]
+    fn untrusted_peers_are_not_believed(proxies: TrustedProxies) {
+        let headers = headers(&[(X_FORWARDED_FOR, "1.2.3.4")]);
+        assert_eq!(proxies.resolve(ip("8.8.8.8"), &headers), ip("8.8.8.8"));
+        assert_eq!(
+            TrustedProxies::default().resolve(ip("10.0.0.1"), &headers),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[rstest]
+    #[case(&[(X_FORWARDED_FOR, "1.2.3.4")], "1.2.3.4")]
+    #[case(&[(X_FORWARDED_FOR, "6.6.6.6, 1.2.3.4, 10.1.1.1")], "1.2.3.4")]
+    #[case(&[(X_FORWARDED_FOR, "6.6.6.6"), (X_FORWARDED_FOR, "1.2.3.4")], "1.2.3.4")]
+    #[case(&[(X_FORWARDED_FOR, "10.2.2.2, 192.168.1.1")], "10.2.2.2")]
+    #[case(&[(X_FORWARDED_FOR, "unknown, 10.2.2.2")], "10.2.2.2")]
+    #[case(&[(FORWARDED, "For=1.2.3.4"), (X_FORWARDED_FOR, "6.6.6.6")], "6.6.6.6")]
+    #[case(&[(FORWARDED, "for=1.2.3.4")], "10.0.0.1")]
+    #[case(&[], "10.0.0.1")]
+    fn clients_behind_trusted_proxies_are_resolved(
+        proxies: TrustedProxies,
+        #[case] entries: &[(HeaderName, &'static str)],
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            proxies.resolve(ip("10.0.0.1"), &headers(entries)),
+            ip(expected)
+        );
+    }
+
+    #[rstest]
+    #[case(&[(FORWARDED, "for=1.2.3.4;proto=https")], "1.2.3.4")]
+    #[case(&[(FORWARDED, "for=6.6.6.6, for=\"[2001:db8::17]:4711\"")], "2001:db8::17")]
+    #[case(&[(FORWARDED, "for=6.6.6.6"), (X_FORWARDED_FOR, "1.2.3.4")], "6.6.6.6")]
+    #[case(&[(X_FORWARDED_FOR, "1.2.3.4")], "10.0.0.1")]
+    fn only_the_header_of_the_proxies_is_read(
+        #[case] entries: &[(HeaderName, &'static str)],
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            trusted(ForwardingHeader::Forwarded).resolve(ip("10.0.0.1"), &headers(entries)),
+            ip(expected)
+        );
+    }
+
+    #[rstest]
+    #[case("10.0.0.1", Some("10.0.0.1"))]
+    #[case("10.0.0.1:8080", Some("10.0.0.1"))]
+    #[case("[::1]:8080", Some("::1"))]
+    #[case("[::1]", Some("::1"))]
+    #[case("unknown", None)]
+    fn ips_are_parsed(#[case] input: &str, #[case] expected: Option<&str>) {
+        assert_eq!(parse_ip(input), expected.map(ip));
+    }
+}
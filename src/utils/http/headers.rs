@@ -0,0 +1,270 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Helpers to build the HTTP headers included in the responses of the API.
+
+use crate::domain::Language;
+use actix_web::{
+    http::header::{
+        AcceptLanguage, EntityTag, Header, HttpDate, IfMatch, IfModifiedSince, IfNoneMatch,
+        LastModified, Preference, IF_MATCH,
+    },
+    HttpMessage, HttpRequest,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, SystemTime};
+
+/// Name of the header that includes the amount of recipes owned by an author.
+pub const X_RECIPE_COUNT: &str = "X-Recipe-Count";
+
+/// Name of the header that includes the rating of a recipe.
+pub const X_RECIPE_RATING: &str = "X-Recipe-Rating";
+
+/// Name of the header that includes the total amount of results of a paginated search.
+pub const X_TOTAL_COUNT: &str = "X-Total-Count";
+
+/// Build a `Last-Modified` header from a UNIX timestamp (seconds).
+///
+/// # Description
+///
+/// Timestamps are retrieved from the DB using `UNIX_TIMESTAMP`, which avoids dealing with the time zone of the DB
+/// server. Negative values are clamped to the UNIX epoch.
+pub fn last_modified(timestamp: i64) -> LastModified {
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp.max(0) as u64);
+
+    LastModified(HttpDate::from(time))
+}
+
+/// Check whether a request includes an `If-Modified-Since` header that is not older than the given UNIX timestamp.
+///
+/// # Description
+///
+/// Requests that satisfy this check can be answered with a code **304**. Missing or malformed headers are ignored.
+pub fn is_not_modified(req: &HttpRequest, timestamp: i64) -> bool {
+    match IfModifiedSince::parse(req) {
+        Ok(IfModifiedSince(since)) => {
+            SystemTime::from(since)
+                >= SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp.max(0) as u64)
+        }
+        Err(_) => false,
+    }
+}
+
+/// Check whether a request includes an `If-None-Match` header that matches the current entity tag of a resource.
+///
+/// # Description
+///
+/// Tags are compared using the weak comparison, and `*` matches any tag. Requests that satisfy this check can be
+/// answered with a code **304**. Missing or malformed headers are ignored.
+pub fn is_etag_not_modified(req: &HttpRequest, current: &EntityTag) -> bool {
+    match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(current)),
+        None => false,
+    }
+}
+
+/// Get the preferred language of a request, using its `Accept-Language` header.
+///
+/// # Description
+///
+/// The first language of the header, by quality, that is supported by the API is returned. Missing or malformed
+/// headers, and headers that only include unsupported languages, are ignored.
+pub fn accepted_language(req: &HttpRequest) -> Option<Language> {
+    AcceptLanguage::parse(req)
+        .ok()?
+        .ranked()
+        .into_iter()
+        .find_map(|preference| match preference {
+            Preference::Specific(tag) => {
+                Language::try_from(tag.primary_language().to_ascii_lowercase().as_str()).ok()
+            }
+            Preference::Any => None,
+        })
+}
+
+/// Amount of hexadecimal digits of the hash included in the entity tags of the resources.
+const ETAG_LENGTH: usize = 32;
+
+/// Build the entity tag of a resource.
+///
+/// # Description
+///
+/// The tag is a prefix of the SHA-256 hash of the JSON representation of the resource, as stored in the DB. Thus it
+/// identifies a version of the resource, regardless of the representation sent to the client, i.e. muted private
+/// data or converted units.
+pub fn resource_etag<T: Serialize>(resource: &T) -> Result<EntityTag, serde_json::Error> {
+    let hash = format!("{:x}", Sha256::digest(serde_json::to_vec(resource)?));
+
+    Ok(EntityTag::new_strong(hash[..ETAG_LENGTH].to_owned()))
+}
+
+/// Preconditions that the requests modifying a resource must include.
+///
+/// # Description
+///
+/// When `If-Match` headers are required, requests that include none shall be answered with a code **428**, so
+/// clients can't delete a resource that changed since they retrieved it by mistake. The check of the header itself is
+/// done by [is_precondition_met].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Preconditions {
+    pub require_if_match: bool,
+}
+
+impl Preconditions {
+    pub fn new(require_if_match: bool) -> Self {
+        Preconditions { require_if_match }
+    }
+
+    /// Check whether a request lacks a required `If-Match` header.
+    pub fn is_missing(&self, req: &HttpRequest) -> bool {
+        self.require_if_match && !req.headers().contains_key(IF_MATCH)
+    }
+}
+
+/// Check the `If-Match` precondition of a request against the current entity tag of a resource.
+///
+/// # Description
+///
+/// Requests with no `If-Match` header always satisfy the precondition. Otherwise, the header must include the current
+/// tag, using the strong comparison, or `*`. Malformed headers never satisfy the precondition. Requests that fail
+/// this check shall be answered with a code **412**.
+pub fn is_precondition_met(req: &HttpRequest, current: &EntityTag) -> bool {
+    if !req.headers().contains_key(IF_MATCH) {
+        return true;
+    }
+
+    match IfMatch::parse(req) {
+        Ok(IfMatch::Any) => true,
+        Ok(IfMatch::Items(tags)) => tags.iter().any(|tag| tag.strong_eq(current)),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        http::header::{ACCEPT_LANGUAGE, IF_MODIFIED_SINCE, IF_NONE_MATCH},
+        test::TestRequest,
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use serde_json::json;
+
+    #[rstest]
+    #[case(0, "Thu, 01 Jan 1970 00:00:00 GMT")]
+    #[case(-10, "Thu, 01 Jan 1970 00:00:00 GMT")]
+    #[case(1_733_234_400, "Tue, 03 Dec 2024 14:00:00 GMT")]
+    fn last_modified_format(#[case] timestamp: i64, #[case] expected: &str) {
+        assert_eq!(last_modified(timestamp).to_string(), expected);
+    }
+
+    #[rstest]
+    #[case(Some("Tue, 03 Dec 2024 14:00:00 GMT"), true)]
+    #[case(Some("Tue, 03 Dec 2024 14:00:01 GMT"), true)]
+    #[case(Some("Tue, 03 Dec 2024 13:59:59 GMT"), false)]
+    #[case(Some("yesterday"), false)]
+    #[case(None, false)]
+    fn not_modified_check(#[case] since: Option<&str>, #[case] expected: bool) {
+        let mut req = TestRequest::default();
+        if let Some(since) = since {
+            req = req.insert_header((IF_MODIFIED_SINCE, since));
+        }
+
+        assert_eq!(
+            is_not_modified(&req.to_http_request(), 1_733_234_400),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(None, true)]
+    #[case(Some("*"), true)]
+    #[case(Some("CURRENT"), true)]
+    #[case(Some("\"stale\", CURRENT"), true)]
+    #[case(Some("W/CURRENT"), false)]
+    #[case(Some("\"stale\""), false)]
+    #[case(Some("not a tag"), false)]
+    fn if_match_check(#[case] if_match: Option<&str>, #[case] expected: bool) {
+        let current = resource_etag(&json!({"name": "Margarita"})).unwrap();
+        let mut req = TestRequest::default();
+        if let Some(if_match) = if_match {
+            req = req.insert_header((IF_MATCH, if_match.replace("CURRENT", &current.to_string())));
+        }
+
+        assert_eq!(
+            is_precondition_met(&req.to_http_request(), &current),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(false, None, false)]
+    #[case(false, Some("*"), false)]
+    #[case(true, None, true)]
+    #[case(true, Some("*"), false)]
+    #[case(true, Some("not a tag"), false)]
+    fn required_if_match(
+        #[case] require_if_match: bool,
+        #[case] if_match: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        let mut req = TestRequest::default();
+        if let Some(if_match) = if_match {
+            req = req.insert_header((IF_MATCH, if_match));
+        }
+
+        assert_eq!(
+            Preconditions::new(require_if_match).is_missing(&req.to_http_request()),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(None, false)]
+    #[case(Some("*"), true)]
+    #[case(Some("\"v1\""), true)]
+    #[case(Some("W/\"v1\""), true)]
+    #[case(Some("\"v0\", \"v1\""), true)]
+    #[case(Some("\"v0\""), false)]
+    fn if_none_match_check(#[case] if_none_match: Option<&str>, #[case] expected: bool) {
+        let mut req = TestRequest::default();
+        if let Some(if_none_match) = if_none_match {
+            req = req.insert_header((IF_NONE_MATCH, if_none_match));
+        }
+
+        assert_eq!(
+            is_etag_not_modified(&req.to_http_request(), &EntityTag::new_strong("v1".into())),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(None, None)]
+    #[case(Some("es-ES"), Some(Language::Es))]
+    #[case(Some("fr-FR, ES;q=0.8, en;q=0.9"), Some(Language::En))]
+    #[case(Some("fr, *;q=0.5"), None)]
+    #[case(Some("not;a;header"), None)]
+    fn accepted_languages(#[case] header: Option<&str>, #[case] expected: Option<Language>) {
+        let mut req = TestRequest::default();
+        if let Some(header) = header {
+            req = req.insert_header((ACCEPT_LANGUAGE, header));
+        }
+
+        assert_eq!(accepted_language(&req.to_http_request()), expected);
+    }
+
+    #[rstest]
+    fn etags_follow_the_content() {
+        let tag = resource_etag(&json!({"name": "Margarita"})).unwrap();
+        assert!(!tag.weak);
+        assert_eq!(tag.tag().len(), ETAG_LENGTH);
+        assert_eq!(resource_etag(&json!({"name": "Margarita"})).unwrap(), tag);
+        assert_ne!(resource_etag(&json!({"name": "Mojito"})).unwrap(), tag);
+    }
+}
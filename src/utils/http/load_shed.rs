@@ -0,0 +1,265 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Load shedding of the non-essential endpoints of the API.
+//!
+//! # Description
+//!
+//! During traffic spikes, requests pile up waiting for a connection of the DB pool, and the wait time of all of them
+//! grows until clients start to time out and retry, making things worse. To avoid such cascades, every worker keeps
+//! the count of the requests that are being served ([InFlight]). When the count exceeds a limit, the requests to the
+//! non-essential endpoints (retrieval and search of resources) are answered straight away with a code **503** and a
+//! *Retry-After* header. The health check and the requests that modify the content are always served.
+//!
+//! [LoadShed::track] counts the requests of a scope of the API, and [LoadShed::shed] rejects the requests of a scope
+//! when the worker is overloaded.
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{CACHE_CONTROL, RETRY_AFTER},
+        Method,
+    },
+    Error, HttpResponse,
+};
+use std::{
+    cell::Cell,
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+    time::Duration,
+};
+use tracing::debug;
+
+/// Count of the requests being served by a worker.
+///
+/// # Description
+///
+/// Workers serve the requests in a single thread, so the count is not shared between workers. A new counter shall be
+/// built within the factory of the application.
+#[derive(Debug, Clone, Default)]
+pub struct InFlight {
+    count: Rc<Cell<usize>>,
+}
+
+impl InFlight {
+    pub fn new() -> Self {
+        InFlight::default()
+    }
+
+    /// Amount of requests being served.
+    pub fn count(&self) -> usize {
+        self.count.get()
+    }
+
+    /// Register a new request. The request is released when the returned guard is dropped.
+    fn enter(&self) -> InFlightGuard {
+        self.count.set(self.count.get() + 1);
+        InFlightGuard {
+            count: self.count.clone(),
+        }
+    }
+}
+
+/// Request registered in an [InFlight] counter.
+struct InFlightGuard {
+    count: Rc<Cell<usize>>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.count.set(self.count.get().saturating_sub(1));
+    }
+}
+
+/// Middleware that counts the requests of a scope of the API, or sheds them when the worker is overloaded.
+///
+/// # Description
+///
+/// Only requests using a safe method (`GET` or `HEAD`) are shed. Shed requests are answered with a code **503** and a
+/// *Retry-After* header. A limit of `None` disables the shedding.
+#[derive(Debug, Clone)]
+pub struct LoadShed {
+    in_flight: InFlight,
+    track: bool,
+    limit: Option<usize>,
+    retry_after: Duration,
+}
+
+impl LoadShed {
+    /// Count the requests of a scope in `in_flight`.
+    pub fn track(in_flight: InFlight) -> Self {
+        LoadShed {
+            in_flight,
+            track: true,
+            limit: None,
+            retry_after: Duration::ZERO,
+        }
+    }
+
+    /// Shed the requests of a scope when `in_flight` exceeds `limit`.
+    ///
+    /// # Description
+    ///
+    /// The requests shall be counted by an outer [LoadShed::track], otherwise nothing is shed.
+    pub fn shed(in_flight: InFlight, limit: Option<usize>, retry_after: Duration) -> Self {
+        LoadShed {
+            in_flight,
+            track: false,
+            limit,
+            retry_after,
+        }
+    }
+
+    /// Check whether a request using `method` shall be shed.
+    pub fn sheds(&self, method: &Method) -> bool {
+        self.limit
+            .is_some_and(|limit| self.in_flight.count() > limit)
+            && matches!(*method, Method::GET | Method::HEAD)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for LoadShed
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = LoadShedMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(LoadShedMiddleware {
+            service: Rc::new(service),
+            load_shed: self.clone(),
+        }))
+    }
+}
+
+/// Service built by [LoadShed].
+pub struct LoadShedMiddleware<S> {
+    service: Rc<S>,
+    load_shed: LoadShed,
+}
+
+impl<S, B> Service<ServiceRequest> for LoadShedMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if self.load_shed.track {
+            let guard = self.load_shed.in_flight.enter();
+            let service = self.service.clone();
+            return Box::pin(async move {
+                let response = service.call(req).await;
+                drop(guard);
+                Ok(response?.map_into_left_body())
+            });
+        }
+
+        if self.load_shed.sheds(req.method()) {
+            debug!(
+                in_flight = self.load_shed.in_flight.count(),
+                "Request {} shed, the worker is overloaded",
+                req.path()
+            );
+            let response = HttpResponse::ServiceUnavailable()
+                .append_header((
+                    RETRY_AFTER,
+                    self.load_shed.retry_after.as_secs().to_string(),
+                ))
+                .append_header((CACHE_CONTROL, "no-cache"))
+                .finish()
+                .map_into_right_body();
+
+            return Box::pin(async move { Ok(req.into_response(response)) });
+        }
+
+        let service = self.service.clone();
+        Box::pin(async move { Ok(service.call(req).await?.map_into_left_body()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        test::{call_service, init_service, TestRequest},
+        web, App,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[actix_web::test]
+    async fn requests_are_counted() {
+        let in_flight = InFlight::new();
+        let counter = in_flight.clone();
+        let app = init_service(App::new().wrap(LoadShed::track(in_flight.clone())).route(
+            "/",
+            web::get().to(move || {
+                let count = counter.count();
+                async move { HttpResponse::Ok().body(count.to_string()) }
+            }),
+        ))
+        .await;
+
+        let response = call_service(&app, TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(
+            actix_web::test::read_body(response).await,
+            web::Bytes::from("1")
+        );
+        assert_eq!(in_flight.count(), 0);
+    }
+
+    #[actix_web::test]
+    async fn overloaded_workers_shed_reads() {
+        let in_flight = InFlight::new();
+        let app = init_service(
+            App::new().service(
+                web::resource("/")
+                    .wrap(LoadShed::shed(
+                        in_flight.clone(),
+                        Some(1),
+                        Duration::from_secs(5),
+                    ))
+                    .route(web::get().to(HttpResponse::Ok))
+                    .route(web::post().to(HttpResponse::Ok)),
+            ),
+        )
+        .await;
+
+        let _first = in_flight.enter();
+        let second = in_flight.enter();
+        let response = call_service(&app, TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(response.status(), 503);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "5");
+        // Writes keep the priority.
+        let response = call_service(&app, TestRequest::post().uri("/").to_request()).await;
+        assert_eq!(response.status(), 200);
+
+        drop(second);
+        let response = call_service(&app, TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(response.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn disabled_shedding_serves_everything() {
+        let in_flight = InFlight::new();
+        let load_shed = LoadShed::shed(in_flight.clone(), None, Duration::from_secs(5));
+        let _guards = (0..100).map(|_| in_flight.enter()).collect::<Vec<_>>();
+        assert!(!load_shed.sheds(&Method::GET));
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Announcement of the scheduled maintenance windows.
+//!
+//! # Description
+//!
+//! Administrators announce the maintenance windows of the server using `POST /admin/maintenance/schedule`. The
+//! announced window is kept in memory ([MaintenanceSchedule]), so it is checked without the DB, and every response
+//! of the API includes the header *X-Maintenance-Scheduled* with the start of the window until the window passes
+//! (see [MaintenanceNotice]).
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    web::Data,
+    Error,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+    sync::RwLock,
+};
+use utoipa::ToSchema;
+
+/// Header that announces a maintenance window.
+pub const MAINTENANCE_HEADER: HeaderName = HeaderName::from_static("x-maintenance-scheduled");
+
+/// Window of time in which the server is not available due to maintenance tasks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MaintenanceWindow {
+    #[schema(value_type = String, example = "2025-09-11T22:00:00Z")]
+    pub starts_at: DateTime<Utc>,
+    #[schema(value_type = String, example = "2025-09-11T23:30:00Z")]
+    pub ends_at: DateTime<Utc>,
+    /// Explanation of the maintenance for the clients.
+    #[schema(example = "Upgrade of the DB server.")]
+    pub message: Option<String>,
+}
+
+impl MaintenanceWindow {
+    /// Check whether the window passed at the given time.
+    pub fn has_passed(&self, now: DateTime<Utc>) -> bool {
+        self.ends_at <= now
+    }
+
+    /// Check whether the window is ongoing at the given time.
+    pub fn is_ongoing(&self, now: DateTime<Utc>) -> bool {
+        self.starts_at <= now && !self.has_passed(now)
+    }
+}
+
+/// Maintenance window announced to the clients of the API.
+#[derive(Debug, Default)]
+pub struct MaintenanceSchedule {
+    window: RwLock<Option<MaintenanceWindow>>,
+}
+
+impl MaintenanceSchedule {
+    pub fn new(window: Option<MaintenanceWindow>) -> Self {
+        MaintenanceSchedule {
+            window: RwLock::new(window),
+        }
+    }
+
+    /// Retrieve the announced window, unless it passed at the given time.
+    pub fn window(&self, now: DateTime<Utc>) -> Option<MaintenanceWindow> {
+        match self.window.read() {
+            Ok(window) => window.clone().filter(|w| !w.has_passed(now)),
+            Err(_) => None,
+        }
+    }
+
+    /// Announce a new window, or cancel the announced one using `None`.
+    pub fn set(&self, window: Option<MaintenanceWindow>) {
+        if let Ok(mut current) = self.window.write() {
+            *current = window;
+        }
+    }
+}
+
+/// Middleware that adds the header *X-Maintenance-Scheduled* to the responses while a maintenance window is
+/// announced.
+#[derive(Clone)]
+pub struct MaintenanceNotice {
+    schedule: Data<MaintenanceSchedule>,
+}
+
+impl MaintenanceNotice {
+    pub fn new(schedule: Data<MaintenanceSchedule>) -> Self {
+        MaintenanceNotice { schedule }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for MaintenanceNotice
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = MaintenanceNoticeMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MaintenanceNoticeMiddleware {
+            service: Rc::new(service),
+            schedule: self.schedule.clone(),
+        }))
+    }
+}
+
+/// Service built by [MaintenanceNotice].
+pub struct MaintenanceNoticeMiddleware<S> {
+    service: Rc<S>,
+    schedule: Data<MaintenanceSchedule>,
+}
+
+impl<S, B> Service<ServiceRequest> for MaintenanceNoticeMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let schedule = self.schedule.clone();
+
+        Box::pin(async move {
+            let mut response = service.call(req).await?;
+            // The window is checked once the request is served, so the announcements of the request are included.
+            if let Some(window) = schedule.window(Utc::now()) {
+                let starts_at = window.starts_at.to_rfc3339_opts(SecondsFormat::Secs, true);
+                if let Ok(value) = HeaderValue::from_str(&starts_at) {
+                    response.headers_mut().insert(MAINTENANCE_HEADER, value);
+                }
+            }
+
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        test::{call_service, init_service, TestRequest},
+        web, App, HttpResponse,
+    };
+    use chrono::TimeDelta;
+    use pretty_assertions::assert_eq;
+
+    fn window(starts_in: TimeDelta, length: TimeDelta) -> MaintenanceWindow {
+        let starts_at = Utc::now() + starts_in;
+        MaintenanceWindow {
+            starts_at,
+            ends_at: starts_at + length,
+            message: None,
+        }
+    }
+
+    #[test]
+    fn passed_windows_are_not_announced() {
+        let schedule = MaintenanceSchedule::default();
+        assert!(schedule.window(Utc::now()).is_none());
+
+        schedule.set(Some(window(TimeDelta::hours(-2), TimeDelta::hours(1))));
+        assert!(schedule.window(Utc::now()).is_none());
+
+        let ongoing = window(TimeDelta::minutes(-10), TimeDelta::hours(1));
+        schedule.set(Some(ongoing.clone()));
+        assert_eq!(schedule.window(Utc::now()), Some(ongoing.clone()));
+        assert!(ongoing.is_ongoing(Utc::now()));
+    }
+
+    #[actix_web::test]
+    async fn responses_announce_the_window() {
+        let schedule = Data::new(MaintenanceSchedule::default());
+        let app = init_service(
+            App::new()
+                .wrap(MaintenanceNotice::new(schedule.clone()))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let response = call_service(&app, TestRequest::get().uri("/").to_request()).await;
+        assert!(response.headers().get(MAINTENANCE_HEADER).is_none());
+
+        let upcoming = window(TimeDelta::days(1), TimeDelta::hours(1));
+        schedule.set(Some(upcoming.clone()));
+        let response = call_service(&app, TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(
+            response.headers().get(MAINTENANCE_HEADER).unwrap(),
+            &upcoming
+                .starts_at
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        );
+    }
+}
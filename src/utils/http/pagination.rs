@@ -0,0 +1,233 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Pagination of the collection resources.
+//!
+//! # Description
+//!
+//! Searches are paginated using `limit` (amount of results of the page) and `offset` (amount of results skipped).
+//! The limit can't exceed the maximum page size of the server, which is set by the settings (see [MaxPageSize]).
+//! Paginated responses include:
+//! - The total amount of results in the `X-Total-Count` header.
+//! - Links to the `first`, `prev`, `next` and `last` pages in the `Link` header (RFC 8288). Links are relative to the
+//!   host of the API, and they keep the query of the request, except for the API key.
+
+use crate::utils::http::X_TOTAL_COUNT;
+use actix_web::{http::header::LINK, HttpRequest, HttpResponseBuilder};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use utoipa::IntoParams;
+
+/// Amount of results per page when the request doesn't give a limit.
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+
+/// Maximum amount of results per page when the settings don't set it.
+pub const DEFAULT_MAX_PAGE_SIZE: u32 = 100;
+
+/// Query params that are replaced, or removed, from the query of the request when building the links to the pages.
+const PAGE_PARAMS: [&str; 3] = ["limit", "offset", "api_key"];
+
+/// Maximum amount of results per page.
+///
+/// # Description
+///
+/// The size is given by the settings (`application.max_page_size`), and it is shared with the handlers using
+/// `web::Data`. Sizes lower than [DEFAULT_PAGE_SIZE] are raised to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaxPageSize(u32);
+
+impl Default for MaxPageSize {
+    fn default() -> Self {
+        MaxPageSize(DEFAULT_MAX_PAGE_SIZE)
+    }
+}
+
+impl MaxPageSize {
+    pub fn new(size: u32) -> Self {
+        if size < DEFAULT_PAGE_SIZE {
+            warn!("The maximum page size ({size}) is lower than the default page size ({DEFAULT_PAGE_SIZE})");
+        }
+
+        MaxPageSize(size.max(DEFAULT_PAGE_SIZE))
+    }
+
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+}
+
+/// Query params of the paginated resources.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, IntoParams)]
+pub struct PageQuery {
+    /// Amount of results of the page, 20 by default. The maximum depends on the settings of the server (100 by
+    /// default).
+    #[param(example = 20)]
+    pub limit: Option<u32>,
+    /// Amount of results skipped, 0 by default.
+    #[param(example = 0)]
+    pub offset: Option<u32>,
+}
+
+impl PageQuery {
+    /// Get the page requested. See [Page::new].
+    pub fn page(&self, max: MaxPageSize) -> Result<Page, String> {
+        Page::new(self.limit, self.offset, max)
+    }
+}
+
+/// Page of the results of a search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Page {
+    pub limit: usize,
+    pub offset: usize,
+}
+
+impl Page {
+    /// Validate the limit and the offset of a page.
+    ///
+    /// # Description
+    ///
+    /// A description of the issue is returned when the limit is not between 1 and `max`.
+    pub fn new(limit: Option<u32>, offset: Option<u32>, max: MaxPageSize) -> Result<Self, String> {
+        let max = max.get();
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE.min(max));
+
+        if !(1..=max).contains(&limit) {
+            return Err(format!("The limit must be between 1 and {max}"));
+        }
+
+        Ok(Page {
+            limit: limit as usize,
+            offset: offset.unwrap_or_default() as usize,
+        })
+    }
+
+    /// Take the results of the page from the whole list of results. A page beyond the end of the list is empty.
+    pub fn slice<T>(&self, results: Vec<T>) -> Vec<T> {
+        results
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit)
+            .collect()
+    }
+
+    /// Build the value of the `Link` header of a page, given the path and the query of the request.
+    ///
+    /// # Description
+    ///
+    /// `None` is returned when there are no results at all.
+    pub fn links(&self, path: &str, query: &str, total: usize) -> Option<String> {
+        if total == 0 {
+            return None;
+        }
+
+        let params = serde_urlencoded::from_str::<Vec<(String, String)>>(query)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(key, _)| !PAGE_PARAMS.contains(&key.as_str()))
+            .collect::<Vec<(String, String)>>();
+        let link = |offset: usize, rel: &str| {
+            let mut params = params.clone();
+            params.push(("limit".into(), self.limit.to_string()));
+            params.push(("offset".into(), offset.to_string()));
+            let query = serde_urlencoded::to_string(params).unwrap_or_default();
+            format!("<{path}?{query}>; rel=\"{rel}\"")
+        };
+
+        let last = (total - 1) / self.limit * self.limit;
+        let mut links = vec![link(0, "first")];
+        if self.offset > 0 {
+            links.push(link(
+                self.offset.saturating_sub(self.limit).min(last),
+                "prev",
+            ));
+        }
+        if self.offset + self.limit < total {
+            links.push(link(self.offset + self.limit, "next"));
+        }
+        links.push(link(last, "last"));
+
+        Some(links.join(", "))
+    }
+
+    /// Add the `X-Total-Count` and `Link` headers of the page to a response.
+    pub fn insert_headers(
+        &self,
+        response: &mut HttpResponseBuilder,
+        request: &HttpRequest,
+        total: usize,
+    ) {
+        response.insert_header((X_TOTAL_COUNT, total.to_string()));
+        if let Some(links) = self.links(request.path(), request.query_string(), total) {
+            response.insert_header((LINK, links));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case(None, None, Ok(Page { limit: DEFAULT_PAGE_SIZE as usize, offset: 0 }))]
+    #[case(Some(1), Some(40), Ok(Page { limit: 1, offset: 40 }))]
+    #[case(Some(DEFAULT_MAX_PAGE_SIZE), None, Ok(Page { limit: DEFAULT_MAX_PAGE_SIZE as usize, offset: 0 }))]
+    #[case(Some(0), None, Err(format!("The limit must be between 1 and {DEFAULT_MAX_PAGE_SIZE}")))]
+    #[case(Some(DEFAULT_MAX_PAGE_SIZE + 1), None, Err(format!("The limit must be between 1 and {DEFAULT_MAX_PAGE_SIZE}")))]
+    fn pages_are_validated(
+        #[case] limit: Option<u32>,
+        #[case] offset: Option<u32>,
+        #[case] expected: Result<Page, String>,
+    ) {
+        assert_eq!(Page::new(limit, offset, MaxPageSize::default()), expected);
+    }
+
+    #[rstest]
+    fn small_maximum_sizes_are_raised() {
+        assert_eq!(MaxPageSize::new(5).get(), DEFAULT_PAGE_SIZE);
+        assert_eq!(MaxPageSize::new(500).get(), 500);
+    }
+
+    #[rstest]
+    fn pages_are_sliced() {
+        let page = Page {
+            limit: 2,
+            offset: 3,
+        };
+        assert_eq!(page.slice((0..10).collect()), [3, 4]);
+        assert_eq!(page.slice((0..4).collect()), [3]);
+        assert!(page.slice((0..3).collect::<Vec<u8>>()).is_empty());
+    }
+
+    #[rstest]
+    fn links_point_to_the_other_pages() {
+        let page = Page {
+            limit: 10,
+            offset: 10,
+        };
+        assert_eq!(
+            page.links("/api/recipe", "name=pi%C3%B1a&api_key=secret&offset=10", 35)
+                .unwrap(),
+            "</api/recipe?name=pi%C3%B1a&limit=10&offset=0>; rel=\"first\", \
+            </api/recipe?name=pi%C3%B1a&limit=10&offset=0>; rel=\"prev\", \
+            </api/recipe?name=pi%C3%B1a&limit=10&offset=20>; rel=\"next\", \
+            </api/recipe?name=pi%C3%B1a&limit=10&offset=30>; rel=\"last\""
+        );
+
+        let page = Page {
+            limit: 10,
+            offset: 0,
+        };
+        assert_eq!(
+            page.links("/api/author", "", 10).unwrap(),
+            "</api/author?limit=10&offset=0>; rel=\"first\", \
+            </api/author?limit=10&offset=0>; rel=\"last\""
+        );
+        assert_eq!(page.links("/api/author", "", 0), None);
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Public read-only deployment profile.
+//!
+//! # Description
+//!
+//! Mirrors and cache nodes can run the application against a replica of the DB, which doesn't accept writes. When the
+//! read-only mode is enabled (`application.read_only`), the [ReadOnly] middleware rejects the requests to the
+//! endpoints that would modify the DB with a code **403**, and an explanation of the reason. Some endpoints only read
+//! data despite using `POST` (i.e. `POST /recipe/search`), these are exempted using [ReadOnly::except]. The mail
+//! client is not required either, as no emails are sent in this mode.
+//!
+//! Access checks of the restricted endpoints that only read data are still served. However, their outcome is not
+//! stored in the DB (see [crate::authentication::register_auth_failure]), so clients can't get locked on these nodes.
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header::CACHE_CONTROL, Method},
+    Error, HttpResponse,
+};
+use serde_json::json;
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+};
+use tracing::debug;
+
+/// Explanation included in the rejected requests.
+pub const READ_ONLY_EXPLANATION: &str =
+    "This server is a read-only mirror of La Coctelera. Modifications shall be sent to the main server.";
+
+/// Middleware that rejects the requests that would modify the DB.
+///
+/// # Description
+///
+/// By default, requests using a safe method (`GET`, `HEAD` or `OPTIONS`) are served, and the rest are answered with a
+/// code **403**. Some scopes of the API modify the DB even on `GET` requests (i.e. the validation of a token request),
+/// [ReadOnly::all] rejects all the requests of such scopes. A disabled middleware serves all the requests.
+///
+/// The other way around, [ReadOnly::except] serves the requests to the given paths whatever their method. Paths are
+/// relative to the scope wrapped by the middleware.
+#[derive(Debug, Clone, Copy)]
+pub struct ReadOnly {
+    enabled: bool,
+    reject_all: bool,
+    exempt: &'static [&'static str],
+}
+
+impl ReadOnly {
+    /// Reject the requests using an unsafe method when `enabled` is `true`.
+    pub fn new(enabled: bool) -> Self {
+        ReadOnly {
+            enabled,
+            reject_all: false,
+            exempt: &[],
+        }
+    }
+
+    /// Reject all the requests when `enabled` is `true`.
+    pub fn all(enabled: bool) -> Self {
+        ReadOnly {
+            enabled,
+            reject_all: true,
+            exempt: &[],
+        }
+    }
+
+    /// Serve the requests to the given paths, as their endpoints don't modify the DB.
+    pub fn except(mut self, paths: &'static [&'static str]) -> Self {
+        self.exempt = paths;
+        self
+    }
+
+    /// Check whether a request using `method` to `path` shall be rejected.
+    pub fn rejects(&self, method: &Method, path: &str) -> bool {
+        self.enabled
+            && (self.reject_all
+                || !(matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+                    || self.exempt.contains(&path)))
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ReadOnly
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = ReadOnlyMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ReadOnlyMiddleware {
+            service: Rc::new(service),
+            read_only: *self,
+        }))
+    }
+}
+
+/// Service built by [ReadOnly].
+pub struct ReadOnlyMiddleware<S> {
+    service: Rc<S>,
+    read_only: ReadOnly,
+}
+
+impl<S, B> Service<ServiceRequest> for ReadOnlyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        // The path of the scope wrapped by the middleware is already consumed.
+        if self
+            .read_only
+            .rejects(req.method(), req.match_info().unprocessed())
+        {
+            debug!(
+                "Request {} {} rejected in read-only mode",
+                req.method(),
+                req.path()
+            );
+            let response = HttpResponse::Forbidden()
+                .append_header((CACHE_CONTROL, "no-cache"))
+                .json(json!({"read_only": true, "explanation": READ_ONLY_EXPLANATION}))
+                .map_into_right_body();
+
+            return Box::pin(async move { Ok(req.into_response(response)) });
+        }
+
+        let service = self.service.clone();
+        Box::pin(async move { Ok(service.call(req).await?.map_into_left_body()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        test::{call_service, init_service, read_body_json, TestRequest},
+        web, App,
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Method::GET, false)]
+    #[case(Method::HEAD, false)]
+    #[case(Method::OPTIONS, false)]
+    #[case(Method::POST, true)]
+    #[case(Method::PATCH, true)]
+    #[case(Method::DELETE, true)]
+    fn unsafe_methods_are_rejected(#[case] method: Method, #[case] rejected: bool) {
+        assert_eq!(ReadOnly::new(true).rejects(&method, "/"), rejected);
+        assert!(ReadOnly::all(true).rejects(&method, "/"));
+        assert!(!ReadOnly::new(false).rejects(&method, "/"));
+        assert!(!ReadOnly::all(false).rejects(&method, "/"));
+    }
+
+    #[test]
+    fn exempted_paths_are_served() {
+        let read_only = ReadOnly::new(true).except(&["/search"]);
+
+        assert!(!read_only.rejects(&Method::POST, "/search"));
+        assert!(read_only.rejects(&Method::POST, "/"));
+        assert!(read_only.rejects(&Method::DELETE, "/search/1"));
+    }
+
+    #[actix_web::test]
+    async fn rejected_requests_get_403() {
+        let app = init_service(
+            App::new().service(
+                web::resource("/")
+                    .wrap(ReadOnly::new(true))
+                    .route(web::get().to(HttpResponse::Ok))
+                    .route(web::post().to(HttpResponse::Ok)),
+            ),
+        )
+        .await;
+
+        let response = call_service(&app, TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(response.status(), 200);
+
+        let response = call_service(&app, TestRequest::post().uri("/").to_request()).await;
+        assert_eq!(response.status(), 403);
+        let body: serde_json::Value = read_body_json(response).await;
+        assert_eq!(body["explanation"], READ_ONLY_EXPLANATION);
+    }
+
+    #[actix_web::test]
+    async fn exempted_paths_are_relative_to_the_scope() {
+        let app = init_service(
+            App::new().service(
+                web::scope("/api")
+                    .wrap(ReadOnly::new(true).except(&["/search"]))
+                    .route("/search", web::post().to(HttpResponse::Ok))
+                    .route("/", web::post().to(HttpResponse::Ok)),
+            ),
+        )
+        .await;
+
+        let response =
+            call_service(&app, TestRequest::post().uri("/api/search").to_request()).await;
+        assert_eq!(response.status(), 200);
+
+        let response = call_service(&app, TestRequest::post().uri("/api/").to_request()).await;
+        assert_eq!(response.status(), 403);
+    }
+}
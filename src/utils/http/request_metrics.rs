@@ -0,0 +1,218 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Registration of the requests served by the API in the [MetricsRegistry].
+//!
+//! # Description
+//!
+//! Requests are grouped by resource, which is the first segment of the path of the matched route after the base URL
+//! of the API, i.e. `recipe` for `/v0/recipe/{id}`. Requests that match no route are grouped as `unmatched`, so
+//! clients can't grow the registry using made-up paths.
+//!
+//! The middleware also registers the requests in the [UsageAnalytics] registry, when given one (see
+//! [RequestMetrics::with_analytics]). The analytics count the requests per endpoint, i.e. `GET /recipe/{id}`.
+
+use crate::utils::metrics::{MetricsRegistry, UsageAnalytics};
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::Method,
+    Error,
+};
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+};
+
+/// Resource of the requests that match no route.
+pub const UNMATCHED_RESOURCE: &str = "unmatched";
+
+/// Middleware that registers the requests of a scope of the API in a [MetricsRegistry].
+#[derive(Debug, Clone)]
+pub struct RequestMetrics {
+    prefix: Rc<str>,
+    registry: &'static MetricsRegistry,
+    analytics: Option<&'static UsageAnalytics>,
+}
+
+impl RequestMetrics {
+    /// Register the requests of the scope mounted at `prefix` in `registry`.
+    pub fn new(prefix: &str, registry: &'static MetricsRegistry) -> Self {
+        RequestMetrics {
+            prefix: Rc::from(prefix),
+            registry,
+            analytics: None,
+        }
+    }
+
+    /// Register the requests in the usage analytics too.
+    pub fn with_analytics(mut self, analytics: &'static UsageAnalytics) -> Self {
+        self.analytics = Some(analytics);
+        self
+    }
+
+    /// Resource of a request, given the pattern of the route it matched.
+    pub fn resource<'a>(&self, pattern: Option<&'a str>) -> &'a str {
+        pattern
+            .and_then(|p| p.strip_prefix(&*self.prefix))
+            .and_then(|p| p.trim_start_matches('/').split('/').next())
+            .filter(|resource| !resource.is_empty())
+            .unwrap_or(UNMATCHED_RESOURCE)
+    }
+
+    /// Endpoint of a request, i.e. `GET /recipe/{id}`, given the pattern of the route it matched. No value is
+    /// returned for the requests that match no route.
+    pub fn endpoint(&self, method: &Method, pattern: Option<&str>) -> Option<String> {
+        pattern
+            .and_then(|p| p.strip_prefix(&*self.prefix))
+            .filter(|p| p.len() > 1)
+            .map(|p| format!("{method} /{}", p.trim_start_matches('/')))
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestMetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestMetricsMiddleware {
+            service: Rc::new(service),
+            metrics: self.clone(),
+        }))
+    }
+}
+
+/// Service built by [RequestMetrics].
+pub struct RequestMetricsMiddleware<S> {
+    service: Rc<S>,
+    metrics: RequestMetrics,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestMetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let metrics = self.metrics.clone();
+
+        Box::pin(async move {
+            let response = service.call(req).await;
+            match &response {
+                Ok(response) => {
+                    let pattern = response.request().match_pattern();
+                    metrics.registry.record_request(
+                        metrics.resource(pattern.as_deref()),
+                        response.status().is_server_error(),
+                    );
+                    if let Some(analytics) = metrics.analytics {
+                        if let Some(endpoint) =
+                            metrics.endpoint(response.request().method(), pattern.as_deref())
+                        {
+                            analytics.record_hit(&endpoint);
+                        }
+                    }
+                }
+                Err(_) => metrics.registry.record_request(UNMATCHED_RESOURCE, true),
+            }
+
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        test::{call_service, init_service, TestRequest},
+        web, App, HttpResponse,
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case(Some("/v0/recipe/{id}"), "recipe")]
+    #[case(Some("/v0/health"), "health")]
+    #[case(Some("/v0/me/notifications"), "me")]
+    #[case(Some("/v0"), UNMATCHED_RESOURCE)]
+    #[case(Some("/other/recipe"), UNMATCHED_RESOURCE)]
+    #[case(None, UNMATCHED_RESOURCE)]
+    fn resources_come_from_the_routes(#[case] pattern: Option<&str>, #[case] expected: &str) {
+        let metrics = RequestMetrics::new("/v0", Box::leak(Box::new(MetricsRegistry::new())));
+        assert_eq!(metrics.resource(pattern), expected);
+    }
+
+    #[rstest]
+    #[case(Method::GET, Some("/v0/recipe/{id}"), Some("GET /recipe/{id}"))]
+    #[case(Method::POST, Some("/v0/recipe"), Some("POST /recipe"))]
+    #[case(Method::GET, Some("/v0"), None)]
+    #[case(Method::GET, Some("/other/recipe"), None)]
+    #[case(Method::GET, None, None)]
+    fn endpoints_come_from_the_routes(
+        #[case] method: Method,
+        #[case] pattern: Option<&str>,
+        #[case] expected: Option<&str>,
+    ) {
+        let metrics = RequestMetrics::new("/v0", Box::leak(Box::new(MetricsRegistry::new())));
+        assert_eq!(metrics.endpoint(&method, pattern).as_deref(), expected);
+    }
+
+    #[actix_web::test]
+    async fn requests_are_registered() {
+        let registry: &'static MetricsRegistry = Box::leak(Box::new(MetricsRegistry::new()));
+        let analytics: &'static UsageAnalytics = Box::leak(Box::new(UsageAnalytics::new()));
+        analytics.configure(true, 5);
+        let app = init_service(
+            App::new().service(
+                web::scope("/v0")
+                    .wrap(RequestMetrics::new("/v0", registry).with_analytics(analytics))
+                    .route("/recipe/{id}", web::get().to(HttpResponse::Ok))
+                    .route(
+                        "/ingredient",
+                        web::get().to(HttpResponse::InternalServerError),
+                    ),
+            ),
+        )
+        .await;
+
+        for uri in [
+            "/v0/recipe/1",
+            "/v0/recipe/2",
+            "/v0/ingredient",
+            "/v0/unknown",
+        ] {
+            call_service(&app, TestRequest::get().uri(uri).to_request()).await;
+        }
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.requests.requests, 4);
+        assert_eq!(snapshot.requests.errors, 1);
+        assert_eq!(snapshot.resources["recipe"].requests, 2);
+        assert_eq!(snapshot.resources["ingredient"].errors, 1);
+        assert_eq!(snapshot.resources[UNMATCHED_RESOURCE].requests, 1);
+
+        let usage = analytics.take();
+        assert_eq!(usage.endpoints["GET /recipe/{id}"], 2);
+        assert_eq!(usage.endpoints["GET /ingredient"], 1);
+        assert_eq!(usage.endpoints.len(), 2);
+    }
+}
@@ -0,0 +1,530 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Per-IP throttling of the anonymous endpoints of the API.
+//!
+//! # Description
+//!
+//! Public endpoints, such as the token request form or the search of resources, can be used by anyone. To avoid abuse,
+//! the amount of requests issued by a single IP within a time window is tracked. When a client exceeds the limit, it
+//! is banned for some time and its requests are answered with a code **429** and a *Retry-After* header. The ban time
+//! doubles every time the same client offends again, up to a maximum.
+//!
+//! IPv6 clients are tracked by their /64 prefix, as a single host usually owns a whole /64 subnet and could rotate its
+//! address within it to avoid the bans.
+//!
+//! [IpThrottle] keeps the count of the requests, and [Throttle] is the middleware that applies it to a scope of the
+//! API. Scopes that mix public searches with other endpoints restrict the throttle to the searches using
+//! [Throttle::only].
+
+use crate::utils::http::client_ip;
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{CACHE_CONTROL, RETRY_AFTER},
+        Method,
+    },
+    Error, HttpResponse,
+};
+use std::{
+    collections::HashMap,
+    future::{ready, Future, Ready},
+    net::{IpAddr, Ipv6Addr},
+    pin::Pin,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::{debug, warn};
+
+/// Minimum time between two removals of the stale entries.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Outcome of the check of a request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThrottleDecision {
+    /// The request can be served.
+    Allowed,
+    /// The client is banned. The ban is lifted after the given time.
+    Banned(Duration),
+}
+
+/// Track record of a client.
+#[derive(Debug, Clone)]
+struct ClientRecord {
+    window_start: Instant,
+    requests: u32,
+    offences: u32,
+    last_offence: Option<Instant>,
+    banned_until: Option<Instant>,
+}
+
+impl ClientRecord {
+    fn new(now: Instant) -> Self {
+        ClientRecord {
+            window_start: now,
+            requests: 0,
+            offences: 0,
+            last_offence: None,
+            banned_until: None,
+        }
+    }
+}
+
+/// Tracked clients, along with the last removal of the stale entries.
+#[derive(Debug)]
+struct Clients {
+    records: HashMap<IpAddr, ClientRecord>,
+    last_cleanup: Instant,
+}
+
+impl Clients {
+    fn new() -> Self {
+        Clients {
+            records: HashMap::new(),
+            last_cleanup: Instant::now(),
+        }
+    }
+}
+
+/// Key of a client: its IPv4 address, or the /64 prefix of its IPv6 address.
+fn client_key(ip: IpAddr) -> IpAddr {
+    match ip.to_canonical() {
+        IpAddr::V6(ip) => IpAddr::V6(Ipv6Addr::from(u128::from(ip) & (u128::MAX << 64))),
+        ip => ip,
+    }
+}
+
+/// Request counter per source IP.
+///
+/// # Description
+///
+/// Clients are allowed to issue `max_requests` requests within a window of `window` time. The first time a client
+/// exceeds the limit, it is banned for `ban` time. Further offences double the ban time, up to `max_ban`. Offences are
+/// forgotten when a client behaves for `max_ban` time.
+///
+/// The clients that are no longer relevant are removed from time to time, at most once every minute.
+///
+/// A disabled throttle allows all the requests.
+#[derive(Debug)]
+pub struct IpThrottle {
+    enabled: bool,
+    max_requests: u32,
+    window: Duration,
+    ban: Duration,
+    max_ban: Duration,
+    clients: Mutex<Clients>,
+}
+
+impl IpThrottle {
+    pub fn new(max_requests: u32, window: Duration, ban: Duration, max_ban: Duration) -> Self {
+        IpThrottle {
+            enabled: true,
+            max_requests,
+            window,
+            ban,
+            max_ban: max_ban.max(ban),
+            clients: Mutex::new(Clients::new()),
+        }
+    }
+
+    /// Build a throttle that allows all the requests.
+    pub fn disabled() -> Self {
+        IpThrottle {
+            enabled: false,
+            max_requests: 0,
+            window: Duration::ZERO,
+            ban: Duration::ZERO,
+            max_ban: Duration::ZERO,
+            clients: Mutex::new(Clients::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Amount of clients that are currently banned.
+    pub fn banned_clients(&self) -> usize {
+        let now = Instant::now();
+        self.clients
+            .lock()
+            .unwrap()
+            .records
+            .values()
+            .filter(|r| r.banned_until.is_some_and(|t| t > now))
+            .count()
+    }
+
+    /// Register a request from `ip` and decide whether it can be served.
+    pub fn check(&self, ip: IpAddr) -> ThrottleDecision {
+        self.check_at(ip, Instant::now())
+    }
+
+    fn check_at(&self, ip: IpAddr, now: Instant) -> ThrottleDecision {
+        if !self.enabled {
+            return ThrottleDecision::Allowed;
+        }
+
+        let mut clients = self.clients.lock().unwrap();
+
+        if now.duration_since(clients.last_cleanup) >= CLEANUP_INTERVAL {
+            self.remove_stale(&mut clients.records, now);
+            clients.last_cleanup = now;
+        }
+
+        let record = clients
+            .records
+            .entry(client_key(ip))
+            .or_insert_with(|| ClientRecord::new(now));
+
+        if let Some(banned_until) = record.banned_until {
+            if banned_until > now {
+                return ThrottleDecision::Banned(banned_until - now);
+            }
+            record.banned_until = None;
+        }
+
+        if now.duration_since(record.window_start) >= self.window {
+            record.window_start = now;
+            record.requests = 0;
+        }
+        record.requests += 1;
+
+        if record.requests <= self.max_requests {
+            return ThrottleDecision::Allowed;
+        }
+
+        // Offences older than the maximum ban time are forgiven.
+        if record
+            .last_offence
+            .is_some_and(|t| now.duration_since(t) >= self.max_ban + self.window)
+        {
+            record.offences = 0;
+        }
+        record.offences += 1;
+        record.last_offence = Some(now);
+        record.requests = 0;
+        record.window_start = now;
+
+        let ban = self
+            .ban
+            .saturating_mul(2u32.saturating_pow(record.offences - 1))
+            .min(self.max_ban);
+        record.banned_until = Some(now + ban);
+
+        warn!(
+            client_ip = %ip,
+            offences = record.offences,
+            ban_secs = ban.as_secs(),
+            "Client banned for exceeding the request limit"
+        );
+
+        ThrottleDecision::Banned(ban)
+    }
+
+    /// Remove the clients that are not banned and whose offences are already forgiven.
+    fn remove_stale(&self, clients: &mut HashMap<IpAddr, ClientRecord>, now: Instant) {
+        let before = clients.len();
+        clients.retain(|_, r| {
+            r.banned_until.is_some_and(|t| t > now)
+                || now.duration_since(r.window_start) < self.window
+                || r.last_offence
+                    .is_some_and(|t| now.duration_since(t) < self.max_ban + self.window)
+        });
+        debug!("Removed {} stale clients", before - clients.len());
+    }
+}
+
+/// Middleware that applies an [IpThrottle] to a scope of the API.
+///
+/// # Description
+///
+/// Requests from banned clients are answered with a code **429** and a *Retry-After* header. The IP of the clients is
+/// resolved using the [crate::utils::http::TrustedProxies] of the application. Requests whose source IP can't be
+/// determined are always served.
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    throttle: Arc<IpThrottle>,
+    routes: &'static [(Method, &'static str)],
+}
+
+impl Throttle {
+    pub fn new(throttle: Arc<IpThrottle>) -> Self {
+        Throttle {
+            throttle,
+            routes: &[],
+        }
+    }
+
+    /// Throttle only the requests to the given routes, whose paths are relative to the scope wrapped by the
+    /// middleware. The rest of the requests are neither counted nor rejected.
+    pub fn only(mut self, routes: &'static [(Method, &'static str)]) -> Self {
+        self.routes = routes;
+        self
+    }
+
+    /// Check whether a request using `method` to `path` is subject to the throttle.
+    fn applies(&self, method: &Method, path: &str) -> bool {
+        self.routes.is_empty() || self.routes.iter().any(|(m, p)| m == method && *p == path)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Throttle
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = ThrottleMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ThrottleMiddleware {
+            service: Rc::new(service),
+            throttle: self.clone(),
+        }))
+    }
+}
+
+/// Service built by [Throttle].
+pub struct ThrottleMiddleware<S> {
+    service: Rc<S>,
+    throttle: Throttle,
+}
+
+impl<S, B> Service<ServiceRequest> for ThrottleMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        // The path of the scope wrapped by the middleware is already consumed.
+        if self.throttle.throttle.is_enabled()
+            && self
+                .throttle
+                .applies(req.method(), req.match_info().unprocessed())
+        {
+            if let Some(ip) = client_ip(req.request()) {
+                if let ThrottleDecision::Banned(remaining) = self.throttle.throttle.check(ip) {
+                    debug!("Request from the banned client {ip} rejected");
+                    // Round up, a Retry-After of 0 would invite the client to retry straight away.
+                    let retry_after = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+                    let response = HttpResponse::TooManyRequests()
+                        .append_header((RETRY_AFTER, retry_after.to_string()))
+                        .append_header((CACHE_CONTROL, "no-cache"))
+                        .finish()
+                        .map_into_right_body();
+
+                    return Box::pin(async move { Ok(req.into_response(response)) });
+                }
+            }
+        }
+
+        let service = self.service.clone();
+        Box::pin(async move { Ok(service.call(req).await?.map_into_left_body()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::http::{ForwardingHeader, TrustedProxies};
+    use actix_web::{
+        test::{call_service, init_service, TestRequest},
+        web::{self, Data},
+        App,
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::{fixture, rstest};
+
+    const IP: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+
+    #[fixture]
+    fn throttle() -> IpThrottle {
+        IpThrottle::new(
+            2,
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+            Duration::from_secs(300),
+        )
+    }
+
+    #[rstest]
+    fn requests_within_the_limit_are_allowed(throttle: IpThrottle) {
+        let now = Instant::now();
+        assert_eq!(throttle.check_at(IP, now), ThrottleDecision::Allowed);
+        assert_eq!(throttle.check_at(IP, now), ThrottleDecision::Allowed);
+        // A new window resets the count.
+        let later = now + Duration::from_secs(61);
+        assert_eq!(throttle.check_at(IP, later), ThrottleDecision::Allowed);
+        assert_eq!(throttle.check_at(IP, later), ThrottleDecision::Allowed);
+    }
+
+    #[rstest]
+    fn bans_escalate(throttle: IpThrottle) {
+        let mut now = Instant::now();
+        let mut bans = Vec::new();
+
+        for _ in 0..4 {
+            throttle.check_at(IP, now);
+            throttle.check_at(IP, now);
+            match throttle.check_at(IP, now) {
+                ThrottleDecision::Banned(ban) => bans.push(ban.as_secs()),
+                ThrottleDecision::Allowed => panic!("The client shall be banned"),
+            }
+            // Requests during the ban are rejected.
+            assert!(matches!(
+                throttle.check_at(IP, now + Duration::from_secs(1)),
+                ThrottleDecision::Banned(_)
+            ));
+            now += Duration::from_secs(bans.last().copied().unwrap());
+        }
+
+        assert_eq!(bans, vec![60, 120, 240, 300]);
+        // Other clients are not affected.
+        assert_eq!(
+            throttle.check_at("10.0.0.2".parse().unwrap(), now),
+            ThrottleDecision::Allowed
+        );
+    }
+
+    #[rstest]
+    fn offences_are_forgiven(throttle: IpThrottle) {
+        let now = Instant::now();
+        for _ in 0..3 {
+            throttle.check_at(IP, now);
+        }
+        let later = now + Duration::from_secs(3600);
+        throttle.check_at(IP, later);
+        throttle.check_at(IP, later);
+        assert_eq!(
+            throttle.check_at(IP, later),
+            ThrottleDecision::Banned(Duration::from_secs(60))
+        );
+    }
+
+    #[rstest]
+    fn ipv6_clients_are_tracked_by_prefix(throttle: IpThrottle) {
+        let now = Instant::now();
+        throttle.check_at("2001:db8:1:2::1".parse().unwrap(), now);
+        throttle.check_at("2001:db8:1:2::2".parse().unwrap(), now);
+        assert!(matches!(
+            throttle.check_at("2001:db8:1:2:ffff::3".parse().unwrap(), now),
+            ThrottleDecision::Banned(_)
+        ));
+        assert_eq!(
+            throttle.check_at("2001:db8:1:3::1".parse().unwrap(), now),
+            ThrottleDecision::Allowed
+        );
+    }
+
+    #[rstest]
+    fn stale_clients_are_removed(throttle: IpThrottle) {
+        let now = Instant::now();
+        throttle.check_at(IP, now);
+        throttle.check_at("10.0.0.2".parse().unwrap(), now);
+        assert_eq!(throttle.clients.lock().unwrap().records.len(), 2);
+
+        throttle.check_at(IP, now + Duration::from_secs(3600));
+        assert_eq!(throttle.clients.lock().unwrap().records.len(), 1);
+    }
+
+    #[rstest]
+    fn disabled_throttle_allows_everything() {
+        let throttle = IpThrottle::disabled();
+        for _ in 0..100 {
+            assert_eq!(throttle.check(IP), ThrottleDecision::Allowed);
+        }
+    }
+
+    #[actix_web::test]
+    async fn banned_clients_get_429() {
+        let throttle = Arc::new(IpThrottle::new(
+            1,
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        ));
+        let app = init_service(
+            App::new()
+                .wrap(Throttle::new(throttle.clone()))
+                .app_data(Data::new(
+                    TrustedProxies::new(&["127.0.0.1".to_owned()], ForwardingHeader::default())
+                        .unwrap(),
+                ))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let request = || {
+            TestRequest::get()
+                .uri("/")
+                .peer_addr("127.0.0.1:4000".parse().unwrap())
+                .insert_header(("X-Forwarded-For", "10.0.0.1"))
+                .to_request()
+        };
+
+        assert_eq!(call_service(&app, request()).await.status(), 200);
+        let response = call_service(&app, request()).await;
+        assert_eq!(response.status(), 429);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "60");
+        assert_eq!(throttle.banned_clients(), 1);
+
+        // The peer's IP is not the banned one.
+        let response = call_service(
+            &app,
+            TestRequest::get()
+                .uri("/")
+                .peer_addr("127.0.0.1:4000".parse().unwrap())
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn only_the_given_routes_are_throttled() {
+        let throttle = Arc::new(IpThrottle::new(
+            1,
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        ));
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(TrustedProxies::default()))
+                .service(
+                    web::scope("/recipe")
+                        .wrap(Throttle::new(throttle).only(&[(Method::GET, "")]))
+                        .route("", web::get().to(HttpResponse::Ok))
+                        .route("/{id}", web::get().to(HttpResponse::Ok)),
+                ),
+        )
+        .await;
+
+        let request = |uri| {
+            TestRequest::get()
+                .uri(uri)
+                .peer_addr("10.0.0.1:4000".parse().unwrap())
+                .to_request()
+        };
+
+        assert_eq!(call_service(&app, request("/recipe")).await.status(), 200);
+        assert_eq!(call_service(&app, request("/recipe")).await.status(), 429);
+        assert_eq!(call_service(&app, request("/recipe/1")).await.status(), 200);
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Structured data (JSON-LD) of the recipes.
+//!
+//! # Description
+//!
+//! Search engines understand the [schema.org Recipe](https://schema.org/Recipe) vocabulary to offer rich results.
+//! This module maps a [Recipe] into such vocabulary, so frontends can embed it straight away in their pages rather
+//! than re-mapping the fields of the recipe.
+
+use crate::{
+    domain::{Language, Recipe, RecipeSource, StarRate},
+    utils::pdf::{format_quantity, SheetIngredient},
+};
+use serde_json::{json, Map, Value};
+
+/// Media type of the JSON-LD documents.
+pub const JSONLD_CONTENT_TYPE: &str = "application/ld+json";
+
+/// Build the schema.org `Recipe` structured data of a recipe.
+///
+/// # Description
+///
+/// `ingredients` shall include the names of the ingredients of the recipe, as [Recipe] only references them by ID.
+/// `author` is the public name of the author of the recipe. Authors that don't share their profile shall be given as
+/// `None`, which removes the author from the output. The units of the ingredients are written in the given `language`.
+///
+/// `votes` is the amount of clients that rated the recipe. Recipes without votes don't include the `aggregateRating`
+/// property, as search engines reject ratings that don't tell how many reviews they aggregate.
+pub fn recipe_to_jsonld(
+    recipe: &Recipe,
+    ingredients: &[SheetIngredient],
+    author: Option<&str>,
+    language: Language,
+    votes: u32,
+) -> Value {
+    let mut document = Map::new();
+
+    document.insert("@context".into(), json!("https://schema.org"));
+    document.insert("@type".into(), json!("Recipe"));
+    if let Some(id) = recipe.id() {
+        document.insert("identifier".into(), json!(id.to_string()));
+    }
+    document.insert("name".into(), json!(recipe.name()));
+    document.insert("inLanguage".into(), json!(language.to_string()));
+    if let Some(description) = recipe.description() {
+        document.insert("description".into(), json!(description));
+    }
+    document.insert("recipeCategory".into(), json!("Cocktail"));
+    document.insert("recipeYield".into(), json!("1 serving"));
+
+    let ingredients = ingredients
+        .iter()
+        .map(|i| {
+            format!(
+                "{} {} {}",
+                format_quantity(i.quantity),
+                language.unit_label(i.unit),
+                i.name
+            )
+        })
+        .collect::<Vec<String>>();
+    document.insert("recipeIngredient".into(), json!(ingredients));
+
+    let steps = recipe
+        .steps()
+        .iter()
+        .enumerate()
+        .map(|(i, step)| json!({"@type": "HowToStep", "position": i + 1, "text": step}))
+        .collect::<Vec<Value>>();
+    document.insert("recipeInstructions".into(), Value::Array(steps));
+
+    if let Some(equipment) = recipe.equipment().filter(|e| !e.is_empty()) {
+        let tools = equipment
+            .iter()
+            .map(|e| json!({"@type": "HowToTool", "name": e.to_string().replace('_', " ")}))
+            .collect::<Vec<Value>>();
+        document.insert("tool".into(), Value::Array(tools));
+    }
+
+    if let Some(minutes) = recipe.prep_time_minutes() {
+        document.insert("totalTime".into(), json!(format!("PT{minutes}M")));
+    }
+
+    let keywords = recipe
+        .author_tags()
+        .unwrap_or_default()
+        .iter()
+        .chain(recipe.tags().unwrap_or_default())
+        .map(|t| t.identifier.as_str())
+        .collect::<Vec<&str>>();
+    if !keywords.is_empty() {
+        document.insert("keywords".into(), json!(keywords.join(", ")));
+    }
+
+    if let Some(author) = author {
+        document.insert("author".into(), json!({"@type": "Person", "name": author}));
+    }
+
+    if votes > 0 {
+        document.insert(
+            "aggregateRating".into(),
+            json!({
+                "@type": "AggregateRating",
+                "ratingValue": recipe.rating().value(),
+                "ratingCount": votes,
+                "bestRating": StarRate::MAX,
+                "worstRating": 0,
+            }),
+        );
+    }
+
+    let license = recipe.license();
+    document.insert(
+        "license".into(),
+        json!(license.url().unwrap_or(license.label())),
+    );
+
+    // The source credits the original publication, so it takes precedence over the linked URL.
+    if let Some(source) = recipe.source() {
+        document.insert("isBasedOn".into(), source_to_jsonld(source));
+    } else if let Some(url) = recipe.url() {
+        document.insert("isBasedOn".into(), json!(url));
+    }
+
+    Value::Object(document)
+}
+
+/// Map the attribution of a recipe into a schema.org `Book`, or a `CreativeWork` when no book is given.
+fn source_to_jsonld(source: &RecipeSource) -> Value {
+    let mut work = Map::new();
+
+    match &source.book {
+        Some(book) => {
+            work.insert("@type".into(), json!("Book"));
+            work.insert("name".into(), json!(book));
+        }
+        None => {
+            work.insert("@type".into(), json!("CreativeWork"));
+        }
+    }
+    if let Some(page) = source.page {
+        work.insert("pagination".into(), json!(page.to_string()));
+    }
+    if let Some(url) = &source.url {
+        work.insert("url".into(), json!(url));
+    }
+    if let Some(author) = &source.author {
+        work.insert("author".into(), json!({"@type": "Person", "name": author}));
+    }
+
+    Value::Object(work)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Equipment, QuantityUnit, RecipeLicense, Tag};
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use uuid::Uuid;
+
+    #[fixture]
+    fn recipe() -> Recipe {
+        Recipe::new(
+            Some(Uuid::now_v7()),
+            "Daiquiri",
+            None,
+            Some(&[Tag::new("sour").unwrap()]),
+            Some(&[Tag::new("rum").unwrap()]),
+            "easy",
+            Some("A classic sour."),
+            None,
+            &[],
+            &[
+                "Shake all the ingredients with ice.",
+                "Strain into a coupe.",
+            ],
+            Some(&[Equipment::Shaker, Equipment::FineStrainer]),
+            Some(3),
+            None,
+        )
+        .expect("Failed to build a recipe")
+    }
+
+    #[rstest]
+    fn recipes_are_mapped_to_schema_org(recipe: Recipe) {
+        let ingredients = [SheetIngredient {
+            name: "White Rum".into(),
+            quantity: 60.0,
+            unit: QuantityUnit::MilliLiter,
+        }];
+        let document = recipe_to_jsonld(
+            &recipe.with_rating(StarRate::new(4.5).unwrap()),
+            &ingredients,
+            Some("Jane Doe"),
+            Language::En,
+            12,
+        );
+
+        assert_eq!(document["@type"], "Recipe");
+        assert_eq!(document["name"], "Daiquiri");
+        assert_eq!(document["recipeIngredient"], json!(["60 ml White Rum"]));
+        assert_eq!(document["recipeInstructions"][1]["position"], 2);
+        assert_eq!(
+            document["recipeInstructions"][1]["text"],
+            "Strain into a coupe."
+        );
+        assert_eq!(document["tool"][1]["name"], "fine strainer");
+        assert_eq!(document["totalTime"], "PT3M");
+        assert_eq!(document["keywords"], "sour, rum");
+        assert_eq!(document["author"]["name"], "Jane Doe");
+        assert_eq!(document["aggregateRating"]["ratingValue"], 4.5);
+        assert_eq!(document["aggregateRating"]["ratingCount"], 12);
+        assert_eq!(document["license"], "All rights reserved");
+    }
+
+    #[rstest]
+    fn units_are_localized(recipe: Recipe) {
+        let ingredients = [SheetIngredient {
+            name: "Azúcar".into(),
+            quantity: 1.0,
+            unit: QuantityUnit::TeaSpoon,
+        }];
+        let document = recipe_to_jsonld(&recipe, &ingredients, None, Language::Es, 0);
+
+        assert_eq!(document["inLanguage"], "es");
+        assert_eq!(document["recipeIngredient"], json!(["1 cdta Azúcar"]));
+    }
+
+    #[rstest]
+    fn optional_properties_are_skipped(recipe: Recipe) {
+        let document = recipe_to_jsonld(&recipe, &[], None, Language::En, 0);
+
+        assert!(document.get("author").is_none());
+        assert!(document.get("aggregateRating").is_none());
+
+        // Ratings without votes are skipped as well.
+        let recipe = recipe.with_rating(StarRate::new(4.0).unwrap());
+        let document = recipe_to_jsonld(&recipe, &[], None, Language::En, 0);
+        assert!(document.get("aggregateRating").is_none());
+        assert!(document.get("isBasedOn").is_none());
+    }
+
+    #[rstest]
+    fn sources_are_credited(recipe: Recipe) {
+        let recipe = recipe
+            .with_license(Some(RecipeLicense::CcBySa))
+            .with_source(Some(RecipeSource {
+                book: Some("The Savoy Cocktail Book".into()),
+                page: Some(42),
+                url: None,
+                author: Some("Harry Craddock".into()),
+            }));
+        let document = recipe_to_jsonld(&recipe, &[], None, Language::En, 0);
+
+        assert_eq!(
+            document["license"],
+            "https://creativecommons.org/licenses/by-sa/4.0/"
+        );
+        assert_eq!(
+            document["isBasedOn"],
+            json!({
+                "@type": "Book",
+                "name": "The Savoy Cocktail Book",
+                "pagination": "42",
+                "author": {"@type": "Person", "name": "Harry Craddock"},
+            })
+        );
+    }
+}
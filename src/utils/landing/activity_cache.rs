@@ -0,0 +1,84 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Cache for the recent activity of the site.
+//!
+//! # Description
+//!
+//! The recent activity is requested by every home page of the frontend, and it combines several queries over recipes
+//! and authors. Changes of the authors are not tracked (see [crate::utils::changes]), so the serialized activity is
+//! kept in memory for a fixed amount of time rather than until the content changes.
+
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+/// Amount of time that the recent activity is served from the cache.
+pub const ACTIVITY_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Cache for the serialized recent activity.
+#[derive(Debug)]
+pub struct ActivityCache {
+    ttl: Duration,
+    cached: RwLock<Option<(Instant, Arc<String>)>>,
+}
+
+impl Default for ActivityCache {
+    fn default() -> Self {
+        ActivityCache::new(ACTIVITY_CACHE_TTL)
+    }
+}
+
+impl ActivityCache {
+    pub fn new(ttl: Duration) -> Self {
+        ActivityCache {
+            ttl,
+            cached: RwLock::new(None),
+        }
+    }
+
+    /// Retrieve the cached activity. `None` is returned when it is older than the TTL of the cache.
+    pub fn get(&self) -> Option<Arc<String>> {
+        match self.cached.read() {
+            Ok(cached) => cached
+                .as_ref()
+                .filter(|(built, _)| built.elapsed() < self.ttl)
+                .map(|(_, body)| body.clone()),
+            Err(_) => None,
+        }
+    }
+
+    /// Store the serialized activity.
+    pub fn store(&self, body: String) -> Arc<String> {
+        let body = Arc::new(body);
+
+        if let Ok(mut cached) = self.cached.write() {
+            *cached = Some((Instant::now(), body.clone()));
+        }
+
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn activity_expires() {
+        let cache = ActivityCache::default();
+        assert!(cache.get().is_none());
+        cache.store("{}".into());
+        assert_eq!(cache.get().unwrap().as_str(), "{}");
+
+        let cache = ActivityCache::new(Duration::ZERO);
+        cache.store("{}".into());
+        assert!(cache.get().is_none());
+    }
+}
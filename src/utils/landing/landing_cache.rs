@@ -0,0 +1,134 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Cache for the collections of recipes served to the landing pages of the frontend.
+//!
+//! # Description
+//!
+//! Landing pages (one per category, one per tag) are requested far more often than the recipes change, so the
+//! serialized collections are kept in memory along with the time of the latest change of the recipes (see
+//! [crate::utils::changes]). Any change of the recipes invalidates all the cached collections. As the reported time of
+//! a change is settled once its second elapses, a collection built within that second is served for one second at
+//! most.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use tracing::debug;
+
+/// Maximum amount of collections held by the cache. Tags are chosen by the clients, so the cache must be bounded.
+pub const LANDING_CACHE_CAPACITY: usize = 512;
+
+/// Amount of recipes included in a landing collection.
+pub const LANDING_COLLECTION_SIZE: usize = 12;
+
+/// Collections that can be served to the landing pages.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LandingCollection {
+    /// Top recipes of a category.
+    Category(String),
+    /// Featured recipes of a tag.
+    Tag(String),
+}
+
+#[derive(Debug, Default)]
+struct CachedCollections {
+    /// Time of the latest change of the recipes when the collections were built.
+    version: Option<i64>,
+    entries: HashMap<LandingCollection, Arc<String>>,
+}
+
+/// Cache for the serialized landing collections.
+#[derive(Debug, Default)]
+pub struct LandingCache {
+    cached: RwLock<CachedCollections>,
+}
+
+impl LandingCache {
+    pub fn new() -> Self {
+        LandingCache::default()
+    }
+
+    /// Retrieve a cached collection. `None` is returned when it was built from a different version of the recipes.
+    pub fn get(&self, collection: &LandingCollection, version: Option<i64>) -> Option<Arc<String>> {
+        match self.cached.read() {
+            Ok(cached) if cached.version == version => cached.entries.get(collection).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Store a serialized collection built from the given version of the recipes.
+    pub fn store(
+        &self,
+        collection: LandingCollection,
+        version: Option<i64>,
+        body: String,
+    ) -> Arc<String> {
+        let body = Arc::new(body);
+
+        if let Ok(mut cached) = self.cached.write() {
+            if cached.version != version {
+                debug!("The recipes changed, dropping the landing collections");
+                cached.entries.clear();
+                cached.version = version;
+            }
+            if cached.entries.len() >= LANDING_CACHE_CAPACITY {
+                debug!("The landing cache is full, dropping all the entries");
+                cached.entries.clear();
+            }
+            cached.entries.insert(collection, body.clone());
+        }
+
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn collections_are_invalidated_by_changes() {
+        let cache = LandingCache::new();
+        let collection = LandingCollection::Tag("rum".into());
+        assert!(cache.get(&collection, Some(1)).is_none());
+
+        cache.store(collection.clone(), Some(1), "[]".into());
+        assert_eq!(cache.get(&collection, Some(1)).unwrap().as_str(), "[]");
+        assert!(cache.get(&collection, Some(2)).is_none());
+        assert!(cache
+            .get(&LandingCollection::Category("rum".into()), Some(1))
+            .is_none());
+
+        cache.store(
+            LandingCollection::Category("easy".into()),
+            Some(2),
+            "[]".into(),
+        );
+        assert!(cache.get(&collection, Some(1)).is_none());
+    }
+
+    #[rstest]
+    fn cache_is_bounded() {
+        let cache = LandingCache::new();
+        for i in 0..=LANDING_CACHE_CAPACITY {
+            cache.store(LandingCollection::Tag(i.to_string()), None, "[]".into());
+        }
+
+        assert!(cache
+            .get(&LandingCollection::Tag("0".into()), None)
+            .is_none());
+        assert!(cache
+            .get(
+                &LandingCollection::Tag(LANDING_CACHE_CAPACITY.to_string()),
+                None
+            )
+            .is_some());
+    }
+}
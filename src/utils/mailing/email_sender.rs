@@ -0,0 +1,171 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Abstraction of the provider used to send emails.
+//!
+//! # Description
+//!
+//! Handlers don't talk to Mailjet straight away. Instead, they get the [EmailSender] of the application, which is
+//! injected as app data (`web::Data<dyn EmailSender>`). The application uses [MailjetSender], and the integration tests
+//! use a sender that captures the emails, so they can check what was sent (see `lacoctelera::testing::email`).
+
+use crate::{
+    domain::ServerError,
+    utils::mailing::{provider_message_id, MailCorrelation, SendResponse},
+};
+use mailjet_client::data_objects;
+use secrecy::{ExposeSecret, SecretString};
+use std::{fmt::Debug, future::Future, pin::Pin};
+use tracing::{debug, error, info};
+
+/// Base URL of the API of Mailjet.
+pub const MAILJET_API_URL: &str = "https://api.mailjet.com";
+
+/// Future returned by [EmailSender::send].
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<Option<String>, ServerError>> + 'a>>;
+
+/// Email composed by the backend.
+#[derive(Debug, Clone)]
+pub struct Email {
+    /// Address of the recipient.
+    pub to: String,
+    /// Name of the recipient, if known.
+    pub to_name: Option<String>,
+    pub subject: String,
+    /// Body of the email (plain text).
+    pub text_body: String,
+    /// Identifiers that link the email with the request that triggered it.
+    pub correlation: MailCorrelation,
+}
+
+/// Provider used to send the emails of the backend.
+pub trait EmailSender: Send + Sync + Debug {
+    /// Address used by the backend to send emails, which is also the address of the sysadmin.
+    fn sender_address(&self) -> Option<&str>;
+
+    /// Name used by the backend to send emails.
+    fn sender_name(&self) -> Option<&str>;
+
+    /// Send an email. The ID given by the provider to the message is returned, if any.
+    fn send<'a>(&'a self, email: &'a Email) -> SendFuture<'a>;
+}
+
+/// Sender that uses the `/send` endpoint (v3.1) of Mailjet.
+///
+/// # Description
+///
+/// Messages are built using the data objects of [mailjet_client], but they are posted by the sender itself, as the
+/// client of the crate hides the objects of the response behind trait objects. The response is deserialized as a
+/// [SendResponse], so the ID that Mailjet assigns to the message is read from it (see [provider_message_id]).
+#[derive(Debug)]
+pub struct MailjetSender {
+    client: reqwest::Client,
+    api_url: String,
+    api_user: SecretString,
+    api_key: SecretString,
+    email_address: String,
+    email_name: String,
+    sandbox_mode: bool,
+}
+
+impl MailjetSender {
+    pub fn new(
+        api_user: SecretString,
+        api_key: SecretString,
+        email_address: &str,
+        user_agent: &str,
+    ) -> Result<Self, reqwest::Error> {
+        Ok(MailjetSender {
+            client: reqwest::Client::builder()
+                .user_agent(user_agent)
+                .https_only(true)
+                .build()?,
+            api_url: MAILJET_API_URL.to_owned(),
+            api_user,
+            api_key,
+            email_address: email_address.to_owned(),
+            email_name: "La Coctelera".to_owned(),
+            sandbox_mode: false,
+        })
+    }
+
+    /// Validate the messages without sending them (see the *sandbox mode* of Mailjet).
+    pub fn with_sandbox_mode(mut self, sandbox_mode: bool) -> Self {
+        self.sandbox_mode = sandbox_mode;
+        self
+    }
+
+    async fn post(&self, params: &data_objects::SendEmailParams) -> Result<SendResponse, String> {
+        let response = self
+            .client
+            .post(format!("{}/v3.1/send", self.api_url))
+            .basic_auth(
+                self.api_user.expose_secret(),
+                Some(self.api_key.expose_secret()),
+            )
+            .json(params)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let payload = response.text().await.unwrap_or_default();
+            return Err(format!("status code: {status}, payload: {payload}"));
+        }
+
+        response
+            .json::<SendResponse>()
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl EmailSender for MailjetSender {
+    fn sender_address(&self) -> Option<&str> {
+        Some(&self.email_address)
+    }
+
+    fn sender_name(&self) -> Option<&str> {
+        Some(&self.email_name)
+    }
+
+    fn send<'a>(&'a self, email: &'a Email) -> SendFuture<'a> {
+        Box::pin(async move {
+            let mut mail = data_objects::MessageBuilder::default()
+                .with_from(&self.email_address, Some(&self.email_name))
+                .with_to(&email.to, email.to_name.as_deref())
+                .with_subject(&email.subject)
+                .with_text_body(&email.text_body)
+                .build();
+            email.correlation.apply(&mut mail);
+
+            let mail_req = data_objects::SendEmailParams {
+                sandbox_mode: Some(self.sandbox_mode),
+                advance_error_handling: Some(false),
+                globals: None,
+                messages: Vec::from([mail]),
+            };
+
+            match self.post(&mail_req).await {
+                Ok(response) => {
+                    let message_id = provider_message_id(&response);
+                    info!(
+                        "Email sent to {} (message ID: {})",
+                        email.to,
+                        message_id.as_deref().unwrap_or("unknown")
+                    );
+                    debug!("{:?}", response);
+                    Ok(message_id)
+                }
+                Err(e) => {
+                    error!("Failed to send email to {} ({e})", email.to);
+                    Err(ServerError::EmailClientError)
+                }
+            }
+        })
+    }
+}
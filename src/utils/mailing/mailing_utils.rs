@@ -4,98 +4,387 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-//! Functions related to sending emails using [MailjetClient].
+//! Functions related to sending emails using an [EmailSender], usually [crate::utils::mailing::MailjetSender].
+//!
+//! # Description
+//!
+//! Every email is tagged with the ID of the request that triggered it and the ID of the client of the API that it
+//! concerns (see [MailCorrelation]). Both are sent to Mailjet as custom headers and variables, and every attempt to
+//! send an email is stored in the DB along with them and the ID that Mailjet assigns to the message (see
+//! [register_email_attempt]). Thus, a report such as *"I never got my confirmation email"* can be traced from the logs
+//! of the backend to the logs of the provider.
 
-use crate::domain::{ClientId, ServerError};
+use crate::{
+    domain::{ClientId, ServerError},
+    utils::mailing::{Email, EmailSender},
+};
 use actix_web::web::Data;
-use mailjet_client::{data_objects, MailjetClient};
-use tracing::{debug, error, info};
+use chrono::{DateTime, Utc};
+use mailjet_client::data_objects::{self, SendResponseObject};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::MySqlPool;
+use std::{collections::HashMap, fmt};
+use tracing::error;
+use utoipa::ToSchema;
+use uuid::Uuid;
 
+/// Header of the emails that includes the ID of the request that triggered them.
+pub const X_REQUEST_ID: &str = "X-Request-Id";
+/// Header of the emails that includes the ID of the client of the API.
+pub const X_CLIENT_ID: &str = "X-Client-Id";
+
+/// Identifiers that link an email with the request that triggered it.
+#[derive(Debug, Clone)]
+pub struct MailCorrelation {
+    /// ID of the HTTP request, as given by [tracing_actix_web::RequestId].
+    pub request_id: Uuid,
+    /// ID of the client of the API.
+    pub client_id: ClientId,
+}
+
+impl MailCorrelation {
+    pub fn new(request_id: Uuid, client_id: &ClientId) -> Self {
+        MailCorrelation {
+            request_id,
+            client_id: client_id.clone(),
+        }
+    }
+
+    /// Tag a message with the identifiers.
+    ///
+    /// # Description
+    ///
+    /// The request ID is used as `CustomID` of the message, which Mailjet includes in its event notifications. Both
+    /// IDs are also added as custom headers and variables of the message.
+    pub fn apply(&self, message: &mut data_objects::Message) {
+        let request_id = self.request_id.to_string();
+        let client_id = self.client_id.to_string();
+
+        message.custom_id = Some(request_id.clone());
+        message.headers = Some(HashMap::from([
+            (X_REQUEST_ID.to_owned(), request_id.clone()),
+            (X_CLIENT_ID.to_owned(), client_id.clone()),
+        ]));
+        message.variables = Some(HashMap::from([
+            ("request_id".to_owned(), request_id),
+            ("client_id".to_owned(), client_id),
+        ]));
+    }
+}
+
+/// Response of the `/send` endpoint (v3.1) of Mailjet.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SendResponse {
+    /// Outcome of every message of the request.
+    pub messages: Vec<SendResponseObject>,
+}
+
+/// Extract the ID that Mailjet assigned to a sent message, which is given per recipient.
+pub fn provider_message_id(response: &SendResponse) -> Option<String> {
+    response
+        .messages
+        .iter()
+        .flat_map(|message| message.to.iter().flatten())
+        .find_map(|recipient| recipient.message_id)
+        .map(|id| id.to_string())
+}
+
+/// Types of the emails sent by the backend.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailKind {
+    /// Email to validate a token request.
+    Confirmation,
+    /// Notification to the sysadmin about a validated token request.
+    AdminNotification,
+    /// Notification to the owner of a locked client.
+    LockoutNotification,
+    /// Digest of the new recipes for a subscribed client.
+    Digest,
+    /// Copy of a notification of a client (see [crate::routes::me::notifications]).
+    Notification,
+    /// Notification to an author whose claim of a recipe was granted (see [crate::routes::recipe::claim]).
+    ClaimNotification,
+}
+
+impl fmt::Display for EmailKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmailKind::Confirmation => write!(f, "confirmation"),
+            EmailKind::AdminNotification => write!(f, "admin_notification"),
+            EmailKind::LockoutNotification => write!(f, "lockout_notification"),
+            EmailKind::Digest => write!(f, "digest"),
+            EmailKind::Notification => write!(f, "notification"),
+            EmailKind::ClaimNotification => write!(f, "claim_notification"),
+        }
+    }
+}
+
+/// Outcome of an attempt to send an email.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailStatus {
+    /// The email was accepted by the provider.
+    Sent,
+    /// The provider rejected the email, or it was unreachable.
+    Failed,
+}
+
+impl fmt::Display for EmailStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmailStatus::Sent => write!(f, "sent"),
+            EmailStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// Hash an email address, so it can be stored without disclosing it.
+///
+/// # Description
+///
+/// Addresses are trimmed and converted to lower case before hashing, so the hash of an address given by an
+/// administrator matches the hash of the stored one. The hash is the hex representation of the SHA-256 digest.
+pub fn recipient_hash(email: &str) -> String {
+    format!(
+        "{:x}",
+        Sha256::digest(email.trim().to_lowercase().as_bytes())
+    )
+}
+
+/// Store an attempt to send an email in the DB.
+///
+/// # Description
+///
+/// `outcome` is the result of the functions that send emails, i.e. [send_confirmation_email].
+#[tracing::instrument(skip(pool, recipient, outcome))]
+pub async fn register_email_attempt(
+    pool: &MySqlPool,
+    correlation: &MailCorrelation,
+    kind: EmailKind,
+    recipient: &str,
+    outcome: &Result<Option<String>, ServerError>,
+) -> Result<(), ServerError> {
+    let (status, provider_message_id, error) = match outcome {
+        Ok(id) => (EmailStatus::Sent, id.as_deref(), None),
+        Err(e) => (EmailStatus::Failed, None, Some(e.to_string())),
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO EmailMessage
+            (kind, request_id, client_id, recipient_hash, status, provider_message_id, error)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(kind.to_string())
+    .bind(correlation.request_id.to_string())
+    .bind(correlation.client_id.to_string())
+    .bind(recipient_hash(recipient))
+    .bind(status.to_string())
+    .bind(provider_message_id)
+    .bind(error)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("{e}");
+        ServerError::DbError
+    })?;
+
+    Ok(())
+}
+
+/// Send the email to validate a token request. The ID given by the provider to the message is returned.
 #[tracing::instrument(skip(mail_client, confirmation_link))]
 pub async fn send_confirmation_email(
-    mail_client: Data<MailjetClient>,
+    mail_client: Data<dyn EmailSender>,
     confirmation_link: &str,
     recipient: &str,
-) -> Result<(), ServerError> {
-    // Build a new message.
-    let mail = data_objects::MessageBuilder::default()
-        .with_from(
-            mail_client
-                .email_address
-                .as_deref()
-                .expect("Missing email address of the backend service"),
-            mail_client.email_name.as_deref(),
-        )
-        .with_to(recipient, None)
-        .with_text_body(&format!(
+    correlation: &MailCorrelation,
+) -> Result<Option<String>, ServerError> {
+    let email = Email {
+        to: recipient.to_owned(),
+        to_name: None,
+        subject: "Verify your email".to_owned(),
+        text_body: format!(
             include_str!("./templates/confirmation_email.txt"),
             confirmation_link
-        ))
-        .with_subject("Verify your email")
-        .build();
-
-    let mail_req = data_objects::SendEmailParams {
-        sandbox_mode: Some(false),
-        advance_error_handling: Some(false),
-        globals: None,
-        messages: Vec::from([mail]),
+        ),
+        correlation: correlation.clone(),
     };
 
-    match mail_client.send_email(&mail_req).await {
-        Ok(info) => {
-            info!("Email sent to {recipient}");
-            debug!("{:?}", info);
-            Ok(())
-        }
-        Err(e) => {
-            error!("Failed to send email to {recipient} ({e})");
-            Err(ServerError::EmailClientError)
-        }
-    }
+    mail_client.send(&email).await
+}
+
+/// Notify the owner of a client that it got locked. The ID given by the provider to the message is returned.
+#[tracing::instrument(skip(mail_client))]
+pub async fn send_lockout_email(
+    mail_client: Data<dyn EmailSender>,
+    recipient: &str,
+    locked_until: DateTime<Utc>,
+    correlation: &MailCorrelation,
+) -> Result<Option<String>, ServerError> {
+    let email = Email {
+        to: recipient.to_owned(),
+        to_name: None,
+        subject: "Your API client is locked".to_owned(),
+        text_body: format!(
+            include_str!("./templates/lockout_email.txt"),
+            locked_until.format("%Y-%m-%d %H:%M:%S")
+        ),
+        correlation: correlation.clone(),
+    };
+
+    mail_client.send(&email).await
+}
+
+/// Send a digest of new recipes to a subscribed client. The ID given by the provider to the message is returned.
+///
+/// # Description
+///
+/// `recipes` is the list of the new recipes, already formatted (one line per recipe).
+#[tracing::instrument(skip(mail_client, recipes))]
+pub async fn send_digest_email(
+    mail_client: Data<dyn EmailSender>,
+    recipient: &str,
+    recipes: &str,
+    correlation: &MailCorrelation,
+) -> Result<Option<String>, ServerError> {
+    let email = Email {
+        to: recipient.to_owned(),
+        to_name: None,
+        subject: "New recipes in La Coctelera".to_owned(),
+        text_body: format!(include_str!("./templates/digest_email.txt"), recipes),
+        correlation: correlation.clone(),
+    };
+
+    mail_client.send(&email).await
+}
+
+/// Send a copy of a notification to its client. The ID given by the provider to the message is returned.
+#[tracing::instrument(skip(mail_client, message))]
+pub async fn send_notification_email(
+    mail_client: Data<dyn EmailSender>,
+    recipient: &str,
+    message: &str,
+    correlation: &MailCorrelation,
+) -> Result<Option<String>, ServerError> {
+    let email = Email {
+        to: recipient.to_owned(),
+        to_name: None,
+        subject: "News from La Coctelera".to_owned(),
+        text_body: format!(include_str!("./templates/notification_email.txt"), message),
+        correlation: correlation.clone(),
+    };
+
+    mail_client.send(&email).await
+}
+
+/// Notify an author that a claimed recipe is owned by the author now. The ID given by the provider to the message is
+/// returned.
+#[tracing::instrument(skip(mail_client))]
+pub async fn send_claim_email(
+    mail_client: Data<dyn EmailSender>,
+    recipient: &str,
+    recipe_name: &str,
+    correlation: &MailCorrelation,
+) -> Result<Option<String>, ServerError> {
+    let email = Email {
+        to: recipient.to_owned(),
+        to_name: None,
+        subject: "Your claim of a recipe was granted".to_owned(),
+        text_body: format!(include_str!("./templates/claim_email.txt"), recipe_name),
+        correlation: correlation.clone(),
+    };
+
+    mail_client.send(&email).await
 }
 
+/// Notify the sysadmin about a validated token request. The ID given by the provider to the message is returned.
 #[tracing::instrument(skip(mail_client))]
 pub async fn notify_pending_req(
-    mail_client: Data<MailjetClient>,
+    mail_client: Data<dyn EmailSender>,
     id: &ClientId,
-) -> Result<(), ServerError> {
-    let mail = data_objects::MessageBuilder::default()
-    .with_from(
-        mail_client
-            .email_address
-            .as_deref()
-            .expect("Missing email address of the backend service"),
-        mail_client.email_name.as_deref(),
-    )
-    .with_to(
-        mail_client
-            .email_address
-            .as_deref()
-            .expect("Missing email address of the backend service"),
-        mail_client.email_name.as_deref(),
-    )
-    .with_subject("New client of the API validated")
-    .with_text_body(
-        &format!("A new client ({id}) has validated the account. Proceed to the evaluation of the request.")
-    )
-    .build();
-
-    let mail_req = data_objects::SendEmailParams {
-        sandbox_mode: Some(false),
-        advance_error_handling: Some(false),
-        globals: None,
-        messages: Vec::from([mail]),
+    correlation: &MailCorrelation,
+) -> Result<Option<String>, ServerError> {
+    let email = Email {
+        to: mail_client
+            .sender_address()
+            .expect("Missing email address of the backend service")
+            .to_owned(),
+        to_name: mail_client.sender_name().map(String::from),
+        subject: "New client of the API validated".to_owned(),
+        text_body: format!(
+            "A new client ({id}) has validated the account. Proceed to the evaluation of the request."
+        ),
+        correlation: correlation.clone(),
     };
 
-    match mail_client.send_email(&mail_req).await {
-        Ok(info) => {
-            info!("Email sent to the admin");
-            debug!("{:?}", info);
-            Ok(())
-        }
-        Err(e) => {
-            error!("Failed to send email to the admin ({e})");
-            Err(ServerError::EmailClientError)
-        }
+    mail_client.send(&email).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mailjet_client::data_objects::{MessageBuilder, ResponseStatus};
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn recipients_are_hashed() {
+        let hash = recipient_hash("janedoe@mail.com");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, recipient_hash("  JaneDoe@Mail.com "));
+        assert_ne!(hash, recipient_hash("johndoe@mail.com"));
+    }
+
+    #[rstest]
+    fn messages_are_tagged() {
+        let correlation = MailCorrelation::new(Uuid::now_v7(), &ClientId::new());
+        let mut message = MessageBuilder::default()
+            .with_from("backend@mail.com", None)
+            .with_to("client@mail.com", None)
+            .build();
+        correlation.apply(&mut message);
+
+        let request_id = correlation.request_id.to_string();
+        assert_eq!(message.custom_id.as_ref(), Some(&request_id));
+        let headers = message.headers.unwrap();
+        assert_eq!(headers[X_REQUEST_ID], request_id);
+        assert_eq!(headers[X_CLIENT_ID], correlation.client_id.to_string());
+        assert_eq!(message.variables.unwrap()["request_id"], request_id);
+    }
+
+    #[rstest]
+    fn message_id_is_extracted() {
+        let response: SendResponse = serde_json::from_str(
+            r#"{"Messages": [{
+                "Status": "success",
+                "To": [{
+                    "Email": "client@mail.com",
+                    "MessageUUID": "1ab23cd4-e567-8901-2345-6789f0gh1i2j",
+                    "MessageID": 288230376456622060,
+                    "MessageHref": "https://api.mailjet.com/v3/message/288230376456622060"
+                }]
+            }]}"#,
+        )
+        .expect("Failed to parse the response");
+        assert_eq!(
+            provider_message_id(&response).as_deref(),
+            Some("288230376456622060")
+        );
+
+        let response = SendResponse {
+            messages: vec![SendResponseObject {
+                status: ResponseStatus::Error,
+                errors: None,
+                to: None,
+                cc: None,
+                bcc: None,
+            }],
+        };
+        assert!(provider_message_id(&response).is_none());
     }
 }
@@ -0,0 +1,215 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Storage of the media files uploaded by the clients of the API.
+//!
+//! # Description
+//!
+//! Images are stored in a directory of the server (see [MediaStore]), and they are referenced by the resources of the
+//! DB using their `image_id`, which is the name of the file: a UUID followed by the extension of the format of the
+//! image, i.e. `0191e13b-5ab7-78f1-bc06-be503a6c111b.png`. Stored images are served by `GET /media/{image_id}`.
+//!
+//! The format of the images is detected from their content, so the `Content-Type` given by the clients is not trusted.
+//! Only PNG, JPEG and WebP images up to [MAX_IMAGE_SIZE] bytes are accepted.
+//!
+//! The files are accessed from the thread pool for blocking operations of the server, so the workers that serve the
+//! requests are never blocked by the disk.
+
+use actix_web::web::{self, Bytes};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Maximum size (bytes) of an uploaded image.
+pub const MAX_IMAGE_SIZE: usize = 2 * 1024 * 1024;
+
+/// Formats of the images accepted by the [MediaStore].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl ImageFormat {
+    /// Detect the format of an image using its signature.
+    pub fn detect(content: &[u8]) -> Option<Self> {
+        if content.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if content.starts_with(&[0xff, 0xd8, 0xff]) {
+            Some(ImageFormat::Jpeg)
+        } else if content.len() >= 12 && &content[..4] == b"RIFF" && &content[8..12] == b"WEBP" {
+            Some(ImageFormat::Webp)
+        } else {
+            None
+        }
+    }
+
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "png" => Some(ImageFormat::Png),
+            "jpg" => Some(ImageFormat::Jpeg),
+            "webp" => Some(ImageFormat::Webp),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Webp => "webp",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Webp => "image/webp",
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum MediaError {
+    #[error("The image is empty or exceeds the maximum size")]
+    InvalidSize,
+    #[error("The format of the image is not supported")]
+    UnsupportedFormat,
+    #[error("Failed to access the media storage: {0}")]
+    Storage(#[from] io::Error),
+}
+
+/// Disk storage for the media files.
+#[derive(Debug, Clone)]
+pub struct MediaStore {
+    dir: PathBuf,
+}
+
+impl MediaStore {
+    pub fn new(dir: &Path) -> Self {
+        MediaStore {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Store a new image. The `image_id` of the stored image is returned.
+    pub async fn store_image(&self, content: Bytes) -> Result<String, MediaError> {
+        if content.is_empty() || content.len() > MAX_IMAGE_SIZE {
+            return Err(MediaError::InvalidSize);
+        }
+        let format = ImageFormat::detect(&content).ok_or(MediaError::UnsupportedFormat)?;
+
+        let image_id = format!("{}.{}", Uuid::now_v7(), format.extension());
+        debug!("Storing the image {image_id}");
+        let dir = self.dir.clone();
+        let path = self.dir.join(&image_id);
+        web::block(move || {
+            fs::create_dir_all(dir)?;
+            fs::write(path, content)
+        })
+        .await
+        .map_err(io::Error::other)??;
+
+        Ok(image_id)
+    }
+
+    /// Retrieve a stored image. `None` is returned when the image doesn't exist, or the ID has an invalid format.
+    pub async fn get_image(&self, image_id: &str) -> Option<(Vec<u8>, ImageFormat)> {
+        let format = MediaStore::parse_image_id(image_id)?;
+
+        let path = self.dir.join(image_id);
+        web::block(move || fs::read(path))
+            .await
+            .ok()?
+            .ok()
+            .map(|content| (content, format))
+    }
+
+    /// Delete a stored image. Missing images are ignored.
+    pub async fn delete_image(&self, image_id: &str) {
+        if MediaStore::parse_image_id(image_id).is_none() {
+            return;
+        }
+
+        let path = self.dir.join(image_id);
+        match web::block(move || fs::remove_file(path))
+            .await
+            .map_err(io::Error::other)
+            .and_then(|result| result)
+        {
+            Ok(_) => debug!("Image {image_id} deleted"),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => warn!("Failed to delete the image {image_id}: {e}"),
+        }
+    }
+
+    /// Check the format of an `image_id`, so it can't point outside the storage.
+    fn parse_image_id(image_id: &str) -> Option<ImageFormat> {
+        let (id, extension) = image_id.split_once('.')?;
+        Uuid::parse_str(id).ok()?;
+
+        ImageFormat::from_extension(extension)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[rstest]
+    #[case(PNG, Some(ImageFormat::Png))]
+    #[case(&[0xff, 0xd8, 0xff, 0xe0], Some(ImageFormat::Jpeg))]
+    #[case(b"RIFF\0\0\0\0WEBPVP8 ", Some(ImageFormat::Webp))]
+    #[case(b"<svg></svg>", None)]
+    #[case(b"", None)]
+    fn formats_are_detected(#[case] content: &[u8], #[case] expected: Option<ImageFormat>) {
+        assert_eq!(ImageFormat::detect(content), expected);
+    }
+
+    #[actix_web::test]
+    async fn images_are_stored() {
+        let dir = std::env::temp_dir().join(format!("media-{}", Uuid::now_v7()));
+        let store = MediaStore::new(&dir);
+
+        let image_id = store.store_image(Bytes::from_static(PNG)).await.unwrap();
+        assert!(image_id.ends_with(".png"));
+        assert_eq!(
+            store.get_image(&image_id).await,
+            Some((PNG.to_vec(), ImageFormat::Png))
+        );
+
+        store.delete_image(&image_id).await;
+        assert!(store.get_image(&image_id).await.is_none());
+
+        assert!(matches!(
+            store.store_image(Bytes::from_static(b"GIF89a")).await,
+            Err(MediaError::UnsupportedFormat)
+        ));
+        assert!(matches!(
+            store.store_image(Bytes::new()).await,
+            Err(MediaError::InvalidSize)
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[rstest]
+    #[case("../secret.png")]
+    #[case("0191e13b-5ab7-78f1-bc06-be503a6c111b.svg")]
+    #[case("0191e13b-5ab7-78f1-bc06-be503a6c111b")]
+    fn invalid_ids_are_rejected(#[case] image_id: &str) {
+        assert!(MediaStore::parse_image_id(image_id).is_none());
+    }
+}
@@ -0,0 +1,325 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Registry of the live metrics of the application.
+//!
+//! # Description
+//!
+//! The registry keeps the requests served by the API, per resource, and the latency of the queries to the DB during
+//! the last [METRICS_WINDOW]. Older samples are dropped, so the memory used by the registry is bounded. Requests are
+//! registered by [crate::utils::http::RequestMetrics], and the latency of the DB by the access checks and the health
+//! check. The throttles of the anonymous endpoints are registered when the application starts, so the amount of
+//! clients they ban is reported as well. A snapshot of the metrics is served by `GET /health` (see
+//! [MetricsRegistry::snapshot]).
+//!
+//! The registry is shared by all the workers of the application, see [metrics].
+
+use crate::utils::http::IpThrottle;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+use utoipa::ToSchema;
+
+/// Time frame of the metrics.
+pub const METRICS_WINDOW: Duration = Duration::from_secs(300);
+
+/// Maximum amount of samples of the latency of the DB kept in the registry.
+const MAX_LATENCY_SAMPLES: usize = 2048;
+
+static METRICS: Lazy<MetricsRegistry> = Lazy::new(MetricsRegistry::new);
+
+/// Get the [MetricsRegistry] of the application.
+pub fn metrics() -> &'static MetricsRegistry {
+    &METRICS
+}
+
+/// Requests served during a second.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    second: u64,
+    requests: u64,
+    errors: u64,
+}
+
+#[derive(Debug, Default)]
+struct Samples {
+    requests: HashMap<String, VecDeque<Bucket>>,
+    db_latency: VecDeque<(Instant, Duration)>,
+    /// Throttles whose banned clients are reported. Throttles dropped along with their application are skipped.
+    throttles: Vec<Weak<IpThrottle>>,
+}
+
+/// Registry of the live metrics of the application.
+#[derive(Debug)]
+pub struct MetricsRegistry {
+    started: Instant,
+    samples: Mutex<Samples>,
+}
+
+/// Requests served during the last [METRICS_WINDOW].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RequestStats {
+    pub requests: u64,
+    /// Average amount of requests per second.
+    pub request_rate: f64,
+    /// Requests answered with a server error (5xx).
+    pub errors: u64,
+    /// Ratio of the requests answered with a server error, from 0 to 1.
+    pub error_rate: f64,
+}
+
+/// Snapshot of the metrics of the application.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MetricsSnapshot {
+    /// Seconds since the application started.
+    pub uptime_secs: u64,
+    /// Requests to all the resources.
+    pub requests: RequestStats,
+    /// Requests per resource, i.e. `recipe`.
+    pub resources: BTreeMap<String, RequestStats>,
+    /// 95th percentile of the latency of the queries to the DB (milliseconds). No value when no query was sampled.
+    pub db_latency_p95_ms: Option<f64>,
+    /// Clients currently banned by the throttles of the anonymous endpoints.
+    pub banned_clients: u64,
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        MetricsRegistry {
+            started: Instant::now(),
+            samples: Mutex::new(Samples::default()),
+        }
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Register a request to a resource, and whether it was answered with a server error.
+    pub fn record_request(&self, resource: &str, error: bool) {
+        let now = self.uptime().as_secs();
+
+        if let Ok(mut samples) = self.samples.lock() {
+            let buckets = samples.requests.entry(resource.to_owned()).or_default();
+            match buckets.back_mut() {
+                Some(bucket) if bucket.second == now => {
+                    bucket.requests += 1;
+                    bucket.errors += error as u64;
+                }
+                _ => buckets.push_back(Bucket {
+                    second: now,
+                    requests: 1,
+                    errors: error as u64,
+                }),
+            }
+            prune_buckets(buckets, now);
+        }
+    }
+
+    /// Register the latency of a query to the DB.
+    pub fn record_db_latency(&self, latency: Duration) {
+        if let Ok(mut samples) = self.samples.lock() {
+            if samples.db_latency.len() >= MAX_LATENCY_SAMPLES {
+                samples.db_latency.pop_front();
+            }
+            samples.db_latency.push_back((Instant::now(), latency));
+        }
+    }
+
+    /// Register a throttle, so the clients that it bans are included in the snapshots.
+    pub fn register_throttle(&self, throttle: &Arc<IpThrottle>) {
+        if let Ok(mut samples) = self.samples.lock() {
+            samples.throttles.retain(|t| t.strong_count() > 0);
+            samples.throttles.push(Arc::downgrade(throttle));
+        }
+    }
+
+    /// Compute the metrics of the last [METRICS_WINDOW].
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let uptime = self.uptime();
+        let now = uptime.as_secs();
+        // Rates are computed over the time the application has been running when it started recently.
+        let elapsed = uptime.min(METRICS_WINDOW).as_secs_f64().max(1.0);
+        let mut snapshot = MetricsSnapshot {
+            uptime_secs: now,
+            ..Default::default()
+        };
+
+        let Ok(mut samples) = self.samples.lock() else {
+            return snapshot;
+        };
+
+        let (mut requests, mut errors) = (0, 0);
+        samples.requests.retain(|resource, buckets| {
+            prune_buckets(buckets, now);
+            let resource_requests = buckets.iter().map(|b| b.requests).sum();
+            let resource_errors = buckets.iter().map(|b| b.errors).sum();
+            requests += resource_requests;
+            errors += resource_errors;
+            if resource_requests > 0 {
+                snapshot.resources.insert(
+                    resource.clone(),
+                    request_stats(resource_requests, resource_errors, elapsed),
+                );
+            }
+
+            !buckets.is_empty()
+        });
+        snapshot.requests = request_stats(requests, errors, elapsed);
+
+        let oldest = Instant::now().checked_sub(METRICS_WINDOW);
+        samples
+            .db_latency
+            .retain(|(ts, _)| oldest.is_none_or(|oldest| *ts >= oldest));
+        let mut latencies = samples
+            .db_latency
+            .iter()
+            .map(|(_, latency)| latency.as_secs_f64() * 1000.0)
+            .collect::<Vec<f64>>();
+        snapshot.db_latency_p95_ms = percentile(&mut latencies, 0.95);
+
+        samples.throttles.retain(|t| t.strong_count() > 0);
+        snapshot.banned_clients = samples
+            .throttles
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|throttle| throttle.banned_clients() as u64)
+            .sum();
+
+        snapshot
+    }
+}
+
+impl Default for MetricsRegistry {
+    fn default() -> Self {
+        MetricsRegistry::new()
+    }
+}
+
+/// Drop the buckets that fall out of the [METRICS_WINDOW].
+fn prune_buckets(buckets: &mut VecDeque<Bucket>, now: u64) {
+    while buckets
+        .front()
+        .is_some_and(|b| b.second + METRICS_WINDOW.as_secs() <= now)
+    {
+        buckets.pop_front();
+    }
+}
+
+fn request_stats(requests: u64, errors: u64, elapsed: f64) -> RequestStats {
+    RequestStats {
+        requests,
+        request_rate: requests as f64 / elapsed,
+        errors,
+        error_rate: if requests > 0 {
+            errors as f64 / requests as f64
+        } else {
+            0.0
+        },
+    }
+}
+
+/// Nearest-rank percentile of the given values.
+fn percentile(values: &mut [f64], p: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let rank = ((p * values.len() as f64).ceil() as usize).clamp(1, values.len());
+
+    Some(values[rank - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn requests_are_counted_per_resource() {
+        let registry = MetricsRegistry::new();
+        registry.record_request("recipe", false);
+        registry.record_request("recipe", true);
+        registry.record_request("ingredient", false);
+        registry.record_request("recipe", false);
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.requests.requests, 4);
+        assert_eq!(snapshot.requests.errors, 1);
+        assert_eq!(snapshot.requests.error_rate, 0.25);
+        assert_eq!(snapshot.resources["recipe"].requests, 3);
+        assert_eq!(snapshot.resources["ingredient"].errors, 0);
+        assert!(snapshot.requests.request_rate > 0.0);
+        assert!(snapshot.db_latency_p95_ms.is_none());
+    }
+
+    #[rstest]
+    fn old_buckets_are_dropped() {
+        let mut buckets = VecDeque::from([
+            Bucket {
+                second: 10,
+                requests: 1,
+                errors: 0,
+            },
+            Bucket {
+                second: 200,
+                requests: 2,
+                errors: 1,
+            },
+        ]);
+        prune_buckets(&mut buckets, 310);
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].second, 200);
+    }
+
+    #[rstest]
+    #[case(vec![], None)]
+    #[case(vec![12.0], Some(12.0))]
+    #[case((1..=100).map(f64::from).collect(), Some(95.0))]
+    #[case(vec![3.0, 1.0, 2.0], Some(3.0))]
+    fn percentiles_use_the_nearest_rank(
+        #[case] mut values: Vec<f64>,
+        #[case] expected: Option<f64>,
+    ) {
+        assert_eq!(percentile(&mut values, 0.95), expected);
+    }
+
+    #[rstest]
+    fn db_latency_is_summarised() {
+        let registry = MetricsRegistry::new();
+        for ms in 1..=20 {
+            registry.record_db_latency(Duration::from_millis(ms));
+        }
+        assert_eq!(registry.snapshot().db_latency_p95_ms, Some(19.0));
+    }
+
+    #[rstest]
+    fn banned_clients_are_reported() {
+        let registry = MetricsRegistry::new();
+        let throttle = Arc::new(IpThrottle::new(
+            1,
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        ));
+        registry.register_throttle(&throttle);
+        assert_eq!(registry.snapshot().banned_clients, 0);
+
+        let ip = "10.0.0.1".parse().unwrap();
+        throttle.check(ip);
+        throttle.check(ip);
+        assert_eq!(registry.snapshot().banned_clients, 1);
+
+        // Throttles of applications that are gone are no longer reported.
+        drop(throttle);
+        assert_eq!(registry.snapshot().banned_clients, 0);
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Cache for the suggestions of search terms.
+//!
+//! # Description
+//!
+//! Suggestions are requested on every keystroke of the search boxes of the frontend, while the totals of the searches
+//! they come from change every few minutes (see [crate::jobs::flush_usage_analytics]). Thus, the suggestions of every
+//! prefix are kept in memory for a fixed amount of time. The amount of prefixes kept is bounded, so the cache can't
+//! grow without limit when clients request random prefixes.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+/// Amount of time that the suggestions of a prefix are served from the cache.
+pub const SUGGESTION_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Maximum amount of prefixes kept in the cache.
+pub const MAX_CACHED_PREFIXES: usize = 1_000;
+
+/// Suggestions of a prefix, along with the moment they were built.
+type CachedSuggestions = (Instant, Arc<Vec<String>>);
+
+/// Cache for the suggestions of search terms, indexed by the normalized prefix.
+#[derive(Debug)]
+pub struct SuggestionCache {
+    ttl: Duration,
+    capacity: usize,
+    cached: RwLock<HashMap<String, CachedSuggestions>>,
+}
+
+impl Default for SuggestionCache {
+    fn default() -> Self {
+        SuggestionCache::new(SUGGESTION_CACHE_TTL, MAX_CACHED_PREFIXES)
+    }
+}
+
+impl SuggestionCache {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        SuggestionCache {
+            ttl,
+            capacity,
+            cached: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Retrieve the cached suggestions of a prefix. `None` is returned when they are older than the TTL of the cache.
+    pub fn get(&self, prefix: &str) -> Option<Arc<Vec<String>>> {
+        match self.cached.read() {
+            Ok(cached) => cached
+                .get(prefix)
+                .filter(|(built, _)| built.elapsed() < self.ttl)
+                .map(|(_, suggestions)| suggestions.clone()),
+            Err(_) => None,
+        }
+    }
+
+    /// Store the suggestions of a prefix.
+    ///
+    /// # Description
+    ///
+    /// When the cache is full, the expired entries are dropped. If none expired, the cache is emptied.
+    pub fn store(&self, prefix: &str, suggestions: Vec<String>) -> Arc<Vec<String>> {
+        let suggestions = Arc::new(suggestions);
+
+        if let Ok(mut cached) = self.cached.write() {
+            if cached.len() >= self.capacity && !cached.contains_key(prefix) {
+                cached.retain(|_, (built, _)| built.elapsed() < self.ttl);
+                if cached.len() >= self.capacity {
+                    cached.clear();
+                }
+            }
+            cached.insert(prefix.to_owned(), (Instant::now(), suggestions.clone()));
+        }
+
+        suggestions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn suggestions_expire() {
+        let cache = SuggestionCache::default();
+        assert!(cache.get("ma").is_none());
+        cache.store("ma", vec!["margarita".into(), "mai tai".into()]);
+        assert_eq!(
+            cache.get("ma").unwrap().as_slice(),
+            ["margarita", "mai tai"]
+        );
+        assert!(cache.get("mar").is_none());
+
+        let cache = SuggestionCache::new(Duration::ZERO, MAX_CACHED_PREFIXES);
+        cache.store("ma", Vec::new());
+        assert!(cache.get("ma").is_none());
+    }
+
+    #[rstest]
+    fn cache_is_bounded() {
+        let cache = SuggestionCache::new(SUGGESTION_CACHE_TTL, 2);
+        cache.store("ma", Vec::new());
+        cache.store("ne", Vec::new());
+        cache.store("ma", vec!["margarita".into()]);
+        assert!(cache.get("ne").is_some());
+
+        cache.store("go", Vec::new());
+        assert!(cache.get("ma").is_none());
+        assert!(cache.get("ne").is_none());
+        assert!(cache.get("go").is_some());
+    }
+}
@@ -0,0 +1,331 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Anonymous usage analytics of the API.
+//!
+//! # Description
+//!
+//! The analytics tell which features of the API are used, so the development can focus on them. They are disabled by
+//! default, and only aggregate counts are kept:
+//! - Requests per endpoint, i.e. `GET /recipe/{id}`. Endpoints are taken from the pattern of the matched route, so
+//!   the IDs and the parameters of the requests are not recorded. Requests that match no route are ignored.
+//! - Searches per term, for the recipes and the ingredients. Terms are normalized (see
+//!   [crate::domain::search_text::normalize_search_text]), and terms longer than [MAX_SEARCH_TERM_LENGTH] are ignored.
+//!
+//! Nothing that identifies the clients (API keys, addresses, user agents) is recorded. The counts are kept in memory
+//! by the [UsageAnalytics] registry, and a periodic job adds them to the daily totals of the DB (see
+//! [crate::jobs::flush_usage_analytics]).
+//!
+//! Search terms are only exposed (reports, suggestions) when at least [UsageAnalytics::k_anonymity] distinct clients
+//! searched them within a day, so rare searches, that could single out a person, are never exposed even when a
+//! single client repeats them. To count the clients, every search registers a hash of the client salted with a
+//! random value that changes every day (UTC) and is only kept in memory. Hence the hashes of a day can't be linked to
+//! the clients, nor to the hashes of other days.
+
+use crate::domain::search_text::normalize_search_text;
+use chrono::{NaiveDate, Utc};
+use once_cell::sync::Lazy;
+use rand::{thread_rng, RngCore};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Mutex,
+    },
+};
+
+/// Maximum length of the search terms that are recorded (characters).
+pub const MAX_SEARCH_TERM_LENGTH: usize = 40;
+
+/// Maximum amount of endpoints and search terms kept in memory between flushes. New keys are ignored past it.
+pub const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// Minimum threshold of the search terms reported.
+pub const MIN_K_ANONYMITY: u32 = 2;
+
+/// Threshold of the search terms reported when it is not configured.
+pub const DEFAULT_K_ANONYMITY: u32 = 5;
+
+static ANALYTICS: Lazy<UsageAnalytics> = Lazy::new(UsageAnalytics::new);
+
+/// Get the [UsageAnalytics] registry of the application.
+pub fn analytics() -> &'static UsageAnalytics {
+    &ANALYTICS
+}
+
+/// Usage counted since the last flush.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageCounts {
+    /// Requests per endpoint, i.e. `GET /recipe/{id}`.
+    pub endpoints: HashMap<String, u64>,
+    /// Searches per resource (`recipe` or `ingredient`) and normalized term.
+    pub searches: HashMap<(&'static str, String), u64>,
+    /// Clients that searched a term, as salted hashes, per day, resource and normalized term.
+    pub searchers: HashSet<Searcher>,
+}
+
+/// Salted hash of a client that searched a term on a day.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Searcher {
+    pub day: NaiveDate,
+    pub resource: &'static str,
+    pub term: String,
+    /// Hexadecimal hash of the client, salted with the random value of the day.
+    pub client_hash: String,
+}
+
+impl UsageCounts {
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty() && self.searches.is_empty() && self.searchers.is_empty()
+    }
+
+    /// Add the counts of another instance.
+    pub fn merge(&mut self, other: UsageCounts) {
+        for (endpoint, hits) in other.endpoints {
+            *self.endpoints.entry(endpoint).or_default() += hits;
+        }
+        for (key, hits) in other.searches {
+            *self.searches.entry(key).or_default() += hits;
+        }
+        self.searchers.extend(other.searchers);
+    }
+}
+
+/// Registry of the usage of the API, see the [module documentation](self).
+#[derive(Debug)]
+pub struct UsageAnalytics {
+    enabled: AtomicBool,
+    k_anonymity: AtomicU32,
+    counts: Mutex<UsageCounts>,
+    /// Salt of the hashes of the clients, along with the day it belongs to.
+    salt: Mutex<Option<(NaiveDate, [u8; 32])>>,
+}
+
+impl UsageAnalytics {
+    /// Build a disabled registry.
+    pub fn new() -> Self {
+        UsageAnalytics {
+            enabled: AtomicBool::new(false),
+            k_anonymity: AtomicU32::new(DEFAULT_K_ANONYMITY),
+            counts: Mutex::new(UsageCounts::default()),
+            salt: Mutex::new(None),
+        }
+    }
+
+    /// Enable or disable the registry, and set the threshold of the search terms reported. The threshold is raised
+    /// to [MIN_K_ANONYMITY] when it is lower.
+    pub fn configure(&self, enabled: bool, k_anonymity: u32) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        self.k_anonymity
+            .store(k_anonymity.max(MIN_K_ANONYMITY), Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Minimum amount of distinct clients that searched a term within a day to expose it.
+    pub fn k_anonymity(&self) -> u32 {
+        self.k_anonymity.load(Ordering::Relaxed)
+    }
+
+    /// Register a request to an endpoint. Nothing is registered when the registry is disabled.
+    pub fn record_hit(&self, endpoint: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        if let Ok(mut counts) = self.counts.lock() {
+            if let Some(hits) = counts.endpoints.get_mut(endpoint) {
+                *hits += 1;
+            } else if counts.endpoints.len() < MAX_TRACKED_KEYS {
+                counts.endpoints.insert(endpoint.to_owned(), 1);
+            }
+        }
+    }
+
+    /// Register a search of a resource by a client, i.e. its client ID or its IP.
+    ///
+    /// # Description
+    ///
+    /// Nothing is registered when the registry is disabled, or the term is empty or too long. Searches of unknown
+    /// clients are counted, but they don't count as a distinct client.
+    pub fn record_search(&self, resource: &'static str, term: &str, client: Option<&str>) {
+        if !self.is_enabled() {
+            return;
+        }
+        let term = normalize_search_text(term);
+        if term.is_empty() || term.chars().count() > MAX_SEARCH_TERM_LENGTH {
+            return;
+        }
+        let day = Utc::now().date_naive();
+        let client_hash = client.map(|client| self.client_hash(day, client));
+
+        if let Ok(mut counts) = self.counts.lock() {
+            if let Some(client_hash) = client_hash {
+                if counts.searchers.len() < MAX_TRACKED_KEYS {
+                    counts.searchers.insert(Searcher {
+                        day,
+                        resource,
+                        term: term.clone(),
+                        client_hash,
+                    });
+                }
+            }
+
+            let key = (resource, term);
+            if let Some(hits) = counts.searches.get_mut(&key) {
+                *hits += 1;
+            } else if counts.searches.len() < MAX_TRACKED_KEYS {
+                counts.searches.insert(key, 1);
+            }
+        }
+    }
+
+    /// Hash a client using the salt of the given day. A new salt is drawn when the day changes.
+    fn client_hash(&self, day: NaiveDate, client: &str) -> String {
+        let salt = match self.salt.lock() {
+            Ok(mut salt) => match *salt {
+                Some((salt_day, value)) if salt_day == day => value,
+                _ => {
+                    let mut value = [0u8; 32];
+                    thread_rng().fill_bytes(&mut value);
+                    *salt = Some((day, value));
+                    value
+                }
+            },
+            // Unsalted hashes could be linked to the clients, so the client is hashed with a throwaway salt.
+            Err(_) => {
+                let mut value = [0u8; 32];
+                thread_rng().fill_bytes(&mut value);
+                value
+            }
+        };
+
+        Sha256::new()
+            .chain_update(salt)
+            .chain_update(client.as_bytes())
+            .finalize()
+            .iter()
+            .take(16)
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
+    /// Take the usage counted since the last call.
+    pub fn take(&self) -> UsageCounts {
+        match self.counts.lock() {
+            Ok(mut counts) => std::mem::take(&mut *counts),
+            Err(_) => UsageCounts::default(),
+        }
+    }
+
+    /// Give back counts that could not be stored, so they are stored by the next flush.
+    pub fn restore(&self, counts: UsageCounts) {
+        if let Ok(mut current) = self.counts.lock() {
+            current.merge(counts);
+        }
+    }
+}
+
+impl Default for UsageAnalytics {
+    fn default() -> Self {
+        UsageAnalytics::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn enabled_registry() -> UsageAnalytics {
+        let registry = UsageAnalytics::new();
+        registry.configure(true, DEFAULT_K_ANONYMITY);
+        registry
+    }
+
+    #[rstest]
+    fn disabled_registries_record_nothing() {
+        let registry = UsageAnalytics::new();
+        registry.record_hit("GET /recipe");
+        registry.record_search("recipe", "mojito", Some("1.2.3.4"));
+        assert!(registry.take().is_empty());
+    }
+
+    #[rstest]
+    fn usage_is_counted() {
+        let registry = enabled_registry();
+        registry.record_hit("GET /recipe");
+        registry.record_hit("GET /recipe");
+        registry.record_hit("GET /recipe/{id}");
+        registry.record_search("recipe", "Piña Colada", Some("1.2.3.4"));
+        registry.record_search("recipe", "pina  colada", Some("1.2.3.4"));
+        registry.record_search("recipe", "pina colada", Some("5.6.7.8"));
+        registry.record_search("ingredient", "pina colada", None);
+
+        let counts = registry.take();
+        assert_eq!(counts.endpoints["GET /recipe"], 2);
+        assert_eq!(counts.endpoints["GET /recipe/{id}"], 1);
+        assert_eq!(counts.searches[&("recipe", "pina colada".into())], 3);
+        assert_eq!(counts.searches[&("ingredient", "pina colada".into())], 1);
+        // Repeated searches of a client count once, and unknown clients don't count.
+        assert_eq!(counts.searchers.len(), 2);
+        assert!(counts
+            .searchers
+            .iter()
+            .all(|s| s.resource == "recipe" && s.client_hash.len() == 32));
+        assert!(registry.take().is_empty());
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("   ")]
+    #[case("a very long search that nobody would type, unless it is personal data")]
+    fn odd_terms_are_ignored(#[case] term: &str) {
+        let registry = enabled_registry();
+        registry.record_search("recipe", term, Some("1.2.3.4"));
+        assert!(registry.take().is_empty());
+    }
+
+    #[rstest]
+    fn clients_are_not_stored() {
+        let registry = enabled_registry();
+        let day = Utc::now().date_naive();
+        let hash = registry.client_hash(day, "1.2.3.4");
+        assert_eq!(hash, registry.client_hash(day, "1.2.3.4"));
+        assert_ne!(hash, registry.client_hash(day, "1.2.3.5"));
+        assert!(!hash.contains("1.2.3.4"));
+
+        // Other registries, or days, use other salts.
+        assert_ne!(hash, enabled_registry().client_hash(day, "1.2.3.4"));
+        assert_ne!(
+            hash,
+            registry.client_hash(day.succ_opt().unwrap(), "1.2.3.4")
+        );
+    }
+
+    #[rstest]
+    fn failed_flushes_are_restored() {
+        let registry = enabled_registry();
+        registry.record_hit("GET /recipe");
+        let counts = registry.take();
+        registry.record_hit("GET /recipe");
+        registry.restore(counts);
+        assert_eq!(registry.take().endpoints["GET /recipe"], 2);
+    }
+
+    #[rstest]
+    #[case(0, MIN_K_ANONYMITY)]
+    #[case(10, 10)]
+    fn the_threshold_has_a_minimum(#[case] k: u32, #[case] expected: u32) {
+        let registry = UsageAnalytics::new();
+        registry.configure(true, k);
+        assert_eq!(registry.k_anonymity(), expected);
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Disk cache for the rendered recipe sheets.
+//!
+//! # Description
+//!
+//! Rendered documents are stored using the ID of the recipe, the amount of servings and the timestamp of the latest
+//! modification of the recipe as key, so a document is never served once its recipe changes. The entries are indexed
+//! in memory, thus storing a document doesn't scan the directory of the cache, and its size is tracked as documents
+//! are stored and removed. The documents of a recipe are removed when the recipe is updated or deleted (see the
+//! [EventSink] implementation), and the oldest documents are removed when the cache exceeds its capacity.
+
+use crate::utils::events::{DomainEvent, EventSink, PublishFuture, StoredEvent};
+use actix_web::web;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Default capacity of the cache (bytes).
+pub const DEFAULT_PDF_CACHE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Key of a cached document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PdfKey {
+    id: Uuid,
+    servings: u8,
+    last_modified: i64,
+}
+
+impl PdfKey {
+    fn file_name(&self) -> String {
+        format!("{}-{}-{}.pdf", self.id, self.servings, self.last_modified)
+    }
+
+    /// Parse the name of a cached document. `None` is returned for files that don't belong to the cache.
+    fn parse(file_name: &str) -> Option<Self> {
+        let stem = file_name.strip_suffix(".pdf")?;
+        let (rest, last_modified) = stem.rsplit_once('-')?;
+        let (id, servings) = rest.rsplit_once('-')?;
+
+        Some(PdfKey {
+            id: Uuid::parse_str(id).ok()?,
+            servings: servings.parse().ok()?,
+            last_modified: last_modified.parse().ok()?,
+        })
+    }
+}
+
+/// Documents of the cache, along with their size.
+#[derive(Debug, Default)]
+struct PdfIndex {
+    sizes: HashMap<PdfKey, u64>,
+    /// Keys in the order the documents were stored, oldest first.
+    order: VecDeque<PdfKey>,
+    size: u64,
+}
+
+impl PdfIndex {
+    fn insert(&mut self, key: PdfKey, size: u64) {
+        if let Some(previous) = self.sizes.insert(key, size) {
+            self.size -= previous;
+            self.order.retain(|k| *k != key);
+        }
+        self.order.push_back(key);
+        self.size += size;
+    }
+
+    fn remove(&mut self, key: &PdfKey) -> bool {
+        match self.sizes.remove(key) {
+            Some(size) => {
+                self.size -= size;
+                self.order.retain(|k| k != key);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Disk cache for the rendered recipe sheets, see the [module documentation](self).
+///
+/// # Description
+///
+/// Clones of the cache share the same index, so the cache can be given to the handlers and to the outbox.
+#[derive(Debug, Clone)]
+pub struct PdfCache {
+    dir: PathBuf,
+    capacity: u64,
+    index: Arc<Mutex<PdfIndex>>,
+}
+
+impl PdfCache {
+    /// Build the cache, indexing the documents stored in `dir` by a previous run of the application.
+    pub fn new(dir: &Path) -> Self {
+        let mut index = PdfIndex::default();
+
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let key = PdfKey::parse(&entry.file_name().to_string_lossy());
+                if let (Some(key), Ok(metadata)) = (key, entry.metadata()) {
+                    index.insert(key, metadata.len());
+                }
+            }
+        }
+        debug!(
+            "{} PDF documents found in the cache ({} bytes)",
+            index.sizes.len(),
+            index.size
+        );
+
+        PdfCache {
+            dir: dir.to_path_buf(),
+            capacity: DEFAULT_PDF_CACHE_SIZE,
+            index: Arc::new(Mutex::new(index)),
+        }
+    }
+
+    /// Set the maximum size of the stored documents (bytes).
+    pub fn with_capacity(mut self, capacity: u64) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Size of the stored documents (bytes).
+    pub fn size(&self) -> u64 {
+        self.index
+            .lock()
+            .map(|index| index.size)
+            .unwrap_or_default()
+    }
+
+    fn file_path(&self, key: &PdfKey) -> PathBuf {
+        self.dir.join(key.file_name())
+    }
+
+    /// Remove the files of some documents that are no longer indexed.
+    fn remove_files(&self, keys: &[PdfKey]) {
+        for key in keys {
+            if let Err(e) = fs::remove_file(self.file_path(key)) {
+                warn!("Failed to remove a stale PDF document: {e}");
+            }
+        }
+    }
+
+    /// Retrieve a cached document. `None` is returned when no valid document is found.
+    pub fn get(&self, id: &Uuid, servings: u8, last_modified: i64) -> Option<Vec<u8>> {
+        let key = PdfKey {
+            id: *id,
+            servings,
+            last_modified,
+        };
+        if !self.index.lock().ok()?.sizes.contains_key(&key) {
+            return None;
+        }
+
+        match fs::read(self.file_path(&key)) {
+            Ok(document) => Some(document),
+            Err(e) => {
+                warn!("Failed to read a cached PDF document: {e}");
+                if let Ok(mut index) = self.index.lock() {
+                    index.remove(&key);
+                }
+                None
+            }
+        }
+    }
+
+    /// Store a document in the cache.
+    ///
+    /// # Description
+    ///
+    /// Stale documents of the same recipe and servings are removed. When the cache exceeds its capacity, the oldest
+    /// documents are removed.
+    pub fn store(
+        &self,
+        id: &Uuid,
+        servings: u8,
+        last_modified: i64,
+        document: &[u8],
+    ) -> Result<(), io::Error> {
+        fs::create_dir_all(&self.dir)?;
+
+        let key = PdfKey {
+            id: *id,
+            servings,
+            last_modified,
+        };
+        let path = self.file_path(&key);
+        debug!("Storing PDF document at {}", path.display());
+        fs::write(path, document)?;
+
+        let removed = match self.index.lock() {
+            Ok(mut index) => {
+                let mut removed = index
+                    .sizes
+                    .keys()
+                    .filter(|k| k.id == key.id && k.servings == servings && **k != key)
+                    .copied()
+                    .collect::<Vec<PdfKey>>();
+                for stale in removed.iter() {
+                    index.remove(stale);
+                }
+                index.insert(key, document.len() as u64);
+
+                while index.size > self.capacity && index.order.len() > 1 {
+                    match index.order.front().copied() {
+                        Some(oldest) => {
+                            index.remove(&oldest);
+                            removed.push(oldest);
+                        }
+                        None => break,
+                    }
+                }
+                removed
+            }
+            Err(_) => Vec::new(),
+        };
+        self.remove_files(&removed);
+
+        Ok(())
+    }
+
+    /// Remove all the documents of a recipe.
+    pub fn evict(&self, id: &Uuid) {
+        let removed = match self.index.lock() {
+            Ok(mut index) => {
+                let removed = index
+                    .sizes
+                    .keys()
+                    .filter(|k| k.id == *id)
+                    .copied()
+                    .collect::<Vec<PdfKey>>();
+                for key in removed.iter() {
+                    index.remove(key);
+                }
+                removed
+            }
+            Err(_) => Vec::new(),
+        };
+
+        if !removed.is_empty() {
+            debug!(
+                "Removing {} PDF documents of the recipe {id}",
+                removed.len()
+            );
+            self.remove_files(&removed);
+        }
+    }
+}
+
+impl EventSink for PdfCache {
+    fn name(&self) -> &str {
+        "pdf_cache"
+    }
+
+    fn publish<'a>(&'a self, event: &'a StoredEvent) -> PublishFuture<'a> {
+        Box::pin(async move {
+            if let DomainEvent::RecipeUpdated { recipe_id }
+            | DomainEvent::RecipeDeleted { recipe_id } = event.event
+            {
+                let cache = self.clone();
+                web::block(move || cache.evict(&recipe_id)).await?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[fixture]
+    fn dir() -> PathBuf {
+        std::env::temp_dir().join(Uuid::now_v7().to_string())
+    }
+
+    #[rstest]
+    fn documents_are_cached(dir: PathBuf) {
+        let cache = PdfCache::new(&dir);
+        let id = Uuid::now_v7();
+
+        assert!(cache.get(&id, 1, 1).is_none());
+        cache.store(&id, 1, 1, b"old").unwrap();
+        assert_eq!(cache.get(&id, 1, 1).unwrap(), b"old");
+        cache.store(&id, 1, 2, b"newer").unwrap();
+        assert!(cache.get(&id, 1, 1).is_none());
+        assert_eq!(cache.get(&id, 1, 2).unwrap(), b"newer");
+        assert_eq!(cache.size(), 5);
+
+        // A new instance indexes the documents of the former one.
+        let cache = PdfCache::new(&dir);
+        assert_eq!(cache.get(&id, 1, 2).unwrap(), b"newer");
+        assert_eq!(cache.size(), 5);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[rstest]
+    fn documents_are_evicted(dir: PathBuf) {
+        let cache = PdfCache::new(&dir);
+        let (id, other) = (Uuid::now_v7(), Uuid::now_v7());
+        cache.store(&id, 1, 1, b"one").unwrap();
+        cache.store(&id, 2, 1, b"two").unwrap();
+        cache.store(&other, 1, 1, b"other").unwrap();
+
+        cache.evict(&id);
+        assert!(cache.get(&id, 1, 1).is_none());
+        assert!(cache.get(&id, 2, 1).is_none());
+        assert_eq!(cache.get(&other, 1, 1).unwrap(), b"other");
+        assert_eq!(cache.size(), 5);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[rstest]
+    fn cache_is_bounded(dir: PathBuf) {
+        let cache = PdfCache::new(&dir).with_capacity(8);
+        let (first, second) = (Uuid::now_v7(), Uuid::now_v7());
+        cache.store(&first, 1, 1, b"first").unwrap();
+        cache.store(&second, 1, 1, b"second").unwrap();
+
+        assert!(cache.get(&first, 1, 1).is_none());
+        assert_eq!(cache.get(&second, 1, 1).unwrap(), b"second");
+        assert_eq!(cache.size(), 6);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[rstest]
+    #[case("0191e13b-5ab7-78f1-bc06-be503a6c111b-2-1729000000.pdf", true)]
+    #[case("0191e13b-5ab7-78f1-bc06-be503a6c111b-2-1729000000.txt", false)]
+    #[case("notes.pdf", false)]
+    fn file_names_are_parsed(#[case] file_name: &str, #[case] valid: bool) {
+        let key = PdfKey::parse(file_name);
+        assert_eq!(key.is_some(), valid);
+        if let Some(key) = key {
+            assert_eq!(key.file_name(), file_name);
+        }
+    }
+}
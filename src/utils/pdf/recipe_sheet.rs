@@ -0,0 +1,319 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Rendering of recipes into print-ready PDF documents.
+
+use crate::domain::{QuantityUnit, Recipe};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+
+/// Maximum amount of servings accepted to scale a recipe.
+pub const MAX_SERVINGS: u8 = 50;
+
+/// Width of an A4 page (points).
+const PAGE_WIDTH: f32 = 595.0;
+/// Height of an A4 page (points).
+const PAGE_HEIGHT: f32 = 842.0;
+/// Margin applied to all the sides of the page (points).
+const MARGIN: f32 = 56.0;
+/// Maximum amount of characters of a line before wrapping the text.
+const LINE_WIDTH: usize = 85;
+
+const REGULAR_FONT: Name = Name(b"F1");
+const BOLD_FONT: Name = Name(b"F2");
+
+/// Ingredient entry of a recipe sheet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetIngredient {
+    pub name: String,
+    pub quantity: f32,
+    pub unit: QuantityUnit,
+}
+
+/// Style of a line of text within the sheet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Style {
+    Title,
+    Heading,
+    Body,
+}
+
+impl Style {
+    fn font(&self) -> (Name<'static>, f32) {
+        match self {
+            Style::Title => (BOLD_FONT, 22.0),
+            Style::Heading => (BOLD_FONT, 14.0),
+            Style::Body => (REGULAR_FONT, 11.0),
+        }
+    }
+
+    /// Vertical space taken by a line of this style.
+    fn leading(&self) -> f32 {
+        self.font().1 * 1.5
+    }
+}
+
+/// Render a recipe into a PDF document.
+///
+/// # Description
+///
+/// The quantities of the ingredients are multiplied by `servings`, as recipes are registered for a single serving.
+/// The document uses the standard PDF fonts (Helvetica), so characters out of the Latin-1 set are replaced by `?`.
+pub fn render_recipe_sheet(
+    recipe: &Recipe,
+    ingredients: &[SheetIngredient],
+    servings: u8,
+) -> Vec<u8> {
+    let mut lines = Vec::new();
+
+    lines.push((Style::Title, recipe.name().to_owned()));
+    let mut summary = format!(
+        "Category: {} - Rating: {}/5 - Servings: {servings}",
+        recipe.category(),
+        recipe.rating()
+    );
+    if let Some(minutes) = recipe.prep_time_minutes() {
+        summary.push_str(&format!(" - Preparation: {minutes} min"));
+    }
+    lines.push((Style::Body, summary));
+
+    if let Some(description) = recipe.description() {
+        push_wrapped(&mut lines, Style::Body, description, "");
+    }
+
+    lines.push((Style::Heading, "Ingredients".to_owned()));
+    for ingredient in ingredients {
+        let text = format!(
+            "- {} {} {}",
+            format_quantity(ingredient.quantity * servings as f32),
+            ingredient.unit,
+            ingredient.name
+        );
+        push_wrapped(&mut lines, Style::Body, &text, "  ");
+    }
+
+    if let Some(equipment) = recipe.equipment().filter(|e| !e.is_empty()) {
+        let equipment = equipment
+            .iter()
+            .map(|e| e.to_string().replace('_', " "))
+            .collect::<Vec<String>>()
+            .join(", ");
+        lines.push((Style::Heading, "Equipment".to_owned()));
+        push_wrapped(&mut lines, Style::Body, &equipment, "");
+    }
+
+    lines.push((Style::Heading, "Steps".to_owned()));
+    for (i, step) in recipe.steps().iter().enumerate() {
+        push_wrapped(
+            &mut lines,
+            Style::Body,
+            &format!("{}. {step}", i + 1),
+            "   ",
+        );
+    }
+
+    if recipe.source().is_some() || recipe.url().is_some() {
+        lines.push((Style::Heading, "Source".to_owned()));
+        if let Some(source) = recipe.source() {
+            push_wrapped(&mut lines, Style::Body, &source.attribution(), "");
+        }
+        if let Some(url) = recipe.url() {
+            push_wrapped(&mut lines, Style::Body, url, "");
+        }
+    }
+
+    let license = recipe.license();
+    let license = match license.url() {
+        Some(url) => format!("License: {} ({url})", license.label()),
+        None => format!("License: {}", license.label()),
+    };
+    push_wrapped(&mut lines, Style::Body, &license, "");
+
+    build_document(&lines)
+}
+
+/// Format a quantity removing the trailing zeros of the decimal part.
+pub fn format_quantity(quantity: f32) -> String {
+    let s = format!("{quantity:.2}");
+    s.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+/// Split a text into lines of [LINE_WIDTH] characters at most. `indent` is prepended to the continuation lines.
+fn push_wrapped(lines: &mut Vec<(Style, String)>, style: Style, text: &str, indent: &str) {
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + word.chars().count() + 1 > LINE_WIDTH {
+            lines.push((style, current));
+            current = indent.to_owned();
+        }
+        if !current.trim().is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+
+    if !current.trim().is_empty() {
+        lines.push((style, current));
+    }
+}
+
+/// Encode a string using the Windows-1252 encoding expected by the standard fonts.
+fn encode_latin1(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x20..=0x7E | 0xA0..=0xFF => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Lay out the lines into as many pages as needed and serialise the document.
+fn build_document(lines: &[(Style, String)]) -> Vec<u8> {
+    let mut next_id = Ref::new(1);
+    let mut alloc = || next_id.bump();
+
+    let catalog_id = alloc();
+    let page_tree_id = alloc();
+    let regular_font_id = alloc();
+    let bold_font_id = alloc();
+
+    // Split the content into pages.
+    let mut pages = Vec::new();
+    let mut content = Content::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+
+    for (style, text) in lines {
+        if y - style.leading() < MARGIN {
+            pages.push(content.finish());
+            content = Content::new();
+            y = PAGE_HEIGHT - MARGIN;
+        }
+        // Leave some extra space before the headings.
+        if *style == Style::Heading {
+            y -= style.leading() / 2.0;
+        }
+        y -= style.leading();
+
+        let (font, size) = style.font();
+        content.begin_text();
+        content.set_font(font, size);
+        content.next_line(MARGIN, y);
+        content.show(Str(&encode_latin1(text)));
+        content.end_text();
+    }
+    pages.push(content.finish());
+
+    let mut pdf = Pdf::new();
+    let page_ids: Vec<(Ref, Ref)> = pages.iter().map(|_| (alloc(), alloc())).collect();
+
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().map(|(page_id, _)| *page_id))
+        .count(page_ids.len() as i32);
+
+    for ((page_id, content_id), content) in page_ids.iter().zip(pages.iter()) {
+        let mut page = pdf.page(*page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+        page.parent(page_tree_id);
+        page.contents(*content_id);
+        page.resources()
+            .fonts()
+            .pair(REGULAR_FONT, regular_font_id)
+            .pair(BOLD_FONT, bold_font_id);
+        page.finish();
+        pdf.stream(*content_id, content);
+    }
+
+    for (id, name) in [
+        (regular_font_id, Name(b"Helvetica")),
+        (bold_font_id, Name(b"Helvetica-Bold")),
+    ] {
+        pdf.type1_font(id)
+            .base_font(name)
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+    }
+
+    pdf.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Equipment, RecipeSource};
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use uuid::Uuid;
+
+    #[fixture]
+    fn recipe() -> Recipe {
+        Recipe::new(
+            Some(Uuid::now_v7()),
+            "Daiquiri",
+            None,
+            None,
+            None,
+            "easy",
+            Some("A classic sour."),
+            None,
+            &[],
+            &[
+                "Shake all the ingredients with ice.",
+                "Double strain into a chilled coupe.",
+            ],
+            Some(&[Equipment::Shaker, Equipment::FineStrainer]),
+            Some(3),
+            None,
+        )
+        .expect("Failed to build a recipe")
+    }
+
+    #[rstest]
+    #[case(2.0, "2")]
+    #[case(1.5, "1.5")]
+    #[case(0.25, "0.25")]
+    fn quantities_are_formatted(#[case] quantity: f32, #[case] expected: &str) {
+        assert_eq!(format_quantity(quantity), expected);
+    }
+
+    #[rstest]
+    fn long_lines_are_wrapped() {
+        let mut lines = Vec::new();
+        push_wrapped(&mut lines, Style::Body, &"word ".repeat(40), "  ");
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|(_, l)| l.chars().count() <= LINE_WIDTH));
+        assert!(lines[1].1.starts_with("  word"));
+    }
+
+    #[rstest]
+    fn recipe_sheet_is_rendered(recipe: Recipe) {
+        let ingredients = [SheetIngredient {
+            name: "White Rum".into(),
+            quantity: 60.0,
+            unit: QuantityUnit::MilliLiter,
+        }];
+        let document = render_recipe_sheet(&recipe, &ingredients, 2);
+        assert!(document.starts_with(b"%PDF-"));
+        // The quantities are scaled with the servings.
+        let text = String::from_utf8_lossy(&document);
+        assert!(text.contains("(- 120 ml White Rum)"));
+        assert!(text.contains("(shaker, fine strainer)"));
+        assert!(text.contains("Preparation: 3 min)"));
+    }
+
+    #[rstest]
+    fn sources_are_credited(recipe: Recipe) {
+        let recipe = recipe.with_source(Some(RecipeSource {
+            book: Some("The Savoy Cocktail Book".into()),
+            page: Some(42),
+            url: None,
+            author: Some("Harry Craddock".into()),
+        }));
+        let document = render_recipe_sheet(&recipe, &[], 1);
+        let text = String::from_utf8_lossy(&document);
+        assert!(text.contains("(Harry Craddock, The Savoy Cocktail Book, p. 42)"));
+        assert!(text.contains("(License: All rights reserved)"));
+    }
+}
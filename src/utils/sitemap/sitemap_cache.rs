@@ -0,0 +1,188 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Generation of the sitemaps of the public content.
+//!
+//! # Description
+//!
+//! Sitemaps follow the [sitemaps.org protocol](https://www.sitemaps.org/protocol.html). A sitemap can't list more
+//! than [MAX_URLS_PER_SITEMAP] URLs, so the content is split into pages when needed. In such case, a sitemap index
+//! listing all the pages is served instead of a single sitemap.
+//!
+//! Listed URLs point to the frontend of the project, not to the API, as the former is what search engines shall index.
+
+use chrono::DateTime;
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+
+/// Maximum amount of URLs that a sitemap can hold (sitemaps.org protocol).
+pub const MAX_URLS_PER_SITEMAP: usize = 50_000;
+
+/// Media type of the sitemaps.
+pub const SITEMAP_CONTENT_TYPE: &str = "application/xml";
+
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+const SITEMAP_NS: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+/// Entry of a sitemap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapEntry {
+    /// Path of the resource within the frontend, i.e. `recipe/0191e13b-5ab7-78f1-bc06-be503a6c111b`.
+    pub path: String,
+    /// UNIX timestamp of the latest modification of the resource.
+    pub last_modified: Option<i64>,
+}
+
+/// Cache for the rendered sitemaps.
+///
+/// # Description
+///
+/// Rendering the sitemaps requires listing all the public content of the DB, so the rendered pages are kept in memory
+/// along with a fingerprint of the content they were built from. The fingerprint shall change whenever the public
+/// content changes (new, modified or deleted entries), which invalidates the cached pages.
+#[derive(Debug)]
+pub struct SitemapCache {
+    frontend_url: String,
+    cached: RwLock<Option<(String, Arc<Vec<String>>)>>,
+}
+
+impl SitemapCache {
+    pub fn new(frontend_url: &str) -> Self {
+        SitemapCache {
+            frontend_url: frontend_url.trim_end_matches('/').to_owned(),
+            cached: RwLock::new(None),
+        }
+    }
+
+    /// Retrieve the cached pages. `None` is returned when the pages were built from a different fingerprint.
+    pub fn get(&self, fingerprint: &str) -> Option<Arc<Vec<String>>> {
+        match self.cached.read() {
+            Ok(cached) => cached
+                .as_ref()
+                .filter(|(f, _)| f == fingerprint)
+                .map(|(_, pages)| pages.clone()),
+            Err(_) => None,
+        }
+    }
+
+    /// Render the sitemap pages of the given entries, and store them in the cache.
+    pub fn build(&self, fingerprint: &str, entries: &[SitemapEntry]) -> Arc<Vec<String>> {
+        let pages = Arc::new(
+            entries
+                .chunks(MAX_URLS_PER_SITEMAP)
+                .map(|chunk| self.render_urlset(chunk))
+                .collect::<Vec<String>>(),
+        );
+        // An empty DB still gets a valid (empty) sitemap.
+        let pages = if pages.is_empty() {
+            Arc::new(vec![self.render_urlset(&[])])
+        } else {
+            pages
+        };
+        debug!("Sitemap rebuilt: {} pages", pages.len());
+
+        if let Ok(mut cached) = self.cached.write() {
+            *cached = Some((fingerprint.to_owned(), pages.clone()));
+        }
+
+        pages
+    }
+
+    fn render_urlset(&self, entries: &[SitemapEntry]) -> String {
+        let mut xml = format!("{XML_HEADER}\n<urlset xmlns=\"{SITEMAP_NS}\">\n");
+
+        for entry in entries {
+            xml.push_str("  <url>\n");
+            xml.push_str(&format!(
+                "    <loc>{}</loc>\n",
+                escape_xml(&format!("{}/{}", self.frontend_url, entry.path))
+            ));
+            if let Some(lastmod) = entry.last_modified.and_then(format_lastmod) {
+                xml.push_str(&format!("    <lastmod>{lastmod}</lastmod>\n"));
+            }
+            xml.push_str("  </url>\n");
+        }
+
+        xml.push_str("</urlset>\n");
+        xml
+    }
+}
+
+/// Render a sitemap index that lists `pages` sitemaps. `page_url` builds the URL of a page from its number (1-based).
+pub fn render_sitemap_index(pages: usize, page_url: impl Fn(usize) -> String) -> String {
+    let mut xml = format!("{XML_HEADER}\n<sitemapindex xmlns=\"{SITEMAP_NS}\">\n");
+
+    for page in 1..=pages {
+        xml.push_str(&format!(
+            "  <sitemap>\n    <loc>{}</loc>\n  </sitemap>\n",
+            escape_xml(&page_url(page))
+        ));
+    }
+
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
+
+/// Format a UNIX timestamp using the W3C Datetime format expected by the `lastmod` tag.
+fn format_lastmod(timestamp: i64) -> Option<String> {
+    DateTime::from_timestamp(timestamp, 0).map(|t| t.format("%Y-%m-%dT%H:%M:%S+00:00").to_string())
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn entries(amount: usize) -> Vec<SitemapEntry> {
+        (0..amount)
+            .map(|i| SitemapEntry {
+                path: format!("recipe/{i}"),
+                last_modified: Some(0),
+            })
+            .collect()
+    }
+
+    #[rstest]
+    fn urlsets_are_rendered() {
+        let cache = SitemapCache::new("https://example.com/?a=1&b=2/");
+        let pages = cache.build("v1", &entries(1));
+
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].contains("<loc>https://example.com/?a=1&amp;b=2/recipe/0</loc>"));
+        assert!(pages[0].contains("<lastmod>1970-01-01T00:00:00+00:00</lastmod>"));
+    }
+
+    #[rstest]
+    fn large_sitemaps_are_paginated() {
+        let cache = SitemapCache::new("https://example.com");
+        let pages = cache.build("v1", &entries(MAX_URLS_PER_SITEMAP + 1));
+        assert_eq!(pages.len(), 2);
+
+        let index = render_sitemap_index(pages.len(), |p| {
+            format!("https://api.example.com/sitemap/{p}.xml")
+        });
+        assert!(index.contains("<loc>https://api.example.com/sitemap/2.xml</loc>"));
+    }
+
+    #[rstest]
+    fn cache_is_invalidated_by_the_fingerprint() {
+        let cache = SitemapCache::new("https://example.com");
+        assert!(cache.get("v1").is_none());
+
+        cache.build("v1", &entries(0));
+        assert_eq!(cache.get("v1").unwrap().len(), 1);
+        assert!(cache.get("v2").is_none());
+    }
+}
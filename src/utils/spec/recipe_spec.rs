@@ -0,0 +1,260 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Cocktail specifications in the style of the IBA.
+//!
+//! # Description
+//!
+//! Bartenders share recipes using a compact specification: the ingredients measured in centilitres, the method, the
+//! glass and the garnish. This module renders a [Recipe] into such specification (see [CocktailSpec]).
+//!
+//! Recipes don't register the glass nor the garnish on their own, so both are taken from the steps: the first step
+//! that starts with "Garnish" gives the garnish, and the first glass mentioned by the steps gives the glass. The
+//! method is chosen from the equipment of the recipe.
+
+use crate::{
+    domain::{units::convert_amount, Equipment, Language, QuantityUnit, Recipe, UnitSystem},
+    utils::pdf::{format_quantity, SheetIngredient},
+};
+use std::fmt;
+
+/// Media type of the specifications.
+pub const SPEC_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// Glasses recognised within the steps of a recipe, and their names in the specification. Longer names come first,
+/// so "old fashioned glass" is not taken as "fashioned".
+const GLASSES: [(&str, &str); 12] = [
+    ("old fashioned", "Old fashioned glass"),
+    ("cocktail glass", "Cocktail glass"),
+    ("martini glass", "Cocktail glass"),
+    ("wine glass", "Wine glass"),
+    ("copper mug", "Copper mug"),
+    ("highball", "Highball glass"),
+    ("collins", "Collins glass"),
+    ("hurricane", "Hurricane glass"),
+    ("rocks", "Rocks glass"),
+    ("coupe", "Coupe"),
+    ("flute", "Champagne flute"),
+    ("tiki", "Tiki mug"),
+];
+
+/// Methods used to mix a cocktail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecMethod {
+    /// The ingredients are poured straight into the glass.
+    Build,
+    Shake,
+    Stir,
+    Blend,
+}
+
+impl SpecMethod {
+    /// Choose the method using the equipment of a recipe. The most energetic method wins, i.e. recipes that need a
+    /// blender and a shaker are blended.
+    pub fn from_equipment(equipment: &[Equipment]) -> Self {
+        if equipment.contains(&Equipment::Blender) {
+            SpecMethod::Blend
+        } else if equipment.contains(&Equipment::Shaker) {
+            SpecMethod::Shake
+        } else if equipment.contains(&Equipment::MixingGlass) {
+            SpecMethod::Stir
+        } else {
+            SpecMethod::Build
+        }
+    }
+}
+
+impl fmt::Display for SpecMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecMethod::Build => write!(f, "Build"),
+            SpecMethod::Shake => write!(f, "Shake"),
+            SpecMethod::Stir => write!(f, "Stir"),
+            SpecMethod::Blend => write!(f, "Blend"),
+        }
+    }
+}
+
+/// Specification of a cocktail.
+///
+/// # Description
+///
+/// Volumes are converted to centilitres (see [crate::domain::units]), while the rest of the units, such as dashes or
+/// grams, are kept. The text representation of the specification is given by its [fmt::Display] implementation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CocktailSpec {
+    pub name: String,
+    /// Ingredients, i.e. `4.5 cl White Rum`.
+    pub ingredients: Vec<String>,
+    pub method: SpecMethod,
+    /// Preparation steps, excluding the garnish.
+    pub steps: Vec<String>,
+    pub glass: Option<String>,
+    pub garnish: Option<String>,
+}
+
+impl CocktailSpec {
+    /// Build the specification of a recipe.
+    ///
+    /// # Description
+    ///
+    /// `ingredients` shall include the names of the ingredients of the recipe, as [Recipe] only references them by ID.
+    pub fn new(recipe: &Recipe, ingredients: &[SheetIngredient]) -> Self {
+        let ingredients = ingredients
+            .iter()
+            .map(|i| format!("{} {}", format_amount(i.quantity, i.unit), i.name))
+            .collect();
+
+        let (garnish, steps): (Vec<&String>, Vec<&String>) = recipe
+            .steps()
+            .iter()
+            .partition(|step| step.to_lowercase().starts_with("garnish"));
+        let garnish = garnish.first().map(|step| {
+            step.trim_start_matches(|c: char| c.is_alphabetic())
+                .trim_start_matches([' ', ':'])
+                .trim_start_matches("with ")
+                .trim_end_matches('.')
+                .to_owned()
+        });
+
+        let glass = recipe.steps().iter().find_map(|step| {
+            let step = step.to_lowercase();
+            GLASSES
+                .iter()
+                .find(|(keyword, _)| step.contains(keyword))
+                .map(|(_, glass)| glass.to_string())
+        });
+
+        CocktailSpec {
+            name: recipe.name().to_owned(),
+            ingredients,
+            method: SpecMethod::from_equipment(recipe.equipment().unwrap_or_default()),
+            steps: steps.into_iter().cloned().collect(),
+            glass,
+            garnish: garnish.filter(|garnish| !garnish.is_empty()),
+        }
+    }
+}
+
+impl fmt::Display for CocktailSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.name.to_uppercase())?;
+        writeln!(f)?;
+        writeln!(f, "INGREDIENTS")?;
+        for ingredient in self.ingredients.iter() {
+            writeln!(f, "{ingredient}")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "METHOD")?;
+        writeln!(f, "{}. {}", self.method, self.steps.join(" "))?;
+        if let Some(glass) = &self.glass {
+            writeln!(f)?;
+            writeln!(f, "GLASS")?;
+            writeln!(f, "{glass}")?;
+        }
+        if let Some(garnish) = &self.garnish {
+            writeln!(f)?;
+            writeln!(f, "GARNISH")?;
+            writeln!(f, "{garnish}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Format an amount for the specification. Volumes are given in centilitres.
+fn format_amount(quantity: f32, unit: QuantityUnit) -> String {
+    match convert_amount(quantity, unit, UnitSystem::Metric) {
+        (quantity, QuantityUnit::MilliLiter) => format!("{} cl", format_quantity(quantity / 10.0)),
+        (quantity, unit) => format!(
+            "{} {}",
+            format_quantity(quantity),
+            Language::En.unit_label(unit)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use uuid::Uuid;
+
+    #[fixture]
+    fn recipe() -> Recipe {
+        Recipe::new(
+            Some(Uuid::now_v7()),
+            "Daiquiri",
+            None,
+            None,
+            None,
+            "easy",
+            None,
+            None,
+            &[],
+            &[
+                "Shake all the ingredients with ice.",
+                "Double strain into a chilled cocktail glass.",
+                "Garnish with a lime wheel.",
+            ],
+            Some(&[Equipment::Shaker, Equipment::FineStrainer]),
+            None,
+            None,
+        )
+        .expect("Failed to build a recipe")
+    }
+
+    #[rstest]
+    #[case(45.0, QuantityUnit::MilliLiter, "4.5 cl")]
+    #[case(1.0, QuantityUnit::Ounces, "3 cl")]
+    #[case(2.0, QuantityUnit::Dash, "2 dash")]
+    #[case(10.0, QuantityUnit::Grams, "10 g")]
+    fn amounts_are_given_in_centilitres(
+        #[case] quantity: f32,
+        #[case] unit: QuantityUnit,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(format_amount(quantity, unit), expected);
+    }
+
+    #[rstest]
+    #[case(&[Equipment::Shaker, Equipment::Blender], SpecMethod::Blend)]
+    #[case(&[Equipment::Jigger, Equipment::Shaker], SpecMethod::Shake)]
+    #[case(&[Equipment::MixingGlass, Equipment::BarSpoon], SpecMethod::Stir)]
+    #[case(&[], SpecMethod::Build)]
+    fn methods_follow_the_equipment(#[case] equipment: &[Equipment], #[case] expected: SpecMethod) {
+        assert_eq!(SpecMethod::from_equipment(equipment), expected);
+    }
+
+    #[rstest]
+    fn recipes_are_specified(recipe: Recipe) {
+        let ingredients = [
+            SheetIngredient {
+                name: "White Rum".into(),
+                quantity: 60.0,
+                unit: QuantityUnit::MilliLiter,
+            },
+            SheetIngredient {
+                name: "Lime Juice".into(),
+                quantity: 0.75,
+                unit: QuantityUnit::Ounces,
+            },
+        ];
+        let spec = CocktailSpec::new(&recipe, &ingredients);
+
+        assert_eq!(spec.ingredients, ["6 cl White Rum", "2.2 cl Lime Juice"]);
+        assert_eq!(spec.method, SpecMethod::Shake);
+        assert_eq!(spec.glass.as_deref(), Some("Cocktail glass"));
+        assert_eq!(spec.garnish.as_deref(), Some("a lime wheel"));
+        assert_eq!(
+            spec.to_string(),
+            "DAIQUIRI\n\nINGREDIENTS\n6 cl White Rum\n2.2 cl Lime Juice\n\nMETHOD\nShake. Shake all the \
+            ingredients with ice. Double strain into a chilled cocktail glass.\n\nGLASS\nCocktail glass\n\nGARNISH\n\
+            a lime wheel\n"
+        );
+    }
+}
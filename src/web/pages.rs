@@ -0,0 +1,254 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! HTML pages of the token request flow.
+//!
+//! # Description
+//!
+//! Pages are rendered within the layout of the [Theme] of the application, which is shared with the handlers using
+//! `web::Data`. Responses of the token request flow that carry no HTML content, i.e. the errors produced by the
+//! middlewares, are replaced by an error page using [error_pages]. The errors raised by the handlers of the API get
+//! the error page of the server using [server_error_pages].
+
+use super::theme::{escape_html, render_template, Theme};
+use actix_web::{
+    body::{BoxBody, EitherBody, MessageBody},
+    dev::ServiceResponse,
+    http::{
+        header::{self, ContentType, HeaderValue},
+        StatusCode,
+    },
+    middleware::{ErrorHandlerResponse, ErrorHandlers},
+    web::Data,
+    HttpResponse,
+};
+
+const TOKEN_REQUEST: &str = include_str!("templates/token_request.html");
+const SECRET_TOKEN: &str = include_str!("templates/secret_token.html");
+
+/// Build an HTML response.
+pub fn html_response(status: StatusCode, page: String) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type(ContentType::html())
+        .body(page)
+}
+
+/// Form to request an API token.
+pub fn token_request_page(theme: &Theme) -> String {
+    theme.render(
+        "Access request",
+        &render_template(TOKEN_REQUEST, &[("name", theme.name())]),
+    )
+}
+
+/// Page that shows the API token to the client once its email is validated.
+pub fn secret_token_page(theme: &Theme, token: &str) -> String {
+    theme.render(
+        "API Token",
+        &render_template(
+            SECRET_TOKEN,
+            &[("token", &escape_html(token)), ("script", theme.script())],
+        ),
+    )
+}
+
+/// Page that shows a message to the client.
+pub fn message_page(theme: &Theme, message: &str) -> String {
+    theme.render(
+        "Access request",
+        &format!(
+            "<div class=\"form\"><h3>{}</h3></div>",
+            escape_html(message)
+        ),
+    )
+}
+
+/// Page for the errors of the token request flow.
+pub fn error_page(theme: &Theme, status: StatusCode) -> String {
+    let (title, message) = match status {
+        StatusCode::NOT_FOUND => ("Page not found", "The requested page doesn't exist."),
+        status if status.is_server_error() => (
+            "Server error",
+            "Detected an error in the server, please, try again later.",
+        ),
+        status => (
+            status.canonical_reason().unwrap_or("Error"),
+            "The request couldn't be completed.",
+        ),
+    };
+
+    theme.render(
+        title,
+        &format!(
+            "<div class=\"form\"><h3>{}</h3><p>Error {}</p></div>",
+            escape_html(message),
+            status.as_u16()
+        ),
+    )
+}
+
+/// Middleware that renders the error pages of the token request flow.
+///
+/// # Description
+///
+/// Responses with a code **404** or **5xx** whose content is not HTML get their body replaced by [error_page].
+pub fn error_pages<B: MessageBody + 'static>() -> ErrorHandlers<B> {
+    ErrorHandlers::new()
+        .handler(StatusCode::NOT_FOUND, render_error_page)
+        .default_handler_server(render_error_page)
+}
+
+/// Middleware that renders the error page of the server.
+///
+/// # Description
+///
+/// Responses with a code **500** that were produced by an error raised by a handler (see [crate::domain::ApiError])
+/// get their body replaced by [error_page]. Other responses are left as they are.
+pub fn server_error_pages<B: MessageBody + 'static>() -> ErrorHandlers<B> {
+    ErrorHandlers::new().handler(StatusCode::INTERNAL_SERVER_ERROR, render_server_error_page)
+}
+
+fn render_server_error_page<B: MessageBody + 'static>(
+    res: ServiceResponse<B>,
+) -> actix_web::Result<ErrorHandlerResponse<B>> {
+    if res.response().error().is_none() {
+        return Ok(ErrorHandlerResponse::Response(res.map_into_left_body()));
+    }
+
+    render_error_page(res)
+}
+
+fn render_error_page<B: MessageBody + 'static>(
+    res: ServiceResponse<B>,
+) -> actix_web::Result<ErrorHandlerResponse<B>> {
+    let is_html = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|content_type| content_type.as_bytes().starts_with(b"text/html"));
+    if is_html {
+        return Ok(ErrorHandlerResponse::Response(res.map_into_left_body()));
+    }
+
+    let (req, res) = res.into_parts();
+    let status = res.status();
+    let page = match req.app_data::<Data<Theme>>() {
+        Some(theme) => error_page(theme, status),
+        None => error_page(&Theme::default(), status),
+    };
+    let mut res = res.set_body(BoxBody::new(page));
+    res.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/html; charset=utf-8"),
+    );
+    let res: ServiceResponse<EitherBody<B, BoxBody>> =
+        ServiceResponse::new(req, res).map_into_right_body();
+
+    Ok(ErrorHandlerResponse::Response(res))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        configuration::BrandingSettings,
+        domain::{ApiError, ServerError},
+        utils::assets::AssetStore,
+    };
+    use actix_web::{
+        body::to_bytes,
+        test::{call_service, init_service, TestRequest},
+        web, App,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[actix_web::test]
+    async fn error_pages_are_rendered() {
+        let app = init_service(
+            App::new().service(
+                web::scope("/token")
+                    .wrap(error_pages())
+                    .route(
+                        "/fail",
+                        web::get().to(|| async { HttpResponse::InternalServerError().finish() }),
+                    )
+                    .route(
+                        "/gone",
+                        web::get().to(|| async {
+                            html_response(
+                                StatusCode::NOT_FOUND,
+                                message_page(&Theme::default(), "<Gone>"),
+                            )
+                        }),
+                    ),
+            ),
+        )
+        .await;
+
+        for (uri, status, text) in [
+            ("/token/unknown", StatusCode::NOT_FOUND, "Page not found"),
+            (
+                "/token/fail",
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Server error",
+            ),
+            ("/token/gone", StatusCode::NOT_FOUND, "&lt;Gone&gt;"),
+        ] {
+            let response = call_service(&app, TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(response.status(), status, "{uri}");
+            assert_eq!(
+                response.headers().get(header::CONTENT_TYPE).unwrap(),
+                "text/html; charset=utf-8"
+            );
+            let body = to_bytes(response.into_body()).await.unwrap();
+            let body = String::from_utf8_lossy(&body);
+            assert!(body.starts_with("<!DOCTYPE html>"), "{uri}");
+            assert!(body.contains(text), "{uri}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn server_errors_get_the_theme_of_the_application() {
+        let branding = BrandingSettings {
+            name: "Tiki Bar".into(),
+            ..Default::default()
+        };
+        let theme = Theme::new(&branding, "/static", &AssetStore::default());
+        let app = init_service(
+            App::new()
+                .wrap(server_error_pages())
+                .app_data(web::Data::new(theme))
+                .route(
+                    "/error",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(ApiError::from(ServerError::DbError))
+                    }),
+                )
+                .route(
+                    "/plain",
+                    web::get().to(|| async { HttpResponse::InternalServerError().finish() }),
+                ),
+        )
+        .await;
+
+        let response = call_service(&app, TestRequest::get().uri("/error").to_request()).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("Server error"));
+        assert!(body.contains("Tiki Bar"));
+
+        let response = call_service(&app, TestRequest::get().uri("/plain").to_request()).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(to_bytes(response.into_body()).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn secret_token_is_escaped() {
+        let page = secret_token_page(&Theme::default(), "<script>");
+        assert!(page.contains("&lt;script&gt;"));
+        assert!(!page.contains("{{"));
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Layout shared by all the HTML pages of the application.
+//!
+//! # Description
+//!
+//! Every page is rendered within the same layout, which includes the branding of the instance (see [Theme]). The
+//! theme is built when the application starts, and it is shared with the handlers using `web::Data`.
+
+use crate::{
+    configuration::BrandingSettings,
+    utils::assets::{AssetMatch, AssetStore},
+};
+use tracing::warn;
+
+const LAYOUT: &str = include_str!("templates/layout.html");
+
+/// Branding of the HTML pages, ready to be rendered.
+///
+/// # Description
+///
+/// The name and the contact email are escaped when the theme is built, and invalid colors are replaced by the
+/// defaults of [BrandingSettings].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    name: String,
+    primary_color: String,
+    accent_color: String,
+    background_color: String,
+    contact_email: Option<String>,
+    stylesheet: String,
+    script: String,
+}
+
+impl Theme {
+    /// Build a theme from the settings.
+    ///
+    /// # Description
+    ///
+    /// `static_url` is the URL under which the `assets` are served. The pages link the hashed names of the assets, so
+    /// browsers cache them until their content changes.
+    pub fn new(branding: &BrandingSettings, static_url: &str, assets: &AssetStore) -> Self {
+        let defaults = BrandingSettings::default();
+
+        Theme {
+            name: escape_html(&branding.name),
+            primary_color: color_or(&branding.primary_color, defaults.primary_color),
+            accent_color: color_or(&branding.accent_color, defaults.accent_color),
+            background_color: color_or(&branding.background_color, defaults.background_color),
+            contact_email: branding.contact_email.as_deref().map(escape_html),
+            stylesheet: asset_url(static_url, assets, "style.css"),
+            script: asset_url(static_url, assets, "app.js"),
+        }
+    }
+
+    /// Escaped name of the instance.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// URL of the script of the pages.
+    pub fn script(&self) -> &str {
+        &self.script
+    }
+
+    /// Render a page within the layout.
+    ///
+    /// # Description
+    ///
+    /// The `title` is escaped, while the `content` is included verbatim, so it must be escaped by the caller.
+    pub fn render(&self, title: &str, content: &str) -> String {
+        let footer = match &self.contact_email {
+            Some(email) => format!(
+                "<footer class=\"form\"><p>Contact: <a href=\"mailto:{email}\">{email}</a></p></footer>"
+            ),
+            None => String::new(),
+        };
+
+        render_template(
+            LAYOUT,
+            &[
+                ("title", &escape_html(title)),
+                ("name", &self.name),
+                ("primary_color", &self.primary_color),
+                ("accent_color", &self.accent_color),
+                ("background_color", &self.background_color),
+                ("stylesheet", &self.stylesheet),
+                ("content", content),
+                ("footer", &footer),
+            ],
+        )
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new(
+            &BrandingSettings::default(),
+            "/static",
+            &AssetStore::default(),
+        )
+    }
+}
+
+/// Escape the characters of a text that have a meaning in HTML.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Replace the placeholders (`{{key}}`) of a template by their values.
+///
+/// # Description
+///
+/// The template is traversed once, so placeholders included by the values are never replaced. Unknown placeholders
+/// are kept untouched.
+pub(super) fn render_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}").and_then(|end| {
+            values
+                .iter()
+                .find(|(key, _)| *key == &after[..end])
+                .map(|(_, value)| (end, value))
+        });
+
+        match value {
+            Some((end, value)) => {
+                output.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                output.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+
+    output
+}
+
+/// Check that a color uses the hexadecimal notation of CSS: `#rgb`, `#rrggbb` or `#rrggbbaa`.
+fn color_or(color: &str, default: String) -> String {
+    let valid = color.strip_prefix('#').is_some_and(|hex| {
+        matches!(hex.len(), 3 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    });
+
+    if valid {
+        color.to_owned()
+    } else {
+        warn!("Invalid color in the branding settings: {color}");
+        default
+    }
+}
+
+/// URL of an asset, using its hashed name when the asset is loaded.
+fn asset_url(static_url: &str, assets: &AssetStore, name: &str) -> String {
+    match assets.get(name) {
+        Some(AssetMatch::Plain(asset)) => format!("{static_url}/{}", asset.hashed_name()),
+        _ => format!("{static_url}/{name}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case("{{a}} and {{b}}", "1 and 2")]
+    #[case("{{a}}{{unknown}}", "1{{unknown}}")]
+    #[case("{{c}}", "{{a}}")]
+    #[case("{{a", "{{a")]
+    fn templates_are_rendered(#[case] template: &str, #[case] expected: &str) {
+        let values = [("a", "1"), ("b", "2"), ("c", "{{a}}")];
+        assert_eq!(render_template(template, &values), expected);
+    }
+
+    #[rstest]
+    fn branding_is_applied() {
+        let branding = BrandingSettings {
+            name: "<My bar>".into(),
+            primary_color: "#ff0000".into(),
+            accent_color: "red; } body { display: none".into(),
+            background_color: "#FFF".into(),
+            contact_email: Some("admin@mybar.com".into()),
+        };
+        let mut assets = AssetStore::default();
+        assets.insert("style.css".into(), b"body {}".to_vec());
+        let hashed = assets.iter().next().unwrap().hashed_name();
+
+        let theme = Theme::new(&branding, "/api/v1/static", &assets);
+        assert_eq!(theme.name(), "&lt;My bar&gt;");
+        assert_eq!(theme.script(), "/api/v1/static/app.js");
+
+        let page = theme.render("A \"title\"", "<p>Content</p>");
+        assert!(page.contains("<title>A &quot;title&quot; | &lt;My bar&gt;</title>"));
+        assert!(page.contains("--primary-500: #ff0000;"));
+        assert!(page.contains("--primary-700: #3c3799;"));
+        assert!(page.contains("--backgroundColor: #FFF;"));
+        assert!(page.contains(&format!("href=\"/api/v1/static/{hashed}\"")));
+        assert!(page.contains("<p>Content</p>"));
+        assert!(page.contains("mailto:admin@mybar.com"));
+        assert!(!page.contains("{{"));
+    }
+}
@@ -0,0 +1,939 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use actix_web::http::StatusCode;
+use chrono::{TimeDelta, Utc};
+use lacoctelera::testing::{
+    fixtures::FixtureSeeder,
+    helpers::{spawn_app, spawn_app_with, TestApp},
+};
+use lacoctelera::{
+    authentication::{check_access, generate_token, Scope},
+    domain::{screening::ScreeningFlag, IngCategory},
+    jobs::flush_usage_analytics,
+    routes::{
+        admin::{
+            analytics::UsageReport,
+            author::AuthorMergeSummary,
+            clients::ClientRecord,
+            emails::EmailRecord,
+            ingredient_categories::ReclassifyReport,
+            migrations::{MigrationState, MigrationsReport},
+            moderation::ModerationEntry,
+            tags::BulkTagReport,
+        },
+        ingredient::categories::IngredientCategory,
+    },
+    utils::{
+        backup::BackupSnapshot,
+        events::{DomainEvent, StoredEvent},
+        mailing::{recipient_hash, EmailKind, EmailStatus},
+        metrics::{UsageAnalytics, DEFAULT_K_ANONYMITY},
+    },
+};
+use pretty_assertions::assert_eq;
+use reqwest::Response;
+use secrecy::{ExposeSecret, SecretString};
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+async fn post_merge(test_app: &TestApp, body: &serde_json::Value) -> Response {
+    let url = format!(
+        "{}/admin/author/merge?api_key={}",
+        &test_app.address,
+        test_app.api_token.api_key.expose_secret()
+    );
+
+    test_app
+        .api_client
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute POST for the resource /admin/author/merge.")
+}
+
+#[actix_web::test]
+async fn merge_authors() -> Result<(), String> {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+
+    let fixtures = FixtureSeeder::new(&test_app.db_pool)
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixtures
+        .recipe
+        .expect("Failed to seed the recipes")
+        .valid_fixtures[0];
+    let source = recipe
+        .owner()
+        .expect("Failed to unwrap recipe's owner")
+        .to_string();
+    let recipe_id = recipe
+        .id()
+        .expect("Failed to unwrap recipe's ID")
+        .to_string();
+
+    let target = Uuid::now_v7().to_string();
+    sqlx::query(
+        "INSERT INTO Author (id, name, surname, email) VALUES (?, 'Jane', 'Doe', 'jane@mail.com')",
+    )
+    .bind(&target)
+    .execute(&test_app.db_pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let body = json!({"source": source, "target": target});
+
+    info!("Test Case::resource::/admin/author/merge (POST) -> Attempt to merge with no admin privileges");
+    let response = post_merge(&test_app, &body).await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    test_app.grant_admin_access().await;
+
+    info!(
+        "Test Case::resource::/admin/author/merge (POST) -> Attempt to merge an author into itself"
+    );
+    let response = post_merge(&test_app, &json!({"source": source, "target": source})).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!(
+        "Test Case::resource::/admin/author/merge (POST) -> Attempt to merge a non existing author"
+    );
+    let response = post_merge(
+        &test_app,
+        &json!({"source": Uuid::now_v7().to_string(), "target": target}),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/admin/author/merge (POST) -> Merge two existing authors");
+    let response = post_merge(&test_app, &body).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let summary: AuthorMergeSummary = response
+        .json()
+        .await
+        .expect("Failed to parse the summary of the merge");
+    assert_eq!(summary.recipes_moved, 1);
+
+    let owner: String = sqlx::query_scalar("SELECT owner FROM Cocktail WHERE id = ?")
+        .bind(&recipe_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(owner, target);
+
+    let alias: String = sqlx::query_scalar("SELECT author_id FROM AuthorAlias WHERE alias_id = ?")
+        .bind(&source)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(alias, target);
+
+    info!("Test Case::resource::/admin/author/merge (POST) -> The merged author is no longer accessible");
+    let response = test_app
+        .api_client
+        .get(format!("{}/author/{source}", &test_app.address))
+        .send()
+        .await
+        .expect("Failed to execute GET for the resource /author.");
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    let response = post_merge(&test_app, &body).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+async fn admin_request(
+    test_app: &TestApp,
+    method: reqwest::Method,
+    path: &str,
+    body: Option<&serde_json::Value>,
+) -> Response {
+    let separator = if path.contains('?') { '&' } else { '?' };
+    let url = format!(
+        "{}/admin/{path}{separator}api_key={}",
+        &test_app.address,
+        test_app.api_token.api_key.expose_secret()
+    );
+
+    let mut request = test_app.api_client.request(method, url);
+    if let Some(body) = body {
+        request = request.json(body);
+    }
+
+    request
+        .send()
+        .await
+        .expect("Failed to execute the request for the resource /admin.")
+}
+
+#[actix_web::test]
+async fn moderation() -> Result<(), String> {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+
+    let fixtures = FixtureSeeder::new(&test_app.db_pool)
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe_id = fixtures
+        .recipe
+        .expect("Failed to seed the recipes")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to unwrap recipe's ID")
+        .to_string();
+
+    sqlx::query("INSERT INTO ModerationQueue (cocktail_id, reasons) VALUES (?, ?)")
+        .bind(&recipe_id)
+        .bind(r#"[{"excessive_links":3}]"#)
+        .execute(&test_app.db_pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let get_recipe = || async {
+        test_app
+            .api_client
+            .get(format!("{}/recipe/{recipe_id}", &test_app.address))
+            .send()
+            .await
+            .expect("Failed to execute GET for the resource /recipe.")
+            .status()
+            .as_u16()
+    };
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Recipes pending moderation are hidden");
+    assert_eq!(get_recipe().await, StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/admin/moderation (GET) -> Attempt to list the queue with no admin privileges");
+    let response = admin_request(&test_app, reqwest::Method::GET, "moderation", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    test_app.grant_admin_access().await;
+
+    info!("Test Case::resource::/admin/moderation (GET) -> List the moderation queue");
+    let response = admin_request(&test_app, reqwest::Method::GET, "moderation", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let queue: Vec<ModerationEntry> = response
+        .json()
+        .await
+        .expect("Failed to parse the moderation queue");
+    assert_eq!(queue.len(), 1);
+    assert_eq!(queue[0].recipe_id, recipe_id);
+    assert_eq!(queue[0].reasons, vec![ScreeningFlag::ExcessiveLinks(3)]);
+
+    info!("Test Case::resource::/admin/moderation/{{id}} (POST) -> Approve a recipe that is not pending");
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::POST,
+        &format!("moderation/{}", Uuid::now_v7()),
+        Some(&json!({"action": "approve"})),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/admin/moderation/{{id}} (POST) -> Approve a flagged recipe");
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::POST,
+        &format!("moderation/{recipe_id}"),
+        Some(&json!({"action": "approve"})),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    assert_eq!(get_recipe().await, StatusCode::OK);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn emails() -> Result<(), String> {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+
+    let body = json!({
+        "email": "janedoe@mail.com",
+        "explanation": "A_very_long_sentence_for_testing",
+    });
+    let response = test_app.post_token_request(&body).await;
+    assert_eq!(response.status().as_u16(), StatusCode::ACCEPTED);
+
+    info!("Test Case::resource::/admin/emails (GET) -> Attempt to list the emails with no admin privileges");
+    let response = admin_request(&test_app, reqwest::Method::GET, "emails", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    test_app.grant_admin_access().await;
+
+    let get_emails = |query: &'static str| {
+        let test_app = &test_app;
+        async move {
+            let response = admin_request(test_app, reqwest::Method::GET, query, None).await;
+            assert_eq!(response.status().as_u16(), StatusCode::OK);
+            response
+                .json::<Vec<EmailRecord>>()
+                .await
+                .expect("Failed to parse the list of emails")
+        }
+    };
+
+    info!("Test Case::resource::/admin/emails (GET) -> List the sent confirmations");
+    let emails = get_emails("emails?status=sent&kind=confirmation").await;
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].kind, EmailKind::Confirmation);
+    assert_eq!(emails[0].status, EmailStatus::Sent);
+    assert_eq!(
+        emails[0].recipient_hash.as_deref(),
+        Some(recipient_hash("janedoe@mail.com").as_str())
+    );
+
+    info!("Test Case::resource::/admin/emails (GET) -> Filter by recipient");
+    assert_eq!(
+        get_emails("emails?recipient=JaneDoe@mail.com").await.len(),
+        1
+    );
+    assert!(get_emails("emails?recipient=johndoe@mail.com")
+        .await
+        .is_empty());
+
+    info!("Test Case::resource::/admin/emails (GET) -> Filter by status and date");
+    assert!(get_emails("emails?status=failed").await.is_empty());
+    assert!(get_emails("emails?since=2999-01-01T00:00:00Z")
+        .await
+        .is_empty());
+
+    info!("Test Case::resource::/admin/emails (GET) -> Invalid filters");
+    let response = admin_request(&test_app, reqwest::Method::GET, "emails?status=lost", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn clients_lockout() -> Result<(), String> {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+    let victim_key = test_app.api_token.api_key.clone();
+    let victim_id = victim_key
+        .expose_secret()
+        .split(':')
+        .next()
+        .unwrap()
+        .to_owned();
+    // A second client acts as the administrator.
+    test_app.generate_access_token().await;
+    test_app.grant_admin_access().await;
+
+    info!("Test Case::resource::/admin/clients (GET) -> Lock a client after consecutive failures");
+    let policy = test_app.access_control.lockout_policy();
+    let wrong_key = SecretString::from(format!("{victim_id}:{}", generate_token()));
+    for _ in 0..policy.max_failures {
+        assert!(check_access(
+            &test_app.db_pool,
+            &test_app.access_control,
+            &wrong_key,
+            Scope::Read
+        )
+        .await
+        .is_err());
+    }
+    // The valid token is denied while the client is locked.
+    assert!(check_access(
+        &test_app.db_pool,
+        &test_app.access_control,
+        &victim_key,
+        Scope::Read
+    )
+    .await
+    .is_err());
+
+    let response =
+        admin_request(&test_app, reqwest::Method::GET, "clients?locked=true", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let clients = response
+        .json::<Vec<ClientRecord>>()
+        .await
+        .expect("Failed to parse the list of clients");
+    assert_eq!(clients.len(), 1);
+    assert_eq!(clients[0].id, victim_id);
+    assert_eq!(clients[0].total_failures, policy.max_failures as i64 + 1);
+    assert_eq!(clients[0].failed_attempts, 0);
+    assert!(clients[0].locked_until.is_some());
+
+    info!("Test Case::resource::/admin/clients (GET) -> List the clients that are not locked");
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::GET,
+        "clients?locked=false",
+        None,
+    )
+    .await;
+    let clients = response
+        .json::<Vec<ClientRecord>>()
+        .await
+        .expect("Failed to parse the list of clients");
+    assert!(clients.iter().all(|c| c.id != victim_id));
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn ingredient_categories() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+
+    let list_categories = || async {
+        test_app
+            .api_client
+            .get(format!("{}/ingredient/categories", &test_app.address))
+            .send()
+            .await
+            .expect("Failed to execute GET for the resource /ingredient/categories.")
+            .json::<Vec<IngredientCategory>>()
+            .await
+            .expect("Failed to parse the list of categories")
+    };
+    let add_ingredient = |category: &'static str| {
+        test_app
+            .api_client
+            .post(format!("{}/ingredient", &test_app.address))
+            .json(&json!({"name": "Agave syrup", "category": category}))
+            .send()
+    };
+
+    info!("Test Case::resource::/ingredient/categories (GET) -> The former categories are seeded");
+    let categories = list_categories().await;
+    assert_eq!(categories.len(), 5);
+    assert!(categories.iter().any(|c| c.name == "soft_drink"));
+
+    info!("Test Case::resource::/ingredient/categories (GET) -> Sort the categories using the Spanish rules");
+    let response = test_app
+        .api_client
+        .get(format!("{}/ingredient/categories", &test_app.address))
+        .header("Accept-Language", "es-ES, en;q=0.8")
+        .send()
+        .await
+        .expect("Failed to execute GET for the resource /ingredient/categories.");
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let sorted = response
+        .json::<Vec<IngredientCategory>>()
+        .await
+        .expect("Failed to parse the list of categories");
+    assert_eq!(sorted.len(), categories.len());
+
+    info!("Test Case::resource::/ingredient (POST) -> Unknown categories are rejected");
+    let response = add_ingredient("syrup")
+        .await
+        .expect("Failed to execute POST");
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/admin/ingredient/categories (POST) -> Attempt to add a category with no admin privileges");
+    let body = json!({"name": "syrup", "description": "Sweeteners"});
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::POST,
+        "ingredient/categories",
+        Some(&body),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    test_app.grant_admin_access().await;
+
+    info!("Test Case::resource::/admin/ingredient/categories (POST) -> Add a new category");
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::POST,
+        "ingredient/categories",
+        Some(&body),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CREATED);
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::POST,
+        "ingredient/categories",
+        Some(&body),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::POST,
+        "ingredient/categories",
+        Some(&json!({"name": "fresh juice"})),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/ingredient (POST) -> New categories can be used");
+    let response = add_ingredient("syrup")
+        .await
+        .expect("Failed to execute POST");
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    info!(
+        "Test Case::resource::/admin/ingredient/categories/{{name}} (PATCH) -> Modify a category"
+    );
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::PATCH,
+        "ingredient/categories/syrup",
+        Some(&json!({"description": "Simple, agave or honey syrups"})),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let category = response
+        .json::<IngredientCategory>()
+        .await
+        .expect("Failed to parse the category");
+    assert_eq!(
+        category.description.as_deref(),
+        Some("Simple, agave or honey syrups")
+    );
+    assert_eq!(category.ingredients, 1);
+
+    info!("Test Case::resource::/admin/ingredient/categories/{{name}} (DELETE) -> Categories in use are kept");
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::DELETE,
+        "ingredient/categories/syrup",
+        None,
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+
+    info!("Test Case::resource::/admin/ingredient/categories/{{name}} (DELETE) -> Delete an unused category");
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::DELETE,
+        "ingredient/categories/bitter",
+        None,
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::DELETE,
+        "ingredient/categories/bitter",
+        None,
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+    assert!(list_categories().await.iter().all(|c| c.name != "bitter"));
+}
+
+#[actix_web::test]
+async fn ingredient_reclassification() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+
+    for name in ["Agave syrup", "Orange juice"] {
+        let response = test_app
+            .api_client
+            .post(format!("{}/ingredient", &test_app.address))
+            .json(&json!({"name": name, "category": "other"}))
+            .send()
+            .await
+            .expect("Failed to execute POST");
+        assert_eq!(response.status().as_u16(), StatusCode::OK);
+    }
+    let category_of = |name: &'static str| {
+        let pool = &test_app.db_pool;
+        async move {
+            sqlx::query_scalar::<_, String>("SELECT category FROM Ingredient WHERE name = ?")
+                .bind(name)
+                .fetch_one(pool)
+                .await
+                .expect("Failed to retrieve the category of the ingredient")
+        }
+    };
+    let body = json!({"from": "other", "to": "syrup", "name": "syrup", "dry_run": true});
+
+    info!("Test Case::resource::/admin/ingredient/reclassify (POST) -> Attempt to reclassify with no admin privileges");
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::POST,
+        "ingredient/reclassify",
+        Some(&body),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    test_app.grant_admin_access().await;
+
+    info!("Test Case::resource::/admin/ingredient/reclassify (POST) -> Attempt to use an unknown category");
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::POST,
+        "ingredient/reclassify",
+        Some(&body),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::POST,
+        "ingredient/reclassify",
+        Some(&json!({"from": "other", "to": "other"})),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::POST,
+        "ingredient/categories",
+        Some(&json!({"name": "syrup"})),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CREATED);
+
+    let reclassify = |body: serde_json::Value| {
+        let test_app = &test_app;
+        async move {
+            let response = admin_request(
+                test_app,
+                reqwest::Method::POST,
+                "ingredient/reclassify",
+                Some(&body),
+            )
+            .await;
+            assert_eq!(response.status().as_u16(), StatusCode::OK);
+            response
+                .json::<ReclassifyReport>()
+                .await
+                .expect("Failed to parse the report")
+        }
+    };
+
+    info!("Test Case::resource::/admin/ingredient/reclassify (POST) -> A dry run doesn't move the ingredients");
+    let report = reclassify(body.clone()).await;
+    assert!(report.dry_run);
+    assert_eq!(report.matched, 1);
+    assert_eq!(category_of("Agave syrup").await, "other");
+
+    info!(
+        "Test Case::resource::/admin/ingredient/reclassify (POST) -> Move the selected ingredients"
+    );
+    let report = reclassify(json!({"from": "other", "to": "syrup", "name": "syrup"})).await;
+    assert_eq!(report.matched, 1);
+    assert_eq!(category_of("Agave syrup").await, "syrup");
+    assert_eq!(category_of("Orange juice").await, "other");
+
+    let ingredient_id = Uuid::parse_str(&report.ingredient_ids[0]).expect("Invalid ID");
+    let events = admin_request(&test_app, reqwest::Method::GET, "events", None)
+        .await
+        .json::<Vec<StoredEvent>>()
+        .await
+        .expect("Failed to parse the events");
+    assert!(events.iter().any(|e| e.event
+        == DomainEvent::IngredientReclassified {
+            ingredient_id,
+            from: IngCategory::try_from("other").unwrap(),
+            to: IngCategory::try_from("syrup").unwrap(),
+        }));
+}
+
+#[actix_web::test]
+async fn migrations_status() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+
+    info!("Test Case::resource::/admin/migrations (GET) -> Attempt to get the status with no admin privileges");
+    let response = admin_request(&test_app, reqwest::Method::GET, "migrations", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    test_app.grant_admin_access().await;
+
+    info!("Test Case::resource::/admin/migrations (GET) -> The testing DB is up to date");
+    let response = admin_request(&test_app, reqwest::Method::GET, "migrations", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let report = response
+        .json::<MigrationsReport>()
+        .await
+        .expect("Failed to parse the status of the migrations");
+    assert!(report.up_to_date);
+    assert_eq!(report.pending, 0);
+    assert_eq!(report.schema_checksum, report.db_checksum);
+    assert!(report
+        .migrations
+        .iter()
+        .all(|m| m.state == MigrationState::Applied && m.installed_on.is_some()));
+}
+
+#[actix_web::test]
+async fn backups() {
+    let backup_dir = std::env::temp_dir().join(format!("backups-{}", Uuid::now_v7()));
+    let mut test_app = spawn_app_with(|settings| {
+        settings.application.backup_dir = backup_dir.to_string_lossy().into_owned();
+    })
+    .await;
+    test_app.generate_access_token().await;
+
+    info!("Test Case::resource::/admin/backup (POST) -> Attempt to take a snapshot with no admin privileges");
+    let response = admin_request(&test_app, reqwest::Method::POST, "backup", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    test_app.grant_admin_access().await;
+
+    info!("Test Case::resource::/admin/backups (GET) -> No snapshots yet");
+    let response = admin_request(&test_app, reqwest::Method::GET, "backups", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert!(response
+        .json::<Vec<BackupSnapshot>>()
+        .await
+        .expect("Failed to parse the list of snapshots")
+        .is_empty());
+
+    info!("Test Case::resource::/admin/backup (POST) -> Take a snapshot");
+    let response = admin_request(&test_app, reqwest::Method::POST, "backup", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::CREATED);
+    let snapshot = response
+        .json::<BackupSnapshot>()
+        .await
+        .expect("Failed to parse the snapshot");
+    assert!(snapshot.size_bytes > 0);
+    let dump = std::fs::read_to_string(backup_dir.join(&snapshot.name))
+        .expect("Failed to read the snapshot");
+    assert!(dump.contains("CREATE TABLE `ApiUser`"));
+    assert!(dump.contains("INSERT INTO `ApiUser`"));
+
+    info!("Test Case::resource::/admin/backups (GET) -> List the snapshots");
+    let response = admin_request(&test_app, reqwest::Method::GET, "backups", None).await;
+    let snapshots = response
+        .json::<Vec<BackupSnapshot>>()
+        .await
+        .expect("Failed to parse the list of snapshots");
+    assert_eq!(snapshots, vec![snapshot]);
+    std::fs::remove_dir_all(backup_dir).unwrap();
+}
+
+#[actix_web::test]
+async fn domain_events() -> Result<(), String> {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+
+    let fixtures = FixtureSeeder::new(&test_app.db_pool)
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixtures
+        .recipe
+        .expect("Failed to seed the recipes")
+        .valid_fixtures[0];
+    let source = recipe.owner().expect("Failed to unwrap recipe's owner");
+    let recipe_id = recipe.id().expect("Failed to unwrap recipe's ID");
+
+    let target = Uuid::now_v7();
+    sqlx::query(
+        "INSERT INTO Author (id, name, surname, email) VALUES (?, 'Jane', 'Doe', 'jane@mail.com')",
+    )
+    .bind(target.to_string())
+    .execute(&test_app.db_pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    info!("Test Case::resource::/admin/events (GET) -> Attempt to list the events with no admin privileges");
+    let response = admin_request(&test_app, reqwest::Method::GET, "events", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    test_app.grant_admin_access().await;
+
+    let body = json!({"source": source.to_string(), "target": target.to_string()});
+    let response = post_merge(&test_app, &body).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    info!("Test Case::resource::/admin/events (GET) -> The merge changed the owner of the recipes");
+    let response = admin_request(&test_app, reqwest::Method::GET, "events", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let events = response
+        .json::<Vec<StoredEvent>>()
+        .await
+        .expect("Failed to parse the list of events");
+    assert!(events.iter().any(|stored| stored.event
+        == DomainEvent::RecipeOwnerChanged {
+            recipe_id,
+            owner: target
+        }));
+
+    info!("Test Case::resource::/admin/events (GET) -> Follow the trail");
+    let last = events.last().expect("No events were recorded").id;
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::GET,
+        &format!("events?after={last}&limit=10"),
+        None,
+    )
+    .await;
+    assert!(response
+        .json::<Vec<StoredEvent>>()
+        .await
+        .expect("Failed to parse the list of events")
+        .is_empty());
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn bulk_retagging() -> Result<(), String> {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+
+    let fixtures = FixtureSeeder::new(&test_app.db_pool)
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe_id = fixtures
+        .recipe
+        .expect("Failed to seed the recipes")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to unwrap recipe's ID")
+        .to_string();
+    let body =
+        json!({"action": "add", "tag": "smoky", "filter": {"tags": "test"}, "dry_run": true});
+
+    info!(
+        "Test Case::resource::/admin/tags/bulk (POST) -> Attempt to retag with no admin privileges"
+    );
+    let response = admin_request(&test_app, reqwest::Method::POST, "tags/bulk", Some(&body)).await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    test_app.grant_admin_access().await;
+
+    let retag = |body: serde_json::Value| {
+        let test_app = &test_app;
+        async move {
+            let response =
+                admin_request(test_app, reqwest::Method::POST, "tags/bulk", Some(&body)).await;
+            assert_eq!(response.status().as_u16(), StatusCode::OK);
+            response
+                .json::<BulkTagReport>()
+                .await
+                .expect("Failed to parse the report")
+        }
+    };
+    let recipe_tags = || async {
+        sqlx::query_scalar::<_, String>("SELECT tag FROM Tagged WHERE cocktail_id = ? ORDER BY tag")
+            .bind(&recipe_id)
+            .fetch_all(&test_app.db_pool)
+            .await
+            .expect("Failed to retrieve the tags")
+    };
+
+    info!("Test Case::resource::/admin/tags/bulk (POST) -> Attempt to retag with no filters");
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::POST,
+        "tags/bulk",
+        Some(&json!({"action": "remove", "tag": "test"})),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/admin/tags/bulk (POST) -> Attempt to replace a tag with no replacement");
+    let response = admin_request(
+        &test_app,
+        reqwest::Method::POST,
+        "tags/bulk",
+        Some(&json!({"action": "replace", "tag": "test", "filter": {"tags": "test"}})),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/admin/tags/bulk (POST) -> Dry run");
+    let report = retag(body).await;
+    assert_eq!(
+        report,
+        BulkTagReport {
+            dry_run: true,
+            matched: 1,
+            affected: 1,
+            recipe_ids: vec![recipe_id.clone()],
+        }
+    );
+    assert_eq!(recipe_tags().await, ["simple", "test"]);
+
+    info!("Test Case::resource::/admin/tags/bulk (POST) -> Add a tag");
+    let report = retag(json!({"action": "add", "tag": "smoky", "filter": {"tags": "test"}})).await;
+    assert_eq!(report.affected, 1);
+    assert_eq!(recipe_tags().await, ["simple", "smoky", "test"]);
+
+    info!("Test Case::resource::/admin/tags/bulk (POST) -> Recipes that have the tag are not modified");
+    let report = retag(json!({"action": "add", "tag": "smoky", "filter": {"tags": "test"}})).await;
+    assert_eq!((report.matched, report.affected), (1, 0));
+
+    info!("Test Case::resource::/admin/tags/bulk (POST) -> Replace a tag");
+    retag(json!({
+        "action": "replace",
+        "tag": "smoky",
+        "replacement": "smoked",
+        "filter": {"tags": "smoky"}
+    }))
+    .await;
+    assert_eq!(recipe_tags().await, ["simple", "smoked", "test"]);
+
+    info!("Test Case::resource::/admin/tags/bulk (POST) -> Remove a tag");
+    retag(json!({"action": "remove", "tag": "smoked", "filter": {"tags": "test"}})).await;
+    assert_eq!(recipe_tags().await, ["simple", "test"]);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn usage_analytics_hide_rare_searches() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+
+    info!("Test Case::resource::/admin/analytics (GET) -> Attempt to read the analytics with no admin privileges");
+    let response = admin_request(&test_app, reqwest::Method::GET, "analytics", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    test_app.grant_admin_access().await;
+
+    info!("Test Case::resource::/admin/analytics (GET) -> Invalid period");
+    let response = admin_request(&test_app, reqwest::Method::GET, "analytics?days=0", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    // A registry of its own keeps the test apart from the requests of the rest of the tests.
+    let registry = UsageAnalytics::new();
+    registry.configure(true, DEFAULT_K_ANONYMITY);
+    for client in 0..DEFAULT_K_ANONYMITY {
+        registry.record_search("recipe", "Margarita", Some(&format!("10.0.0.{client}")));
+        registry.record_hit("GET /recipe");
+    }
+    // A single client repeating a search doesn't make it popular.
+    for _ in 0..DEFAULT_K_ANONYMITY * 2 {
+        registry.record_search("recipe", "my own secret cocktail", Some("10.0.1.1"));
+    }
+    registry.record_hit("GET /recipe/{id}");
+    for _ in 0..2 {
+        flush_usage_analytics(
+            &test_app.db_pool,
+            &registry,
+            TimeDelta::days(90),
+            Utc::now(),
+        )
+        .await
+        .expect("Failed to store the analytics");
+    }
+
+    info!("Test Case::resource::/admin/analytics (GET) -> Rare searches are not reported");
+    let response = admin_request(&test_app, reqwest::Method::GET, "analytics?days=7", None).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let report: UsageReport = response.json().await.expect("Failed to parse the report");
+    assert_eq!(report.k_anonymity, DEFAULT_K_ANONYMITY);
+    assert_eq!(report.daily.len(), 1);
+    assert_eq!(report.daily[0].hits, u64::from(DEFAULT_K_ANONYMITY) + 1);
+    assert_eq!(report.endpoints[0].endpoint, "GET /recipe");
+    assert_eq!(report.endpoints[0].hits, u64::from(DEFAULT_K_ANONYMITY));
+    assert_eq!(report.top_searches.len(), 1);
+    assert_eq!(report.top_searches[0].term, "margarita");
+}
@@ -4,16 +4,22 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::{
-    fixtures::{self, AuthorFixture},
+use actix_web::http::StatusCode;
+use lacoctelera::domain::{Author, AuthorBuilder, IdScheme, SocialProfile};
+use lacoctelera::routes::{author::delete::OwnedRecipesSummary, expand::ExpandedAuthor};
+use lacoctelera::testing::{
+    fixtures::{self, AuthorFixture, FixtureSeeder},
     helpers::{
-        spawn_app, ApiTesterBuilder, Credentials, Resource, TestApp, TestBuilder, TestObject,
+        spawn_app, spawn_app_with, ApiTesterBuilder, Credentials, Resource, TestApp, TestBuilder,
+        TestObject,
     },
 };
-use actix_web::http::StatusCode;
-use lacoctelera::domain::{Author, AuthorBuilder, SocialProfile};
 use pretty_assertions::assert_eq;
-use reqwest::Response;
+use reqwest::{
+    header::{ETAG, IF_MATCH},
+    Response,
+};
+use secrecy::ExposeSecret;
 use sqlx::MySqlPool;
 use std::iter::zip;
 use tracing::info;
@@ -51,6 +57,71 @@ impl ApiTesterBuilder for AuthorApiBuilder {
     }
 }
 
+impl AuthorApiTester {
+    /// DELETE request that includes extra query params besides the API token.
+    pub async fn delete_with_params(&self, id: &str, params: &str) -> Response {
+        let url = format!(
+            "{}/{}/{id}?api_key={}&{params}",
+            &self.test_app.address,
+            self.resource,
+            self.test_app.api_token.api_key.expose_secret()
+        );
+
+        self.test_app
+            .api_client
+            .delete(url)
+            .send()
+            .await
+            .expect("Failed to execute DELETE for the resource /author.")
+    }
+
+    /// DELETE request guarded by an `If-Match` header.
+    pub async fn delete_if_match(&self, id: &str, etag: &str) -> Response {
+        let url = format!(
+            "{}/{}/{id}?api_key={}",
+            &self.test_app.address,
+            self.resource,
+            self.test_app.api_token.api_key.expose_secret()
+        );
+
+        self.test_app
+            .api_client
+            .delete(url)
+            .header(IF_MATCH, etag)
+            .send()
+            .await
+            .expect("Failed to execute DELETE for the resource /author.")
+    }
+
+    /// Request to the `/author/{id}/social-profiles` sub-resource.
+    pub async fn social_profile_request(
+        &self,
+        method: reqwest::Method,
+        id: &str,
+        provider: Option<&str>,
+        body: Option<&SocialProfile>,
+    ) -> Response {
+        let provider = provider.map(|p| format!("/{p}")).unwrap_or_default();
+        let url = format!(
+            "{}/{}/{id}/social-profiles{provider}?api_key={}",
+            &self.test_app.address,
+            self.resource,
+            self.test_app.api_token.api_key.expose_secret()
+        );
+
+        let request = self.test_app.api_client.request(method, url);
+        let request = match body {
+            Some(body) => request.json(body),
+            None => request,
+        };
+
+        request
+            .send()
+            .await
+            .expect("Failed to execute the request for the resource /author/{id}/social-profiles.")
+    }
+}
+
 impl AuthorApiTester {
     pub async fn new(credentials: Credentials) -> Self {
         let mut app = AuthorApiTester {
@@ -158,15 +229,13 @@ async fn delete_with_credentials() -> Result<(), String> {
     info!("Test Case::resource::/author (DELETE) -> Attempt to delete using a wrong author ID");
     let id = rand::random::<i32>().to_string();
     let response = test.delete(&id).await;
-    assert_eq!(
-        response.status().as_u16(),
-        StatusCode::INTERNAL_SERVER_ERROR
-    );
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
     info!("Test Case::resource::/author (DELETE) -> Attempt to delete a non existing author");
     let id = Uuid::now_v7().to_string();
-
-    // This might change in the future.
-    assert_eq!(test.delete(&id).await.status().as_u16(), StatusCode::OK);
+    assert_eq!(
+        test.delete(&id).await.status().as_u16(),
+        StatusCode::NOT_FOUND
+    );
 
     info!("Test Case::resource::/author (DELETE) -> Attempt to delete an existing author");
     let mut social_profile_fixture = fixtures::SocialProfileFixture::default();
@@ -195,6 +264,184 @@ async fn delete_with_credentials() -> Result<(), String> {
     Ok(())
 }
 
+#[actix_web::test]
+async fn delete_with_preconditions() -> Result<(), String> {
+    let mut test_builder = AuthorApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let mut author_fixture = AuthorFixture::default();
+    author_fixture.load()?;
+    author_fixture.seed(test.db_pool(), false).await?;
+    let author_id = author_fixture.valid_fixtures[0]
+        .id()
+        .expect("Failed to unwrap fixture author's ID")
+        .to_string();
+
+    let etag_of = |response: &Response| {
+        response
+            .headers()
+            .get(ETAG)
+            .expect("The response includes no ETag")
+            .to_str()
+            .expect("Invalid ETag")
+            .to_owned()
+    };
+
+    let response = test.get(&format!("/{author_id}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let stale_etag = etag_of(&response);
+
+    info!(
+        "Test Case::resource::/author (DELETE) -> Attempt to delete an author using a stale ETag"
+    );
+    let response = test
+        .patch(
+            &author_id,
+            &AuthorBuilder::default()
+                .set_description("A brand new description")
+                .build()
+                .expect("Failed to build an author descriptor"),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let response = test.delete_if_match(&author_id, &stale_etag).await;
+    assert_eq!(response.status().as_u16(), StatusCode::PRECONDITION_FAILED);
+
+    info!("Test Case::resource::/author (DELETE) -> Delete an author using its current ETag");
+    let response = test.get(&format!("/{author_id}")).await;
+    let etag = etag_of(&response);
+    assert_ne!(etag, stale_etag);
+    let response = test.delete_if_match(&author_id, &etag).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn delete_author_owning_recipes() -> Result<(), String> {
+    let mut test_builder = AuthorApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixtures = FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixtures
+        .recipe
+        .expect("Failed to seed the recipes")
+        .valid_fixtures[0];
+    let owner_id = recipe
+        .owner()
+        .expect("Failed to unwrap recipe's owner")
+        .to_string();
+    let recipe_id = recipe
+        .id()
+        .expect("Failed to unwrap recipe's ID")
+        .to_string();
+
+    info!("Test Case::resource::/author (DELETE) -> Attempt to delete an author that owns recipes");
+    let response = test.delete(&owner_id).await;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+    let summary: OwnedRecipesSummary = response
+        .json()
+        .await
+        .expect("Failed to parse the summary of owned recipes");
+    assert_eq!(summary.recipe_count, 1);
+    assert_eq!(summary.recipes[0].id, recipe_id);
+
+    info!("Test Case::resource::/author (DELETE) -> Attempt to use contradictory options");
+    let response = test
+        .delete_with_params(&owner_id, &format!("reassign_to={owner_id}"))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    let response = test
+        .delete_with_params(
+            &owner_id,
+            &format!("reassign_to={}&cascade=true", Uuid::now_v7()),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/author (DELETE) -> Attempt to reassign recipes to a non existing author");
+    let response = test
+        .delete_with_params(&owner_id, &format!("reassign_to={}", Uuid::now_v7()))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/author (DELETE) -> Reassign recipes to another author");
+    let target_id = Uuid::now_v7().to_string();
+    sqlx::query(
+        "INSERT INTO Author (id, name, surname, email) VALUES (?, 'Jane', 'Doe', 'jane@mail.com')",
+    )
+    .bind(&target_id)
+    .execute(test.db_pool())
+    .await
+    .map_err(|e| e.to_string())?;
+    let response = test
+        .delete_with_params(&owner_id, &format!("reassign_to={target_id}"))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let owner: String = sqlx::query_scalar("SELECT owner FROM Cocktail WHERE id = ?")
+        .bind(&recipe_id)
+        .fetch_one(test.db_pool())
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(owner, target_id);
+
+    info!("Test Case::resource::/author (DELETE) -> Delete an author along with its recipes");
+    let response = test.delete_with_params(&target_id, "cascade=true").await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let recipes: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Cocktail WHERE id = ?")
+        .bind(&recipe_id)
+        .fetch_one(test.db_pool())
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(recipes, 0);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn get_expanded_recipes() -> Result<(), String> {
+    let mut test_builder = AuthorApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixtures = FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixtures
+        .recipe
+        .expect("Failed to seed the recipes")
+        .valid_fixtures[0];
+    let owner_id = recipe
+        .owner()
+        .expect("Failed to unwrap recipe's owner")
+        .to_string();
+
+    info!("Test Case::resource::/author (GET) -> Embed the recipes of an author");
+    let response = test.get(&format!("/{owner_id}?expand=recipes")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let author: ExpandedAuthor = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse the author: {e}"))?;
+    let recipes = author
+        .embedded
+        .recipes
+        .expect("The recipes were not embedded");
+    assert!(recipes.iter().all(|r| r.owner() == recipe.owner()));
+
+    info!("Test Case::resource::/author (GET) -> Authors can't expand their ingredients");
+    let response = test.get(&format!("/{owner_id}?expand=ingredients")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
 #[actix_web::test]
 async fn get_no_credentials() -> Result<(), String> {
     info!("Test Case::resource::/author (GET) -> Request an author whose ID doesn't exist");
@@ -208,6 +455,12 @@ async fn get_no_credentials() -> Result<(), String> {
         StatusCode::NOT_FOUND
     );
 
+    info!("Test Case::resource::/author (GET) -> Request an author using a malformed ID");
+    assert_eq!(
+        test.get("/1234").await.status().as_u16(),
+        StatusCode::BAD_REQUEST
+    );
+
     info!("Test Case::resource::/author (GET) -> Request an author whose ID does exist");
     // Let's get two author instances.
     let mut social_profile_fixture = fixtures::SocialProfileFixture::default();
@@ -293,6 +546,12 @@ async fn get_with_credentials() -> Result<(), String> {
         StatusCode::NOT_FOUND
     );
 
+    info!("Test Case::resource::/author (GET) -> Request an author using a malformed ID");
+    assert_eq!(
+        test.get("/1234").await.status().as_u16(),
+        StatusCode::BAD_REQUEST
+    );
+
     info!("Test Case::resource::/author (GET) -> Request an author whose ID does exist");
     // Let's get two author instances.
     let mut social_profile_fixture = fixtures::SocialProfileFixture::default();
@@ -391,6 +650,12 @@ async fn head() -> Result<(), String> {
         StatusCode::NOT_FOUND
     );
 
+    info!("Test Case::resource::/author (HEAD) -> Attempt to request a malformed ID");
+    assert_eq!(
+        test.head("not-an-id").await.status().as_u16(),
+        StatusCode::BAD_REQUEST
+    );
+
     info!("Test Case::resource::/author (HEAD) -> Attempt to request an existing client");
     let with_social_media = false;
     let mut author_fixture = AuthorFixture::default();
@@ -400,18 +665,33 @@ async fn head() -> Result<(), String> {
         .await?;
     let author_shareable = &author_fixture.valid_fixtures[0];
 
-    assert_eq!(
-        test.head(
+    let response = test
+        .head(
             &author_shareable
                 .id()
                 .expect("Failed to extract ID")
-                .to_string()
+                .to_string(),
         )
-        .await
-        .status()
-        .as_u16(),
-        StatusCode::OK
-    );
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert_eq!(response.headers().get("x-recipe-count").unwrap(), &"0");
+    assert!(response.headers().get("last-modified").is_some());
+
+    info!("Test Case::resource::/author (HEAD) -> Request an author that owns recipes");
+    let fixtures = FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let owner_id = fixtures
+        .recipe
+        .expect("Failed to seed the recipes")
+        .valid_fixtures[0]
+        .owner()
+        .expect("Failed to unwrap recipe's owner")
+        .to_string();
+    let response = test.head(&owner_id).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert_eq!(response.headers().get("x-recipe-count").unwrap(), &"1");
 
     Ok(())
 }
@@ -512,10 +792,7 @@ async fn post_with_credentials() -> Result<(), String> {
     .expect("Failed to deserialize payload");
     let received_author = serde_json::from_str::<Author>(
         &test
-            .get(&format!(
-                "/{}",
-                payload.id().expect("Failed to extract ID").to_string()
-            ))
+            .get(&format!("/{}", payload.id().expect("Failed to extract ID")))
             .await
             .text()
             .await
@@ -557,10 +834,7 @@ async fn post_with_credentials() -> Result<(), String> {
     .expect("Failed to deserialize payload");
     let author = serde_json::from_str::<Author>(
         &test
-            .get(&format!(
-                "/{}",
-                payload.id().expect("Failed to extract ID").to_string()
-            ))
+            .get(&format!("/{}", payload.id().expect("Failed to extract ID")))
             .await
             .text()
             .await
@@ -573,6 +847,74 @@ async fn post_with_credentials() -> Result<(), String> {
     assert_eq!(author_base.email(), author.email());
     assert_eq!(author_base.shareable(), author.shareable());
 
+    info!(
+        "Test Case::resource::/author (POST) -> Add a new author entry using a client-provided ID"
+    );
+    let existing_id = payload.id().expect("Failed to extract ID");
+    for id in [existing_id.clone(), Uuid::now_v7().to_string()] {
+        let author_base = AuthorBuilder::default()
+            .set_id(&id)
+            .set_email("squatter@mail.com")
+            .build()
+            .expect("Failed to build test author");
+        let response = test.post(&author_base).await;
+        assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    }
+    let response = test.get(&format!("/{existing_id}")).await;
+    let author = serde_json::from_str::<Author>(&response.text().await.unwrap())
+        .expect("Failed to parse the received author");
+    assert_eq!(author_base.email(), author.email());
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn post_with_registered_email() -> Result<(), String> {
+    let mut test_builder = AuthorApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let mut ids = Vec::new();
+    for (email, shareable) in [("private@mail.com", false), ("public@mail.com", true)] {
+        let author = AuthorBuilder::default()
+            .set_email(email)
+            .set_shareable(shareable)
+            .build()
+            .expect("Failed to build test author");
+        let response = test.post(&author).await;
+        assert_eq!(response.status().as_u16(), StatusCode::OK);
+        let payload = serde_json::from_str::<Author>(&response.text().await.unwrap())
+            .expect("Failed to deserialize payload");
+        ids.push(payload.id().expect("Failed to extract ID"));
+    }
+
+    info!("Test Case::resource::/author (POST) -> Register the email of a private profile");
+    let author = AuthorBuilder::default()
+        .set_email("PRIVATE@mail.com")
+        .build()
+        .expect("Failed to build test author");
+    let response = test.post(&author).await;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+    // The ID of a private profile is never disclosed.
+    assert!(response.text().await.unwrap().is_empty());
+
+    info!("Test Case::resource::/author (POST) -> Register the email of a shareable profile");
+    let author = AuthorBuilder::default()
+        .set_email("public@mail.com")
+        .build()
+        .expect("Failed to build test author");
+    let response = test.post(&author).await;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+    let payload = serde_json::from_str::<Author>(&response.text().await.unwrap())
+        .expect("Failed to deserialize payload");
+    assert_eq!(payload.id().as_ref(), Some(&ids[1]));
+
+    info!("Test Case::resource::/author (PATCH) -> Take the email of another author");
+    let response = test
+        .patch(&ids[0], &serde_json::json!({"email": "public@mail.com"}))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+
     Ok(())
 }
 
@@ -657,6 +999,16 @@ async fn patch_with_credentials() -> Result<(), String> {
         .build()
         .expect("Failed to build an author descriptor");
 
+    info!("Test Case::resource::/author (PATCH) -> Modify a non existing author entry");
+    let response = test
+        .patch(&Uuid::now_v7().to_string(), &patched_author)
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/author (PATCH) -> Modify an author entry using a malformed ID");
+    let response = test.patch("1234", &patched_author).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
     let response = test.patch(&author.id().unwrap(), &patched_author).await;
 
     assert_eq!(response.status().as_u16(), StatusCode::OK);
@@ -712,7 +1064,7 @@ async fn search_no_credentials() -> Result<(), String> {
             .expect("Failed to retrieve response's payload"),
     )
     .expect("Failed to deserialize the payload");
-    assert!(payload.len() == 0);
+    assert!(payload.is_empty());
 
     info!("Test Case::resource::/author (GET) -> Search existing authors");
     let mut author_fixture = AuthorFixture::default();
@@ -786,7 +1138,7 @@ async fn search_with_credentials() -> Result<(), String> {
             .expect("Failed to retrieve response's payload"),
     )
     .expect("Failed to deserialize the payload");
-    assert!(payload.len() == 0);
+    assert!(payload.is_empty());
 
     info!("Test Case::resource::/author (GET) -> Search existing authors");
     let mut author_fixture = AuthorFixture::default();
@@ -841,3 +1193,172 @@ async fn search_with_credentials() -> Result<(), String> {
 
     Ok(())
 }
+
+#[actix_web::test]
+async fn social_profiles_with_credentials() -> Result<(), String> {
+    let mut test_builder = AuthorApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let mut social_profile_fixture = fixtures::SocialProfileFixture::default();
+    social_profile_fixture.load()?;
+    social_profile_fixture.seed(test.db_pool()).await?;
+
+    let mut author_fixture = AuthorFixture::default();
+    author_fixture.load()?;
+    author_fixture.seed(test.db_pool(), true).await?;
+    let author_id = author_fixture.valid_fixtures[0]
+        .id()
+        .expect("Failed to unwrap fixture author's ID");
+
+    let instagram = SocialProfile {
+        provider_name: "Instagram".into(),
+        website: "https://instagram.com/valid_author".into(),
+    };
+
+    let profiles = |author: Author| -> Vec<SocialProfile> {
+        author.social_profiles().unwrap_or_default().to_vec()
+    };
+    let get_author = || async {
+        serde_json::from_str::<Author>(
+            &test
+                .get(&format!("/{author_id}"))
+                .await
+                .text()
+                .await
+                .expect("Failed to read response's payload"),
+        )
+        .expect("Failed to deserialize author")
+    };
+
+    info!("Test Case::resource::/author/{{id}}/social-profiles (POST) -> Add a new social profile");
+    let response = test
+        .social_profile_request(reqwest::Method::POST, &author_id, None, Some(&instagram))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert!(profiles(get_author().await)
+        .iter()
+        .any(|p| p.provider_name == "Instagram" && p.website.ends_with("valid_author")));
+
+    info!("Test Case::resource::/author/{{id}}/social-profiles (POST) -> Add an existing social profile");
+    let response = test
+        .social_profile_request(reqwest::Method::POST, &author_id, None, Some(&instagram))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+
+    info!("Test Case::resource::/author/{{id}}/social-profiles (POST) -> Add an unsupported social network");
+    let unknown = SocialProfile {
+        provider_name: "Unknown".into(),
+        website: "valid_author".into(),
+    };
+    let response = test
+        .social_profile_request(reqwest::Method::POST, &author_id, None, Some(&unknown))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/author/{{id}}/social-profiles (POST) -> Add a profile to a non existing author");
+    let response = test
+        .social_profile_request(
+            reqwest::Method::POST,
+            &Uuid::now_v7().to_string(),
+            None,
+            Some(&instagram),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/author/{{id}}/social-profiles (PATCH) -> Modify an existing social profile");
+    let modified = SocialProfile {
+        provider_name: "Instagram".into(),
+        website: "new_account".into(),
+    };
+    let response = test
+        .social_profile_request(
+            reqwest::Method::PATCH,
+            &author_id,
+            Some("Instagram"),
+            Some(&modified),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert!(profiles(get_author().await)
+        .iter()
+        .any(|p| p.provider_name == "Instagram" && p.website.ends_with("new_account")));
+
+    info!("Test Case::resource::/author/{{id}}/social-profiles (PATCH) -> Provider mismatch");
+    let response = test
+        .social_profile_request(
+            reqwest::Method::PATCH,
+            &author_id,
+            Some("X"),
+            Some(&modified),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/author/{{id}}/social-profiles (DELETE) -> Delete an existing social profile");
+    let response = test
+        .social_profile_request(reqwest::Method::DELETE, &author_id, Some("Instagram"), None)
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert!(!profiles(get_author().await)
+        .iter()
+        .any(|p| p.provider_name == "Instagram"));
+
+    info!("Test Case::resource::/author/{{id}}/social-profiles (DELETE) -> Delete a non existing social profile");
+    let response = test
+        .social_profile_request(reqwest::Method::DELETE, &author_id, Some("Instagram"), None)
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    let response = test
+        .social_profile_request(
+            reqwest::Method::PATCH,
+            &author_id,
+            Some("Instagram"),
+            Some(&modified),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/author (PATCH) -> Replace the whole list of social profiles");
+    let patched_author = AuthorBuilder::default()
+        .set_social_profiles(std::slice::from_ref(&instagram))
+        .build()
+        .expect("Failed to build an author descriptor");
+    let response = test.patch(&author_id, &patched_author).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let received_profiles = profiles(get_author().await);
+    assert_eq!(received_profiles.len(), 1);
+    assert_eq!(received_profiles[0].provider_name, "Instagram");
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn post_with_sequential_ids() -> Result<(), String> {
+    let mut test_app = spawn_app_with(|c| c.application.id_scheme = IdScheme::Sequential).await;
+    test_app.generate_access_token().await;
+
+    info!("Test Case::resource::/author (POST) -> New authors get predictable IDs");
+    for expected_id in [
+        "00000000-0000-0000-0000-000000000001",
+        "00000000-0000-0000-0000-000000000002",
+    ] {
+        let response = test_app
+            .post_test(
+                Resource::Author,
+                Credentials::WithCredentials,
+                &serde_json::json!({"name": "Jane"}),
+            )
+            .await;
+        assert_eq!(response.status().as_u16(), StatusCode::OK);
+        let body = response
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| e.to_string())?;
+        assert_eq!(body["id"], expected_id);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use lacoctelera::{
+    client::{ApiClient, ClientError},
+    domain::RecipeQuery,
+    routes::ingredient::{FormData, QueryData},
+    testing::helpers::spawn_app,
+};
+use pretty_assertions::assert_eq;
+use tracing::info;
+use uuid::Uuid;
+
+#[actix_web::test]
+async fn client_covers_the_public_resources() {
+    let mut test_app = spawn_app().await;
+    let client = ApiClient::new(&test_app.address);
+
+    info!("Test Case::client -> Register and search an ingredient");
+    client
+        .add_ingredient(&FormData {
+            name: "Client Vodka".into(),
+            category: "spirit".into(),
+            desc: None,
+        })
+        .await
+        .expect("Failed to register the ingredient");
+    let page = client
+        .search_ingredients(&QueryData {
+            name: "client vodka".into(),
+            limit: None,
+            offset: None,
+        })
+        .await
+        .expect("Failed to search the ingredient");
+    assert_eq!(page.total, 1);
+    assert_eq!(page.items[0].name(), "Client Vodka");
+
+    info!("Test Case::client -> Missing resources");
+    let missing = client
+        .get_ingredient(&Uuid::now_v7())
+        .await
+        .expect("Failed to retrieve the ingredient");
+    assert!(missing.is_none());
+    let query = RecipeQuery {
+        name: Some("no recipe is named like this".into()),
+        tags: None,
+        rating: None,
+        category: None,
+        equipment_excludes: None,
+        max_prep_time: None,
+        state: None,
+        q: None,
+        sort: None,
+        limit: None,
+        offset: None,
+    };
+    let page = client
+        .search_recipes(&query)
+        .await
+        .expect("Failed to search recipes");
+    assert!(page.items.is_empty());
+
+    info!("Test Case::client -> Restricted resources need an API key");
+    let result = client.delete_recipe(&Uuid::now_v7()).await;
+    assert!(matches!(result, Err(ClientError::MissingApiKey)));
+
+    test_app.generate_access_token().await;
+    let client = client.with_api_key(test_app.api_token.api_key.clone());
+    let deleted = client
+        .delete_recipe(&Uuid::now_v7())
+        .await
+        .expect("Failed to delete the recipe");
+    assert!(!deleted);
+}
@@ -0,0 +1,84 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use lacoctelera::testing::{
+    contract::ContractChecker,
+    fixtures::FixtureSeeder,
+    helpers::{spawn_app, Credentials, Resource},
+};
+use reqwest::Method;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+#[actix_web::test]
+async fn responses_fulfill_the_openapi_spec() -> Result<(), String> {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+    let api_key = format!("api_key={}", test_app.api_token.api_key.expose_secret());
+
+    let fixtures = FixtureSeeder::new(&test_app.db_pool)
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixtures
+        .recipe
+        .expect("Failed to seed the recipes")
+        .valid_fixtures[0];
+    let recipe_id = recipe.id().expect("Failed to unwrap recipe's ID");
+    let author_id = recipe.owner().expect("Failed to unwrap recipe's owner");
+    let ingredient_id = recipe.ingredients()[0].ingredient_id;
+    let unknown_id = Uuid::now_v7();
+
+    // Make sure that the credentials are valid before checking the restricted endpoints.
+    let response = test_app
+        .get_test(Resource::TokenKeys, Credentials::WithCredentials, "")
+        .await;
+    assert!(response.status().is_success());
+
+    let checker = ContractChecker::default();
+    let cases = [
+        (Method::GET, "/echo".to_string(), None),
+        (Method::GET, format!("/recipe/{recipe_id}"), None),
+        (Method::GET, format!("/recipe/{unknown_id}"), None),
+        (Method::GET, "/recipe/not-an-id".to_string(), None),
+        (Method::HEAD, format!("/recipe/{recipe_id}"), None),
+        (Method::HEAD, format!("/recipe/{unknown_id}"), None),
+        (Method::GET, format!("/recipe?name={}", recipe.name()), None),
+        (Method::GET, "/recipe?name=nothingmatches".to_string(), None),
+        (
+            Method::POST,
+            "/recipe/classify".to_string(),
+            Some(json!({"steps": ["Shake."], "equipment": ["shaker"]})),
+        ),
+        (Method::GET, format!("/author/{author_id}?{api_key}"), None),
+        (Method::GET, format!("/author/{unknown_id}?{api_key}"), None),
+        (Method::HEAD, format!("/author/{author_id}"), None),
+        (Method::GET, format!("/ingredient/{ingredient_id}"), None),
+        (Method::GET, format!("/ingredient/{unknown_id}"), None),
+        (Method::GET, format!("/token/keys?{api_key}"), None),
+    ];
+
+    let mut violations = Vec::new();
+    for (method, path, body) in cases {
+        info!("Test Case::contract::{method} {path}");
+        if let Err(mut errors) = test_app
+            .contract_test(&checker, method, &path, body.as_ref())
+            .await
+        {
+            violations.append(&mut errors);
+        }
+    }
+
+    assert!(
+        violations.is_empty(),
+        "The responses don't match the OpenAPI spec:\n{}",
+        violations.join("\n")
+    );
+
+    Ok(())
+}
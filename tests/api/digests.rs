@@ -0,0 +1,223 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use actix_web::{http::StatusCode, web::Data};
+use chrono::{TimeDelta, Utc};
+use lacoctelera::{
+    domain::IdScheme,
+    jobs::send_due_digests,
+    routes::me::digests::{Digest, DigestFrequency, MAX_DIGESTS_PER_CLIENT},
+    seeding::seed_demo_data,
+    testing::helpers::{spawn_app, Credentials, Resource, TestApp},
+    utils::mailing::EmailSender,
+};
+use pretty_assertions::assert_eq;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+const CLIENT_EMAIL: &str = "jane_doe@mail.com";
+
+async fn get_digests(test_app: &TestApp) -> Vec<Digest> {
+    let response = test_app
+        .get_test(Resource::Digests, Credentials::WithCredentials, "")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    response
+        .json::<Vec<Digest>>()
+        .await
+        .expect("Failed to parse the list of digests")
+}
+
+#[actix_web::test]
+async fn digests_management() {
+    let mut test_app = spawn_app().await;
+
+    info!("Test Case::resource::/me/digests (GET) -> Access is restricted");
+    let response = test_app
+        .get_test(Resource::Digests, Credentials::NoCredentials, "")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    test_app.generate_access_token().await;
+    assert!(get_digests(&test_app).await.is_empty());
+
+    info!("Test Case::resource::/me/digests (POST) -> Invalid tags are rejected");
+    let response = test_app
+        .post_test(
+            Resource::Digests,
+            Credentials::WithCredentials,
+            &json!({"frequency": "daily", "tags": ["t@g"]}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/me/digests (POST) -> Subscribe to a digest");
+    let response = test_app
+        .post_test(
+            Resource::Digests,
+            Credentials::WithCredentials,
+            &json!({"frequency": "weekly", "tags": ["Rum", "rum"], "categories": ["medium"]}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CREATED);
+    let digest = response
+        .json::<Digest>()
+        .await
+        .expect("Failed to parse the new digest");
+    assert_eq!(digest.frequency, DigestFrequency::Weekly);
+    assert_eq!(digest.tags, vec!["rum".to_owned()]);
+    assert!(digest.last_sent_at.is_none());
+    assert_eq!(get_digests(&test_app).await.len(), 1);
+
+    info!("Test Case::resource::/me/digests (POST) -> The amount of digests is limited");
+    for _ in 1..MAX_DIGESTS_PER_CLIENT {
+        let response = test_app
+            .post_test(
+                Resource::Digests,
+                Credentials::WithCredentials,
+                &json!({"frequency": "daily"}),
+            )
+            .await;
+        assert_eq!(response.status().as_u16(), StatusCode::CREATED);
+    }
+    let response = test_app
+        .post_test(
+            Resource::Digests,
+            Credentials::WithCredentials,
+            &json!({"frequency": "daily"}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+
+    info!("Test Case::resource::/me/digests (DELETE) -> Unsubscribe from a digest");
+    let response = test_app
+        .delete_test(Resource::Digests, Credentials::WithCredentials, &digest.id)
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    let response = test_app
+        .delete_test(Resource::Digests, Credentials::WithCredentials, &digest.id)
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        get_digests(&test_app).await.len(),
+        MAX_DIGESTS_PER_CLIENT - 1
+    );
+}
+
+#[actix_web::test]
+async fn digests_list_new_recipes() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+    let mail_client: Data<dyn EmailSender> =
+        Data::from(test_app.emails.clone() as Arc<dyn EmailSender>);
+
+    let response = test_app
+        .post_test(
+            Resource::Digests,
+            Credentials::WithCredentials,
+            &json!({"frequency": "daily", "tags": ["rum"], "categories": ["medium"]}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CREATED);
+    // Timestamps of the DB have a resolution of one second.
+    actix_web::rt::time::sleep(std::time::Duration::from_millis(1100)).await;
+    seed_demo_data(&test_app.db_pool, IdScheme::UuidV7.generator().as_ref())
+        .await
+        .expect("Failed to seed the demo dataset");
+
+    info!("Test Case::job::digests -> Digests are not sent before their period elapses");
+    let sent = send_due_digests(
+        &test_app.db_pool,
+        mail_client.clone(),
+        "https://lacoctelera.test",
+        Utc::now(),
+    )
+    .await
+    .expect("Failed to run the digests job");
+    assert_eq!(sent, 0);
+
+    info!("Test Case::job::digests -> Due digests list the matching recipes");
+    let tomorrow = Utc::now() + TimeDelta::days(1) + TimeDelta::minutes(1);
+    let sent = send_due_digests(
+        &test_app.db_pool,
+        mail_client.clone(),
+        "https://lacoctelera.test",
+        tomorrow,
+    )
+    .await
+    .expect("Failed to run the digests job");
+    assert_eq!(sent, 1);
+    let email = test_app
+        .emails
+        .last_to(CLIENT_EMAIL)
+        .expect("The digest was not sent");
+    assert!(email.text_body.contains("Mojito"));
+    assert!(!email.text_body.contains("Daiquiri"));
+    assert!(email.text_body.contains("https://lacoctelera.test/recipe/"));
+    assert!(get_digests(&test_app).await[0].last_sent_at.is_some());
+
+    info!("Test Case::job::digests -> Digests are sent once per period");
+    let sent = send_due_digests(
+        &test_app.db_pool,
+        mail_client.clone(),
+        "https://lacoctelera.test",
+        tomorrow,
+    )
+    .await
+    .expect("Failed to run the digests job");
+    assert_eq!(sent, 0);
+    assert_eq!(test_app.emails.sent_to(CLIENT_EMAIL).len(), 1);
+
+    info!("Test Case::job::digests -> Recipes are listed once they get published");
+    // A recipe registered before the previous digest, but published after it.
+    let mojito: String = sqlx::query_scalar("SELECT id FROM Cocktail WHERE name = 'Mojito'")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to find the recipe");
+    sqlx::query(
+        "UPDATE Cocktail SET state = 'submitted', publication_date = NULL, creation_date = ? \
+        WHERE id = ?",
+    )
+    .bind(Utc::now() - TimeDelta::days(2))
+    .bind(&mojito)
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to turn the recipe into a submission");
+    sqlx::query("UPDATE DigestSubscription SET last_sent_at = ?")
+        .bind(Utc::now() - TimeDelta::days(1))
+        .execute(&test_app.db_pool)
+        .await
+        .expect("Failed to move the previous digest");
+    test_app.grant_admin_access().await;
+    let response = test_app
+        .api_client
+        .post(format!(
+            "{}/recipe/{mojito}/publish?api_key={}",
+            test_app.address,
+            test_app.api_token.api_key.expose_secret()
+        ))
+        .send()
+        .await
+        .expect("Failed to publish the recipe");
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    let sent = send_due_digests(
+        &test_app.db_pool,
+        mail_client,
+        "https://lacoctelera.test",
+        Utc::now() + TimeDelta::minutes(1),
+    )
+    .await
+    .expect("Failed to run the digests job");
+    assert_eq!(sent, 1);
+    let email = test_app
+        .emails
+        .last_to(CLIENT_EMAIL)
+        .expect("The digest was not sent");
+    assert!(email.text_body.contains("Mojito"));
+}
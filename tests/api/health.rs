@@ -0,0 +1,241 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use actix_web::http::StatusCode;
+use chrono::{Local, SecondsFormat, TimeDelta, Utc};
+use lacoctelera::{
+    jobs::record_health_history,
+    routes::{
+        health::{HealthResponse, ServerStatus},
+        status::{SnapshotStatus, StatusHistory},
+    },
+    testing::helpers::{spawn_app, TestApp},
+    utils::http::{MaintenanceWindow, MAINTENANCE_HEADER},
+};
+use pretty_assertions::assert_eq;
+use secrecy::ExposeSecret;
+use serde_json::{json, Value};
+use tracing::info;
+
+#[actix_web::test]
+async fn health_report_includes_live_metrics() {
+    let mut test_app = spawn_app().await;
+
+    info!("Test Case::resource::/health (GET) -> Access is restricted");
+    let response = test_app
+        .api_client
+        .get(format!("{}/health?api_key=wrong:key", test_app.address))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status().as_u16(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["cache-control"], "no-cache");
+
+    test_app.generate_access_token().await;
+    let url = format!(
+        "{}/health?api_key={}",
+        test_app.address,
+        test_app.api_token.api_key.expose_secret()
+    );
+
+    info!("Test Case::resource::/health (GET) -> Report of a healthy server");
+    // Warm up the metrics with a request to another resource.
+    test_app
+        .api_client
+        .get(format!("{}/echo", test_app.address))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    let response = test_app
+        .api_client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let report: HealthResponse = response.json().await.expect("Failed to parse the report");
+    assert!(matches!(report.server_status, ServerStatus::Ok));
+    assert!(report.api_expire_time.is_some());
+    assert_eq!(report.mail_outbox, Some(0));
+    assert!(report.metrics.resources["echo"].requests > 0);
+    assert!(report.metrics.requests.request_rate > 0.0);
+    assert!(report.metrics.db_latency_p95_ms.is_some());
+}
+
+#[actix_web::test]
+async fn health_history_is_public() {
+    let test_app = spawn_app().await;
+    let url = format!("{}/status/history", test_app.address);
+
+    info!("Test Case::resource::/status/history (GET) -> Invalid window");
+    let response = test_app
+        .api_client
+        .get(format!("{url}?window=1y"))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/status/history (GET) -> Empty history");
+    let response = test_app
+        .api_client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let history: StatusHistory = response.json().await.expect("Failed to parse the history");
+    assert!(history.snapshots.is_empty());
+    assert_eq!(history.availability, None);
+
+    info!("Test Case::resource::/status/history (GET) -> Snapshots within the window");
+    let mut pending = Vec::new();
+    let now = Utc::now();
+    for taken_at in [now - TimeDelta::days(2), now - TimeDelta::hours(1), now] {
+        record_health_history(
+            &test_app.db_pool,
+            &mut pending,
+            TimeDelta::days(30),
+            taken_at,
+        )
+        .await
+        .expect("Failed to record the health history");
+    }
+    assert!(pending.is_empty());
+
+    let response = test_app
+        .api_client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let history: StatusHistory = response.json().await.expect("Failed to parse the history");
+    assert_eq!(history.snapshots.len(), 2);
+    assert_eq!(history.availability, Some(1.0));
+    assert!(history
+        .snapshots
+        .iter()
+        .all(|s| s.status == SnapshotStatus::Ok && s.db_latency_ms.is_some()));
+
+    let response = test_app
+        .api_client
+        .get(format!("{url}?window=7d"))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    let history: StatusHistory = response.json().await.expect("Failed to parse the history");
+    assert_eq!(history.snapshots.len(), 3);
+}
+
+async fn post_schedule(test_app: &TestApp, body: &Value) -> reqwest::Response {
+    test_app
+        .api_client
+        .post(format!(
+            "{}/admin/maintenance/schedule?api_key={}",
+            test_app.address,
+            test_app.api_token.api_key.expose_secret()
+        ))
+        .json(body)
+        .send()
+        .await
+        .expect("Failed to execute the request")
+}
+
+#[actix_web::test]
+async fn scheduled_maintenance_is_announced() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+    let starts_at = (Utc::now() + TimeDelta::days(1)).to_rfc3339_opts(SecondsFormat::Secs, true);
+    let ends_at = (Utc::now() + TimeDelta::days(1) + TimeDelta::hours(2))
+        .to_rfc3339_opts(SecondsFormat::Secs, true);
+    let body =
+        json!({"starts_at": starts_at, "ends_at": ends_at, "message": "Upgrade of the DB server."});
+
+    info!("Test Case::resource::/admin/maintenance/schedule (POST) -> Attempt to schedule with no admin privileges");
+    let response = post_schedule(&test_app, &body).await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+    assert!(response
+        .headers()
+        .get(MAINTENANCE_HEADER.as_str())
+        .is_none());
+
+    test_app.grant_admin_access().await;
+
+    info!("Test Case::resource::/admin/maintenance/schedule (POST) -> Attempt to schedule a window in the past");
+    let response = post_schedule(
+        &test_app,
+        &json!({"starts_at": "2020-01-01T00:00:00Z", "ends_at": "2020-01-01T01:00:00Z"}),
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/admin/maintenance/schedule (POST) -> Schedule a window");
+    let response = post_schedule(&test_app, &body).await;
+    assert_eq!(response.status().as_u16(), StatusCode::CREATED);
+    let window: MaintenanceWindow = response.json().await.expect("Failed to parse the window");
+    assert_eq!(window.message.as_deref(), Some("Upgrade of the DB server."));
+
+    info!("Test Case::resource::/health (GET) -> The scheduled window is announced");
+    let response = test_app
+        .api_client
+        .get(format!(
+            "{}/health?api_key={}",
+            test_app.address,
+            test_app.api_token.api_key.expose_secret()
+        ))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(
+        response.headers()[MAINTENANCE_HEADER.as_str()],
+        starts_at.as_str()
+    );
+    let report: HealthResponse = response.json().await.expect("Failed to parse the report");
+    match report.server_status {
+        ServerStatus::MaintenanceScheduled(ts) => {
+            assert_eq!(ts, window.starts_at.with_timezone(&Local))
+        }
+        status => panic!("Unexpected server status: {status:?}"),
+    }
+
+    info!("Test Case::resource::/echo (GET) -> Public resources announce the window");
+    let response = test_app
+        .api_client
+        .get(format!("{}/echo", test_app.address))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(
+        response.headers()[MAINTENANCE_HEADER.as_str()],
+        starts_at.as_str()
+    );
+
+    info!("Test Case::resource::/admin/maintenance/schedule (DELETE) -> Cancel the window");
+    let url = format!(
+        "{}/admin/maintenance/schedule?api_key={}",
+        test_app.address,
+        test_app.api_token.api_key.expose_secret()
+    );
+    let response = test_app
+        .api_client
+        .delete(&url)
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    assert!(response
+        .headers()
+        .get(MAINTENANCE_HEADER.as_str())
+        .is_none());
+    let response = test_app
+        .api_client
+        .delete(&url)
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+}
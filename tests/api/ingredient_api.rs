@@ -4,13 +4,26 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::helpers::{
-    spawn_app, ApiTesterBuilder, Credentials, Resource, TestApp, TestBuilder, TestObject,
-};
 use actix_web::http::StatusCode;
-use lacoctelera::{routes::ingredient::FormData, IngCategory, Ingredient};
+use lacoctelera::testing::{
+    fixtures::FixtureSeeder,
+    helpers::{
+        spawn_app, spawn_app_with, ApiTesterBuilder, Credentials, Resource, TestApp, TestBuilder,
+        TestObject,
+    },
+};
+use lacoctelera::{
+    domain::search_text::normalize_search_text,
+    routes::{
+        batch::{BatchItemResult, BatchOutcome},
+        ingredient::FormData,
+    },
+    Ingredient,
+};
 use pretty_assertions::assert_eq;
 use reqwest::Response;
+use secrecy::ExposeSecret;
+use serde_json::json;
 use sqlx::{Executor, MySqlPool};
 use tracing::{debug, error, info};
 use uuid::Uuid;
@@ -122,16 +135,17 @@ async fn seed_ingredients(pool: &MySqlPool) -> FixtureResult {
     let mut conn = pool.acquire().await.unwrap();
 
     for ingredient in test_ingredients.iter() {
-        let query = sqlx::query!(
+        let query = sqlx::query(
             r#"
-            INSERT INTO Ingredient (`id`, `name`, `category`, `description`) VALUES
-                (?,?,?,?)
+            INSERT INTO Ingredient (`id`, `name`, `search_name`, `category`, `description`) VALUES
+                (?,?,?,?,?)
             "#,
-            uuid::Uuid::now_v7().to_string(),
-            ingredient.name(),
-            ingredient.category().to_string(),
-            ingredient.desc(),
-        );
+        )
+        .bind(uuid::Uuid::now_v7().to_string())
+        .bind(ingredient.name())
+        .bind(normalize_search_text(ingredient.name()))
+        .bind(ingredient.category().to_string())
+        .bind(ingredient.desc());
 
         conn.execute(query)
             .await
@@ -185,6 +199,40 @@ async fn search_no_credentials() -> Result<(), String> {
     // The first match should be the closes match.
     assert_eq!(*test_ingredient, response_ingredient[0]);
 
+    info!("Test Case::resource::/ingredient (GET) -> Search ignoring accents and letter case");
+    let response = test.get("?name=VÓDKA").await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let response_ingredient = response
+        .json::<Vec<Ingredient>>()
+        .await
+        .expect("Failed to deserialize the response");
+    assert_eq!(response_ingredient.len(), 2);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn get_by_id() -> Result<(), String> {
+    let mut test_builder = IngredientApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    info!("Test Case::resource::/ingredient (GET) -> Request a malformed ID");
+    let response = test.get("/1234").await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/ingredient (GET) -> Request a non existing ingredient");
+    let response = test.get(&format!("/{}", Uuid::now_v7())).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/ingredient (GET) -> Request an existing ingredient");
+    let ingredients = seed_ingredients(test.db_pool()).await?;
+    let test_ingredient = &ingredients[0];
+    let response = test
+        .get(&format!("/{}", test_ingredient.id().expect("Missing ID")))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
     Ok(())
 }
 
@@ -214,7 +262,7 @@ async fn post_no_credentials() -> Result<(), String> {
         (
             FormData {
                 name: "tc1".to_string(),
-                category: IngCategory::Spirit.to_string(),
+                category: "spirit".to_string(),
                 desc: Some(Uuid::new_v4().to_string()),
             },
             "Spirit test case",
@@ -222,7 +270,7 @@ async fn post_no_credentials() -> Result<(), String> {
         (
             FormData {
                 name: "tc2".to_string(),
-                category: IngCategory::Bitter.to_string(),
+                category: "bitter".to_string(),
                 desc: Some(Uuid::new_v4().to_string()),
             },
             "Bitter test case",
@@ -230,7 +278,7 @@ async fn post_no_credentials() -> Result<(), String> {
         (
             FormData {
                 name: "tc3".to_string(),
-                category: IngCategory::Garnish.to_string(),
+                category: "garnish".to_string(),
                 desc: Some(Uuid::new_v4().to_string()),
             },
             "Garnish test case",
@@ -238,7 +286,7 @@ async fn post_no_credentials() -> Result<(), String> {
         (
             FormData {
                 name: "tc4".to_string(),
-                category: IngCategory::SoftDrink.to_string(),
+                category: "soft_drink".to_string(),
                 desc: Some(Uuid::new_v4().to_string()),
             },
             "SoftDrink test case",
@@ -246,7 +294,7 @@ async fn post_no_credentials() -> Result<(), String> {
         (
             FormData {
                 name: "tc5".to_string(),
-                category: IngCategory::Other.to_string(),
+                category: "other".to_string(),
                 desc: Some(Uuid::new_v4().to_string()),
             },
             "Other test case",
@@ -254,7 +302,7 @@ async fn post_no_credentials() -> Result<(), String> {
         (
             FormData {
                 name: "My drink 80%".to_string(),
-                category: IngCategory::Other.to_string(),
+                category: "other".to_string(),
                 desc: None,
             },
             "Composed name test case",
@@ -262,7 +310,7 @@ async fn post_no_credentials() -> Result<(), String> {
         (
             FormData {
                 name: "tc7".to_string(),
-                category: IngCategory::Other.to_string(),
+                category: "other".to_string(),
                 desc: None,
             },
             "No description teste case",
@@ -280,7 +328,7 @@ async fn post_no_credentials() -> Result<(), String> {
         (
             FormData {
                 name: "1nvalid".to_string(),
-                category: IngCategory::Other.to_string(),
+                category: "other".to_string(),
                 desc: None,
             },
             "Wrong name format test case 1",
@@ -288,7 +336,7 @@ async fn post_no_credentials() -> Result<(), String> {
         (
             FormData {
                 name: "alco;hol".to_string(),
-                category: IngCategory::Other.to_string(),
+                category: "other".to_string(),
                 desc: Some(Uuid::new_v4().to_string()),
             },
             "Wrong name format test case 2",
@@ -311,3 +359,238 @@ async fn post_no_credentials() -> Result<(), String> {
 
     Ok(())
 }
+
+#[actix_web::test]
+async fn batch_delete() -> Result<(), String> {
+    let mut test_builder = IngredientApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+    test.test_app.grant_admin_access().await;
+
+    let fixture = FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let used_id = fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures[0]
+        .ingredients()[0]
+        .ingredient_id
+        .to_string();
+
+    let unused_id = Uuid::now_v7().to_string();
+    sqlx::query("INSERT INTO Ingredient (id, name, category) VALUES (?, 'Blue Curaçao', 'other')")
+        .bind(&unused_id)
+        .execute(test.db_pool())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    info!("Test Case::resource::/ingredient/batch-delete (POST) -> Delete a mixed batch");
+    let response = test
+        .test_app
+        .batch_delete_test(
+            Resource::Ingredient,
+            &json!({"ids": [unused_id, used_id, Uuid::now_v7().to_string()]}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let results: Vec<BatchItemResult> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse the batch results: {e}"))?;
+    let outcomes: Vec<BatchOutcome> = results.iter().map(|r| r.outcome).collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            BatchOutcome::Deleted,
+            BatchOutcome::Referenced,
+            BatchOutcome::NotFound
+        ]
+    );
+
+    let response = test.get(&format!("/{unused_id}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn conditional_search() -> Result<(), String> {
+    let mut test_builder = IngredientApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+    let url = format!("{}/ingredient?name=Vodka", test.test_app.address);
+    let payload = FormData {
+        name: "Vodka".to_string(),
+        category: "spirit".to_string(),
+        desc: None,
+    };
+
+    let response = test.post(&payload).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    // HTTP dates have a resolution of one second.
+    actix_web::rt::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+    info!("Test Case::resource::/ingredient (GET) -> Search includes the latest change");
+    let response = test.get("?name=Vodka").await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let last_modified = response
+        .headers()
+        .get("Last-Modified")
+        .expect("Missing Last-Modified header")
+        .to_str()
+        .map_err(|e| e.to_string())?
+        .to_owned();
+
+    info!("Test Case::resource::/ingredient (GET) -> Nothing changed since the last search");
+    let response = test
+        .test_app
+        .api_client
+        .get(&url)
+        .header("If-Modified-Since", &last_modified)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_MODIFIED);
+
+    info!(
+        "Test Case::resource::/ingredient (GET) -> An ingredient was added since the last search"
+    );
+    let response = test.post(&payload).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let response = test
+        .test_app
+        .api_client
+        .get(&url)
+        .header("If-Modified-Since", &last_modified)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let ingredients: Vec<Ingredient> = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(ingredients.len(), 2);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn post_sanitizes_the_description() -> Result<(), String> {
+    let mut test_builder = IngredientApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+    let payload = FormData {
+        name: "Gin".to_string(),
+        category: "spirit".to_string(),
+        desc: Some("A <b>dry</b> gin<script>alert('xss')</script>".to_string()),
+    };
+
+    info!("Test Case::resource::/ingredient (POST) -> Markup is removed from the description");
+    let response = test.post(&payload).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let description: Option<String> =
+        sqlx::query_scalar("SELECT description FROM Ingredient WHERE name = 'Gin'")
+            .fetch_one(test.db_pool())
+            .await
+            .map_err(|e| e.to_string())?;
+    assert_eq!(description.as_deref(), Some("A dry ginalert('xss')"));
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn ingredient_images() -> Result<(), String> {
+    let media_dir = std::env::temp_dir().join(format!("media-{}", Uuid::now_v7()));
+    let dir = media_dir.to_string_lossy().into_owned();
+    let mut test_app = spawn_app_with(|c| c.application.media_dir = dir).await;
+    test_app.generate_access_token().await;
+    let api_key = test_app.api_token.api_key.expose_secret().to_owned();
+
+    let id = Uuid::now_v7().to_string();
+    sqlx::query("INSERT INTO Ingredient (id, name, category) VALUES (?, 'Lime', 'other')")
+        .bind(&id)
+        .execute(&test_app.db_pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let image_url = format!(
+        "{}/ingredient/{id}/image?api_key={api_key}",
+        test_app.address
+    );
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+
+    info!("Test Case::resource::/ingredient/{{id}}/image (PUT) -> Upload an image");
+    let response = test_app
+        .api_client
+        .put(&image_url)
+        .header("Content-Type", "image/png")
+        .body(png.clone())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let ingredient: Ingredient = response.json().await.map_err(|e| e.to_string())?;
+    let image_id = ingredient
+        .image_id()
+        .expect("The ingredient has no image")
+        .to_owned();
+    assert!(image_id.ends_with(".png"));
+
+    info!("Test Case::resource::/media/{{image_id}} (GET) -> Retrieve the image");
+    let response = test_app
+        .api_client
+        .get(format!("{}/media/{image_id}", test_app.address))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert_eq!(response.headers().get("content-type").unwrap(), "image/png");
+    assert!(response.headers().contains_key("cache-control"));
+    assert_eq!(
+        response.bytes().await.map_err(|e| e.to_string())?.to_vec(),
+        png
+    );
+
+    info!("Test Case::resource::/ingredient/{{id}}/image (PUT) -> Unsupported format");
+    let response = test_app
+        .api_client
+        .put(&image_url)
+        .body("<svg></svg>")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/ingredient/{{id}}/image (DELETE) -> Remove the image");
+    let response = test_app
+        .api_client
+        .delete(&image_url)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    let response = test_app
+        .api_client
+        .get(format!("{}/media/{image_id}", test_app.address))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/ingredient/{{id}}/image (PUT) -> Unknown ingredient");
+    let response = test_app
+        .api_client
+        .put(format!(
+            "{}/ingredient/{}/image?api_key={api_key}",
+            test_app.address,
+            Uuid::now_v7()
+        ))
+        .body(png)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    let _ = std::fs::remove_dir_all(media_dir);
+
+    Ok(())
+}
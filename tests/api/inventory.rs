@@ -0,0 +1,261 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use actix_web::http::StatusCode;
+use lacoctelera::{
+    domain::{IdScheme, Recipe},
+    routes::me::inventory::InventoryItem,
+    seeding::seed_demo_data,
+    testing::helpers::{spawn_app, Credentials, Resource},
+};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+#[actix_web::test]
+async fn inventory_management() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+    seed_demo_data(&test_app.db_pool, IdScheme::UuidV7.generator().as_ref())
+        .await
+        .expect("Failed to seed the demo dataset");
+
+    let response = test_app
+        .get_test(Resource::Recipe, Credentials::NoCredentials, "?name=Mojito")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let mojito = response
+        .json::<Vec<Recipe>>()
+        .await
+        .expect("Failed to parse the recipes")
+        .into_iter()
+        .find(|r| r.name() == "Mojito")
+        .expect("The demo dataset has no Mojito");
+    let ingredients: Vec<String> = mojito
+        .ingredients()
+        .iter()
+        .map(|i| i.ingredient_id.to_string())
+        .collect();
+
+    info!("Test Case::resource::/me/inventory (PUT) -> Unknown ingredients are rejected");
+    let response = test_app
+        .put_test(
+            Resource::Inventory,
+            Credentials::WithCredentials,
+            "",
+            &json!([{"ingredient_id": Uuid::now_v7().to_string()}]),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/me/inventory (PUT) -> Amounts need a unit");
+    let response = test_app
+        .put_test(
+            Resource::Inventory,
+            Credentials::WithCredentials,
+            "",
+            &json!([{"ingredient_id": ingredients[0], "quantity": 70}]),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/me/inventory (PUT) -> Store the ingredients of a Mojito");
+    let entries: Vec<_> = ingredients
+        .iter()
+        .map(|id| json!({"ingredient_id": id}))
+        .collect();
+    let response = test_app
+        .put_test(
+            Resource::Inventory,
+            Credentials::WithCredentials,
+            "",
+            &entries,
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let inventory = response
+        .json::<Vec<InventoryItem>>()
+        .await
+        .expect("Failed to parse the inventory");
+    assert_eq!(inventory.len(), ingredients.len());
+
+    info!("Test Case::resource::/me/inventory/{{id}} (PUT) -> Update the amount of an ingredient");
+    let response = test_app
+        .put_test(
+            Resource::Inventory,
+            Credentials::WithCredentials,
+            &ingredients[0],
+            &json!({"quantity": 700, "unit": "ml"}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let item = response
+        .json::<InventoryItem>()
+        .await
+        .expect("Failed to parse the inventory item");
+    assert_eq!(item.quantity, Some(700.0));
+
+    info!("Test Case::resource::/recipe/suggest (GET) -> Suggestions use the inventory");
+    let response = test_app
+        .get_test(Resource::Recipe, Credentials::WithCredentials, "/suggest")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let suggestions = response
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .expect("Failed to parse the suggestions");
+    assert_eq!(suggestions[0]["recipe"]["name"], "Mojito");
+    assert_eq!(suggestions[0]["missing"], json!([]));
+
+    info!("Test Case::resource::/recipe/suggest (GET) -> An explicit list overrides the inventory");
+    let response = test_app
+        .get_test(
+            Resource::Recipe,
+            Credentials::NoCredentials,
+            &format!("/suggest?ingredients={}", ingredients[0]),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let suggestions = response
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .expect("Failed to parse the suggestions");
+    assert!(suggestions
+        .iter()
+        .all(|s| s["missing"] != json!([]) || s["recipe"]["name"] != "Mojito"));
+
+    info!("Test Case::resource::/recipe/makeable (GET) -> Recipes that can be prepared");
+    let response = test_app
+        .get_test(
+            Resource::Recipe,
+            Credentials::NoCredentials,
+            &format!("/makeable?ingredients={}", ingredients.join(",")),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let makeable = response
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .expect("Failed to parse the recipes");
+    assert!(makeable.iter().any(|m| m["recipe"]["name"] == "Mojito"));
+    assert!(makeable.iter().all(|m| m["missing"] == json!([])));
+
+    info!("Test Case::resource::/recipe/makeable (GET) -> Pages of the recipes");
+    let total = makeable.len();
+    let response = test_app
+        .get_test(
+            Resource::Recipe,
+            Credentials::NoCredentials,
+            &format!("/makeable?ingredients={}&limit=1", ingredients.join(",")),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["X-Total-Count"].to_str().unwrap(),
+        total.to_string()
+    );
+    let page = response
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .expect("Failed to parse the recipes");
+    assert_eq!(page, makeable[..1]);
+    let response = test_app
+        .get_test(
+            Resource::Recipe,
+            Credentials::NoCredentials,
+            &format!(
+                "/makeable?ingredients={}&offset={total}",
+                ingredients.join(",")
+            ),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["X-Total-Count"].to_str().unwrap(),
+        total.to_string()
+    );
+    assert_eq!(
+        response
+            .json::<Vec<serde_json::Value>>()
+            .await
+            .expect("Failed to parse the recipes"),
+        Vec::<serde_json::Value>::new()
+    );
+
+    info!("Test Case::resource::/recipe/makeable (GET) -> Near misses");
+    let pantry = &ingredients[1..];
+    let response = test_app
+        .get_test(
+            Resource::Recipe,
+            Credentials::NoCredentials,
+            &format!("/makeable?ingredients={}", pantry.join(",")),
+        )
+        .await;
+    let makeable = response
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .expect("Failed to parse the recipes");
+    assert!(makeable.iter().all(|m| m["recipe"]["name"] != "Mojito"));
+    let response = test_app
+        .get_test(
+            Resource::Recipe,
+            Credentials::NoCredentials,
+            &format!("/makeable?ingredients={}&missing=1", pantry.join(",")),
+        )
+        .await;
+    let makeable = response
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .expect("Failed to parse the recipes");
+    let mojito = makeable
+        .iter()
+        .find(|m| m["recipe"]["name"] == "Mojito")
+        .expect("The Mojito is a near miss");
+    assert_eq!(mojito["missing"], json!([ingredients[0]]));
+
+    info!("Test Case::resource::/recipe/makeable (GET) -> Invalid queries");
+    let response = test_app
+        .get_test(
+            Resource::Recipe,
+            Credentials::NoCredentials,
+            &format!("/makeable?ingredients={}&missing=2", pantry.join(",")),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    let response = test_app
+        .get_test(
+            Resource::Recipe,
+            Credentials::NoCredentials,
+            "/makeable?ingredients=rum",
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe/suggest (GET) -> The inventory needs an API key");
+    let response = test_app
+        .get_test(Resource::Recipe, Credentials::NoCredentials, "/suggest")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::UNAUTHORIZED);
+
+    info!("Test Case::resource::/me/inventory/{{id}} (DELETE) -> Remove an ingredient");
+    let response = test_app
+        .delete_test(
+            Resource::Inventory,
+            Credentials::WithCredentials,
+            &ingredients[0],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    let response = test_app
+        .delete_test(
+            Resource::Inventory,
+            Credentials::WithCredentials,
+            &ingredients[0],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+}
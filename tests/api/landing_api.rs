@@ -0,0 +1,93 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use actix_web::http::StatusCode;
+use lacoctelera::{
+    domain::{IdScheme, Recipe},
+    routes::activity::RecentActivity,
+    seeding::seed_demo_data,
+    testing::helpers::{spawn_app, TestApp},
+};
+use pretty_assertions::assert_eq;
+use reqwest::Response;
+use tracing::info;
+
+async fn get_landing(test_app: &TestApp, path: &str) -> Response {
+    test_app
+        .api_client
+        .get(format!("{}{path}", &test_app.address))
+        .send()
+        .await
+        .expect("Failed to execute GET for the landing collection.")
+}
+
+async fn landing_recipes(test_app: &TestApp, path: &str) -> Vec<Recipe> {
+    let response = get_landing(test_app, path).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert!(response.headers().contains_key("cache-control"));
+
+    response
+        .json::<Vec<Recipe>>()
+        .await
+        .expect("Failed to parse the landing collection")
+}
+
+#[actix_web::test]
+async fn landing_collections() {
+    let test_app = spawn_app().await;
+
+    info!("Test Case::resource::/category/{{category}}/recipes (GET) -> Unknown categories");
+    let response = get_landing(&test_app, "/category/impossible/recipes").await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/tag/{{tag}}/featured (GET) -> Invalid tags");
+    let response = get_landing(&test_app, "/tag/t@g/featured").await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/tag/{{tag}}/featured (GET) -> Empty collections");
+    assert!(landing_recipes(&test_app, "/tag/rum/featured")
+        .await
+        .is_empty());
+
+    seed_demo_data(&test_app.db_pool, IdScheme::UuidV7.generator().as_ref())
+        .await
+        .expect("Failed to seed the demo dataset");
+    // Collections built within the second of the latest change are served until that second elapses.
+    actix_web::rt::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+    info!("Test Case::resource::/tag/{{tag}}/featured (GET) -> Collections are rebuilt when the recipes change");
+    let recipes = landing_recipes(&test_app, "/tag/rum/featured").await;
+    assert!(recipes.iter().any(|r| r.name() == "Mojito"));
+    assert!(!recipes.iter().any(|r| r.name() == "Negroni"));
+
+    info!("Test Case::resource::/category/{{category}}/recipes (GET) -> Top recipes of a category");
+    let recipes = landing_recipes(&test_app, "/category/medium/recipes").await;
+    assert!(recipes.iter().any(|r| r.name() == "Mojito"));
+}
+
+#[actix_web::test]
+async fn recent_activity() {
+    let test_app = spawn_app().await;
+    seed_demo_data(&test_app.db_pool, IdScheme::UuidV7.generator().as_ref())
+        .await
+        .expect("Failed to seed the demo dataset");
+
+    info!("Test Case::resource::/activity/recent (GET) -> Recent activity of the site");
+    let response = get_landing(&test_app, "/activity/recent").await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert!(response.headers().contains_key("cache-control"));
+    let activity = response
+        .json::<RecentActivity>()
+        .await
+        .expect("Failed to parse the recent activity");
+
+    assert!(activity.recipes.iter().any(|r| r.name() == "Mojito"));
+    assert!(activity
+        .trending_tags
+        .windows(2)
+        .all(|tags| tags[0].count >= tags[1].count));
+    assert!(activity.trending_tags.iter().any(|t| t.tag == "rum"));
+}
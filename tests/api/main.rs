@@ -4,9 +4,22 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+mod admin_api;
 mod author_api;
-mod fixtures;
-mod helpers;
+mod client;
+mod contract;
+mod digests;
+mod health;
 mod ingredient_api;
+mod inventory;
+mod landing_api;
+mod notifications;
+mod outbox;
+mod preferences;
+mod read_only;
 mod recipe_api;
+mod searches;
+mod seeding;
+mod sitemap_api;
+mod token_keys;
 mod token_request;
@@ -0,0 +1,158 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use actix_web::{http::StatusCode, web::Data};
+use chrono::{TimeDelta, Utc};
+use lacoctelera::{
+    domain::id_generator::UuidV7Generator,
+    jobs::run_notifications,
+    routes::me::notifications::{Notification, NotificationKind},
+    testing::helpers::{spawn_app, Credentials, Resource, TestApp},
+    utils::mailing::EmailSender,
+};
+use pretty_assertions::assert_eq;
+use secrecy::ExposeSecret;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+const CLIENT_EMAIL: &str = "jane_doe@mail.com";
+
+async fn get_notifications(test_app: &TestApp, query: &str) -> Vec<Notification> {
+    let response = test_app
+        .get_test(Resource::Notifications, Credentials::WithCredentials, query)
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    response
+        .json::<Vec<Notification>>()
+        .await
+        .expect("Failed to parse the list of notifications")
+}
+
+async fn post_read(test_app: &TestApp, path: &str) -> reqwest::Response {
+    test_app
+        .api_client
+        .post(format!(
+            "{}/me/notifications{path}/read?api_key={}",
+            test_app.address,
+            test_app.api_token.api_key.expose_secret()
+        ))
+        .send()
+        .await
+        .expect("Failed to execute the request")
+}
+
+#[actix_web::test]
+async fn notifications_management() {
+    let mut test_app = spawn_app().await;
+
+    info!("Test Case::resource::/me/notifications (GET) -> Access is restricted");
+    let response = test_app
+        .get_test(Resource::Notifications, Credentials::NoCredentials, "")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    test_app.generate_access_token().await;
+    assert!(get_notifications(&test_app, "").await.is_empty());
+
+    info!("Test Case::job::notifications -> Keys about to expire are notified once");
+    let notified = run_notifications(
+        &test_app.db_pool,
+        &UuidV7Generator,
+        None,
+        TimeDelta::days(7),
+        Utc::now(),
+    )
+    .await
+    .expect("Failed to run the notifications job");
+    assert_eq!(notified, 1);
+    let notified = run_notifications(
+        &test_app.db_pool,
+        &UuidV7Generator,
+        None,
+        TimeDelta::days(7),
+        Utc::now(),
+    )
+    .await
+    .expect("Failed to run the notifications job");
+    assert_eq!(notified, 0);
+    let notifications = get_notifications(&test_app, "?unread=true").await;
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].kind, NotificationKind::TokenExpiring);
+    assert!(notifications[0].read_at.is_none());
+
+    info!("Test Case::resource::/me/notifications/{{id}}/read (POST) -> Unknown notification");
+    let response = post_read(&test_app, &format!("/{}", Uuid::now_v7())).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!(
+        "Test Case::resource::/me/notifications/{{id}}/read (POST) -> Mark a notification as read"
+    );
+    let response = post_read(&test_app, &format!("/{}", notifications[0].id)).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    assert!(get_notifications(&test_app, "?unread=true")
+        .await
+        .is_empty());
+    assert!(get_notifications(&test_app, "").await[0].read_at.is_some());
+
+    info!("Test Case::resource::/me/notifications/read (POST) -> Nothing left to read");
+    let response = post_read(&test_app, "").await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert_eq!(response.json::<Value>().await.unwrap(), json!({"read": 0}));
+}
+
+#[actix_web::test]
+async fn notifications_are_mirrored_to_email() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+    let mail_client: Data<dyn EmailSender> =
+        Data::from(test_app.emails.clone() as Arc<dyn EmailSender>);
+
+    info!("Test Case::job::notifications -> Emails are only sent to the clients that opt in");
+    run_notifications(
+        &test_app.db_pool,
+        &UuidV7Generator,
+        Some(mail_client.clone()),
+        TimeDelta::days(7),
+        Utc::now(),
+    )
+    .await
+    .expect("Failed to run the notifications job");
+    assert!(test_app.emails.sent_to(CLIENT_EMAIL).is_empty());
+
+    let response = test_app
+        .api_client
+        .patch(format!(
+            "{}/me/preferences?api_key={}",
+            test_app.address,
+            test_app.api_token.api_key.expose_secret()
+        ))
+        .json(&json!({"email_notifications": true}))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    info!("Test Case::job::notifications -> Pending notifications are emailed once");
+    for _ in 0..2 {
+        run_notifications(
+            &test_app.db_pool,
+            &UuidV7Generator,
+            Some(mail_client.clone()),
+            TimeDelta::days(7),
+            Utc::now(),
+        )
+        .await
+        .expect("Failed to run the notifications job");
+    }
+    assert_eq!(test_app.emails.sent_to(CLIENT_EMAIL).len(), 1);
+    let email = test_app
+        .emails
+        .last_to(CLIENT_EMAIL)
+        .expect("The notification was not sent");
+    assert!(email.text_body.contains("expires on"));
+}
@@ -0,0 +1,132 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::bail;
+use lacoctelera::{
+    configuration::OutboxSettings,
+    jobs::dispatch_events,
+    testing::helpers::spawn_app,
+    utils::events::{record_event, DomainEvent, EventSink, PublishFuture, StoredEvent},
+};
+use pretty_assertions::assert_eq;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use tracing::info;
+use uuid::Uuid;
+
+/// Sink that captures the published events, and fails on demand.
+#[derive(Debug)]
+struct CapturingSink {
+    name: String,
+    events: Mutex<Vec<u64>>,
+    failing: AtomicBool,
+}
+
+impl CapturingSink {
+    fn new(name: &str) -> Arc<Self> {
+        Arc::new(CapturingSink {
+            name: name.into(),
+            events: Mutex::new(Vec::new()),
+            failing: AtomicBool::new(false),
+        })
+    }
+
+    fn events(&self) -> Vec<u64> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl EventSink for CapturingSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn publish<'a>(&'a self, event: &'a StoredEvent) -> PublishFuture<'a> {
+        Box::pin(async move {
+            if self.failing.load(Ordering::Relaxed) {
+                bail!("The sink is down");
+            }
+            self.events.lock().unwrap().push(event.id);
+
+            Ok(())
+        })
+    }
+}
+
+#[actix_web::test]
+async fn events_are_dispatched() -> Result<(), String> {
+    let test_app = spawn_app().await;
+
+    for _ in 0..3 {
+        record_event(
+            &test_app.db_pool,
+            &DomainEvent::RecipeUpdated {
+                recipe_id: Uuid::now_v7(),
+            },
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    let settings = OutboxSettings {
+        enabled: true,
+        max_attempts: 2,
+        ..Default::default()
+    };
+    let healthy = CapturingSink::new("healthy");
+    let broken = CapturingSink::new("broken");
+    broken.failing.store(true, Ordering::Relaxed);
+    let sinks: Vec<Arc<dyn EventSink>> = vec![healthy.clone(), broken.clone()];
+
+    info!("Test Case::job::outbox -> A broken sink doesn't hold back the rest");
+    let delivered = dispatch_events(&test_app.db_pool, &sinks, &settings)
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(delivered, 3);
+    assert_eq!(healthy.events().len(), 3);
+    assert!(broken.events().is_empty());
+
+    info!("Test Case::job::outbox -> Delivered events are not published again");
+    dispatch_events(&test_app.db_pool, &sinks[..1], &settings)
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(healthy.events().len(), 3);
+
+    info!("Test Case::job::outbox -> Poison events are dead-lettered");
+    // Every run dead-letters the event that reached the maximum of attempts, and makes the first attempt of the next.
+    for _ in 0..3 {
+        dispatch_events(&test_app.db_pool, &sinks[1..], &settings)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let dead: Vec<u64> = sqlx::query_scalar(
+        "SELECT event_id FROM DeadEvent WHERE sink = 'broken' ORDER BY event_id",
+    )
+    .fetch_all(&test_app.db_pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    assert_eq!(dead, healthy.events());
+
+    info!("Test Case::job::outbox -> Sinks resume after the dead letters");
+    broken.failing.store(false, Ordering::Relaxed);
+    record_event(
+        &test_app.db_pool,
+        &DomainEvent::RecipeDeleted {
+            recipe_id: Uuid::now_v7(),
+        },
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    dispatch_events(&test_app.db_pool, &sinks, &settings)
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(broken.events().len(), 1);
+    assert_eq!(healthy.events().len(), 4);
+
+    Ok(())
+}
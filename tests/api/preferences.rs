@@ -0,0 +1,102 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use actix_web::http::StatusCode;
+use lacoctelera::{
+    domain::{IdScheme, QuantityUnit, Recipe, UnitSystem},
+    routes::me::preferences::Preferences,
+    seeding::seed_demo_data,
+    testing::helpers::{spawn_app, TestApp},
+};
+use pretty_assertions::assert_eq;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use tracing::info;
+
+async fn get_mojito(test_app: &TestApp, query: &str) -> Recipe {
+    let response = test_app
+        .api_client
+        .get(format!(
+            "{}/recipe?name=Mojito&api_key={}{query}",
+            test_app.address,
+            test_app.api_token.api_key.expose_secret()
+        ))
+        .send()
+        .await
+        .expect("Failed to execute the search");
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    response
+        .json::<Vec<Recipe>>()
+        .await
+        .expect("Failed to parse the recipes")
+        .into_iter()
+        .find(|r| r.name() == "Mojito")
+        .expect("The demo dataset has no Mojito")
+}
+
+#[actix_web::test]
+async fn preferences_apply_to_recipes() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+    seed_demo_data(&test_app.db_pool, IdScheme::UuidV7.generator().as_ref())
+        .await
+        .expect("Failed to seed the demo dataset");
+    let url = format!(
+        "{}/me/preferences?api_key={}",
+        test_app.address,
+        test_app.api_token.api_key.expose_secret()
+    );
+
+    info!("Test Case::resource::/me/preferences (GET) -> No preferences by default");
+    let response = test_app
+        .api_client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert_eq!(
+        response.json::<Preferences>().await.unwrap(),
+        Preferences::default()
+    );
+    let mojito = get_mojito(&test_app, "").await;
+    assert_eq!(mojito.ingredients()[0].unit, QuantityUnit::MilliLiter);
+
+    info!("Test Case::resource::/me/preferences (PATCH) -> Unsupported languages are rejected");
+    let response = test_app
+        .api_client
+        .patch(&url)
+        .json(&json!({"lang": "fr"}))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/me/preferences (PATCH) -> Prefer the imperial system");
+    let response = test_app
+        .api_client
+        .patch(&url)
+        .json(&json!({"units": "imperial"}))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert_eq!(
+        response.json::<Preferences>().await.unwrap().units,
+        Some(UnitSystem::Imperial)
+    );
+
+    info!("Test Case::resource::/recipe (GET) -> Recipes use the preferences of the client");
+    let mojito = get_mojito(&test_app, "").await;
+    assert_eq!(mojito.ingredients()[0].unit, QuantityUnit::Ounces);
+    assert_eq!(mojito.ingredients()[0].quantity, 1.75);
+
+    info!("Test Case::resource::/recipe (GET) -> The query overrides the preferences");
+    let mojito = get_mojito(&test_app, "&units=metric").await;
+    assert_eq!(mojito.ingredients()[0].unit, QuantityUnit::MilliLiter);
+    assert_eq!(mojito.ingredients()[0].quantity, 50.0);
+}
@@ -0,0 +1,60 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use actix_web::http::StatusCode;
+use lacoctelera::testing::helpers::{spawn_app_with, Credentials, Resource};
+use lacoctelera::utils::http::READ_ONLY_EXPLANATION;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tracing::info;
+
+#[actix_web::test]
+async fn read_only_mode() {
+    let test_app = spawn_app_with(|c| {
+        c.application.read_only = true;
+        c.email_client = None;
+    })
+    .await;
+
+    info!("Test Case::resource::/recipe (GET) -> Searches are served");
+    let response = test_app
+        .search_test(Resource::Recipe, Credentials::NoCredentials, "?name=gin")
+        .await;
+    // The DB of the test is empty, no recipe matches the search.
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe/search (POST) -> Searches by example are served");
+    let response = test_app
+        .api_client
+        .post(format!("{}/recipe/search", test_app.address))
+        .json(&json!({"name": "gin"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    // Unlike GET /recipe, an empty page is returned when no recipe matches the example.
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    info!("Test Case::resource::/author (POST) -> Modifications are rejected");
+    let response = test_app
+        .post_test(
+            Resource::Author,
+            Credentials::NoCredentials,
+            &json!({"name": "Jane"}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+    let body = response
+        .json::<serde_json::Value>()
+        .await
+        .expect("Failed to parse the explanation");
+    assert_eq!(body["explanation"], READ_ONLY_EXPLANATION);
+
+    info!("Test Case::resource::/token/request (GET) -> Token requests are rejected");
+    let response = test_app
+        .get_test(Resource::TokenRequest, Credentials::NoCredentials, "")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+}
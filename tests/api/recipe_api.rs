@@ -4,21 +4,56 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::{
+use actix_web::http::StatusCode;
+use chrono::{TimeDelta, Utc};
+use lacoctelera::testing::{
     fixtures,
     helpers::{
-        spawn_app, ApiTesterBuilder, Credentials, Resource, TestApp, TestBuilder, TestObject,
+        spawn_app, spawn_app_with, ApiTesterBuilder, Credentials, Resource, TestApp, TestBuilder,
+        TestObject,
     },
 };
-use actix_web::http::StatusCode;
-use lacoctelera::domain::{QuantityUnit, Recipe, RecipeContains, Tag};
+use lacoctelera::{
+    domain::{
+        classifier::Classification, rating::RecipeRating, Equipment, QuantityUnit, Recipe,
+        RecipeCategory, RecipeContains, RecipeLimits, RecipeState, StarRate, Tag,
+    },
+    jobs::flush_usage_analytics,
+    routes::{
+        batch::{BatchItemResult, BatchOutcome},
+        expand::ExpandedRecipe,
+        me::notifications::{Notification, NotificationKind},
+        recipe::{
+            claim::{ClaimState, RecipeClaim},
+            get::FacetedResults,
+            search_suggestions::SearchSuggestions,
+        },
+    },
+    utils::metrics::{UsageAnalytics, DEFAULT_K_ANONYMITY},
+};
 use pretty_assertions::assert_eq;
-use reqwest::Response;
+use reqwest::{
+    header::{ETAG, IF_MATCH},
+    Response,
+};
+use secrecy::ExposeSecret;
 use serde::Deserialize;
+use serde_json::json;
 use sqlx::MySqlPool;
 use tracing::{debug, info};
 use uuid::Uuid;
 
+/// Extract the `ETag` header of a response.
+fn etag_of(response: &Response) -> String {
+    response
+        .headers()
+        .get(ETAG)
+        .expect("The response includes no ETag")
+        .to_str()
+        .expect("Invalid ETag")
+        .to_owned()
+}
+
 pub struct RecipeApiTester {
     resource: Resource,
     credentials: Credentials,
@@ -64,6 +99,21 @@ impl RecipeApiTester {
 
         app
     }
+
+    /// DELETE request guarded by an `If-Match` header.
+    pub async fn delete_if_match(&self, id: &str, etag: &str) -> Response {
+        self.test_app
+            .api_client
+            .delete(format!(
+                "{}/recipe/{id}?api_key={}",
+                self.test_app.address,
+                self.test_app.api_token.api_key.expose_secret()
+            ))
+            .header(IF_MATCH, etag)
+            .send()
+            .await
+            .expect("Failed to execute DELETE for the resource /recipe.")
+    }
 }
 
 impl TestObject for RecipeApiTester {
@@ -158,6 +208,8 @@ async fn post_no_credentials() -> Result<(), String> {
         None,
         included_ingredients,
         &["Pour everything into a cup and enjoy."],
+        None,
+        None,
         Some(&authors[0].id().unwrap().to_string()),
     )
     .map_err(|e| e.to_string())?;
@@ -235,6 +287,8 @@ async fn post_with_credentials() -> Result<(), String> {
         None,
         included_ingredients,
         &["Pour everything into a cup and enjoy."],
+        Some(&[Equipment::Shaker, Equipment::Blender]),
+        Some(3),
         Some(&authors[0].id().unwrap().to_string()),
     )
     .expect("Failed to build a new recipe");
@@ -250,10 +304,15 @@ async fn post_with_credentials() -> Result<(), String> {
     assert!(json.is_ok());
     let id = json.unwrap();
 
-    let recipe_from_db = sqlx::query!("SELECT * FROM `Cocktail` WHERE `id`=?", id.id.to_string(),)
-        .fetch_optional(test.db_pool())
-        .await
-        .expect("Failed to retrieve a cocktail entry from the DB");
+    // The rating is a DECIMAL, which sqlx can't decode without extra features, so the columns are listed.
+    let recipe_from_db = sqlx::query!(
+        "SELECT `id`, `name`, `image_id`, `category`, `description`, `url`, `steps`, `owner` \
+        FROM `Cocktail` WHERE `id`=?",
+        id.id.to_string(),
+    )
+    .fetch_optional(test.db_pool())
+    .await
+    .expect("Failed to retrieve a cocktail entry from the DB");
 
     let recipe_from_db = match recipe_from_db {
         Some(recipe) => recipe,
@@ -310,6 +369,17 @@ async fn post_with_credentials() -> Result<(), String> {
         })
         .collect();
 
+    let equipment: Vec<Equipment> = sqlx::query_scalar::<_, String>(
+        "SELECT `equipment` FROM `RecipeEquipment` WHERE `cocktail_id`=?",
+    )
+    .bind(id.id.to_string())
+    .fetch_all(test.db_pool())
+    .await
+    .map_err(|e| e.to_string())?
+    .iter()
+    .map(|e| Equipment::try_from(e.as_str()).expect("Failed to parse the equipment"))
+    .collect();
+
     let received_recipe = Recipe::new(
         Some(Uuid::parse_str(&recipe_from_db.id).expect("Failed to parse UUID")),
         &recipe_from_db.name,
@@ -323,6 +393,8 @@ async fn post_with_credentials() -> Result<(), String> {
         recipe_from_db.url.as_deref(),
         &ingredients,
         &stepize(&recipe_from_db.steps),
+        Some(&equipment),
+        recipe.prep_time_minutes(),
         recipe_from_db.owner.as_deref(),
     )
     .expect("Failed to build a new recipe");
@@ -334,6 +406,7 @@ async fn post_with_credentials() -> Result<(), String> {
     assert_eq!(recipe.url(), received_recipe.url());
     assert_eq!(recipe.ingredients(), received_recipe.ingredients());
     assert_eq!(recipe.steps(), received_recipe.steps());
+    assert_eq!(recipe.equipment(), received_recipe.equipment());
     assert_eq!(recipe.owner(), received_recipe.owner());
     assert_eq!(recipe.tags(), received_recipe.tags());
     assert_eq!(recipe.author_tags(), received_recipe.author_tags());
@@ -341,7 +414,7 @@ async fn post_with_credentials() -> Result<(), String> {
     Ok(())
 }
 
-fn stepize<'a>(steps: &'a str) -> Vec<&'a str> {
+fn stepize(steps: &str) -> Vec<&str> {
     let mut step_list = Vec::new();
 
     for line in steps.split("/n") {
@@ -370,13 +443,7 @@ async fn get_no_credentials() -> Result<(), String> {
         .valid_fixtures;
     let a_recipe = &recipe_fixture[0];
 
-    let query = format!(
-        "/{}",
-        a_recipe
-            .id()
-            .expect("Failed to extract recipe's ID")
-            .to_string()
-    );
+    let query = format!("/{}", a_recipe.id().expect("Failed to extract recipe's ID"));
     let response = test.get(&query).await;
     debug!("Received payload:\n{:?}", response);
     assert_eq!(response.status().as_u16(), StatusCode::OK);
@@ -399,6 +466,11 @@ async fn get_no_credentials() -> Result<(), String> {
     assert_eq!(a_recipe.owner(), received_recipe.owner());
     assert_eq!(a_recipe.steps(), received_recipe.steps());
     assert_eq!(a_recipe.url(), received_recipe.url());
+    assert_eq!(a_recipe.equipment(), received_recipe.equipment());
+    assert_eq!(
+        a_recipe.prep_time_minutes(),
+        received_recipe.prep_time_minutes()
+    );
     // The fractional part sometimes is not equal.
     assert_eq!(
         a_recipe
@@ -441,3 +513,1763 @@ async fn get_no_credentials() -> Result<(), String> {
 
     Ok(())
 }
+
+#[actix_web::test]
+async fn head() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    info!("Test Case::resource::/recipe (HEAD) -> Request a non existing recipe");
+    let response = test.head(&Uuid::now_v7().to_string()).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe (HEAD) -> Request a malformed ID");
+    let response = test.head("1234").await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe (HEAD) -> Request an existing recipe");
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe_id = fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract recipe's ID")
+        .to_string();
+    let response = test.head(&recipe_id).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert!(response.headers().get("last-modified").is_some());
+    // The rating of the fixture is 0 stars.
+    assert_eq!(response.headers().get("x-recipe-rating").unwrap(), "0");
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn malformed_and_unknown_ids() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+    let unknown_id = Uuid::now_v7().to_string();
+
+    for id in ["1234", "Wrong_ID", "00000000-0000-0000-0000-000000000000"] {
+        info!("Test Case::resource::/recipe -> Malformed ID ({id})");
+        let response = test.get(&format!("/{id}")).await;
+        assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+        let response = test.get(&format!("/{id}/pdf")).await;
+        assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+        let response = test.head(id).await;
+        assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+        let response = test.patch(id, &json!({"name": "Margarita"})).await;
+        assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    }
+
+    info!("Test Case::resource::/recipe -> Valid ID of a recipe that doesn't exist");
+    let response = test.get(&format!("/{unknown_id}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+    let response = test.get(&format!("/{unknown_id}/pdf")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+    let response = test.head(&unknown_id).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+    let response = test.patch(&unknown_id, &json!({"name": "Margarita"})).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn slugs() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_ingredients(true)
+        .with_authors(true)
+        .seed()
+        .await?;
+    let ingredient_id = fixture
+        .ingredient
+        .expect("Failed to extract ingredient fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract ingredient's ID");
+    let author_id = fixture
+        .author
+        .expect("Failed to extract author fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract author's ID");
+    let recipe = json!({
+        "name": "Piña colada",
+        "ingredients": [{"quantity": 50.0, "unit": "ml", "ingredient_id": ingredient_id}],
+        "steps": ["Blend with crushed ice."],
+        "author_id": author_id
+    });
+
+    #[derive(Deserialize)]
+    struct Id {
+        pub id: Uuid,
+    }
+
+    info!("Test Case::resource::/recipe (POST) -> The slug is generated from the name");
+    let response = test.post(&recipe).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let id = response.json::<Id>().await.map_err(|e| e.to_string())?.id;
+    let response = test.get(&format!("/{id}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let stored: Recipe = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(stored.slug(), Some("pina-colada"));
+
+    info!("Test Case::resource::/recipe/by-slug (GET) -> Known slug");
+    let response = test.get(&format!("/by-slug/{author_id}/pina-colada")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let found: Recipe = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(found.id(), Some(id));
+
+    info!("Test Case::resource::/recipe/by-slug (GET) -> Unknown slug and malformed author");
+    let response = test.get(&format!("/by-slug/{author_id}/mojito")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+    let response = test.get("/by-slug/Wrong_ID/pina-colada").await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe (POST) -> Duplicated name for the same author");
+    let response = test.post(&recipe).await;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn short_ids() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let id = fixture
+        .recipe
+        .expect("Failed to extract recipe fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract recipe's ID");
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> The short ID is included");
+    let response = test.get(&format!("/{id}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let recipe: Recipe = response.json().await.map_err(|e| e.to_string())?;
+    let short_id = recipe
+        .short_id()
+        .expect("Failed to extract recipe's short ID")
+        .to_string();
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Short IDs are accepted");
+    let response = test.get(&format!("/{short_id}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let found: Recipe = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(found.id(), Some(id));
+    let response = test.head(&short_id).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Unknown short ID");
+    let response = test.get("/11111111111").await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+    let response = test.head("11111111111").await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn delete() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let id = fixture
+        .recipe
+        .expect("Failed to extract recipe fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract recipe's ID")
+        .to_string();
+
+    info!("Test Case::resource::/recipe/{{id}} (DELETE) -> Malformed ID");
+    let response = test.delete("not-a-recipe").await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe/{{id}} (DELETE) -> Attempt to delete a recipe using a stale ETag");
+    let stale_etag = etag_of(&test.get(&format!("/{id}")).await);
+    let response = test
+        .patch(&id, &json!({"description": "A brand new description"}))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    let response = test.delete_if_match(&id, &stale_etag).await;
+    assert_eq!(response.status().as_u16(), StatusCode::PRECONDITION_FAILED);
+
+    info!("Test Case::resource::/recipe/{{id}} (DELETE) -> Delete an existing recipe");
+    let response = test.delete(&id).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    let response = test.get(&format!("/{id}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe/{{id}} (DELETE) -> Delete a recipe that no longer exists");
+    let response = test.delete(&id).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn delete_requires_if_match() -> Result<(), String> {
+    let mut test_app = spawn_app_with(|c| c.application.require_if_match = true).await;
+    test_app.generate_access_token().await;
+    let test = RecipeApiTester {
+        resource: Resource::Recipe,
+        credentials: Credentials::WithCredentials,
+        test_app,
+    };
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let id = fixture
+        .recipe
+        .expect("Failed to extract recipe fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract recipe's ID")
+        .to_string();
+
+    info!("Test Case::resource::/recipe/{{id}} (DELETE) -> The If-Match header is required");
+    let response = test.delete(&id).await;
+    assert_eq!(
+        response.status().as_u16(),
+        StatusCode::PRECONDITION_REQUIRED
+    );
+
+    info!("Test Case::resource::/recipe/{{id}} (DELETE) -> Delete a recipe using its current ETag");
+    let etag = etag_of(&test.get(&format!("/{id}")).await);
+    let response = test.delete_if_match(&id, &etag).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn batch_delete() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipes = fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures;
+    let recipe_id = recipes[0]
+        .id()
+        .expect("Failed to extract recipe's ID")
+        .to_string();
+    let owner = recipes[0]
+        .owner()
+        .expect("Failed to extract recipe's owner")
+        .to_string();
+    let missing_id = Uuid::now_v7().to_string();
+    let body = json!({"ids": [recipe_id, missing_id, "1234"]});
+
+    info!("Test Case::resource::/recipe/batch-delete (POST) -> Attempt with no admin privileges");
+    let response = test
+        .test_app
+        .batch_delete_test(Resource::Recipe, &body)
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    test.test_app.grant_admin_access().await;
+
+    info!("Test Case::resource::/recipe/batch-delete (POST) -> Send an empty batch");
+    let response = test
+        .test_app
+        .batch_delete_test(Resource::Recipe, &json!({"ids": []}))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe/batch-delete (POST) -> Delete recipes owned by another author");
+    let response = test
+        .test_app
+        .batch_delete_test(
+            Resource::Recipe,
+            &json!({"ids": [recipe_id], "owner": Uuid::now_v7().to_string()}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let results: Vec<BatchItemResult> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse the batch results: {e}"))?;
+    assert_eq!(results[0].outcome, BatchOutcome::NotOwned);
+
+    info!("Test Case::resource::/recipe/batch-delete (POST) -> Delete a mixed batch");
+    let response = test
+        .test_app
+        .batch_delete_test(
+            Resource::Recipe,
+            &json!({"ids": [recipe_id, missing_id, "1234"], "owner": owner}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let results: Vec<BatchItemResult> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse the batch results: {e}"))?;
+    let outcomes: Vec<BatchOutcome> = results.iter().map(|r| r.outcome).collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            BatchOutcome::Deleted,
+            BatchOutcome::NotFound,
+            BatchOutcome::InvalidId
+        ]
+    );
+
+    let response = test.get(&format!("/{recipe_id}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn get_pdf() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe_id = fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract recipe's ID")
+        .to_string();
+
+    info!("Test Case::resource::/recipe/{{id}}/pdf (GET) -> Request a non existing recipe");
+    let response = test.get(&format!("/{}/pdf", Uuid::now_v7())).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe/{{id}}/pdf (GET) -> Request an invalid amount of servings");
+    let response = test.get(&format!("/{recipe_id}/pdf?servings=0")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe/{{id}}/pdf (GET) -> Request an existing recipe");
+    for _ in 0..2 {
+        // The second request is served from the cache.
+        let response = test.get(&format!("/{recipe_id}/pdf?servings=4")).await;
+        assert_eq!(response.status().as_u16(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/pdf"
+        );
+        let document = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read the PDF document: {e}"))?;
+        assert!(document.starts_with(b"%PDF-"));
+    }
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn get_jsonld() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures[0];
+    let recipe_id = recipe
+        .id()
+        .expect("Failed to extract recipe's ID")
+        .to_string();
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Request an unknown format");
+    let response = test.get(&format!("/{recipe_id}?format=xml")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Request the recipe as JSON-LD");
+    let response = test.get(&format!("/{recipe_id}?format=jsonld")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/ld+json"
+    );
+    let document: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse the JSON-LD document: {e}"))?;
+    assert_eq!(document["@type"], "Recipe");
+    assert_eq!(document["name"], recipe.name());
+    assert_eq!(
+        document["recipeIngredient"].as_array().unwrap().len(),
+        recipe.ingredients().len()
+    );
+    assert_eq!(
+        document["recipeInstructions"].as_array().unwrap().len(),
+        recipe.steps().len()
+    );
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn get_spec() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures[0];
+    let recipe_id = recipe.id().expect("Failed to extract recipe's ID");
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Request the recipe as a specification");
+    let response = test.get(&format!("/{recipe_id}?format=spec")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert!(response
+        .headers()
+        .get("content-type")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+    let spec = response.text().await.map_err(|e| e.to_string())?;
+    assert!(spec.starts_with(&recipe.name().to_uppercase()));
+    assert!(spec.contains("\nINGREDIENTS\n"));
+    assert!(spec.contains("\nMETHOD\n"));
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn equipment() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures[0];
+    let recipe_id = recipe
+        .id()
+        .expect("Failed to extract recipe's ID")
+        .to_string();
+    let category = recipe.category().to_string();
+
+    info!("Test Case::resource::/recipe (GET) -> Search excluding an invalid equipment");
+    let response = test.search("?equipment_excludes=spoon").await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!(
+        "Test Case::resource::/recipe (GET) -> Search excluding equipment not used by the recipe"
+    );
+    let response = test
+        .search(&format!("?category={category}&equipment_excludes=blender"))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    info!("Test Case::resource::/recipe/{{id}} (PATCH) -> Replace the equipment of the recipe");
+    sqlx::query("UPDATE Cocktail SET update_date = '2020-01-01 00:00:00' WHERE id = ?")
+        .bind(&recipe_id)
+        .execute(test.db_pool())
+        .await
+        .map_err(|e| e.to_string())?;
+    let response = test
+        .patch(&recipe_id, &json!({"equipment": ["blender"]}))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    // Only the equipment changed, yet the recipe was modified.
+    let updated: i64 = sqlx::query_scalar(
+        "SELECT CAST(update_date > '2020-01-01 00:00:00' AS SIGNED) FROM Cocktail WHERE id = ?",
+    )
+    .bind(&recipe_id)
+    .fetch_one(test.db_pool())
+    .await
+    .map_err(|e| e.to_string())?;
+    assert_eq!(updated, 1);
+
+    let response = test.get(&format!("/{recipe_id}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let modified: Recipe = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse the recipe: {e}"))?;
+    assert_eq!(modified.equipment(), Some([Equipment::Blender].as_slice()));
+    assert_eq!(modified.name(), recipe.name());
+    assert_eq!(modified.ingredients(), recipe.ingredients());
+
+    info!("Test Case::resource::/recipe (GET) -> Search excluding equipment used by the recipe");
+    let response = test
+        .search(&format!("?category={category}&equipment_excludes=blender"))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe/{{id}} (PATCH) -> Modify a non existing recipe");
+    let response = test
+        .patch(&Uuid::now_v7().to_string(), &json!({"name": "New name"}))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe/{{id}} (PATCH) -> Use an invalid name");
+    let response = test
+        .patch(
+            &recipe_id,
+            &json!({"name": "A name that is way too long to be accepted"}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn prep_time() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures[0];
+    let recipe_id = recipe
+        .id()
+        .expect("Failed to extract recipe's ID")
+        .to_string();
+    let prep_time = recipe
+        .prep_time_minutes()
+        .expect("The recipe fixture has no preparation time");
+
+    info!("Test Case::resource::/recipe (GET) -> Search recipes using the preparation time");
+    let response = test.search(&format!("?max_prep_time={prep_time}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let response = test
+        .search(&format!("?max_prep_time={}", prep_time - 1))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe/{{id}} (PATCH) -> Use an invalid preparation time");
+    let response = test
+        .patch(&recipe_id, &json!({"prep_time_minutes": 0}))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe/{{id}} (PATCH) -> Modify the preparation time");
+    let response = test
+        .patch(&recipe_id, &json!({"prep_time_minutes": prep_time - 1}))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    let response = test
+        .search(&format!("?max_prep_time={}", prep_time - 1))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn search_by_tags() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures[0];
+    let tags = recipe
+        .tags()
+        .unwrap_or_default()
+        .iter()
+        .chain(recipe.author_tags().unwrap_or_default())
+        .map(|t| t.identifier.clone())
+        .collect::<Vec<String>>()
+        .join(",");
+
+    info!("Test Case::resource::/recipe (GET) -> Search recipes using all their tags");
+    let response = test.search(&format!("?tags={tags}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    info!("Test Case::resource::/recipe (GET) -> Search recipes using a tag that is not used");
+    let response = test.search(&format!("?tags={tags},unused_tag")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe (GET) -> Search recipes using an invalid tag");
+    let response = test.search("?tags=a(tag)").await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe (GET) -> Search recipes combining several criteria");
+    let response = test
+        .search(&format!("?name=test%20recipe&tags={tags}&category=easy"))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let found: Vec<Recipe> = response.json().await.map_err(|e| e.to_string())?;
+    assert!(found.iter().all(|r| r.name() == recipe.name()));
+    let response = test.search(&format!("?tags={tags}&category=pro")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe (GET) -> Paginate the results of a search");
+    let response = test.search(&format!("?tags={tags}&limit=1")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    assert!(response.headers().contains_key("X-Total-Count"));
+    let links = response
+        .headers()
+        .get("Link")
+        .and_then(|v| v.to_str().ok())
+        .expect("Failed to extract the links to the pages");
+    assert!(links.contains("rel=\"first\"") && links.contains("rel=\"last\""));
+    let found: Vec<Recipe> = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(found.len(), 1);
+    let response = test.search(&format!("?tags={tags}&limit=0")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe (GET) -> Sort the results of a search");
+    let response = test
+        .search(&format!("?tags={tags}&sort=-rating,name"))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let response = test.search(&format!("?tags={tags}&sort=owner")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn search_by_example() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures[0];
+    let ingredients = recipe
+        .ingredients()
+        .iter()
+        .map(|i| json!({"ingredient_id": i.ingredient_id}))
+        .collect::<Vec<_>>();
+
+    let search = |query: &'static str, body: serde_json::Value| {
+        test.test_app
+            .api_client
+            .post(format!("{}/recipe/search{query}", test.test_app.address))
+            .json(&body)
+            .send()
+    };
+
+    info!("Test Case::resource::/recipe/search (POST) -> Search recipes using their ingredients");
+    let response = search("?limit=1", json!({"ingredients": ingredients}))
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let total = response
+        .headers()
+        .get("X-Total-Count")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok())
+        .expect("Failed to extract the total amount of results");
+    assert!(total >= 1);
+    let recipes: Vec<Recipe> = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(recipes.len(), 1);
+
+    info!("Test Case::resource::/recipe/search (POST) -> Page beyond the end of the results");
+    let response = search("?offset=100000", json!({"ingredients": ingredients}))
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let recipes: Vec<Recipe> = response.json().await.map_err(|e| e.to_string())?;
+    assert!(recipes.is_empty());
+
+    info!("Test Case::resource::/recipe/search (POST) -> Invalid examples and pages");
+    let response = search("", json!({"steps": ["Shake with ice."]}))
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    let response = search("?limit=0", json!({}))
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe/search (POST) -> Drafts need an API key");
+    let response = search("", json!({"state": "draft"}))
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn search_expression() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+
+    info!("Test Case::resource::/recipe (GET) -> Search recipes using an expression");
+    let response = test.search("?q=tag:test%20-tag:unused_tag").await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let response = test
+        .search("?q=tag:test%20AND%20(tag:unused_tag%20OR%20tag:other_tag)")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe (GET) -> Search recipes using an invalid expression");
+    let response = test.search("?q=tag:test%20AND").await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    let response = test.search("?q=glass:coupe").await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe (GET) -> Expressions combined with other criteria");
+    let response = test.search("?q=tag:test&category=easy").await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let response = test.search("?q=tag:test&category=pro").await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn expand_related_resources() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures[0];
+    let recipe_id = recipe.id().expect("Failed to extract recipe's ID");
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Recipes don't embed resources by default");
+    let response = test.get(&format!("/{recipe_id}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let expanded: ExpandedRecipe = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse the recipe: {e}"))?;
+    assert_eq!(expanded.embedded.ingredients, None);
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Embed the ingredients of a recipe");
+    let response = test.get(&format!("/{recipe_id}?expand=ingredients")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let expanded: ExpandedRecipe = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse the recipe: {e}"))?;
+    let ingredients = expanded
+        .embedded
+        .ingredients
+        .expect("The ingredients were not embedded");
+    assert_eq!(
+        ingredients
+            .iter()
+            .map(|i| i.ingredient_id)
+            .collect::<Vec<Uuid>>(),
+        recipe
+            .ingredients()
+            .iter()
+            .map(|i| i.ingredient_id)
+            .collect::<Vec<Uuid>>()
+    );
+    assert!(ingredients.iter().all(|i| !i.name.is_empty()));
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Embed the author of a recipe");
+    let response = test
+        .get(&format!("/{recipe_id}?expand=author,ingredients"))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let expanded: ExpandedRecipe = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse the recipe: {e}"))?;
+    assert!(expanded.embedded.ingredients.is_some());
+    assert_eq!(
+        expanded.embedded.author.and_then(|a| a.id()),
+        recipe.owner().map(|id| id.to_string())
+    );
+
+    info!("Test Case::resource::/recipe (GET) -> Embed the ingredients of the search results");
+    let response = test.search("?q=tag:test&expand=ingredients").await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let results: Vec<ExpandedRecipe> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse the search results: {e}"))?;
+    assert!(results.iter().all(|r| r.embedded.ingredients.is_some()));
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Unknown expansions are rejected");
+    let response = test.get(&format!("/{recipe_id}?expand=comments")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    let response = test.get(&format!("/{recipe_id}?expand=recipes")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn search_facets() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_no_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures[0];
+    let tag = recipe
+        .tags()
+        .unwrap_or_default()
+        .first()
+        .expect("The recipe fixture has no tags")
+        .identifier
+        .clone();
+    let name = recipe.name();
+
+    info!("Test Case::resource::/recipe (GET) -> Search recipes without facets");
+    let response = test.search(&format!("?name={name}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    response
+        .json::<Vec<Recipe>>()
+        .await
+        .expect("Failed to parse the search results");
+
+    info!("Test Case::resource::/recipe (GET) -> Search recipes with facets");
+    let response = test.search(&format!("?name={name}&facets=true")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let results = response
+        .json::<FacetedResults>()
+        .await
+        .expect("Failed to parse the search results");
+    let found = results.recipes.len() as u64;
+    assert!(results.recipes.iter().any(|r| r.recipe.id() == recipe.id()));
+    assert_eq!(
+        results.facets.ratings.iter().map(|f| f.count).sum::<u64>(),
+        found
+    );
+    assert!(
+        results
+            .facets
+            .categories
+            .iter()
+            .map(|f| f.count)
+            .sum::<u64>()
+            <= found
+    );
+    assert!(results
+        .facets
+        .tags
+        .iter()
+        .any(|f| f.tag == tag && f.count <= found));
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn classify() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    info!("Test Case::resource::/recipe/classify (POST) -> Classify a recipe draft");
+    let response = test
+        .test_app
+        .api_client
+        .post(format!("{}/recipe/classify", test.test_app.address))
+        .json(&json!({
+            "steps": ["Fat-wash the bourbon overnight.", "Stir and smoke the glass."],
+            "equipment": ["smoker"],
+            "prep_time_minutes": 90
+        }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let classification: Classification = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(classification.category, RecipeCategory::Pro);
+    assert!(classification
+        .techniques
+        .contains(&"fat-washing".to_owned()));
+
+    info!("Test Case::resource::/recipe (POST) -> Add a recipe with no category");
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_ingredients(true)
+        .with_authors(true)
+        .seed()
+        .await?;
+    let ingredient_id = fixture
+        .ingredient
+        .expect("Failed to extract ingredient fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract ingredient's ID");
+    let response = test
+        .post(&json!({
+            "name": "Simple highball",
+            "ingredients": [{"quantity": 50.0, "unit": "ml", "ingredient_id": ingredient_id}],
+            "steps": ["Pour over ice and top with soda."]
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    #[derive(Deserialize)]
+    struct Id {
+        pub id: Uuid,
+    }
+    let id = response.json::<Id>().await.map_err(|e| e.to_string())?.id;
+    let response = test.get(&format!("/{id}")).await;
+    let recipe: Recipe = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(recipe.category(), RecipeCategory::Easy);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn publishing_workflow() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+    let api_key = test.test_app.api_token.api_key.expose_secret().to_owned();
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_ingredients(true)
+        .with_authors(true)
+        .seed()
+        .await?;
+    let ingredient_id = fixture
+        .ingredient
+        .expect("Failed to extract ingredient fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract ingredient's ID");
+    let author_id = fixture
+        .author
+        .expect("Failed to extract author fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract author's ID");
+    let draft = json!({
+        "name": "Draft highball",
+        "ingredients": [{"quantity": 50.0, "unit": "ml", "ingredient_id": ingredient_id}],
+        "steps": ["Pour over ice and top with soda."],
+        "author_id": author_id,
+        "state": "draft"
+    });
+
+    #[derive(Deserialize)]
+    struct Id {
+        pub id: Uuid,
+    }
+
+    #[derive(Deserialize)]
+    struct StateChange {
+        pub state: RecipeState,
+    }
+
+    let transition = |id: Uuid, transition: &'static str| {
+        test.test_app
+            .api_client
+            .post(format!(
+                "{}/recipe/{id}/{transition}?api_key={api_key}",
+                test.test_app.address
+            ))
+            .send()
+    };
+    let public_get = |id: Uuid| {
+        test.test_app
+            .api_client
+            .get(format!("{}/recipe/{id}", test.test_app.address))
+            .send()
+    };
+
+    info!("Test Case::resource::/recipe (POST) -> Recipes of first-time clients need a review");
+    let mut unreviewed = draft.clone();
+    unreviewed["name"] = json!("Unreviewed highball");
+    unreviewed["state"] = json!("published");
+    let response = test.post(&unreviewed).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let change: StateChange = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(change.state, RecipeState::Submitted);
+
+    info!("Test Case::resource::/recipe (POST) -> Register a draft");
+    let response = test.post(&draft).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let id = response.json::<Id>().await.map_err(|e| e.to_string())?.id;
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Drafts are hidden from the public");
+    let response = public_get(id).await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+    let response = test.get(&format!("/{id}")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let recipe: Recipe = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(recipe.state(), Some(RecipeState::Draft));
+
+    info!("Test Case::resource::/recipe (GET) -> Search the drafts");
+    let response = test
+        .test_app
+        .api_client
+        .get(format!("{}/recipe?state=draft", test.test_app.address))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::UNAUTHORIZED);
+    let response = test.search("?state=draft").await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let response = test.search("?name=Draft").await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe/{{id}}/archive (POST) -> Drafts can't be archived");
+    let response = transition(id, "archive").await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+
+    info!("Test Case::resource::/recipe/{{id}}/submit (POST) -> First-time authors need a review");
+    let response = transition(id, "submit").await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let change: StateChange = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(change.state, RecipeState::Submitted);
+
+    info!("Test Case::resource::/recipe/{{id}}/publish (POST) -> Reviews need admin privileges");
+    let response = transition(id, "publish").await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+    test.test_app.grant_admin_access().await;
+    let response = transition(id, "publish").await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let response = public_get(id).await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    info!("Test Case::resource::/me/notifications (GET) -> The approval is notified");
+    let response = test
+        .test_app
+        .get_test(Resource::Notifications, Credentials::WithCredentials, "")
+        .await;
+    let notifications: Vec<Notification> = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].kind, NotificationKind::RecipeApproved);
+    assert_eq!(notifications[0].subject_id, id.to_string());
+
+    info!("Test Case::resource::/recipe/{{id}}/submit (POST) -> Known authors skip the review");
+    let mut second_draft = draft.clone();
+    second_draft["name"] = json!("Second draft highball");
+    let response = test.post(&second_draft).await;
+    let second_id = response.json::<Id>().await.map_err(|e| e.to_string())?.id;
+    let response = transition(second_id, "submit")
+        .await
+        .map_err(|e| e.to_string())?;
+    let change: StateChange = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(change.state, RecipeState::Published);
+
+    info!("Test Case::resource::/recipe (POST) -> Recipes of known clients skip the review");
+    let mut reviewed = draft.clone();
+    reviewed["name"] = json!("Reviewed highball");
+    reviewed["state"] = json!(null);
+    let response = test.post(&reviewed).await;
+    let change: StateChange = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(change.state, RecipeState::Published);
+
+    info!("Test Case::resource::/recipe/{{id}}/archive (POST) -> Archive a published recipe");
+    let response = transition(id, "archive").await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let response = public_get(id).await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe/{{id}}/unknown (POST) -> Unknown transition");
+    let response = transition(id, "unknown").await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn recipe_claims() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+    let api_key = test.test_app.api_token.api_key.expose_secret().to_owned();
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_ingredients(true)
+        .with_authors(true)
+        .seed()
+        .await?;
+    let ingredient_id = fixture
+        .ingredient
+        .expect("Failed to extract ingredient fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract ingredient's ID");
+    let author_id = fixture
+        .author
+        .expect("Failed to extract author fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract author's ID");
+
+    #[derive(Deserialize)]
+    struct Id {
+        pub id: Uuid,
+    }
+
+    let claim = |id: Uuid, author: &str| {
+        test.test_app
+            .api_client
+            .post(format!(
+                "{}/recipe/{id}/claim?api_key={api_key}",
+                test.test_app.address
+            ))
+            .json(&json!({"author": author}))
+            .send()
+    };
+    let review = |id: &str, action: &'static str| {
+        test.test_app
+            .api_client
+            .post(format!(
+                "{}/admin/claims/{id}?api_key={api_key}",
+                test.test_app.address
+            ))
+            .json(&json!({"action": action}))
+            .send()
+    };
+
+    // The source of the recipe is not public, so the claim needs the review of an administrator.
+    let response = test
+        .post(&json!({
+            "name": "Imported sour",
+            "ingredients": [{"quantity": 50.0, "unit": "ml", "ingredient_id": ingredient_id}],
+            "steps": ["Shake with ice and strain."],
+            "url": "http://localhost/imported-sour"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let id = response.json::<Id>().await.map_err(|e| e.to_string())?.id;
+
+    info!(
+        "Test Case::resource::/recipe/{{id}}/claim (POST) -> Unknown authors can't claim recipes"
+    );
+    let response = claim(id, &Uuid::now_v7().to_string())
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!(
+        "Test Case::resource::/recipe/{{id}}/claim (POST) -> Authors of other clients can't claim recipes"
+    );
+    let response = claim(id, &author_id).await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+    sqlx::query("UPDATE Author SET email = (SELECT email FROM ApiUser WHERE id = ?) WHERE id = ?")
+        .bind(api_key.split(':').next().unwrap_or_default())
+        .bind(&author_id)
+        .execute(test.db_pool())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    info!("Test Case::resource::/recipe/{{id}}/claim (POST) -> Register a pending claim");
+    let response = claim(id, &author_id).await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::ACCEPTED);
+    let pending: RecipeClaim = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(pending.state, ClaimState::Pending);
+    assert_eq!(
+        pending.source_url.as_deref(),
+        Some("http://localhost/imported-sour")
+    );
+
+    info!("Test Case::resource::/recipe/{{id}}/claim (POST) -> Unverified claims stay pending");
+    let response = claim(id, &author_id).await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::ACCEPTED);
+    let repeated: RecipeClaim = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(repeated.id, pending.id);
+    assert_eq!(repeated.token, pending.token);
+
+    info!("Test Case::resource::/admin/claims/{{id}} (POST) -> Reviews need admin privileges");
+    let response = review(&pending.id, "approve")
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+    test.test_app.grant_admin_access().await;
+
+    info!("Test Case::resource::/admin/claims (GET) -> List the pending claims");
+    let response = test
+        .test_app
+        .api_client
+        .get(format!(
+            "{}/admin/claims?api_key={api_key}",
+            test.test_app.address
+        ))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let claims: Vec<RecipeClaim> = response.json().await.map_err(|e| e.to_string())?;
+    assert!(claims.iter().any(|claim| claim.id == pending.id));
+
+    info!("Test Case::resource::/admin/claims/{{id}} (POST) -> Approve the claim");
+    let response = review(&pending.id, "approve")
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    let response = test.get(&format!("/{id}")).await;
+    let recipe: Recipe = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(
+        recipe.owner().map(|id| id.to_string()),
+        Some(author_id.clone())
+    );
+
+    info!("Test Case::resource::/admin/claims/{{id}} (POST) -> Resolved claims can't be reviewed");
+    let response = review(&pending.id, "reject")
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe/{{id}}/claim (POST) -> Owned recipes can't be claimed");
+    let response = claim(id, &author_id).await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn invalid_quantities_are_rejected() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_ingredients(true)
+        .seed()
+        .await?;
+    let ingredient_id = fixture
+        .ingredient
+        .expect("Failed to extract ingredient fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract ingredient's ID");
+    let recipe = |quantity: f32, unit: &str| {
+        json!({
+            "name": "Simple highball",
+            "ingredients": [
+                {"quantity": 50.0, "unit": "ml", "ingredient_id": ingredient_id},
+                {"quantity": quantity, "unit": unit, "ingredient_id": ingredient_id}
+            ],
+            "steps": ["Pour over ice and top with soda."]
+        })
+    };
+
+    info!("Test Case::resource::/recipe (POST) -> Negative quantities are rejected");
+    let response = test.post(&recipe(-10.0, "ml")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    let errors: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(errors["ingredients"]["1"]["quantity"][0]["code"], "range");
+
+    info!("Test Case::resource::/recipe (POST) -> Dashes are whole numbers");
+    let response = test.post(&recipe(1.5, "dash")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    let errors: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(errors["ingredients"]["1"]["quantity"][0]["code"], "integer");
+
+    info!("Test Case::resource::/recipe (POST) -> Oversized recipes are rejected");
+    let mut oversized = recipe(2.0, "dash");
+    oversized["steps"] = json!(vec!["Stir."; RecipeLimits::default().max_steps + 1]);
+    let response = test.post(&oversized).await;
+    assert_eq!(response.status().as_u16(), StatusCode::UNPROCESSABLE_ENTITY);
+    let errors: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(errors["steps"][0]["code"], "length");
+
+    info!("Test Case::resource::/recipe (POST) -> Recipes at the limits are accepted");
+    let limits = RecipeLimits::default();
+    let step = "é".repeat(limits.max_step_length);
+    let mut largest = recipe(2.0, "dash");
+    largest["steps"] = json!(vec![step.as_str(); limits.max_steps]);
+    let response = test.post(&largest).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let id = response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| e.to_string())?["id"]
+        .as_str()
+        .expect("Failed to extract the ID of the recipe")
+        .to_owned();
+    let response = test.get(&format!("/{id}")).await;
+    let stored: Recipe = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(stored.steps(), vec![step; limits.max_steps].as_slice());
+
+    info!("Test Case::resource::/recipe (POST) -> Valid quantities are accepted");
+    let response = test.post(&recipe(2.0, "dash")).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    #[derive(Deserialize)]
+    struct Id {
+        pub id: Uuid,
+    }
+    let id = response.json::<Id>().await.map_err(|e| e.to_string())?.id;
+
+    info!("Test Case::resource::/recipe/{{id}} (PATCH) -> Absurd quantities are rejected");
+    let response = test
+        .patch(
+            &id.to_string(),
+            &json!({"ingredients": [{"quantity": 9000.0, "unit": "ml", "ingredient_id": ingredient_id}]}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    let errors: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(errors["ingredients"]["0"]["quantity"][0]["code"], "range");
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn step_images() -> Result<(), String> {
+    let media_dir = std::env::temp_dir().join(format!("media-{}", Uuid::now_v7()));
+    let dir = media_dir.to_string_lossy().into_owned();
+    let mut test_app = spawn_app_with(|c| c.application.media_dir = dir).await;
+    test_app.generate_access_token().await;
+    let api_key = test_app.api_token.api_key.expose_secret().to_owned();
+
+    let fixture = fixtures::FixtureSeeder::new(&test_app.db_pool)
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe = &fixture
+        .recipe
+        .expect("Failed to extract the recipe fixture")
+        .valid_fixtures[0];
+    let id = recipe.id().expect("Failed to extract the recipe's ID");
+    let image_url = |step: usize| {
+        format!(
+            "{}/recipe/{id}/steps/{step}/image?api_key={api_key}",
+            test_app.address
+        )
+    };
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+
+    info!("Test Case::resource::/recipe/{{id}}/steps/{{step}}/image (PUT) -> Upload an image");
+    let response = test_app
+        .api_client
+        .put(image_url(0))
+        .header("Content-Type", "image/png")
+        .body(png.clone())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let received: Recipe = response.json().await.map_err(|e| e.to_string())?;
+    let images = received
+        .step_images()
+        .expect("The recipe has no step images");
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].step, 0);
+    let image_id = images[0].image_id.clone();
+
+    let response = test_app
+        .api_client
+        .get(format!("{}/media/{image_id}", test_app.address))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    info!("Test Case::resource::/recipe/{{id}}/steps/{{step}}/image (PUT) -> Unknown step");
+    let response = test_app
+        .api_client
+        .put(image_url(recipe.steps().len()))
+        .body(png.clone())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!(
+        "Test Case::resource::/recipe/{{id}}/steps/{{step}}/image (PUT) -> Upload an image bigger than 256 KiB"
+    );
+    let mut big_png = png.clone();
+    big_png.resize(1024 * 1024, 0);
+    let response = test_app
+        .api_client
+        .put(image_url(0))
+        .header("Content-Type", "image/png")
+        .body(big_png)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let received: Recipe = response.json().await.map_err(|e| e.to_string())?;
+    let image_id = received
+        .step_images()
+        .expect("The recipe has no step images")[0]
+        .image_id
+        .clone();
+
+    info!("Test Case::resource::/recipe/{{id}}/steps/{{step}}/image (DELETE) -> Remove the image");
+    let response = test_app
+        .api_client
+        .delete(image_url(0))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    let response = test_app
+        .api_client
+        .get(format!("{}/media/{image_id}", test_app.address))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    let _ = std::fs::remove_dir_all(media_dir);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn source_and_license() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_ingredients(true)
+        .seed()
+        .await?;
+    let ingredient_id = fixture
+        .ingredient
+        .expect("Failed to extract ingredient fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract ingredient's ID");
+    let recipe = |source: serde_json::Value| {
+        json!({
+            "name": "Savoy Daiquiri",
+            "ingredients": [{"quantity": 60.0, "unit": "ml", "ingredient_id": ingredient_id}],
+            "steps": ["Shake well and strain into a cocktail glass."],
+            "source": source
+        })
+    };
+
+    info!("Test Case::resource::/recipe (POST) -> Empty sources are rejected");
+    let response = test.post(&recipe(json!({"page": 42}))).await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/recipe (POST) -> Recipes credit their source");
+    let source = json!({"book": "The Savoy Cocktail Book", "page": 42, "url": null, "author": "Harry Craddock"});
+    let response = test.post(&recipe(source.clone())).await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    #[derive(Deserialize)]
+    struct Id {
+        pub id: Uuid,
+    }
+    let id = response.json::<Id>().await.map_err(|e| e.to_string())?.id;
+    let recipe: serde_json::Value = test
+        .get(&format!("/{id}"))
+        .await
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(recipe["source"], source);
+    assert_eq!(recipe["license"], "all-rights-reserved");
+
+    info!("Test Case::resource::/recipe/{{id}} (PATCH) -> Recipes are relicensed");
+    let response = test
+        .patch(&id.to_string(), &json!({"license": "cc-by-sa"}))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    let recipe: serde_json::Value = test
+        .get(&format!("/{id}"))
+        .await
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(recipe["license"], "cc-by-sa");
+    assert_eq!(recipe["source"], source);
+
+    info!("Test Case::resource::/recipe/{{id}} (PATCH) -> Invalid sources are rejected");
+    let response = test
+        .patch(&id.to_string(), &json!({"source": {"url": "not a url"}}))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn rating_votes() -> Result<(), String> {
+    let mut test_builder = RecipeApiBuilder::default();
+    TestBuilder::api_with_credentials(&mut test_builder);
+    let test = test_builder.build().await;
+    let api_key = test.test_app.api_token.api_key.expose_secret().to_owned();
+
+    let fixture = fixtures::FixtureSeeder::new(test.db_pool())
+        .with_ingredients(true)
+        .seed()
+        .await?;
+    let ingredient_id = fixture
+        .ingredient
+        .expect("Failed to extract ingredient fixture")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to extract ingredient's ID");
+
+    #[derive(Deserialize)]
+    struct Id {
+        pub id: Uuid,
+    }
+
+    let vote = |id: Uuid, stars: serde_json::Value| {
+        test.test_app
+            .api_client
+            .post(format!(
+                "{}/recipe/{id}/rating?api_key={api_key}",
+                test.test_app.address
+            ))
+            .json(&json!({"stars": stars}))
+            .send()
+    };
+
+    let response = test
+        .post(&json!({
+            "name": "Rated sour",
+            "ingredients": [{"quantity": 50.0, "unit": "ml", "ingredient_id": ingredient_id}],
+            "steps": ["Shake with ice and strain."],
+            "rating": 5
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let id = response.json::<Id>().await.map_err(|e| e.to_string())?.id;
+
+    info!("Test Case::resource::/recipe (POST) -> Ratings of the new recipes are ignored");
+    let recipe: Recipe = test
+        .get(&format!("/{id}"))
+        .await
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(recipe.rating(), StarRate::default());
+
+    // Recipes of first-time clients need a review before they can be voted.
+    test.test_app.grant_admin_access().await;
+    let response = test
+        .test_app
+        .api_client
+        .post(format!(
+            "{}/recipe/{id}/publish?api_key={api_key}",
+            test.test_app.address
+        ))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Recipes without votes have no aggregated rating");
+    let document: serde_json::Value = test
+        .get(&format!("/{id}?format=jsonld"))
+        .await
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert!(document.get("aggregateRating").is_none());
+
+    info!("Test Case::resource::/recipe/{{id}}/rating (POST) -> Invalid votes are rejected");
+    for stars in [json!(0), json!(3.7), json!(6)] {
+        let response = vote(id, stars).await.map_err(|e| e.to_string())?;
+        assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    }
+
+    info!("Test Case::resource::/recipe/{{id}}/rating (POST) -> Unknown recipes can't be voted");
+    let response = vote(Uuid::now_v7(), json!(4))
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/recipe/{{id}}/rating (POST) -> Clients have a single vote");
+    let response = vote(id, json!(2)).await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let response = vote(id, json!(4.5)).await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let rating: RecipeRating = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(rating.votes, 1);
+    assert_eq!(rating.vote.value(), 4.5);
+    assert_eq!(rating.rating.value(), 4.5);
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> Recipes include the rating of the votes");
+    let recipe: Recipe = test
+        .get(&format!("/{id}"))
+        .await
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(recipe.rating(), rating.rating);
+
+    info!("Test Case::resource::/recipe/{{id}} (GET) -> The aggregated rating includes the amount of votes");
+    let document: serde_json::Value = test
+        .get(&format!("/{id}?format=jsonld"))
+        .await
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    assert_eq!(document["aggregateRating"]["ratingValue"], 4.5);
+    assert_eq!(document["aggregateRating"]["ratingCount"], 1);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn search_suggestions() -> Result<(), String> {
+    let test_app = spawn_app().await;
+    let suggestions = |query: &'static str| {
+        test_app
+            .api_client
+            .get(format!(
+                "{}/recipe/search-suggestions?{query}",
+                test_app.address
+            ))
+            .send()
+    };
+
+    info!("Test Case::resource::/recipe/search-suggestions (GET) -> Invalid prefixes are rejected");
+    for query in ["prefix=m", "prefix=%20m%20", "prefix=ma&limit=0", "limit=5"] {
+        let response = suggestions(query).await.map_err(|e| e.to_string())?;
+        assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    }
+
+    // A registry of its own keeps the test apart from the requests of the rest of the tests.
+    let registry = UsageAnalytics::new();
+    registry.configure(true, DEFAULT_K_ANONYMITY);
+    for client in 0..DEFAULT_K_ANONYMITY {
+        let client = format!("10.0.0.{client}");
+        for _ in 0..2 {
+            registry.record_search("recipe", "Margarita", Some(&client));
+        }
+        registry.record_search("recipe", "Mai Tai", Some(&client));
+        registry.record_search("ingredient", "mango", Some(&client));
+    }
+    // A single client repeating a search doesn't make it popular.
+    for _ in 0..DEFAULT_K_ANONYMITY * 2 {
+        registry.record_search("recipe", "martini of my own", Some("10.0.1.1"));
+    }
+    flush_usage_analytics(
+        &test_app.db_pool,
+        &registry,
+        TimeDelta::days(90),
+        Utc::now(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    info!("Test Case::resource::/recipe/search-suggestions (GET) -> Popular terms are suggested");
+    let response = suggestions("prefix=MA").await.map_err(|e| e.to_string())?;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let found: SearchSuggestions = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(found.prefix, "ma");
+    assert_eq!(found.suggestions, ["margarita", "mai tai"]);
+
+    info!("Test Case::resource::/recipe/search-suggestions (GET) -> The amount of suggestions is limited");
+    let response = suggestions("prefix=ma&limit=1")
+        .await
+        .map_err(|e| e.to_string())?;
+    let found: SearchSuggestions = response.json().await.map_err(|e| e.to_string())?;
+    assert_eq!(found.suggestions, ["margarita"]);
+
+    info!("Test Case::resource::/recipe/search-suggestions (GET) -> Wildcards match themselves");
+    let response = suggestions("prefix=m%25")
+        .await
+        .map_err(|e| e.to_string())?;
+    let found: SearchSuggestions = response.json().await.map_err(|e| e.to_string())?;
+    assert!(found.suggestions.is_empty());
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn only_searches_are_throttled() {
+    let test_app = spawn_app_with(|c| c.application.throttling.search_max_requests = 2).await;
+
+    info!("Test Case::resource::/recipe (GET) -> Clients that exceed the limit of searches get banned");
+    let mut statuses = Vec::new();
+    for _ in 0..3 {
+        let response = test_app
+            .search_test(Resource::Recipe, Credentials::NoCredentials, "?name=gin")
+            .await;
+        statuses.push(response.status().as_u16());
+    }
+    // The DB of the test is empty, no recipe matches the search.
+    assert_eq!(statuses, vec![404, 404, 429]);
+
+    info!(
+        "Test Case::resource::/recipe/{{id}} (GET) -> Banned clients still get the recipes by ID"
+    );
+    let response = test_app
+        .get_test(
+            Resource::Recipe,
+            Credentials::NoCredentials,
+            "/0191e13b-5ab7-78f1-bc06-be503a6c111b",
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+}
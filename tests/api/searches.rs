@@ -0,0 +1,191 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use actix_web::{http::StatusCode, web::Data};
+use chrono::{TimeDelta, Utc};
+use lacoctelera::{
+    domain::{IdScheme, Recipe},
+    jobs::send_due_digests,
+    routes::me::{digests::Digest, searches::SavedSearch},
+    seeding::seed_demo_data,
+    testing::helpers::{spawn_app, Credentials, Resource, TestApp},
+    utils::mailing::EmailSender,
+};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+const CLIENT_EMAIL: &str = "jane_doe@mail.com";
+
+async fn save_search(test_app: &TestApp, name: &str, tags: &str) -> SavedSearch {
+    let response = test_app
+        .post_test(
+            Resource::Searches,
+            Credentials::WithCredentials,
+            &json!({"name": name, "query": {"tags": tags}}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CREATED);
+    response
+        .json::<SavedSearch>()
+        .await
+        .expect("Failed to parse the new saved search")
+}
+
+#[actix_web::test]
+async fn searches_management() {
+    let mut test_app = spawn_app().await;
+
+    info!("Test Case::resource::/me/searches (GET) -> Access is restricted");
+    let response = test_app
+        .get_test(Resource::Searches, Credentials::NoCredentials, "")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    test_app.generate_access_token().await;
+
+    info!("Test Case::resource::/me/searches (POST) -> Queries with no criteria are rejected");
+    let response = test_app
+        .post_test(
+            Resource::Searches,
+            Credentials::WithCredentials,
+            &json!({"name": "everything", "query": {}}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/me/searches (POST) -> Save a search");
+    let search = save_search(&test_app, "rum drinks", "rum").await;
+    assert_eq!(search.name, "rum drinks");
+
+    info!("Test Case::resource::/me/searches (POST) -> Names are unique");
+    let response = test_app
+        .post_test(
+            Resource::Searches,
+            Credentials::WithCredentials,
+            &json!({"name": "rum drinks", "query": {"tags": "gin"}}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+
+    let response = test_app
+        .get_test(Resource::Searches, Credentials::WithCredentials, "")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let searches = response
+        .json::<Vec<SavedSearch>>()
+        .await
+        .expect("Failed to parse the list of saved searches");
+    assert_eq!(searches.len(), 1);
+
+    info!("Test Case::resource::/me/searches/{{id}}/results (GET) -> Run a saved search");
+    seed_demo_data(&test_app.db_pool, IdScheme::UuidV7.generator().as_ref())
+        .await
+        .expect("Failed to seed the demo dataset");
+    let response = test_app
+        .get_test(
+            Resource::Searches,
+            Credentials::WithCredentials,
+            &format!("/{}/results", search.id),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    let recipes = response
+        .json::<Vec<Recipe>>()
+        .await
+        .expect("Failed to parse the results of the saved search");
+    assert!(recipes.iter().any(|r| r.name() == "Mojito"));
+
+    info!("Test Case::resource::/me/searches/{{id}}/results (GET) -> Unknown searches");
+    let response = test_app
+        .get_test(
+            Resource::Searches,
+            Credentials::WithCredentials,
+            &format!("/{}/results", Uuid::now_v7()),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/me/searches (DELETE) -> Delete a saved search");
+    let response = test_app
+        .delete_test(Resource::Searches, Credentials::WithCredentials, &search.id)
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    let response = test_app
+        .delete_test(Resource::Searches, Credentials::WithCredentials, &search.id)
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn digests_use_saved_searches() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+    let mail_client: Data<dyn EmailSender> =
+        Data::from(test_app.emails.clone() as Arc<dyn EmailSender>);
+
+    info!("Test Case::resource::/me/digests (POST) -> Unknown saved searches are rejected");
+    let response = test_app
+        .post_test(
+            Resource::Digests,
+            Credentials::WithCredentials,
+            &json!({"frequency": "daily", "search_id": Uuid::now_v7().to_string()}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/me/digests (POST) -> Subscribe to a saved search");
+    let search = save_search(&test_app, "rum drinks", "rum").await;
+    let response = test_app
+        .post_test(
+            Resource::Digests,
+            Credentials::WithCredentials,
+            &json!({"frequency": "daily", "search_id": search.id}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CREATED);
+    let digest = response
+        .json::<Digest>()
+        .await
+        .expect("Failed to parse the new digest");
+    assert_eq!(digest.search_id.as_deref(), Some(search.id.as_str()));
+
+    // Timestamps of the DB have a resolution of one second.
+    actix_web::rt::time::sleep(std::time::Duration::from_millis(1100)).await;
+    seed_demo_data(&test_app.db_pool, IdScheme::UuidV7.generator().as_ref())
+        .await
+        .expect("Failed to seed the demo dataset");
+
+    info!("Test Case::job::digests -> Digests list the matches of the saved search");
+    let tomorrow = Utc::now() + TimeDelta::days(1) + TimeDelta::minutes(1);
+    let sent = send_due_digests(
+        &test_app.db_pool,
+        mail_client,
+        "https://lacoctelera.test",
+        tomorrow,
+    )
+    .await
+    .expect("Failed to run the digests job");
+    assert_eq!(sent, 1);
+    let email = test_app
+        .emails
+        .last_to(CLIENT_EMAIL)
+        .expect("The digest was not sent");
+    assert!(email.text_body.contains("Mojito"));
+    assert!(!email.text_body.contains("Negroni"));
+
+    info!("Test Case::resource::/me/searches (DELETE) -> Digests of a deleted search are deleted");
+    let response = test_app
+        .delete_test(Resource::Searches, Credentials::WithCredentials, &search.id)
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+    let response = test_app
+        .delete_test(Resource::Digests, Credentials::WithCredentials, &digest.id)
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,44 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use actix_web::http::StatusCode;
+use lacoctelera::{
+    domain::IdScheme,
+    seeding::{seed_demo_data, DemoDataset},
+    testing::helpers::{spawn_app, Credentials, Resource},
+};
+use pretty_assertions::assert_eq;
+use tracing::info;
+
+#[actix_web::test]
+async fn seed_the_demo_dataset() {
+    let test_app = spawn_app().await;
+    let ids = IdScheme::UuidV7.generator();
+    let dataset = DemoDataset::load().expect("Failed to load the demo dataset");
+
+    info!("Test Case::seeding -> A fresh DB gets the whole dataset");
+    let summary = seed_demo_data(&test_app.db_pool, ids.as_ref())
+        .await
+        .expect("Failed to seed the demo dataset");
+    assert_eq!(summary.authors, dataset.authors.len());
+    assert_eq!(summary.ingredients, dataset.ingredients.len());
+    assert_eq!(summary.recipes, dataset.recipes.len());
+
+    let response = test_app
+        .search_test(
+            Resource::Recipe,
+            Credentials::NoCredentials,
+            "?name=negroni",
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+
+    info!("Test Case::seeding -> Seeding twice doesn't duplicate the entries");
+    let summary = seed_demo_data(&test_app.db_pool, ids.as_ref())
+        .await
+        .expect("Failed to seed the demo dataset");
+    assert_eq!(summary, Default::default());
+}
@@ -0,0 +1,61 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use actix_web::http::StatusCode;
+use lacoctelera::testing::{
+    fixtures::FixtureSeeder,
+    helpers::{spawn_app, TestApp},
+};
+use pretty_assertions::assert_eq;
+use tracing::info;
+
+async fn get_sitemap(test_app: &TestApp, path: &str) -> (u16, String) {
+    let response = test_app
+        .api_client
+        .get(format!("{}{path}", &test_app.address))
+        .send()
+        .await
+        .expect("Failed to execute GET for the sitemap.");
+    let status = response.status().as_u16();
+
+    (status, response.text().await.unwrap_or_default())
+}
+
+#[actix_web::test]
+async fn sitemap() -> Result<(), String> {
+    let test_app = spawn_app().await;
+
+    info!("Test Case::resource::/sitemap.xml (GET) -> Request the sitemap of an empty DB");
+    let (status, body) = get_sitemap(&test_app, "/sitemap.xml").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("<urlset"));
+    assert!(!body.contains("<url>"));
+
+    let fixtures = FixtureSeeder::new(&test_app.db_pool)
+        .with_recipes(true)
+        .seed()
+        .await?;
+    let recipe_id = fixtures
+        .recipe
+        .expect("Failed to seed the recipes")
+        .valid_fixtures[0]
+        .id()
+        .expect("Failed to unwrap recipe's ID")
+        .to_string();
+
+    info!("Test Case::resource::/sitemap.xml (GET) -> The sitemap is rebuilt when new content is added");
+    let (status, body) = get_sitemap(&test_app, "/sitemap.xml").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains(&format!("/recipe/{recipe_id}</loc>")));
+
+    info!("Test Case::resource::/sitemap/{{page}}.xml (GET) -> Request the pages of the sitemap");
+    let (status, _) = get_sitemap(&test_app, "/sitemap/1.xml").await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = get_sitemap(&test_app, "/sitemap/2.xml").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
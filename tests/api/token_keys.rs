@@ -0,0 +1,220 @@
+// Copyright 2024 Felipe Torres González
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use actix_web::http::StatusCode;
+use lacoctelera::testing::helpers::{spawn_app, Credentials, Resource, TestApp};
+use lacoctelera::{
+    authentication::{AuthData, ClientKey, Scope, MAX_KEYS_PER_CLIENT},
+    routes::token::keys::NewClientKey,
+};
+use pretty_assertions::assert_eq;
+use secrecy::SecretString;
+use serde_json::json;
+use tracing::info;
+
+async fn issue_key(test_app: &TestApp, label: &str, scopes: &[Scope]) -> NewClientKey {
+    let response = test_app
+        .post_test(
+            Resource::TokenKeys,
+            Credentials::WithCredentials,
+            &json!({"label": label, "scopes": scopes}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CREATED);
+    response
+        .json::<NewClientKey>()
+        .await
+        .expect("Failed to parse the new key")
+}
+
+async fn get_keys(test_app: &TestApp) -> Vec<ClientKey> {
+    let response = test_app
+        .get_test(Resource::TokenKeys, Credentials::WithCredentials, "")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+    response
+        .json::<Vec<ClientKey>>()
+        .await
+        .expect("Failed to parse the list of keys")
+}
+
+#[actix_web::test]
+async fn keys_management() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+
+    info!("Test Case::resource::/token/keys (GET) -> The client holds a single key");
+    let keys = get_keys(&test_app).await;
+    assert_eq!(keys.len(), 1);
+    let default_key = keys[0].id;
+
+    info!("Test Case::resource::/token/keys (POST) -> Invalid labels are rejected");
+    for label in ["", "   ", &"a".repeat(41)] {
+        let response = test_app
+            .post_test(
+                Resource::TokenKeys,
+                Credentials::WithCredentials,
+                &json!({"label": label}),
+            )
+            .await;
+        assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+    }
+
+    info!("Test Case::resource::/token/keys (POST) -> Issue a new key");
+    let response = test_app
+        .post_test(
+            Resource::TokenKeys,
+            Credentials::WithCredentials,
+            &json!({"label": "staging"}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CREATED);
+    let new_key = response
+        .json::<NewClientKey>()
+        .await
+        .expect("Failed to parse the new key");
+    assert_eq!(new_key.key.label, "staging");
+    assert_eq!(get_keys(&test_app).await.len(), 2);
+
+    info!("Test Case::resource::/token/keys (GET) -> Tokens only match the key they point to");
+    let default_api_key = test_app.api_token.api_key.clone();
+    let (client_id, token) = new_key.api_key.split_once(':').unwrap();
+    let (_, token) = token.split_once('.').unwrap();
+    test_app.api_token = AuthData {
+        api_key: SecretString::from(format!("{client_id}:{default_key}.{token}")),
+    };
+    let response = test_app
+        .get_test(Resource::TokenKeys, Credentials::WithCredentials, "")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::UNAUTHORIZED);
+
+    info!("Test Case::resource::/token/keys (GET) -> Both keys grant access");
+    test_app.api_token = AuthData {
+        api_key: SecretString::from(new_key.api_key),
+    };
+    assert_eq!(get_keys(&test_app).await.len(), 2);
+
+    info!("Test Case::resource::/token/keys (DELETE) -> Revoke the default key using the new one");
+    let response = test_app
+        .delete_test(
+            Resource::TokenKeys,
+            Credentials::WithCredentials,
+            &default_key.to_string(),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NO_CONTENT);
+
+    let new_api_key = test_app.api_token.api_key.clone();
+    test_app.api_token = AuthData {
+        api_key: default_api_key,
+    };
+    let response = test_app
+        .get_test(Resource::TokenKeys, Credentials::WithCredentials, "")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::UNAUTHORIZED);
+    test_app.api_token = AuthData {
+        api_key: new_api_key,
+    };
+
+    info!("Test Case::resource::/token/keys (DELETE) -> Unknown keys are not found");
+    let response = test_app
+        .delete_test(
+            Resource::TokenKeys,
+            Credentials::WithCredentials,
+            &default_key.to_string(),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/token/keys (DELETE) -> The last key can't be revoked");
+    let response = test_app
+        .delete_test(
+            Resource::TokenKeys,
+            Credentials::WithCredentials,
+            &new_key.key.id.to_string(),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+
+    info!("Test Case::resource::/token/keys (POST) -> The amount of keys is limited");
+    for i in 1..MAX_KEYS_PER_CLIENT {
+        let response = test_app
+            .post_test(
+                Resource::TokenKeys,
+                Credentials::WithCredentials,
+                &json!({"label": format!("key {i}")}),
+            )
+            .await;
+        assert_eq!(response.status().as_u16(), StatusCode::CREATED);
+    }
+    let response = test_app
+        .post_test(
+            Resource::TokenKeys,
+            Credentials::WithCredentials,
+            &json!({"label": "one too many"}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::CONFLICT);
+}
+
+#[actix_web::test]
+async fn scoped_keys() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+    let body = json!({"name": "Jane", "surname": "Doe"});
+
+    info!("Test Case::resource::/token/keys (POST) -> Keys must grant some scope");
+    let response = test_app
+        .post_test(
+            Resource::TokenKeys,
+            Credentials::WithCredentials,
+            &json!({"label": "empty", "scopes": []}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST);
+
+    info!("Test Case::resource::/token/keys (POST) -> Derive a key to manage read-only keys");
+    let manager_key = issue_key(&test_app, "manager", &[Scope::Keys, Scope::Read]).await;
+    assert_eq!(manager_key.key.scopes, vec![Scope::Read, Scope::Keys]);
+    test_app.api_token = AuthData {
+        api_key: SecretString::from(manager_key.api_key),
+    };
+
+    info!("Test Case::resource::/token/keys (POST) -> Scopes can't be escalated");
+    let response = test_app
+        .post_test(
+            Resource::TokenKeys,
+            Credentials::WithCredentials,
+            &json!({"label": "cms", "scopes": ["write"]}),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    info!("Test Case::resource::/token/keys (POST) -> Derive a read-only key");
+    let website_key = issue_key(&test_app, "website", &[Scope::Read]).await;
+    assert_eq!(website_key.key.scopes, vec![Scope::Read]);
+    test_app.api_token = AuthData {
+        api_key: SecretString::from(website_key.api_key),
+    };
+
+    info!("Test Case::resource::/author (POST) -> Read-only keys can't write");
+    let response = test_app
+        .post_test(Resource::Author, Credentials::WithCredentials, &body)
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    info!("Test Case::resource::/token/keys (GET) -> Read-only keys can't manage keys");
+    let response = test_app
+        .get_test(Resource::TokenKeys, Credentials::WithCredentials, "")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::FORBIDDEN);
+
+    info!("Test Case::resource::/author (GET) -> Read-only keys can read the restricted data");
+    let response = test_app
+        .get_test(Resource::Author, Credentials::WithCredentials, "")
+        .await;
+    assert_eq!(response.status().as_u16(), StatusCode::OK);
+}
@@ -4,16 +4,20 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::helpers::{spawn_app, Credentials, Resource};
 use actix_web::http::StatusCode;
-use chrono::{Local, TimeDelta};
+use chrono::TimeDelta;
+use lacoctelera::testing::{
+    email::{email_links, TEST_SENDER_ADDRESS},
+    helpers::{spawn_app, Credentials, Resource},
+};
 use lacoctelera::{
     authentication::*,
     domain::{ClientId, DataDomainError},
 };
 use pretty_assertions::assert_eq;
-use secrecy::SecretString;
+use secrecy::{ExposeSecret, SecretString};
 use sqlx::{Executor, MySqlPool};
+use std::str::FromStr;
 use tracing::{error, info};
 
 async fn seed_api_client(pool: &MySqlPool) -> Result<ClientId, anyhow::Error> {
@@ -34,6 +38,35 @@ async fn seed_api_client(pool: &MySqlPool) -> Result<ClientId, anyhow::Error> {
     Ok(client_id)
 }
 
+/// Issue a new validation token to a client.
+///
+/// # Description
+///
+/// Only the hash of the validation tokens is stored in the DB, and the plain token is sent to the client via email.
+/// Hence tests issue a known token that supersedes the one sent by the API.
+async fn issue_validation_token(pool: &MySqlPool, client_id: &str) -> String {
+    let token = generate_token();
+    let client_id = ClientId::from_str(client_id).expect("Invalid client ID");
+    let mut transaction = pool
+        .begin()
+        .await
+        .expect("Failed to begin a new DB transaction");
+    store_email_validation_token(
+        &mut transaction,
+        &SecretString::from(token.as_str()),
+        TimeDelta::days(1),
+        &client_id,
+    )
+    .await
+    .expect("Failed to store the validation token in the DB");
+    transaction
+        .commit()
+        .await
+        .expect("Failed to commit transaction to the DB");
+
+    token
+}
+
 #[actix_web::test]
 async fn get_request() {
     let test_app = spawn_app().await;
@@ -47,6 +80,25 @@ async fn get_request() {
     assert!(payload.contains("<!DOCTYPE html>"));
 }
 
+#[actix_web::test]
+async fn token_requests_are_throttled() {
+    let test_app = spawn_app().await;
+
+    info!("Test Case::resource::/token/request (GET) -> Clients that exceed the limit get banned");
+    let mut statuses = Vec::new();
+    for _ in 0..6 {
+        let response = test_app
+            .get_test(Resource::TokenRequest, Credentials::NoCredentials, "")
+            .await;
+        statuses.push(response.status().as_u16());
+        if response.status().as_u16() == StatusCode::TOO_MANY_REQUESTS {
+            assert!(response.headers().get("Retry-After").is_some());
+        }
+    }
+
+    assert_eq!(statuses, vec![200, 200, 200, 200, 200, 429]);
+}
+
 #[actix_web::test]
 async fn get_validate() {
     let test_app = spawn_app().await;
@@ -61,31 +113,34 @@ async fn get_validate() {
     let response = test_app.post_token_request(&body).await;
     assert_eq!(response.status().as_u16(), StatusCode::ACCEPTED);
 
-    let query = sqlx::query!(r#"SELECT id FROM ApiUser WHERE email = 'janedoe@mail.com'"#)
+    let client = sqlx::query!(r#"SELECT id FROM ApiUser WHERE email = 'janedoe@mail.com'"#)
         .fetch_one(&test_app.db_pool)
         .await
         .expect("Failed to query ApiUser's ID");
 
-    let query = sqlx::query!(
-        r#"SELECT api_token FROM ApiToken WHERE client_id = ?"#,
-        query.id
-    )
-    .fetch_one(&test_app.db_pool)
-    .await
-    .expect("Failed to query ApiToke from the DB");
+    let validation_token = issue_validation_token(&test_app.db_pool, &client.id).await;
+    let query = format!("?email=janedoe@mail.com&token={validation_token}");
+    let validate =
+        || test_app.get_test(Resource::TokenValidate, Credentials::NoCredentials, &query);
 
-    let response = test_app
-        .get_test(
-            Resource::TokenValidate,
-            Credentials::NoCredentials,
-            &format!("?email=janedoe@mail.com&token={}", query.api_token),
-        )
-        .await;
+    let response = validate().await;
 
     assert_eq!(response.status().as_u16(), StatusCode::ACCEPTED);
     let payload = response.text().await.unwrap();
     println!("{:?}", payload);
     assert!(payload.contains("<!DOCTYPE html>"));
+
+    info!("Test Case::resource::/token/request/validate (GET) -> Links can be used only once");
+    assert_eq!(validate().await.status().as_u16(), StatusCode::GONE);
+
+    info!("Test Case::resource::/token/request (GET) -> The sent emails are correlated with the client");
+    let emails: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM EmailMessage WHERE client_id = ?")
+        .bind(&client.id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to query the sent emails");
+    // The confirmation email and the notification to the admin.
+    assert_eq!(emails, 2);
 }
 
 #[actix_web::test]
@@ -119,10 +174,17 @@ async fn token_request_returns_200_for_existing_email() {
     // The first time, it shall return Ok (202).
     assert_eq!(202, response.status().as_u16());
 
-    // Attempt to register twice the same email.
+    // Attempt to register twice the same email. The email is not validated yet, so a new link is issued.
     let response = test_app.post_token_request(&body).await;
+    assert_eq!(202, response.status().as_u16());
+
+    sqlx::query("UPDATE ApiUser SET validated = TRUE WHERE email = 'janedoe@mail.com'")
+        .execute(&test_app.db_pool)
+        .await
+        .expect("Failed to validate the test client");
 
     // This time, the response shall be 406, as the email is already used.
+    let response = test_app.post_token_request(&body).await;
     assert_eq!(406, response.status().as_u16());
 
     // This avoids a dummy warning message in the tracer.
@@ -156,25 +218,131 @@ async fn register_new_token_request() {
     assert_eq!(record.validated, Some(0));
     let client_id = record.id;
 
-    let record = sqlx::query!(
-        "SELECT api_token, valid_until FROM ApiToken WHERE client_id = ?",
-        client_id
+    let (token_hash, expiry_hours): (String, i64) = sqlx::query_as(
+        r#"
+        SELECT token_hash, TIMESTAMPDIFF(HOUR, created_at, expires_at)
+        FROM ValidationToken WHERE client_id = ?
+        "#,
     )
-    .fetch_optional(&test_app.db_pool)
+    .bind(&client_id)
+    .fetch_one(&test_app.db_pool)
     .await
-    .expect("Failed to search test user data in the DB")
-    .unwrap();
+    .expect("Failed to search test user data in the DB");
 
-    assert!(record.api_token.len() == 25);
-    assert_eq!(
-        record.valid_until.date_naive(),
-        Local::now().date_naive() + TimeDelta::days(1)
-    );
+    // Only the hash of the token is stored.
+    assert!(token_hash.starts_with("$argon2id$"));
+    assert_eq!(expiry_hours, 24);
 
     // This avoids a dummy warning message in the tracer.
     test_app.db_pool.close().await;
 }
 
+#[actix_web::test]
+async fn confirmation_emails_complete_the_request() {
+    let test_app = spawn_app().await;
+    let email = "janedoe@mail.com";
+    let body = serde_json::json!({
+        "email": email,
+        "explanation": "A_very_long_sentence_for_testing",
+    });
+
+    info!("Test Case::resource::/token/request (POST) -> A confirmation email is sent");
+    let response = test_app.post_token_request(&body).await;
+    assert_eq!(response.status().as_u16(), StatusCode::ACCEPTED);
+    let confirmation = test_app
+        .emails
+        .last_to(email)
+        .expect("No confirmation email was sent");
+    assert_eq!(confirmation.subject, "Verify your email");
+    let links = email_links(&confirmation.text_body);
+    assert_eq!(links.len(), 1);
+
+    info!("Test Case::resource::/token/request/validate (GET) -> The link of the email validates the request");
+    let response = test_app
+        .api_client
+        .get(&links[0])
+        .send()
+        .await
+        .expect("Failed to follow the confirmation link");
+    assert_eq!(response.status().as_u16(), StatusCode::ACCEPTED);
+
+    info!("Test Case::resource::/token/request/validate (GET) -> The admin is notified");
+    let notification = test_app
+        .emails
+        .last_to(TEST_SENDER_ADDRESS)
+        .expect("The admin was not notified");
+    assert!(notification
+        .text_body
+        .contains(&confirmation.correlation.client_id.to_string()));
+    assert_eq!(test_app.emails.sent().len(), 2);
+}
+
+#[actix_web::test]
+async fn expired_validation_links_are_reissued() {
+    let test_app = spawn_app().await;
+
+    let body = serde_json::json!({
+        "email": "janedoe@mail.com",
+        "explanation": "A_very_long_sentence_for_testing",
+    });
+    let response = test_app.post_token_request(&body).await;
+    assert_eq!(response.status().as_u16(), StatusCode::ACCEPTED);
+
+    let client_id: String = sqlx::query_scalar("SELECT id FROM ApiUser WHERE email = ?")
+        .bind("janedoe@mail.com")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to query ApiUser's ID");
+    let expired_token = issue_validation_token(&test_app.db_pool, &client_id).await;
+    sqlx::query(
+        "UPDATE ValidationToken SET expires_at = CURRENT_TIMESTAMP - INTERVAL 1 HOUR WHERE client_id = ?",
+    )
+    .bind(&client_id)
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to expire the validation token");
+
+    let validate = |token: String| {
+        let test_app = &test_app;
+        async move {
+            test_app
+                .get_test(
+                    Resource::TokenValidate,
+                    Credentials::NoCredentials,
+                    &format!("?email=janedoe@mail.com&token={token}"),
+                )
+                .await
+                .status()
+                .as_u16()
+        }
+    };
+
+    info!("Test Case::resource::/token/request/validate (GET) -> Expired links are gone");
+    assert_eq!(validate(expired_token.clone()).await, StatusCode::GONE);
+
+    info!("Test Case::resource::/token/request/validate (GET) -> Unknown links are not found");
+    assert_eq!(validate(generate_token()).await, StatusCode::NOT_FOUND);
+
+    info!("Test Case::resource::/token/request (POST) -> Request a new link");
+    let response = test_app.post_token_request(&body).await;
+    assert_eq!(response.status().as_u16(), StatusCode::ACCEPTED);
+    let active_tokens: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM ValidationToken
+        WHERE client_id = ? AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(&client_id)
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to query the validation tokens");
+    assert_eq!(active_tokens, 1);
+
+    let new_token = issue_validation_token(&test_app.db_pool, &client_id).await;
+    assert_eq!(validate(expired_token).await, StatusCode::GONE);
+    assert_eq!(validate(new_token).await, StatusCode::ACCEPTED);
+}
+
 /// Test to check all the utility functions that handle tokens and the DB.
 #[actix_web::test]
 async fn token_management() {
@@ -209,9 +377,13 @@ async fn token_management() {
 
     // At this point, the API client should have access to the API. However, that is checked in another test case,
     // let's simply wipe that token and call it a day.
-    delete_token(&test_app.db_pool, token_hashed)
-        .await
-        .expect("Failed to delete the token from the DB");
+    delete_token(
+        &test_app.db_pool,
+        test_app.access_control.cache(),
+        token_hashed,
+    )
+    .await
+    .expect("Failed to delete the token from the DB");
 
     // This avoids a dummy warning message in the tracer.
     test_app.db_pool.close().await;
@@ -225,12 +397,20 @@ async fn non_existing_client_has_no_access_to_the_api() {
     let token = generate_token();
     info!("Token for the client: {token}");
     let token_string = SecretString::from(format!("{non_existing_client}:{token}"));
-    let expected_error = check_access(&test_app.db_pool, &token_string).await;
+    let expected_error = check_access(
+        &test_app.db_pool,
+        &test_app.access_control,
+        &token_string,
+        Scope::Read,
+    )
+    .await;
     assert!(expected_error.is_err());
     match expected_error {
         Ok(_) => info!("Cant' really be here..."),
         Err(e) => match e.downcast_ref() {
-            Some(DataDomainError::InvalidId) => info!("DataDomainError::InvalidId received"),
+            Some(DataDomainError::InvalidAccessCredentials) => {
+                info!("DataDomainError::InvalidAccessCredentials received")
+            }
             _ => panic!("Unexpected error type received"),
         },
     }
@@ -274,10 +454,18 @@ async fn disabled_account_fails_to_grant_access() {
         .await
         .expect("Failed to commit transaction to the DB");
 
-    // Yet, the client's account is disabled.
-    assert!(check_access(&test_app.db_pool, &token_string)
-        .await
-        .is_err());
+    // Yet, the client's account is disabled. The reason is not disclosed.
+    let expected_error = check_access(
+        &test_app.db_pool,
+        &test_app.access_control,
+        &token_string,
+        Scope::Read,
+    )
+    .await;
+    assert!(matches!(
+        expected_error.unwrap_err().downcast_ref(),
+        Some(DataDomainError::InvalidAccessCredentials)
+    ));
     info!("Disabled account check passed");
 
     // This avoids a dummy warning message in the tracer.
@@ -325,7 +513,14 @@ async fn enabled_and_validated_token_grants_access() {
         .await
         .expect("Failed to enable the test client in the DB");
     // Time to have access.
-    match check_access(&test_app.db_pool, &token_string).await {
+    match check_access(
+        &test_app.db_pool,
+        &test_app.access_control,
+        &token_string,
+        Scope::Read,
+    )
+    .await
+    {
         Ok(_) => info!("Enabled account check passed"),
         Err(e) => {
             error!("{e}");
@@ -377,10 +572,20 @@ async fn expired_token_fails_to_grant_access() {
         .await
         .expect("Failed to enable the test client in the DB");
 
-    match check_access(&test_app.db_pool, &token_string).await {
+    match check_access(
+        &test_app.db_pool,
+        &test_app.access_control,
+        &token_string,
+        Scope::Read,
+    )
+    .await
+    {
         Ok(_) => panic!("The access is granted to the client and it should be denied"),
         Err(e) => {
-            error!("{e}");
+            assert!(matches!(
+                e.downcast_ref(),
+                Some(DataDomainError::InvalidAccessCredentials)
+            ));
             info!("Expiry date check passed");
         }
     }
@@ -388,3 +593,105 @@ async fn expired_token_fails_to_grant_access() {
     // This avoids a dummy warning message in the tracer.
     test_app.db_pool.close().await;
 }
+
+#[actix_web::test]
+async fn access_denials_are_not_disclosed() {
+    let mut test_app = spawn_app().await;
+    test_app.generate_access_token().await;
+    let client_id = test_app
+        .api_token
+        .api_key
+        .expose_secret()
+        .split(':')
+        .next()
+        .unwrap()
+        .to_owned();
+    let body = serde_json::json!({"name": "Jane"});
+
+    for (case, api_key) in [
+        (
+            "Unknown client",
+            format!("{}:{}", ClientId::new(), generate_token()),
+        ),
+        ("Wrong token", format!("{client_id}:{}", generate_token())),
+        ("Malformed API key", generate_token()),
+    ] {
+        info!("Test Case::resource::/author (POST) -> {case}");
+        test_app.api_token = AuthData {
+            api_key: SecretString::from(api_key),
+        };
+        let response = test_app
+            .post_test(Resource::Author, Credentials::WithCredentials, &body)
+            .await;
+        assert_eq!(response.status().as_u16(), StatusCode::UNAUTHORIZED);
+        assert!(response.text().await.unwrap().is_empty());
+    }
+}
+
+#[actix_web::test]
+async fn revoked_tokens_are_not_cached() {
+    let test_app = spawn_app().await;
+
+    let client_id = seed_api_client(&test_app.db_pool)
+        .await
+        .expect("Failed to seed an ApiClient into the DB");
+
+    let plain_token = generate_token();
+    let token_string = SecretString::from(format!("{client_id}:{plain_token}"));
+    let token_hashed = generate_new_token_hash(SecretString::from(plain_token))
+        .expect("Failed to generate the token hash");
+    let mut transaction = test_app
+        .db_pool
+        .begin()
+        .await
+        .expect("Failed to begin a new DB transaction");
+    store_validation_token(
+        &mut transaction,
+        &token_hashed,
+        TimeDelta::days(1),
+        &client_id,
+    )
+    .await
+    .expect("Failed to store the token in the DB");
+    validate_client_account(&mut transaction, &client_id)
+        .await
+        .expect("Failed to validate the test client in the DB");
+    transaction
+        .commit()
+        .await
+        .expect("Failed to commit transaction to the DB");
+    enable_client(&test_app.db_pool, &client_id)
+        .await
+        .expect("Failed to enable the test client in the DB");
+
+    // The first check grants the access, and it gets cached.
+    assert!(check_access(
+        &test_app.db_pool,
+        &test_app.access_control,
+        &token_string,
+        Scope::Read
+    )
+    .await
+    .is_ok());
+    assert!(test_app.access_control.cache().is_granted(&token_string));
+
+    delete_token(
+        &test_app.db_pool,
+        test_app.access_control.cache(),
+        token_hashed,
+    )
+    .await
+    .expect("Failed to delete the token from the DB");
+    assert!(!test_app.access_control.cache().is_granted(&token_string));
+    assert!(check_access(
+        &test_app.db_pool,
+        &test_app.access_control,
+        &token_string,
+        Scope::Read
+    )
+    .await
+    .is_err());
+
+    // This avoids a dummy warning message in the tracer.
+    test_app.db_pool.close().await;
+}